@@ -0,0 +1,530 @@
+use std::collections::BTreeMap;
+use std::fmt::{Display, Formatter};
+use std::fs::File;
+use std::io::{BufReader, BufWriter};
+use std::path::{Path, PathBuf};
+
+use brushes::{BrushConfigItem, BrushConfigKind, BrushConfigValue, UnitIntervalPoint};
+use serde::{Deserialize, Serialize};
+
+use crate::config;
+use crate::preferences::user_config_home;
+
+const PRESET_VERSION: u32 = 1;
+const PRESET_FILE_EXTENSION: &str = "json";
+
+#[derive(Debug)]
+pub enum BrushPresetError {
+    Io(std::io::Error),
+    Json(serde_json::Error),
+    UnsupportedVersion(u32),
+    EmptyName,
+    NotFound(String),
+}
+
+impl Display for BrushPresetError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Io(error) => write!(f, "brush preset io error: {error}"),
+            Self::Json(error) => write!(f, "brush preset json error: {error}"),
+            Self::UnsupportedVersion(version) => {
+                write!(f, "unsupported brush preset version: {version}")
+            }
+            Self::EmptyName => write!(f, "brush preset name is empty"),
+            Self::NotFound(name) => write!(f, "brush preset not found: {name}"),
+        }
+    }
+}
+
+impl std::error::Error for BrushPresetError {}
+
+impl From<std::io::Error> for BrushPresetError {
+    fn from(value: std::io::Error) -> Self {
+        Self::Io(value)
+    }
+}
+
+impl From<serde_json::Error> for BrushPresetError {
+    fn from(value: serde_json::Error) -> Self {
+        Self::Json(value)
+    }
+}
+
+/// Serialized form of [`BrushConfigValue`].
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", content = "value", rename_all = "snake_case")]
+pub enum StoredBrushConfigValue {
+    ScalarF32(f32),
+    UnitIntervalCurve(Vec<[f32; 2]>),
+    Bool(bool),
+    Integer(i32),
+    /// Option label rather than index, so presets survive option reordering.
+    Enum(String),
+    AngleDeg(f32),
+    ColorRgb([f32; 3]),
+    TextureRef(String),
+}
+
+impl StoredBrushConfigValue {
+    fn capture(value: &BrushConfigValue, item: &BrushConfigItem) -> Self {
+        match value {
+            BrushConfigValue::ScalarF32(value) => Self::ScalarF32(*value),
+            BrushConfigValue::UnitIntervalCurve(points) => {
+                Self::UnitIntervalCurve(points.iter().map(|point| [point.x, point.y]).collect())
+            }
+            BrushConfigValue::Bool(value) => Self::Bool(*value),
+            BrushConfigValue::Integer(value) => Self::Integer(*value),
+            BrushConfigValue::Enum(index) => match &item.kind {
+                BrushConfigKind::Enum { options } => match options.get(*index) {
+                    Some(option) => Self::Enum((*option).to_string()),
+                    None => Self::Integer(*index as i32),
+                },
+                _ => Self::Integer(*index as i32),
+            },
+            BrushConfigValue::AngleDeg(value) => Self::AngleDeg(*value),
+            BrushConfigValue::ColorRgb(rgb) => Self::ColorRgb(*rgb),
+            BrushConfigValue::TextureRef(name) => Self::TextureRef(name.clone()),
+        }
+    }
+
+    /// Converts back for `item`, or `None` when the stored kind no longer
+    /// matches the item. Numbers are clamped to the item's current range.
+    ///
+    /// Older presets stored toggles, counts, choices and angles as scalars;
+    /// those scalars still load into the newer kinds.
+    fn to_config_value(&self, item: &BrushConfigItem) -> Option<BrushConfigValue> {
+        let number = match self {
+            Self::ScalarF32(value) | Self::AngleDeg(value) if value.is_finite() => Some(*value),
+            Self::Integer(value) => Some(*value as f32),
+            _ => None,
+        };
+        match (self, &item.kind) {
+            (Self::UnitIntervalCurve(points), BrushConfigKind::UnitIntervalCurve)
+                if points.len() >= 2 =>
+            {
+                Some(BrushConfigValue::UnitIntervalCurve(
+                    points
+                        .iter()
+                        .map(|[x, y]| UnitIntervalPoint::new(x.clamp(0.0, 1.0), y.clamp(0.0, 1.0)))
+                        .collect(),
+                ))
+            }
+            (Self::Bool(value), BrushConfigKind::Bool) => Some(BrushConfigValue::Bool(*value)),
+            (Self::Enum(label), BrushConfigKind::Enum { options }) => options
+                .iter()
+                .position(|option| option == label)
+                .map(BrushConfigValue::Enum),
+            (Self::ColorRgb(rgb), BrushConfigKind::ColorRgb) => Some(BrushConfigValue::ColorRgb(
+                rgb.map(|channel| channel.clamp(0.0, 1.0)),
+            )),
+            (Self::TextureRef(name), BrushConfigKind::TextureRef) => {
+                Some(BrushConfigValue::TextureRef(name.clone()))
+            }
+            (Self::ScalarF32(_), BrushConfigKind::ScalarF32 { min, max }) => {
+                number.map(|value| BrushConfigValue::ScalarF32(value.clamp(*min, *max)))
+            }
+            (Self::ScalarF32(_) | Self::AngleDeg(_), BrushConfigKind::AngleDeg { min, max }) => {
+                number.map(|value| BrushConfigValue::AngleDeg(value.clamp(*min, *max)))
+            }
+            (Self::ScalarF32(_) | Self::Integer(_), BrushConfigKind::Integer { min, max }) => {
+                number.map(|value| {
+                    BrushConfigValue::Integer((value.round() as i32).clamp(*min, *max))
+                })
+            }
+            (Self::ScalarF32(_) | Self::Integer(_), BrushConfigKind::Enum { options }) => {
+                let last = options.len().checked_sub(1)?;
+                number.map(|value| {
+                    BrushConfigValue::Enum((value.round().max(0.0) as usize).min(last))
+                })
+            }
+            (Self::ScalarF32(_), BrushConfigKind::Bool) => {
+                number.map(|value| BrushConfigValue::Bool(value >= 0.5))
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Named brush setup stored as one file in a preset directory.
+///
+/// Config values are keyed by [`BrushConfigItem::key`] so presets survive
+/// brushes gaining, losing or reordering config items.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BrushPreset {
+    pub version: u32,
+    pub name: String,
+    /// Brush engine label, e.g. `Round`.
+    pub brush: String,
+    pub color_rgb: [f32; 3],
+    #[serde(default)]
+    pub eraser: bool,
+    #[serde(default)]
+    pub values: BTreeMap<String, StoredBrushConfigValue>,
+}
+
+/// Config values resolved from a preset against a brush's current items.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ResolvedPresetValues {
+    /// One value per item, in item order.
+    pub values: Vec<BrushConfigValue>,
+    /// Items the preset had no usable value for; these use the default.
+    pub defaulted_keys: Vec<&'static str>,
+    /// Preset keys the brush no longer has.
+    pub ignored_keys: Vec<String>,
+}
+
+impl BrushPreset {
+    pub fn capture(
+        name: String,
+        brush: &str,
+        color_rgb: [f32; 3],
+        eraser: bool,
+        items: &[BrushConfigItem],
+        values: &[BrushConfigValue],
+    ) -> Self {
+        Self {
+            version: PRESET_VERSION,
+            name,
+            brush: brush.to_string(),
+            color_rgb,
+            eraser,
+            values: items
+                .iter()
+                .zip(values)
+                .map(|(item, value)| {
+                    (
+                        item.key.to_string(),
+                        StoredBrushConfigValue::capture(value, item),
+                    )
+                })
+                .collect(),
+        }
+    }
+
+    pub fn resolve_values(&self, items: &[BrushConfigItem]) -> ResolvedPresetValues {
+        let mut defaulted_keys = Vec::new();
+        let values = items
+            .iter()
+            .map(|item| {
+                self.values
+                    .get(item.key)
+                    .and_then(|stored| stored.to_config_value(item))
+                    .unwrap_or_else(|| {
+                        defaulted_keys.push(item.key);
+                        item.default_value.clone()
+                    })
+            })
+            .collect();
+        let ignored_keys = self
+            .values
+            .keys()
+            .filter(|key| !items.iter().any(|item| item.key == key.as_str()))
+            .cloned()
+            .collect();
+        ResolvedPresetValues {
+            values,
+            defaulted_keys,
+            ignored_keys,
+        }
+    }
+
+    pub fn read(path: &Path) -> Result<Self, BrushPresetError> {
+        let preset: Self = serde_json::from_reader(BufReader::new(File::open(path)?))?;
+        if preset.version > PRESET_VERSION {
+            return Err(BrushPresetError::UnsupportedVersion(preset.version));
+        }
+        if preset.name.trim().is_empty() {
+            return Err(BrushPresetError::EmptyName);
+        }
+        Ok(preset)
+    }
+
+    pub fn write(&self, path: &Path) -> Result<(), BrushPresetError> {
+        if let Some(parent) = path.parent()
+            && !parent.as_os_str().is_empty()
+        {
+            std::fs::create_dir_all(parent)?;
+        }
+        serde_json::to_writer_pretty(BufWriter::new(File::create(path)?), self)?;
+        Ok(())
+    }
+}
+
+/// Presets loaded from a directory with one `<name>.json` file per preset.
+#[derive(Debug)]
+pub struct BrushPresetLibrary {
+    dir: PathBuf,
+    presets: Vec<BrushPreset>,
+}
+
+impl BrushPresetLibrary {
+    pub fn default_dir() -> PathBuf {
+        user_config_home().join(config::brush_presets::DEFAULT_DIR_NAME)
+    }
+
+    /// Loads every preset in `dir`, creating it if needed. Unreadable files
+    /// are logged and skipped so one bad file does not hide the rest.
+    pub fn open(dir: &Path) -> Result<Self, BrushPresetError> {
+        std::fs::create_dir_all(dir)?;
+        let mut presets = Vec::new();
+        for entry in std::fs::read_dir(dir)? {
+            let path = entry?.path();
+            if path.extension().and_then(|ext| ext.to_str()) != Some(PRESET_FILE_EXTENSION) {
+                continue;
+            }
+            match BrushPreset::read(&path) {
+                Ok(preset) => presets.push(preset),
+                Err(error) => eprintln!("brush preset skipped ({}): {error}", path.display()),
+            }
+        }
+        let mut library = Self {
+            dir: dir.to_path_buf(),
+            presets,
+        };
+        library.sort();
+        Ok(library)
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    pub fn presets(&self) -> &[BrushPreset] {
+        &self.presets
+    }
+
+    pub fn presets_for<'a>(&'a self, brush: &'a str) -> impl Iterator<Item = &'a BrushPreset> {
+        self.presets
+            .iter()
+            .filter(move |preset| preset.brush == brush)
+    }
+
+    pub fn get(&self, name: &str) -> Option<&BrushPreset> {
+        self.presets.iter().find(|preset| preset.name == name)
+    }
+
+    /// Writes `preset` to the directory, replacing any preset of the same name.
+    pub fn save(&mut self, preset: BrushPreset) -> Result<(), BrushPresetError> {
+        if preset.name.trim().is_empty() {
+            return Err(BrushPresetError::EmptyName);
+        }
+        preset.write(&self.preset_path(&preset.name))?;
+        self.presets.retain(|existing| existing.name != preset.name);
+        self.presets.push(preset);
+        self.sort();
+        Ok(())
+    }
+
+    pub fn remove(&mut self, name: &str) -> Result<(), BrushPresetError> {
+        let Some(index) = self.presets.iter().position(|preset| preset.name == name) else {
+            return Err(BrushPresetError::NotFound(name.to_string()));
+        };
+        let path = self.preset_path(name);
+        if path.exists() {
+            std::fs::remove_file(path)?;
+        }
+        self.presets.remove(index);
+        Ok(())
+    }
+
+    /// Copies a preset file from anywhere into the library.
+    pub fn import(&mut self, path: &Path) -> Result<&BrushPreset, BrushPresetError> {
+        let preset = BrushPreset::read(path)?;
+        let name = preset.name.clone();
+        self.save(preset)?;
+        self.get(&name).ok_or(BrushPresetError::NotFound(name))
+    }
+
+    pub fn export(&self, name: &str, path: &Path) -> Result<(), BrushPresetError> {
+        self.get(name)
+            .ok_or_else(|| BrushPresetError::NotFound(name.to_string()))?
+            .write(path)
+    }
+
+    fn preset_path(&self, name: &str) -> PathBuf {
+        let file_stem: String = name
+            .trim()
+            .chars()
+            .map(|ch| match ch {
+                '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|' => '_',
+                ch if ch.is_control() => '_',
+                ch => ch,
+            })
+            .collect();
+        self.dir
+            .join(format!("{file_stem}.{PRESET_FILE_EXTENSION}"))
+    }
+
+    fn sort(&mut self) {
+        self.presets
+            .sort_by(|lhs, rhs| (&lhs.brush, &lhs.name).cmp(&(&rhs.brush, &rhs.name)));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{SystemTime, UNIX_EPOCH};
+
+    use brushes::{BrushConfigItem, BrushConfigKind, BrushConfigValue, UnitIntervalPoint};
+
+    use super::{BrushPreset, BrushPresetLibrary, StoredBrushConfigValue};
+
+    fn items() -> Vec<BrushConfigItem> {
+        vec![
+            BrushConfigItem {
+                key: "radius",
+                label: "Radius",
+                default_hidden: false,
+                kind: BrushConfigKind::ScalarF32 {
+                    min: 1.0,
+                    max: 64.0,
+                },
+                default_value: BrushConfigValue::ScalarF32(8.0),
+            },
+            BrushConfigItem {
+                key: "pressure_curve",
+                label: "Pressure Curve",
+                default_hidden: true,
+                kind: BrushConfigKind::UnitIntervalCurve,
+                default_value: BrushConfigValue::UnitIntervalCurve(vec![
+                    UnitIntervalPoint::new(0.0, 0.0),
+                    UnitIntervalPoint::new(1.0, 1.0),
+                ]),
+            },
+        ]
+    }
+
+    #[test]
+    fn resolve_matches_by_key_and_tolerates_schema_changes() {
+        let mut preset = BrushPreset::capture(
+            "ink".to_string(),
+            "Round",
+            [0.0, 0.0, 0.0],
+            false,
+            &items(),
+            &[
+                BrushConfigValue::ScalarF32(20.0),
+                BrushConfigValue::UnitIntervalCurve(vec![
+                    UnitIntervalPoint::new(0.0, 0.2),
+                    UnitIntervalPoint::new(1.0, 0.9),
+                ]),
+            ],
+        );
+        preset
+            .values
+            .insert("jitter".to_string(), StoredBrushConfigValue::ScalarF32(0.5));
+        preset.values.insert(
+            "pressure_curve".to_string(),
+            StoredBrushConfigValue::ScalarF32(1.0),
+        );
+
+        // Items reordered: lookup must go by key, not position.
+        let mut reordered = items();
+        reordered.reverse();
+        let resolved = preset.resolve_values(&reordered);
+
+        assert_eq!(resolved.values[0], reordered[0].default_value);
+        assert_eq!(resolved.values[1], BrushConfigValue::ScalarF32(20.0));
+        assert_eq!(resolved.defaulted_keys, vec!["pressure_curve"]);
+        assert_eq!(resolved.ignored_keys, vec!["jitter".to_string()]);
+    }
+
+    #[test]
+    fn resolve_loads_old_scalars_into_newer_kinds() {
+        let items = vec![
+            BrushConfigItem {
+                key: "rotation_source",
+                label: "Rotation",
+                default_hidden: false,
+                kind: BrushConfigKind::Enum {
+                    options: &["Fixed", "Stroke Direction", "Pen Twist"],
+                },
+                default_value: BrushConfigValue::Enum(0),
+            },
+            BrushConfigItem {
+                key: "dab_count",
+                label: "Dabs",
+                default_hidden: true,
+                kind: BrushConfigKind::Integer { min: 1, max: 16 },
+                default_value: BrushConfigValue::Integer(1),
+            },
+        ];
+        let mut preset = BrushPreset::capture(
+            "stamp".to_string(),
+            "Stamp",
+            [0.0, 0.0, 0.0],
+            false,
+            &items,
+            &[BrushConfigValue::Enum(2), BrushConfigValue::Integer(3)],
+        );
+        assert_eq!(
+            preset.values.get("rotation_source"),
+            Some(&StoredBrushConfigValue::Enum("Pen Twist".to_string()))
+        );
+        assert_eq!(
+            preset.resolve_values(&items).values,
+            vec![BrushConfigValue::Enum(2), BrushConfigValue::Integer(3)]
+        );
+
+        preset.values.insert(
+            "rotation_source".to_string(),
+            StoredBrushConfigValue::ScalarF32(1.0),
+        );
+        preset.values.insert(
+            "dab_count".to_string(),
+            StoredBrushConfigValue::ScalarF32(40.0),
+        );
+        let resolved = preset.resolve_values(&items);
+        assert_eq!(
+            resolved.values,
+            vec![BrushConfigValue::Enum(1), BrushConfigValue::Integer(16)]
+        );
+        assert!(resolved.defaulted_keys.is_empty());
+
+        // A choice with no options left cannot hold an old scalar.
+        let no_options = vec![BrushConfigItem {
+            kind: BrushConfigKind::Enum { options: &[] },
+            ..items[0].clone()
+        }];
+        let resolved = preset.resolve_values(&no_options);
+        assert_eq!(resolved.values, vec![BrushConfigValue::Enum(0)]);
+        assert_eq!(resolved.defaulted_keys, vec!["rotation_source"]);
+    }
+
+    #[test]
+    fn library_saves_reopens_imports_and_exports() {
+        let unique = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_nanos();
+        let root = std::env::temp_dir().join(format!("glaphica-presets-{unique}"));
+        let preset = BrushPreset::capture(
+            "soft/round".to_string(),
+            "Round",
+            [0.2, 0.4, 0.6],
+            true,
+            &items(),
+            &[
+                BrushConfigValue::ScalarF32(200.0),
+                items()[1].default_value.clone(),
+            ],
+        );
+
+        let mut library = BrushPresetLibrary::open(&root.join("library")).unwrap();
+        library.save(preset.clone()).unwrap();
+        let exported = root.join("shared/soft.json");
+        library.export("soft/round", &exported).unwrap();
+        library.remove("soft/round").unwrap();
+        assert!(library.presets().is_empty());
+
+        library.import(&exported).unwrap();
+        let reopened = BrushPresetLibrary::open(&root.join("library")).unwrap();
+
+        assert_eq!(reopened.presets(), std::slice::from_ref(&preset));
+        assert_eq!(
+            reopened.presets()[0].resolve_values(&items()).values[0],
+            BrushConfigValue::ScalarF32(64.0)
+        );
+        let _ = std::fs::remove_dir_all(root);
+    }
+}
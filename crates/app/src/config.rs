@@ -1,7 +1,8 @@
 /// Thread channel capacities for main thread and engine thread communication
 pub mod thread_channels {
-    /// Capacity of the main-to-engine input ring buffer
-    pub const MAIN_TO_ENGINE_INPUT_RING: usize = 256;
+    /// Capacity of the main-to-engine input ring buffer, sized to hold a
+    /// backlog of several batches before samples are overwritten
+    pub const MAIN_TO_ENGINE_INPUT_RING: usize = 1024;
 
     /// Capacity of the engine-to-main input control queue
     pub const ENGINE_TO_MAIN_INPUT_CONTROL: usize = 64;
@@ -13,6 +14,19 @@ pub mod thread_channels {
     pub const MAIN_TO_ENGINE_FEEDBACK: usize = 256;
 }
 
+/// Dedicated engine thread configuration
+pub mod engine_thread {
+    /// Thread name shown in debuggers and profilers
+    pub const THREAD_NAME: &str = "glaphica-engine";
+
+    /// Longest the engine thread waits for input before checking controls and
+    /// requests again, in milliseconds
+    pub const IDLE_WAIT_MS: u64 = 4;
+
+    /// Shortest interval between snapshots published mid-stroke, in milliseconds
+    pub const SNAPSHOT_INTERVAL_MS: u64 = 100;
+}
+
 /// Vector pre-allocation capacities for batch processing
 pub mod batch_capacities {
     /// Pre-allocated capacity for input samples vector
@@ -29,15 +43,28 @@ pub mod batch_capacities {
 pub mod brush_processing {
     /// Maximum number of brushes that can be registered
     pub const MAX_BRUSHES: usize = 16;
+}
 
-    /// Maximum batch size for draining input samples with wait
-    pub const MAX_INPUT_BATCH_SIZE: usize = 256;
+/// Adaptive input batching configuration
+pub mod input_batching {
+    /// Samples drained per engine frame while the engine keeps up
+    pub const BASE_BATCH_SIZE: usize = 256;
+
+    /// Largest batch the engine grows to while catching up with a backlog
+    pub const MAX_BATCH_SIZE: usize = 1024;
 }
 
 /// Atlas storage configuration
 pub mod atlas_storage {
+    use glaphica_core::TextureFormat;
+
     /// Initial capacity for atlas backend storage
     pub const INITIAL_BACKEND_CAPACITY: usize = 2;
+
+    /// Texture format of the document leaf and branch-cache atlases; half
+    /// floats keep gradients finer than one 8-bit step through compositing
+    /// and 16-bit export
+    pub const DOCUMENT_FORMAT: TextureFormat = TextureFormat::Rgba16Float;
 }
 
 /// Registry capacities for brush-related registries
@@ -75,3 +102,39 @@ pub mod render_background {
     /// Checkerboard cell size in screen pixels
     pub const DOC_CHECKER_SIZE_PX: f32 = 16.0;
 }
+
+/// Brush preset library configuration
+pub mod brush_presets {
+    /// Directory under the user config dir holding one file per preset
+    pub const DEFAULT_DIR_NAME: &str = "glaphica/brush-presets";
+}
+
+/// User preferences configuration
+pub mod preferences {
+    /// File under the user config dir holding preferences shared by documents
+    pub const DEFAULT_FILE_NAME: &str = "glaphica/preferences.json";
+}
+
+/// Runtime-loaded file brush configuration
+pub mod file_brushes {
+    /// Interval between shader file modification checks, in milliseconds
+    pub const WATCH_POLL_INTERVAL_MS: u64 = 250;
+}
+
+/// Saved document metadata configuration
+pub mod document_metadata {
+    /// Longest edge of the composite thumbnail embedded in saved documents
+    pub const THUMBNAIL_MAX_EDGE: u32 = 256;
+
+    /// Gaps between edits longer than this count as idle, in milliseconds
+    pub const EDITING_IDLE_CAP_MS: u64 = 60_000;
+}
+
+/// Crash recovery configuration
+pub mod recovery {
+    /// Interval between recovery checkpoints while edits are pending, in seconds
+    pub const AUTOSAVE_INTERVAL_S: u64 = 120;
+
+    /// Directory name under the system temp dir used when no recovery dir is given
+    pub const DEFAULT_DIR_NAME: &str = "glaphica-recovery";
+}
@@ -2,15 +2,20 @@ use atlas::{BackendManager, EditSession, TileKeySwap};
 use brushes::{BrushEngineRuntime, BrushResamplerDistance, StrokeDrawOutput, TileSlotAllocator};
 use document::{Document, FlatRenderTree, SharedRenderTree};
 use glaphica_core::{
-    BackendId, BrushId, BrushInput, NodeId, RenderTreeGeneration, StrokeId, TileKey,
+    BackendId, BrushId, BrushInput, InputDeviceKind, NodeId, RenderTreeGeneration, StrokeId,
+    TileKey,
 };
 use images::Image;
 use std::{collections::HashMap, sync::Arc};
-use stroke_input::{InputProcessingConfig, StrokeInputProcessor};
+use stroke_input::{
+    DeviceCalibrations, InputProcessingConfig, ShapeTaper, SmoothingConfig, StrokeInputProcessor,
+    StrokeShape, StrokeTaper, Symmetry, SyntheticPressure,
+};
 
 pub struct EngineBackendManager {
     manager: BackendManager,
     stroke_edits: HashMap<u8, EditSession>,
+    stroke_discards: Vec<TileKey>,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -31,6 +36,7 @@ impl EngineBackendManager {
         Self {
             manager: BackendManager::new(),
             stroke_edits: HashMap::new(),
+            stroke_discards: Vec::new(),
         }
     }
 
@@ -122,6 +128,7 @@ impl TileSlotAllocator for EngineBackendManager {
 
     fn begin_stroke(&mut self) {
         self.stroke_edits.clear();
+        self.stroke_discards.clear();
     }
 
     fn end_stroke(&mut self) {
@@ -172,6 +179,18 @@ impl TileSlotAllocator for EngineBackendManager {
         }
         self.stroke_edits.insert(backend_key, session);
     }
+
+    fn discard(&mut self, tile: TileKey) {
+        self.stroke_discards.push(tile);
+    }
+
+    fn cancel_stroke(&mut self) {
+        // The sessions only list tiles the stroke replaced; dropping them
+        // unfinished leaves those tiles active.
+        self.stroke_edits.clear();
+        let discards = std::mem::take(&mut self.stroke_discards);
+        self.drop_tiles(discards);
+    }
 }
 
 pub struct EngineThreadState {
@@ -182,6 +201,8 @@ pub struct EngineThreadState {
     stroke_outputs: Vec<StrokeDrawOutput>,
     input_processor: StrokeInputProcessor,
     active_stroke_id: Option<StrokeId>,
+    symmetry: Symmetry,
+    symmetry_inputs: Vec<BrushInput>,
     pending_stroke_undo_tiles: Vec<StrokeTileUndoRecord>,
     undo_strokes: Vec<StrokeUndoRecord>,
     redo_strokes: Vec<StrokeUndoRecord>,
@@ -199,18 +220,24 @@ const RESAMPLER_MAX_TIME_S: f32 = 0.05;
 impl EngineThreadState {
     pub fn new(document: Document, shared_tree: Arc<SharedRenderTree>, max_brushes: usize) -> Self {
         let input_processor = StrokeInputProcessor::new(InputProcessingConfig {
-            smoothing: stroke_input::ExponentialMovingAverageConfig {
-                position_alpha: 0.3,
-                pressure_alpha: 0.3,
-                tilt_alpha: 0.3,
-                twist_alpha: 0.3,
-            },
+            smoothing: SmoothingConfig::ExponentialMovingAverage(
+                stroke_input::ExponentialMovingAverageConfig {
+                    position_alpha: 0.3,
+                    pressure_alpha: 0.3,
+                    tilt_alpha: 0.3,
+                    twist_alpha: 0.3,
+                },
+            ),
             resampling: stroke_input::ResamplerConfig {
                 min_distance: 2.0,
                 max_distance: 10.0,
                 min_time_s: RESAMPLER_MIN_TIME_S,
                 max_time_s: RESAMPLER_MAX_TIME_S,
             },
+            resampler: stroke_input::ResamplerKind::Linear,
+            taper: StrokeTaper::NONE,
+            synthetic_pressure: SyntheticPressure::Off,
+            calibration: DeviceCalibrations::default(),
             velocity_window_size: 4,
             curvature_window_size: 4,
         });
@@ -223,6 +250,8 @@ impl EngineThreadState {
             stroke_outputs: Vec::new(),
             input_processor,
             active_stroke_id: None,
+            symmetry: Symmetry::OFF,
+            symmetry_inputs: Vec::new(),
             pending_stroke_undo_tiles: Vec::new(),
             undo_strokes: Vec::new(),
             redo_strokes: Vec::new(),
@@ -300,15 +329,53 @@ impl EngineThreadState {
         self.brush_runtime.begin_stroke(&mut self.backend_manager);
     }
 
+    /// Commits the active stroke. The redo stack is dropped and the stroke is
+    /// counted here rather than at begin, so a cancelled stroke leaves both
+    /// untouched.
     pub fn end_stroke(&mut self) {
         self.input_processor.end_stroke();
         self.brush_runtime.end_stroke(&mut self.backend_manager);
+        if self.active_stroke_id.take().is_some() {
+            self.invalidate_redo_strokes();
+            self.document.metadata_mut().record_stroke();
+        }
         if !self.pending_stroke_undo_tiles.is_empty() {
             self.undo_strokes.push(StrokeUndoRecord {
                 tiles: std::mem::take(&mut self.pending_stroke_undo_tiles),
             });
         }
-        self.active_stroke_id = None;
+    }
+
+    /// Abandons the active stroke, pointing every tile it touched back at the
+    /// origin tile it copied from and discarding the tiles it allocated.
+    /// Nothing is recorded for undo. Returns the tile key update that restores
+    /// the touched tiles, if there were any.
+    pub fn cancel_stroke(&mut self) -> Option<thread_protocol::TileSlotKeyUpdateMsg> {
+        self.active_stroke_id.take()?;
+        self.input_processor.end_stroke();
+        let record = StrokeUndoRecord {
+            tiles: std::mem::take(&mut self.pending_stroke_undo_tiles),
+        };
+        for tile in &record.tiles {
+            if let Some(image) = self.document.get_leaf_image_mut(tile.node_id)
+                && image
+                    .set_tile_key(tile.tile_index, tile.old_tile_key)
+                    .is_err()
+            {
+                eprintln!(
+                    "failed to restore tile {} of node {} after stroke cancel",
+                    tile.tile_index, tile.node_id.0
+                );
+            }
+            if tile.new_tile_key != TileKey::EMPTY {
+                self.backend_manager.discard(tile.new_tile_key);
+            }
+        }
+        self.brush_runtime.cancel_stroke(&mut self.backend_manager);
+        if record.tiles.is_empty() {
+            return None;
+        }
+        Some(Self::tile_update_msg_from_record(&record, true))
     }
 
     pub fn undo_stroke(&mut self) -> Option<thread_protocol::TileSlotKeyUpdateMsg> {
@@ -346,13 +413,51 @@ impl EngineThreadState {
 
     pub fn process_raw_input(
         &mut self,
+        device: InputDeviceKind,
         cursor: glaphica_core::MappedCursor,
         timestamp_ns: u64,
     ) -> Vec<BrushInput> {
         match self.active_stroke_id {
-            Some(stroke_id) => self
-                .input_processor
-                .process_input(stroke_id, cursor, timestamp_ns),
+            Some(stroke_id) => {
+                self.input_processor
+                    .process_input(stroke_id, device, cursor, timestamp_ns)
+            }
+            None => Vec::new(),
+        }
+    }
+
+    /// Catch-up inputs the smoother still owes the active stroke.
+    pub fn finish_stroke_input(&mut self) -> Vec<BrushInput> {
+        self.input_processor.finish_stroke()
+    }
+
+    pub fn set_smoothing(&mut self, smoothing: SmoothingConfig) {
+        self.input_processor.set_smoothing_config(smoothing);
+    }
+
+    pub fn set_resampler_kind(&mut self, kind: stroke_input::ResamplerKind) {
+        self.input_processor.set_resampler_kind(kind);
+    }
+
+    pub fn set_pressure_ramp(&mut self, taper: StrokeTaper, synthetic_pressure: SyntheticPressure) {
+        self.input_processor.set_taper(taper);
+        self.input_processor
+            .set_synthetic_pressure(synthetic_pressure);
+    }
+
+    pub fn set_device_calibration(&mut self, calibration: DeviceCalibrations) {
+        self.input_processor.set_calibration(calibration);
+    }
+
+    /// Inputs stroking `shape` in the active stroke, spaced no wider than the
+    /// resampler would space a freehand stroke with the active brush.
+    pub fn shape_stroke_inputs(&self, shape: &StrokeShape, taper: ShapeTaper) -> Vec<BrushInput> {
+        match self.active_stroke_id {
+            Some(stroke_id) => shape.brush_inputs(
+                stroke_id,
+                self.input_processor.resampling_config().max_distance,
+                taper,
+            ),
             None => Vec::new(),
         }
     }
@@ -365,6 +470,14 @@ impl EngineThreadState {
         &mut self.input_processor
     }
 
+    pub fn symmetry(&self) -> Symmetry {
+        self.symmetry
+    }
+
+    pub fn set_symmetry(&mut self, symmetry: Symmetry) {
+        self.symmetry = symmetry;
+    }
+
     pub fn set_resampler_distance(&mut self, distance: BrushResamplerDistance) {
         self.input_processor
             .set_resampling_config(stroke_input::ResamplerConfig {
@@ -394,17 +507,25 @@ impl EngineThreadState {
             }
         };
 
-        self.brush_runtime.build_stroke_draw_outputs_for_image(
-            brush_id,
-            brush_input,
-            rgb,
-            erase,
-            node_id,
-            image,
-            ref_image,
-            &mut self.backend_manager,
-            &mut self.stroke_outputs,
-        )?;
+        // Symmetry copies share the stroke, so one undo removes all of them.
+        self.symmetry_inputs.clear();
+        self.symmetry
+            .replicate(brush_input, &mut self.symmetry_inputs);
+        for (copy_index, copy_input) in self.symmetry_inputs.iter().enumerate() {
+            self.brush_runtime
+                .begin_symmetry_copy(brush_id, copy_index)?;
+            self.brush_runtime.build_stroke_draw_outputs_for_image(
+                brush_id,
+                copy_input,
+                rgb,
+                erase,
+                node_id,
+                image,
+                ref_image,
+                &mut self.backend_manager,
+                &mut self.stroke_outputs,
+            )?;
+        }
 
         let mut clear_ops = Vec::new();
         let mut copy_ops = Vec::new();
@@ -684,6 +805,10 @@ mod tests {
         EngineBackendManager, EngineThreadState, collect_render_cache_tile_keys,
         retire_stale_render_cache_tiles,
     };
+    use brushes::builtin_brushes::pixel_rect::PixelRectBrush;
+    use brushes::{
+        BrushGpuPipelineRegistry, BrushLayoutRegistry, BrushSpec, DabDynamics, WithDabDynamics,
+    };
     use document::{
         Document, FlatNodeKind, FlatRenderNode, FlatRenderTree, LeafBlendMode, NodeConfig,
         SharedRenderTree,
@@ -691,8 +816,17 @@ mod tests {
     use glaphica_core::{
         AtlasLayout, BackendId, IMAGE_TILE_SIZE, NodeId, RenderTreeGeneration, TileKey,
     };
+    use glaphica_core::{
+        BrushId, BrushInput, BrushInputFlags, CanvasVec2, InputDeviceKind, MappedCursor,
+        RadianVec2, StrokeId,
+    };
     use images::{Image, layout::ImageLayout};
     use std::{collections::HashMap, sync::Arc};
+    use stroke_input::{
+        MovingWindowConfig, ShapeTaper, SmoothingConfig, StrokeShape, StrokeTaper, Symmetry,
+        SymmetryMode, SyntheticPressure, VelocityPressureConfig,
+    };
+    use thread_protocol::GpuCmdMsg;
 
     fn build_branch_tree(layout: ImageLayout, tile_keys: &[TileKey]) -> FlatRenderTree {
         let mut render_cache = Image::new(layout, BackendId::new(1)).unwrap();
@@ -862,4 +996,463 @@ mod tests {
         assert_eq!(new_root.tile_key(3), Some(old_keys[2]));
         assert_eq!(new_root.tile_key(4), Some(old_keys[3]));
     }
+
+    #[test]
+    fn symmetry_copies_draw_mirrored_tiles_in_one_undo_stroke() {
+        let layout = ImageLayout::new(IMAGE_TILE_SIZE * 2, IMAGE_TILE_SIZE);
+        let document = Document::new(
+            "default".to_string(),
+            layout,
+            BackendId::new(0),
+            BackendId::new(1),
+        )
+        .unwrap();
+        let shared_tree = Arc::new(SharedRenderTree::new(FlatRenderTree {
+            generation: RenderTreeGeneration(0),
+            nodes: Arc::new(HashMap::new()),
+            root_id: None,
+        }));
+        let mut engine = EngineThreadState::new(document, shared_tree, 8);
+        engine
+            .backend_manager_mut()
+            .add_backend(AtlasLayout::Small11)
+            .unwrap();
+        engine
+            .backend_manager_mut()
+            .add_backend(AtlasLayout::Small11)
+            .unwrap();
+        let mut layouts = BrushLayoutRegistry::new(8);
+        let mut pipelines = BrushGpuPipelineRegistry::new(8);
+        PixelRectBrush::new(2)
+            .register(
+                BrushId(1),
+                engine.brush_runtime_mut(),
+                &mut layouts,
+                &mut pipelines,
+            )
+            .unwrap();
+        engine.set_symmetry(Symmetry::new(
+            SymmetryMode::Vertical,
+            CanvasVec2::new(IMAGE_TILE_SIZE as f32, 0.0),
+        ));
+
+        engine.begin_stroke(StrokeId(1));
+        let input = BrushInput {
+            stroke: StrokeId(1),
+            cursor: MappedCursor {
+                cursor: CanvasVec2::new(10.0, 10.0),
+                tilt: RadianVec2::new(0.0, 0.0),
+                pressure: 1.0,
+                twist: 0.0,
+            },
+            flags: BrushInputFlags::empty(),
+            path_s: 0.0,
+            delta_s: 0.0,
+            dt_s: 0.0,
+            vel: CanvasVec2::new(0.0, 0.0),
+            speed: 0.0,
+            tangent: CanvasVec2::new(0.0, 0.0),
+            acc: CanvasVec2::new(0.0, 0.0),
+            accel: 0.0,
+            curvature: 0.0,
+            confidence: 1.0,
+        };
+        let commands = engine
+            .process_stroke_input(BrushId(1), &input, [1.0, 0.0, 0.0], false, NodeId(1), None)
+            .unwrap();
+        engine.end_stroke();
+
+        let mut drawn_tiles = commands
+            .iter()
+            .filter_map(|command| match command {
+                GpuCmdMsg::DrawOp(draw_op) => Some(draw_op.tile_index),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        drawn_tiles.sort_unstable();
+        assert_eq!(drawn_tiles, vec![0, 1]);
+        assert_eq!(engine.stats().undo_stroke_count, 1);
+    }
+
+    #[test]
+    fn symmetry_copies_keep_one_pixel_perfect_trail_through_dab_dynamics() {
+        let layout = ImageLayout::new(IMAGE_TILE_SIZE * 2, IMAGE_TILE_SIZE);
+        let document = Document::new(
+            "default".to_string(),
+            layout,
+            BackendId::new(0),
+            BackendId::new(1),
+        )
+        .unwrap();
+        let shared_tree = Arc::new(SharedRenderTree::new(FlatRenderTree {
+            generation: RenderTreeGeneration(0),
+            nodes: Arc::new(HashMap::new()),
+            root_id: None,
+        }));
+        let mut engine = EngineThreadState::new(document, shared_tree, 8);
+        engine
+            .backend_manager_mut()
+            .add_backend(AtlasLayout::Small11)
+            .unwrap();
+        engine
+            .backend_manager_mut()
+            .add_backend(AtlasLayout::Small11)
+            .unwrap();
+        let mut layouts = BrushLayoutRegistry::new(8);
+        let mut pipelines = BrushGpuPipelineRegistry::new(8);
+        WithDabDynamics::new(
+            PixelRectBrush::new(1).with_pixel_perfect(true),
+            DabDynamics::default(),
+        )
+        .register(
+            BrushId(1),
+            engine.brush_runtime_mut(),
+            &mut layouts,
+            &mut pipelines,
+        )
+        .unwrap();
+        let axis_x = IMAGE_TILE_SIZE as f32;
+        engine.set_symmetry(Symmetry::new(
+            SymmetryMode::Vertical,
+            CanvasVec2::new(axis_x, 0.0),
+        ));
+
+        let input = |x: f32, y: f32, flags: BrushInputFlags| BrushInput {
+            stroke: StrokeId(1),
+            cursor: MappedCursor {
+                cursor: CanvasVec2::new(x, y),
+                tilt: RadianVec2::new(0.0, 0.0),
+                pressure: 1.0,
+                twist: 0.0,
+            },
+            flags,
+            path_s: 0.0,
+            delta_s: 0.0,
+            dt_s: 0.0,
+            vel: CanvasVec2::new(0.0, 0.0),
+            speed: 0.0,
+            tangent: CanvasVec2::new(0.0, 0.0),
+            acc: CanvasVec2::new(0.0, 0.0),
+            accel: 0.0,
+            curvature: 0.0,
+            confidence: 1.0,
+        };
+        let staircase = [
+            (10.5, 10.5),
+            (11.5, 10.5),
+            (11.5, 11.5),
+            (12.5, 11.5),
+            (12.5, 12.5),
+        ];
+        let inputs = staircase
+            .iter()
+            .map(|&(x, y)| input(x, y, BrushInputFlags::empty()))
+            .chain([input(12.5, 12.5, BrushInputFlags::STROKE_END)]);
+
+        engine.begin_stroke(StrokeId(1));
+        let mut cells = Vec::new();
+        for input in inputs {
+            let commands = engine
+                .process_stroke_input(BrushId(1), &input, [1.0, 0.0, 0.0], false, NodeId(1), None)
+                .unwrap();
+            for command in commands {
+                if let GpuCmdMsg::DrawOp(draw_op) = command {
+                    let origin_x = (draw_op.tile_index as u32 * IMAGE_TILE_SIZE) as f32;
+                    cells.push((
+                        (origin_x + draw_op.input[0]).floor() as i32,
+                        draw_op.input[1].floor() as i32,
+                    ));
+                }
+            }
+        }
+        engine.end_stroke();
+
+        // Each copy drops its own staircase corners; a shared trail would
+        // instead draw a line between the copies.
+        cells.sort_unstable();
+        assert_eq!(
+            cells,
+            vec![
+                (10, 10),
+                (11, 11),
+                (12, 12),
+                (111, 12),
+                (112, 11),
+                (113, 10),
+            ]
+        );
+    }
+
+    #[test]
+    fn shape_stroke_inputs_follow_the_active_stroke_and_resampler_spacing() {
+        let layout = ImageLayout::new(IMAGE_TILE_SIZE, IMAGE_TILE_SIZE);
+        let document = Document::new(
+            "default".to_string(),
+            layout,
+            BackendId::new(0),
+            BackendId::new(1),
+        )
+        .unwrap();
+        let shared_tree = Arc::new(SharedRenderTree::new(FlatRenderTree {
+            generation: RenderTreeGeneration(0),
+            nodes: Arc::new(HashMap::new()),
+            root_id: None,
+        }));
+        let mut engine = EngineThreadState::new(document, shared_tree, 8);
+        let shape = StrokeShape::Line {
+            start: CanvasVec2::new(0.0, 0.0),
+            end: CanvasVec2::new(100.0, 0.0),
+        };
+        assert!(
+            engine
+                .shape_stroke_inputs(&shape, ShapeTaper::NONE)
+                .is_empty()
+        );
+
+        engine.begin_stroke(StrokeId(4));
+        let max_distance = engine.input_processor().resampling_config().max_distance;
+        let inputs = engine.shape_stroke_inputs(&shape, ShapeTaper::NONE);
+        engine.end_stroke();
+
+        assert!(inputs.iter().all(|input| input.stroke == StrokeId(4)));
+        assert!(
+            inputs
+                .windows(2)
+                .all(|pair| pair[1].delta_s <= max_distance + 0.001)
+        );
+        assert_eq!(
+            inputs.last().map(|input| input.cursor.cursor.x),
+            Some(100.0)
+        );
+    }
+
+    #[test]
+    fn moving_window_smoothing_catches_up_to_the_pen_at_stroke_end() {
+        let layout = ImageLayout::new(IMAGE_TILE_SIZE, IMAGE_TILE_SIZE);
+        let document = Document::new(
+            "default".to_string(),
+            layout,
+            BackendId::new(0),
+            BackendId::new(1),
+        )
+        .unwrap();
+        let shared_tree = Arc::new(SharedRenderTree::new(FlatRenderTree {
+            generation: RenderTreeGeneration(0),
+            nodes: Arc::new(HashMap::new()),
+            root_id: None,
+        }));
+        let mut engine = EngineThreadState::new(document, shared_tree, 8);
+        engine.set_smoothing(SmoothingConfig::MovingWindow(MovingWindowConfig {
+            window_size: 6,
+        }));
+
+        engine.begin_stroke(StrokeId(2));
+        let mut last_x = 0.0;
+        for index in 0..10u64 {
+            let inputs = engine.process_raw_input(
+                InputDeviceKind::Pen,
+                MappedCursor {
+                    cursor: CanvasVec2::new(index as f32 * 8.0, 0.0),
+                    tilt: RadianVec2::new(0.0, 0.0),
+                    pressure: 1.0,
+                    twist: 0.0,
+                },
+                index * 10_000_000,
+            );
+            if let Some(input) = inputs.last() {
+                last_x = input.cursor.cursor.x;
+            }
+        }
+        assert!(last_x < 60.0);
+        let catch_up = engine.finish_stroke_input();
+        engine.end_stroke();
+
+        assert!(!catch_up.is_empty());
+        assert_eq!(
+            catch_up.last().map(|input| input.cursor.cursor.x),
+            Some(72.0)
+        );
+    }
+
+    #[test]
+    fn mouse_strokes_get_tapered_ends_and_speed_pressure() {
+        let layout = ImageLayout::new(IMAGE_TILE_SIZE, IMAGE_TILE_SIZE);
+        let document = Document::new(
+            "default".to_string(),
+            layout,
+            BackendId::new(0),
+            BackendId::new(1),
+        )
+        .unwrap();
+        let shared_tree = Arc::new(SharedRenderTree::new(FlatRenderTree {
+            generation: RenderTreeGeneration(0),
+            nodes: Arc::new(HashMap::new()),
+            root_id: None,
+        }));
+        let mut engine = EngineThreadState::new(document, shared_tree, 8);
+        engine.set_smoothing(SmoothingConfig::None);
+        engine.set_pressure_ramp(
+            StrokeTaper::new(20.0, 20.0),
+            SyntheticPressure::Velocity(VelocityPressureConfig {
+                min_pressure: 0.5,
+                full_speed: 1000.0,
+            }),
+        );
+
+        engine.begin_stroke(StrokeId(3));
+        let mut inputs = Vec::new();
+        for index in 0..=20u64 {
+            inputs.extend(engine.process_raw_input(
+                InputDeviceKind::Cursor,
+                MappedCursor {
+                    cursor: CanvasVec2::new(index as f32 * 10.0, 0.0),
+                    tilt: RadianVec2::new(0.0, 0.0),
+                    pressure: 1.0,
+                    twist: 0.0,
+                },
+                index * 10_000_000,
+            ));
+        }
+        let held_back = inputs.last().map(|input| input.path_s).unwrap();
+        assert!(held_back <= 180.0);
+        inputs.extend(engine.finish_stroke_input());
+        engine.end_stroke();
+
+        let pressures = inputs
+            .iter()
+            .map(|input| input.cursor.pressure)
+            .collect::<Vec<_>>();
+        assert_eq!(pressures.first(), Some(&0.0));
+        assert_eq!(pressures.last(), Some(&0.0));
+        let middle = pressures[pressures.len() / 2];
+        assert!(middle > 0.5 && middle < 0.9, "pressure {middle}");
+    }
+
+    fn pixel_rect_engine_with_origin_tile() -> (EngineThreadState, TileKey) {
+        let layout = ImageLayout::new(IMAGE_TILE_SIZE, IMAGE_TILE_SIZE);
+        let document = Document::new(
+            "default".to_string(),
+            layout,
+            BackendId::new(0),
+            BackendId::new(1),
+        )
+        .unwrap();
+        let shared_tree = Arc::new(SharedRenderTree::new(FlatRenderTree {
+            generation: RenderTreeGeneration(0),
+            nodes: Arc::new(HashMap::new()),
+            root_id: None,
+        }));
+        let mut engine = EngineThreadState::new(document, shared_tree, 8);
+        engine
+            .backend_manager_mut()
+            .add_backend(AtlasLayout::Small11)
+            .unwrap();
+        engine
+            .backend_manager_mut()
+            .add_backend(AtlasLayout::Small11)
+            .unwrap();
+        let mut layouts = BrushLayoutRegistry::new(8);
+        let mut pipelines = BrushGpuPipelineRegistry::new(8);
+        PixelRectBrush::new(2)
+            .register(
+                BrushId(1),
+                engine.brush_runtime_mut(),
+                &mut layouts,
+                &mut pipelines,
+            )
+            .unwrap();
+        let origin_tile = engine.allocate_leaf_tile(BackendId::new(0)).unwrap();
+        engine
+            .document_mut()
+            .get_leaf_image_mut(NodeId(1))
+            .unwrap()
+            .set_tile_key(0, origin_tile)
+            .unwrap();
+        (engine, origin_tile)
+    }
+
+    fn paint_single_dab(engine: &mut EngineThreadState, stroke: StrokeId) -> TileKey {
+        let input = BrushInput {
+            stroke,
+            cursor: MappedCursor {
+                cursor: CanvasVec2::new(10.0, 10.0),
+                tilt: RadianVec2::new(0.0, 0.0),
+                pressure: 1.0,
+                twist: 0.0,
+            },
+            flags: BrushInputFlags::empty(),
+            path_s: 0.0,
+            delta_s: 0.0,
+            dt_s: 0.0,
+            vel: CanvasVec2::new(0.0, 0.0),
+            speed: 0.0,
+            tangent: CanvasVec2::new(0.0, 0.0),
+            acc: CanvasVec2::new(0.0, 0.0),
+            accel: 0.0,
+            curvature: 0.0,
+            confidence: 1.0,
+        };
+        engine
+            .process_stroke_input(BrushId(1), &input, [1.0, 0.0, 0.0], false, NodeId(1), None)
+            .unwrap();
+        engine
+            .document()
+            .get_leaf_image(NodeId(1))
+            .unwrap()
+            .tile_key(0)
+            .unwrap()
+    }
+
+    #[test]
+    fn cancel_stroke_restores_origin_tiles_and_records_no_undo() {
+        let (mut engine, origin_tile) = pixel_rect_engine_with_origin_tile();
+
+        engine.begin_stroke(StrokeId(5));
+        let stroke_tile = paint_single_dab(&mut engine, StrokeId(5));
+        assert_ne!(stroke_tile, origin_tile);
+
+        let update = engine.cancel_stroke().unwrap();
+
+        assert_eq!(update.updates, vec![(NodeId(1), 0, origin_tile)]);
+        assert_eq!(
+            engine
+                .document()
+                .get_leaf_image(NodeId(1))
+                .unwrap()
+                .tile_key(0),
+            Some(origin_tile)
+        );
+        let backend = engine.backend_manager().backend(BackendId::new(0)).unwrap();
+        assert_eq!(
+            backend.tile_state(origin_tile).unwrap(),
+            atlas::TileState::Active
+        );
+        assert_eq!(
+            backend.tile_state(stroke_tile).unwrap(),
+            atlas::TileState::Cached
+        );
+        assert_eq!(engine.stats().undo_stroke_count, 0);
+        assert!(engine.undo_stroke().is_none());
+        assert!(engine.cancel_stroke().is_none());
+    }
+
+    #[test]
+    fn cancelled_stroke_keeps_redo_stack_and_stroke_count() {
+        let (mut engine, origin_tile) = pixel_rect_engine_with_origin_tile();
+        engine.begin_stroke(StrokeId(1));
+        let committed_tile = paint_single_dab(&mut engine, StrokeId(1));
+        engine.end_stroke();
+        assert_eq!(engine.document().metadata().stroke_count(), 1);
+        assert!(engine.undo_stroke().is_some());
+
+        engine.begin_stroke(StrokeId(2));
+        paint_single_dab(&mut engine, StrokeId(2));
+        assert!(engine.cancel_stroke().is_some());
+
+        assert_eq!(engine.document().metadata().stroke_count(), 1);
+        let redo = engine.redo_stroke().unwrap();
+        assert_eq!(redo.updates, vec![(NodeId(1), 0, committed_tile)]);
+        assert_ne!(committed_tile, origin_tile);
+        assert_eq!(engine.stats().undo_stroke_count, 1);
+    }
 }
@@ -0,0 +1,527 @@
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::Path;
+
+use glaphica_core::NodeId;
+use images::{StoredImage, StoredPixelFormat};
+
+use crate::ExportImageError;
+
+const RGBA_CHANNELS: usize = 4;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    Png,
+    WebpLossless,
+    WebpLossy,
+    Tiff,
+    Jpeg,
+}
+
+impl ExportFormat {
+    pub const ALL: [Self; 5] = [
+        Self::Png,
+        Self::WebpLossless,
+        Self::WebpLossy,
+        Self::Tiff,
+        Self::Jpeg,
+    ];
+
+    /// Picks a format from the path extension; `.webp` maps to lossless.
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let extension = path.extension()?.to_str()?.to_ascii_lowercase();
+        match extension.as_str() {
+            "png" => Some(Self::Png),
+            "webp" => Some(Self::WebpLossless),
+            "tif" | "tiff" => Some(Self::Tiff),
+            "jpg" | "jpeg" => Some(Self::Jpeg),
+            _ => None,
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "png" => Some(Self::Png),
+            "webp" | "webp-lossless" => Some(Self::WebpLossless),
+            "webp-lossy" => Some(Self::WebpLossy),
+            "tif" | "tiff" => Some(Self::Tiff),
+            "jpg" | "jpeg" => Some(Self::Jpeg),
+            _ => None,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Png => "PNG",
+            Self::WebpLossless => "WebP (lossless)",
+            Self::WebpLossy => "WebP (lossy)",
+            Self::Tiff => "TIFF",
+            Self::Jpeg => "JPEG",
+        }
+    }
+
+    pub fn extension(self) -> &'static str {
+        match self {
+            Self::Png => "png",
+            Self::WebpLossless | Self::WebpLossy => "webp",
+            Self::Tiff => "tiff",
+            Self::Jpeg => "jpeg",
+        }
+    }
+
+    pub fn supports_alpha(self) -> bool {
+        !matches!(self, Self::Jpeg)
+    }
+
+    pub fn supports_sixteen_bit(self) -> bool {
+        matches!(self, Self::Png | Self::Tiff)
+    }
+
+    pub fn uses_quality(self) -> bool {
+        matches!(self, Self::WebpLossy | Self::Jpeg)
+    }
+
+    pub fn matches_path(self, path: &Path) -> bool {
+        Self::from_path(path).is_some_and(|format| format.extension() == self.extension())
+    }
+}
+
+/// Region of the source image in canvas pixels.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ExportRect {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportBitDepth {
+    Eight,
+    /// 16 bits per channel; formats without 16-bit support fall back to 8.
+    Sixteen,
+}
+
+impl ExportBitDepth {
+    pub fn from_bits(bits: u32) -> Option<Self> {
+        match bits {
+            8 => Some(Self::Eight),
+            16 => Some(Self::Sixteen),
+            _ => None,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Eight => "8-bit",
+            Self::Sixteen => "16-bit",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ExportBackground {
+    Transparent,
+    Fill([f32; 3]),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExportOptions {
+    pub format: ExportFormat,
+    /// Encoder quality in `1..=100`, used by lossy formats only.
+    pub quality: u8,
+    pub bit_depth: ExportBitDepth,
+    pub scale: f32,
+    pub crop: Option<ExportRect>,
+    pub background: ExportBackground,
+    /// Exports this node's subtree instead of the root composite.
+    pub node_id: Option<NodeId>,
+}
+
+impl ExportOptions {
+    pub fn new(format: ExportFormat) -> Self {
+        Self {
+            format,
+            quality: 90,
+            bit_depth: ExportBitDepth::Eight,
+            scale: 1.0,
+            crop: None,
+            background: ExportBackground::Transparent,
+            node_id: None,
+        }
+    }
+
+    pub fn output_pixel_format(&self) -> StoredPixelFormat {
+        if self.bit_depth == ExportBitDepth::Sixteen && self.format.supports_sixteen_bit() {
+            StoredPixelFormat::Rgba16
+        } else {
+            StoredPixelFormat::Rgba8
+        }
+    }
+}
+
+/// Applies crop, scale and background to a full-resolution readback.
+///
+/// Work happens in 16-bit so that 8-bit output only rounds once at the end.
+pub fn prepare_export_image(
+    image: &StoredImage,
+    options: &ExportOptions,
+) -> Result<StoredImage, ExportImageError> {
+    let (mut width, mut height, mut pixels) = match options.crop {
+        Some(rect) => crop_rgba16(image, rect)?,
+        None => (
+            image.width(),
+            image.height(),
+            image.pixels_rgba16().into_owned(),
+        ),
+    };
+
+    if !options.scale.is_finite() || options.scale <= 0.0 {
+        return Err(ExportImageError::InvalidSize);
+    }
+    if options.scale != 1.0 {
+        let scaled_width = scaled_extent(width, options.scale)?;
+        let scaled_height = scaled_extent(height, options.scale)?;
+        pixels = resample_rgba16(width, height, &pixels, scaled_width, scaled_height);
+        width = scaled_width;
+        height = scaled_height;
+    }
+
+    let fill = match options.background {
+        ExportBackground::Fill(rgb) => Some(rgb),
+        ExportBackground::Transparent if !options.format.supports_alpha() => Some([1.0; 3]),
+        ExportBackground::Transparent => None,
+    };
+    if let Some(rgb) = fill {
+        fill_background_rgba16(&mut pixels, rgb);
+    }
+
+    let prepared = StoredImage::new_rgba16(width, height, pixels)
+        .map_err(|_| ExportImageError::InvalidSize)?;
+    Ok(prepared.to_format(options.output_pixel_format()))
+}
+
+pub fn write_export_image(
+    path: &Path,
+    image: &StoredImage,
+    options: &ExportOptions,
+) -> Result<(), ExportImageError> {
+    if let Some(parent_dir) = path.parent()
+        && !parent_dir.as_os_str().is_empty()
+    {
+        std::fs::create_dir_all(parent_dir).map_err(ExportImageError::Io)?;
+    }
+    let quality = options.quality.clamp(1, 100);
+    match options.format {
+        ExportFormat::Png => save_png(path, image),
+        ExportFormat::WebpLossless | ExportFormat::WebpLossy => {
+            let lossless = options.format == ExportFormat::WebpLossless;
+            let encoded =
+                webp::Encoder::from_rgba(&image.pixels_rgba8(), image.width(), image.height())
+                    .encode_simple(lossless, f32::from(quality))
+                    .map_err(ExportImageError::Webp)?;
+            std::fs::write(path, &*encoded).map_err(ExportImageError::Io)
+        }
+        ExportFormat::Tiff => save_tiff(path, image),
+        ExportFormat::Jpeg => save_jpeg_rgba8(path, image, quality),
+    }
+}
+
+fn crop_rgba16(
+    image: &StoredImage,
+    rect: ExportRect,
+) -> Result<(u32, u32, Vec<u16>), ExportImageError> {
+    let x0 = rect.x.min(image.width());
+    let y0 = rect.y.min(image.height());
+    let x1 = rect.x.saturating_add(rect.width).min(image.width());
+    let y1 = rect.y.saturating_add(rect.height).min(image.height());
+    if x1 <= x0 || y1 <= y0 {
+        return Err(ExportImageError::InvalidSize);
+    }
+    let src_stride = image.width() as usize * RGBA_CHANNELS;
+    let row_len = (x1 - x0) as usize * RGBA_CHANNELS;
+    let source = image.pixels_rgba16();
+    let mut pixels = Vec::with_capacity(row_len * (y1 - y0) as usize);
+    for y in y0..y1 {
+        let start = y as usize * src_stride + x0 as usize * RGBA_CHANNELS;
+        pixels.extend_from_slice(&source[start..start + row_len]);
+    }
+    Ok((x1 - x0, y1 - y0, pixels))
+}
+
+fn scaled_extent(extent: u32, scale: f32) -> Result<u32, ExportImageError> {
+    let scaled = (extent as f64 * f64::from(scale)).round().max(1.0);
+    if scaled > f64::from(u32::MAX) {
+        return Err(ExportImageError::InvalidSize);
+    }
+    Ok(scaled as u32)
+}
+
+/// Source pixel coverage for each destination pixel along one axis.
+fn area_weights(src_len: u32, dst_len: u32) -> Vec<Vec<(usize, f32)>> {
+    let ratio = src_len as f64 / dst_len as f64;
+    (0..dst_len)
+        .map(|dst| {
+            let start = dst as f64 * ratio;
+            let end = (dst as f64 + 1.0) * ratio;
+            let first = start.floor() as u32;
+            let last = (end.ceil() as u32).min(src_len);
+            let mut weights = Vec::with_capacity((last - first) as usize);
+            for src in first..last {
+                let overlap = (end.min(src as f64 + 1.0) - start.max(src as f64)) / ratio;
+                if overlap > 0.0 {
+                    weights.push((src as usize, overlap as f32));
+                }
+            }
+            weights
+        })
+        .collect()
+}
+
+/// Area-weighted resampling in premultiplied alpha.
+fn resample_rgba16(
+    src_width: u32,
+    src_height: u32,
+    pixels: &[u16],
+    dst_width: u32,
+    dst_height: u32,
+) -> Vec<u16> {
+    let premultiplied: Vec<[f32; 4]> = pixels
+        .chunks_exact(RGBA_CHANNELS)
+        .map(|rgba| {
+            let alpha = u16_to_unit(rgba[3]);
+            [
+                u16_to_unit(rgba[0]) * alpha,
+                u16_to_unit(rgba[1]) * alpha,
+                u16_to_unit(rgba[2]) * alpha,
+                alpha,
+            ]
+        })
+        .collect();
+
+    let x_weights = area_weights(src_width, dst_width);
+    let mut horizontal = vec![[0.0f32; 4]; dst_width as usize * src_height as usize];
+    for y in 0..src_height as usize {
+        let src_row = &premultiplied[y * src_width as usize..(y + 1) * src_width as usize];
+        for (x, weights) in x_weights.iter().enumerate() {
+            let mut sum = [0.0f32; 4];
+            for &(src_x, weight) in weights {
+                for channel in 0..RGBA_CHANNELS {
+                    sum[channel] += src_row[src_x][channel] * weight;
+                }
+            }
+            horizontal[y * dst_width as usize + x] = sum;
+        }
+    }
+
+    let y_weights = area_weights(src_height, dst_height);
+    let mut output = Vec::with_capacity(dst_width as usize * dst_height as usize * RGBA_CHANNELS);
+    for weights in &y_weights {
+        for x in 0..dst_width as usize {
+            let mut sum = [0.0f32; 4];
+            for &(src_y, weight) in weights {
+                for channel in 0..RGBA_CHANNELS {
+                    sum[channel] += horizontal[src_y * dst_width as usize + x][channel] * weight;
+                }
+            }
+            let alpha = sum[3].clamp(0.0, 1.0);
+            let unpremultiply = if alpha > 0.0 { 1.0 / alpha } else { 0.0 };
+            for value in &sum[..3] {
+                output.push(unit_to_u16(value * unpremultiply));
+            }
+            output.push(unit_to_u16(alpha));
+        }
+    }
+    output
+}
+
+fn fill_background_rgba16(pixels: &mut [u16], rgb: [f32; 3]) {
+    for rgba in pixels.chunks_exact_mut(RGBA_CHANNELS) {
+        let alpha = u16_to_unit(rgba[3]);
+        for channel in 0..3 {
+            let value = u16_to_unit(rgba[channel]);
+            rgba[channel] =
+                unit_to_u16(value * alpha + rgb[channel].clamp(0.0, 1.0) * (1.0 - alpha));
+        }
+        rgba[3] = u16::MAX;
+    }
+}
+
+fn u16_to_unit(value: u16) -> f32 {
+    f32::from(value) / 65535.0
+}
+
+fn unit_to_u16(value: f32) -> u16 {
+    (value.clamp(0.0, 1.0) * 65535.0).round() as u16
+}
+
+fn save_png(path: &Path, image: &StoredImage) -> Result<(), ExportImageError> {
+    let file = File::create(path).map_err(ExportImageError::Io)?;
+    let mut encoder = png::Encoder::new(BufWriter::new(file), image.width(), image.height());
+    encoder.set_color(png::ColorType::Rgba);
+    let data = match image.format() {
+        StoredPixelFormat::Rgba8 => {
+            encoder.set_depth(png::BitDepth::Eight);
+            image.pixels_rgba8().into_owned()
+        }
+        StoredPixelFormat::Rgba16 => {
+            // PNG stores 16-bit samples big-endian.
+            encoder.set_depth(png::BitDepth::Sixteen);
+            image
+                .pixels_rgba16()
+                .iter()
+                .flat_map(|value| value.to_be_bytes())
+                .collect()
+        }
+    };
+    let mut writer = encoder.write_header().map_err(ExportImageError::Png)?;
+    writer
+        .write_image_data(&data)
+        .map_err(ExportImageError::Png)?;
+    Ok(())
+}
+
+fn save_tiff(path: &Path, image: &StoredImage) -> Result<(), ExportImageError> {
+    let file = File::create(path).map_err(ExportImageError::Io)?;
+    let mut encoder =
+        tiff::encoder::TiffEncoder::new(BufWriter::new(file)).map_err(ExportImageError::Tiff)?;
+    match image.format() {
+        StoredPixelFormat::Rgba8 => encoder.write_image::<tiff::encoder::colortype::RGBA8>(
+            image.width(),
+            image.height(),
+            &image.pixels_rgba8(),
+        ),
+        StoredPixelFormat::Rgba16 => encoder.write_image::<tiff::encoder::colortype::RGBA16>(
+            image.width(),
+            image.height(),
+            &image.pixels_rgba16(),
+        ),
+    }
+    .map_err(ExportImageError::Tiff)?;
+    Ok(())
+}
+
+fn save_jpeg_rgba8(path: &Path, image: &StoredImage, quality: u8) -> Result<(), ExportImageError> {
+    let rgba_pixels = image.pixels_rgba8();
+    let mut rgb_pixels = Vec::with_capacity(rgba_pixels.len() / 4 * 3);
+    for rgba in rgba_pixels.chunks_exact(4) {
+        rgb_pixels.extend_from_slice(&rgba[..3]);
+    }
+
+    let file = File::create(path).map_err(ExportImageError::Io)?;
+    let encoder = jpeg_encoder::Encoder::new(file, quality);
+    let jpeg_width = u16::try_from(image.width()).map_err(|_| ExportImageError::InvalidSize)?;
+    let jpeg_height = u16::try_from(image.height()).map_err(|_| ExportImageError::InvalidSize)?;
+    encoder
+        .encode(
+            &rgb_pixels,
+            jpeg_width,
+            jpeg_height,
+            jpeg_encoder::ColorType::Rgb,
+        )
+        .map_err(ExportImageError::Jpeg)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use images::{StoredImage, StoredPixelFormat};
+
+    use super::{
+        ExportBackground, ExportBitDepth, ExportFormat, ExportOptions, ExportRect,
+        prepare_export_image,
+    };
+
+    fn checker_2x2() -> StoredImage {
+        StoredImage::new_rgba8(
+            2,
+            2,
+            vec![255, 0, 0, 255, 0, 0, 255, 255, 0, 255, 0, 255, 0, 0, 0, 0],
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn format_from_path_accepts_known_extensions() {
+        assert_eq!(
+            ExportFormat::from_path(Path::new("a.PNG")),
+            Some(ExportFormat::Png)
+        );
+        assert_eq!(
+            ExportFormat::from_path(Path::new("a.tif")),
+            Some(ExportFormat::Tiff)
+        );
+        assert!(ExportFormat::WebpLossy.matches_path(Path::new("a.webp")));
+        assert!(!ExportFormat::Png.matches_path(Path::new("a.jpg")));
+        assert_eq!(ExportFormat::from_path(Path::new("a.bmp")), None);
+    }
+
+    #[test]
+    fn prepare_crops_then_scales_with_area_average() {
+        let mut options = ExportOptions::new(ExportFormat::Png);
+        options.crop = Some(ExportRect {
+            x: 0,
+            y: 0,
+            width: 2,
+            height: 1,
+        });
+        options.scale = 0.5;
+
+        let prepared = prepare_export_image(&checker_2x2(), &options).unwrap();
+
+        assert_eq!((prepared.width(), prepared.height()), (1, 1));
+        assert_eq!(&*prepared.pixels_rgba8(), &[128, 0, 128, 255]);
+    }
+
+    #[test]
+    fn prepare_fills_background_for_opaque_formats() {
+        let mut options = ExportOptions::new(ExportFormat::Jpeg);
+        let prepared = prepare_export_image(&checker_2x2(), &options).unwrap();
+        assert_eq!(&prepared.pixels_rgba8()[12..], &[255, 255, 255, 255]);
+
+        options.format = ExportFormat::Png;
+        options.background = ExportBackground::Fill([0.0, 0.0, 0.0]);
+        let prepared = prepare_export_image(&checker_2x2(), &options).unwrap();
+        assert_eq!(&prepared.pixels_rgba8()[12..], &[0, 0, 0, 255]);
+
+        options.background = ExportBackground::Transparent;
+        let prepared = prepare_export_image(&checker_2x2(), &options).unwrap();
+        assert_eq!(&prepared.pixels_rgba8()[12..], &[0, 0, 0, 0]);
+    }
+
+    #[test]
+    fn prepare_keeps_sixteen_bit_precision_for_png_and_tiff_only() {
+        let source = StoredImage::new_rgba16(1, 1, vec![1000, 2000, 3000, 65535]).unwrap();
+        let mut options = ExportOptions::new(ExportFormat::Tiff);
+        options.bit_depth = ExportBitDepth::Sixteen;
+
+        let prepared = prepare_export_image(&source, &options).unwrap();
+        assert_eq!(prepared.format(), StoredPixelFormat::Rgba16);
+        assert_eq!(&*prepared.pixels_rgba16(), &[1000, 2000, 3000, 65535]);
+
+        options.format = ExportFormat::Jpeg;
+        let prepared = prepare_export_image(&source, &options).unwrap();
+        assert_eq!(prepared.format(), StoredPixelFormat::Rgba8);
+    }
+
+    #[test]
+    fn sixteen_bit_png_round_trips_through_decoder() {
+        let path =
+            std::env::temp_dir().join(format!("glaphica-export-16bit-{}.png", std::process::id()));
+        let source = StoredImage::new_rgba16(1, 1, vec![1, 258, 65534, 65535]).unwrap();
+        let mut options = ExportOptions::new(ExportFormat::Png);
+        options.bit_depth = ExportBitDepth::Sixteen;
+        super::write_export_image(&path, &source, &options).unwrap();
+
+        let decoder = png::Decoder::new(std::fs::File::open(&path).unwrap());
+        let mut reader = decoder.read_info().unwrap();
+        let mut buffer = vec![0; reader.output_buffer_size()];
+        let info = reader.next_frame(&mut buffer).unwrap();
+        let _ = std::fs::remove_file(&path);
+
+        assert_eq!(info.bit_depth, png::BitDepth::Sixteen);
+        assert_eq!(&buffer[..8], &[0, 1, 1, 2, 255, 254, 255, 255]);
+    }
+}
@@ -0,0 +1,94 @@
+use std::time::Duration;
+
+use crate::config;
+
+/// Sizes each engine frame's input drain from how far behind the engine is.
+///
+/// A drain that leaves samples in the ring, or finds some were overwritten,
+/// doubles the batch and skips the idle wait so the backlog clears in a few
+/// larger frames. Drains that empty the ring with room to spare halve it back
+/// toward [`config::input_batching::BASE_BATCH_SIZE`].
+#[derive(Debug, Clone)]
+pub(crate) struct InputBatchController {
+    batch_size: usize,
+    behind: bool,
+    seen_dropped_samples: u64,
+}
+
+impl Default for InputBatchController {
+    fn default() -> Self {
+        Self {
+            batch_size: config::input_batching::BASE_BATCH_SIZE,
+            behind: false,
+            seen_dropped_samples: 0,
+        }
+    }
+}
+
+impl InputBatchController {
+    pub(crate) fn batch_size(&self) -> usize {
+        self.batch_size
+    }
+
+    pub(crate) fn is_behind(&self) -> bool {
+        self.behind
+    }
+
+    /// The wait to use for the next drain; none while catching up.
+    pub(crate) fn wait_timeout(&self, idle_wait: Duration) -> Duration {
+        if self.behind {
+            Duration::ZERO
+        } else {
+            idle_wait
+        }
+    }
+
+    /// Updates the batch size after a drain of `drained` samples that left
+    /// `pending` in the ring, with `dropped_total` overwritten so far.
+    pub(crate) fn observe(&mut self, drained: usize, pending: usize, dropped_total: u64) {
+        let dropped = dropped_total > self.seen_dropped_samples;
+        self.seen_dropped_samples = dropped_total;
+        self.behind = pending > 0 || dropped;
+        if self.behind {
+            self.batch_size = (self.batch_size * 2).min(config::input_batching::MAX_BATCH_SIZE);
+        } else if drained < self.batch_size / 2 {
+            self.batch_size = (self.batch_size / 2).max(config::input_batching::BASE_BATCH_SIZE);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::InputBatchController;
+    use crate::config::input_batching::{BASE_BATCH_SIZE, MAX_BATCH_SIZE};
+
+    #[test]
+    fn batch_grows_while_behind_and_shrinks_once_caught_up() {
+        let mut batch = InputBatchController::default();
+        let idle_wait = Duration::from_millis(4);
+        assert_eq!(batch.wait_timeout(idle_wait), idle_wait);
+
+        batch.observe(BASE_BATCH_SIZE, 40, 0);
+        assert!(batch.is_behind());
+        assert_eq!(batch.batch_size(), BASE_BATCH_SIZE * 2);
+        assert_eq!(batch.wait_timeout(idle_wait), Duration::ZERO);
+
+        batch.observe(batch.batch_size(), 0, 3);
+        assert!(batch.is_behind());
+        for _ in 0..8 {
+            batch.observe(batch.batch_size(), 10, 3);
+        }
+        assert_eq!(batch.batch_size(), MAX_BATCH_SIZE);
+
+        batch.observe(MAX_BATCH_SIZE, 0, 3);
+        assert!(!batch.is_behind());
+        assert_eq!(batch.batch_size(), MAX_BATCH_SIZE);
+        for _ in 0..8 {
+            batch.observe(1, 0, 3);
+        }
+        assert_eq!(batch.batch_size(), BASE_BATCH_SIZE);
+        assert_eq!(batch.wait_timeout(idle_wait), idle_wait);
+    }
+}
@@ -4,27 +4,45 @@ use std::fmt::{Display, Formatter};
 use std::fs::File;
 use std::io::{BufReader, BufWriter};
 use std::path::Path;
-use std::sync::Arc;
+use std::sync::{Arc, mpsc};
+use std::thread::JoinHandle;
 use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 
-use brushes::{BrushResamplerDistance, BrushResamplerDistancePolicy, BrushSpec};
+use brushes::{
+    BrushRegistryError, BrushResamplerDistance, BrushResamplerDistancePolicy, BrushSpec,
+    ColorDynamics, EngineBrushPipeline, FileBrush, FileBrushDefinition, FileBrushError,
+    ShaderFileWatcher,
+};
 use document::{
     Document, DocumentStorageError, DocumentStorageManifest, FlatRenderTree, LayerMoveTarget,
-    NewLayerKind, SharedRenderTree, UiBlendMode, UiLayerTreeItem,
+    Metadata, NewLayerKind, SharedRenderTree, StoredDocumentMetadata, UiBlendMode, UiLayerTreeItem,
 };
 use flate2::{Compression, read::GzDecoder, write::GzEncoder};
-use glaphica_core::{AtlasLayout, BrushId, NodeId, StrokeId};
+use glaphica_core::{AtlasLayout, BrushId, BrushInput, NodeId, StrokeId};
 use gpu_runtime::surface_runtime::SurfaceRuntime;
-use images::StoredImage;
 use images::layout::ImageLayout;
+use images::{Image, StoredImage, StoredPixelFormat};
 use serde::{Deserialize, Serialize};
+use stroke_input::{
+    DeviceCalibrations, ResamplerKind, ShapeTaper, SmoothingConfig, StrokeShape, StrokeTaper,
+    Symmetry, SyntheticPressure,
+};
 use thread_protocol::{
     DrawFrameMergePolicy, GpuCmdFrameMergeTag, GpuCmdMsg, GpuFeedbackFrame, InputControlEvent,
     InputControlOp, InputRingSample, MergeItem, MergeVecIndex, TileKey,
 };
 use threads::{EngineThreadChannels, MainThreadChannels, create_thread_channels};
 
-use crate::trace::{TraceInputFrame, TraceIoError, TraceRecorder};
+use crate::image_export::ExportOptions;
+use crate::input_batching::InputBatchController;
+use crate::layer_batch_export::{
+    self, LayerBatchExportError, LayerBatchExportOptions, LayerBatchExportReport, LayerBatchScope,
+    LayerBatchTarget, LayerBatchWriter,
+};
+use crate::recovery::{
+    RecoveryIoError, RecoveryJournal, RecoveryJournalEntry, RecoverySessionLock,
+};
+use crate::trace::{TraceAppControl, TraceInputFrame, TraceIoError, TraceRecorder};
 use crate::{
     BrushRegisterError, EngineThreadState, ExportImageError, LayerImageExportError,
     LayerPreviewBitmap, MainThreadState, config,
@@ -46,9 +64,25 @@ pub enum DocumentPackageError {
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 struct PackedDocumentFile {
     manifest: DocumentStorageManifest,
+    /// Written before `layers` so previews can stop reading early.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    thumbnail_png: Option<Vec<u8>>,
     layers: Vec<PackedLayerAsset>,
 }
 
+/// Document summary read without decoding any layer images.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DocumentPreview {
+    pub name: String,
+    pub canvas_width: u32,
+    pub canvas_height: u32,
+    pub metadata: StoredDocumentMetadata,
+    pub thumbnail: Option<StoredImage>,
+}
+
+const PACKAGE_MANIFEST_FILE: &str = "manifest.json";
+const PACKAGE_THUMBNAIL_FILE: &str = "thumbnail.png";
+
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 struct PackedLayerAsset {
     node_id: u64,
@@ -126,12 +160,47 @@ impl Display for DocumentPackageError {
 
 impl Error for DocumentPackageError {}
 
+#[derive(Debug)]
+pub enum FileBrushReloadError {
+    Shader(FileBrushError),
+    Register(BrushRegisterError),
+}
+
+impl Display for FileBrushReloadError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Shader(error) => write!(f, "{error}"),
+            Self::Register(error) => write!(f, "brush shader reload failed: {error:?}"),
+        }
+    }
+}
+
+impl Error for FileBrushReloadError {}
+
+/// Outcome of reloading one file brush whose shader changed on disk.
+#[derive(Debug)]
+pub struct FileBrushReload {
+    pub brush_id: BrushId,
+    pub label: &'static str,
+    pub result: Result<(), FileBrushReloadError>,
+}
+
+struct WatchedFileBrush {
+    brush_id: BrushId,
+    definition: Arc<FileBrushDefinition>,
+    watcher: ShaderFileWatcher,
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub enum AppControl {
     StrokeBoundary {
         node_id: NodeId,
         begin: bool,
     },
+    /// Closes the open stroke on `node_id` and restores the tiles it touched.
+    CancelStroke {
+        node_id: NodeId,
+    },
     SelectNode {
         node_id: NodeId,
     },
@@ -157,28 +226,62 @@ pub enum AppControl {
     },
     MoveActiveNodeUp,
     MoveActiveNodeDown,
+    /// Mirror or radial copies stamped for every following stroke input.
+    SetSymmetry {
+        symmetry: Symmetry,
+    },
+    /// Stabilizer used for every following stroke.
+    SetSmoothing {
+        smoothing: SmoothingConfig,
+    },
+    /// How following strokes are filled in between raw samples.
+    SetResampler {
+        kind: ResamplerKind,
+    },
+    /// Freehand end tapers and the pressure made up for pressure-less devices.
+    SetPressureRamp {
+        taper: StrokeTaper,
+        synthetic_pressure: SyntheticPressure,
+    },
+    /// Per-device pressure curves and tilt dead zones for following input.
+    SetDeviceCalibration {
+        calibration: DeviceCalibrations,
+    },
+    /// Strokes a line, rectangle, ellipse or polyline inside the open stroke.
+    ShapeStroke {
+        shape: StrokeShape,
+        taper: ShapeTaper,
+    },
 }
 
 impl InputControlOp for AppControl {
     type Target = Option<NodeId>;
 
     fn apply(&self, target: &mut Self::Target) {
-        if let Self::StrokeBoundary { node_id, begin } = self {
-            if *begin {
-                *target = Some(*node_id);
-            } else {
-                *target = None;
+        match self {
+            Self::StrokeBoundary { node_id, begin } => {
+                if *begin {
+                    *target = Some(*node_id);
+                } else {
+                    *target = None;
+                }
             }
+            Self::CancelStroke { .. } => *target = None,
+            _ => {}
         }
     }
 
     fn undo(&self, target: &mut Self::Target) {
-        if let Self::StrokeBoundary { node_id, begin } = self {
-            if *begin {
-                *target = None;
-            } else {
-                *target = Some(*node_id);
+        match self {
+            Self::StrokeBoundary { node_id, begin } => {
+                if *begin {
+                    *target = None;
+                } else {
+                    *target = Some(*node_id);
+                }
             }
+            Self::CancelStroke { node_id } => *target = Some(*node_id),
+            _ => {}
         }
     }
 }
@@ -257,66 +360,295 @@ impl PerfTraceConfig {
     }
 }
 
-#[derive(Default)]
-struct EngineFramePerf {
-    input_sample: Duration,
-    smooth_and_resample: Duration,
-    brush_handling: Duration,
-    send_to_app_thread: Duration,
-    send_inline_submit: Duration,
-    accept_by_app_thread: Duration,
-    submit_to_gpu: Duration,
-    submit_collect_render_tree: Duration,
-    submit_materialize_parametric: Duration,
-    submit_composite_render_tree: Duration,
-    submit_queue: Duration,
-    sample_count: usize,
-    brush_input_count: usize,
-    generated_gpu_command_count: usize,
-    inline_submitted_gpu_command_count: usize,
-    inline_submit_batches: usize,
-    pending_send_gpu_command_count: usize,
-    gpu_command_count: usize,
-    submit_parametric_cmd_count: usize,
-    submit_parametric_tile_count: usize,
-    submit_render_cmd_count: usize,
-    submit_render_tile_count: usize,
-    submit_render_source_count: usize,
-    submit_dirty_tile_count: usize,
-    submit_dirty_rect_count: usize,
-    submit_dirty_bbox_tile_area: usize,
-    submit_dirty_node_count: usize,
+/// Stage timings and counts of one engine frame.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct EngineFramePerf {
+    pub input_sample: Duration,
+    pub smooth_and_resample: Duration,
+    pub brush_handling: Duration,
+    pub send_to_app_thread: Duration,
+    pub send_inline_submit: Duration,
+    pub accept_by_app_thread: Duration,
+    pub submit_to_gpu: Duration,
+    pub submit_collect_render_tree: Duration,
+    pub submit_materialize_parametric: Duration,
+    pub submit_composite_render_tree: Duration,
+    pub submit_queue: Duration,
+    pub sample_count: usize,
+    pub brush_input_count: usize,
+    pub generated_gpu_command_count: usize,
+    pub inline_submitted_gpu_command_count: usize,
+    pub inline_submit_batches: usize,
+    pub pending_send_gpu_command_count: usize,
+    pub gpu_command_count: usize,
+    pub submit_parametric_cmd_count: usize,
+    pub submit_parametric_tile_count: usize,
+    pub submit_render_cmd_count: usize,
+    pub submit_render_tile_count: usize,
+    pub submit_render_source_count: usize,
+    pub submit_dirty_tile_count: usize,
+    pub submit_dirty_rect_count: usize,
+    pub submit_dirty_bbox_tile_area: usize,
+    pub submit_dirty_node_count: usize,
+}
+
+impl EngineFramePerf {
+    fn stages(&self) -> [(&'static str, Duration); 6] {
+        [
+            ("input_sample", self.input_sample),
+            ("smooth_and_resample", self.smooth_and_resample),
+            ("brush_handling", self.brush_handling),
+            ("send_to_app_thread", self.send_to_app_thread),
+            ("accept_by_app_thread", self.accept_by_app_thread),
+            ("submit_to_gpu", self.submit_to_gpu),
+        ]
+    }
+
+    /// Sum of the stage timings, input wait included.
+    pub fn total(&self) -> Duration {
+        self.stages()
+            .iter()
+            .map(|(_, duration)| *duration)
+            .fold(Duration::ZERO, |acc, item| acc + item)
+    }
+
+    /// Takes the stages the main thread runs from `submit`, which accepted
+    /// and submitted this frame's commands.
+    fn merge_submit_stages(&mut self, submit: &EngineFramePerf) {
+        self.accept_by_app_thread = submit.accept_by_app_thread;
+        self.submit_to_gpu = submit.submit_to_gpu;
+        self.submit_collect_render_tree = submit.submit_collect_render_tree;
+        self.submit_materialize_parametric = submit.submit_materialize_parametric;
+        self.submit_composite_render_tree = submit.submit_composite_render_tree;
+        self.submit_queue = submit.submit_queue;
+        self.gpu_command_count = submit.gpu_command_count;
+        self.submit_parametric_cmd_count = submit.submit_parametric_cmd_count;
+        self.submit_parametric_tile_count = submit.submit_parametric_tile_count;
+        self.submit_render_cmd_count = submit.submit_render_cmd_count;
+        self.submit_render_tile_count = submit.submit_render_tile_count;
+        self.submit_render_source_count = submit.submit_render_source_count;
+        self.submit_dirty_tile_count = submit.submit_dirty_tile_count;
+        self.submit_dirty_rect_count = submit.submit_dirty_rect_count;
+        self.submit_dirty_bbox_tile_area = submit.submit_dirty_bbox_tile_area;
+        self.submit_dirty_node_count = submit.submit_dirty_node_count;
+    }
+
+    fn trace(&self, config: &PerfTraceConfig, frame_seq: &mut u64) {
+        if !config.enabled {
+            return;
+        }
+        let stages = self.stages();
+        let total = self.total();
+        if total < config.slow_threshold {
+            return;
+        }
+        let Some((bottleneck, bottleneck_duration)) =
+            stages.iter().max_by_key(|(_, duration)| *duration)
+        else {
+            return;
+        };
+        *frame_seq += 1;
+        eprintln!(
+            "[PERF][pipeline][engine_frame={}] total_ms={:.3} bottleneck={} ({:.3}ms) samples={} brush_inputs={} gpu_cmds={} generated_gpu_cmds={} pending_send_gpu_cmds={} inline_submit_cmds={} inline_submit_batches={} inline_submit_ms={:.3} stages_ms={{input:{:.3}, smooth_resample:{:.3}, brush:{:.3}, send:{:.3}, accept:{:.3}, submit:{:.3}}} submit_ms={{collect:{:.3}, materialize:{:.3}, composite:{:.3}, queue:{:.3}}} dirty={{tiles:{}, rects:{}, bbox_tiles:{}, nodes:{}}} render_work={{parametric_cmds:{}, parametric_tiles:{}, render_cmds:{}, render_tiles:{}, render_sources:{}}}",
+            *frame_seq,
+            duration_ms(total),
+            bottleneck,
+            duration_ms(*bottleneck_duration),
+            self.sample_count,
+            self.brush_input_count,
+            self.gpu_command_count,
+            self.generated_gpu_command_count,
+            self.pending_send_gpu_command_count,
+            self.inline_submitted_gpu_command_count,
+            self.inline_submit_batches,
+            duration_ms(self.send_inline_submit),
+            duration_ms(self.input_sample),
+            duration_ms(self.smooth_and_resample),
+            duration_ms(self.brush_handling),
+            duration_ms(self.send_to_app_thread),
+            duration_ms(self.accept_by_app_thread),
+            duration_ms(self.submit_to_gpu),
+            duration_ms(self.submit_collect_render_tree),
+            duration_ms(self.submit_materialize_parametric),
+            duration_ms(self.submit_composite_render_tree),
+            duration_ms(self.submit_queue),
+            self.submit_dirty_tile_count,
+            self.submit_dirty_rect_count,
+            self.submit_dirty_bbox_tile_area,
+            self.submit_dirty_node_count,
+            self.submit_parametric_cmd_count,
+            self.submit_parametric_tile_count,
+            self.submit_render_cmd_count,
+            self.submit_render_tile_count,
+            self.submit_render_source_count,
+        );
+    }
 }
 
 pub struct AppThreadIntegration {
     main_state: MainThreadState,
-    engine_state: EngineThreadState,
     main_channels: AppMainThreadChannels,
-    engine_channels: AppEngineThreadChannels,
+    engine: EngineHost,
+    /// Latest view of the engine's document, read by UI queries.
+    engine_snapshot: EngineSnapshot,
+    engine_snapshots: mpsc::Receiver<EngineSnapshot>,
+    gpu_commands: Vec<thread_protocol::GpuCmdMsg>,
+    feedback_merge_state: AppGpuFeedbackMergeState,
+    file_brushes: Vec<WatchedFileBrush>,
+    last_file_brush_poll_at: Option<Instant>,
+    /// Stroke the UI has open; the engine may not have applied it yet.
+    active_stroke_node: Option<NodeId>,
+    current_brush_id: Option<BrushId>,
+    current_brush_paint: BrushPaint,
+    /// Main-thread stages of the latest frame that submitted commands.
+    last_submit_perf: EngineFramePerf,
+    perf_trace: PerfTraceConfig,
+    perf_frame_seq: u64,
+    document_layout: ImageLayout,
+}
+
+/// Where the engine half of [`AppThreadIntegration`] runs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum EngineThreadMode {
+    /// Engine frames run inside [`AppThreadIntegration::process_engine_frame`].
+    #[default]
+    Inline,
+    /// A dedicated thread owns the engine state and strokes input as it
+    /// arrives; the main thread only submits the GPU commands it sends back.
+    Dedicated,
+}
+
+/// What the UI reads from the engine, published after frames that change it.
+#[derive(Debug, Clone, PartialEq)]
+struct EngineSnapshot {
+    layer_tree: Vec<UiLayerTreeItem>,
+    selected_node: Option<NodeId>,
+    active_paint_node: Option<NodeId>,
+    stats: AppStats,
+}
+
+impl EngineSnapshot {
+    fn find_node(&self, node_id: NodeId) -> Option<&UiLayerTreeItem> {
+        fn find(items: &[UiLayerTreeItem], node_id: NodeId) -> Option<&UiLayerTreeItem> {
+            items.iter().find_map(|item| {
+                if item.id == node_id {
+                    Some(item)
+                } else {
+                    find(&item.children, node_id)
+                }
+            })
+        }
+        find(&self.layer_tree, node_id)
+    }
+
+    fn contains_node(&self, node_id: NodeId) -> bool {
+        self.find_node(node_id).is_some()
+    }
+
+    /// Every node but the root, as [`Document::can_select_node`] allows.
+    fn can_select_node(&self, node_id: NodeId) -> bool {
+        self.layer_tree
+            .first()
+            .is_some_and(|root| root.id != node_id)
+            && self.contains_node(node_id)
+    }
+}
+
+/// Colors a stroke takes from the brush settings when it begins.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct BrushPaint {
+    color_rgb: [f32; 3],
+    background_rgb: [f32; 3],
+    erase: bool,
+}
+
+impl Default for BrushPaint {
+    fn default() -> Self {
+        Self {
+            color_rgb: [1.0, 0.0, 0.0],
+            background_rgb: [1.0, 1.0, 1.0],
+            erase: false,
+        }
+    }
+}
+
+/// Main-to-engine messages that are not part of the traced control stream.
+enum EngineRequest {
+    SelectBrush(BrushId),
+    SetBrushPaint(BrushPaint),
+    ReplayFrame(Box<TraceInputFrame>),
+    UndoStroke(mpsc::Sender<bool>),
+    RedoStroke(mpsc::Sender<bool>),
+    UpdateBrush {
+        brush_id: BrushId,
+        max_affected_radius_px: u32,
+        resampler_distance: BrushResamplerDistance,
+        pipeline: Box<dyn EngineBrushPipeline>,
+        reply: mpsc::Sender<Result<(), BrushRegistryError>>,
+    },
+    SetColorDynamics {
+        brush_id: BrushId,
+        dynamics: ColorDynamics,
+        reply: mpsc::Sender<Result<(), BrushRegistryError>>,
+    },
+    /// Lends the worker to the main thread until the next dedicated frame.
+    Park,
+}
+
+/// The engine half of the app: the document and everything a frame does to
+/// it, owned by whichever thread runs engine frames.
+struct EngineWorker {
+    engine_state: EngineThreadState,
+    channels: AppEngineThreadChannels,
     input_controls: Vec<InputControlEvent<AppControl>>,
     input_samples: Vec<InputRingSample>,
     brush_inputs: Vec<glaphica_core::BrushInput>,
     gpu_commands: Vec<thread_protocol::GpuCmdMsg>,
     pending_send_gpu_commands: VecDeque<thread_protocol::GpuCmdMsg>,
-    feedback_merge_state: AppGpuFeedbackMergeState,
+    /// Commands sent this frame, kept only while recording a trace.
+    sent_gpu_commands: Vec<thread_protocol::GpuCmdMsg>,
     trace_recorder: Option<TraceRecorder>,
+    recovery_journal: Option<RecoveryJournal>,
+    last_editing_activity_at: Option<Instant>,
     active_stroke_node: Option<NodeId>,
     current_brush_id: Option<BrushId>,
-    current_brush_color_rgb: [f32; 3],
-    current_brush_erase: bool,
-    active_stroke_color_rgb: [f32; 3],
-    active_stroke_erase: bool,
+    current_brush_paint: BrushPaint,
+    active_stroke_paint: BrushPaint,
+    /// Last input of the active stroke, re-sent as the stroke-end input.
+    last_stroke_input: Option<glaphica_core::BrushInput>,
     brush_resampler_distances: Vec<Option<BrushResamplerDistance>>,
     next_stroke_id: u64,
+    input_batch: InputBatchController,
+    last_frame_perf: EngineFramePerf,
+    snapshots: mpsc::Sender<EngineSnapshot>,
+    last_snapshot_at: Option<Instant>,
+    has_new_output: bool,
+    waker: Option<Arc<dyn Fn() + Send + Sync>>,
     perf_trace: PerfTraceConfig,
     perf_frame_seq: u64,
-    document_layout: ImageLayout,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct AppStats {
     pub backend_tiles: Vec<atlas::BackendTileStats>,
     pub undo_stroke_count: usize,
+    pub input: InputStats,
+    /// Latest engine frame that stroked input.
+    pub engine_frame: EngineFramePerf,
+    /// GPU commands the engine produced that the main thread has not submitted.
+    pub gpu_command_queue_depth: usize,
+}
+
+/// Input ring counters and the engine's current drain batch.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct InputStats {
+    pub pushed_samples: u64,
+    /// Samples overwritten in the ring before the engine drained them.
+    pub dropped_samples: u64,
+    pub pending_samples: usize,
+    pub batch_size: usize,
+    /// Whether the last drain left a backlog or found overwritten samples.
+    pub behind: bool,
 }
 
 impl AppThreadIntegration {
@@ -561,28 +893,27 @@ impl AppThreadIntegration {
             config::thread_channels::MAIN_TO_ENGINE_FEEDBACK,
         );
 
+        let (snapshots, engine_snapshots) = mpsc::channel();
+        let worker = EngineWorker::new(engine_state, engine_channels, snapshots);
+
         Ok(Self {
             main_state,
-            engine_state,
             main_channels,
-            engine_channels,
-            input_controls: Vec::with_capacity(config::batch_capacities::INPUT_SAMPLES),
-            input_samples: Vec::with_capacity(config::batch_capacities::INPUT_SAMPLES),
-            brush_inputs: Vec::with_capacity(config::batch_capacities::BRUSH_INPUTS),
+            engine_snapshot: worker.snapshot(),
+            engine_snapshots,
+            engine: EngineHost {
+                mode: EngineThreadMode::default(),
+                worker: Some(Box::new(worker)),
+                thread: None,
+            },
             gpu_commands: Vec::with_capacity(config::batch_capacities::GPU_COMMANDS),
-            pending_send_gpu_commands: VecDeque::with_capacity(
-                config::batch_capacities::GPU_COMMANDS,
-            ),
             feedback_merge_state: AppGpuFeedbackMergeState::default(),
-            trace_recorder: None,
+            file_brushes: Vec::new(),
+            last_file_brush_poll_at: None,
             active_stroke_node: None,
             current_brush_id: None,
-            current_brush_color_rgb: [1.0, 0.0, 0.0],
-            current_brush_erase: false,
-            active_stroke_color_rgb: [1.0, 0.0, 0.0],
-            active_stroke_erase: false,
-            brush_resampler_distances: vec![None; config::brush_processing::MAX_BRUSHES],
-            next_stroke_id: 1,
+            current_brush_paint: BrushPaint::default(),
+            last_submit_perf: EngineFramePerf::default(),
             perf_trace: PerfTraceConfig::from_env(),
             perf_frame_seq: 0,
             document_layout: layout,
@@ -593,6 +924,17 @@ impl AppThreadIntegration {
         (self.document_layout.size_x(), self.document_layout.size_y())
     }
 
+    pub fn document_metadata(&mut self) -> &Metadata {
+        self.park_engine().engine_state.document().metadata()
+    }
+
+    pub fn document_metadata_mut(&mut self) -> &mut Metadata {
+        self.park_engine()
+            .engine_state
+            .document_mut()
+            .metadata_mut()
+    }
+
     pub fn main_state(&self) -> &MainThreadState {
         &self.main_state
     }
@@ -601,12 +943,29 @@ impl AppThreadIntegration {
         &mut self.main_state
     }
 
-    pub fn engine_state(&self) -> &EngineThreadState {
-        &self.engine_state
+    /// Engine state for direct edits; the engine thread stays parked until
+    /// the next [`Self::process_engine_frame`].
+    pub fn engine_state_mut(&mut self) -> &mut EngineThreadState {
+        &mut self.park_engine().engine_state
     }
 
-    pub fn engine_state_mut(&mut self) -> &mut EngineThreadState {
-        &mut self.engine_state
+    pub fn engine_thread_mode(&self) -> EngineThreadMode {
+        self.engine.mode
+    }
+
+    /// Leaving dedicated mode parks the engine thread right away; entering it
+    /// starts or resumes the thread on the next [`Self::process_engine_frame`].
+    pub fn set_engine_thread_mode(&mut self, mode: EngineThreadMode) {
+        if mode == EngineThreadMode::Inline {
+            self.park_engine();
+        }
+        self.engine.mode = mode;
+    }
+
+    /// Called from the engine thread after it sends GPU commands or a new
+    /// snapshot, so an idle event loop knows to run another frame.
+    pub fn set_engine_waker(&mut self, waker: impl Fn() + Send + Sync + 'static) {
+        self.park_engine().waker = Some(Arc::new(waker));
     }
 
     pub fn push_input_sample(&self, sample: InputRingSample) {
@@ -646,20 +1005,31 @@ impl AppThreadIntegration {
             .input_control_queue
             .blocking_push(InputControlEvent::Control(control));
         self.active_stroke_node = Some(node_id);
-        self.active_stroke_color_rgb = self.current_brush_color_rgb;
-        self.active_stroke_erase = self.current_brush_erase;
+        self.main_state.begin_preview_stroke(node_id);
+    }
+
+    /// Draws `shape` as one complete stroke with the current brush.
+    pub fn draw_shape_stroke(&mut self, node_id: NodeId, shape: StrokeShape, taper: ShapeTaper) {
+        self.begin_stroke(node_id);
+        self.main_channels
+            .input_control_queue
+            .blocking_push(InputControlEvent::Control(AppControl::ShapeStroke {
+                shape,
+                taper,
+            }));
+        self.end_stroke();
     }
 
     pub fn active_document_node(&self) -> Option<NodeId> {
-        self.engine_state.document().selected_node()
+        self.engine_snapshot.selected_node
     }
 
     pub fn active_paint_node(&self) -> Option<NodeId> {
-        self.engine_state.document().active_paint_node()
+        self.engine_snapshot.active_paint_node
     }
 
     pub fn layer_tree_items(&self) -> Vec<UiLayerTreeItem> {
-        self.engine_state.document().layer_tree_items()
+        self.engine_snapshot.layer_tree.clone()
     }
 
     pub fn take_layer_preview_updates(&mut self) -> Vec<LayerPreviewBitmap> {
@@ -667,7 +1037,7 @@ impl AppThreadIntegration {
     }
 
     pub fn select_document_node(&mut self, node_id: NodeId) -> bool {
-        if !self.engine_state.document().can_select_node(node_id) {
+        if !self.engine_snapshot.can_select_node(node_id) {
             return false;
         }
         self.main_channels
@@ -682,7 +1052,7 @@ impl AppThreadIntegration {
         &mut self,
         kind: NewLayerKind,
     ) -> Result<(), document::LayerEditError> {
-        if self.engine_state.document().selected_node().is_none() {
+        if self.engine_snapshot.selected_node.is_none() {
             return Err(document::LayerEditError::NoActiveNode);
         }
         self.main_channels
@@ -694,7 +1064,7 @@ impl AppThreadIntegration {
     }
 
     pub fn create_group_above_active(&mut self) -> Result<(), document::LayerEditError> {
-        if self.engine_state.document().selected_node().is_none() {
+        if self.engine_snapshot.selected_node.is_none() {
             return Err(document::LayerEditError::NoActiveNode);
         }
         self.main_channels
@@ -710,12 +1080,7 @@ impl AppThreadIntegration {
         node_id: NodeId,
         target: LayerMoveTarget,
     ) -> Result<(), document::LayerEditError> {
-        if !self
-            .engine_state
-            .document()
-            .layer_tree()
-            .contains_node(node_id)
-        {
+        if !self.engine_snapshot.contains_node(node_id) {
             return Err(document::LayerEditError::InvalidNode);
         }
         self.main_channels
@@ -732,12 +1097,7 @@ impl AppThreadIntegration {
         node_id: NodeId,
         visible: bool,
     ) -> Result<(), document::LayerEditError> {
-        if !self
-            .engine_state
-            .document()
-            .layer_tree()
-            .contains_node(node_id)
-        {
+        if !self.engine_snapshot.contains_node(node_id) {
             return Err(document::LayerEditError::InvalidNode);
         }
         self.main_channels
@@ -754,12 +1114,7 @@ impl AppThreadIntegration {
         node_id: NodeId,
         opacity: f32,
     ) -> Result<(), document::LayerEditError> {
-        if !self
-            .engine_state
-            .document()
-            .layer_tree()
-            .contains_node(node_id)
-        {
+        if !self.engine_snapshot.contains_node(node_id) {
             return Err(document::LayerEditError::InvalidNode);
         }
         self.main_channels
@@ -776,12 +1131,7 @@ impl AppThreadIntegration {
         node_id: NodeId,
         blend_mode: UiBlendMode,
     ) -> Result<(), document::LayerEditError> {
-        if !self
-            .engine_state
-            .document()
-            .layer_tree()
-            .contains_node(node_id)
-        {
+        if !self.engine_snapshot.contains_node(node_id) {
             return Err(document::LayerEditError::InvalidNode);
         }
         self.main_channels
@@ -794,7 +1144,7 @@ impl AppThreadIntegration {
     }
 
     pub fn move_active_node_up(&mut self) -> Result<(), document::LayerEditError> {
-        if self.engine_state.document().selected_node().is_none() {
+        if self.engine_snapshot.selected_node.is_none() {
             return Err(document::LayerEditError::NoActiveNode);
         }
         self.main_channels
@@ -804,7 +1154,7 @@ impl AppThreadIntegration {
     }
 
     pub fn move_active_node_down(&mut self) -> Result<(), document::LayerEditError> {
-        if self.engine_state.document().selected_node().is_none() {
+        if self.engine_snapshot.selected_node.is_none() {
             return Err(document::LayerEditError::NoActiveNode);
         }
         self.main_channels
@@ -813,39 +1163,92 @@ impl AppThreadIntegration {
         Ok(())
     }
 
+    pub fn set_symmetry(&mut self, symmetry: Symmetry) {
+        self.main_channels
+            .input_control_queue
+            .blocking_push(InputControlEvent::Control(AppControl::SetSymmetry {
+                symmetry,
+            }));
+    }
+
+    pub fn set_smoothing(&mut self, smoothing: SmoothingConfig) {
+        self.main_channels
+            .input_control_queue
+            .blocking_push(InputControlEvent::Control(AppControl::SetSmoothing {
+                smoothing,
+            }));
+    }
+
+    pub fn set_resampler(&mut self, kind: ResamplerKind) {
+        self.main_channels
+            .input_control_queue
+            .blocking_push(InputControlEvent::Control(AppControl::SetResampler {
+                kind,
+            }));
+    }
+
+    pub fn set_pressure_ramp(&mut self, taper: StrokeTaper, synthetic_pressure: SyntheticPressure) {
+        self.main_channels
+            .input_control_queue
+            .blocking_push(InputControlEvent::Control(AppControl::SetPressureRamp {
+                taper,
+                synthetic_pressure,
+            }));
+    }
+
+    pub fn set_device_calibration(&mut self, calibration: DeviceCalibrations) {
+        self.main_channels
+            .input_control_queue
+            .blocking_push(InputControlEvent::Control(
+                AppControl::SetDeviceCalibration { calibration },
+            ));
+    }
+
     pub fn set_active_brush(&mut self, brush_id: BrushId) {
         self.current_brush_id = Some(brush_id);
-        let Some(brush_index) = usize::try_from(brush_id.0).ok() else {
-            return;
-        };
-        let Some(Some(distance)) = self.brush_resampler_distances.get(brush_index).copied() else {
-            return;
-        };
-        self.engine_state.set_resampler_distance(distance);
+        self.engine.request(EngineRequest::SelectBrush(brush_id));
     }
 
     pub fn active_brush_id(&self) -> Option<BrushId> {
         self.current_brush_id
     }
 
+    /// Engine stats from the latest snapshot, with the input counters and
+    /// main-thread frame stages read live.
     pub fn stats(&self) -> AppStats {
-        let engine_stats = self.engine_state.stats();
-        AppStats {
-            backend_tiles: engine_stats.backend_tiles,
-            undo_stroke_count: engine_stats.undo_stroke_count,
-        }
+        let mut stats = self.engine_snapshot.stats.clone();
+        let producer = &self.main_channels.input_ring_producer;
+        stats.input.pushed_samples = producer.pushed_samples();
+        stats.input.dropped_samples = producer.dropped_samples();
+        stats.input.pending_samples = producer.pending_samples();
+        stats
+            .engine_frame
+            .merge_submit_stages(&self.last_submit_perf);
+        stats.gpu_command_queue_depth += self.main_channels.gpu_command_receiver.slots();
+        stats
     }
 
     pub fn set_active_brush_color_rgb(&mut self, rgb: [f32; 3]) {
-        self.current_brush_color_rgb = rgb;
+        self.current_brush_paint.color_rgb = rgb;
+        self.engine
+            .request(EngineRequest::SetBrushPaint(self.current_brush_paint));
+    }
+
+    /// Background color that brush color dynamics mix toward.
+    pub fn set_active_brush_background_rgb(&mut self, rgb: [f32; 3]) {
+        self.current_brush_paint.background_rgb = rgb;
+        self.engine
+            .request(EngineRequest::SetBrushPaint(self.current_brush_paint));
     }
 
     pub fn set_active_brush_erase(&mut self, erase: bool) {
-        self.current_brush_erase = erase;
+        self.current_brush_paint.erase = erase;
+        self.engine
+            .request(EngineRequest::SetBrushPaint(self.current_brush_paint));
     }
 
     pub fn end_stroke(&mut self) {
-        if let Some(node_id) = self.active_stroke_node {
+        if let Some(node_id) = self.active_stroke_node.take() {
             let control = AppControl::StrokeBoundary {
                 node_id,
                 begin: false,
@@ -853,392 +1256,184 @@ impl AppThreadIntegration {
             self.main_channels
                 .input_control_queue
                 .blocking_push(InputControlEvent::Control(control));
+            self.main_state.end_preview_stroke(node_id);
         }
-        self.active_stroke_node = None;
+    }
+
+    /// Abandons the open stroke, if any, leaving the document as it was
+    /// before the stroke began. Returns whether a stroke was open.
+    pub fn cancel_stroke(&mut self) -> bool {
+        let Some(node_id) = self.active_stroke_node.take() else {
+            return false;
+        };
+        self.main_channels
+            .input_control_queue
+            .blocking_push(InputControlEvent::Control(AppControl::CancelStroke {
+                node_id,
+            }));
+        self.main_state.end_preview_stroke(node_id);
+        true
     }
 
     pub fn undo_stroke(&mut self) -> bool {
         if self.active_stroke_node.is_some() {
             return false;
         }
-        let Some(update) = self.engine_state.undo_stroke() else {
-            return false;
-        };
-        self.pending_send_gpu_commands
-            .push_back(GpuCmdMsg::TileSlotKeyUpdate(update));
-        true
+        self.engine_round_trip(EngineRequest::UndoStroke)
     }
 
     pub fn redo_stroke(&mut self) -> bool {
         if self.active_stroke_node.is_some() {
             return false;
         }
-        let Some(update) = self.engine_state.redo_stroke() else {
-            return false;
-        };
-        self.pending_send_gpu_commands
-            .push_back(GpuCmdMsg::TileSlotKeyUpdate(update));
-        true
+        self.engine_round_trip(EngineRequest::RedoStroke)
+    }
+
+    /// Sends a request that carries a reply channel and waits for the answer.
+    fn engine_round_trip<T>(
+        &mut self,
+        request: impl FnOnce(mpsc::Sender<T>) -> EngineRequest,
+    ) -> T {
+        let answer = self.engine.round_trip(request);
+        self.sync_engine_snapshot();
+        answer
     }
 
+    /// Runs one engine frame and submits the GPU commands it produced.
+    ///
+    /// In dedicated mode the engine thread strokes input on its own and this
+    /// only submits what it has sent back; `wait_timeout` applies inline.
     pub fn process_engine_frame(&mut self, wait_timeout: std::time::Duration) -> bool {
         let mut perf = EngineFramePerf::default();
-        self.input_controls.clear();
-        while let Ok(event) = self.engine_channels.input_control_queue.pop() {
-            self.input_controls.push(event);
-        }
-        let drained_controls = self.input_controls.clone();
-        for event in &drained_controls {
-            self.apply_input_control_event(event);
-        }
-        self.input_samples.clear();
-        let input_sample_started = Instant::now();
-        self.engine_channels
-            .input_ring_consumer
-            .drain_batch_with_wait(
-                &mut self.input_samples,
-                config::brush_processing::MAX_INPUT_BATCH_SIZE,
-                wait_timeout,
-            );
-        self.normalize_input_sample_timestamps();
-        perf.input_sample = input_sample_started.elapsed();
-        perf.sample_count = self.input_samples.len();
-        if let Some(trace_recorder) = &mut self.trace_recorder {
-            trace_recorder.record_input_frame(&self.input_controls, &self.input_samples);
+        if let Some(worker) = self.engine.frame_worker() {
+            worker.collect_input(wait_timeout, &mut perf);
+            worker.process_input(&mut perf);
         }
-        self.process_engine_frame_from_samples(Some(&mut perf))
+        let has_commands = self.submit_engine_output(&mut perf);
+        self.sync_engine_snapshot();
+        perf.trace(&self.perf_trace, &mut self.perf_frame_seq);
+        has_commands
     }
 
     pub fn process_replay_input_frame(&mut self, input_frame: &TraceInputFrame) -> bool {
         let mut perf = EngineFramePerf::default();
-        let (controls, samples) = input_frame.to_runtime();
-        self.input_controls = controls;
-        self.input_samples = samples;
-        perf.sample_count = self.input_samples.len();
+        self.track_replayed_strokes(input_frame);
+        match self.engine.frame_worker() {
+            Some(worker) => worker.process_replay_frame(input_frame, &mut perf),
+            None => self
+                .engine
+                .request(EngineRequest::ReplayFrame(Box::new(input_frame.clone()))),
+        }
+        let has_commands = self.submit_engine_output(&mut perf);
+        self.sync_engine_snapshot();
+        perf.trace(&self.perf_trace, &mut self.perf_frame_seq);
+        has_commands
+    }
 
-        let replay_controls = self.input_controls.clone();
-        for event in &replay_controls {
-            self.apply_input_control_event(event);
+    /// Mirrors a replayed frame's stroke boundaries on the main thread, as
+    /// [`Self::begin_stroke`] and [`Self::end_stroke`] do for live input.
+    fn track_replayed_strokes(&mut self, input_frame: &TraceInputFrame) {
+        for control in &input_frame.controls {
+            let (node_id, begin) = match *control {
+                TraceAppControl::StrokeBoundary { node_id, begin } => (node_id, begin),
+                TraceAppControl::CancelStroke { node_id } => (node_id, false),
+                _ => continue,
+            };
+            let node_id = NodeId(node_id);
+            if begin {
+                self.active_stroke_node = Some(node_id);
+                self.main_state.begin_preview_stroke(node_id);
+            } else {
+                self.active_stroke_node = None;
+                self.main_state.end_preview_stroke(node_id);
+            }
         }
-        self.process_engine_frame_from_samples(Some(&mut perf))
     }
 
-    fn apply_input_control_event(&mut self, event: &InputControlEvent<AppControl>) {
-        let InputControlEvent::Control(control) = event;
-        match control {
-            AppControl::StrokeBoundary { node_id, begin } => {
-                if *begin {
-                    self.engine_state.invalidate_redo_strokes();
-                    let stroke_id = StrokeId(self.next_stroke_id);
-                    self.next_stroke_id += 1;
-                    self.active_stroke_node = Some(*node_id);
-                    self.main_state.begin_preview_stroke(*node_id);
-                    self.engine_state.begin_stroke(stroke_id);
-                } else {
-                    self.active_stroke_node = None;
-                    self.engine_state.end_stroke();
-                    self.main_state.end_preview_stroke(*node_id);
-                }
-            }
-            AppControl::SelectNode { node_id } => {
-                let _ = self.engine_state.document_mut().set_active_node(*node_id);
-            }
-            AppControl::CreateLayerAboveActive { kind } => {
-                self.engine_state.invalidate_redo_strokes();
-                match self
-                    .engine_state
-                    .document_mut()
-                    .create_layer_above_active(*kind)
-                {
-                    Ok(_) => self.enqueue_render_tree_update(),
-                    Err(error) => eprintln!("create layer control failed: {error:?}"),
-                }
-            }
-            AppControl::CreateGroupAboveActive => {
-                self.engine_state.invalidate_redo_strokes();
-                match self.engine_state.document_mut().create_group_above_active() {
-                    Ok(_) => self.enqueue_render_tree_update(),
-                    Err(error) => eprintln!("create group control failed: {error:?}"),
-                }
-            }
-            AppControl::MoveNode { node_id, target } => {
-                self.engine_state.invalidate_redo_strokes();
-                match self
-                    .engine_state
-                    .document_mut()
-                    .move_node_to(*node_id, *target)
-                {
-                    Ok(()) => self.enqueue_render_tree_update(),
-                    Err(error) => eprintln!("move node control failed: {error:?}"),
-                }
-            }
-            AppControl::SetNodeVisibility { node_id, visible } => {
-                self.engine_state.invalidate_redo_strokes();
-                match self
-                    .engine_state
-                    .document_mut()
-                    .set_node_visibility(*node_id, *visible)
-                {
-                    Ok(_) => self.enqueue_render_tree_update(),
-                    Err(error) => eprintln!("set node visibility control failed: {error:?}"),
-                }
-            }
-            AppControl::SetNodeOpacity { node_id, opacity } => {
-                self.engine_state.invalidate_redo_strokes();
-                match self
-                    .engine_state
-                    .document_mut()
-                    .set_node_opacity(*node_id, *opacity)
-                {
-                    Ok(()) => self.enqueue_render_tree_update(),
-                    Err(error) => eprintln!("set node opacity control failed: {error:?}"),
-                }
-            }
-            AppControl::SetNodeBlendMode {
-                node_id,
-                blend_mode,
-            } => {
-                self.engine_state.invalidate_redo_strokes();
-                match self
-                    .engine_state
-                    .document_mut()
-                    .set_node_blend_mode(*node_id, *blend_mode)
-                {
-                    Ok(()) => self.enqueue_render_tree_update(),
-                    Err(error) => eprintln!("set node blend mode control failed: {error:?}"),
-                }
-            }
-            AppControl::MoveActiveNodeUp => {
-                self.engine_state.invalidate_redo_strokes();
-                match self.engine_state.document_mut().move_active_node_up() {
-                    Ok(()) => self.enqueue_render_tree_update(),
-                    Err(error) => eprintln!("move layer up control failed: {error:?}"),
-                }
-            }
-            AppControl::MoveActiveNodeDown => {
-                self.engine_state.invalidate_redo_strokes();
-                match self.engine_state.document_mut().move_active_node_down() {
-                    Ok(()) => self.enqueue_render_tree_update(),
-                    Err(error) => eprintln!("move layer down control failed: {error:?}"),
-                }
-            }
-        }
-    }
-
-    fn enqueue_render_tree_update(&mut self) {
-        match self.engine_state.rebuild_render_tree() {
-            Ok(msg) => self
-                .pending_send_gpu_commands
-                .push_back(thread_protocol::GpuCmdMsg::RenderTreeUpdated(msg)),
-            Err(error) => eprintln!("render tree rebuild failed after control event: {error}"),
-        }
-    }
-
-    fn process_engine_frame_from_samples(
-        &mut self,
-        mut perf: Option<&mut EngineFramePerf>,
-    ) -> bool {
-        let mut generated_gpu_command_count = 0usize;
-        if !self.input_samples.is_empty()
-            && let Some(node_id) = self.active_stroke_node
-        {
-            if let Some(brush_id) = self.current_brush_id {
-                self.brush_inputs.clear();
-                self.gpu_commands.clear();
-
-                let smooth_and_resample_started = Instant::now();
-                for sample in &self.input_samples {
-                    let new_inputs = self
-                        .engine_state
-                        .process_raw_input(sample.cursor, sample.time_ns);
-                    self.brush_inputs.extend(new_inputs);
-                }
-                if let Some(perf) = perf.as_deref_mut() {
-                    perf.smooth_and_resample = smooth_and_resample_started.elapsed();
-                    perf.brush_input_count = self.brush_inputs.len();
-                }
-
-                let brush_inputs = self.brush_inputs.clone();
-                let stroke_rgb = self.active_stroke_color_rgb;
-                let stroke_erase = self.active_stroke_erase;
-                let brush_handling_started = Instant::now();
-                for brush_input in &brush_inputs {
-                    match self.engine_state.process_stroke_input(
-                        brush_id,
-                        brush_input,
-                        stroke_rgb,
-                        stroke_erase,
-                        node_id,
-                        None,
-                    ) {
-                        Ok(cmds) => {
-                            self.gpu_commands.extend(cmds);
-                        }
-                        Err(e) => {
-                            eprintln!("Stroke processing failed: {e:?}");
-                        }
-                    }
-                }
-                if let Some(perf) = perf.as_deref_mut() {
-                    perf.brush_handling = brush_handling_started.elapsed();
-                }
-
-                Self::compact_frame_mergeable_draws(&mut self.gpu_commands);
-                Self::compact_frame_mergeable_copy_write(&mut self.gpu_commands);
-                Self::move_setup_ops_before_draws(&mut self.gpu_commands);
-                Self::move_mergeable_writes_to_end(&mut self.gpu_commands);
-                Self::move_metadata_updates_to_end(&mut self.gpu_commands);
-
-                let pending_gpu_cmds = std::mem::take(&mut self.gpu_commands);
-                generated_gpu_command_count = pending_gpu_cmds.len();
-                self.pending_send_gpu_commands.extend(pending_gpu_cmds);
-            }
-        }
-
-        let send_to_app_thread_started = Instant::now();
-        while self.engine_channels.gpu_command_sender.slots() > 0 {
-            let Some(cmd) = self.pending_send_gpu_commands.front().cloned() else {
-                break;
-            };
-            if let Err(e) = self.engine_channels.gpu_command_sender.push(cmd) {
-                eprintln!("GPU command send failed: {e:?}");
-                break;
-            }
-            self.pending_send_gpu_commands.pop_front();
-        }
-        if let Some(perf) = perf.as_deref_mut() {
-            perf.send_to_app_thread = send_to_app_thread_started.elapsed();
-            perf.send_inline_submit = Duration::ZERO;
-            perf.generated_gpu_command_count = generated_gpu_command_count;
-            perf.inline_submitted_gpu_command_count = 0;
-            perf.inline_submit_batches = 0;
-            perf.pending_send_gpu_command_count = self.pending_send_gpu_commands.len();
-        }
-
+    /// Accepts the GPU commands the engine sent and submits them.
+    fn submit_engine_output(&mut self, perf: &mut EngineFramePerf) -> bool {
         self.gpu_commands.clear();
         let accept_by_app_thread_started = Instant::now();
         while let Ok(cmd) = self.main_channels.gpu_command_receiver.pop() {
             self.gpu_commands.push(cmd);
         }
-        if let Some(perf) = perf.as_deref_mut() {
-            perf.accept_by_app_thread = accept_by_app_thread_started.elapsed();
-            perf.gpu_command_count = self.gpu_commands.len();
-        }
-
-        if let Some(trace_recorder) = &mut self.trace_recorder {
-            trace_recorder.record_output_frame(&self.gpu_commands);
-        }
+        perf.accept_by_app_thread = accept_by_app_thread_started.elapsed();
+        perf.gpu_command_count = self.gpu_commands.len();
 
         let has_commands = !self.gpu_commands.is_empty();
         if has_commands {
             let submit_to_gpu_started = Instant::now();
             let submit_stats = self.main_state.process_gpu_commands(&self.gpu_commands);
-            if let Some(perf) = perf.as_deref_mut() {
-                perf.submit_to_gpu = submit_to_gpu_started.elapsed();
-                perf.submit_collect_render_tree = submit_stats.frame_batch.render_tree_collect;
-                perf.submit_materialize_parametric =
-                    submit_stats.frame_batch.parametric_materialize;
-                perf.submit_composite_render_tree = submit_stats.frame_batch.render_tree_composite;
-                perf.submit_queue = submit_stats.frame_batch.queue_submit;
-                perf.submit_parametric_cmd_count = submit_stats.frame_batch.parametric_cmd_count;
-                perf.submit_parametric_tile_count =
-                    submit_stats.frame_batch.parametric_dst_tile_count;
-                perf.submit_render_cmd_count = submit_stats.frame_batch.render_cmd_count;
-                perf.submit_render_tile_count = submit_stats.frame_batch.render_dst_tile_count;
-                perf.submit_render_source_count = submit_stats.frame_batch.render_source_count;
-                perf.submit_dirty_tile_count = submit_stats.dirty_tile_count;
-                perf.submit_dirty_rect_count = submit_stats.dirty_rect_count;
-                perf.submit_dirty_bbox_tile_area = submit_stats.dirty_bbox_tile_area;
-                perf.submit_dirty_node_count = submit_stats.dirty_node_count;
-            }
+            perf.submit_to_gpu = submit_to_gpu_started.elapsed();
+            perf.submit_collect_render_tree = submit_stats.frame_batch.render_tree_collect;
+            perf.submit_materialize_parametric = submit_stats.frame_batch.parametric_materialize;
+            perf.submit_composite_render_tree = submit_stats.frame_batch.render_tree_composite;
+            perf.submit_queue = submit_stats.frame_batch.queue_submit;
+            perf.submit_parametric_cmd_count = submit_stats.frame_batch.parametric_cmd_count;
+            perf.submit_parametric_tile_count = submit_stats.frame_batch.parametric_dst_tile_count;
+            perf.submit_render_cmd_count = submit_stats.frame_batch.render_cmd_count;
+            perf.submit_render_tile_count = submit_stats.frame_batch.render_dst_tile_count;
+            perf.submit_render_source_count = submit_stats.frame_batch.render_source_count;
+            perf.submit_dirty_tile_count = submit_stats.dirty_tile_count;
+            perf.submit_dirty_rect_count = submit_stats.dirty_rect_count;
+            perf.submit_dirty_bbox_tile_area = submit_stats.dirty_bbox_tile_area;
+            perf.submit_dirty_node_count = submit_stats.dirty_node_count;
+            self.last_submit_perf = perf.clone();
         }
-
-        if let Some(perf) = perf {
-            self.trace_engine_frame_perf(perf);
-        }
-
         has_commands
     }
 
-    fn trace_engine_frame_perf(&mut self, perf: &EngineFramePerf) {
-        if !self.perf_trace.enabled {
-            return;
-        }
-        let stages = [
-            ("input_sample", perf.input_sample),
-            ("smooth_and_resample", perf.smooth_and_resample),
-            ("brush_handling", perf.brush_handling),
-            ("send_to_app_thread", perf.send_to_app_thread),
-            ("accept_by_app_thread", perf.accept_by_app_thread),
-            ("submit_to_gpu", perf.submit_to_gpu),
-        ];
-        let total = stages
-            .iter()
-            .map(|(_, duration)| *duration)
-            .fold(Duration::ZERO, |acc, item| acc + item);
-        if total < self.perf_trace.slow_threshold {
-            return;
+    /// Borrows the worker back from the engine thread if it runs and submits
+    /// what it already sent, so the caller can use the engine state directly.
+    /// The thread resumes on the next [`Self::process_engine_frame`].
+    fn park_engine(&mut self) -> &mut EngineWorker {
+        if self.engine.is_running() {
+            self.engine.worker();
+            self.submit_engine_output(&mut EngineFramePerf::default());
         }
-        let Some((bottleneck, bottleneck_duration)) =
-            stages.iter().max_by_key(|(_, duration)| *duration)
-        else {
-            return;
-        };
-        self.perf_frame_seq += 1;
-        eprintln!(
-            "[PERF][pipeline][engine_frame={}] total_ms={:.3} bottleneck={} ({:.3}ms) samples={} brush_inputs={} gpu_cmds={} generated_gpu_cmds={} pending_send_gpu_cmds={} inline_submit_cmds={} inline_submit_batches={} inline_submit_ms={:.3} stages_ms={{input:{:.3}, smooth_resample:{:.3}, brush:{:.3}, send:{:.3}, accept:{:.3}, submit:{:.3}}} submit_ms={{collect:{:.3}, materialize:{:.3}, composite:{:.3}, queue:{:.3}}} dirty={{tiles:{}, rects:{}, bbox_tiles:{}, nodes:{}}} render_work={{parametric_cmds:{}, parametric_tiles:{}, render_cmds:{}, render_tiles:{}, render_sources:{}}}",
-            self.perf_frame_seq,
-            duration_ms(total),
-            bottleneck,
-            duration_ms(*bottleneck_duration),
-            perf.sample_count,
-            perf.brush_input_count,
-            perf.gpu_command_count,
-            perf.generated_gpu_command_count,
-            perf.pending_send_gpu_command_count,
-            perf.inline_submitted_gpu_command_count,
-            perf.inline_submit_batches,
-            duration_ms(perf.send_inline_submit),
-            duration_ms(perf.input_sample),
-            duration_ms(perf.smooth_and_resample),
-            duration_ms(perf.brush_handling),
-            duration_ms(perf.send_to_app_thread),
-            duration_ms(perf.accept_by_app_thread),
-            duration_ms(perf.submit_to_gpu),
-            duration_ms(perf.submit_collect_render_tree),
-            duration_ms(perf.submit_materialize_parametric),
-            duration_ms(perf.submit_composite_render_tree),
-            duration_ms(perf.submit_queue),
-            perf.submit_dirty_tile_count,
-            perf.submit_dirty_rect_count,
-            perf.submit_dirty_bbox_tile_area,
-            perf.submit_dirty_node_count,
-            perf.submit_parametric_cmd_count,
-            perf.submit_parametric_tile_count,
-            perf.submit_render_cmd_count,
-            perf.submit_render_tile_count,
-            perf.submit_render_source_count,
-        );
+        self.engine.worker()
     }
 
-    fn normalize_input_sample_timestamps(&mut self) {
-        for sample in &mut self.input_samples {
-            if sample.time_ns == 0 {
-                sample.time_ns = current_time_ns();
+    /// Parks the engine and submits every GPU command it has queued, so
+    /// screenshots and exports include all input processed so far.
+    pub fn flush_engine_output(&mut self) {
+        self.park_engine();
+        loop {
+            let worker = self.engine.worker();
+            worker.send_pending_gpu_commands(&mut EngineFramePerf::default());
+            let pending = worker.pending_send_gpu_commands.len();
+            let submitted = self.submit_engine_output(&mut EngineFramePerf::default());
+            if pending == 0 && !submitted {
+                break;
             }
         }
+        self.engine.worker().record_trace_output();
+        self.refresh_engine_snapshot();
+    }
+
+    /// Takes the newest snapshot the engine published, if any.
+    fn sync_engine_snapshot(&mut self) {
+        while let Ok(snapshot) = self.engine_snapshots.try_recv() {
+            self.engine_snapshot = snapshot;
+        }
+    }
+
+    /// Reads a fresh snapshot from the parked engine after direct edits.
+    fn refresh_engine_snapshot(&mut self) {
+        self.sync_engine_snapshot();
+        self.engine_snapshot = self.park_engine().snapshot();
     }
 
     pub fn enable_trace_recording(&mut self) {
-        self.trace_recorder = Some(TraceRecorder::default());
+        self.park_engine().trace_recorder = Some(TraceRecorder::default());
     }
 
     pub fn save_trace_files(
-        &self,
+        &mut self,
         input_path: Option<&std::path::Path>,
         output_path: Option<&std::path::Path>,
     ) -> Result<(), TraceIoError> {
-        match &self.trace_recorder {
+        match &self.park_engine().trace_recorder {
             Some(trace_recorder) => {
                 if let Some(input_path) = input_path {
                     trace_recorder.save_input_file(input_path)?;
@@ -1323,15 +1518,165 @@ impl AppThreadIntegration {
         self.main_state.save_screenshot(output_path, width, height)
     }
 
-    pub fn export_document_jpeg(&mut self, output_path: &Path) -> Result<(), ExportImageError> {
-        self.main_state.export_jpeg_image(output_path)
+    pub fn export_document_image(
+        &mut self,
+        output_path: &Path,
+        options: &ExportOptions,
+    ) -> Result<(), ExportImageError> {
+        self.main_state.export_image(output_path, options)
+    }
+
+    /// Writes one image per leaf or top-level group into `output_dir`.
+    ///
+    /// Leaves are read straight from their raster image. Groups are rendered
+    /// solo by hiding their top-level siblings for the duration of the export.
+    pub fn export_layer_batch(
+        &mut self,
+        output_dir: &Path,
+        options: &LayerBatchExportOptions,
+    ) -> Result<LayerBatchExportReport, LayerBatchExportError> {
+        let mut writer = LayerBatchWriter::new(output_dir, options, self.document_layout)?;
+        self.park_engine();
+        let Some(root_item) = self.engine_snapshot.layer_tree.first().cloned() else {
+            return Ok(writer.finish());
+        };
+        let targets = layer_batch_export::collect_batch_targets(
+            &root_item,
+            options.scope,
+            options.include_hidden,
+        );
+
+        match options.scope {
+            LayerBatchScope::Leaves => {
+                for target in &targets {
+                    let Some(image) = self
+                        .engine
+                        .worker()
+                        .engine_state
+                        .document()
+                        .get_leaf_image(target.node_id)
+                    else {
+                        continue;
+                    };
+                    let tile_bounds = image.non_empty_tile_bounds();
+                    let stored = self.main_state.export_layer_image(image).map_err(|error| {
+                        LayerBatchExportError::Export {
+                            node_id: target.node_id,
+                            error: error.into(),
+                        }
+                    })?;
+                    writer.write(target, &stored, tile_bounds)?;
+                }
+            }
+            LayerBatchScope::TopLevelGroups => {
+                let sibling_visibility: Vec<(NodeId, bool)> = root_item
+                    .children
+                    .iter()
+                    .map(|child| (child.id, child.visible))
+                    .collect();
+                let result =
+                    self.export_solo_groups(&mut writer, &root_item, &targets, &sibling_visibility);
+                for (node_id, visible) in &sibling_visibility {
+                    let _ = self
+                        .engine
+                        .worker()
+                        .engine_state
+                        .document_mut()
+                        .set_node_visibility(*node_id, *visible);
+                }
+                self.rerender_all_render_caches()
+                    .map_err(LayerBatchExportError::RenderTree)?;
+                result?;
+            }
+        }
+        Ok(writer.finish())
+    }
+
+    fn export_solo_groups(
+        &mut self,
+        writer: &mut LayerBatchWriter<'_>,
+        root_item: &document::UiLayerTreeItem,
+        targets: &[LayerBatchTarget],
+        sibling_visibility: &[(NodeId, bool)],
+    ) -> Result<(), LayerBatchExportError> {
+        for target in targets {
+            for (node_id, _) in sibling_visibility {
+                let _ = self
+                    .engine
+                    .worker()
+                    .engine_state
+                    .document_mut()
+                    .set_node_visibility(*node_id, *node_id == target.node_id);
+            }
+            self.rerender_all_render_caches()
+                .map_err(LayerBatchExportError::RenderTree)?;
+            let stored = self.main_state.read_node_image(None).map_err(|error| {
+                LayerBatchExportError::Export {
+                    node_id: target.node_id,
+                    error,
+                }
+            })?;
+            let tile_bounds = root_item
+                .children
+                .iter()
+                .find(|child| child.id == target.node_id)
+                .and_then(|group| {
+                    Self::group_tile_bounds(self.engine.worker().engine_state.document(), group)
+                });
+            writer.write(target, &stored, tile_bounds)?;
+        }
+        Ok(())
+    }
+
+    /// Union of descendant raster bounds; special layers fill the canvas.
+    fn group_tile_bounds(
+        document: &Document,
+        group: &document::UiLayerTreeItem,
+    ) -> Option<images::NonEmptyTileBounds> {
+        let mut bounds = None;
+        for child in &group.children {
+            if !child.visible {
+                continue;
+            }
+            let child_bounds = match child.kind {
+                document::UiNodeKind::Branch => Self::group_tile_bounds(document, child),
+                document::UiNodeKind::RasterLayer => document
+                    .get_leaf_image(child.id)
+                    .and_then(|image| image.non_empty_tile_bounds()),
+                document::UiNodeKind::SpecialLayer => {
+                    let layout = document.layout();
+                    Some(images::NonEmptyTileBounds {
+                        min_tile_x: 0,
+                        min_tile_y: 0,
+                        max_tile_x: layout.tile_x().saturating_sub(1),
+                        max_tile_y: layout.tile_y().saturating_sub(1),
+                    })
+                }
+            };
+            bounds = layer_batch_export::union_tile_bounds(bounds, child_bounds);
+        }
+        bounds
+    }
+
+    fn rerender_all_render_caches(&mut self) -> Result<(), document::ImageCreateError> {
+        let engine_state = &mut self.park_engine().engine_state;
+        let mut msg = engine_state.rebuild_render_tree()?;
+        msg.dirty_render_caches =
+            collect_all_render_cache_node_ids(&engine_state.shared_tree().read());
+        let _ = self
+            .main_state
+            .process_gpu_commands(&[thread_protocol::GpuCmdMsg::RenderTreeUpdated(msg)]);
+        self.main_state.process_render();
+        self.refresh_engine_snapshot();
+        Ok(())
     }
 
     pub fn rebuild_render_tree(&mut self) -> Result<(), document::ImageCreateError> {
-        let msg = self.engine_state.rebuild_render_tree()?;
+        let msg = self.park_engine().engine_state.rebuild_render_tree()?;
         let _ = self
             .main_state
             .process_gpu_commands(&[thread_protocol::GpuCmdMsg::RenderTreeUpdated(msg)]);
+        self.refresh_engine_snapshot();
         Ok(())
     }
 
@@ -1340,12 +1685,15 @@ impl AppThreadIntegration {
         layout: ImageLayout,
     ) -> Result<(), document::ImageCreateError> {
         let msg = self
+            .park_engine()
             .engine_state
             .resize_document_canvas_anchored_top_left(layout)?;
         self.document_layout = layout;
         let _ = self
             .main_state
             .process_gpu_commands(&[thread_protocol::GpuCmdMsg::RenderTreeUpdated(msg)]);
+        self.refresh_engine_snapshot();
+        self.checkpoint_recovery_after_reset();
         Ok(())
     }
 
@@ -1353,6 +1701,7 @@ impl AppThreadIntegration {
         &mut self,
         package_dir: &Path,
     ) -> Result<(), DocumentPackageError> {
+        self.mark_document_saved();
         let package = self.build_packed_document_file()?;
         std::fs::create_dir_all(package_dir)?;
         std::fs::create_dir_all(package_dir.join("layers"))?;
@@ -1360,13 +1709,34 @@ impl AppThreadIntegration {
         for layer in &package.layers {
             save_png_bytes(&package_dir.join(&layer.file_name), &layer.png_bytes)?;
         }
+        let thumbnail_path = package_dir.join(PACKAGE_THUMBNAIL_FILE);
+        match &package.thumbnail_png {
+            Some(png_bytes) => save_png_bytes(&thumbnail_path, png_bytes)?,
+            None if thumbnail_path.exists() => std::fs::remove_file(&thumbnail_path)?,
+            None => {}
+        }
 
-        let manifest_file = File::create(package_dir.join("manifest.json"))?;
+        let manifest_file = File::create(package_dir.join(PACKAGE_MANIFEST_FILE))?;
         serde_json::to_writer_pretty(BufWriter::new(manifest_file), &package.manifest)?;
         Ok(())
     }
 
     pub fn save_document_bundle(&mut self, bundle_path: &Path) -> Result<(), DocumentPackageError> {
+        self.mark_document_saved();
+        self.write_document_bundle(bundle_path)
+    }
+
+    fn mark_document_saved(&mut self) {
+        self.park_engine()
+            .engine_state
+            .document_mut()
+            .metadata_mut()
+            .mark_saved(current_time_ms());
+    }
+
+    /// Writes the bundle without touching the saved timestamps, which
+    /// recovery checkpoints rely on.
+    fn write_document_bundle(&mut self, bundle_path: &Path) -> Result<(), DocumentPackageError> {
         if let Some(parent) = bundle_path.parent()
             && !parent.as_os_str().is_empty()
         {
@@ -1385,7 +1755,7 @@ impl AppThreadIntegration {
         &mut self,
         package_dir: &Path,
     ) -> Result<(), DocumentPackageError> {
-        let manifest_file = File::open(package_dir.join("manifest.json"))?;
+        let manifest_file = File::open(package_dir.join(PACKAGE_MANIFEST_FILE))?;
         let manifest: DocumentStorageManifest =
             serde_json::from_reader(BufReader::new(manifest_file))?;
         let raster_requests = collect_manifest_raster_assets(&manifest.root);
@@ -1397,7 +1767,13 @@ impl AppThreadIntegration {
                 png_bytes: std::fs::read(package_dir.join(file_name))?,
             });
         }
-        self.load_packed_document_file(PackedDocumentFile { manifest, layers })
+        self.load_packed_document_file(PackedDocumentFile {
+            manifest,
+            thumbnail_png: None,
+            layers,
+        })?;
+        self.checkpoint_recovery_after_reset();
+        Ok(())
     }
 
     pub fn load_document_bundle(&mut self, bundle_path: &Path) -> Result<(), DocumentPackageError> {
@@ -1405,15 +1781,139 @@ impl AppThreadIntegration {
         let reader = BufReader::new(file);
         let decoder = GzDecoder::new(reader);
         let package: PackedDocumentFile = serde_json::from_reader(decoder)?;
-        self.load_packed_document_file(package)
+        self.load_packed_document_file(package)?;
+        self.checkpoint_recovery_after_reset();
+        Ok(())
+    }
+
+    /// Starts journaling edits into `dir` on top of a fresh checkpoint of the
+    /// current document, replacing whatever a previous session left there.
+    pub fn begin_recovery_session(&mut self, dir: &Path) -> Result<(), RecoveryIoError> {
+        self.park_engine().recovery_journal = None;
+        self.write_recovery_checkpoint(dir)
+    }
+
+    /// Writes a new checkpoint when edits were journaled since the last one.
+    pub fn autosave_recovery_checkpoint(&mut self) -> Result<bool, RecoveryIoError> {
+        if self.active_stroke_node.is_some() {
+            return Ok(false);
+        }
+        let worker = self.park_engine();
+        let Some(journal) = &worker.recovery_journal else {
+            return Ok(false);
+        };
+        if journal.committed_entry_count() == 0 || worker.active_stroke_node.is_some() {
+            return Ok(false);
+        }
+        let dir = journal.dir().to_path_buf();
+        self.write_recovery_checkpoint(&dir)?;
+        Ok(true)
+    }
+
+    /// Stops journaling and removes the recovery files after a clean shutdown.
+    pub fn end_recovery_session(&mut self) -> Result<(), RecoveryIoError> {
+        let Some(journal) = self.park_engine().recovery_journal.take() else {
+            return Ok(());
+        };
+        let dir = journal.dir().to_path_buf();
+        drop(journal);
+        RecoveryJournal::discard(&dir)
+    }
+
+    /// Loads the checkpoint left in `dir` and replays its journal on top.
+    ///
+    /// Returns the number of journal entries replayed. Journaling stays off
+    /// until [`Self::begin_recovery_session`] is called again.
+    pub fn restore_recovery_session(&mut self, dir: &Path) -> Result<usize, RecoveryIoError> {
+        let journal_file = RecoveryJournal::load(dir)?;
+        self.park_engine().recovery_journal = None;
+        self.load_document_bundle(&journal_file.checkpoint_path)?;
+
+        let brush_id = self.current_brush_id;
+        let brush_paint = self.current_brush_paint;
+        for entry in &journal_file.entries {
+            match entry {
+                RecoveryJournalEntry::Brush {
+                    brush_id,
+                    rgb,
+                    erase,
+                } => {
+                    self.set_active_brush(BrushId(*brush_id));
+                    self.set_active_brush_color_rgb(*rgb);
+                    self.set_active_brush_erase(*erase);
+                }
+                RecoveryJournalEntry::Input(input_frame) => {
+                    self.track_replayed_strokes(input_frame);
+                    let mut perf = EngineFramePerf::default();
+                    self.engine
+                        .worker()
+                        .process_replay_frame(input_frame, &mut perf);
+                    self.submit_engine_output(&mut perf);
+                }
+                RecoveryJournalEntry::UndoStroke => {
+                    self.undo_stroke();
+                }
+                RecoveryJournalEntry::RedoStroke => {
+                    self.redo_stroke();
+                }
+            }
+        }
+        if let Some(node_id) = self.active_stroke_node.take() {
+            self.engine.worker().close_stroke(node_id);
+            self.main_state.end_preview_stroke(node_id);
+        }
+        self.flush_engine_output();
+
+        if let Some(brush_id) = brush_id {
+            self.set_active_brush(brush_id);
+        }
+        self.current_brush_paint = brush_paint;
+        self.engine
+            .request(EngineRequest::SetBrushPaint(self.current_brush_paint));
+        Ok(journal_file.entries.len())
+    }
+
+    fn write_recovery_checkpoint(&mut self, dir: &Path) -> Result<(), RecoveryIoError> {
+        let owns_dir = self
+            .park_engine()
+            .recovery_journal
+            .as_ref()
+            .is_some_and(|journal| journal.dir() == dir);
+        let lock = if owns_dir {
+            None
+        } else {
+            Some(RecoverySessionLock::acquire(dir)?)
+        };
+        let checkpoint = RecoveryJournal::next_checkpoint(dir);
+        self.write_document_bundle(&RecoveryJournal::checkpoint_path(dir, checkpoint))?;
+        let worker = self.park_engine();
+        if let Some(lock) = lock {
+            worker.recovery_journal = Some(RecoveryJournal::start(lock, checkpoint)?);
+        } else if let Some(journal) = worker.recovery_journal.as_mut() {
+            journal.rotate(checkpoint)?;
+        }
+        Ok(())
+    }
+
+    fn checkpoint_recovery_after_reset(&mut self) {
+        let Some(journal) = &self.park_engine().recovery_journal else {
+            return;
+        };
+        let dir = journal.dir().to_path_buf();
+        if let Err(error) = self.write_recovery_checkpoint(&dir) {
+            eprintln!("recovery checkpoint failed: {error}");
+        }
     }
 
     fn build_packed_document_file(&mut self) -> Result<PackedDocumentFile, DocumentPackageError> {
-        let manifest = self.engine_state.document().storage_manifest();
-        let requests = self.engine_state.document().raster_layer_export_requests();
+        let document = self.park_engine().engine_state.document();
+        let manifest = document.storage_manifest();
+        let requests = document.raster_layer_export_requests();
         let mut layers = Vec::with_capacity(requests.len());
         for request in requests {
             let image = self
+                .engine
+                .worker()
                 .engine_state
                 .document()
                 .get_leaf_image(request.node_id)
@@ -1424,20 +1924,43 @@ impl AppThreadIntegration {
             layers.push(PackedLayerAsset {
                 node_id: request.node_id.0,
                 file_name: request.file_name,
-                png_bytes: encode_png_rgba8(&stored)?,
+                png_bytes: encode_png(&stored)?,
             });
         }
-        Ok(PackedDocumentFile { manifest, layers })
+        let thumbnail_png = match self.render_thumbnail() {
+            Ok(thumbnail) => Some(encode_png(&thumbnail)?),
+            Err(error) => {
+                eprintln!("document thumbnail render failed: {error}");
+                None
+            }
+        };
+        Ok(PackedDocumentFile {
+            manifest,
+            thumbnail_png,
+            layers,
+        })
+    }
+
+    /// Downscales the root composite to fit the thumbnail edge limit.
+    fn render_thumbnail(&mut self) -> Result<StoredImage, ExportImageError> {
+        self.main_state.process_render();
+        let composite = self.main_state.read_node_image(None)?;
+        let longest_edge = composite.width().max(composite.height()).max(1);
+        let mut options = ExportOptions::new(crate::image_export::ExportFormat::Png);
+        options.scale =
+            (config::document_metadata::THUMBNAIL_MAX_EDGE as f32 / longest_edge as f32).min(1.0);
+        crate::image_export::prepare_export_image(&composite, &options)
     }
 
     fn load_packed_document_file(
         &mut self,
         package: PackedDocumentFile,
     ) -> Result<(), DocumentPackageError> {
+        self.park_engine();
         let manifest = package.manifest;
         let mut raster_images = Vec::with_capacity(package.layers.len());
         for layer in package.layers {
-            let image = decode_png_rgba8(&layer.png_bytes)?;
+            let image = decode_png(&layer.png_bytes)?;
             raster_images.push((NodeId(layer.node_id), image));
         }
 
@@ -1453,9 +1976,10 @@ impl AppThreadIntegration {
             let Some(layer) = document.get_leaf_image_mut(node_id) else {
                 return Err(DocumentPackageError::MissingRasterNode { node_id });
             };
-            let mut tile_pixels = Vec::new();
             for tile_index in tile_indices {
                 let tile_key = self
+                    .engine
+                    .worker()
                     .engine_state
                     .allocate_leaf_tile(layer.backend())
                     .ok_or(DocumentPackageError::TileAlloc {
@@ -1468,13 +1992,7 @@ impl AppThreadIntegration {
                         tile_index,
                     }
                 })?;
-                image
-                    .copy_tile_rgba8(tile_index, &mut tile_pixels)
-                    .map_err(|_| DocumentPackageError::TileUpload {
-                        node_id,
-                        tile_index,
-                    })?;
-                if !self.main_state.upload_tile_rgba8(tile_key, &tile_pixels) {
+                if !self.main_state.upload_tile(tile_key, &image, tile_index) {
                     return Err(DocumentPackageError::TileUpload {
                         node_id,
                         tile_index,
@@ -1483,87 +2001,1007 @@ impl AppThreadIntegration {
             }
         }
 
-        self.engine_state.replace_document(document);
-        let mut msg = self.engine_state.rebuild_render_tree().map_err(|error| {
-            DocumentPackageError::Storage(DocumentStorageError::ImageCreate(error))
-        })?;
-        msg.dirty_render_caches =
-            collect_all_render_cache_node_ids(&self.engine_state.shared_tree().read());
-        let _ = self
-            .main_state
-            .process_gpu_commands(&[thread_protocol::GpuCmdMsg::RenderTreeUpdated(msg)]);
-        Ok(())
+        self.document_layout = document.layout();
+        let engine_state = &mut self.engine.worker().engine_state;
+        engine_state.replace_document(document);
+        let mut msg = engine_state.rebuild_render_tree().map_err(|error| {
+            DocumentPackageError::Storage(DocumentStorageError::ImageCreate(error))
+        })?;
+        msg.dirty_render_caches =
+            collect_all_render_cache_node_ids(&engine_state.shared_tree().read());
+        let _ = self
+            .main_state
+            .process_gpu_commands(&[thread_protocol::GpuCmdMsg::RenderTreeUpdated(msg)]);
+        self.refresh_engine_snapshot();
+        Ok(())
+    }
+
+    pub fn register_brush<S: BrushSpec + BrushResamplerDistancePolicy>(
+        &mut self,
+        brush_id: BrushId,
+        brush: S,
+    ) -> Result<(), BrushRegisterError> {
+        let resampler_distance = brush.resampler_distance();
+        let max_affected_radius_px = brush.max_affected_radius_px();
+
+        self.park_engine();
+        let cache_backend_id = self.main_state.register_brush(brush_id, &brush)?;
+        let worker = self.engine.worker();
+        if let Some(cache_backend_id) = cache_backend_id {
+            while worker
+                .engine_state
+                .backend_manager()
+                .backend(cache_backend_id)
+                .is_none()
+            {
+                if worker
+                    .engine_state
+                    .backend_manager_mut()
+                    .add_backend(AtlasLayout::Small11)
+                    .is_err()
+                {
+                    break;
+                }
+            }
+        }
+
+        worker
+            .engine_state
+            .brush_runtime_mut()
+            .register_pipeline_with_stroke_buffer_backend(
+                brush_id,
+                max_affected_radius_px,
+                cache_backend_id,
+                brush,
+            )
+            .map_err(BrushRegisterError::Engine)?;
+
+        let Some(brush_index) = usize::try_from(brush_id.0).ok() else {
+            return Ok(());
+        };
+        if let Some(slot) = worker.brush_resampler_distances.get_mut(brush_index) {
+            *slot = Some(resampler_distance);
+        }
+
+        Ok(())
+    }
+
+    /// Registers a brush loaded from a manifest and starts watching its shader.
+    pub fn register_file_brush(
+        &mut self,
+        brush_id: BrushId,
+        brush: FileBrush,
+    ) -> Result<(), BrushRegisterError> {
+        let definition = brush.definition().clone();
+        self.register_brush(brush_id, brush)?;
+        let watcher = ShaderFileWatcher::new(definition.shader_path().to_path_buf());
+        self.file_brushes
+            .retain(|watched| watched.brush_id != brush_id);
+        self.file_brushes.push(WatchedFileBrush {
+            brush_id,
+            definition,
+            watcher,
+        });
+        Ok(())
+    }
+
+    /// Recompiles file brushes whose shader changed since the last poll.
+    ///
+    /// A shader that fails validation is reported and the previous pipeline
+    /// stays in use. Polling is throttled to
+    /// [`config::file_brushes::WATCH_POLL_INTERVAL_MS`].
+    pub fn poll_file_brush_reloads(&mut self) -> Vec<FileBrushReload> {
+        let now = Instant::now();
+        let interval = Duration::from_millis(config::file_brushes::WATCH_POLL_INTERVAL_MS);
+        if self.file_brushes.is_empty()
+            || self
+                .last_file_brush_poll_at
+                .is_some_and(|last| now.duration_since(last) < interval)
+        {
+            return Vec::new();
+        }
+        self.last_file_brush_poll_at = Some(now);
+
+        let mut reloads = Vec::new();
+        for index in 0..self.file_brushes.len() {
+            let watched = &mut self.file_brushes[index];
+            if !watched.watcher.poll_changed() {
+                continue;
+            }
+            let brush_id = watched.brush_id;
+            let definition = watched.definition.clone();
+            let result = match definition.read_shader() {
+                Ok(wgsl_source) => self
+                    .main_state
+                    .reload_brush_shader(brush_id, wgsl_source.into())
+                    .map_err(FileBrushReloadError::Register),
+                Err(error) => Err(FileBrushReloadError::Shader(error)),
+            };
+            reloads.push(FileBrushReload {
+                brush_id,
+                label: definition.label(),
+                result,
+            });
+        }
+        reloads
+    }
+
+    /// Swaps a registered brush's settings. In dedicated mode the engine
+    /// thread applies it between frames without parking.
+    pub fn update_brush<S: BrushSpec + BrushResamplerDistancePolicy>(
+        &mut self,
+        brush_id: BrushId,
+        brush: S,
+    ) -> Result<(), BrushRegisterError> {
+        let resampler_distance = brush.resampler_distance();
+        let max_affected_radius_px = brush.max_affected_radius_px();
+        self.engine
+            .round_trip(|reply| EngineRequest::UpdateBrush {
+                brush_id,
+                max_affected_radius_px,
+                resampler_distance,
+                pipeline: Box::new(brush),
+                reply,
+            })
+            .map_err(BrushRegisterError::Engine)
+    }
+
+    pub fn set_brush_color_dynamics(
+        &mut self,
+        brush_id: BrushId,
+        dynamics: ColorDynamics,
+    ) -> Result<(), BrushRegisterError> {
+        self.engine
+            .round_trip(|reply| EngineRequest::SetColorDynamics {
+                brush_id,
+                dynamics,
+                reply,
+            })
+            .map_err(BrushRegisterError::Engine)
+    }
+}
+
+impl EngineWorker {
+    fn new(
+        engine_state: EngineThreadState,
+        channels: AppEngineThreadChannels,
+        snapshots: mpsc::Sender<EngineSnapshot>,
+    ) -> Self {
+        Self {
+            engine_state,
+            channels,
+            input_controls: Vec::with_capacity(config::batch_capacities::INPUT_SAMPLES),
+            input_samples: Vec::with_capacity(config::batch_capacities::INPUT_SAMPLES),
+            brush_inputs: Vec::with_capacity(config::batch_capacities::BRUSH_INPUTS),
+            gpu_commands: Vec::with_capacity(config::batch_capacities::GPU_COMMANDS),
+            pending_send_gpu_commands: VecDeque::with_capacity(
+                config::batch_capacities::GPU_COMMANDS,
+            ),
+            sent_gpu_commands: Vec::new(),
+            trace_recorder: None,
+            recovery_journal: None,
+            last_editing_activity_at: None,
+            active_stroke_node: None,
+            current_brush_id: None,
+            current_brush_paint: BrushPaint::default(),
+            active_stroke_paint: BrushPaint::default(),
+            last_stroke_input: None,
+            brush_resampler_distances: vec![None; config::brush_processing::MAX_BRUSHES],
+            next_stroke_id: 1,
+            input_batch: InputBatchController::default(),
+            last_frame_perf: EngineFramePerf::default(),
+            snapshots,
+            last_snapshot_at: None,
+            has_new_output: false,
+            waker: None,
+            perf_trace: PerfTraceConfig::from_env(),
+            perf_frame_seq: 0,
+        }
+    }
+
+    fn snapshot(&self) -> EngineSnapshot {
+        let document = self.engine_state.document();
+        let engine_stats = self.engine_state.stats();
+        EngineSnapshot {
+            layer_tree: document.layer_tree_items(),
+            selected_node: document.selected_node(),
+            active_paint_node: document.active_paint_node(),
+            stats: AppStats {
+                backend_tiles: engine_stats.backend_tiles,
+                undo_stroke_count: engine_stats.undo_stroke_count,
+                input: InputStats {
+                    pushed_samples: self.channels.input_ring_consumer.pushed_samples(),
+                    dropped_samples: self.channels.input_ring_consumer.dropped_samples(),
+                    pending_samples: self.channels.input_ring_consumer.pending_samples(),
+                    batch_size: self.input_batch.batch_size(),
+                    behind: self.input_batch.is_behind(),
+                },
+                engine_frame: self.last_frame_perf.clone(),
+                gpu_command_queue_depth: self.pending_send_gpu_commands.len(),
+            },
+        }
+    }
+
+    fn publish_snapshot(&mut self) {
+        let _ = self.snapshots.send(self.snapshot());
+        self.last_snapshot_at = Some(Instant::now());
+        self.has_new_output = true;
+    }
+
+    fn wake_if_new_output(&mut self) {
+        if std::mem::take(&mut self.has_new_output)
+            && let Some(waker) = &self.waker
+        {
+            waker();
+        }
+    }
+
+    /// Applies one request; returns whether it asks the engine thread to park.
+    fn handle_request(&mut self, request: EngineRequest) -> bool {
+        match request {
+            EngineRequest::SelectBrush(brush_id) => self.select_brush(brush_id),
+            EngineRequest::SetBrushPaint(paint) => self.current_brush_paint = paint,
+            EngineRequest::ReplayFrame(input_frame) => {
+                let mut perf = EngineFramePerf::default();
+                self.process_replay_frame(&input_frame, &mut perf);
+                perf.trace(&self.perf_trace, &mut self.perf_frame_seq);
+            }
+            EngineRequest::UndoStroke(reply) => {
+                let _ = reply.send(self.undo_stroke());
+            }
+            EngineRequest::RedoStroke(reply) => {
+                let _ = reply.send(self.redo_stroke());
+            }
+            EngineRequest::UpdateBrush {
+                brush_id,
+                max_affected_radius_px,
+                resampler_distance,
+                pipeline,
+                reply,
+            } => {
+                let _ = reply.send(self.update_brush(
+                    brush_id,
+                    max_affected_radius_px,
+                    resampler_distance,
+                    pipeline,
+                ));
+            }
+            EngineRequest::SetColorDynamics {
+                brush_id,
+                dynamics,
+                reply,
+            } => {
+                let _ = reply.send(
+                    self.engine_state
+                        .brush_runtime_mut()
+                        .set_color_dynamics(brush_id, dynamics),
+                );
+            }
+            EngineRequest::Park => return true,
+        }
+        false
+    }
+
+    fn update_brush(
+        &mut self,
+        brush_id: BrushId,
+        max_affected_radius_px: u32,
+        resampler_distance: BrushResamplerDistance,
+        pipeline: Box<dyn EngineBrushPipeline>,
+    ) -> Result<(), BrushRegistryError> {
+        self.engine_state
+            .brush_runtime_mut()
+            .update_boxed_pipeline(brush_id, max_affected_radius_px, pipeline)?;
+        if let Some(slot) = usize::try_from(brush_id.0)
+            .ok()
+            .and_then(|brush_index| self.brush_resampler_distances.get_mut(brush_index))
+        {
+            *slot = Some(resampler_distance);
+        }
+        Ok(())
+    }
+
+    fn select_brush(&mut self, brush_id: BrushId) {
+        self.current_brush_id = Some(brush_id);
+        let Some(brush_index) = usize::try_from(brush_id.0).ok() else {
+            return;
+        };
+        let Some(Some(distance)) = self.brush_resampler_distances.get(brush_index).copied() else {
+            return;
+        };
+        self.engine_state.set_resampler_distance(distance);
+    }
+
+    fn undo_stroke(&mut self) -> bool {
+        if self.active_stroke_node.is_some() {
+            return false;
+        }
+        let Some(update) = self.engine_state.undo_stroke() else {
+            return false;
+        };
+        self.pending_send_gpu_commands
+            .push_back(GpuCmdMsg::TileSlotKeyUpdate(update));
+        self.journal_recovery_entry(RecoveryJournalEntry::UndoStroke);
+        self.publish_snapshot();
+        true
+    }
+
+    fn redo_stroke(&mut self) -> bool {
+        if self.active_stroke_node.is_some() {
+            return false;
+        }
+        let Some(update) = self.engine_state.redo_stroke() else {
+            return false;
+        };
+        self.pending_send_gpu_commands
+            .push_back(GpuCmdMsg::TileSlotKeyUpdate(update));
+        self.journal_recovery_entry(RecoveryJournalEntry::RedoStroke);
+        self.publish_snapshot();
+        true
+    }
+
+    /// Drains the controls and samples queued since the last frame. Samples
+    /// are waited for only when no control is pending and the engine is not
+    /// catching up; the batch size adapts to the backlog left behind.
+    fn collect_input(&mut self, wait_timeout: Duration, perf: &mut EngineFramePerf) {
+        self.input_controls.clear();
+        while let Ok(event) = self.channels.input_control_queue.pop() {
+            self.input_controls.push(event);
+        }
+        let wait_timeout = if self.input_controls.is_empty() {
+            self.input_batch.wait_timeout(wait_timeout)
+        } else {
+            Duration::ZERO
+        };
+        self.input_samples.clear();
+        let input_sample_started = Instant::now();
+        let input_ring = &self.channels.input_ring_consumer;
+        input_ring.drain_batch_with_wait(
+            &mut self.input_samples,
+            self.input_batch.batch_size(),
+            wait_timeout,
+        );
+        self.input_batch.observe(
+            self.input_samples.len(),
+            input_ring.pending_samples(),
+            input_ring.dropped_samples(),
+        );
+        self.normalize_input_sample_timestamps();
+        perf.input_sample = input_sample_started.elapsed();
+        perf.sample_count = self.input_samples.len();
+    }
+
+    /// Applies the collected controls and strokes the collected samples.
+    fn process_input(&mut self, perf: &mut EngineFramePerf) {
+        let drained_controls = self.input_controls.clone();
+        for event in &drained_controls {
+            self.apply_input_control_event(event);
+        }
+        if !self.input_controls.is_empty() || !self.input_samples.is_empty() {
+            self.note_editing_activity();
+        }
+        if let Some(trace_recorder) = &mut self.trace_recorder {
+            trace_recorder.record_input_frame(&self.input_controls, &self.input_samples);
+        }
+        self.journal_recovery_input_frame();
+        self.stroke_input_samples(perf);
+        self.finish_frame(perf);
+    }
+
+    /// Runs a recorded frame in place of live input. The live batch collected
+    /// for this frame is set aside and restored, so the engine thread neither
+    /// loses it nor processes the replayed frame a second time.
+    fn process_replay_frame(&mut self, input_frame: &TraceInputFrame, perf: &mut EngineFramePerf) {
+        let (controls, samples) = input_frame.to_runtime();
+        let live_controls = std::mem::replace(&mut self.input_controls, controls);
+        let live_samples = std::mem::replace(&mut self.input_samples, samples);
+        perf.sample_count = self.input_samples.len();
+
+        let replay_controls = self.input_controls.clone();
+        for event in &replay_controls {
+            self.apply_input_control_event(event);
+        }
+        self.journal_recovery_input_frame();
+        self.stroke_input_samples(perf);
+        self.finish_frame(perf);
+        self.input_controls = live_controls;
+        self.input_samples = live_samples;
+    }
+
+    /// Closes a stroke whose end never reached the engine.
+    fn close_stroke(&mut self, node_id: NodeId) {
+        self.apply_input_control_event(&InputControlEvent::Control(AppControl::StrokeBoundary {
+            node_id,
+            begin: false,
+        }));
+        self.input_controls.clear();
+        self.input_samples.clear();
+    }
+
+    /// Sends the frame's GPU commands and publishes a snapshot when the frame
+    /// changed what the UI shows.
+    fn finish_frame(&mut self, perf: &mut EngineFramePerf) {
+        let sent = self.send_pending_gpu_commands(perf);
+        self.has_new_output |= sent > 0;
+        if perf.sample_count > 0 {
+            self.last_frame_perf = perf.clone();
+        }
+        if let Some(trace_recorder) = &mut self.trace_recorder {
+            trace_recorder.record_output_frame(&self.sent_gpu_commands);
+        }
+        self.sent_gpu_commands.clear();
+
+        let snapshot_interval = Duration::from_millis(config::engine_thread::SNAPSHOT_INTERVAL_MS);
+        let snapshot_due = !self.input_samples.is_empty()
+            && self
+                .last_snapshot_at
+                .is_none_or(|published_at| published_at.elapsed() >= snapshot_interval);
+        if !self.input_controls.is_empty() || snapshot_due {
+            self.publish_snapshot();
+        }
+    }
+
+    /// Records commands sent outside a frame as one more output frame.
+    fn record_trace_output(&mut self) {
+        if self.sent_gpu_commands.is_empty() {
+            return;
+        }
+        if let Some(trace_recorder) = &mut self.trace_recorder {
+            trace_recorder.record_output_frame(&self.sent_gpu_commands);
+        }
+        self.sent_gpu_commands.clear();
+    }
+
+    fn stroke_input_samples(&mut self, perf: &mut EngineFramePerf) {
+        if self.input_samples.is_empty() {
+            return;
+        }
+        let (Some(node_id), Some(brush_id)) = (self.active_stroke_node, self.current_brush_id)
+        else {
+            return;
+        };
+        self.brush_inputs.clear();
+        self.gpu_commands.clear();
+
+        let smooth_and_resample_started = Instant::now();
+        for sample in &self.input_samples {
+            let new_inputs =
+                self.engine_state
+                    .process_raw_input(sample.device, sample.cursor, sample.time_ns);
+            self.brush_inputs.extend(new_inputs);
+        }
+        perf.smooth_and_resample = smooth_and_resample_started.elapsed();
+        perf.brush_input_count = self.brush_inputs.len();
+
+        let brush_inputs = self.brush_inputs.clone();
+        let brush_handling_started = Instant::now();
+        self.dispatch_stroke_inputs(brush_id, node_id, &brush_inputs);
+        perf.brush_handling = brush_handling_started.elapsed();
+
+        if let Some(last_input) = brush_inputs.last() {
+            self.last_stroke_input = Some(*last_input);
+        }
+        perf.generated_gpu_command_count = self.queue_stroke_gpu_commands();
+    }
+
+    /// Pushes queued commands into the channel to the main thread until it
+    /// is full; returns how many were sent.
+    fn send_pending_gpu_commands(&mut self, perf: &mut EngineFramePerf) -> usize {
+        let send_to_app_thread_started = Instant::now();
+        let mut sent = 0usize;
+        while self.channels.gpu_command_sender.slots() > 0 {
+            let Some(cmd) = self.pending_send_gpu_commands.front().cloned() else {
+                break;
+            };
+            if let Err(e) = self.channels.gpu_command_sender.push(cmd) {
+                eprintln!("GPU command send failed: {e:?}");
+                break;
+            }
+            if let Some(cmd) = self.pending_send_gpu_commands.pop_front()
+                && self.trace_recorder.is_some()
+            {
+                self.sent_gpu_commands.push(cmd);
+            }
+            sent += 1;
+        }
+        perf.send_to_app_thread = send_to_app_thread_started.elapsed();
+        perf.send_inline_submit = Duration::ZERO;
+        perf.inline_submitted_gpu_command_count = 0;
+        perf.inline_submit_batches = 0;
+        perf.pending_send_gpu_command_count = self.pending_send_gpu_commands.len();
+        sent
+    }
+
+    fn journal_recovery_input_frame(&mut self) {
+        let Some(journal) = &mut self.recovery_journal else {
+            return;
+        };
+        if !self.input_controls.is_empty() || !self.input_samples.is_empty() {
+            let begins_stroke = self.input_controls.iter().any(|event| {
+                matches!(
+                    event,
+                    InputControlEvent::Control(AppControl::StrokeBoundary { begin: true, .. })
+                )
+            });
+            if begins_stroke && let Some(brush_id) = self.current_brush_id {
+                journal.record(RecoveryJournalEntry::Brush {
+                    brush_id: brush_id.0,
+                    rgb: self.active_stroke_paint.color_rgb,
+                    erase: self.active_stroke_paint.erase,
+                });
+            }
+            journal.record(RecoveryJournalEntry::Input(TraceInputFrame::from_runtime(
+                &self.input_controls,
+                &self.input_samples,
+            )));
+        }
+        if self.active_stroke_node.is_none()
+            && let Err(error) = journal.commit()
+        {
+            eprintln!("recovery journal commit failed: {error}");
+        }
+    }
+
+    /// Adds the time since the previous edit to the document's editing time,
+    /// ignoring idle gaps.
+    fn note_editing_activity(&mut self) {
+        let now = Instant::now();
+        if let Some(last) = self.last_editing_activity_at {
+            let elapsed_ms = now.duration_since(last).as_millis() as u64;
+            if elapsed_ms <= config::document_metadata::EDITING_IDLE_CAP_MS {
+                self.engine_state
+                    .document_mut()
+                    .metadata_mut()
+                    .add_editing_time_ms(elapsed_ms);
+            }
+        }
+        self.last_editing_activity_at = Some(now);
+    }
+
+    fn journal_recovery_entry(&mut self, entry: RecoveryJournalEntry) {
+        let Some(journal) = &mut self.recovery_journal else {
+            return;
+        };
+        journal.record(entry);
+        if let Err(error) = journal.commit() {
+            eprintln!("recovery journal commit failed: {error}");
+        }
+    }
+
+    fn apply_input_control_event(&mut self, event: &InputControlEvent<AppControl>) {
+        let InputControlEvent::Control(control) = event;
+        match control {
+            AppControl::StrokeBoundary { node_id, begin } => {
+                if *begin {
+                    let stroke_id = StrokeId(self.next_stroke_id);
+                    self.next_stroke_id += 1;
+                    self.active_stroke_node = Some(*node_id);
+                    self.active_stroke_paint = self.current_brush_paint;
+                    self.last_stroke_input = None;
+                    self.engine_state.begin_stroke(stroke_id);
+                } else {
+                    self.flush_smoother_catch_up(*node_id);
+                    self.flush_held_back_dabs(*node_id);
+                    self.active_stroke_node = None;
+                    self.engine_state.end_stroke();
+                }
+            }
+            AppControl::CancelStroke { node_id } => {
+                if self.active_stroke_node != Some(*node_id) {
+                    return;
+                }
+                self.active_stroke_node = None;
+                self.last_stroke_input = None;
+                if let Some(update) = self.engine_state.cancel_stroke() {
+                    self.pending_send_gpu_commands
+                        .push_back(GpuCmdMsg::TileSlotKeyUpdate(update));
+                }
+            }
+            AppControl::SelectNode { node_id } => {
+                let _ = self.engine_state.document_mut().set_active_node(*node_id);
+            }
+            AppControl::CreateLayerAboveActive { kind } => {
+                self.engine_state.invalidate_redo_strokes();
+                match self
+                    .engine_state
+                    .document_mut()
+                    .create_layer_above_active(*kind)
+                {
+                    Ok(_) => self.enqueue_render_tree_update(),
+                    Err(error) => eprintln!("create layer control failed: {error:?}"),
+                }
+            }
+            AppControl::CreateGroupAboveActive => {
+                self.engine_state.invalidate_redo_strokes();
+                match self.engine_state.document_mut().create_group_above_active() {
+                    Ok(_) => self.enqueue_render_tree_update(),
+                    Err(error) => eprintln!("create group control failed: {error:?}"),
+                }
+            }
+            AppControl::MoveNode { node_id, target } => {
+                self.engine_state.invalidate_redo_strokes();
+                match self
+                    .engine_state
+                    .document_mut()
+                    .move_node_to(*node_id, *target)
+                {
+                    Ok(()) => self.enqueue_render_tree_update(),
+                    Err(error) => eprintln!("move node control failed: {error:?}"),
+                }
+            }
+            AppControl::SetNodeVisibility { node_id, visible } => {
+                self.engine_state.invalidate_redo_strokes();
+                match self
+                    .engine_state
+                    .document_mut()
+                    .set_node_visibility(*node_id, *visible)
+                {
+                    Ok(_) => self.enqueue_render_tree_update(),
+                    Err(error) => eprintln!("set node visibility control failed: {error:?}"),
+                }
+            }
+            AppControl::SetNodeOpacity { node_id, opacity } => {
+                self.engine_state.invalidate_redo_strokes();
+                match self
+                    .engine_state
+                    .document_mut()
+                    .set_node_opacity(*node_id, *opacity)
+                {
+                    Ok(()) => self.enqueue_render_tree_update(),
+                    Err(error) => eprintln!("set node opacity control failed: {error:?}"),
+                }
+            }
+            AppControl::SetNodeBlendMode {
+                node_id,
+                blend_mode,
+            } => {
+                self.engine_state.invalidate_redo_strokes();
+                match self
+                    .engine_state
+                    .document_mut()
+                    .set_node_blend_mode(*node_id, *blend_mode)
+                {
+                    Ok(()) => self.enqueue_render_tree_update(),
+                    Err(error) => eprintln!("set node blend mode control failed: {error:?}"),
+                }
+            }
+            AppControl::MoveActiveNodeUp => {
+                self.engine_state.invalidate_redo_strokes();
+                match self.engine_state.document_mut().move_active_node_up() {
+                    Ok(()) => self.enqueue_render_tree_update(),
+                    Err(error) => eprintln!("move layer up control failed: {error:?}"),
+                }
+            }
+            AppControl::MoveActiveNodeDown => {
+                self.engine_state.invalidate_redo_strokes();
+                match self.engine_state.document_mut().move_active_node_down() {
+                    Ok(()) => self.enqueue_render_tree_update(),
+                    Err(error) => eprintln!("move layer down control failed: {error:?}"),
+                }
+            }
+            AppControl::SetSymmetry { symmetry } => self.engine_state.set_symmetry(*symmetry),
+            AppControl::SetSmoothing { smoothing } => self.engine_state.set_smoothing(*smoothing),
+            AppControl::SetResampler { kind } => self.engine_state.set_resampler_kind(*kind),
+            AppControl::SetPressureRamp {
+                taper,
+                synthetic_pressure,
+            } => self
+                .engine_state
+                .set_pressure_ramp(*taper, *synthetic_pressure),
+            AppControl::SetDeviceCalibration { calibration } => {
+                self.engine_state.set_device_calibration(*calibration)
+            }
+            AppControl::ShapeStroke { shape, taper } => self.draw_shape_inputs(shape, *taper),
+        }
+    }
+
+    fn enqueue_render_tree_update(&mut self) {
+        match self.engine_state.rebuild_render_tree() {
+            Ok(msg) => self
+                .pending_send_gpu_commands
+                .push_back(thread_protocol::GpuCmdMsg::RenderTreeUpdated(msg)),
+            Err(error) => eprintln!("render tree rebuild failed after control event: {error}"),
+        }
+    }
+
+    /// Orders the stroke commands built this frame and queues them to send.
+    fn queue_stroke_gpu_commands(&mut self) -> usize {
+        AppThreadIntegration::compact_frame_mergeable_draws(&mut self.gpu_commands);
+        AppThreadIntegration::compact_frame_mergeable_copy_write(&mut self.gpu_commands);
+        AppThreadIntegration::move_setup_ops_before_draws(&mut self.gpu_commands);
+        AppThreadIntegration::move_mergeable_writes_to_end(&mut self.gpu_commands);
+        AppThreadIntegration::move_metadata_updates_to_end(&mut self.gpu_commands);
+
+        let pending_gpu_cmds = std::mem::take(&mut self.gpu_commands);
+        let count = pending_gpu_cmds.len();
+        self.pending_send_gpu_commands.extend(pending_gpu_cmds);
+        count
+    }
+
+    /// Strokes the catch-up inputs a lagging smoother emits at stroke end, so
+    /// the line reaches where the pen lifted.
+    fn flush_smoother_catch_up(&mut self, node_id: NodeId) {
+        let Some(brush_id) = self.current_brush_id else {
+            return;
+        };
+        let brush_inputs = self.engine_state.finish_stroke_input();
+        let Some(last_input) = brush_inputs.last() else {
+            return;
+        };
+        self.last_stroke_input = Some(*last_input);
+        self.gpu_commands.clear();
+        self.dispatch_stroke_inputs(brush_id, node_id, &brush_inputs);
+        self.queue_stroke_gpu_commands();
+    }
+
+    /// Re-sends the stroke's last input marked as the stroke end, so brushes
+    /// that look ahead (such as pixel-perfect lines) stamp the dabs they held.
+    fn flush_held_back_dabs(&mut self, node_id: NodeId) {
+        let (Some(brush_id), Some(mut last_input)) =
+            (self.current_brush_id, self.last_stroke_input.take())
+        else {
+            return;
+        };
+        last_input.flags |= glaphica_core::BrushInputFlags::STROKE_END;
+        self.gpu_commands.clear();
+        self.dispatch_stroke_inputs(brush_id, node_id, &[last_input]);
+        self.queue_stroke_gpu_commands();
+    }
+
+    /// Strokes a generated shape in the open stroke as if it had been drawn
+    /// by hand, so it goes through the same brush, symmetry and undo paths.
+    fn draw_shape_inputs(&mut self, shape: &StrokeShape, taper: ShapeTaper) {
+        let (Some(brush_id), Some(node_id)) = (self.current_brush_id, self.active_stroke_node)
+        else {
+            return;
+        };
+        let brush_inputs = self.engine_state.shape_stroke_inputs(shape, taper);
+        if let Some(last_input) = brush_inputs.last() {
+            self.last_stroke_input = Some(*last_input);
+        }
+        self.gpu_commands.clear();
+        self.dispatch_stroke_inputs(brush_id, node_id, &brush_inputs);
+        self.queue_stroke_gpu_commands();
+    }
+
+    /// Runs stroke inputs through the brush with the active stroke's colors,
+    /// collecting the draw commands into `gpu_commands`.
+    fn dispatch_stroke_inputs(
+        &mut self,
+        brush_id: BrushId,
+        node_id: NodeId,
+        brush_inputs: &[BrushInput],
+    ) {
+        self.engine_state
+            .brush_runtime_mut()
+            .set_background_rgb(self.active_stroke_paint.background_rgb);
+        let composite_tree = self
+            .engine_state
+            .brush_runtime()
+            .samples_composite(brush_id)
+            .unwrap_or(false)
+            .then(|| self.engine_state.shared_tree().read());
+        let composite = composite_tree.as_deref().and_then(root_render_cache);
+        for brush_input in brush_inputs {
+            match self.engine_state.process_stroke_input(
+                brush_id,
+                brush_input,
+                self.active_stroke_paint.color_rgb,
+                self.active_stroke_paint.erase,
+                node_id,
+                composite,
+            ) {
+                Ok(cmds) => {
+                    self.gpu_commands.extend(cmds);
+                }
+                Err(e) => {
+                    eprintln!("Stroke processing failed: {e:?}");
+                }
+            }
+        }
+    }
+
+    fn normalize_input_sample_timestamps(&mut self) {
+        for sample in &mut self.input_samples {
+            if sample.time_ns == 0 {
+                sample.time_ns = current_time_ns();
+            }
+        }
     }
+}
 
-    pub fn register_brush<S: BrushSpec + BrushResamplerDistancePolicy>(
-        &mut self,
-        brush_id: BrushId,
-        brush: S,
-    ) -> Result<(), BrushRegisterError> {
-        let resampler_distance = brush.resampler_distance();
-        let max_affected_radius_px = brush.max_affected_radius_px();
+/// Owns the [`EngineWorker`], either directly or through the engine thread.
+struct EngineHost {
+    mode: EngineThreadMode,
+    /// Held while the engine is parked: always in inline mode, and from a
+    /// park until the next frame in dedicated mode.
+    worker: Option<Box<EngineWorker>>,
+    /// Started by the first dedicated frame and kept until the host drops.
+    thread: Option<EngineThread>,
+}
 
-        let cache_backend_id = self.main_state.register_brush(brush_id, &brush)?;
-        if let Some(cache_backend_id) = cache_backend_id {
-            while self
-                .engine_state
-                .backend_manager()
-                .backend(cache_backend_id)
-                .is_none()
-            {
-                if self
-                    .engine_state
-                    .backend_manager_mut()
-                    .add_backend(AtlasLayout::Small11)
-                    .is_err()
-                {
-                    break;
+impl EngineHost {
+    fn is_running(&self) -> bool {
+        self.worker.is_none()
+    }
+
+    /// The worker, parking the engine thread first if it runs.
+    fn worker(&mut self) -> &mut EngineWorker {
+        if self.worker.is_none() {
+            let thread = self
+                .thread
+                .as_mut()
+                .expect("engine worker is on the engine thread when not held");
+            self.worker = Some(thread.park());
+        }
+        self.worker
+            .as_mut()
+            .expect("engine worker is held by the host while parked")
+    }
+
+    /// The worker to run a frame on inline. In dedicated mode this hands a
+    /// parked worker back to the engine thread and returns `None`.
+    fn frame_worker(&mut self) -> Option<&mut EngineWorker> {
+        match self.mode {
+            EngineThreadMode::Inline => Some(self.worker()),
+            EngineThreadMode::Dedicated => {
+                if let Some(worker) = self.worker.take() {
+                    match &mut self.thread {
+                        Some(thread) => thread.resume(worker),
+                        None => self.thread = Some(EngineThread::spawn(worker)),
+                    }
                 }
+                None
             }
         }
+    }
 
-        self.engine_state
-            .brush_runtime_mut()
-            .register_pipeline_with_stroke_buffer_backend(
-                brush_id,
-                max_affected_radius_px,
-                cache_backend_id,
-                brush,
-            )
-            .map_err(BrushRegisterError::Engine)?;
+    /// Sends `request` to the engine thread, or applies it right away while
+    /// the worker is parked.
+    fn request(&mut self, request: EngineRequest) {
+        if let Some(worker) = &mut self.worker {
+            worker.handle_request(request);
+            return;
+        }
+        let thread = self
+            .thread
+            .as_ref()
+            .expect("engine worker is on the engine thread when not held");
+        if thread.requests.send(request).is_err() {
+            eprintln!("engine thread stopped; request dropped");
+        }
+    }
 
-        let Some(brush_index) = usize::try_from(brush_id.0).ok() else {
-            return Ok(());
-        };
-        if let Some(slot) = self.brush_resampler_distances.get_mut(brush_index) {
-            *slot = Some(resampler_distance);
+    /// Sends a request that carries a reply channel and waits for the answer.
+    fn round_trip<T>(&mut self, request: impl FnOnce(mpsc::Sender<T>) -> EngineRequest) -> T {
+        let (reply, answer) = mpsc::channel();
+        self.request(request(reply));
+        match answer.recv() {
+            Ok(answer) => answer,
+            Err(_) => self
+                .thread
+                .as_mut()
+                .expect("only the engine thread drops a reply unanswered")
+                .rethrow_exit(),
         }
+    }
+}
 
-        Ok(())
+impl Drop for EngineHost {
+    fn drop(&mut self) {
+        if let Some(thread) = self.thread.take() {
+            thread.stop();
+        }
     }
+}
 
-    pub fn update_brush<S: BrushSpec + BrushResamplerDistancePolicy>(
-        &mut self,
-        brush_id: BrushId,
-        brush: S,
-    ) -> Result<(), BrushRegisterError> {
-        let resampler_distance = brush.resampler_distance();
-        let max_affected_radius_px = brush.max_affected_radius_px();
-        self.engine_state
-            .brush_runtime_mut()
-            .update_pipeline(brush_id, max_affected_radius_px, brush)
-            .map_err(BrushRegisterError::Engine)?;
+/// The long-lived engine thread. Parking lends the worker back to the main
+/// thread while the engine thread waits for it to be resumed.
+struct EngineThread {
+    requests: mpsc::Sender<EngineRequest>,
+    lent: mpsc::Receiver<Box<EngineWorker>>,
+    resume: mpsc::Sender<Box<EngineWorker>>,
+    handle: Option<JoinHandle<()>>,
+}
 
-        let Some(brush_index) = usize::try_from(brush_id.0).ok() else {
-            return Ok(());
-        };
-        if let Some(slot) = self.brush_resampler_distances.get_mut(brush_index) {
-            *slot = Some(resampler_distance);
+impl EngineThread {
+    fn spawn(worker: Box<EngineWorker>) -> Self {
+        let (requests, request_receiver) = mpsc::channel();
+        let (lend, lent) = mpsc::channel();
+        let (resume, resume_receiver) = mpsc::channel();
+        let handle = std::thread::Builder::new()
+            .name(config::engine_thread::THREAD_NAME.to_string())
+            .spawn(move || run_engine_thread(worker, request_receiver, lend, resume_receiver))
+            .expect("failed to spawn engine thread");
+        Self {
+            requests,
+            lent,
+            resume,
+            handle: Some(handle),
+        }
+    }
+
+    fn park(&mut self) -> Box<EngineWorker> {
+        let _ = self.requests.send(EngineRequest::Park);
+        match self.lent.recv() {
+            Ok(worker) => worker,
+            Err(_) => self.rethrow_exit(),
+        }
+    }
+
+    fn resume(&mut self, worker: Box<EngineWorker>) {
+        if self.resume.send(worker).is_err() {
+            self.rethrow_exit();
+        }
+    }
+
+    /// Joins a thread that stopped answering, re-raising its panic.
+    fn rethrow_exit(&mut self) -> ! {
+        match self.handle.take().map(JoinHandle::join) {
+            Some(Err(panic)) => std::panic::resume_unwind(panic),
+            _ => panic!("engine thread exited while the host still used it"),
+        }
+    }
+
+    fn stop(self) {
+        let Self {
+            requests,
+            lent,
+            resume,
+            handle,
+        } = self;
+        drop((requests, lent, resume));
+        if let Some(handle) = handle {
+            let _ = handle.join();
         }
-        Ok(())
     }
 }
 
+/// Engine thread body: strokes input as it arrives, lends the worker out when
+/// asked to park, and exits once the host drops its channels.
+fn run_engine_thread(
+    mut worker: Box<EngineWorker>,
+    requests: mpsc::Receiver<EngineRequest>,
+    lend: mpsc::Sender<Box<EngineWorker>>,
+    resume: mpsc::Receiver<Box<EngineWorker>>,
+) {
+    let idle_wait = Duration::from_millis(config::engine_thread::IDLE_WAIT_MS);
+    loop {
+        let mut perf = EngineFramePerf::default();
+        worker.collect_input(idle_wait, &mut perf);
+        // Requests go after the controls are drained, so a brush change sent
+        // before a stroke began is in place when that stroke's control applies.
+        let mut park = false;
+        let mut stop = false;
+        loop {
+            match requests.try_recv() {
+                Ok(request) => park |= worker.handle_request(request),
+                Err(mpsc::TryRecvError::Empty) => break,
+                Err(mpsc::TryRecvError::Disconnected) => {
+                    stop = true;
+                    break;
+                }
+            }
+        }
+        worker.process_input(&mut perf);
+        if perf.sample_count > 0 {
+            perf.trace(&worker.perf_trace, &mut worker.perf_frame_seq);
+        }
+        worker.wake_if_new_output();
+        if stop {
+            return;
+        }
+        if park {
+            if lend.send(worker).is_err() {
+                return;
+            }
+            match resume.recv() {
+                Ok(resumed) => worker = resumed,
+                Err(_) => return,
+            }
+        }
+    }
+}
 fn duration_ms(duration: Duration) -> f64 {
     duration.as_secs_f64() * 1000.0
 }
@@ -1581,6 +3019,12 @@ fn collect_all_render_cache_node_ids(tree: &FlatRenderTree) -> Vec<NodeId> {
         .collect()
 }
 
+/// The root group's cache, i.e. the visible composite of the whole document.
+fn root_render_cache(tree: &FlatRenderTree) -> Option<&Image> {
+    let root_id = tree.root_id?;
+    tree.nodes.get(&root_id)?.kind.render_cache()
+}
+
 fn collect_manifest_raster_assets_from_node<'a>(
     node: &'a document::StoredLayerNode,
     output: &mut Vec<(u64, &'a str)>,
@@ -1598,18 +3042,18 @@ fn collect_manifest_raster_assets_from_node<'a>(
     }
 }
 
-fn save_png_rgba8(path: &Path, image: &StoredImage) -> Result<(), DocumentPackageError> {
+fn save_png(path: &Path, image: &StoredImage) -> Result<(), DocumentPackageError> {
     if let Some(parent) = path.parent()
         && !parent.as_os_str().is_empty()
     {
         std::fs::create_dir_all(parent)?;
     }
-    save_png_bytes(path, &encode_png_rgba8(image)?)?;
+    save_png_bytes(path, &encode_png(image)?)?;
     Ok(())
 }
 
-fn load_png_rgba8(path: &Path) -> Result<StoredImage, DocumentPackageError> {
-    decode_png_rgba8(&std::fs::read(path)?)
+fn load_png(path: &Path) -> Result<StoredImage, DocumentPackageError> {
+    decode_png(&std::fs::read(path)?)
 }
 
 fn save_png_bytes(path: &Path, png_bytes: &[u8]) -> Result<(), DocumentPackageError> {
@@ -1622,58 +3066,165 @@ fn save_png_bytes(path: &Path, png_bytes: &[u8]) -> Result<(), DocumentPackageEr
     Ok(())
 }
 
-fn encode_png_rgba8(image: &StoredImage) -> Result<Vec<u8>, DocumentPackageError> {
+/// Encodes at the image's own depth so 16-bit layers survive a save.
+fn encode_png(image: &StoredImage) -> Result<Vec<u8>, DocumentPackageError> {
     let mut bytes = Vec::new();
     let mut encoder = png::Encoder::new(&mut bytes, image.width(), image.height());
     encoder.set_color(png::ColorType::Rgba);
-    encoder.set_depth(png::BitDepth::Eight);
+    let data = match image.format() {
+        StoredPixelFormat::Rgba8 => {
+            encoder.set_depth(png::BitDepth::Eight);
+            image.pixels_rgba8().into_owned()
+        }
+        StoredPixelFormat::Rgba16 => {
+            encoder.set_depth(png::BitDepth::Sixteen);
+            image
+                .pixels_rgba16()
+                .iter()
+                .flat_map(|value| value.to_be_bytes())
+                .collect()
+        }
+    };
     let mut writer = encoder.write_header()?;
-    writer.write_image_data(image.pixels_rgba8())?;
+    writer.write_image_data(&data)?;
     drop(writer);
     Ok(bytes)
 }
 
-fn decode_png_rgba8(png_bytes: &[u8]) -> Result<StoredImage, DocumentPackageError> {
+fn decode_png(png_bytes: &[u8]) -> Result<StoredImage, DocumentPackageError> {
     let decoder = png::Decoder::new(std::io::Cursor::new(png_bytes));
     let mut reader = decoder.read_info()?;
     let mut buf = vec![0; reader.output_buffer_size()];
     let info = reader.next_frame(&mut buf)?;
     let bytes = &buf[..info.buffer_size()];
-    let pixels = match info.color_type {
-        png::ColorType::Rgba => bytes.to_vec(),
-        png::ColorType::Rgb => {
-            let mut rgba = Vec::with_capacity((info.width * info.height * 4) as usize);
-            for chunk in bytes.chunks_exact(3) {
-                rgba.extend_from_slice(&[chunk[0], chunk[1], chunk[2], 255]);
-            }
-            rgba
-        }
-        png::ColorType::GrayscaleAlpha => {
-            let mut rgba = Vec::with_capacity((info.width * info.height * 4) as usize);
-            for chunk in bytes.chunks_exact(2) {
-                rgba.extend_from_slice(&[chunk[0], chunk[0], chunk[0], chunk[1]]);
-            }
-            rgba
-        }
-        png::ColorType::Grayscale => {
-            let mut rgba = Vec::with_capacity((info.width * info.height * 4) as usize);
-            for value in bytes {
-                rgba.extend_from_slice(&[*value, *value, *value, 255]);
-            }
-            rgba
-        }
-        png::ColorType::Indexed => {
-            return Err(DocumentPackageError::Io(std::io::Error::new(
-                std::io::ErrorKind::InvalidData,
-                "indexed color png is not supported for document package",
-            )));
+    if info.color_type == png::ColorType::Indexed {
+        return Err(DocumentPackageError::Io(std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            "indexed color png is not supported for document package",
+        )));
+    }
+    let image = match info.bit_depth {
+        png::BitDepth::Sixteen => {
+            let samples: Vec<u16> = bytes
+                .chunks_exact(2)
+                .map(|pair| u16::from_be_bytes([pair[0], pair[1]]))
+                .collect();
+            let pixels = expand_png_samples_to_rgba(info.color_type, &samples, u16::MAX);
+            StoredImage::new_rgba16(info.width, info.height, pixels)
+        }
+        _ => {
+            let pixels = expand_png_samples_to_rgba(info.color_type, bytes, u8::MAX);
+            StoredImage::new_rgba8(info.width, info.height, pixels)
         }
     };
-    StoredImage::new_rgba8(info.width, info.height, pixels).map_err(|error| {
+    image.map_err(|error| {
         DocumentPackageError::Io(std::io::Error::new(std::io::ErrorKind::InvalidData, error))
     })
 }
 
+fn expand_png_samples_to_rgba<T: Copy>(
+    color_type: png::ColorType,
+    samples: &[T],
+    opaque: T,
+) -> Vec<T> {
+    match color_type {
+        png::ColorType::Rgba => samples.to_vec(),
+        png::ColorType::Rgb => samples
+            .chunks_exact(3)
+            .flat_map(|chunk| [chunk[0], chunk[1], chunk[2], opaque])
+            .collect(),
+        png::ColorType::GrayscaleAlpha => samples
+            .chunks_exact(2)
+            .flat_map(|chunk| [chunk[0], chunk[0], chunk[0], chunk[1]])
+            .collect(),
+        png::ColorType::Grayscale | png::ColorType::Indexed => samples
+            .iter()
+            .flat_map(|value| [*value, *value, *value, opaque])
+            .collect(),
+    }
+}
+
+/// Reads the name, metadata and thumbnail of a bundle file or package
+/// directory without decoding layer images.
+///
+/// Bundles are read only up to the layer payload, so cost does not grow
+/// with the number or size of layers.
+pub fn read_document_preview(path: &Path) -> Result<DocumentPreview, DocumentPackageError> {
+    let (manifest, thumbnail_png) = if path.is_dir() {
+        let manifest_file = File::open(path.join(PACKAGE_MANIFEST_FILE))?;
+        let manifest: DocumentStorageManifest =
+            serde_json::from_reader(BufReader::new(manifest_file))?;
+        let thumbnail_png = match std::fs::read(path.join(PACKAGE_THUMBNAIL_FILE)) {
+            Ok(bytes) => Some(bytes),
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => None,
+            Err(error) => return Err(error.into()),
+        };
+        (manifest, thumbnail_png)
+    } else {
+        read_bundle_preview_fields(GzDecoder::new(BufReader::new(File::open(path)?)))?
+    };
+    let thumbnail = thumbnail_png.as_deref().map(decode_png).transpose()?;
+    Ok(DocumentPreview {
+        name: manifest.name,
+        canvas_width: manifest.canvas_width,
+        canvas_height: manifest.canvas_height,
+        metadata: manifest.metadata,
+        thumbnail,
+    })
+}
+
+fn read_bundle_preview_fields(
+    reader: impl std::io::Read,
+) -> Result<(DocumentStorageManifest, Option<Vec<u8>>), DocumentPackageError> {
+    let mut deserializer = serde_json::Deserializer::from_reader(reader);
+    let fields = serde::Deserializer::deserialize_map(&mut deserializer, BundlePreviewVisitor)?;
+    let manifest = fields
+        .manifest
+        .ok_or_else(|| DocumentPackageError::Json(serde::de::Error::missing_field("manifest")))?;
+    Ok((manifest, fields.thumbnail_png))
+}
+
+#[derive(Default)]
+struct BundlePreviewFields {
+    manifest: Option<DocumentStorageManifest>,
+    thumbnail_png: Option<Vec<u8>>,
+}
+
+/// Collects the preview bundle fields and skips the layer payloads without decoding them.
+struct BundlePreviewVisitor;
+
+impl<'de> serde::de::Visitor<'de> for BundlePreviewVisitor {
+    type Value = BundlePreviewFields;
+
+    fn expecting(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "a document bundle object")
+    }
+
+    fn visit_map<A: serde::de::MapAccess<'de>>(
+        self,
+        mut map: A,
+    ) -> Result<BundlePreviewFields, A::Error> {
+        let mut fields = BundlePreviewFields::default();
+        while let Some(key) = map.next_key::<String>()? {
+            match key.as_str() {
+                "manifest" => fields.manifest = Some(map.next_value()?),
+                "thumbnail_png" => fields.thumbnail_png = map.next_value()?,
+                _ => {
+                    map.next_value::<serde::de::IgnoredAny>()?;
+                }
+            }
+        }
+        Ok(fields)
+    }
+}
+
+fn current_time_ms() -> u64 {
+    match SystemTime::now().duration_since(UNIX_EPOCH) {
+        Ok(duration) => duration.as_millis() as u64,
+        Err(_) => 0,
+    }
+}
+
 fn current_time_ns() -> u64 {
     match SystemTime::now().duration_since(UNIX_EPOCH) {
         Ok(duration) => duration.as_nanos() as u64,
@@ -1683,21 +3234,36 @@ fn current_time_ns() -> u64 {
 
 #[cfg(test)]
 mod tests {
-    use std::time::{SystemTime, UNIX_EPOCH};
+    use std::collections::HashMap;
+    use std::sync::{Arc, mpsc};
+    use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
-    use document::StoredLayerNode;
+    use brushes::builtin_brushes::round::RoundBrush;
+    use brushes::{BrushResamplerDistancePolicy, BrushSpec};
+    use document::{Document, FlatRenderTree, SharedRenderTree, StoredLayerNode};
     use flate2::{Compression, read::GzDecoder, write::GzEncoder};
-    use glaphica_core::{BrushId, NodeId, StrokeId, TileKey};
-    use images::StoredImage;
+    use glaphica_core::{
+        AtlasLayout, BackendId, BrushId, CanvasVec2, EpochId, IMAGE_TILE_SIZE, InputDeviceKind,
+        MappedCursor, NodeId, RadianVec2, RenderTreeGeneration, StrokeId, TileKey,
+    };
     use images::layout::ImageLayout;
+    use images::{StoredImage, StoredPixelFormat};
     use thread_protocol::{
         CopyOp, DrawBlendMode, DrawFrameMergePolicy, DrawOp, GpuCmdFrameMergeTag, GpuCmdMsg,
-        WriteBlendMode, WriteOp,
+        InputRingSample, WriteBlendMode, WriteOp,
     };
 
+    use threads::create_thread_channels;
+
+    use crate::config;
+    use crate::recovery::{RecoveryIoError, RecoveryJournal};
+    use crate::trace::{TraceAppControl, TraceInputFrame};
+
     use super::{
-        AppThreadIntegration, PackedDocumentFile, PackedLayerAsset, collect_manifest_raster_assets,
-        decode_png_rgba8, encode_png_rgba8, load_png_rgba8, save_png_rgba8,
+        AppControl, AppThreadIntegration, EngineFramePerf, EngineHost, EngineRequest,
+        EngineThreadMode, EngineThreadState, EngineWorker, InputControlEvent, PackedDocumentFile,
+        PackedLayerAsset, collect_manifest_raster_assets, decode_png, encode_png, load_png,
+        read_bundle_preview_fields, save_png,
     };
 
     #[test]
@@ -1929,13 +3495,23 @@ mod tests {
         )
         .unwrap();
 
-        save_png_rgba8(&path, &image).unwrap();
-        let loaded = load_png_rgba8(&path).unwrap();
+        save_png(&path, &image).unwrap();
+        let loaded = load_png(&path).unwrap();
 
         assert_eq!(loaded, image);
         let _ = std::fs::remove_dir_all(dir);
     }
 
+    #[test]
+    fn sixteen_bit_png_keeps_full_precision() {
+        let image =
+            StoredImage::new_rgba16(1, 2, vec![1, 2, 3, 65535, 40000, 257, 0, 12345]).unwrap();
+
+        let decoded = decode_png(&encode_png(&image).unwrap()).unwrap();
+
+        assert_eq!(decoded, image);
+    }
+
     #[test]
     fn packed_document_file_round_trip_through_gzip_json() {
         let package = PackedDocumentFile {
@@ -1961,11 +3537,19 @@ mod tests {
                 next_node_id: 10,
                 next_layer_label_index: 2,
                 next_group_label_index: 1,
+                metadata: document::StoredDocumentMetadata {
+                    author: "ada".to_string(),
+                    stroke_count: 3,
+                    ..Default::default()
+                },
             },
+            thumbnail_png: Some(
+                encode_png(&StoredImage::new_rgba8(1, 1, vec![5, 6, 7, 255]).unwrap()).unwrap(),
+            ),
             layers: vec![PackedLayerAsset {
                 node_id: 9,
                 file_name: "layers/9.png".to_string(),
-                png_bytes: encode_png_rgba8(
+                png_bytes: encode_png(
                     &StoredImage::new_rgba8(
                         2,
                         2,
@@ -1985,9 +3569,9 @@ mod tests {
         let decoded: PackedDocumentFile =
             serde_json::from_reader(GzDecoder::new(std::io::Cursor::new(compressed))).unwrap();
 
-        assert_eq!(decoded.manifest.name, "demo");
+        assert_eq!(decoded, package);
         assert_eq!(
-            decode_png_rgba8(&decoded.layers[0].png_bytes).unwrap(),
+            decode_png(&decoded.layers[0].png_bytes).unwrap(),
             StoredImage::new_rgba8(
                 2,
                 2,
@@ -1999,6 +3583,360 @@ mod tests {
         );
     }
 
+    #[test]
+    fn bundle_preview_skips_layer_payload() {
+        let thumbnail =
+            encode_png(&StoredImage::new_rgba8(1, 1, vec![5, 6, 7, 255]).unwrap()).unwrap();
+        let manifest = serde_json::json!({
+            "version": 1,
+            "name": "demo",
+            "canvas_width": 4,
+            "canvas_height": 2,
+            "root": { "kind": "branch", "id": 0, "label": "root", "visible": true,
+                "opacity": 1.0, "blend_mode": { "kind": "penetrate" }, "children": [] },
+            "active_node_id": null,
+            "next_node_id": 1,
+            "next_layer_label_index": 1,
+            "next_group_label_index": 1,
+            "metadata": { "author": "ada", "dpi": 300.0 },
+        });
+        // The layer payload does not match the layer schema; previews only skip over it.
+        let bundle = format!(
+            "{{\"manifest\":{manifest},\"thumbnail_png\":{},\"layers\":[{{\"bogus\":[1,2,3]}}]}}",
+            serde_json::to_string(&thumbnail).unwrap()
+        );
+
+        let (manifest, thumbnail_png) =
+            read_bundle_preview_fields(std::io::Cursor::new(bundle)).unwrap();
+
+        assert_eq!(manifest.name, "demo");
+        assert_eq!(manifest.metadata.author, "ada");
+        assert_eq!(manifest.metadata.dpi, 300.0);
+        assert_eq!(thumbnail_png, Some(thumbnail));
+    }
+
+    fn export_leaf_image(app: &mut AppThreadIntegration, node_id: NodeId) -> StoredImage {
+        app.flush_engine_output();
+        let image = app
+            .engine
+            .worker()
+            .engine_state
+            .document()
+            .get_leaf_image(node_id)
+            .unwrap();
+        app.main_state.export_layer_image(image).unwrap()
+    }
+
+    fn engine_thread_id(host: &EngineHost) -> Option<std::thread::ThreadId> {
+        host.thread
+            .as_ref()
+            .and_then(|thread| thread.handle.as_ref())
+            .map(|handle| handle.thread().id())
+    }
+
+    /// Replays a recorded trace on a GPU-less engine host and returns every
+    /// GPU command the engine sent, in order.
+    fn replay_recorded_command_stream(mode: EngineThreadMode, record: &str) -> Vec<GpuCmdMsg> {
+        let record_path = std::path::Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("../../test/records")
+            .join(record);
+        // The checked-in records are version 1 traces, whose only controls
+        // were untagged stroke boundaries.
+        let mut trace: serde_json::Value =
+            serde_json::from_slice(&std::fs::read(record_path).unwrap()).unwrap();
+        let frames = trace["frames"].as_array_mut().unwrap();
+        for frame in frames.iter_mut() {
+            for control in frame["controls"].as_array_mut().unwrap() {
+                *control = serde_json::json!({ "StrokeBoundary": control.take() });
+            }
+        }
+        let frames: Vec<TraceInputFrame> = serde_json::from_value(trace["frames"].take()).unwrap();
+        let document = Document::new(
+            "parity".to_string(),
+            ImageLayout::new(1024, 1024),
+            BackendId::new(0),
+            BackendId::new(1),
+        )
+        .unwrap();
+        let shared_tree = Arc::new(SharedRenderTree::new(FlatRenderTree {
+            generation: RenderTreeGeneration(0),
+            nodes: Arc::new(HashMap::new()),
+            root_id: None,
+        }));
+        let mut engine_state =
+            EngineThreadState::new(document, shared_tree, config::brush_processing::MAX_BRUSHES);
+        for _ in 0..2 {
+            engine_state
+                .backend_manager_mut()
+                .add_backend(AtlasLayout::Small11)
+                .unwrap();
+        }
+        let brush = RoundBrush::with_default_curves(6.0, 0.8).unwrap();
+        engine_state
+            .brush_runtime_mut()
+            .register_pipeline_with_stroke_buffer_backend(
+                BrushId(0),
+                brush.max_affected_radius_px(),
+                None,
+                brush,
+            )
+            .unwrap();
+        let paint_node = engine_state.document().active_paint_node().unwrap().0;
+        let (mut main_channels, engine_channels) = create_thread_channels(
+            config::thread_channels::MAIN_TO_ENGINE_INPUT_RING,
+            config::thread_channels::ENGINE_TO_MAIN_INPUT_CONTROL,
+            config::thread_channels::ENGINE_TO_MAIN_GPU_COMMAND,
+            config::thread_channels::MAIN_TO_ENGINE_FEEDBACK,
+        );
+        let (snapshots, _engine_snapshots) = mpsc::channel();
+        let mut host = EngineHost {
+            mode,
+            worker: Some(Box::new(EngineWorker::new(
+                engine_state,
+                engine_channels,
+                snapshots,
+            ))),
+            thread: None,
+        };
+        host.request(EngineRequest::SelectBrush(BrushId(0)));
+
+        let mut stream = Vec::new();
+        let mut drain = |stream: &mut Vec<GpuCmdMsg>| {
+            while let Ok(cmd) = main_channels.gpu_command_receiver.pop() {
+                stream.push(cmd);
+            }
+        };
+        let midpoint = frames.len() / 2;
+        let mut engine_thread_id_after_first_frame = None;
+        for (index, mut frame) in frames.into_iter().enumerate() {
+            for control in &mut frame.controls {
+                if let TraceAppControl::StrokeBoundary { node_id, .. } = control {
+                    *node_id = paint_node;
+                }
+            }
+            match host.frame_worker() {
+                Some(worker) => {
+                    worker.process_replay_frame(&frame, &mut EngineFramePerf::default())
+                }
+                None => host.request(EngineRequest::ReplayFrame(Box::new(frame))),
+            }
+            if index == 0 {
+                engine_thread_id_after_first_frame = engine_thread_id(&host);
+            }
+            if index == midpoint {
+                let brush = RoundBrush::with_default_curves(12.0, 0.6).unwrap();
+                let max_affected_radius_px = brush.max_affected_radius_px();
+                let resampler_distance = brush.resampler_distance();
+                host.round_trip(|reply| EngineRequest::UpdateBrush {
+                    brush_id: BrushId(0),
+                    max_affected_radius_px,
+                    resampler_distance,
+                    pipeline: Box::new(brush),
+                    reply,
+                })
+                .unwrap();
+            }
+            if index % 64 == 0 {
+                host.worker();
+            }
+            drain(&mut stream);
+        }
+        // Parking lends the worker out; the engine thread itself is never respawned.
+        let engine_thread = engine_thread_id(&host);
+        assert_eq!(engine_thread.is_some(), mode == EngineThreadMode::Dedicated);
+        assert_eq!(engine_thread, engine_thread_id_after_first_frame);
+        loop {
+            let worker = host.worker();
+            worker.send_pending_gpu_commands(&mut EngineFramePerf::default());
+            let pending = worker.pending_send_gpu_commands.len();
+            let before = stream.len();
+            drain(&mut stream);
+            if pending == 0 && stream.len() == before {
+                break;
+            }
+        }
+        stream
+    }
+
+    #[test]
+    fn dedicated_engine_thread_matches_inline_recorded_command_streams() {
+        for record in ["draw_a_circle_input.json", "large_brushes_input.json"] {
+            let inline = replay_recorded_command_stream(EngineThreadMode::Inline, record);
+            let dedicated = replay_recorded_command_stream(EngineThreadMode::Dedicated, record);
+
+            assert!(
+                inline.iter().any(|cmd| matches!(cmd, GpuCmdMsg::DrawOp(_))),
+                "{record} produced no draws"
+            );
+            assert_eq!(dedicated, inline, "{record}");
+        }
+    }
+
+    #[test]
+    fn dedicated_engine_thread_matches_inline_replay() {
+        let paint = |mode| {
+            let mut app = pollster::block_on(AppThreadIntegration::new(
+                "engine-thread".to_string(),
+                ImageLayout::new(256, 256),
+            ))
+            .ok()?;
+            app.set_engine_thread_mode(mode);
+            app.register_brush(
+                BrushId(0),
+                RoundBrush::with_default_curves(3.0, 0.8).unwrap(),
+            )
+            .unwrap();
+            app.set_active_brush(BrushId(0));
+            let node_id = app.active_paint_node().unwrap();
+            let boundary = |begin| {
+                vec![InputControlEvent::Control(AppControl::StrokeBoundary {
+                    node_id,
+                    begin,
+                })]
+            };
+            let samples: Vec<_> = (0..16)
+                .map(|index| InputRingSample {
+                    epoch: EpochId(0),
+                    time_ns: 1_000_000 * (index + 1),
+                    device: InputDeviceKind::Cursor,
+                    cursor: MappedCursor {
+                        cursor: CanvasVec2::new(20.0 + index as f32 * 8.0, 40.0 + index as f32),
+                        tilt: RadianVec2::new(0.0, 0.0),
+                        pressure: 1.0,
+                        twist: 0.0,
+                    },
+                })
+                .collect();
+            for frame in [
+                TraceInputFrame::from_runtime(&boundary(true), &[]),
+                TraceInputFrame::from_runtime(&[], &samples),
+                TraceInputFrame::from_runtime(&boundary(false), &[]),
+            ] {
+                app.process_replay_input_frame(&frame);
+            }
+            let image = export_leaf_image(&mut app, node_id);
+            Some((image, app.stats().undo_stroke_count))
+        };
+        let Some(inline) = paint(EngineThreadMode::Inline) else {
+            return;
+        };
+        let dedicated = paint(EngineThreadMode::Dedicated).unwrap();
+
+        assert_eq!(inline.1, 1);
+        assert_eq!(dedicated, inline);
+    }
+
+    #[test]
+    fn recovery_session_restores_strokes_after_unclean_shutdown() {
+        let unique = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_nanos();
+        let dir = std::env::temp_dir().join(format!("glaphica-recovery-session-{unique}"));
+        let open_session = || {
+            let mut app = pollster::block_on(AppThreadIntegration::new(
+                "recovery".to_string(),
+                ImageLayout::new(256, 256),
+            ))
+            .ok()?;
+            app.register_brush(
+                BrushId(0),
+                RoundBrush::with_default_curves(3.0, 0.8).unwrap(),
+            )
+            .unwrap();
+            app.set_active_brush(BrushId(0));
+            Some(app)
+        };
+        let Some(mut app) = open_session() else {
+            return;
+        };
+        app.begin_recovery_session(&dir).unwrap();
+
+        let node_id = app.active_paint_node().unwrap();
+        app.begin_stroke(node_id);
+        app.process_engine_frame(Duration::ZERO);
+        for index in 0..16 {
+            app.push_input_sample(InputRingSample {
+                epoch: EpochId(0),
+                time_ns: 1_000_000 * (index + 1),
+                device: InputDeviceKind::Cursor,
+                cursor: MappedCursor {
+                    cursor: CanvasVec2::new(20.0 + index as f32 * 8.0, 40.0),
+                    tilt: RadianVec2::new(0.0, 0.0),
+                    pressure: 1.0,
+                    twist: 0.0,
+                },
+            });
+        }
+        app.process_engine_frame(Duration::ZERO);
+        app.end_stroke();
+        app.process_engine_frame(Duration::ZERO);
+        let expected = export_leaf_image(&mut app, node_id);
+
+        assert!(!RecoveryJournal::has_unclean_session(&dir));
+        let mut concurrent = open_session().unwrap();
+        assert!(matches!(
+            concurrent.begin_recovery_session(&dir),
+            Err(RecoveryIoError::SessionInUse(_))
+        ));
+        drop(concurrent);
+        drop(app);
+
+        assert!(RecoveryJournal::has_unclean_session(&dir));
+        let mut reopened = open_session().unwrap();
+        let replayed = reopened.restore_recovery_session(&dir).unwrap();
+        let restored = export_leaf_image(&mut reopened, node_id);
+
+        assert!(replayed > 0);
+        assert_eq!(reopened.stats().undo_stroke_count, 1);
+        assert_eq!(restored, expected);
+
+        reopened.begin_recovery_session(&dir).unwrap();
+        reopened.end_recovery_session().unwrap();
+        assert!(!RecoveryJournal::has_unclean_session(&dir));
+    }
+
+    #[test]
+    fn sub_eight_bit_gradient_round_trips_through_document_atlas() {
+        let Ok(mut app) = pollster::block_on(AppThreadIntegration::new(
+            "gradient".to_string(),
+            ImageLayout::new(IMAGE_TILE_SIZE, 1),
+        )) else {
+            return;
+        };
+        let node_id = app.active_paint_node().unwrap();
+        // Neighbouring columns differ by far less than one 8-bit step (257).
+        let pixels: Vec<u16> = (0..IMAGE_TILE_SIZE as u16)
+            .flat_map(|x| {
+                let value = 4096 + x * 32;
+                [value, value, value, u16::MAX]
+            })
+            .collect();
+        let gradient = StoredImage::new_rgba16(IMAGE_TILE_SIZE, 1, pixels.clone()).unwrap();
+
+        let engine_state = &mut app.engine.worker().engine_state;
+        let backend = engine_state
+            .document()
+            .get_leaf_image(node_id)
+            .unwrap()
+            .backend();
+        let tile_key = engine_state.allocate_leaf_tile(backend).unwrap();
+        engine_state
+            .document_mut()
+            .get_leaf_image_mut(node_id)
+            .unwrap()
+            .set_tile_key(0, tile_key)
+            .unwrap();
+        assert!(app.main_state.upload_tile(tile_key, &gradient, 0));
+
+        let exported = export_leaf_image(&mut app, node_id);
+        assert_eq!(exported.format(), StoredPixelFormat::Rgba16);
+        for (actual, expected) in exported.pixels_rgba16().iter().zip(&pixels) {
+            // Half floats hold values in this range to within a few u16 steps.
+            assert!(actual.abs_diff(*expected) <= 4, "{actual} != {expected}");
+        }
+    }
+
     #[test]
     fn solid_white_document_root_image_fills_canvas() {
         let Ok(mut app) = pollster::block_on(AppThreadIntegration::new(
@@ -2007,7 +3945,7 @@ mod tests {
         )) else {
             return;
         };
-        let tree = app.engine_state.shared_tree().read();
+        let tree = app.engine.worker().engine_state.shared_tree().read();
         let root_id = tree.root_id.unwrap();
         let root_image = tree
             .nodes
@@ -0,0 +1,500 @@
+use std::collections::HashSet;
+use std::fmt::{Display, Formatter};
+use std::path::{Path, PathBuf};
+
+use document::{ImageCreateError, UiLayerTreeItem, UiNodeKind};
+use glaphica_core::{IMAGE_TILE_SIZE, NodeId};
+use images::layout::ImageLayout;
+use images::{NonEmptyTileBounds, StoredImage, StoredPixelFormat};
+
+use crate::ExportImageError;
+use crate::image_export::{self, ExportOptions, ExportRect};
+
+pub const DEFAULT_NAME_TEMPLATE: &str = "{index:2}-{label}";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LayerBatchScope {
+    /// Every raster leaf, wherever it sits in the tree.
+    Leaves,
+    /// Every group directly under the root, composited on its own.
+    TopLevelGroups,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LayerBatchBounds {
+    /// Non-empty tiles, tightened to the visible pixels inside them.
+    Trimmed,
+    FullCanvas,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct LayerBatchExportOptions {
+    pub scope: LayerBatchScope,
+    pub bounds: LayerBatchBounds,
+    pub include_hidden: bool,
+    /// File stem template; `{label}`, `{index}` and zero-padded `{index:N}`
+    /// are substituted.
+    pub name_template: String,
+    /// Format and pixel options; `crop` and `node_id` are set per target.
+    pub image: ExportOptions,
+}
+
+impl LayerBatchExportOptions {
+    pub fn new(image: ExportOptions) -> Self {
+        Self {
+            scope: LayerBatchScope::Leaves,
+            bounds: LayerBatchBounds::Trimmed,
+            include_hidden: false,
+            name_template: DEFAULT_NAME_TEMPLATE.to_string(),
+            image,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LayerBatchTarget {
+    pub node_id: NodeId,
+    pub label: String,
+    /// 1-based position in bottom-to-top order, counted before hidden
+    /// targets are filtered so names stay stable across toggles.
+    pub index: usize,
+    pub visible: bool,
+}
+
+#[derive(Debug, Default)]
+pub struct LayerBatchExportReport {
+    pub written: Vec<PathBuf>,
+    /// Targets with nothing to export under trimmed bounds.
+    pub skipped_empty: Vec<NodeId>,
+}
+
+#[derive(Debug)]
+pub enum LayerBatchExportError {
+    Io(std::io::Error),
+    RenderTree(ImageCreateError),
+    Export {
+        node_id: NodeId,
+        error: ExportImageError,
+    },
+}
+
+impl Display for LayerBatchExportError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Io(error) => write!(f, "layer batch export io error: {error}"),
+            Self::RenderTree(error) => {
+                write!(f, "layer batch export render tree error: {error:?}")
+            }
+            Self::Export { node_id, error } => {
+                write!(
+                    f,
+                    "layer batch export of node {} failed: {error}",
+                    node_id.0
+                )
+            }
+        }
+    }
+}
+
+impl std::error::Error for LayerBatchExportError {}
+
+/// Names, trims and writes each target's readback into one output folder.
+pub struct LayerBatchWriter<'a> {
+    output_dir: &'a Path,
+    options: &'a LayerBatchExportOptions,
+    layout: ImageLayout,
+    used_names: HashSet<String>,
+    report: LayerBatchExportReport,
+}
+
+impl<'a> LayerBatchWriter<'a> {
+    pub fn new(
+        output_dir: &'a Path,
+        options: &'a LayerBatchExportOptions,
+        layout: ImageLayout,
+    ) -> Result<Self, LayerBatchExportError> {
+        std::fs::create_dir_all(output_dir).map_err(LayerBatchExportError::Io)?;
+        Ok(Self {
+            output_dir,
+            options,
+            layout,
+            used_names: HashSet::new(),
+            report: LayerBatchExportReport::default(),
+        })
+    }
+
+    /// `tile_bounds` limits the trimmed search; `None` means nothing painted.
+    pub fn write(
+        &mut self,
+        target: &LayerBatchTarget,
+        stored: &StoredImage,
+        tile_bounds: Option<NonEmptyTileBounds>,
+    ) -> Result<(), LayerBatchExportError> {
+        let mut image_options = self.options.image.clone();
+        image_options.node_id = None;
+        image_options.crop = match self.options.bounds {
+            LayerBatchBounds::FullCanvas => None,
+            LayerBatchBounds::Trimmed => {
+                let trimmed = tile_bounds
+                    .and_then(|bounds| tile_bounds_rect(bounds, self.layout))
+                    .and_then(|rect| alpha_bounds_within(stored, rect));
+                let Some(rect) = trimmed else {
+                    self.report.skipped_empty.push(target.node_id);
+                    return Ok(());
+                };
+                Some(rect)
+            }
+        };
+
+        let file_name = batch_file_name(
+            &self.options.name_template,
+            target,
+            image_options.format.extension(),
+            &mut self.used_names,
+        );
+        let path = self.output_dir.join(file_name);
+        let export_error = |error| LayerBatchExportError::Export {
+            node_id: target.node_id,
+            error,
+        };
+        let prepared =
+            image_export::prepare_export_image(stored, &image_options).map_err(export_error)?;
+        image_export::write_export_image(&path, &prepared, &image_options).map_err(export_error)?;
+        self.report.written.push(path);
+        Ok(())
+    }
+
+    pub fn finish(self) -> LayerBatchExportReport {
+        self.report
+    }
+}
+
+/// Lists export targets under `root`, which is the document's root item.
+pub fn collect_batch_targets(
+    root: &UiLayerTreeItem,
+    scope: LayerBatchScope,
+    include_hidden: bool,
+) -> Vec<LayerBatchTarget> {
+    let mut targets = Vec::new();
+    match scope {
+        LayerBatchScope::Leaves => collect_raster_leaves(&root.children, true, &mut targets),
+        LayerBatchScope::TopLevelGroups => {
+            for child in &root.children {
+                if child.kind == UiNodeKind::Branch {
+                    targets.push(LayerBatchTarget {
+                        node_id: child.id,
+                        label: child.label.clone(),
+                        index: targets.len() + 1,
+                        visible: child.visible,
+                    });
+                }
+            }
+        }
+    }
+    if !include_hidden {
+        targets.retain(|target| target.visible);
+    }
+    targets
+}
+
+fn collect_raster_leaves(
+    items: &[UiLayerTreeItem],
+    parent_visible: bool,
+    output: &mut Vec<LayerBatchTarget>,
+) {
+    for item in items {
+        let visible = parent_visible && item.visible;
+        match item.kind {
+            UiNodeKind::Branch => collect_raster_leaves(&item.children, visible, output),
+            UiNodeKind::RasterLayer => output.push(LayerBatchTarget {
+                node_id: item.id,
+                label: item.label.clone(),
+                index: output.len() + 1,
+                visible,
+            }),
+            UiNodeKind::SpecialLayer => {}
+        }
+    }
+}
+
+/// Expands the name template and makes the result unique within `used`.
+pub fn batch_file_name(
+    template: &str,
+    target: &LayerBatchTarget,
+    extension: &str,
+    used: &mut HashSet<String>,
+) -> String {
+    let mut stem = String::new();
+    let mut rest = template;
+    while let Some(open) = rest.find('{') {
+        // An unclosed brace is literal text; the tail push below copies it once.
+        let Some(close) = rest[open..].find('}') else {
+            break;
+        };
+        stem.push_str(&rest[..open]);
+        let token = &rest[open + 1..open + close];
+        match token.split_once(':') {
+            None if token == "label" => stem.push_str(&target.label),
+            None if token == "index" => stem.push_str(&target.index.to_string()),
+            Some(("index", width)) if width.parse::<usize>().is_ok() => {
+                let width = width.parse::<usize>().unwrap_or(0);
+                stem.push_str(&format!("{:0width$}", target.index));
+            }
+            _ => stem.push_str(&rest[open..=open + close]),
+        }
+        rest = &rest[open + close + 1..];
+    }
+    stem.push_str(rest);
+
+    let mut stem = sanitize_file_stem(&stem);
+    if stem.is_empty() {
+        stem = format!("layer-{}", target.index);
+    }
+    let mut file_name = format!("{stem}.{extension}");
+    let mut suffix = 2;
+    while !used.insert(file_name.clone()) {
+        file_name = format!("{stem}-{suffix}.{extension}");
+        suffix += 1;
+    }
+    file_name
+}
+
+fn sanitize_file_stem(stem: &str) -> String {
+    stem.chars()
+        .map(|ch| match ch {
+            '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|' => '_',
+            ch if ch.is_control() => '_',
+            ch => ch,
+        })
+        .collect::<String>()
+        .trim()
+        .trim_matches('.')
+        .to_string()
+}
+
+pub fn tile_bounds_rect(bounds: NonEmptyTileBounds, layout: ImageLayout) -> Option<ExportRect> {
+    let x0 = bounds.min_tile_x.saturating_mul(IMAGE_TILE_SIZE);
+    let y0 = bounds.min_tile_y.saturating_mul(IMAGE_TILE_SIZE);
+    let x1 = (bounds.max_tile_x.saturating_add(1))
+        .saturating_mul(IMAGE_TILE_SIZE)
+        .min(layout.size_x());
+    let y1 = (bounds.max_tile_y.saturating_add(1))
+        .saturating_mul(IMAGE_TILE_SIZE)
+        .min(layout.size_y());
+    (x1 > x0 && y1 > y0).then_some(ExportRect {
+        x: x0,
+        y: y0,
+        width: x1 - x0,
+        height: y1 - y0,
+    })
+}
+
+pub fn union_tile_bounds(
+    a: Option<NonEmptyTileBounds>,
+    b: Option<NonEmptyTileBounds>,
+) -> Option<NonEmptyTileBounds> {
+    match (a, b) {
+        (Some(a), Some(b)) => Some(NonEmptyTileBounds {
+            min_tile_x: a.min_tile_x.min(b.min_tile_x),
+            min_tile_y: a.min_tile_y.min(b.min_tile_y),
+            max_tile_x: a.max_tile_x.max(b.max_tile_x),
+            max_tile_y: a.max_tile_y.max(b.max_tile_y),
+        }),
+        (a, None) => a,
+        (None, b) => b,
+    }
+}
+
+/// Shrinks `rect` to the pixels with non-zero alpha, or `None` if all are clear.
+pub fn alpha_bounds_within(image: &StoredImage, rect: ExportRect) -> Option<ExportRect> {
+    let x_end = rect.x.saturating_add(rect.width).min(image.width());
+    let y_end = rect.y.saturating_add(rect.height).min(image.height());
+    let bounds = match image.format() {
+        StoredPixelFormat::Rgba8 => {
+            alpha_bounds(&image.pixels_rgba8(), image.width(), rect, x_end, y_end)
+        }
+        StoredPixelFormat::Rgba16 => {
+            alpha_bounds(&image.pixels_rgba16(), image.width(), rect, x_end, y_end)
+        }
+    };
+    bounds.map(|(min_x, min_y, max_x, max_y)| ExportRect {
+        x: min_x,
+        y: min_y,
+        width: max_x - min_x + 1,
+        height: max_y - min_y + 1,
+    })
+}
+
+fn alpha_bounds<T: Copy + Default + PartialEq>(
+    pixels: &[T],
+    image_width: u32,
+    rect: ExportRect,
+    x_end: u32,
+    y_end: u32,
+) -> Option<(u32, u32, u32, u32)> {
+    let mut bounds: Option<(u32, u32, u32, u32)> = None;
+    for y in rect.y..y_end {
+        for x in rect.x..x_end {
+            let alpha_offset = (y as usize * image_width as usize + x as usize) * 4 + 3;
+            if pixels[alpha_offset] == T::default() {
+                continue;
+            }
+            bounds = Some(match bounds {
+                Some((min_x, min_y, max_x, max_y)) => {
+                    (min_x.min(x), min_y.min(y), max_x.max(x), max_y.max(y))
+                }
+                None => (x, y, x, y),
+            });
+        }
+    }
+    bounds
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use document::{UiBlendMode, UiLayerTreeItem, UiNodeKind};
+    use glaphica_core::{IMAGE_TILE_SIZE, NodeId};
+    use images::layout::ImageLayout;
+    use images::{NonEmptyTileBounds, StoredImage};
+
+    use super::{
+        LayerBatchScope, LayerBatchTarget, alpha_bounds_within, batch_file_name,
+        collect_batch_targets, tile_bounds_rect,
+    };
+    use crate::image_export::ExportRect;
+
+    fn item(id: u64, label: &str, kind: UiNodeKind, visible: bool) -> UiLayerTreeItem {
+        UiLayerTreeItem {
+            id: NodeId(id),
+            label: label.to_string(),
+            visible,
+            opacity: 1.0,
+            blend_mode: UiBlendMode::Normal,
+            kind,
+            solid_color: None,
+            children: Vec::new(),
+        }
+    }
+
+    fn sample_tree() -> UiLayerTreeItem {
+        let mut hidden_group = item(2, "Arms", UiNodeKind::Branch, false);
+        hidden_group
+            .children
+            .push(item(3, "Left", UiNodeKind::RasterLayer, true));
+        let mut root = item(0, "Root", UiNodeKind::Branch, true);
+        root.children = vec![
+            item(1, "Body", UiNodeKind::RasterLayer, true),
+            hidden_group,
+            item(4, "Fill", UiNodeKind::SpecialLayer, true),
+            item(5, "Head", UiNodeKind::RasterLayer, true),
+        ];
+        root
+    }
+
+    #[test]
+    fn leaves_inherit_hidden_ancestors_and_keep_stable_indices() {
+        let all = collect_batch_targets(&sample_tree(), LayerBatchScope::Leaves, true);
+        let ids: Vec<_> = all.iter().map(|target| target.node_id.0).collect();
+        assert_eq!(ids, vec![1, 3, 5]);
+        assert!(!all[1].visible);
+
+        let visible = collect_batch_targets(&sample_tree(), LayerBatchScope::Leaves, false);
+        let indices: Vec<_> = visible.iter().map(|target| target.index).collect();
+        assert_eq!(indices, vec![1, 3]);
+
+        let groups = collect_batch_targets(&sample_tree(), LayerBatchScope::TopLevelGroups, false);
+        assert!(groups.is_empty());
+    }
+
+    #[test]
+    fn file_names_expand_template_sanitize_and_dedupe() {
+        let target = LayerBatchTarget {
+            node_id: NodeId(7),
+            label: "arm/left".to_string(),
+            index: 3,
+            visible: true,
+        };
+        let mut used = HashSet::new();
+        assert_eq!(
+            batch_file_name("{index:3}_{label}", &target, "png", &mut used),
+            "003_arm_left.png"
+        );
+        assert_eq!(
+            batch_file_name("{index:3}_{label}", &target, "png", &mut used),
+            "003_arm_left-2.png"
+        );
+        assert_eq!(
+            batch_file_name("{label}{unknown}", &target, "tiff", &mut used),
+            "arm_left{unknown}.tiff"
+        );
+    }
+
+    #[test]
+    fn file_names_keep_unclosed_and_unknown_placeholders_literal() {
+        let target = LayerBatchTarget {
+            node_id: NodeId(1),
+            label: "Body".to_string(),
+            index: 2,
+            visible: true,
+        };
+        let mut used = HashSet::new();
+        assert_eq!(
+            batch_file_name("layer_{label", &target, "png", &mut used),
+            "layer_{label.png"
+        );
+        assert_eq!(
+            batch_file_name("{index}_{label", &target, "png", &mut used),
+            "2_{label.png"
+        );
+        assert_eq!(
+            batch_file_name("{index:x}_{size}", &target, "png", &mut used),
+            "{index_x}_{size}.png"
+        );
+    }
+
+    #[test]
+    fn trimmed_bounds_clamp_to_canvas_then_tighten_to_alpha() {
+        let layout = ImageLayout::new(IMAGE_TILE_SIZE + 10, IMAGE_TILE_SIZE);
+        let rect = tile_bounds_rect(
+            NonEmptyTileBounds {
+                min_tile_x: 1,
+                min_tile_y: 0,
+                max_tile_x: 1,
+                max_tile_y: 0,
+            },
+            layout,
+        )
+        .unwrap();
+        assert_eq!(
+            rect,
+            ExportRect {
+                x: IMAGE_TILE_SIZE,
+                y: 0,
+                width: 10,
+                height: IMAGE_TILE_SIZE,
+            }
+        );
+
+        let mut pixels = vec![0u8; 4 * 4 * 4];
+        pixels[(4 + 1) * 4 + 3] = 255;
+        pixels[(2 * 4 + 2) * 4 + 3] = 1;
+        let image = StoredImage::new_rgba8(4, 4, pixels).unwrap();
+        let full = ExportRect {
+            x: 0,
+            y: 0,
+            width: 4,
+            height: 4,
+        };
+        assert_eq!(
+            alpha_bounds_within(&image, full),
+            Some(ExportRect {
+                x: 1,
+                y: 1,
+                width: 2,
+                height: 2,
+            })
+        );
+    }
+}
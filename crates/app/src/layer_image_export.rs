@@ -11,6 +11,7 @@ pub enum LayerImageExportError {
     BufferMap(wgpu::BufferAsyncError),
     MapChannelRecv(mpsc::RecvError),
     StoredImage(images::StoredImageError),
+    UnsupportedTextureFormat(wgpu::TextureFormat),
 }
 
 impl From<images::StoredImageError> for LayerImageExportError {
@@ -39,12 +40,11 @@ impl LayerImageExporter {
             return Err(LayerImageExportError::InvalidOutputSize);
         }
 
-        let bytes_per_pixel = 4usize;
         let width_usize =
             usize::try_from(width).map_err(|_| LayerImageExportError::InvalidOutputSize)?;
         let height_usize =
             usize::try_from(height).map_err(|_| LayerImageExportError::InvalidOutputSize)?;
-        let mut pixels = vec![0; width_usize * height_usize * bytes_per_pixel];
+        let mut high_precision = false;
         let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
             label: Some("glaphica-layer-image-export-encoder"),
         });
@@ -71,7 +71,11 @@ impl LayerImageExporter {
             let Some(resolved) = atlas_storage.resolve(tile_key) else {
                 return Err(LayerImageExportError::MissingTileAddress { tile_key });
             };
-            let bytes_per_row = sample_width.saturating_mul(4);
+            let texel_format = ReadbackTexelFormat::from_wgpu(resolved.format).ok_or(
+                LayerImageExportError::UnsupportedTextureFormat(resolved.format),
+            )?;
+            high_precision |= texel_format == ReadbackTexelFormat::Rgba16Float;
+            let bytes_per_row = sample_width.saturating_mul(texel_format.bytes_per_pixel());
             let padded_bytes_per_row = bytes_per_row.div_ceil(256).saturating_mul(256);
             let buffer_size = u64::from(padded_bytes_per_row) * u64::from(sample_height);
             let buffer = device.create_buffer(&wgpu::BufferDescriptor {
@@ -112,6 +116,7 @@ impl LayerImageExporter {
                 tile_origin_y,
                 sample_width,
                 sample_height,
+                texel_format,
                 padded_bytes_per_row: usize::try_from(padded_bytes_per_row)
                     .map_err(|_| LayerImageExportError::InvalidOutputSize)?,
                 buffer,
@@ -120,27 +125,14 @@ impl LayerImageExporter {
 
         queue.submit(Some(encoder.finish()));
 
-        for readback in readbacks {
-            let buffer_slice = readback.buffer.slice(..);
-            let (sender, receiver) = mpsc::channel();
-            buffer_slice.map_async(wgpu::MapMode::Read, move |result| {
-                if let Err(send_error) = sender.send(result) {
-                    eprintln!("layer image export map callback send failed: {send_error}");
-                }
-            });
-            let _ = device.poll(wgpu::PollType::wait_indefinitely());
-            let map_result = receiver
-                .recv()
-                .map_err(LayerImageExportError::MapChannelRecv)?;
-            map_result.map_err(LayerImageExportError::BufferMap)?;
-
-            let mapped = buffer_slice.get_mapped_range();
-            scatter_tile_readback(&mut pixels, width_usize, &mapped, &readback)?;
-            drop(mapped);
-            readback.buffer.unmap();
+        let sample_len = width_usize * height_usize * 4;
+        if high_precision {
+            let pixels = read_back_pixels::<u16>(device, readbacks, width_usize, sample_len)?;
+            Ok(StoredImage::new_rgba16(width, height, pixels)?)
+        } else {
+            let pixels = read_back_pixels::<u8>(device, readbacks, width_usize, sample_len)?;
+            Ok(StoredImage::new_rgba8(width, height, pixels)?)
         }
-
-        StoredImage::new_rgba8(width, height, pixels).map_err(Into::into)
     }
 }
 
@@ -155,12 +147,117 @@ struct TileReadback {
     tile_origin_y: u32,
     sample_width: u32,
     sample_height: u32,
+    texel_format: ReadbackTexelFormat,
     padded_bytes_per_row: usize,
     buffer: wgpu::Buffer,
 }
 
-fn scatter_tile_readback(
-    dst_pixels: &mut [u8],
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ReadbackTexelFormat {
+    Rgba8Unorm,
+    Bgra8Unorm,
+    Rgba16Float,
+}
+
+impl ReadbackTexelFormat {
+    fn from_wgpu(format: wgpu::TextureFormat) -> Option<Self> {
+        match format {
+            wgpu::TextureFormat::Rgba8Unorm => Some(Self::Rgba8Unorm),
+            wgpu::TextureFormat::Bgra8Unorm => Some(Self::Bgra8Unorm),
+            wgpu::TextureFormat::Rgba16Float => Some(Self::Rgba16Float),
+            _ => None,
+        }
+    }
+
+    fn bytes_per_pixel(self) -> u32 {
+        match self {
+            Self::Rgba8Unorm | Self::Bgra8Unorm => 4,
+            Self::Rgba16Float => 8,
+        }
+    }
+}
+
+/// Channel type of an export buffer: `u8` unless a tile was read from a
+/// 16-bit atlas.
+trait ExportChannel: Copy + Default {
+    /// Decodes one texel into straight RGBA channels.
+    fn decode(format: ReadbackTexelFormat, texel: &[u8], output: &mut [Self]);
+}
+
+impl ExportChannel for u8 {
+    fn decode(format: ReadbackTexelFormat, texel: &[u8], output: &mut [Self]) {
+        match format {
+            ReadbackTexelFormat::Rgba8Unorm => output.copy_from_slice(&texel[..4]),
+            ReadbackTexelFormat::Bgra8Unorm => {
+                output.copy_from_slice(&[texel[2], texel[1], texel[0], texel[3]]);
+            }
+            ReadbackTexelFormat::Rgba16Float => {
+                for (dst, bits) in output.iter_mut().zip(texel.chunks_exact(2)) {
+                    let value = half::f16::from_le_bytes([bits[0], bits[1]]).to_f32();
+                    *dst = (value.clamp(0.0, 1.0) * 255.0).round() as u8;
+                }
+            }
+        }
+    }
+}
+
+impl ExportChannel for u16 {
+    fn decode(format: ReadbackTexelFormat, texel: &[u8], output: &mut [Self]) {
+        match format {
+            ReadbackTexelFormat::Rgba8Unorm => {
+                for (dst, src) in output.iter_mut().zip(texel) {
+                    *dst = u16::from(*src) * 257;
+                }
+            }
+            ReadbackTexelFormat::Bgra8Unorm => {
+                for (dst, src) in output
+                    .iter_mut()
+                    .zip([texel[2], texel[1], texel[0], texel[3]])
+                {
+                    *dst = u16::from(src) * 257;
+                }
+            }
+            ReadbackTexelFormat::Rgba16Float => {
+                for (dst, bits) in output.iter_mut().zip(texel.chunks_exact(2)) {
+                    let value = half::f16::from_le_bytes([bits[0], bits[1]]).to_f32();
+                    *dst = (value.clamp(0.0, 1.0) * 65535.0).round() as u16;
+                }
+            }
+        }
+    }
+}
+
+fn read_back_pixels<C: ExportChannel>(
+    device: &wgpu::Device,
+    readbacks: Vec<TileReadback>,
+    image_width: usize,
+    sample_len: usize,
+) -> Result<Vec<C>, LayerImageExportError> {
+    let mut pixels = vec![C::default(); sample_len];
+    for readback in readbacks {
+        let buffer_slice = readback.buffer.slice(..);
+        let (sender, receiver) = mpsc::channel();
+        buffer_slice.map_async(wgpu::MapMode::Read, move |result| {
+            if let Err(send_error) = sender.send(result) {
+                eprintln!("layer image export map callback send failed: {send_error}");
+            }
+        });
+        let _ = device.poll(wgpu::PollType::wait_indefinitely());
+        let map_result = receiver
+            .recv()
+            .map_err(LayerImageExportError::MapChannelRecv)?;
+        map_result.map_err(LayerImageExportError::BufferMap)?;
+
+        let mapped = buffer_slice.get_mapped_range();
+        scatter_tile_readback(&mut pixels, image_width, &mapped, &readback)?;
+        drop(mapped);
+        readback.buffer.unmap();
+    }
+    Ok(pixels)
+}
+
+fn scatter_tile_readback<C: ExportChannel>(
+    dst_pixels: &mut [C],
     image_width: usize,
     mapped: &[u8],
     readback: &TileReadback,
@@ -173,14 +270,20 @@ fn scatter_tile_readback(
         .map_err(|_| LayerImageExportError::InvalidOutputSize)?;
     let sample_height = usize::try_from(readback.sample_height)
         .map_err(|_| LayerImageExportError::InvalidOutputSize)?;
-    let bytes_per_row = sample_width * 4;
+    let bytes_per_pixel = readback.texel_format.bytes_per_pixel() as usize;
+    let bytes_per_row = sample_width * bytes_per_pixel;
 
     for row in 0..sample_height {
         let src_start = row * readback.padded_bytes_per_row;
         let src_end = src_start + bytes_per_row;
         let dst_start = ((tile_origin_y + row) * image_width + tile_origin_x) * 4;
-        let dst_end = dst_start + bytes_per_row;
-        dst_pixels[dst_start..dst_end].copy_from_slice(&mapped[src_start..src_end]);
+        let dst_end = dst_start + sample_width * 4;
+        for (texel, dst) in mapped[src_start..src_end]
+            .chunks_exact(bytes_per_pixel)
+            .zip(dst_pixels[dst_start..dst_end].chunks_exact_mut(4))
+        {
+            C::decode(readback.texel_format, texel, dst);
+        }
     }
 
     Ok(())
@@ -1,9 +1,15 @@
+pub mod brush_presets;
 pub mod config;
 mod engine_thread;
+pub mod image_export;
+mod input_batching;
 mod integration;
+pub mod layer_batch_export;
 mod layer_image_export;
 mod layer_preview;
 mod main_thread;
+pub mod preferences;
+pub mod recovery;
 mod screen_blitter;
 pub mod trace;
 
@@ -12,7 +18,9 @@ mod screen_blitter_test;
 
 pub use engine_thread::EngineThreadState;
 pub use integration::{
-    AppControl, AppStats, AppThreadIntegration, DocumentPackageError, GpuError, TileAllocReceipt,
+    AppControl, AppStats, AppThreadIntegration, DocumentPackageError, DocumentPreview,
+    EngineFramePerf, EngineThreadMode, FileBrushReload, FileBrushReloadError, GpuError, InputStats,
+    TileAllocReceipt, read_document_preview,
 };
 pub use layer_image_export::{LayerImageExportError, LayerImageExporter};
 pub use layer_preview::LayerPreviewBitmap;
@@ -16,15 +16,16 @@ use glaphica_core::{
 use gpu_runtime::{
     FrameBatch, FrameBatchContext, FrameBatchPerfStats, GpuContext, GpuContextInitDescriptor,
     RenderContext, RenderExecutor,
-    atlas_runtime::AtlasStorageRuntime,
+    atlas_runtime::{AtlasStorageRuntime, AtlasTextureConfig},
     brush_runtime::{BrushGpuRuntime, validate_draw_op_layout},
     surface_runtime::{SurfaceError, SurfaceRuntime},
-    wgpu_brush_executor::WgpuBrushExecutorError,
+    wgpu_brush_executor::{WgpuBrushContext, WgpuBrushExecutorError},
 };
 use thread_protocol::{GpuCmdMsg, RenderTreeUpdatedMsg, TileSlotKeyUpdateMsg};
 
 use crate::{
     config,
+    image_export::{self, ExportOptions},
     layer_image_export::{LayerImageExportError, LayerImageExporter},
     layer_preview::{LayerPreviewBitmap, LayerPreviewRenderer, PreviewSource},
     screen_blitter::ScreenBlitter,
@@ -313,13 +314,17 @@ impl MainThreadState {
     pub async fn init_with_gpu_context(gpu_context: Arc<GpuContext>) -> Result<Self, InitError> {
         let mut atlas_storage =
             AtlasStorageRuntime::with_capacity(config::atlas_storage::INITIAL_BACKEND_CAPACITY);
+        let document_texture_config = AtlasTextureConfig {
+            format: to_wgpu_texture_format(config::atlas_storage::DOCUMENT_FORMAT),
+            ..Default::default()
+        };
         atlas_storage
             .create_backend(
                 &gpu_context.device,
                 0,
                 BackendKind::Leaf,
                 AtlasLayout::Small11,
-                Default::default(),
+                document_texture_config,
             )
             .map_err(InitError::Atlas)?;
         atlas_storage
@@ -328,7 +333,7 @@ impl MainThreadState {
                 1,
                 BackendKind::BranchCache,
                 AtlasLayout::Small11,
-                Default::default(),
+                document_texture_config,
             )
             .map_err(InitError::Atlas)?;
 
@@ -377,13 +382,13 @@ impl MainThreadState {
             .register_layout(brush_id, layout)
             .map_err(BrushRegisterError::Layout)?;
         self.brush_pipeline_registry
-            .register_pipeline_spec(brush_id, spec)
+            .register_pipeline_spec(brush_id, spec.clone())
             .map_err(BrushRegisterError::Pipeline)?;
 
         let cache_backend_id = match brush.cache_backend_kind() {
             Some(kind) => {
                 let id = self.next_brush_cache_backend_id;
-                let mut texture_config = gpu_runtime::atlas_runtime::AtlasTextureConfig::default();
+                let mut texture_config = AtlasTextureConfig::default();
                 if let Some(format) = spec.cache_backend_format {
                     texture_config.format = to_wgpu_texture_format(format);
                 }
@@ -410,6 +415,38 @@ impl MainThreadState {
         Ok(cache_backend_id)
     }
 
+    /// Swaps a registered brush's shader; the caller validates the source.
+    ///
+    /// The executor builds the new pipelines before anything is replaced, so
+    /// on error the brush keeps drawing with its previous shader.
+    pub fn reload_brush_shader(
+        &mut self,
+        brush_id: BrushId,
+        wgsl_source: std::borrow::Cow<'static, str>,
+    ) -> Result<(), BrushRegisterError> {
+        let leaf_format = self
+            .atlas_storage
+            .backend_resource(0)
+            .map(|backend| backend.format)
+            .ok_or(BrushRegisterError::Executor(
+                WgpuBrushExecutorError::MissingTargetAtlasBackend { brush_id },
+            ))?;
+        let context = WgpuBrushContext {
+            gpu_context: &self.gpu_context,
+            atlas_storage: &self.atlas_storage,
+        };
+        pollster::block_on(self.brush_runtime.executor_mut().reload_brush_shader(
+            &context,
+            brush_id,
+            wgsl_source.clone(),
+            leaf_format,
+        ))
+        .map_err(BrushRegisterError::Executor)?;
+        self.brush_pipeline_registry
+            .replace_wgsl_source(brush_id, wgsl_source)
+            .map_err(BrushRegisterError::Pipeline)
+    }
+
     pub fn set_shared_tree(&mut self, shared_tree: Arc<SharedRenderTree>) {
         self.shared_tree = shared_tree;
         let tree = self.shared_tree.read();
@@ -432,15 +469,40 @@ impl MainThreadState {
         )
     }
 
-    pub fn upload_tile_rgba8(&self, tile_key: TileKey, rgba8: &[u8]) -> bool {
+    /// Writes one tile of `image` into `tile_key`, converting to the atlas
+    /// format. Returns `false` if the tile or format cannot be uploaded.
+    pub fn upload_tile(
+        &self,
+        tile_key: TileKey,
+        image: &images::StoredImage,
+        tile_index: usize,
+    ) -> bool {
         let Some(resolved) = self.atlas_storage.resolve(tile_key) else {
             return false;
         };
-        let expected_len =
-            (glaphica_core::IMAGE_TILE_SIZE * glaphica_core::IMAGE_TILE_SIZE * 4) as usize;
-        if rgba8.len() != expected_len {
-            return false;
-        }
+        let (texels, bytes_per_pixel) = match resolved.format {
+            wgpu::TextureFormat::Rgba8Unorm => {
+                let mut rgba8 = Vec::new();
+                if image.copy_tile_rgba8(tile_index, &mut rgba8).is_err() {
+                    return false;
+                }
+                (rgba8, 4)
+            }
+            wgpu::TextureFormat::Rgba16Float => {
+                let mut rgba16 = Vec::new();
+                if image.copy_tile_rgba16(tile_index, &mut rgba16).is_err() {
+                    return false;
+                }
+                let texels = rgba16
+                    .iter()
+                    .flat_map(|value| {
+                        half::f16::from_f32(f32::from(*value) / 65535.0).to_le_bytes()
+                    })
+                    .collect();
+                (texels, 8)
+            }
+            _ => return false,
+        };
         self.gpu_context.queue.write_texture(
             wgpu::TexelCopyTextureInfo {
                 texture: resolved.texture2d_array,
@@ -452,10 +514,10 @@ impl MainThreadState {
                 },
                 aspect: wgpu::TextureAspect::All,
             },
-            rgba8,
+            &texels,
             wgpu::TexelCopyBufferLayout {
                 offset: 0,
-                bytes_per_row: Some(glaphica_core::IMAGE_TILE_SIZE * 4),
+                bytes_per_row: Some(glaphica_core::IMAGE_TILE_SIZE * bytes_per_pixel),
                 rows_per_image: Some(glaphica_core::IMAGE_TILE_SIZE),
             },
             wgpu::Extent3d {
@@ -965,33 +1027,48 @@ impl MainThreadState {
         Ok(())
     }
 
-    pub fn export_jpeg_image(&mut self, output_path: &Path) -> Result<(), ExportImageError> {
-        if !matches_jpeg_extension(output_path) {
+    pub fn export_image(
+        &mut self,
+        output_path: &Path,
+        options: &ExportOptions,
+    ) -> Result<(), ExportImageError> {
+        if !options.format.matches_path(output_path) {
             return Err(ExportImageError::InvalidExtension);
         }
-        if let Some(parent_dir) = output_path.parent()
-            && !parent_dir.as_os_str().is_empty()
-        {
-            std::fs::create_dir_all(parent_dir).map_err(ExportImageError::Io)?;
-        }
 
+        let stored = self.read_node_image(options.node_id)?;
+        let prepared = image_export::prepare_export_image(&stored, options)?;
+        image_export::write_export_image(output_path, &prepared, options)
+    }
+
+    /// Reads back a node's render image, or the root composite for `None`.
+    pub fn read_node_image(
+        &mut self,
+        node_id: Option<NodeId>,
+    ) -> Result<images::StoredImage, ExportImageError> {
         let tree = self.shared_tree.read();
-        let Some(root_id) = tree.root_id else {
-            return Err(ExportImageError::MissingDocumentImage);
+        let target_id = match node_id {
+            Some(node_id) => node_id,
+            None => tree.root_id.ok_or(ExportImageError::MissingDocumentImage)?,
         };
-        let Some(root_node) = tree.nodes.get(&root_id) else {
-            return Err(ExportImageError::MissingDocumentImage);
+        let missing_image = || match node_id {
+            Some(node_id) => ExportImageError::MissingNodeImage(node_id),
+            None => ExportImageError::MissingDocumentImage,
         };
-        let Some(image) = root_node.kind.render_image() else {
-            return Err(ExportImageError::MissingDocumentImage);
+        let Some(node) = tree.nodes.get(&target_id) else {
+            return Err(missing_image());
         };
-        let stored = self.layer_image_exporter.export(
-            &self.gpu_context.device,
-            &self.gpu_context.queue,
-            &self.atlas_storage,
-            image,
-        )?;
-        save_jpeg_rgba8(output_path, &stored)
+        let Some(image) = node.kind.render_image() else {
+            return Err(missing_image());
+        };
+        self.layer_image_exporter
+            .export(
+                &self.gpu_context.device,
+                &self.gpu_context.queue,
+                &self.atlas_storage,
+                image,
+            )
+            .map_err(Into::into)
     }
 
     fn read_final_image_rgba8(
@@ -1118,22 +1195,20 @@ impl MainThreadState {
     }
 }
 
-fn matches_jpeg_extension(path: &Path) -> bool {
-    path.extension()
-        .and_then(|ext| ext.to_str())
-        .is_some_and(|ext| ext.eq_ignore_ascii_case("jpg") || ext.eq_ignore_ascii_case("jpeg"))
-}
-
 fn map_export_image_error_to_screenshot(error: ExportImageError) -> ScreenshotError {
     match error {
         ExportImageError::InvalidExtension
         | ExportImageError::MissingDocumentImage
+        | ExportImageError::MissingNodeImage(_)
         | ExportImageError::InvalidSize
         | ExportImageError::LayerExport(_) => ScreenshotError::InvalidSize,
         ExportImageError::Io(error) => ScreenshotError::Io(error),
         ExportImageError::Map(error) => ScreenshotError::Map(error),
         ExportImageError::MapChannel(error) => ScreenshotError::MapChannel(error),
-        ExportImageError::Jpeg(_) => ScreenshotError::InvalidSize,
+        ExportImageError::Png(error) => ScreenshotError::Png(error),
+        ExportImageError::Jpeg(_) | ExportImageError::Webp(_) | ExportImageError::Tiff(_) => {
+            ScreenshotError::InvalidSize
+        }
     }
 }
 
@@ -1325,24 +1400,36 @@ impl std::error::Error for ScreenshotError {}
 pub enum ExportImageError {
     InvalidExtension,
     MissingDocumentImage,
+    MissingNodeImage(NodeId),
     InvalidSize,
     Io(std::io::Error),
     Map(wgpu::BufferAsyncError),
     MapChannel(std::sync::mpsc::RecvError),
+    Png(png::EncodingError),
     Jpeg(jpeg_encoder::EncodingError),
+    Webp(webp::WebPEncodingError),
+    Tiff(tiff::TiffError),
     LayerExport(LayerImageExportError),
 }
 
 impl Display for ExportImageError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
-            Self::InvalidExtension => write!(f, "export path must end with .jpg or .jpeg"),
+            Self::InvalidExtension => {
+                write!(f, "export path extension does not match the export format")
+            }
             Self::MissingDocumentImage => write!(f, "document render image is unavailable"),
+            Self::MissingNodeImage(node_id) => {
+                write!(f, "render image for node {} is unavailable", node_id.0)
+            }
             Self::InvalidSize => write!(f, "invalid export image size"),
             Self::Io(error) => write!(f, "export image io error: {error}"),
             Self::Map(error) => write!(f, "export image map error: {error}"),
             Self::MapChannel(error) => write!(f, "export image map channel error: {error}"),
+            Self::Png(error) => write!(f, "export image png error: {error}"),
             Self::Jpeg(error) => write!(f, "export image jpeg error: {error}"),
+            Self::Webp(error) => write!(f, "export image webp error: {error:?}"),
+            Self::Tiff(error) => write!(f, "export image tiff error: {error}"),
             Self::LayerExport(error) => write!(f, "export image layer export error: {error:?}"),
         }
     }
@@ -1356,32 +1443,12 @@ impl From<LayerImageExportError> for ExportImageError {
     }
 }
 
-fn save_jpeg_rgba8(path: &Path, image: &images::StoredImage) -> Result<(), ExportImageError> {
-    let mut rgb_pixels = Vec::with_capacity(image.pixels_rgba8().len() / 4 * 3);
-    for rgba in image.pixels_rgba8().chunks_exact(4) {
-        rgb_pixels.extend_from_slice(&rgba[..3]);
-    }
-
-    let file = std::fs::File::create(path).map_err(ExportImageError::Io)?;
-    let encoder = jpeg_encoder::Encoder::new(file, 90);
-    let jpeg_width = u16::try_from(image.width()).map_err(|_| ExportImageError::InvalidSize)?;
-    let jpeg_height = u16::try_from(image.height()).map_err(|_| ExportImageError::InvalidSize)?;
-    encoder
-        .encode(
-            &rgb_pixels,
-            jpeg_width,
-            jpeg_height,
-            jpeg_encoder::ColorType::Rgb,
-        )
-        .map_err(ExportImageError::Jpeg)?;
-    Ok(())
-}
-
 #[cfg(test)]
 mod tests {
-    use super::compact_round_draws;
-    use brushes::builtin_brushes::round::ROUND_DRAW_LAYOUT;
+    use super::{BrushRegisterError, MainThreadState, compact_round_draws};
+    use brushes::builtin_brushes::round::{ROUND_DRAW_LAYOUT, RoundBrush};
     use glaphica_core::{BrushId, NodeId, StrokeId, TileKey};
+    use gpu_runtime::wgpu_brush_executor::WgpuBrushExecutorError;
     use thread_protocol::{DrawBlendMode, DrawFrameMergePolicy, DrawOp, GpuCmdMsg};
 
     #[test]
@@ -1417,4 +1484,50 @@ mod tests {
         assert_eq!(&draw_op.input[..6], &[1.0; 6]);
         assert_eq!(&draw_op.input[6..], &[2.0; 6]);
     }
+
+    #[test]
+    fn reload_rejecting_shader_keeps_previous_source() {
+        let Ok(mut state) = pollster::block_on(MainThreadState::init()) else {
+            eprintln!("skipping: no GPU adapter available");
+            return;
+        };
+        let brush_id = BrushId(0);
+        state
+            .register_brush(
+                brush_id,
+                &RoundBrush::with_default_curves(4.0, 0.8).unwrap(),
+            )
+            .unwrap();
+        let original = state
+            .brush_pipeline_registry
+            .pipeline_spec(brush_id)
+            .unwrap();
+
+        // Valid WGSL, but it binds a group the brush pipeline layout lacks.
+        let mismatched = format!(
+            "@group(3) @binding(0) var<uniform> extra: vec4<f32>;\n\
+             @vertex fn {}() -> @builtin(position) vec4<f32> {{ return extra; }}\n\
+             @fragment fn {}() -> @location(0) vec4<f32> {{ return vec4<f32>(1.0); }}\n",
+            original.vertex_entry, original.fragment_entry
+        );
+        let result = state.reload_brush_shader(brush_id, mismatched.into());
+        assert!(
+            matches!(
+                result,
+                Err(BrushRegisterError::Executor(
+                    WgpuBrushExecutorError::PipelineValidationFailed { .. }
+                ))
+            ),
+            "{result:?}"
+        );
+        let kept = state
+            .brush_pipeline_registry
+            .pipeline_spec(brush_id)
+            .unwrap();
+        assert_eq!(kept.wgsl_source, original.wgsl_source);
+
+        state
+            .reload_brush_shader(brush_id, original.wgsl_source.clone())
+            .unwrap();
+    }
 }
@@ -0,0 +1,218 @@
+use std::fmt::{Display, Formatter};
+use std::fs::File;
+use std::io::{BufReader, BufWriter};
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+use stroke_input::{DeviceCalibration, DeviceCalibrations, PRESSURE_CURVE_POINTS, PressureCurve};
+
+use crate::config;
+
+const PREFERENCES_VERSION: u32 = 1;
+
+#[derive(Debug)]
+pub enum PreferencesError {
+    Io(std::io::Error),
+    Json(serde_json::Error),
+    UnsupportedVersion(u32),
+}
+
+impl Display for PreferencesError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Io(error) => write!(f, "preferences io error: {error}"),
+            Self::Json(error) => write!(f, "preferences json error: {error}"),
+            Self::UnsupportedVersion(version) => {
+                write!(f, "unsupported preferences version: {version}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for PreferencesError {}
+
+impl From<std::io::Error> for PreferencesError {
+    fn from(value: std::io::Error) -> Self {
+        Self::Io(value)
+    }
+}
+
+impl From<serde_json::Error> for PreferencesError {
+    fn from(value: serde_json::Error) -> Self {
+        Self::Json(value)
+    }
+}
+
+/// `$XDG_CONFIG_HOME` or `~/.config`, falling back to the temp dir.
+pub fn user_config_home() -> PathBuf {
+    std::env::var_os("XDG_CONFIG_HOME")
+        .filter(|value| !value.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))
+        .unwrap_or_else(std::env::temp_dir)
+}
+
+/// Serialized form of [`DeviceCalibration`].
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct StoredDeviceCalibration {
+    pub pressure_curve: [[f32; 2]; PRESSURE_CURVE_POINTS],
+    #[serde(default)]
+    pub tilt_deadzone: f32,
+}
+
+impl Default for StoredDeviceCalibration {
+    fn default() -> Self {
+        Self::from(DeviceCalibration::default())
+    }
+}
+
+impl From<DeviceCalibration> for StoredDeviceCalibration {
+    fn from(value: DeviceCalibration) -> Self {
+        Self {
+            pressure_curve: value.pressure.points,
+            tilt_deadzone: value.tilt_deadzone,
+        }
+    }
+}
+
+impl From<StoredDeviceCalibration> for DeviceCalibration {
+    fn from(value: StoredDeviceCalibration) -> Self {
+        Self {
+            pressure: PressureCurve {
+                points: value.pressure_curve,
+            },
+            tilt_deadzone: value.tilt_deadzone,
+        }
+    }
+}
+
+/// Serialized form of [`DeviceCalibrations`].
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+pub struct StoredDeviceCalibrations {
+    #[serde(default)]
+    pub pen: StoredDeviceCalibration,
+    #[serde(default)]
+    pub cursor: StoredDeviceCalibration,
+    #[serde(default)]
+    pub finger: StoredDeviceCalibration,
+}
+
+impl From<DeviceCalibrations> for StoredDeviceCalibrations {
+    fn from(value: DeviceCalibrations) -> Self {
+        Self {
+            pen: value.pen.into(),
+            cursor: value.cursor.into(),
+            finger: value.finger.into(),
+        }
+    }
+}
+
+impl From<StoredDeviceCalibrations> for DeviceCalibrations {
+    fn from(value: StoredDeviceCalibrations) -> Self {
+        Self {
+            pen: value.pen.into(),
+            cursor: value.cursor.into(),
+            finger: value.finger.into(),
+        }
+    }
+}
+
+/// Settings that follow the user across documents, kept in one JSON file.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct UserPreferences {
+    pub version: u32,
+    #[serde(default)]
+    pub device_calibration: StoredDeviceCalibrations,
+}
+
+impl Default for UserPreferences {
+    fn default() -> Self {
+        Self {
+            version: PREFERENCES_VERSION,
+            device_calibration: StoredDeviceCalibrations::default(),
+        }
+    }
+}
+
+impl UserPreferences {
+    pub fn default_path() -> PathBuf {
+        user_config_home().join(config::preferences::DEFAULT_FILE_NAME)
+    }
+
+    /// Reads `path`; a missing file reads as the defaults.
+    pub fn load(path: &Path) -> Result<Self, PreferencesError> {
+        let file = match File::open(path) {
+            Ok(file) => file,
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => {
+                return Ok(Self::default());
+            }
+            Err(error) => return Err(error.into()),
+        };
+        let preferences: Self = serde_json::from_reader(BufReader::new(file))?;
+        if preferences.version > PREFERENCES_VERSION {
+            return Err(PreferencesError::UnsupportedVersion(preferences.version));
+        }
+        Ok(preferences)
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), PreferencesError> {
+        if let Some(parent) = path.parent()
+            && !parent.as_os_str().is_empty()
+        {
+            std::fs::create_dir_all(parent)?;
+        }
+        serde_json::to_writer_pretty(BufWriter::new(File::create(path)?), self)?;
+        Ok(())
+    }
+
+    pub fn device_calibrations(&self) -> DeviceCalibrations {
+        self.device_calibration.into()
+    }
+
+    pub fn set_device_calibrations(&mut self, calibrations: DeviceCalibrations) {
+        self.device_calibration = calibrations.into();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{SystemTime, UNIX_EPOCH};
+
+    use stroke_input::{DeviceCalibration, DeviceCalibrations, PressureCurve};
+
+    use super::UserPreferences;
+
+    #[test]
+    fn preferences_round_trip_and_default_when_missing() {
+        let unique = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_nanos();
+        let root = std::env::temp_dir().join(format!("glaphica-preferences-{unique}"));
+        let path = root.join("nested/preferences.json");
+        assert_eq!(
+            UserPreferences::load(&path).unwrap(),
+            UserPreferences::default()
+        );
+
+        let mut preferences = UserPreferences::default();
+        preferences.set_device_calibrations(DeviceCalibrations {
+            pen: DeviceCalibration {
+                pressure: PressureCurve {
+                    points: [[0.1, 0.0], [0.2, 0.3], [0.3, 0.6], [0.4, 0.8], [0.5, 1.0]],
+                },
+                tilt_deadzone: 0.05,
+            },
+            ..DeviceCalibrations::default()
+        });
+        preferences.save(&path).unwrap();
+
+        let loaded = UserPreferences::load(&path).unwrap();
+        assert_eq!(loaded, preferences);
+        assert_eq!(
+            loaded.device_calibrations().pen.pressure.eval(0.45),
+            preferences.device_calibrations().pen.pressure.eval(0.45)
+        );
+        let _ = std::fs::remove_dir_all(root);
+    }
+}
@@ -0,0 +1,479 @@
+use std::fmt::{Display, Formatter};
+use std::fs::{File, TryLockError};
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::DocumentPackageError;
+use crate::config;
+use crate::trace::TraceInputFrame;
+
+const RECOVERY_VERSION: u32 = 1;
+const LOCK_FILE_NAME: &str = "session.lock";
+const JOURNAL_FILE_NAME: &str = "journal.jsonl";
+const JOURNAL_TEMP_FILE_NAME: &str = "journal.jsonl.tmp";
+const CHECKPOINT_FILE_PREFIX: &str = "checkpoint-";
+const CHECKPOINT_FILE_EXTENSION: &str = "glaphica";
+
+#[derive(Debug)]
+pub enum RecoveryIoError {
+    Io(std::io::Error),
+    Json(serde_json::Error),
+    Document(DocumentPackageError),
+    UnsupportedVersion(u32),
+    MissingJournal(PathBuf),
+    MissingCheckpoint(PathBuf),
+    SessionInUse(PathBuf),
+}
+
+impl Display for RecoveryIoError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Io(error) => write!(f, "recovery io error: {error}"),
+            Self::Json(error) => write!(f, "recovery json error: {error}"),
+            Self::Document(error) => write!(f, "recovery document error: {error}"),
+            Self::UnsupportedVersion(version) => {
+                write!(f, "unsupported recovery journal version: {version}")
+            }
+            Self::MissingJournal(path) => {
+                write!(f, "recovery journal missing: {}", path.display())
+            }
+            Self::MissingCheckpoint(path) => {
+                write!(f, "recovery checkpoint missing: {}", path.display())
+            }
+            Self::SessionInUse(path) => {
+                write!(
+                    f,
+                    "recovery directory in use by another session: {}",
+                    path.display()
+                )
+            }
+        }
+    }
+}
+
+impl std::error::Error for RecoveryIoError {}
+
+impl From<std::io::Error> for RecoveryIoError {
+    fn from(value: std::io::Error) -> Self {
+        Self::Io(value)
+    }
+}
+
+impl From<serde_json::Error> for RecoveryIoError {
+    fn from(value: serde_json::Error) -> Self {
+        Self::Json(value)
+    }
+}
+
+impl From<DocumentPackageError> for RecoveryIoError {
+    fn from(value: DocumentPackageError) -> Self {
+        Self::Document(value)
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+struct RecoveryJournalHeader {
+    version: u32,
+    checkpoint: u64,
+}
+
+/// One committed edit recorded after the journal's checkpoint.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum RecoveryJournalEntry {
+    /// Brush state that applies to the strokes recorded after it.
+    Brush {
+        brush_id: u64,
+        rgb: [f32; 3],
+        erase: bool,
+    },
+    Input(TraceInputFrame),
+    UndoStroke,
+    RedoStroke,
+}
+
+/// Contents of a recovery directory left behind by an unclean shutdown.
+#[derive(Debug, Clone)]
+pub struct RecoveryJournalFile {
+    pub checkpoint_path: PathBuf,
+    pub entries: Vec<RecoveryJournalEntry>,
+}
+
+/// Exclusive OS lock on a recovery directory's lock file.
+///
+/// The lock file records the owner pid and stays on disk until
+/// [`RecoveryJournal::discard`]; the OS releases the lock itself when the
+/// owning process exits, so a lock file nobody holds marks an unclean session.
+#[derive(Debug)]
+pub struct RecoverySessionLock {
+    dir: PathBuf,
+    _file: File,
+}
+
+impl RecoverySessionLock {
+    pub fn acquire(dir: &Path) -> Result<Self, RecoveryIoError> {
+        std::fs::create_dir_all(dir)?;
+        let mut file = std::fs::OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(dir.join(LOCK_FILE_NAME))?;
+        match file.try_lock() {
+            Ok(()) => {}
+            Err(TryLockError::WouldBlock) => {
+                return Err(RecoveryIoError::SessionInUse(dir.to_path_buf()));
+            }
+            Err(TryLockError::Error(error)) => return Err(error.into()),
+        }
+        file.set_len(0)?;
+        file.write_all(std::process::id().to_string().as_bytes())?;
+        file.flush()?;
+        Ok(Self {
+            dir: dir.to_path_buf(),
+            _file: file,
+        })
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Returns true when a live process currently holds the lock for `dir`.
+    pub fn is_held(dir: &Path) -> bool {
+        let Ok(file) = std::fs::OpenOptions::new()
+            .write(true)
+            .open(dir.join(LOCK_FILE_NAME))
+        else {
+            return false;
+        };
+        matches!(file.try_lock(), Err(TryLockError::WouldBlock))
+    }
+}
+
+/// Write-ahead journal of committed edits on top of a document checkpoint.
+///
+/// Entries are buffered while a stroke is in progress and appended to disk on
+/// [`RecoveryJournal::commit`], so an interrupted stroke never reaches the
+/// journal half-written. The journal holds the directory's
+/// [`RecoverySessionLock`] for as long as it lives.
+#[derive(Debug)]
+pub struct RecoveryJournal {
+    lock: RecoverySessionLock,
+    checkpoint: u64,
+    writer: BufWriter<File>,
+    pending_entries: Vec<RecoveryJournalEntry>,
+    committed_entry_count: usize,
+}
+
+impl RecoveryJournal {
+    pub fn default_dir() -> PathBuf {
+        std::env::temp_dir().join(config::recovery::DEFAULT_DIR_NAME)
+    }
+
+    pub fn checkpoint_path(dir: &Path, checkpoint: u64) -> PathBuf {
+        dir.join(format!(
+            "{CHECKPOINT_FILE_PREFIX}{checkpoint}.{CHECKPOINT_FILE_EXTENSION}"
+        ))
+    }
+
+    /// Returns true when a previous session exited without discarding its
+    /// journal and no running session still owns the directory.
+    pub fn has_unclean_session(dir: &Path) -> bool {
+        dir.join(LOCK_FILE_NAME).is_file()
+            && dir.join(JOURNAL_FILE_NAME).is_file()
+            && !RecoverySessionLock::is_held(dir)
+    }
+
+    /// Returns a checkpoint number greater than any checkpoint present in `dir`.
+    pub fn next_checkpoint(dir: &Path) -> u64 {
+        list_checkpoints(dir)
+            .into_iter()
+            .map(|(checkpoint, _)| checkpoint)
+            .max()
+            .map_or(1, |checkpoint| checkpoint + 1)
+    }
+
+    /// Starts a journal in the locked directory on top of an already written
+    /// checkpoint.
+    pub fn start(lock: RecoverySessionLock, checkpoint: u64) -> Result<Self, RecoveryIoError> {
+        let writer = write_journal_header(lock.dir(), checkpoint)?;
+        Ok(Self {
+            lock,
+            checkpoint,
+            writer,
+            pending_entries: Vec::new(),
+            committed_entry_count: 0,
+        })
+    }
+
+    /// Moves the journal onto a newer checkpoint while keeping the session lock.
+    pub fn rotate(&mut self, checkpoint: u64) -> Result<(), RecoveryIoError> {
+        self.writer = write_journal_header(self.lock.dir(), checkpoint)?;
+        self.checkpoint = checkpoint;
+        self.pending_entries.clear();
+        self.committed_entry_count = 0;
+        Ok(())
+    }
+
+    pub fn dir(&self) -> &Path {
+        self.lock.dir()
+    }
+
+    pub fn checkpoint(&self) -> u64 {
+        self.checkpoint
+    }
+
+    pub fn committed_entry_count(&self) -> usize {
+        self.committed_entry_count
+    }
+
+    pub fn record(&mut self, entry: RecoveryJournalEntry) {
+        self.pending_entries.push(entry);
+    }
+
+    pub fn commit(&mut self) -> Result<(), RecoveryIoError> {
+        if self.pending_entries.is_empty() {
+            return Ok(());
+        }
+        for entry in &self.pending_entries {
+            serde_json::to_writer(&mut self.writer, entry)?;
+            self.writer.write_all(b"\n")?;
+        }
+        self.writer.flush()?;
+        self.committed_entry_count += self.pending_entries.len();
+        self.pending_entries.clear();
+        Ok(())
+    }
+
+    /// Loads the journal and checkpoint location left in `dir`.
+    ///
+    /// A trailing line without a newline is the tail of an interrupted append
+    /// and is ignored.
+    pub fn load(dir: &Path) -> Result<RecoveryJournalFile, RecoveryIoError> {
+        let journal_path = dir.join(JOURNAL_FILE_NAME);
+        if !journal_path.is_file() {
+            return Err(RecoveryIoError::MissingJournal(journal_path));
+        }
+        let contents = std::fs::read_to_string(&journal_path)?;
+        let mut lines = contents
+            .split_inclusive('\n')
+            .filter(|line| line.ends_with('\n'));
+        let Some(header_line) = lines.next() else {
+            return Err(RecoveryIoError::MissingJournal(journal_path));
+        };
+        let header: RecoveryJournalHeader = serde_json::from_str(header_line)?;
+        if header.version != RECOVERY_VERSION {
+            return Err(RecoveryIoError::UnsupportedVersion(header.version));
+        }
+        let checkpoint_path = Self::checkpoint_path(dir, header.checkpoint);
+        if !checkpoint_path.is_file() {
+            return Err(RecoveryIoError::MissingCheckpoint(checkpoint_path));
+        }
+
+        let mut entries = Vec::new();
+        for line in lines {
+            entries.push(serde_json::from_str(line)?);
+        }
+        Ok(RecoveryJournalFile {
+            checkpoint_path,
+            entries,
+        })
+    }
+
+    /// Removes the lock, journal and checkpoints from `dir`.
+    pub fn discard(dir: &Path) -> Result<(), RecoveryIoError> {
+        for file_name in [LOCK_FILE_NAME, JOURNAL_FILE_NAME, JOURNAL_TEMP_FILE_NAME] {
+            match std::fs::remove_file(dir.join(file_name)) {
+                Ok(()) => {}
+                Err(error) if error.kind() == std::io::ErrorKind::NotFound => {}
+                Err(error) => return Err(error.into()),
+            }
+        }
+        for (_, path) in list_checkpoints(dir) {
+            std::fs::remove_file(path)?;
+        }
+        let _ = std::fs::remove_dir(dir);
+        Ok(())
+    }
+}
+
+/// Atomically replaces the journal in `dir` with a header for `checkpoint`,
+/// then removes every other checkpoint.
+fn write_journal_header(dir: &Path, checkpoint: u64) -> Result<BufWriter<File>, RecoveryIoError> {
+    let temp_path = dir.join(JOURNAL_TEMP_FILE_NAME);
+    {
+        let mut writer = BufWriter::new(File::create(&temp_path)?);
+        serde_json::to_writer(
+            &mut writer,
+            &RecoveryJournalHeader {
+                version: RECOVERY_VERSION,
+                checkpoint,
+            },
+        )?;
+        writer.write_all(b"\n")?;
+        writer.flush()?;
+    }
+    let journal_path = dir.join(JOURNAL_FILE_NAME);
+    std::fs::rename(&temp_path, &journal_path)?;
+
+    for (stale_checkpoint, path) in list_checkpoints(dir) {
+        if stale_checkpoint != checkpoint {
+            let _ = std::fs::remove_file(path);
+        }
+    }
+
+    let file = std::fs::OpenOptions::new()
+        .append(true)
+        .open(&journal_path)?;
+    Ok(BufWriter::new(file))
+}
+
+fn list_checkpoints(dir: &Path) -> Vec<(u64, PathBuf)> {
+    let Ok(read_dir) = std::fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut checkpoints = Vec::new();
+    for entry in read_dir.flatten() {
+        let path = entry.path();
+        if path.extension().and_then(|ext| ext.to_str()) != Some(CHECKPOINT_FILE_EXTENSION) {
+            continue;
+        }
+        let Some(checkpoint) = path
+            .file_stem()
+            .and_then(|stem| stem.to_str())
+            .and_then(|stem| stem.strip_prefix(CHECKPOINT_FILE_PREFIX))
+            .and_then(|value| value.parse::<u64>().ok())
+        else {
+            continue;
+        };
+        checkpoints.push((checkpoint, path));
+    }
+    checkpoints
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::{Path, PathBuf};
+    use std::time::{SystemTime, UNIX_EPOCH};
+
+    use crate::trace::TraceInputFrame;
+
+    use super::{
+        JOURNAL_FILE_NAME, RecoveryIoError, RecoveryJournal, RecoveryJournalEntry,
+        RecoverySessionLock,
+    };
+
+    fn start(dir: &Path, checkpoint: u64) -> RecoveryJournal {
+        RecoveryJournal::start(RecoverySessionLock::acquire(dir).unwrap(), checkpoint).unwrap()
+    }
+
+    fn unique_dir(name: &str) -> PathBuf {
+        let unique = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_nanos();
+        std::env::temp_dir().join(format!("glaphica-recovery-{name}-{unique}"))
+    }
+
+    #[test]
+    fn journal_round_trips_committed_entries_only() {
+        let dir = unique_dir("round-trip");
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(RecoveryJournal::checkpoint_path(&dir, 1), b"checkpoint").unwrap();
+
+        let mut journal = start(&dir, 1);
+        journal.record(RecoveryJournalEntry::Brush {
+            brush_id: 0,
+            rgb: [0.0, 0.5, 1.0],
+            erase: false,
+        });
+        journal.record(RecoveryJournalEntry::Input(TraceInputFrame {
+            controls: Vec::new(),
+            samples: Vec::new(),
+        }));
+        journal.commit().unwrap();
+        journal.record(RecoveryJournalEntry::UndoStroke);
+        drop(journal);
+
+        assert!(RecoveryJournal::has_unclean_session(&dir));
+        let loaded = RecoveryJournal::load(&dir).unwrap();
+        assert_eq!(
+            loaded.checkpoint_path,
+            RecoveryJournal::checkpoint_path(&dir, 1)
+        );
+        assert_eq!(loaded.entries.len(), 2);
+        assert!(matches!(
+            loaded.entries[0],
+            RecoveryJournalEntry::Brush { brush_id: 0, .. }
+        ));
+
+        RecoveryJournal::discard(&dir).unwrap();
+        assert!(!RecoveryJournal::has_unclean_session(&dir));
+        assert!(!dir.exists());
+    }
+
+    #[test]
+    fn load_ignores_interrupted_trailing_line() {
+        let dir = unique_dir("torn");
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(RecoveryJournal::checkpoint_path(&dir, 3), b"checkpoint").unwrap();
+        let mut journal = start(&dir, 3);
+        journal.record(RecoveryJournalEntry::RedoStroke);
+        journal.commit().unwrap();
+        drop(journal);
+
+        let journal_path = dir.join(JOURNAL_FILE_NAME);
+        let mut contents = std::fs::read_to_string(&journal_path).unwrap();
+        contents.push_str("{\"Input\":{\"controls\":[");
+        std::fs::write(&journal_path, contents).unwrap();
+
+        let loaded = RecoveryJournal::load(&dir).unwrap();
+        assert_eq!(loaded.entries.len(), 1);
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn start_keeps_only_the_new_checkpoint() {
+        let dir = unique_dir("rotate");
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(RecoveryJournal::checkpoint_path(&dir, 1), b"old").unwrap();
+        assert_eq!(RecoveryJournal::next_checkpoint(&dir), 2);
+        std::fs::write(RecoveryJournal::checkpoint_path(&dir, 2), b"new").unwrap();
+
+        let mut journal = start(&dir, 2);
+        assert_eq!(journal.checkpoint(), 2);
+        assert!(!RecoveryJournal::checkpoint_path(&dir, 1).exists());
+        assert!(RecoveryJournal::checkpoint_path(&dir, 2).exists());
+
+        std::fs::write(RecoveryJournal::checkpoint_path(&dir, 3), b"newer").unwrap();
+        journal.rotate(3).unwrap();
+        assert_eq!(RecoveryJournal::load(&dir).unwrap().entries.len(), 0);
+        assert!(!RecoveryJournal::checkpoint_path(&dir, 2).exists());
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn live_session_is_not_unclean_and_cannot_be_taken_over() {
+        let dir = unique_dir("live");
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(RecoveryJournal::checkpoint_path(&dir, 1), b"checkpoint").unwrap();
+        let mut first = start(&dir, 1);
+        first.record(RecoveryJournalEntry::UndoStroke);
+        first.commit().unwrap();
+
+        assert!(!RecoveryJournal::has_unclean_session(&dir));
+        assert!(matches!(
+            RecoverySessionLock::acquire(&dir),
+            Err(RecoveryIoError::SessionInUse(_))
+        ));
+        assert_eq!(RecoveryJournal::load(&dir).unwrap().entries.len(), 1);
+
+        drop(first);
+        assert!(RecoveryJournal::has_unclean_session(&dir));
+        let second = start(&dir, 1);
+        assert!(!RecoveryJournal::has_unclean_session(&dir));
+        drop(second);
+        RecoveryJournal::discard(&dir).unwrap();
+    }
+}
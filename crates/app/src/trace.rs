@@ -9,6 +9,11 @@ use glaphica_core::{
     RenderTreeGeneration, StrokeId, TileKey,
 };
 use serde::{Deserialize, Serialize};
+use stroke_input::{
+    ExponentialMovingAverageConfig, LazyNibConfig, MovingWindowConfig, OneEuroConfig,
+    ResamplerKind, ShapeTaper, SmoothingConfig, StrokeShape, StrokeTaper, Symmetry, SymmetryMode,
+    SyntheticPressure, VelocityPressureConfig,
+};
 use thread_protocol::{
     ClearOp, CompositeBlendMode, CompositeOp, CopyOp, DrawBlendMode, DrawFrameMergePolicy, DrawOp,
     GpuCmdFrameMergeTag, GpuCmdMsg, InputControlEvent, InputRingSample, RefImage,
@@ -16,8 +21,12 @@ use thread_protocol::{
 };
 
 use crate::AppControl;
+use crate::preferences::StoredDeviceCalibrations;
 
-const TRACE_VERSION: u32 = 2;
+const TRACE_VERSION: u32 = 3;
+/// Version 2 traces predate [`TraceAppControl::SetResampler`]; they were all
+/// recorded with the linear resampler, which is still the default.
+const OLDEST_TRACE_VERSION: u32 = 2;
 
 #[derive(Debug)]
 pub enum TraceIoError {
@@ -75,12 +84,15 @@ pub struct TraceOutputFrame {
     pub commands: Vec<TraceGpuCmd>,
 }
 
-#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum TraceAppControl {
     StrokeBoundary {
         node_id: u64,
         begin: bool,
     },
+    CancelStroke {
+        node_id: u64,
+    },
     SelectNode {
         node_id: u64,
     },
@@ -107,6 +119,81 @@ pub enum TraceAppControl {
     },
     MoveActiveNodeUp,
     MoveActiveNodeDown,
+    SetSymmetry {
+        mode: TraceSymmetryMode,
+        center_x: f32,
+        center_y: f32,
+    },
+    SetSmoothing {
+        smoothing: TraceSmoothing,
+    },
+    SetResampler {
+        kind: TraceResamplerKind,
+    },
+    SetPressureRamp {
+        taper_start_length: f32,
+        taper_end_length: f32,
+        synthetic_pressure: TraceSyntheticPressure,
+    },
+    SetDeviceCalibration {
+        calibration: StoredDeviceCalibrations,
+    },
+    ShapeStroke {
+        shape: TraceStrokeShape,
+        taper_start: f32,
+        taper_end: f32,
+    },
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub enum TraceSyntheticPressure {
+    Off,
+    Velocity { min_pressure: f32, full_speed: f32 },
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub enum TraceResamplerKind {
+    Linear,
+    CatmullRom,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub enum TraceSmoothing {
+    None,
+    ExponentialMovingAverage {
+        position_alpha: f32,
+        pressure_alpha: f32,
+        tilt_alpha: f32,
+        twist_alpha: f32,
+    },
+    LazyNib {
+        radius: f32,
+    },
+    MovingWindow {
+        window_size: usize,
+    },
+    OneEuro {
+        min_cutoff_hz: f32,
+        beta: f32,
+        derivative_cutoff_hz: f32,
+    },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub enum TraceStrokeShape {
+    Line { start: [f32; 2], end: [f32; 2] },
+    Rectangle { start: [f32; 2], end: [f32; 2] },
+    Ellipse { start: [f32; 2], end: [f32; 2] },
+    Polyline { points: Vec<[f32; 2]> },
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub enum TraceSymmetryMode {
+    Off,
+    Vertical,
+    Horizontal,
+    Both,
+    Radial { ways: u32 },
 }
 
 #[derive(Debug, Clone, Copy, Serialize, Deserialize)]
@@ -280,21 +367,8 @@ impl TraceRecorder {
         if controls.is_empty() && samples.is_empty() {
             return;
         }
-        let mut trace_controls = Vec::with_capacity(controls.len());
-        for control in controls {
-            let InputControlEvent::Control(control) = control;
-            trace_controls.push(TraceAppControl::from(control.clone()));
-        }
-
-        let mut trace_samples = Vec::with_capacity(samples.len());
-        for sample in samples {
-            trace_samples.push(TraceInputSample::from(*sample));
-        }
-
-        self.input_frames.push(TraceInputFrame {
-            controls: trace_controls,
-            samples: trace_samples,
-        });
+        self.input_frames
+            .push(TraceInputFrame::from_runtime(controls, samples));
     }
 
     pub fn record_output_frame(&mut self, commands: &[GpuCmdMsg]) {
@@ -334,7 +408,7 @@ impl TraceRecorder {
         let file = File::open(path)?;
         let reader = BufReader::new(file);
         let trace_file: TraceInputFile = serde_json::from_reader(reader)?;
-        if trace_file.version != TRACE_VERSION {
+        if !(OLDEST_TRACE_VERSION..=TRACE_VERSION).contains(&trace_file.version) {
             return Err(TraceIoError::UnsupportedVersion(trace_file.version));
         }
         Ok(trace_file)
@@ -342,6 +416,27 @@ impl TraceRecorder {
 }
 
 impl TraceInputFrame {
+    pub fn from_runtime(
+        controls: &[InputControlEvent<AppControl>],
+        samples: &[InputRingSample],
+    ) -> Self {
+        let mut trace_controls = Vec::with_capacity(controls.len());
+        for control in controls {
+            let InputControlEvent::Control(control) = control;
+            trace_controls.push(TraceAppControl::from(control.clone()));
+        }
+
+        let mut trace_samples = Vec::with_capacity(samples.len());
+        for sample in samples {
+            trace_samples.push(TraceInputSample::from(*sample));
+        }
+
+        Self {
+            controls: trace_controls,
+            samples: trace_samples,
+        }
+    }
+
     pub fn to_runtime(&self) -> (Vec<InputControlEvent<AppControl>>, Vec<InputRingSample>) {
         let mut controls = Vec::with_capacity(self.controls.len());
         for control in &self.controls {
@@ -414,6 +509,7 @@ impl From<AppControl> for TraceAppControl {
                 node_id: node_id.0,
                 begin,
             },
+            AppControl::CancelStroke { node_id } => Self::CancelStroke { node_id: node_id.0 },
             AppControl::SelectNode { node_id } => Self::SelectNode { node_id: node_id.0 },
             AppControl::CreateLayerAboveActive { kind } => Self::CreateLayerAboveActive {
                 kind: match kind {
@@ -448,6 +544,48 @@ impl From<AppControl> for TraceAppControl {
             },
             AppControl::MoveActiveNodeUp => Self::MoveActiveNodeUp,
             AppControl::MoveActiveNodeDown => Self::MoveActiveNodeDown,
+            AppControl::SetSymmetry { symmetry } => Self::SetSymmetry {
+                mode: match symmetry.mode {
+                    SymmetryMode::Off => TraceSymmetryMode::Off,
+                    SymmetryMode::Vertical => TraceSymmetryMode::Vertical,
+                    SymmetryMode::Horizontal => TraceSymmetryMode::Horizontal,
+                    SymmetryMode::Both => TraceSymmetryMode::Both,
+                    SymmetryMode::Radial { ways } => TraceSymmetryMode::Radial { ways },
+                },
+                center_x: symmetry.center.x,
+                center_y: symmetry.center.y,
+            },
+            AppControl::SetSmoothing { smoothing } => Self::SetSmoothing {
+                smoothing: TraceSmoothing::from(smoothing),
+            },
+            AppControl::SetResampler { kind } => Self::SetResampler {
+                kind: match kind {
+                    ResamplerKind::Linear => TraceResamplerKind::Linear,
+                    ResamplerKind::CatmullRom => TraceResamplerKind::CatmullRom,
+                },
+            },
+            AppControl::SetPressureRamp {
+                taper,
+                synthetic_pressure,
+            } => Self::SetPressureRamp {
+                taper_start_length: taper.start_length,
+                taper_end_length: taper.end_length,
+                synthetic_pressure: match synthetic_pressure {
+                    SyntheticPressure::Off => TraceSyntheticPressure::Off,
+                    SyntheticPressure::Velocity(config) => TraceSyntheticPressure::Velocity {
+                        min_pressure: config.min_pressure,
+                        full_speed: config.full_speed,
+                    },
+                },
+            },
+            AppControl::SetDeviceCalibration { calibration } => Self::SetDeviceCalibration {
+                calibration: StoredDeviceCalibrations::from(calibration),
+            },
+            AppControl::ShapeStroke { shape, taper } => Self::ShapeStroke {
+                shape: TraceStrokeShape::from(shape),
+                taper_start: taper.start,
+                taper_end: taper.end,
+            },
         }
     }
 }
@@ -459,6 +597,9 @@ impl From<TraceAppControl> for AppControl {
                 node_id: NodeId(node_id),
                 begin,
             },
+            TraceAppControl::CancelStroke { node_id } => Self::CancelStroke {
+                node_id: NodeId(node_id),
+            },
             TraceAppControl::SelectNode { node_id } => Self::SelectNode {
                 node_id: NodeId(node_id),
             },
@@ -503,6 +644,168 @@ impl From<TraceAppControl> for AppControl {
             },
             TraceAppControl::MoveActiveNodeUp => Self::MoveActiveNodeUp,
             TraceAppControl::MoveActiveNodeDown => Self::MoveActiveNodeDown,
+            TraceAppControl::SetSymmetry {
+                mode,
+                center_x,
+                center_y,
+            } => Self::SetSymmetry {
+                symmetry: Symmetry::new(
+                    match mode {
+                        TraceSymmetryMode::Off => SymmetryMode::Off,
+                        TraceSymmetryMode::Vertical => SymmetryMode::Vertical,
+                        TraceSymmetryMode::Horizontal => SymmetryMode::Horizontal,
+                        TraceSymmetryMode::Both => SymmetryMode::Both,
+                        TraceSymmetryMode::Radial { ways } => SymmetryMode::Radial { ways },
+                    },
+                    CanvasVec2::new(center_x, center_y),
+                ),
+            },
+            TraceAppControl::SetSmoothing { smoothing } => Self::SetSmoothing {
+                smoothing: SmoothingConfig::from(smoothing),
+            },
+            TraceAppControl::SetResampler { kind } => Self::SetResampler {
+                kind: match kind {
+                    TraceResamplerKind::Linear => ResamplerKind::Linear,
+                    TraceResamplerKind::CatmullRom => ResamplerKind::CatmullRom,
+                },
+            },
+            TraceAppControl::SetPressureRamp {
+                taper_start_length,
+                taper_end_length,
+                synthetic_pressure,
+            } => Self::SetPressureRamp {
+                taper: StrokeTaper::new(taper_start_length, taper_end_length),
+                synthetic_pressure: match synthetic_pressure {
+                    TraceSyntheticPressure::Off => SyntheticPressure::Off,
+                    TraceSyntheticPressure::Velocity {
+                        min_pressure,
+                        full_speed,
+                    } => SyntheticPressure::Velocity(VelocityPressureConfig {
+                        min_pressure,
+                        full_speed,
+                    }),
+                },
+            },
+            TraceAppControl::SetDeviceCalibration { calibration } => Self::SetDeviceCalibration {
+                calibration: calibration.into(),
+            },
+            TraceAppControl::ShapeStroke {
+                shape,
+                taper_start,
+                taper_end,
+            } => Self::ShapeStroke {
+                shape: StrokeShape::from(shape),
+                taper: ShapeTaper::new(taper_start, taper_end),
+            },
+        }
+    }
+}
+
+impl From<SmoothingConfig> for TraceSmoothing {
+    fn from(value: SmoothingConfig) -> Self {
+        match value {
+            SmoothingConfig::None => Self::None,
+            SmoothingConfig::ExponentialMovingAverage(config) => Self::ExponentialMovingAverage {
+                position_alpha: config.position_alpha,
+                pressure_alpha: config.pressure_alpha,
+                tilt_alpha: config.tilt_alpha,
+                twist_alpha: config.twist_alpha,
+            },
+            SmoothingConfig::LazyNib(config) => Self::LazyNib {
+                radius: config.radius,
+            },
+            SmoothingConfig::MovingWindow(config) => Self::MovingWindow {
+                window_size: config.window_size,
+            },
+            SmoothingConfig::OneEuro(config) => Self::OneEuro {
+                min_cutoff_hz: config.min_cutoff_hz,
+                beta: config.beta,
+                derivative_cutoff_hz: config.derivative_cutoff_hz,
+            },
+        }
+    }
+}
+
+impl From<TraceSmoothing> for SmoothingConfig {
+    fn from(value: TraceSmoothing) -> Self {
+        match value {
+            TraceSmoothing::None => Self::None,
+            TraceSmoothing::ExponentialMovingAverage {
+                position_alpha,
+                pressure_alpha,
+                tilt_alpha,
+                twist_alpha,
+            } => Self::ExponentialMovingAverage(ExponentialMovingAverageConfig {
+                position_alpha,
+                pressure_alpha,
+                tilt_alpha,
+                twist_alpha,
+            }),
+            TraceSmoothing::LazyNib { radius } => Self::LazyNib(LazyNibConfig { radius }),
+            TraceSmoothing::MovingWindow { window_size } => {
+                Self::MovingWindow(MovingWindowConfig { window_size })
+            }
+            TraceSmoothing::OneEuro {
+                min_cutoff_hz,
+                beta,
+                derivative_cutoff_hz,
+            } => Self::OneEuro(OneEuroConfig {
+                min_cutoff_hz,
+                beta,
+                derivative_cutoff_hz,
+            }),
+        }
+    }
+}
+
+fn trace_point(point: CanvasVec2) -> [f32; 2] {
+    [point.x, point.y]
+}
+
+fn canvas_point([x, y]: [f32; 2]) -> CanvasVec2 {
+    CanvasVec2::new(x, y)
+}
+
+impl From<StrokeShape> for TraceStrokeShape {
+    fn from(value: StrokeShape) -> Self {
+        match value {
+            StrokeShape::Line { start, end } => Self::Line {
+                start: trace_point(start),
+                end: trace_point(end),
+            },
+            StrokeShape::Rectangle { start, end } => Self::Rectangle {
+                start: trace_point(start),
+                end: trace_point(end),
+            },
+            StrokeShape::Ellipse { start, end } => Self::Ellipse {
+                start: trace_point(start),
+                end: trace_point(end),
+            },
+            StrokeShape::Polyline { points } => Self::Polyline {
+                points: points.into_iter().map(trace_point).collect(),
+            },
+        }
+    }
+}
+
+impl From<TraceStrokeShape> for StrokeShape {
+    fn from(value: TraceStrokeShape) -> Self {
+        match value {
+            TraceStrokeShape::Line { start, end } => Self::Line {
+                start: canvas_point(start),
+                end: canvas_point(end),
+            },
+            TraceStrokeShape::Rectangle { start, end } => Self::Rectangle {
+                start: canvas_point(start),
+                end: canvas_point(end),
+            },
+            TraceStrokeShape::Ellipse { start, end } => Self::Ellipse {
+                start: canvas_point(start),
+                end: canvas_point(end),
+            },
+            TraceStrokeShape::Polyline { points } => Self::Polyline {
+                points: points.into_iter().map(canvas_point).collect(),
+            },
         }
     }
 }
@@ -760,3 +1063,26 @@ impl From<TraceGpuCmd> for GpuCmdMsg {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resampler_kind_survives_a_trace_round_trip() {
+        let control = InputControlEvent::Control(AppControl::SetResampler {
+            kind: ResamplerKind::CatmullRom,
+        });
+        let frame = TraceInputFrame::from_runtime(&[control], &[]);
+        let json = serde_json::to_string(&frame).unwrap();
+        let frame: TraceInputFrame = serde_json::from_str(&json).unwrap();
+
+        let (controls, _) = frame.to_runtime();
+        assert!(matches!(
+            controls.as_slice(),
+            [InputControlEvent::Control(AppControl::SetResampler {
+                kind: ResamplerKind::CatmullRom
+            })]
+        ));
+    }
+}
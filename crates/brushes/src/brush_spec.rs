@@ -112,11 +112,12 @@ mod tests {
         fn gpu_pipeline_spec(&self) -> BrushGpuPipelineSpec {
             BrushGpuPipelineSpec {
                 label: "test-brush",
-                wgsl_source: "@vertex fn vs_main(@builtin(vertex_index) idx:u32)->@builtin(position) vec4<f32>{ let p=array<vec2<f32>,3>(vec2<f32>(-1.0,-1.0),vec2<f32>(3.0,-1.0),vec2<f32>(-1.0,3.0)); let xy=p[idx]; return vec4<f32>(xy,0.0,1.0);} @fragment fn fs_main()->@location(0) vec4<f32>{ return vec4<f32>(1.0); }",
+                wgsl_source: "@vertex fn vs_main(@builtin(vertex_index) idx:u32)->@builtin(position) vec4<f32>{ let p=array<vec2<f32>,3>(vec2<f32>(-1.0,-1.0),vec2<f32>(3.0,-1.0),vec2<f32>(-1.0,3.0)); let xy=p[idx]; return vec4<f32>(xy,0.0,1.0);} @fragment fn fs_main()->@location(0) vec4<f32>{ return vec4<f32>(1.0); }".into(),
                 vertex_entry: "vs_main",
                 fragment_entry: "fs_main",
                 uses_brush_cache_backend: false,
                 cache_backend_format: None,
+                tip_image: None,
             }
         }
     }
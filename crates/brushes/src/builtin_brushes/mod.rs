@@ -1,2 +1,5 @@
 pub mod pixel_rect;
 pub mod round;
+pub mod smudge;
+pub mod spray;
+pub mod stamp;
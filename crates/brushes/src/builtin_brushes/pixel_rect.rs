@@ -1,26 +1,78 @@
+use std::borrow::Cow;
 use std::error::Error;
 use std::fmt::{Display, Formatter};
 
-use glaphica_core::{BrushInput, CanvasVec2, TileKey};
+use glaphica_core::{BrushInput, CanvasVec2, StrokeId, TileKey};
 
 use crate::BrushPipelineError;
 use crate::brush_spec::BrushSpec;
-use crate::config::{BrushConfigItem, BrushConfigKind, BrushConfigValue};
+use crate::config::{
+    BrushConfigItem, BrushConfigKind, BrushConfigValue, BrushConfigValueError, BrushConfigValues,
+};
 use crate::draw_layout::{BrushDrawInputLayout, BrushDrawInputShape, BrushDrawKind};
+use crate::dynamics::{DabDynamicsTarget, DabVariation};
 use crate::engine_runtime::EngineBrushPipeline;
 use crate::gpu_pipeline_spec::BrushGpuPipelineSpec;
 use crate::resampler_distance::BrushResamplerDistancePolicy;
 
 pub const PIXEL_RECT_DRAW_LAYOUT: BrushDrawInputLayout = BrushDrawInputLayout::new(
     BrushDrawKind::PixelRect,
-    &[BrushDrawInputShape::Vec2F32, BrushDrawInputShape::F32],
+    &[
+        BrushDrawInputShape::Vec2F32,
+        BrushDrawInputShape::F32,
+        BrushDrawInputShape::F32,
+        BrushDrawInputShape::F32,
+        BrushDrawInputShape::Vec2F32,
+    ],
 );
 
+const DITHER_OPTIONS: &[&str] = &["Off", "Bayer 4x4", "Bayer 8x8"];
+
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub struct PixelRectDrawInput {
     pub center_x: f32,
     pub center_y: f32,
     pub radius_px: f32,
+    pub opacity: f32,
+    /// Bayer matrix size, or 0 when opacity is drawn as partial alpha.
+    pub dither_order: f32,
+    /// Tile canvas origin modulo 8, so the dither pattern stays fixed to the
+    /// canvas across tiles.
+    pub dither_origin_x: f32,
+    pub dither_origin_y: f32,
+}
+
+impl PixelRectDrawInput {
+    /// CPU mirror of the fragment shader: whether the tile-local pixel
+    /// `(x, y)` is painted by this dab.
+    pub fn covers(&self, x: i32, y: i32) -> bool {
+        let dx = x as f32 + 0.5 - self.center_x;
+        let dy = y as f32 + 0.5 - self.center_y;
+        if dx.abs() > self.radius_px || dy.abs() > self.radius_px {
+            return false;
+        }
+        let order = self.dither_order as i32;
+        if order <= 0 {
+            return true;
+        }
+        let canvas_x = (x + self.dither_origin_x as i32) & (order - 1);
+        let canvas_y = (y + self.dither_origin_y as i32) & (order - 1);
+        self.opacity > bayer_threshold(canvas_x as u32, canvas_y as u32, order as u32)
+    }
+}
+
+/// Threshold of the ordered-dither matrix of size `order` (a power of two)
+/// at `(x, y)`, in `(0, 1)`.
+pub fn bayer_threshold(x: u32, y: u32, order: u32) -> f32 {
+    let bits = order.trailing_zeros();
+    let mut value = 0;
+    for bit in 0..bits {
+        let x_bit = (x >> bit) & 1;
+        let y_bit = (y >> bit) & 1;
+        let shift = 2 * (bits - 1 - bit);
+        value |= ((x_bit ^ y_bit) << (shift + 1)) | (y_bit << shift);
+    }
+    (value as f32 + 0.5) / (order * order) as f32
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -88,38 +140,151 @@ pub fn decode_pixel_rect_draw_input(
             provided: input.len(),
         });
     }
-    let center_slot = layout
-        .slot_slice(input, 0)
-        .ok_or(PixelRectDecodeError::MissingSlot { slot_index: 0 })?;
-    if center_slot.len() != 2 {
-        return Err(PixelRectDecodeError::SlotShapeMismatch {
-            slot_index: 0,
-            expected_lane_count: 2,
-            provided_lane_count: center_slot.len(),
-        });
-    }
-
-    let radius_slot = layout
-        .slot_slice(input, 1)
-        .ok_or(PixelRectDecodeError::MissingSlot { slot_index: 1 })?;
-    if radius_slot.len() != 1 {
-        return Err(PixelRectDecodeError::SlotShapeMismatch {
-            slot_index: 1,
-            expected_lane_count: 1,
-            provided_lane_count: radius_slot.len(),
-        });
-    }
+    let center_slot = read_slot(layout, input, 0, 2)?;
+    let radius_slot = read_slot(layout, input, 1, 1)?;
+    let opacity_slot = read_slot(layout, input, 2, 1)?;
+    let dither_order_slot = read_slot(layout, input, 3, 1)?;
+    let dither_origin_slot = read_slot(layout, input, 4, 2)?;
 
     Ok(PixelRectDrawInput {
         center_x: center_slot[0],
         center_y: center_slot[1],
         radius_px: radius_slot[0],
+        opacity: opacity_slot[0],
+        dither_order: dither_order_slot[0],
+        dither_origin_x: dither_origin_slot[0],
+        dither_origin_y: dither_origin_slot[1],
     })
 }
 
+fn read_slot(
+    layout: BrushDrawInputLayout,
+    input: &[f32],
+    slot_index: usize,
+    lane_count: usize,
+) -> Result<&[f32], PixelRectDecodeError> {
+    let slot = layout
+        .slot_slice(input, slot_index)
+        .ok_or(PixelRectDecodeError::MissingSlot { slot_index })?;
+    if slot.len() != lane_count {
+        return Err(PixelRectDecodeError::SlotShapeMismatch {
+            slot_index,
+            expected_lane_count: lane_count,
+            provided_lane_count: slot.len(),
+        });
+    }
+    Ok(slot)
+}
+
+/// How opacity below 1 is drawn.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PixelRectDither {
+    /// Partial alpha.
+    Off,
+    /// Fully painted pixels picked by a 4x4 ordered-dither matrix.
+    Bayer4,
+    /// Fully painted pixels picked by an 8x8 ordered-dither matrix.
+    Bayer8,
+}
+
+impl PixelRectDither {
+    fn from_index(index: usize) -> Self {
+        match index {
+            1 => Self::Bayer4,
+            2 => Self::Bayer8,
+            _ => Self::Off,
+        }
+    }
+
+    fn index(self) -> usize {
+        match self {
+            Self::Off => 0,
+            Self::Bayer4 => 1,
+            Self::Bayer8 => 2,
+        }
+    }
+
+    pub const fn order(self) -> u32 {
+        match self {
+            Self::Off => 0,
+            Self::Bayer4 => 4,
+            Self::Bayer8 => 8,
+        }
+    }
+}
+
+/// Look-back over the dabs of a pixel-perfect stroke. The newest cell is
+/// held back until the next one shows whether it is the corner of an L.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+struct PixelPerfectTrail {
+    stroke: Option<StrokeId>,
+    drawn: Option<[i32; 2]>,
+    pending: Option<([i32; 2], BrushInput)>,
+}
+
+impl PixelPerfectTrail {
+    fn step(&mut self, cell: [i32; 2], brush_input: &BrushInput, dabs: &mut Vec<BrushInput>) {
+        if let Some((pending_cell, pending_input)) = self.pending {
+            if pending_cell == cell {
+                return;
+            }
+            let skips_corner = self.drawn.is_some_and(|drawn| {
+                (drawn[0] - cell[0]).abs() == 1 && (drawn[1] - cell[1]).abs() == 1
+            });
+            if !skips_corner {
+                dabs.push(cell_dab(&pending_input, pending_cell));
+                self.drawn = Some(pending_cell);
+            }
+        }
+        self.pending = Some((cell, *brush_input));
+    }
+
+    fn finish(&mut self, dabs: &mut Vec<BrushInput>) {
+        if let Some((cell, brush_input)) = self.pending.take() {
+            dabs.push(cell_dab(&brush_input, cell));
+            self.drawn = Some(cell);
+        }
+    }
+}
+
+fn cell_dab(brush_input: &BrushInput, cell: [i32; 2]) -> BrushInput {
+    let mut dab = *brush_input;
+    dab.cursor.cursor = CanvasVec2::new(cell[0] as f32 + 0.5, cell[1] as f32 + 0.5);
+    dab
+}
+
+/// Visits the 8-connected cells on the line from `from` to `to`, excluding
+/// `from`.
+fn for_each_line_cell(from: [i32; 2], to: [i32; 2], mut visit: impl FnMut([i32; 2])) {
+    let dx = (to[0] - from[0]).abs();
+    let dy = -(to[1] - from[1]).abs();
+    let step_x = if from[0] < to[0] { 1 } else { -1 };
+    let step_y = if from[1] < to[1] { 1 } else { -1 };
+    let mut error = dx + dy;
+    let mut cell = from;
+    while cell != to {
+        let doubled = 2 * error;
+        if doubled >= dy {
+            error += dy;
+            cell[0] += step_x;
+        }
+        if doubled <= dx {
+            error += dx;
+            cell[1] += step_y;
+        }
+        visit(cell);
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
 pub struct PixelRectBrush {
     radius_px: u32,
+    opacity: f32,
+    dither: PixelRectDither,
+    pixel_perfect: bool,
+    /// One look-back trail per symmetry copy.
+    trails: Vec<PixelPerfectTrail>,
+    copy_index: usize,
 }
 
 impl PixelRectBrush {
@@ -127,60 +292,133 @@ impl PixelRectBrush {
     pub const CONSTANT_B: f32 = 2.0 / 3.0;
 
     pub const fn new(radius_px: u32) -> Self {
-        Self { radius_px }
+        Self {
+            radius_px,
+            opacity: 1.0,
+            dither: PixelRectDither::Off,
+            pixel_perfect: false,
+            trails: Vec::new(),
+            copy_index: 0,
+        }
     }
 
-    pub const fn radius_px(self) -> u32 {
+    pub const fn with_opacity(mut self, opacity: f32, dither: PixelRectDither) -> Self {
+        self.opacity = opacity;
+        self.dither = dither;
+        self
+    }
+
+    /// Snaps dabs to whole pixels and drops the corner pixel of every L a
+    /// stroke turns, so 1 px lines stay one pixel wide.
+    pub const fn with_pixel_perfect(mut self, pixel_perfect: bool) -> Self {
+        self.pixel_perfect = pixel_perfect;
+        self
+    }
+
+    pub const fn radius_px(&self) -> u32 {
         self.radius_px
     }
 
+    pub const fn opacity(&self) -> f32 {
+        self.opacity
+    }
+
+    pub const fn dither(&self) -> PixelRectDither {
+        self.dither
+    }
+
+    pub const fn pixel_perfect(&self) -> bool {
+        self.pixel_perfect
+    }
+
+    fn trail_mut(&mut self) -> &mut PixelPerfectTrail {
+        if self.trails.len() <= self.copy_index {
+            self.trails
+                .resize(self.copy_index + 1, PixelPerfectTrail::default());
+        }
+        &mut self.trails[self.copy_index]
+    }
+
     pub fn config_items(&self) -> Vec<BrushConfigItem> {
-        vec![BrushConfigItem {
-            key: "radius_px",
-            label: "Size",
-            default_hidden: false,
-            kind: BrushConfigKind::ScalarF32 {
-                min: 1.0,
-                max: 128.0,
+        vec![
+            BrushConfigItem {
+                key: "radius_px",
+                label: "Size",
+                default_hidden: false,
+                kind: BrushConfigKind::ScalarF32 {
+                    min: 1.0,
+                    max: 128.0,
+                },
+                default_value: BrushConfigValue::ScalarF32(self.radius_px as f32),
+            },
+            BrushConfigItem {
+                key: "opacity",
+                label: "Opacity",
+                default_hidden: false,
+                kind: BrushConfigKind::ScalarF32 { min: 0.0, max: 1.0 },
+                default_value: BrushConfigValue::ScalarF32(self.opacity),
             },
-            default_value: BrushConfigValue::ScalarF32(self.radius_px as f32),
-        }]
+            BrushConfigItem {
+                key: "dither",
+                label: "Dither",
+                default_hidden: false,
+                kind: BrushConfigKind::Enum {
+                    options: DITHER_OPTIONS,
+                },
+                default_value: BrushConfigValue::Enum(self.dither.index()),
+            },
+            BrushConfigItem {
+                key: "pixel_perfect",
+                label: "Pixel Perfect",
+                default_hidden: false,
+                kind: BrushConfigKind::Bool,
+                default_value: BrushConfigValue::Bool(self.pixel_perfect),
+            },
+        ]
     }
 
-    pub fn from_config_values(values: &[BrushConfigValue]) -> Result<Self, PixelRectConfigError> {
-        if values.len() != 1 {
-            return Err(PixelRectConfigError::InvalidConfigLength);
-        }
-        let radius_px = match values.first() {
-            Some(BrushConfigValue::ScalarF32(value)) => *value,
-            _ => return Err(PixelRectConfigError::ConfigTypeMismatch),
-        };
+    pub fn from_config_values(values: &BrushConfigValues) -> Result<Self, PixelRectConfigError> {
+        let radius_px = values.scalar_f32("radius_px")?;
+        let opacity = values.scalar_f32("opacity")?;
+        let dither = values.enum_index("dither")?;
+        let pixel_perfect = values.bool("pixel_perfect")?;
         if !(1.0..=128.0).contains(&radius_px) {
             return Err(PixelRectConfigError::RadiusOutOfRange);
         }
-        Ok(Self::new(radius_px.round() as u32))
+        if !(0.0..=1.0).contains(&opacity) {
+            return Err(PixelRectConfigError::OpacityOutOfRange);
+        }
+        Ok(Self::new(radius_px.round() as u32)
+            .with_opacity(opacity, PixelRectDither::from_index(dither))
+            .with_pixel_perfect(pixel_perfect))
     }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum PixelRectConfigError {
-    InvalidConfigLength,
-    ConfigTypeMismatch,
+    Config(BrushConfigValueError),
     RadiusOutOfRange,
+    OpacityOutOfRange,
 }
 
 impl Display for PixelRectConfigError {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         match self {
-            Self::InvalidConfigLength => write!(f, "pixel rect brush config item count is invalid"),
-            Self::ConfigTypeMismatch => write!(f, "pixel rect brush config value type mismatch"),
+            Self::Config(error) => write!(f, "pixel rect brush {error}"),
             Self::RadiusOutOfRange => write!(f, "pixel rect brush size must be in [1, 128]"),
+            Self::OpacityOutOfRange => write!(f, "pixel rect brush opacity must be in [0, 1]"),
         }
     }
 }
 
 impl Error for PixelRectConfigError {}
 
+impl From<BrushConfigValueError> for PixelRectConfigError {
+    fn from(error: BrushConfigValueError) -> Self {
+        Self::Config(error)
+    }
+}
+
 impl BrushResamplerDistancePolicy for PixelRectBrush {
     fn brush_size(&self) -> u32 {
         self.radius_px
@@ -204,7 +442,72 @@ impl EngineBrushPipeline for PixelRectBrush {
     ) -> Result<Vec<f32>, BrushPipelineError> {
         let local_x = brush_input.cursor.cursor.x - tile_canvas_origin.x;
         let local_y = brush_input.cursor.cursor.y - tile_canvas_origin.y;
-        Ok(vec![local_x, local_y, self.radius_px as f32])
+        // Pixel-perfect dabs sit on pixel centers, so a half size of r - 0.5
+        // covers exactly 2r - 1 pixels and size 1 paints a single pixel.
+        let half_size = if self.pixel_perfect {
+            self.radius_px as f32 - 0.5
+        } else {
+            self.radius_px as f32
+        };
+        Ok(vec![
+            local_x,
+            local_y,
+            half_size,
+            self.opacity,
+            self.dither.order() as f32,
+            tile_canvas_origin.x.rem_euclid(8.0),
+            tile_canvas_origin.y.rem_euclid(8.0),
+        ])
+    }
+
+    fn expand_dabs(&mut self, brush_input: &BrushInput, dabs: &mut Vec<BrushInput>) {
+        if !self.pixel_perfect {
+            dabs.push(*brush_input);
+            return;
+        }
+        let trail = self.trail_mut();
+        if trail.stroke != Some(brush_input.stroke) {
+            *trail = PixelPerfectTrail {
+                stroke: Some(brush_input.stroke),
+                ..PixelPerfectTrail::default()
+            };
+        }
+        let cursor = brush_input.cursor.cursor;
+        let cell = [cursor.x.floor() as i32, cursor.y.floor() as i32];
+        match trail.pending.map(|(cell, _)| cell).or(trail.drawn) {
+            Some(last) => {
+                for_each_line_cell(last, cell, |cell| trail.step(cell, brush_input, dabs))
+            }
+            None => trail.step(cell, brush_input, dabs),
+        }
+    }
+
+    fn finish_dabs(&mut self, dabs: &mut Vec<BrushInput>) {
+        if self.pixel_perfect {
+            self.trail_mut().finish(dabs);
+        }
+    }
+
+    fn begin_symmetry_copy(&mut self, copy_index: usize) {
+        self.copy_index = copy_index;
+    }
+}
+
+impl DabDynamicsTarget for PixelRectBrush {
+    fn base_config_items(&self) -> Vec<BrushConfigItem> {
+        self.config_items()
+    }
+
+    fn dab_size_px(&self) -> f32 {
+        self.radius_px as f32 * 2.0
+    }
+
+    fn apply_dab_variation(&self, input: &mut [f32], variation: &DabVariation) {
+        let [_, _, half_size, opacity, ..] = input else {
+            return;
+        };
+        *half_size *= variation.size_scale;
+        *opacity *= variation.opacity_scale * variation.flow_scale;
     }
 }
 
@@ -220,11 +523,12 @@ impl BrushSpec for PixelRectBrush {
     fn gpu_pipeline_spec(&self) -> BrushGpuPipelineSpec {
         BrushGpuPipelineSpec {
             label: "pixel-rect-brush",
-            wgsl_source: include_str!("pixel_rect.wgsl"),
+            wgsl_source: Cow::Borrowed(include_str!("pixel_rect.wgsl")),
             vertex_entry: "vs_main",
             fragment_entry: "fs_main",
             uses_brush_cache_backend: false,
             cache_backend_format: None,
+            tip_image: None,
         }
     }
 }
@@ -243,7 +547,14 @@ mod tests {
         BrushEngineRuntime, BrushGpuPipelineRegistry, BrushLayoutRegistry, EngineBrushPipeline,
     };
 
-    use super::{PIXEL_RECT_DRAW_LAYOUT, PixelRectBrush, decode_pixel_rect_draw_input};
+    use std::collections::BTreeSet;
+
+    use crate::file_brush::validate_wgsl;
+
+    use super::{
+        PIXEL_RECT_DRAW_LAYOUT, PixelRectBrush, PixelRectDither, PixelRectDrawInput,
+        decode_pixel_rect_draw_input,
+    };
 
     fn build_input(center: CanvasVec2) -> BrushInput {
         BrushInput {
@@ -282,7 +593,7 @@ mod tests {
             Ok(encoded) => encoded,
             Err(_) => return,
         };
-        assert_eq!(encoded, vec![12.0, 7.0, 5.0]);
+        assert_eq!(encoded, vec![12.0, 7.0, 5.0, 1.0, 0.0, 0.0, 0.0]);
     }
 
     #[test]
@@ -296,12 +607,15 @@ mod tests {
             Ok(encoded) => encoded,
             Err(_) => return,
         };
-        assert_eq!(encoded, vec![36.0, 72.0, 5.0]);
+        assert_eq!(encoded, vec![36.0, 72.0, 5.0, 1.0, 0.0, 0.0, 0.0]);
     }
 
     #[test]
     fn decode_resolves_fields_from_layout() {
-        let decoded = decode_pixel_rect_draw_input(PIXEL_RECT_DRAW_LAYOUT, &[1.0, 2.0, 3.0]);
+        let decoded = decode_pixel_rect_draw_input(
+            PIXEL_RECT_DRAW_LAYOUT,
+            &[1.0, 2.0, 3.0, 0.5, 4.0, 6.0, 7.0],
+        );
         assert!(decoded.is_ok());
         let decoded = match decoded {
             Ok(decoded) => decoded,
@@ -310,6 +624,10 @@ mod tests {
         assert_eq!(decoded.center_x, 1.0);
         assert_eq!(decoded.center_y, 2.0);
         assert_eq!(decoded.radius_px, 3.0);
+        assert_eq!(decoded.opacity, 0.5);
+        assert_eq!(decoded.dither_order, 4.0);
+        assert_eq!(decoded.dither_origin_x, 6.0);
+        assert_eq!(decoded.dither_origin_y, 7.0);
     }
 
     #[test]
@@ -343,4 +661,143 @@ mod tests {
         assert!((distance.max_distance - 3.6).abs() < 0.0001);
         assert!((distance.min_distance - 2.0).abs() < 0.0001);
     }
+
+    fn dab_cells(brush: &mut PixelRectBrush, points: &[(f32, f32)]) -> Vec<(i32, i32)> {
+        let mut dabs = Vec::new();
+        for &(x, y) in points {
+            brush.expand_dabs(&build_input(CanvasVec2::new(x, y)), &mut dabs);
+        }
+        brush.finish_dabs(&mut dabs);
+        dabs.iter()
+            .map(|dab| {
+                let cursor = dab.cursor.cursor;
+                (cursor.x.floor() as i32, cursor.y.floor() as i32)
+            })
+            .collect()
+    }
+
+    fn painted_pixels(brush: &mut PixelRectBrush, center: CanvasVec2) -> BTreeSet<(i32, i32)> {
+        let encoded = brush.encode_draw_input(
+            &build_input(center),
+            TileKey::from_parts(0, 0, 0),
+            CanvasVec2::new(0.0, 0.0),
+        );
+        assert!(encoded.is_ok());
+        let Ok(encoded) = encoded else {
+            return BTreeSet::new();
+        };
+        let decoded = decode_pixel_rect_draw_input(PIXEL_RECT_DRAW_LAYOUT, &encoded);
+        assert!(decoded.is_ok());
+        let decoded: PixelRectDrawInput = match decoded {
+            Ok(decoded) => decoded,
+            Err(_) => return BTreeSet::new(),
+        };
+        let mut pixels = BTreeSet::new();
+        for y in 0..16 {
+            for x in 0..16 {
+                if decoded.covers(x, y) {
+                    pixels.insert((x, y));
+                }
+            }
+        }
+        pixels
+    }
+
+    #[test]
+    fn pixel_perfect_drops_corners_of_staircase() {
+        let mut brush = PixelRectBrush::new(1).with_pixel_perfect(true);
+        let cells = dab_cells(
+            &mut brush,
+            &[(0.5, 0.5), (1.5, 0.5), (1.5, 1.5), (2.5, 1.5), (2.5, 2.5)],
+        );
+        assert_eq!(cells, vec![(0, 0), (1, 1), (2, 2)]);
+    }
+
+    #[test]
+    fn pixel_perfect_fills_gaps_between_inputs() {
+        let mut brush = PixelRectBrush::new(1).with_pixel_perfect(true);
+        let cells = dab_cells(&mut brush, &[(0.2, 3.7), (4.9, 3.1), (4.9, 3.1)]);
+        assert_eq!(cells, vec![(0, 3), (1, 3), (2, 3), (3, 3), (4, 3)]);
+    }
+
+    #[test]
+    fn pixel_perfect_restarts_trail_for_new_stroke() {
+        let mut brush = PixelRectBrush::new(1).with_pixel_perfect(true);
+        let mut dabs = Vec::new();
+        brush.expand_dabs(&build_input(CanvasVec2::new(0.5, 0.5)), &mut dabs);
+        let mut next = build_input(CanvasVec2::new(9.5, 9.5));
+        next.stroke = StrokeId(10);
+        brush.expand_dabs(&next, &mut dabs);
+        brush.finish_dabs(&mut dabs);
+        assert_eq!(dabs.len(), 1);
+        assert_eq!(dabs[0].cursor.cursor, CanvasVec2::new(9.5, 9.5));
+    }
+
+    #[test]
+    fn pixel_perfect_keeps_one_trail_per_symmetry_copy() {
+        let mut brush = PixelRectBrush::new(1).with_pixel_perfect(true);
+        let mut dabs = Vec::new();
+        for (x, mirrored_x) in [(0.5, 20.5), (1.5, 19.5), (2.5, 18.5)] {
+            brush.begin_symmetry_copy(0);
+            brush.expand_dabs(&build_input(CanvasVec2::new(x, 0.5)), &mut dabs);
+            brush.begin_symmetry_copy(1);
+            brush.expand_dabs(&build_input(CanvasVec2::new(mirrored_x, 0.5)), &mut dabs);
+        }
+        for copy_index in 0..2 {
+            brush.begin_symmetry_copy(copy_index);
+            brush.finish_dabs(&mut dabs);
+        }
+        let mut cells = dabs
+            .iter()
+            .map(|dab| dab.cursor.cursor.x.floor() as i32)
+            .collect::<Vec<_>>();
+        cells.sort_unstable();
+        assert_eq!(cells, vec![0, 1, 2, 18, 19, 20]);
+    }
+
+    #[test]
+    fn pixel_perfect_size_one_paints_single_pixel() {
+        let mut brush = PixelRectBrush::new(1).with_pixel_perfect(true);
+        let pixels = painted_pixels(&mut brush, CanvasVec2::new(4.5, 6.5));
+        assert_eq!(pixels, BTreeSet::from([(4, 6)]));
+
+        let mut brush = PixelRectBrush::new(2).with_pixel_perfect(true);
+        assert_eq!(
+            painted_pixels(&mut brush, CanvasVec2::new(4.5, 6.5)).len(),
+            9
+        );
+    }
+
+    #[test]
+    fn bayer_dither_picks_hard_pixels_by_opacity() {
+        let center = CanvasVec2::new(2.0, 2.0);
+        let mut half = PixelRectBrush::new(2).with_opacity(0.5, PixelRectDither::Bayer4);
+        let pixels = painted_pixels(&mut half, center);
+        assert_eq!(pixels.len(), 8);
+        assert!(pixels.iter().all(|(x, y)| (x + y) % 2 == 0));
+
+        let mut full = PixelRectBrush::new(2).with_opacity(1.0, PixelRectDither::Bayer4);
+        assert_eq!(painted_pixels(&mut full, center).len(), 16);
+
+        let mut none = PixelRectBrush::new(2).with_opacity(0.0, PixelRectDither::Bayer8);
+        assert!(painted_pixels(&mut none, center).is_empty());
+    }
+
+    #[test]
+    fn bayer_thresholds_cover_every_level_once() {
+        for order in [4, 8] {
+            let mut levels = (0..order)
+                .flat_map(|y| (0..order).map(move |x| super::bayer_threshold(x, y, order)))
+                .map(|threshold| (threshold * (order * order) as f32) as u32)
+                .collect::<Vec<_>>();
+            levels.sort_unstable();
+            assert_eq!(levels, (0..order * order).collect::<Vec<_>>());
+        }
+    }
+
+    #[test]
+    fn shader_validates() {
+        let spec = PixelRectBrush::new(1).gpu_pipeline_spec();
+        assert!(validate_wgsl(&spec.wgsl_source, spec.vertex_entry, spec.fragment_entry).is_ok());
+    }
 }
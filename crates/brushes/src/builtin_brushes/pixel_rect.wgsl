@@ -2,6 +2,10 @@ struct DrawInput {
     center_local_x: f32,
     center_local_y: f32,
     radius_px: f32,
+    opacity: f32,
+    dither_order: f32,
+    dither_origin_x: f32,
+    dither_origin_y: f32,
 }
 
 struct ShaderParams {
@@ -28,6 +32,20 @@ struct ShaderParams {
 @group(0) @binding(0) var<storage, read> draw_input: DrawInput;
 @group(0) @binding(1) var<uniform> params: ShaderParams;
 
+// Threshold of the `order` x `order` ordered-dither matrix at (x, y), built by
+// bit-reversed interleaving of (x ^ y) and y.
+fn bayer_threshold(x: u32, y: u32, order: u32) -> f32 {
+    let bits = countTrailingZeros(order);
+    var value = 0u;
+    for (var bit = 0u; bit < bits; bit = bit + 1u) {
+        let x_bit = (x >> bit) & 1u;
+        let y_bit = (y >> bit) & 1u;
+        let shift = 2u * (bits - 1u - bit);
+        value = value | (((x_bit ^ y_bit) << (shift + 1u)) | (y_bit << shift));
+    }
+    return (f32(value) + 0.5) / f32(order * order);
+}
+
 @vertex
 fn vs_main(@builtin(vertex_index) vertex_index: u32) -> @builtin(position) vec4<f32> {
     let positions = array<vec2<f32>, 3>(
@@ -54,11 +72,23 @@ fn fs_main(@builtin(position) pos: vec4<f32>) -> @location(0) vec4<f32> {
     let dy = tile_local_y - center_y;
     let tint = vec3<f32>(params.tint_r, params.tint_g, params.tint_b);
     
-    if (abs(dx) <= half_size && abs(dy) <= half_size) {
-        if (params.erase != 0u) {
-            return vec4<f32>(0.0);
+    if (abs(dx) > half_size || abs(dy) > half_size) {
+        discard;
+    }
+    var alpha = draw_input.opacity;
+    let order = u32(draw_input.dither_order);
+    if (order > 0u) {
+        // Dither on canvas pixels so the pattern does not shift between tiles.
+        let mask = i32(order) - 1;
+        let canvas_x = u32((i32(floor(tile_local_x)) + i32(draw_input.dither_origin_x)) & mask);
+        let canvas_y = u32((i32(floor(tile_local_y)) + i32(draw_input.dither_origin_y)) & mask);
+        if (draw_input.opacity <= bayer_threshold(canvas_x, canvas_y, order)) {
+            discard;
         }
-        return vec4<f32>(tint, 1.0);
+        alpha = 1.0;
+    }
+    if (params.erase != 0u) {
+        return vec4<f32>(0.0);
     }
-    discard;
+    return vec4<f32>(tint, alpha);
 }
@@ -1,3 +1,4 @@
+use std::borrow::Cow;
 use std::error::Error;
 use std::f32::consts::{FRAC_PI_2, PI};
 use std::fmt::{Display, Formatter};
@@ -8,10 +9,11 @@ use thread_protocol::GpuCmdFrameMergeTag;
 use crate::BrushPipelineError;
 use crate::brush_spec::BrushSpec;
 use crate::config::{
-    BrushConfigItem, BrushConfigKind, BrushConfigValue, UnitIntervalPoint,
-    eval_unit_interval_curve_polynomial,
+    BrushConfigItem, BrushConfigKind, BrushConfigValue, BrushConfigValueError, BrushConfigValues,
+    UnitIntervalPoint, eval_unit_interval_curve,
 };
 use crate::draw_layout::{BrushDrawInputLayout, BrushDrawInputShape, BrushDrawKind};
+use crate::dynamics::{DabDynamicsTarget, DabVariation};
 use crate::engine_runtime::EngineBrushPipeline;
 use crate::gpu_pipeline_spec::BrushGpuPipelineSpec;
 use crate::resampler_distance::BrushResamplerDistancePolicy;
@@ -214,7 +216,7 @@ impl ModulationCurve {
     }
 
     pub fn sample(&self, x: f32) -> f32 {
-        eval_unit_interval_curve_polynomial(&self.points, x).unwrap_or(1.0)
+        eval_unit_interval_curve(&self.points, x).unwrap_or(1.0)
     }
 
     pub fn points(&self) -> &[CurvePoint] {
@@ -294,8 +296,7 @@ pub enum RoundBrushConfigError {
     RadiusMustBePositive,
     HardnessOutOfRange,
     OpacityOutOfRange,
-    InvalidConfigLength,
-    ConfigTypeMismatch,
+    Config(BrushConfigValueError),
     CurveInvalid,
 }
 
@@ -305,8 +306,7 @@ impl Display for RoundBrushConfigError {
             Self::RadiusMustBePositive => write!(f, "round brush radius must be > 0"),
             Self::HardnessOutOfRange => write!(f, "round brush hardness must be in [0, 1]"),
             Self::OpacityOutOfRange => write!(f, "round brush opacity must be in [0, 1]"),
-            Self::InvalidConfigLength => write!(f, "round brush config item count is invalid"),
-            Self::ConfigTypeMismatch => write!(f, "round brush config value type mismatch"),
+            Self::Config(error) => write!(f, "round brush {error}"),
             Self::CurveInvalid => write!(f, "round brush curve config is invalid"),
         }
     }
@@ -314,6 +314,12 @@ impl Display for RoundBrushConfigError {
 
 impl Error for RoundBrushConfigError {}
 
+impl From<BrushConfigValueError> for RoundBrushConfigError {
+    fn from(error: BrushConfigValueError) -> Self {
+        Self::Config(error)
+    }
+}
+
 impl RoundBrush {
     pub const CONSTANT_A: f32 = 1.2;
     pub const CONSTANT_B: f32 = 2.0 / 3.0;
@@ -445,34 +451,22 @@ impl RoundBrush {
         ]
     }
 
-    pub fn from_config_values(values: &[BrushConfigValue]) -> Result<Self, RoundBrushConfigError> {
-        if values.len() != 15 {
-            return Err(RoundBrushConfigError::InvalidConfigLength);
-        }
-        let base_radius_px = match values.first() {
-            Some(BrushConfigValue::ScalarF32(value)) => *value,
-            _ => return Err(RoundBrushConfigError::ConfigTypeMismatch),
-        };
-        let base_hardness = match values.get(1) {
-            Some(BrushConfigValue::ScalarF32(value)) => *value,
-            _ => return Err(RoundBrushConfigError::ConfigTypeMismatch),
-        };
-        let base_opacity = match values.get(2) {
-            Some(BrushConfigValue::ScalarF32(value)) => *value,
-            _ => return Err(RoundBrushConfigError::ConfigTypeMismatch),
-        };
-        let pressure_to_radius = curve_from_value(values.get(3))?;
-        let pressure_to_hardness = curve_from_value(values.get(4))?;
-        let pressure_to_opacity = curve_from_value(values.get(5))?;
-        let tilt_to_radius = curve_from_value(values.get(6))?;
-        let tilt_to_hardness = curve_from_value(values.get(7))?;
-        let tilt_to_opacity = curve_from_value(values.get(8))?;
-        let twist_to_radius = curve_from_value(values.get(9))?;
-        let twist_to_hardness = curve_from_value(values.get(10))?;
-        let twist_to_opacity = curve_from_value(values.get(11))?;
-        let speed_to_radius = curve_from_value(values.get(12))?;
-        let speed_to_hardness = curve_from_value(values.get(13))?;
-        let speed_to_opacity = curve_from_value(values.get(14))?;
+    pub fn from_config_values(values: &BrushConfigValues) -> Result<Self, RoundBrushConfigError> {
+        let base_radius_px = values.scalar_f32("base_radius_px")?;
+        let base_hardness = values.scalar_f32("base_hardness")?;
+        let base_opacity = values.scalar_f32("base_opacity")?;
+        let pressure_to_radius = curve_from_values(values, "pressure_to_radius")?;
+        let pressure_to_hardness = curve_from_values(values, "pressure_to_hardness")?;
+        let pressure_to_opacity = curve_from_values(values, "pressure_to_opacity")?;
+        let tilt_to_radius = curve_from_values(values, "tilt_to_radius")?;
+        let tilt_to_hardness = curve_from_values(values, "tilt_to_hardness")?;
+        let tilt_to_opacity = curve_from_values(values, "tilt_to_opacity")?;
+        let twist_to_radius = curve_from_values(values, "twist_to_radius")?;
+        let twist_to_hardness = curve_from_values(values, "twist_to_hardness")?;
+        let twist_to_opacity = curve_from_values(values, "twist_to_opacity")?;
+        let speed_to_radius = curve_from_values(values, "speed_to_radius")?;
+        let speed_to_hardness = curve_from_values(values, "speed_to_hardness")?;
+        let speed_to_opacity = curve_from_values(values, "speed_to_opacity")?;
         Self::new_with_opacity(
             base_radius_px,
             base_hardness,
@@ -616,6 +610,10 @@ impl EngineBrushPipeline for RoundBrush {
     fn restore_origin_before_each_dab(&self) -> bool {
         true
     }
+
+    fn stroke_buffer_accumulates_color(&self) -> bool {
+        true
+    }
 }
 
 impl BrushSpec for RoundBrush {
@@ -630,11 +628,12 @@ impl BrushSpec for RoundBrush {
     fn gpu_pipeline_spec(&self) -> BrushGpuPipelineSpec {
         BrushGpuPipelineSpec {
             label: "round-brush",
-            wgsl_source: include_str!("round.wgsl"),
+            wgsl_source: Cow::Borrowed(include_str!("round.wgsl")),
             vertex_entry: "vs_main",
             fragment_entry: "fs_main",
             uses_brush_cache_backend: true,
             cache_backend_format: Some(TextureFormat::Rgba16Float),
+            tip_image: None,
         }
     }
 
@@ -643,6 +642,24 @@ impl BrushSpec for RoundBrush {
     }
 }
 
+impl DabDynamicsTarget for RoundBrush {
+    fn base_config_items(&self) -> Vec<BrushConfigItem> {
+        self.config_items()
+    }
+
+    fn dab_size_px(&self) -> f32 {
+        self.base_radius_px * 2.0
+    }
+
+    fn apply_dab_variation(&self, input: &mut [f32], variation: &DabVariation) {
+        let [_, _, radius_px, _, opacity, _] = input else {
+            return;
+        };
+        *radius_px *= variation.size_scale;
+        *opacity *= variation.opacity_scale * variation.flow_scale;
+    }
+}
+
 fn clamp01(v: f32) -> f32 {
     v.clamp(0.0, 1.0)
 }
@@ -671,14 +688,12 @@ fn curve_item(key: &'static str, label: &'static str, curve: &ModulationCurve) -
     }
 }
 
-fn curve_from_value(
-    value: Option<&BrushConfigValue>,
+fn curve_from_values(
+    values: &BrushConfigValues,
+    key: &'static str,
 ) -> Result<ModulationCurve, RoundBrushConfigError> {
-    let points = match value {
-        Some(BrushConfigValue::UnitIntervalCurve(points)) => points,
-        _ => return Err(RoundBrushConfigError::ConfigTypeMismatch),
-    };
-    ModulationCurve::new(points.clone()).map_err(|_| RoundBrushConfigError::CurveInvalid)
+    let points = values.curve(key)?;
+    ModulationCurve::new(points.to_vec()).map_err(|_| RoundBrushConfigError::CurveInvalid)
 }
 
 #[cfg(test)]
@@ -694,12 +709,13 @@ mod tests {
     use crate::draw_layout::{BrushDrawInputLayout, BrushDrawInputShape, BrushDrawKind};
     use crate::resampler_distance::BrushResamplerDistancePolicy;
     use crate::{
-        BrushEngineRuntime, BrushGpuPipelineRegistry, BrushLayoutRegistry, EngineBrushPipeline,
+        BrushConfigValue, BrushConfigValueError, BrushConfigValues, BrushEngineRuntime,
+        BrushGpuPipelineRegistry, BrushLayoutRegistry, EngineBrushPipeline,
     };
 
     use super::{
-        CurvePoint, ModulationCurve, ROUND_DRAW_LAYOUT, RoundBrush, RoundBrushCurves,
-        decode_round_draw_input,
+        CurvePoint, ModulationCurve, ROUND_DRAW_LAYOUT, RoundBrush, RoundBrushConfigError,
+        RoundBrushCurves, decode_round_draw_input,
     };
 
     fn build_input(center: CanvasVec2, pressure: f32, tilt: RadianVec2, twist: f32) -> BrushInput {
@@ -857,4 +873,39 @@ mod tests {
         assert!((distance.max_distance - 3.6).abs() < 0.0001);
         assert!((distance.min_distance - 2.0).abs() < 0.0001);
     }
+
+    #[test]
+    fn from_config_values_reads_values_by_key() {
+        let brush = RoundBrush::with_default_curves(3.0, 0.8);
+        assert!(brush.is_ok());
+        let brush = match brush {
+            Ok(brush) => brush,
+            Err(_) => return,
+        };
+        let mut items = brush.config_items();
+        items.rotate_left(5);
+        let values = BrushConfigValues::defaults(&items)
+            .with("base_radius_px", BrushConfigValue::ScalarF32(9.0))
+            .with("unknown_key", BrushConfigValue::Bool(true));
+        let rebuilt = RoundBrush::from_config_values(&values);
+        assert!(rebuilt.is_ok());
+        let rebuilt = match rebuilt {
+            Ok(rebuilt) => rebuilt,
+            Err(_) => return,
+        };
+        assert_eq!(rebuilt.brush_size(), 9);
+
+        let values = values.with(
+            "base_hardness",
+            BrushConfigValue::UnitIntervalCurve(Vec::new()),
+        );
+        assert_eq!(
+            RoundBrush::from_config_values(&values),
+            Err(RoundBrushConfigError::Config(
+                BrushConfigValueError::TypeMismatch {
+                    key: "base_hardness"
+                }
+            ))
+        );
+    }
 }
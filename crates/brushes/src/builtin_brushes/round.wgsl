@@ -121,7 +121,10 @@ fn fs_main(@builtin(position) pos: vec4<f32>) -> @location(0) vec4<f32> {
         if (thickness <= 0.0) {
             discard;
         }
-        return vec4<f32>(0.0, 0.0, 0.0, thickness);
+        // Color is premultiplied by thickness so dabs of different colors
+        // blend by how much each one deposited.
+        let tint = vec3<f32>(params.tint_r, params.tint_g, params.tint_b);
+        return vec4<f32>(tint * thickness, thickness);
     }
 
     let source_texel = vec2<i32>(
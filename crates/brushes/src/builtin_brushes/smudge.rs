@@ -0,0 +1,302 @@
+use std::borrow::Cow;
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+
+use glaphica_core::{BrushInput, CanvasVec2, TileKey};
+
+use crate::BrushPipelineError;
+use crate::brush_spec::BrushSpec;
+use crate::config::{
+    BrushConfigItem, BrushConfigKind, BrushConfigValue, BrushConfigValueError, BrushConfigValues,
+};
+use crate::draw_layout::{BrushDrawInputLayout, BrushDrawInputShape, BrushDrawKind};
+use crate::engine_runtime::EngineBrushPipeline;
+use crate::gpu_pipeline_spec::BrushGpuPipelineSpec;
+use crate::resampler_distance::BrushResamplerDistancePolicy;
+
+/// Center, drag offset to the previous dab, radius, hardness, strength,
+/// pickup rate, brush color mix.
+pub const SMUDGE_DRAW_LAYOUT: BrushDrawInputLayout = BrushDrawInputLayout::new(
+    BrushDrawKind::Smudge,
+    &[
+        BrushDrawInputShape::Vec2F32,
+        BrushDrawInputShape::Vec2F32,
+        BrushDrawInputShape::F32,
+        BrushDrawInputShape::F32,
+        BrushDrawInputShape::F32,
+        BrushDrawInputShape::F32,
+        BrushDrawInputShape::F32,
+    ],
+);
+
+const RADIUS_RANGE: (f32, f32) = (1.0, 128.0);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SmudgeBrushConfigError {
+    Config(BrushConfigValueError),
+    RadiusOutOfRange,
+}
+
+impl Display for SmudgeBrushConfigError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Config(error) => write!(f, "smudge brush {error}"),
+            Self::RadiusOutOfRange => write!(f, "smudge brush size must be in [1, 128]"),
+        }
+    }
+}
+
+impl Error for SmudgeBrushConfigError {}
+
+impl From<BrushConfigValueError> for SmudgeBrushConfigError {
+    fn from(error: BrushConfigValueError) -> Self {
+        Self::Config(error)
+    }
+}
+
+/// Drags canvas color from the previous dab into the current one.
+///
+/// Color is read from the document composite bound as the ref image, so the
+/// smudge sees every visible layer as of the previous frame.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SmudgeBrush {
+    radius_px: f32,
+    hardness: f32,
+    strength: f32,
+    pickup_rate: f32,
+    color_mix: f32,
+}
+
+impl SmudgeBrush {
+    /// Dab spacing as a fraction of the radius; short steps keep the drag
+    /// offset inside the sampled tile.
+    pub const SPACING_RATE: f32 = 0.15;
+
+    pub fn new(radius_px: f32) -> Self {
+        Self {
+            radius_px: radius_px.clamp(RADIUS_RANGE.0, RADIUS_RANGE.1),
+            hardness: 0.5,
+            strength: 0.8,
+            pickup_rate: 0.2,
+            color_mix: 0.0,
+        }
+    }
+
+    pub fn config_items(&self) -> Vec<BrushConfigItem> {
+        vec![
+            scalar_item("radius_px", "Size", RADIUS_RANGE, self.radius_px),
+            scalar_item("hardness", "Hardness", (0.0, 1.0), self.hardness),
+            scalar_item("strength", "Strength", (0.0, 1.0), self.strength),
+            scalar_item("pickup_rate", "Pickup Rate", (0.0, 1.0), self.pickup_rate),
+            scalar_item("color_mix", "Brush Color Mix", (0.0, 1.0), self.color_mix),
+        ]
+    }
+
+    pub fn from_config_values(values: &BrushConfigValues) -> Result<Self, SmudgeBrushConfigError> {
+        let radius_px = values.scalar_f32("radius_px")?;
+        let hardness = values.scalar_f32("hardness")?;
+        let strength = values.scalar_f32("strength")?;
+        let pickup_rate = values.scalar_f32("pickup_rate")?;
+        let color_mix = values.scalar_f32("color_mix")?;
+        if !(RADIUS_RANGE.0..=RADIUS_RANGE.1).contains(&radius_px) {
+            return Err(SmudgeBrushConfigError::RadiusOutOfRange);
+        }
+        Ok(Self {
+            radius_px,
+            hardness: hardness.clamp(0.0, 1.0),
+            strength: strength.clamp(0.0, 1.0),
+            pickup_rate: pickup_rate.clamp(0.0, 1.0),
+            color_mix: color_mix.clamp(0.0, 1.0),
+        })
+    }
+}
+
+fn scalar_item(
+    key: &'static str,
+    label: &'static str,
+    (min, max): (f32, f32),
+    value: f32,
+) -> BrushConfigItem {
+    BrushConfigItem {
+        key,
+        label,
+        default_hidden: false,
+        kind: BrushConfigKind::ScalarF32 { min, max },
+        default_value: BrushConfigValue::ScalarF32(value),
+    }
+}
+
+impl BrushResamplerDistancePolicy for SmudgeBrush {
+    fn brush_size(&self) -> u32 {
+        self.radius_px.ceil() as u32
+    }
+
+    fn max_distance_rate(&self) -> f32 {
+        Self::SPACING_RATE
+    }
+
+    fn min_distance_rate(&self) -> f32 {
+        Self::SPACING_RATE
+    }
+}
+
+impl EngineBrushPipeline for SmudgeBrush {
+    fn encode_draw_input(
+        &mut self,
+        brush_input: &BrushInput,
+        _tile_key: TileKey,
+        tile_canvas_origin: CanvasVec2,
+    ) -> Result<Vec<f32>, BrushPipelineError> {
+        let local_x = brush_input.cursor.cursor.x - tile_canvas_origin.x;
+        let local_y = brush_input.cursor.cursor.y - tile_canvas_origin.y;
+        // The previous dab sits one resampling step back along the tangent,
+        // which keeps the offset identical for every tile the dab touches.
+        let drag_x = -brush_input.tangent.x * brush_input.delta_s;
+        let drag_y = -brush_input.tangent.y * brush_input.delta_s;
+        let pressure = brush_input.cursor.pressure.clamp(0.0, 1.0);
+        Ok(vec![
+            local_x,
+            local_y,
+            drag_x,
+            drag_y,
+            self.radius_px,
+            self.hardness,
+            self.strength * pressure,
+            self.pickup_rate,
+            self.color_mix,
+        ])
+    }
+
+    fn samples_composite(&self) -> bool {
+        true
+    }
+}
+
+impl BrushSpec for SmudgeBrush {
+    fn max_affected_radius_px(&self) -> u32 {
+        self.radius_px.ceil() as u32
+    }
+
+    fn draw_input_layout(&self) -> BrushDrawInputLayout {
+        SMUDGE_DRAW_LAYOUT
+    }
+
+    fn gpu_pipeline_spec(&self) -> BrushGpuPipelineSpec {
+        BrushGpuPipelineSpec {
+            label: "smudge-brush",
+            wgsl_source: Cow::Borrowed(include_str!("smudge.wgsl")),
+            vertex_entry: "vs_main",
+            fragment_entry: "fs_main",
+            uses_brush_cache_backend: false,
+            cache_backend_format: None,
+            tip_image: None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use glaphica_core::{
+        BrushInput, BrushInputFlags, CanvasVec2, MappedCursor, RadianVec2, StrokeId, TileKey,
+    };
+
+    use crate::config::{BrushConfigValue, BrushConfigValueError, BrushConfigValues};
+    use crate::engine_runtime::EngineBrushPipeline;
+    use crate::file_brush::validate_wgsl;
+
+    use super::{SMUDGE_DRAW_LAYOUT, SmudgeBrush, SmudgeBrushConfigError};
+
+    fn build_input(center: CanvasVec2, pressure: f32, delta_s: f32) -> BrushInput {
+        BrushInput {
+            stroke: StrokeId(5),
+            cursor: MappedCursor {
+                cursor: center,
+                tilt: RadianVec2::new(0.0, 0.0),
+                pressure,
+                twist: 0.0,
+            },
+            flags: BrushInputFlags::empty(),
+            path_s: 30.0,
+            delta_s,
+            dt_s: 0.0,
+            vel: CanvasVec2::new(0.0, 0.0),
+            speed: 0.0,
+            tangent: CanvasVec2::new(1.0, 0.0),
+            acc: CanvasVec2::new(0.0, 0.0),
+            accel: 0.0,
+            curvature: 0.0,
+            confidence: 1.0,
+        }
+    }
+
+    #[test]
+    fn encode_points_drag_back_along_the_stroke() {
+        let values: Vec<_> = [20.0, 0.5, 0.6, 0.25, 0.1]
+            .into_iter()
+            .map(BrushConfigValue::ScalarF32)
+            .collect();
+        let values = BrushConfigValues::from_items(&SmudgeBrush::new(1.0).config_items(), &values);
+        let brush = SmudgeBrush::from_config_values(&values);
+        assert!(brush.is_ok());
+        let mut brush = match brush {
+            Ok(brush) => brush,
+            Err(_) => return,
+        };
+        let input = build_input(CanvasVec2::new(70.0, 80.0), 0.5, 3.0);
+        let encoded = brush.encode_draw_input(
+            &input,
+            TileKey::from_parts(0, 0, 0),
+            CanvasVec2::new(62.0, 62.0),
+        );
+        assert!(encoded.is_ok());
+        let encoded = match encoded {
+            Ok(encoded) => encoded,
+            Err(_) => return,
+        };
+        assert!(SMUDGE_DRAW_LAYOUT.validate_input(&encoded));
+        assert_eq!(
+            encoded,
+            vec![8.0, 18.0, -3.0, 0.0, 20.0, 0.5, 0.3, 0.25, 0.1]
+        );
+        assert!(brush.samples_composite());
+    }
+
+    #[test]
+    fn from_config_values_rejects_out_of_range_radius() {
+        let values: Vec<_> = [0.0, 0.5, 0.5, 0.5, 0.0]
+            .into_iter()
+            .map(BrushConfigValue::ScalarF32)
+            .collect();
+        let values = BrushConfigValues::from_items(&SmudgeBrush::new(1.0).config_items(), &values);
+        assert_eq!(
+            SmudgeBrush::from_config_values(&values),
+            Err(SmudgeBrushConfigError::RadiusOutOfRange)
+        );
+    }
+
+    #[test]
+    fn from_config_values_reads_by_key_and_reports_missing_keys() {
+        let defaults = SmudgeBrush::new(12.0).config_items();
+        let mut reordered: Vec<_> = defaults.clone();
+        reordered.reverse();
+        let values = BrushConfigValues::defaults(&reordered);
+        assert_eq!(
+            SmudgeBrush::from_config_values(&values),
+            Ok(SmudgeBrush::new(12.0))
+        );
+
+        let values = BrushConfigValues::defaults(&defaults[..4]);
+        assert_eq!(
+            SmudgeBrush::from_config_values(&values),
+            Err(SmudgeBrushConfigError::Config(
+                BrushConfigValueError::Missing { key: "color_mix" }
+            ))
+        );
+    }
+
+    #[test]
+    fn smudge_shader_validates() {
+        let result = validate_wgsl(include_str!("smudge.wgsl"), "vs_main", "fs_main");
+        assert!(result.is_ok(), "{:?}", result.err());
+    }
+}
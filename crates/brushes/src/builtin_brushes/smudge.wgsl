@@ -0,0 +1,112 @@
+struct DrawInput {
+    center_local_x: f32,
+    center_local_y: f32,
+    drag_x: f32,
+    drag_y: f32,
+    radius_px: f32,
+    hardness: f32,
+    strength: f32,
+    pickup_rate: f32,
+    color_mix: f32,
+}
+
+struct ShaderParams {
+    input_len: u32,
+    tile_origin_x: u32,
+    tile_origin_y: u32,
+    tile_layer: u32,
+    tile_size_x: u32,
+    tile_size_y: u32,
+    src_tile_origin_x: u32,
+    src_tile_origin_y: u32,
+    src_tile_layer: u32,
+    cache_tile_origin_x: u32,
+    cache_tile_origin_y: u32,
+    cache_tile_layer: u32,
+    has_cache_tile: u32,
+    erase: u32,
+    tint_r: f32,
+    tint_g: f32,
+    tint_b: f32,
+    has_ref_image: u32,
+}
+
+@group(0) @binding(0) var<storage, read> draw_input: DrawInput;
+@group(0) @binding(1) var<uniform> params: ShaderParams;
+@group(1) @binding(0) var source_atlas: texture_2d_array<f32>;
+@group(1) @binding(1) var cache_atlas: texture_2d_array<f32>;
+@group(1) @binding(2) var atlas_sampler: sampler;
+
+@vertex
+fn vs_main(@builtin(vertex_index) vertex_index: u32) -> @builtin(position) vec4<f32> {
+    let positions = array<vec2<f32>, 3>(
+        vec2<f32>(-1.0, -1.0),
+        vec2<f32>(3.0, -1.0),
+        vec2<f32>(-1.0, 3.0),
+    );
+    let xy = positions[vertex_index];
+    return vec4<f32>(xy, 0.0, 1.0);
+}
+
+// Reads the composite tile at the same place as `pos`, shifted by `offset`.
+// The atlas only keeps a 1px gutter, so reads past the tile clamp to its edge.
+fn load_source(pos: vec2<f32>, offset: vec2<f32>) -> vec4<f32> {
+    if (params.has_ref_image == 0u) {
+        return vec4<f32>(0.0);
+    }
+    let local = vec2<i32>(
+        i32(pos.x) - i32(params.tile_origin_x),
+        i32(pos.y) - i32(params.tile_origin_y),
+    ) + vec2<i32>(round(offset));
+    let clamped = clamp(
+        local,
+        vec2<i32>(0),
+        vec2<i32>(i32(params.tile_size_x) - 1, i32(params.tile_size_y) - 1),
+    );
+    let texel = clamped + vec2<i32>(i32(params.src_tile_origin_x), i32(params.src_tile_origin_y));
+    return textureLoad(source_atlas, texel, i32(params.src_tile_layer), 0);
+}
+
+@fragment
+fn fs_main(@builtin(position) pos: vec4<f32>) -> @location(0) vec4<f32> {
+    // Brush input uses image-tile coordinates (62x62), atlas tile is 64x64 with 1px gutter.
+    let tile_local = vec2<f32>(
+        pos.x - f32(params.tile_origin_x) - 1.0,
+        pos.y - f32(params.tile_origin_y) - 1.0,
+    );
+    let center = vec2<f32>(draw_input.center_local_x, draw_input.center_local_y);
+    let radius = max(draw_input.radius_px, 0.5);
+    let t = distance(tile_local, center) / radius;
+    if (t >= 1.0) {
+        discard;
+    }
+    let hardness = clamp(draw_input.hardness, 0.0, 0.99);
+    let falloff = 1.0 - smoothstep(hardness, 1.0, t);
+    let coverage = falloff * clamp(draw_input.strength, 0.0, 1.0);
+    if (coverage <= 0.0) {
+        discard;
+    }
+
+    if (params.erase != 0u) {
+        // Erase draws replace texels outright, so only the dab's solid core erases.
+        if (coverage < 0.5) {
+            discard;
+        }
+        return vec4<f32>(0.0);
+    }
+
+    // Low pickup keeps dragging what sat under the previous dab; high pickup
+    // takes up the color already under the current one.
+    let carried = load_source(pos.xy, vec2<f32>(draw_input.drag_x, draw_input.drag_y));
+    let under = load_source(pos.xy, vec2<f32>(0.0));
+    let picked = mix(carried, under, clamp(draw_input.pickup_rate, 0.0, 1.0));
+
+    let tint = vec3<f32>(params.tint_r, params.tint_g, params.tint_b);
+    let color_mix = clamp(draw_input.color_mix, 0.0, 1.0);
+    let rgb = mix(picked.rgb, tint, color_mix);
+    let alpha = mix(picked.a, 1.0, color_mix) * coverage;
+    if (alpha <= 0.0) {
+        discard;
+    }
+    return vec4<f32>(rgb, alpha);
+}
@@ -0,0 +1,401 @@
+use std::borrow::Cow;
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+
+use glaphica_core::{BrushInput, CanvasVec2, StrokeId, TileKey};
+
+use crate::BrushPipelineError;
+use crate::brush_spec::BrushSpec;
+use crate::config::{
+    BrushConfigItem, BrushConfigKind, BrushConfigValue, BrushConfigValueError, BrushConfigValues,
+};
+use crate::draw_layout::{BrushDrawInputLayout, BrushDrawInputShape, BrushDrawKind};
+use crate::dynamics::unit_random;
+use crate::engine_runtime::EngineBrushPipeline;
+use crate::gpu_pipeline_spec::BrushGpuPipelineSpec;
+use crate::resampler_distance::BrushResamplerDistancePolicy;
+
+/// Center, spray radius, particle size, size jitter, opacity, opacity jitter,
+/// particle seed, particle count.
+pub const SPRAY_DRAW_LAYOUT: BrushDrawInputLayout = BrushDrawInputLayout::new(
+    BrushDrawKind::Spray,
+    &[
+        BrushDrawInputShape::Vec2F32,
+        BrushDrawInputShape::F32,
+        BrushDrawInputShape::F32,
+        BrushDrawInputShape::F32,
+        BrushDrawInputShape::F32,
+        BrushDrawInputShape::F32,
+        BrushDrawInputShape::F32,
+        BrushDrawInputShape::F32,
+    ],
+);
+
+/// Upper bound on particles drawn by one dab; the shader loops over all of them
+/// for every covered texel.
+pub const SPRAY_MAX_PARTICLES_PER_DAB: u32 = 256;
+
+const RADIUS_RANGE: (f32, f32) = (1.0, 256.0);
+const DENSITY_RANGE: (f32, f32) = (0.0, 200.0);
+const FLOW_RANGE: (f32, f32) = (0.0, 2000.0);
+const PARTICLE_SIZE_RANGE: (f32, f32) = (0.5, 16.0);
+/// Seeds are kept to 24 bits so they survive the trip through an `f32` lane.
+const SEED_SCALE: f32 = (1u32 << 24) as f32;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SprayBrushConfigError {
+    Config(BrushConfigValueError),
+    RadiusOutOfRange,
+    DensityOutOfRange,
+    FlowOutOfRange,
+    ParticleSizeOutOfRange,
+}
+
+impl Display for SprayBrushConfigError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Config(error) => write!(f, "spray brush {error}"),
+            Self::RadiusOutOfRange => write!(f, "spray brush size must be in [1, 256]"),
+            Self::DensityOutOfRange => write!(f, "spray brush density must be in [0, 200]"),
+            Self::FlowOutOfRange => write!(f, "spray brush flow must be in [0, 2000]"),
+            Self::ParticleSizeOutOfRange => {
+                write!(f, "spray brush particle size must be in [0.5, 16]")
+            }
+        }
+    }
+}
+
+impl Error for SprayBrushConfigError {}
+
+impl From<BrushConfigValueError> for SprayBrushConfigError {
+    fn from(error: BrushConfigValueError) -> Self {
+        Self::Config(error)
+    }
+}
+
+/// Scatters small round particles inside the brush radius.
+///
+/// Particles are emitted per distance travelled (`density`) and per second
+/// (`flow_per_second`), so a stationary pen keeps spraying. The random state is
+/// seeded from the stroke id and advanced once per dab, which makes replaying the
+/// same input produce the same particles.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SprayBrush {
+    radius_px: f32,
+    density: f32,
+    flow_per_second: f32,
+    particle_size_px: f32,
+    size_jitter: f32,
+    opacity: f32,
+    opacity_jitter: f32,
+    stroke: Option<StrokeId>,
+    rng_state: u64,
+    emission_carry: f32,
+    dab_seed: u32,
+    dab_particle_count: u32,
+}
+
+impl SprayBrush {
+    pub fn new(radius_px: f32) -> Self {
+        Self {
+            radius_px: radius_px.clamp(RADIUS_RANGE.0, RADIUS_RANGE.1),
+            density: 40.0,
+            flow_per_second: 300.0,
+            particle_size_px: 1.5,
+            size_jitter: 0.5,
+            opacity: 0.8,
+            opacity_jitter: 0.3,
+            stroke: None,
+            rng_state: 0,
+            emission_carry: 0.0,
+            dab_seed: 0,
+            dab_particle_count: 0,
+        }
+    }
+
+    pub fn config_items(&self) -> Vec<BrushConfigItem> {
+        vec![
+            scalar_item("radius_px", "Size", false, RADIUS_RANGE, self.radius_px),
+            scalar_item(
+                "density",
+                "Density (per radius)",
+                false,
+                DENSITY_RANGE,
+                self.density,
+            ),
+            scalar_item(
+                "flow_per_second",
+                "Flow (per second)",
+                false,
+                FLOW_RANGE,
+                self.flow_per_second,
+            ),
+            scalar_item(
+                "particle_size_px",
+                "Particle Size",
+                false,
+                PARTICLE_SIZE_RANGE,
+                self.particle_size_px,
+            ),
+            scalar_item(
+                "size_jitter",
+                "Size Jitter",
+                true,
+                (0.0, 1.0),
+                self.size_jitter,
+            ),
+            scalar_item("opacity", "Opacity", false, (0.0, 1.0), self.opacity),
+            scalar_item(
+                "opacity_jitter",
+                "Opacity Jitter",
+                true,
+                (0.0, 1.0),
+                self.opacity_jitter,
+            ),
+        ]
+    }
+
+    pub fn from_config_values(values: &BrushConfigValues) -> Result<Self, SprayBrushConfigError> {
+        let radius_px = values.scalar_f32("radius_px")?;
+        let density = values.scalar_f32("density")?;
+        let flow_per_second = values.scalar_f32("flow_per_second")?;
+        let particle_size_px = values.scalar_f32("particle_size_px")?;
+        let size_jitter = values.scalar_f32("size_jitter")?;
+        let opacity = values.scalar_f32("opacity")?;
+        let opacity_jitter = values.scalar_f32("opacity_jitter")?;
+        if !(RADIUS_RANGE.0..=RADIUS_RANGE.1).contains(&radius_px) {
+            return Err(SprayBrushConfigError::RadiusOutOfRange);
+        }
+        if !(DENSITY_RANGE.0..=DENSITY_RANGE.1).contains(&density) {
+            return Err(SprayBrushConfigError::DensityOutOfRange);
+        }
+        if !(FLOW_RANGE.0..=FLOW_RANGE.1).contains(&flow_per_second) {
+            return Err(SprayBrushConfigError::FlowOutOfRange);
+        }
+        if !(PARTICLE_SIZE_RANGE.0..=PARTICLE_SIZE_RANGE.1).contains(&particle_size_px) {
+            return Err(SprayBrushConfigError::ParticleSizeOutOfRange);
+        }
+        let mut brush = Self::new(radius_px);
+        brush.density = density;
+        brush.flow_per_second = flow_per_second;
+        brush.particle_size_px = particle_size_px;
+        brush.size_jitter = size_jitter.clamp(0.0, 1.0);
+        brush.opacity = opacity.clamp(0.0, 1.0);
+        brush.opacity_jitter = opacity_jitter.clamp(0.0, 1.0);
+        Ok(brush)
+    }
+
+    /// Particles owed for one dab before the fractional carry is applied.
+    fn emission(&self, brush_input: &BrushInput) -> f32 {
+        let travelled = self.density * brush_input.delta_s.max(0.0) / self.radius_px;
+        let sprayed = self.flow_per_second * brush_input.dt_s.max(0.0);
+        (travelled + sprayed) * brush_input.cursor.pressure.clamp(0.0, 1.0)
+    }
+}
+
+fn scalar_item(
+    key: &'static str,
+    label: &'static str,
+    default_hidden: bool,
+    (min, max): (f32, f32),
+    value: f32,
+) -> BrushConfigItem {
+    BrushConfigItem {
+        key,
+        label,
+        default_hidden,
+        kind: BrushConfigKind::ScalarF32 { min, max },
+        default_value: BrushConfigValue::ScalarF32(value),
+    }
+}
+
+impl BrushResamplerDistancePolicy for SprayBrush {
+    fn brush_size(&self) -> u32 {
+        self.radius_px.ceil() as u32
+    }
+
+    fn max_distance_rate(&self) -> f32 {
+        0.5
+    }
+
+    fn min_distance_rate(&self) -> f32 {
+        0.25
+    }
+}
+
+impl EngineBrushPipeline for SprayBrush {
+    fn begin_dab(&mut self, brush_input: &BrushInput) {
+        if self.stroke != Some(brush_input.stroke) {
+            self.stroke = Some(brush_input.stroke);
+            self.rng_state = brush_input.stroke.0;
+            self.emission_carry = 0.0;
+        }
+        let emission = self.emission(brush_input) + self.emission_carry;
+        let count = emission.floor().min(SPRAY_MAX_PARTICLES_PER_DAB as f32);
+        self.emission_carry = (emission - count).clamp(0.0, 1.0);
+        self.dab_particle_count = count as u32;
+        self.dab_seed = (unit_random(&mut self.rng_state) * SEED_SCALE) as u32;
+    }
+
+    fn encode_draw_input(
+        &mut self,
+        brush_input: &BrushInput,
+        _tile_key: TileKey,
+        tile_canvas_origin: CanvasVec2,
+    ) -> Result<Vec<f32>, BrushPipelineError> {
+        let local_x = brush_input.cursor.cursor.x - tile_canvas_origin.x;
+        let local_y = brush_input.cursor.cursor.y - tile_canvas_origin.y;
+        Ok(vec![
+            local_x,
+            local_y,
+            self.radius_px,
+            self.particle_size_px,
+            self.size_jitter,
+            self.opacity,
+            self.opacity_jitter,
+            self.dab_seed as f32,
+            self.dab_particle_count as f32,
+        ])
+    }
+}
+
+impl BrushSpec for SprayBrush {
+    fn max_affected_radius_px(&self) -> u32 {
+        (self.radius_px + self.particle_size_px).ceil() as u32
+    }
+
+    fn draw_input_layout(&self) -> BrushDrawInputLayout {
+        SPRAY_DRAW_LAYOUT
+    }
+
+    fn gpu_pipeline_spec(&self) -> BrushGpuPipelineSpec {
+        BrushGpuPipelineSpec {
+            label: "spray-brush",
+            wgsl_source: Cow::Borrowed(include_str!("spray.wgsl")),
+            vertex_entry: "vs_main",
+            fragment_entry: "fs_main",
+            uses_brush_cache_backend: false,
+            cache_backend_format: None,
+            tip_image: None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use glaphica_core::{
+        BrushInput, BrushInputFlags, CanvasVec2, MappedCursor, RadianVec2, StrokeId, TileKey,
+    };
+
+    use crate::config::{BrushConfigValue, BrushConfigValues};
+    use crate::engine_runtime::EngineBrushPipeline;
+    use crate::file_brush::validate_wgsl;
+
+    use super::{SPRAY_DRAW_LAYOUT, SprayBrush, SprayBrushConfigError};
+
+    fn build_input(stroke: u64, center: CanvasVec2, delta_s: f32, dt_s: f32) -> BrushInput {
+        BrushInput {
+            stroke: StrokeId(stroke),
+            cursor: MappedCursor {
+                cursor: center,
+                tilt: RadianVec2::new(0.0, 0.0),
+                pressure: 1.0,
+                twist: 0.0,
+            },
+            flags: BrushInputFlags::empty(),
+            path_s: 0.0,
+            delta_s,
+            dt_s,
+            vel: CanvasVec2::new(0.0, 0.0),
+            speed: 0.0,
+            tangent: CanvasVec2::new(1.0, 0.0),
+            acc: CanvasVec2::new(0.0, 0.0),
+            accel: 0.0,
+            curvature: 0.0,
+            confidence: 1.0,
+        }
+    }
+
+    fn brush(density: f32, flow: f32) -> Option<SprayBrush> {
+        let values: Vec<_> = [10.0, density, flow, 2.0, 0.5, 1.0, 0.0]
+            .into_iter()
+            .map(BrushConfigValue::ScalarF32)
+            .collect();
+        let values = BrushConfigValues::from_items(&SprayBrush::new(1.0).config_items(), &values);
+        SprayBrush::from_config_values(&values).ok()
+    }
+
+    fn encode_dab(brush: &mut SprayBrush, input: &BrushInput, origin: CanvasVec2) -> Vec<f32> {
+        brush.begin_dab(input);
+        brush
+            .encode_draw_input(input, TileKey::from_parts(0, 0, 0), origin)
+            .unwrap_or_default()
+    }
+
+    #[test]
+    fn stationary_pen_keeps_emitting_from_elapsed_time() {
+        let Some(mut brush) = brush(0.0, 100.0) else {
+            return;
+        };
+        let input = build_input(1, CanvasVec2::new(5.0, 5.0), 0.0, 0.1);
+        let encoded = encode_dab(&mut brush, &input, CanvasVec2::new(0.0, 0.0));
+        assert!(SPRAY_DRAW_LAYOUT.validate_input(&encoded));
+        assert_eq!(encoded[8], 10.0);
+
+        // Fractions carry over so slow emission still lands eventually.
+        let trickle = build_input(1, CanvasVec2::new(5.0, 5.0), 0.0, 0.006);
+        let counts: Vec<f32> = (0..4)
+            .map(|_| encode_dab(&mut brush, &trickle, CanvasVec2::new(0.0, 0.0))[8])
+            .collect();
+        assert_eq!(counts, vec![0.0, 1.0, 0.0, 1.0]);
+    }
+
+    #[test]
+    fn particle_seeds_replay_per_stroke_and_match_across_tiles() {
+        let (Some(mut first), Some(mut second)) = (brush(40.0, 0.0), brush(40.0, 0.0)) else {
+            return;
+        };
+        let input = build_input(7, CanvasVec2::new(62.0, 20.0), 5.0, 0.01);
+        let first_seeds: Vec<f32> = (0..3)
+            .map(|_| encode_dab(&mut first, &input, CanvasVec2::new(0.0, 0.0))[7])
+            .collect();
+        let second_seeds: Vec<f32> = (0..3)
+            .map(|_| encode_dab(&mut second, &input, CanvasVec2::new(0.0, 0.0))[7])
+            .collect();
+        assert_eq!(first_seeds, second_seeds);
+        assert_ne!(first_seeds[0], first_seeds[1]);
+
+        let neighbour = first.encode_draw_input(
+            &input,
+            TileKey::from_parts(0, 0, 1),
+            CanvasVec2::new(62.0, 0.0),
+        );
+        assert_eq!(
+            neighbour.ok().map(|encoded| encoded[7]),
+            Some(first_seeds[2])
+        );
+
+        let other_stroke = build_input(8, CanvasVec2::new(62.0, 20.0), 5.0, 0.01);
+        let other = encode_dab(&mut first, &other_stroke, CanvasVec2::new(0.0, 0.0));
+        assert_ne!(other[7], first_seeds[0]);
+    }
+
+    #[test]
+    fn from_config_values_rejects_out_of_range_particle_size() {
+        let values: Vec<_> = [10.0, 40.0, 100.0, 0.0, 0.5, 1.0, 0.0]
+            .into_iter()
+            .map(BrushConfigValue::ScalarF32)
+            .collect();
+        let values = BrushConfigValues::from_items(&SprayBrush::new(1.0).config_items(), &values);
+        assert_eq!(
+            SprayBrush::from_config_values(&values),
+            Err(SprayBrushConfigError::ParticleSizeOutOfRange)
+        );
+    }
+
+    #[test]
+    fn spray_shader_validates() {
+        let result = validate_wgsl(include_str!("spray.wgsl"), "vs_main", "fs_main");
+        assert!(result.is_ok(), "{:?}", result.err());
+    }
+}
@@ -0,0 +1,115 @@
+struct DrawInput {
+    center_local_x: f32,
+    center_local_y: f32,
+    radius_px: f32,
+    particle_size_px: f32,
+    size_jitter: f32,
+    opacity: f32,
+    opacity_jitter: f32,
+    seed: f32,
+    particle_count: f32,
+}
+
+struct ShaderParams {
+    input_len: u32,
+    tile_origin_x: u32,
+    tile_origin_y: u32,
+    tile_layer: u32,
+    tile_size_x: u32,
+    tile_size_y: u32,
+    src_tile_origin_x: u32,
+    src_tile_origin_y: u32,
+    src_tile_layer: u32,
+    cache_tile_origin_x: u32,
+    cache_tile_origin_y: u32,
+    cache_tile_layer: u32,
+    has_cache_tile: u32,
+    erase: u32,
+    tint_r: f32,
+    tint_g: f32,
+    tint_b: f32,
+    _pad0: f32,
+}
+
+@group(0) @binding(0) var<storage, read> draw_input: DrawInput;
+@group(0) @binding(1) var<uniform> params: ShaderParams;
+
+const TAU: f32 = 6.283185307179586;
+const MAX_PARTICLES: u32 = 256u;
+
+@vertex
+fn vs_main(@builtin(vertex_index) vertex_index: u32) -> @builtin(position) vec4<f32> {
+    let positions = array<vec2<f32>, 3>(
+        vec2<f32>(-1.0, -1.0),
+        vec2<f32>(3.0, -1.0),
+        vec2<f32>(-1.0, 3.0),
+    );
+    let xy = positions[vertex_index];
+    return vec4<f32>(xy, 0.0, 1.0);
+}
+
+// PCG hash; integer only so every tile of a dab places the same particles.
+fn pcg_hash(value: u32) -> u32 {
+    let state = value * 747796405u + 2891336453u;
+    let word = ((state >> ((state >> 28u) + 4u)) ^ state) * 277803737u;
+    return (word >> 22u) ^ word;
+}
+
+fn unit_from_hash(hash: u32) -> f32 {
+    return f32(hash >> 8u) / 16777216.0;
+}
+
+@fragment
+fn fs_main(@builtin(position) pos: vec4<f32>) -> @location(0) vec4<f32> {
+    // Brush input uses image-tile coordinates (62x62), atlas tile is 64x64 with 1px gutter.
+    let pixel = vec2<f32>(
+        pos.x - f32(params.tile_origin_x) - 1.0,
+        pos.y - f32(params.tile_origin_y) - 1.0,
+    );
+    let center = vec2<f32>(draw_input.center_local_x, draw_input.center_local_y);
+    let radius = max(draw_input.radius_px, 0.0);
+    let max_particle_radius = max(draw_input.particle_size_px, 0.0) * 0.5;
+    if (distance(pixel, center) > radius + max_particle_radius + 1.0) {
+        discard;
+    }
+
+    let seed = u32(draw_input.seed);
+    let count = min(u32(draw_input.particle_count), MAX_PARTICLES);
+    var transmittance = 1.0;
+    var strongest = 0.0;
+    for (var index = 0u; index < count; index = index + 1u) {
+        let h0 = pcg_hash(seed ^ pcg_hash(index));
+        let h1 = pcg_hash(h0);
+        let h2 = pcg_hash(h1);
+        let h3 = pcg_hash(h2);
+        // sqrt keeps the scatter uniform over the disc's area.
+        let r = radius * sqrt(unit_from_hash(h0));
+        let angle = TAU * unit_from_hash(h1);
+        let particle = center + r * vec2<f32>(cos(angle), sin(angle));
+        let particle_radius =
+            max_particle_radius * (1.0 - clamp(draw_input.size_jitter, 0.0, 1.0) * unit_from_hash(h2));
+        let coverage = clamp(particle_radius + 0.5 - distance(pixel, particle), 0.0, 1.0);
+        if (coverage <= 0.0) {
+            continue;
+        }
+        let alpha = coverage * clamp(draw_input.opacity, 0.0, 1.0)
+            * (1.0 - clamp(draw_input.opacity_jitter, 0.0, 1.0) * unit_from_hash(h3));
+        transmittance = transmittance * (1.0 - alpha);
+        strongest = max(strongest, alpha);
+    }
+
+    let alpha = 1.0 - transmittance;
+    if (alpha <= 0.0) {
+        discard;
+    }
+
+    if (params.erase != 0u) {
+        // Erase draws replace texels outright, so only well-covered particles erase.
+        if (strongest < 0.5) {
+            discard;
+        }
+        return vec4<f32>(0.0);
+    }
+    let tint = vec3<f32>(params.tint_r, params.tint_g, params.tint_b);
+    return vec4<f32>(tint, alpha);
+}
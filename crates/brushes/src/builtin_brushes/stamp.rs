@@ -0,0 +1,548 @@
+use std::borrow::Cow;
+use std::error::Error;
+use std::f32::consts::SQRT_2;
+use std::fmt::{Display, Formatter};
+use std::sync::Arc;
+
+use glaphica_core::{BackendKind, BrushInput, CanvasVec2, TextureFormat, TileKey};
+
+use crate::BrushPipelineError;
+use crate::brush_spec::BrushSpec;
+use crate::config::{
+    BrushConfigItem, BrushConfigKind, BrushConfigValue, BrushConfigValueError, BrushConfigValues,
+};
+use crate::draw_layout::{BrushDrawInputLayout, BrushDrawInputShape, BrushDrawKind};
+use crate::dynamics::{DabDynamicsTarget, DabVariation, unit_random};
+use crate::engine_runtime::EngineBrushPipeline;
+use crate::gpu_pipeline_spec::BrushGpuPipelineSpec;
+use crate::resampler_distance::BrushResamplerDistancePolicy;
+use crate::tip_image::BrushTipImage;
+
+/// Center, half extent, rotation as (cos, sin), horizontal flip sign, opacity,
+/// and the size of the tip sheet's base level.
+pub const STAMP_DRAW_LAYOUT: BrushDrawInputLayout = BrushDrawInputLayout::new(
+    BrushDrawKind::Stamp,
+    &[
+        BrushDrawInputShape::Vec2F32,
+        BrushDrawInputShape::Vec2F32,
+        BrushDrawInputShape::Vec2F32,
+        BrushDrawInputShape::F32,
+        BrushDrawInputShape::F32,
+        BrushDrawInputShape::Vec2F32,
+    ],
+);
+
+const SIZE_RANGE: (f32, f32) = (1.0, 512.0);
+const SPACING_PERCENT_RANGE: (f32, f32) = (1.0, 400.0);
+const ROTATION_OPTIONS: &[&str] = &["Fixed", "Stroke Direction", "Pen Twist"];
+
+/// What the tip's rotation follows before the fixed angle and jitter.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StampRotation {
+    Fixed,
+    StrokeDirection,
+    PenTwist,
+}
+
+impl StampRotation {
+    fn from_index(index: usize) -> Self {
+        match index {
+            1 => Self::StrokeDirection,
+            2 => Self::PenTwist,
+            _ => Self::Fixed,
+        }
+    }
+
+    fn index(self) -> usize {
+        match self {
+            Self::Fixed => 0,
+            Self::StrokeDirection => 1,
+            Self::PenTwist => 2,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StampBrushConfigError {
+    Config(BrushConfigValueError),
+    SizeOutOfRange,
+    SpacingOutOfRange,
+    OpacityOutOfRange,
+}
+
+impl Display for StampBrushConfigError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Config(error) => write!(f, "stamp brush {error}"),
+            Self::SizeOutOfRange => write!(f, "stamp brush size must be in [1, 512]"),
+            Self::SpacingOutOfRange => write!(f, "stamp brush spacing must be in [1, 400]%"),
+            Self::OpacityOutOfRange => write!(f, "stamp brush opacity must be in [0, 1]"),
+        }
+    }
+}
+
+impl Error for StampBrushConfigError {}
+
+impl From<BrushConfigValueError> for StampBrushConfigError {
+    fn from(error: BrushConfigValueError) -> Self {
+        Self::Config(error)
+    }
+}
+
+/// Stamps a grayscale tip image along the stroke.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StampBrush {
+    tip: Arc<BrushTipImage>,
+    tip_texture: String,
+    size_px: f32,
+    spacing_percent: f32,
+    opacity: f32,
+    min_pressure_scale: f32,
+    rotation: StampRotation,
+    angle_deg: f32,
+    random_rotation_deg: f32,
+    random_flip: f32,
+}
+
+impl StampBrush {
+    pub fn new(tip: Arc<BrushTipImage>, size_px: f32, spacing_percent: f32) -> Self {
+        Self {
+            tip,
+            tip_texture: String::new(),
+            size_px: size_px.clamp(SIZE_RANGE.0, SIZE_RANGE.1),
+            spacing_percent: spacing_percent
+                .clamp(SPACING_PERCENT_RANGE.0, SPACING_PERCENT_RANGE.1),
+            opacity: 1.0,
+            min_pressure_scale: 0.2,
+            rotation: StampRotation::Fixed,
+            angle_deg: 0.0,
+            random_rotation_deg: 0.0,
+            random_flip: 0.0,
+        }
+    }
+
+    pub fn tip(&self) -> &Arc<BrushTipImage> {
+        &self.tip
+    }
+
+    /// Texture name the tip was loaded from; empty for the app's default tip.
+    pub fn tip_texture(&self) -> &str {
+        &self.tip_texture
+    }
+
+    pub fn config_items(&self) -> Vec<BrushConfigItem> {
+        vec![
+            scalar_item("size_px", "Size", false, SIZE_RANGE, self.size_px),
+            scalar_item(
+                "spacing_percent",
+                "Spacing %",
+                false,
+                SPACING_PERCENT_RANGE,
+                self.spacing_percent,
+            ),
+            scalar_item("opacity", "Opacity", false, (0.0, 1.0), self.opacity),
+            scalar_item(
+                "min_pressure_scale",
+                "Min Pressure Size",
+                false,
+                (0.0, 1.0),
+                self.min_pressure_scale,
+            ),
+            BrushConfigItem {
+                key: "rotation_source",
+                label: "Rotation",
+                default_hidden: false,
+                kind: BrushConfigKind::Enum {
+                    options: ROTATION_OPTIONS,
+                },
+                default_value: BrushConfigValue::Enum(self.rotation.index()),
+            },
+            angle_item("angle_deg", "Angle", (-180.0, 180.0), self.angle_deg),
+            angle_item(
+                "random_rotation_deg",
+                "Random Rotation",
+                (0.0, 180.0),
+                self.random_rotation_deg,
+            ),
+            scalar_item(
+                "random_flip",
+                "Random Flip",
+                true,
+                (0.0, 1.0),
+                self.random_flip,
+            ),
+            BrushConfigItem {
+                key: "tip_texture",
+                label: "Tip Image (PNG path, empty for default)",
+                default_hidden: true,
+                kind: BrushConfigKind::TextureRef,
+                default_value: BrushConfigValue::TextureRef(self.tip_texture.clone()),
+            },
+        ]
+    }
+
+    /// Builds the brush from keyed values; `tip` is the image the caller
+    /// resolved for the `tip_texture` value.
+    pub fn from_config_values(
+        tip: Arc<BrushTipImage>,
+        values: &BrushConfigValues,
+    ) -> Result<Self, StampBrushConfigError> {
+        let size_px = values.scalar_f32("size_px")?;
+        let spacing_percent = values.scalar_f32("spacing_percent")?;
+        let opacity = values.scalar_f32("opacity")?;
+        let min_pressure_scale = values.scalar_f32("min_pressure_scale")?;
+        let rotation = values.enum_index("rotation_source")?;
+        let angle_deg = values.angle_deg("angle_deg")?;
+        let random_rotation_deg = values.angle_deg("random_rotation_deg")?;
+        let random_flip = values.scalar_f32("random_flip")?;
+        let tip_texture = values.texture_ref("tip_texture")?.to_owned();
+        if !(SIZE_RANGE.0..=SIZE_RANGE.1).contains(&size_px) {
+            return Err(StampBrushConfigError::SizeOutOfRange);
+        }
+        if !(SPACING_PERCENT_RANGE.0..=SPACING_PERCENT_RANGE.1).contains(&spacing_percent) {
+            return Err(StampBrushConfigError::SpacingOutOfRange);
+        }
+        if !(0.0..=1.0).contains(&opacity) {
+            return Err(StampBrushConfigError::OpacityOutOfRange);
+        }
+        Ok(Self {
+            tip,
+            tip_texture,
+            size_px,
+            spacing_percent,
+            opacity,
+            min_pressure_scale: min_pressure_scale.clamp(0.0, 1.0),
+            rotation: StampRotation::from_index(rotation),
+            angle_deg: angle_deg.clamp(-180.0, 180.0),
+            random_rotation_deg: random_rotation_deg.clamp(0.0, 180.0),
+            random_flip: random_flip.clamp(0.0, 1.0),
+        })
+    }
+
+    fn dab_scale(&self, brush_input: &BrushInput) -> f32 {
+        let pressure = brush_input.cursor.pressure.clamp(0.0, 1.0);
+        self.min_pressure_scale + (1.0 - self.min_pressure_scale) * pressure
+    }
+
+    /// Half width and height in pixels; the tip's longer edge spans the size.
+    fn half_extent(&self, scale: f32) -> (f32, f32) {
+        let half = self.size_px * scale * 0.5;
+        let aspect = self.tip.aspect();
+        if aspect >= 1.0 {
+            (half, half / aspect)
+        } else {
+            (half * aspect, half)
+        }
+    }
+
+    fn dab_angle_and_flip(&self, brush_input: &BrushInput) -> (f32, f32) {
+        let base = match self.rotation {
+            StampRotation::Fixed => 0.0,
+            StampRotation::StrokeDirection => brush_input.tangent.y.atan2(brush_input.tangent.x),
+            StampRotation::PenTwist => brush_input.cursor.twist,
+        };
+        // One dab is encoded once per touched tile, so the jitter is derived
+        // from the dab itself rather than a running generator.
+        let mut seed = brush_input.stroke.0 ^ u64::from(brush_input.path_s.to_bits());
+        let jitter = (unit_random(&mut seed) * 2.0 - 1.0) * self.random_rotation_deg;
+        let flip = if unit_random(&mut seed) < self.random_flip {
+            -1.0
+        } else {
+            1.0
+        };
+        (base + (self.angle_deg + jitter).to_radians(), flip)
+    }
+}
+
+fn scalar_item(
+    key: &'static str,
+    label: &'static str,
+    default_hidden: bool,
+    (min, max): (f32, f32),
+    value: f32,
+) -> BrushConfigItem {
+    BrushConfigItem {
+        key,
+        label,
+        default_hidden,
+        kind: BrushConfigKind::ScalarF32 { min, max },
+        default_value: BrushConfigValue::ScalarF32(value),
+    }
+}
+
+fn angle_item(
+    key: &'static str,
+    label: &'static str,
+    (min, max): (f32, f32),
+    value: f32,
+) -> BrushConfigItem {
+    BrushConfigItem {
+        key,
+        label,
+        default_hidden: true,
+        kind: BrushConfigKind::AngleDeg { min, max },
+        default_value: BrushConfigValue::AngleDeg(value),
+    }
+}
+
+impl BrushResamplerDistancePolicy for StampBrush {
+    fn brush_size(&self) -> u32 {
+        self.size_px.ceil() as u32
+    }
+
+    fn max_distance_rate(&self) -> f32 {
+        self.spacing_percent / 100.0
+    }
+
+    fn min_distance_rate(&self) -> f32 {
+        self.spacing_percent / 100.0
+    }
+}
+
+impl EngineBrushPipeline for StampBrush {
+    fn encode_draw_input(
+        &mut self,
+        brush_input: &BrushInput,
+        _tile_key: TileKey,
+        tile_canvas_origin: CanvasVec2,
+    ) -> Result<Vec<f32>, BrushPipelineError> {
+        let local_x = brush_input.cursor.cursor.x - tile_canvas_origin.x;
+        let local_y = brush_input.cursor.cursor.y - tile_canvas_origin.y;
+        let (half_width, half_height) = self.half_extent(self.dab_scale(brush_input));
+        let (angle, flip) = self.dab_angle_and_flip(brush_input);
+        let (tip_width, tip_height) = self.tip.sheet_base_size();
+        Ok(vec![
+            local_x,
+            local_y,
+            half_width,
+            half_height,
+            angle.cos(),
+            angle.sin(),
+            flip,
+            self.opacity,
+            tip_width as f32,
+            tip_height as f32,
+        ])
+    }
+}
+
+impl BrushSpec for StampBrush {
+    fn max_affected_radius_px(&self) -> u32 {
+        // Any rotation stays inside the circle through the tip's corners.
+        (self.size_px * 0.5 * SQRT_2).ceil() as u32
+    }
+
+    fn draw_input_layout(&self) -> BrushDrawInputLayout {
+        STAMP_DRAW_LAYOUT
+    }
+
+    fn gpu_pipeline_spec(&self) -> BrushGpuPipelineSpec {
+        BrushGpuPipelineSpec {
+            label: "stamp-brush",
+            wgsl_source: Cow::Borrowed(include_str!("stamp.wgsl")),
+            vertex_entry: "vs_main",
+            fragment_entry: "fs_main",
+            uses_brush_cache_backend: true,
+            cache_backend_format: Some(TextureFormat::R8Unorm),
+            tip_image: Some(self.tip.clone()),
+        }
+    }
+
+    fn cache_backend_kind(&self) -> Option<BackendKind> {
+        Some(BackendKind::Leaf)
+    }
+}
+
+impl DabDynamicsTarget for StampBrush {
+    fn base_config_items(&self) -> Vec<BrushConfigItem> {
+        self.config_items()
+    }
+
+    fn dab_size_px(&self) -> f32 {
+        self.size_px
+    }
+
+    fn apply_dab_variation(&self, input: &mut [f32], variation: &DabVariation) {
+        let [_, _, half_width, half_height, cos, sin, _, opacity, _, _] = input else {
+            return;
+        };
+        *half_width *= variation.size_scale;
+        *half_height *= variation.size_scale;
+        let (jitter_sin, jitter_cos) = variation.angle_rad.sin_cos();
+        (*cos, *sin) = (
+            *cos * jitter_cos - *sin * jitter_sin,
+            *sin * jitter_cos + *cos * jitter_sin,
+        );
+        *opacity *= variation.opacity_scale * variation.flow_scale;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use glaphica_core::{
+        BrushInput, BrushInputFlags, CanvasVec2, MappedCursor, RadianVec2, StrokeId, TextureFormat,
+        TileKey,
+    };
+
+    use crate::brush_spec::BrushSpec;
+    use crate::config::{BrushConfigValue, BrushConfigValueError, BrushConfigValues};
+    use crate::engine_runtime::EngineBrushPipeline;
+    use crate::file_brush::validate_wgsl;
+    use crate::resampler_distance::BrushResamplerDistancePolicy;
+    use crate::tip_image::BrushTipImage;
+
+    use super::{STAMP_DRAW_LAYOUT, StampBrush, StampBrushConfigError};
+
+    fn build_input(center: CanvasVec2, pressure: f32, tangent: CanvasVec2) -> BrushInput {
+        BrushInput {
+            stroke: StrokeId(3),
+            cursor: MappedCursor {
+                cursor: center,
+                tilt: RadianVec2::new(0.0, 0.0),
+                pressure,
+                twist: 0.0,
+            },
+            flags: BrushInputFlags::empty(),
+            path_s: 12.5,
+            delta_s: 0.0,
+            dt_s: 0.0,
+            vel: CanvasVec2::new(0.0, 0.0),
+            speed: 0.0,
+            tangent,
+            acc: CanvasVec2::new(0.0, 0.0),
+            accel: 0.0,
+            curvature: 0.0,
+            confidence: 1.0,
+        }
+    }
+
+    fn wide_tip() -> Arc<BrushTipImage> {
+        match BrushTipImage::new(4, 2, vec![255; 8]) {
+            Ok(tip) => Arc::new(tip),
+            Err(_) => Arc::new(BrushTipImage::soft_disc(4)),
+        }
+    }
+
+    fn config_values(
+        rotation_source: usize,
+        random_rotation: f32,
+        random_flip: f32,
+    ) -> BrushConfigValues {
+        BrushConfigValues::defaults(&StampBrush::new(wide_tip(), 40.0, 25.0).config_items())
+            .with("opacity", BrushConfigValue::ScalarF32(0.8))
+            .with("min_pressure_scale", BrushConfigValue::ScalarF32(0.5))
+            .with("rotation_source", BrushConfigValue::Enum(rotation_source))
+            .with(
+                "random_rotation_deg",
+                BrushConfigValue::AngleDeg(random_rotation),
+            )
+            .with("random_flip", BrushConfigValue::ScalarF32(random_flip))
+    }
+
+    #[test]
+    fn encode_scales_with_pressure_and_follows_stroke_direction() {
+        let brush = StampBrush::from_config_values(wide_tip(), &config_values(1, 0.0, 0.0));
+        assert!(brush.is_ok());
+        let mut brush = match brush {
+            Ok(brush) => brush,
+            Err(_) => return,
+        };
+        let input = build_input(CanvasVec2::new(100.0, 70.0), 1.0, CanvasVec2::new(0.0, 1.0));
+        let encoded = brush.encode_draw_input(
+            &input,
+            TileKey::from_parts(0, 0, 0),
+            CanvasVec2::new(64.0, 64.0),
+        );
+        assert!(encoded.is_ok());
+        let encoded = match encoded {
+            Ok(encoded) => encoded,
+            Err(_) => return,
+        };
+        assert!(STAMP_DRAW_LAYOUT.validate_input(&encoded));
+        assert_eq!(&encoded[..4], &[36.0, 6.0, 20.0, 10.0]);
+        assert!(encoded[4].abs() < 1e-6 && (encoded[5] - 1.0).abs() < 1e-6);
+        assert_eq!(&encoded[6..], &[1.0, 0.8, 4.0, 2.0]);
+
+        let light = build_input(CanvasVec2::new(100.0, 70.0), 0.0, CanvasVec2::new(1.0, 0.0));
+        let encoded = brush.encode_draw_input(
+            &light,
+            TileKey::from_parts(0, 0, 0),
+            CanvasVec2::new(0.0, 0.0),
+        );
+        assert_eq!(encoded.ok().map(|encoded| encoded[2]), Some(10.0));
+    }
+
+    #[test]
+    fn random_rotation_and_flip_match_across_tiles_of_one_dab() {
+        let brush = StampBrush::from_config_values(wide_tip(), &config_values(0, 180.0, 0.5));
+        assert!(brush.is_ok());
+        let mut brush = match brush {
+            Ok(brush) => brush,
+            Err(_) => return,
+        };
+        let input = build_input(CanvasVec2::new(64.0, 64.0), 1.0, CanvasVec2::new(1.0, 0.0));
+        let left = brush.encode_draw_input(
+            &input,
+            TileKey::from_parts(0, 0, 0),
+            CanvasVec2::new(0.0, 0.0),
+        );
+        let right = brush.encode_draw_input(
+            &input,
+            TileKey::from_parts(0, 0, 1),
+            CanvasVec2::new(62.0, 0.0),
+        );
+        let (left, right) = match (left, right) {
+            (Ok(left), Ok(right)) => (left, right),
+            _ => return,
+        };
+        assert_eq!(&left[4..], &right[4..]);
+    }
+
+    #[test]
+    fn spacing_is_a_fraction_of_tip_size() {
+        let brush = StampBrush::from_config_values(wide_tip(), &config_values(0, 0.0, 0.0));
+        assert!(brush.is_ok());
+        let brush = match brush {
+            Ok(brush) => brush,
+            Err(_) => return,
+        };
+        let distance = brush.resampler_distance();
+        assert_eq!(distance.min_distance, 10.0);
+        assert_eq!(distance.max_distance, 10.0);
+        assert_eq!(brush.max_affected_radius_px(), 29);
+        let spec = brush.gpu_pipeline_spec();
+        assert!(spec.tip_image.is_some() && spec.uses_brush_cache_backend);
+        assert_eq!(spec.cache_backend_format, Some(TextureFormat::R8Unorm));
+    }
+
+    #[test]
+    fn stamp_shader_validates() {
+        let result = validate_wgsl(include_str!("stamp.wgsl"), "vs_main", "fs_main");
+        assert!(result.is_ok(), "{:?}", result.err());
+    }
+
+    #[test]
+    fn from_config_values_rejects_out_of_range_size() {
+        let values = config_values(0, 0.0, 0.0).with("size_px", BrushConfigValue::ScalarF32(0.0));
+        assert_eq!(
+            StampBrush::from_config_values(wide_tip(), &values),
+            Err(StampBrushConfigError::SizeOutOfRange)
+        );
+    }
+
+    #[test]
+    fn from_config_values_rejects_rotation_given_as_scalar() {
+        let values =
+            config_values(0, 0.0, 0.0).with("rotation_source", BrushConfigValue::ScalarF32(1.0));
+        assert_eq!(
+            StampBrush::from_config_values(wide_tip(), &values),
+            Err(StampBrushConfigError::Config(
+                BrushConfigValueError::TypeMismatch {
+                    key: "rotation_source"
+                }
+            ))
+        );
+    }
+}
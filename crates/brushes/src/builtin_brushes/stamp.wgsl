@@ -0,0 +1,146 @@
+struct DrawInput {
+    center_local_x: f32,
+    center_local_y: f32,
+    half_width: f32,
+    half_height: f32,
+    rotation_cos: f32,
+    rotation_sin: f32,
+    flip_x: f32,
+    opacity: f32,
+    tip_width: f32,
+    tip_height: f32,
+}
+
+struct ShaderParams {
+    input_len: u32,
+    tile_origin_x: u32,
+    tile_origin_y: u32,
+    tile_layer: u32,
+    tile_size_x: u32,
+    tile_size_y: u32,
+    src_tile_origin_x: u32,
+    src_tile_origin_y: u32,
+    src_tile_layer: u32,
+    cache_tile_origin_x: u32,
+    cache_tile_origin_y: u32,
+    cache_tile_layer: u32,
+    has_cache_tile: u32,
+    erase: u32,
+    tint_r: f32,
+    tint_g: f32,
+    tint_b: f32,
+    _pad0: f32,
+}
+
+@group(0) @binding(0) var<storage, read> draw_input: DrawInput;
+@group(0) @binding(1) var<uniform> params: ShaderParams;
+// The brush cache backend, holding the tip sheet (see `BrushTipSheet`).
+@group(1) @binding(1) var cache_atlas: texture_2d_array<f32>;
+
+@vertex
+fn vs_main(@builtin(vertex_index) vertex_index: u32) -> @builtin(position) vec4<f32> {
+    let positions = array<vec2<f32>, 3>(
+        vec2<f32>(-1.0, -1.0),
+        vec2<f32>(3.0, -1.0),
+        vec2<f32>(-1.0, 3.0),
+    );
+    let xy = positions[vertex_index];
+    return vec4<f32>(xy, 0.0, 1.0);
+}
+
+// One texel of the tip sheet. The sheet is cut into gutter-inset tiles
+// stored row by row in the cache backend's even-parity slots 0, 1, 2, ...
+fn sheet_texel(texel: vec2<i32>, tiles_x: i32) -> f32 {
+    let slot_size = i32(params.tile_size_x);
+    let content = slot_size - 2;
+    let tile = texel / content;
+    let local = texel - tile * content;
+    let slot = tile.y * tiles_x + tile.x;
+    let tiles_per_edge = i32(textureDimensions(cache_atlas).x) / slot_size;
+    let tiles_per_layer = tiles_per_edge * tiles_per_edge;
+    let in_layer = slot % tiles_per_layer;
+    let slot_xy = vec2<i32>(in_layer % tiles_per_edge, in_layer / tiles_per_edge);
+    let layer = 2 * (slot / tiles_per_layer);
+    return textureLoad(cache_atlas, slot_xy * slot_size + local + vec2<i32>(1), layer, 0).r;
+}
+
+// Bilinear tip coverage at `uv`, read from the mip level closest to one
+// tip texel per pixel.
+fn sample_tip(uv: vec2<f32>, texels_per_px: f32) -> f32 {
+    var size = vec2<i32>(i32(draw_input.tip_width), i32(draw_input.tip_height));
+    let has_levels = any(size > vec2<i32>(1));
+    let sheet_width = select(size.x, size.x + max(size.x / 2, 1), has_levels);
+    let content = i32(params.tile_size_x) - 2;
+    let tiles_x = (sheet_width + content - 1) / content;
+
+    // Level 1 sits right of level 0; every further level below the last.
+    let wanted = i32(floor(log2(max(texels_per_px, 1.0))));
+    var origin = vec2<i32>(0);
+    for (var level = 0; level < wanted && any(size > vec2<i32>(1)); level++) {
+        if (level == 0) {
+            origin = vec2<i32>(size.x, 0);
+        } else {
+            origin.y = origin.y + size.y;
+        }
+        size = max(size / 2, vec2<i32>(1));
+    }
+
+    // Clamp to texel centers so filtering never reads a neighbouring level.
+    let size_f = vec2<f32>(size);
+    let p = clamp(uv * size_f, vec2<f32>(0.5), size_f - vec2<f32>(0.5)) - vec2<f32>(0.5);
+    let near = vec2<i32>(floor(p));
+    let far = min(near + vec2<i32>(1), size - vec2<i32>(1));
+    let f = p - floor(p);
+    let top = mix(
+        sheet_texel(origin + near, tiles_x),
+        sheet_texel(origin + vec2<i32>(far.x, near.y), tiles_x),
+        f.x,
+    );
+    let bottom = mix(
+        sheet_texel(origin + vec2<i32>(near.x, far.y), tiles_x),
+        sheet_texel(origin + far, tiles_x),
+        f.x,
+    );
+    return mix(top, bottom, f.y);
+}
+
+@fragment
+fn fs_main(@builtin(position) pos: vec4<f32>) -> @location(0) vec4<f32> {
+    // Same tile-local mapping as the pixel rect brush: skip the 1px gutter.
+    let tile_local_x = pos.x - f32(params.tile_origin_x) - 1.0;
+    let tile_local_y = pos.y - f32(params.tile_origin_y) - 1.0;
+    let offset = vec2<f32>(
+        tile_local_x - draw_input.center_local_x,
+        tile_local_y - draw_input.center_local_y,
+    );
+
+    // Rotate into tip space (inverse of the dab rotation), then flip.
+    let c = draw_input.rotation_cos;
+    let s = draw_input.rotation_sin;
+    let tip_offset = vec2<f32>(
+        (offset.x * c + offset.y * s) * draw_input.flip_x,
+        -offset.x * s + offset.y * c,
+    );
+    let half_extent = max(vec2<f32>(draw_input.half_width, draw_input.half_height), vec2<f32>(0.5));
+    let uv = tip_offset / (2.0 * half_extent) + vec2<f32>(0.5);
+
+    let inside = all(uv >= vec2<f32>(0.0)) && all(uv <= vec2<f32>(1.0));
+    if (!inside) {
+        discard;
+    }
+    let texels_per_px = draw_input.tip_width / (2.0 * half_extent.x);
+    let coverage = sample_tip(uv, texels_per_px) * clamp(draw_input.opacity, 0.0, 1.0);
+    if (coverage <= 0.0) {
+        discard;
+    }
+
+    if (params.erase != 0u) {
+        // Erase draws replace texels outright, so only the tip's solid core erases.
+        if (coverage < 0.5) {
+            discard;
+        }
+        return vec4<f32>(0.0);
+    }
+    let tint = vec3<f32>(params.tint_r, params.tint_g, params.tint_b);
+    return vec4<f32>(tint, coverage);
+}
@@ -0,0 +1,377 @@
+use std::error::Error;
+use std::f32::consts::FRAC_PI_2;
+use std::fmt::{Display, Formatter};
+
+use glaphica_core::{BrushInput, StrokeId};
+
+use crate::config::{
+    BrushConfigItem, BrushConfigKind, BrushConfigValue, BrushConfigValueError, BrushConfigValues,
+};
+use crate::dynamics::{dab_randoms, unit_random};
+
+/// Keeps color jitter from replaying the dab dynamics sequence of the same stroke.
+const COLOR_SEED_SALT: u64 = 0x6A09_E667_F3BC_C909;
+
+/// What blends each dab from the foreground toward the background color.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColorMixSource {
+    Off,
+    /// Light pressure paints the background color, full pressure the foreground.
+    Pressure,
+    /// An upright pen paints the foreground color, a flat one the background.
+    Tilt,
+}
+
+impl ColorMixSource {
+    const OPTIONS: &[&str] = &["Off", "Pressure", "Tilt"];
+
+    fn from_index(index: usize) -> Self {
+        match index {
+            1 => Self::Pressure,
+            2 => Self::Tilt,
+            _ => Self::Off,
+        }
+    }
+
+    fn index(self) -> usize {
+        match self {
+            Self::Off => 0,
+            Self::Pressure => 1,
+            Self::Tilt => 2,
+        }
+    }
+
+    fn background_weight(self, brush_input: &BrushInput) -> f32 {
+        match self {
+            Self::Off => 0.0,
+            Self::Pressure => 1.0 - brush_input.cursor.pressure.clamp(0.0, 1.0),
+            Self::Tilt => {
+                let tilt = brush_input.cursor.tilt;
+                ((tilt.x * tilt.x + tilt.y * tilt.y).sqrt() / FRAC_PI_2).clamp(0.0, 1.0)
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColorDynamicsConfigError {
+    Config(BrushConfigValueError),
+}
+
+impl Display for ColorDynamicsConfigError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Config(error) => write!(f, "color dynamics {error}"),
+        }
+    }
+}
+
+impl Error for ColorDynamicsConfigError {}
+
+impl From<BrushConfigValueError> for ColorDynamicsConfigError {
+    fn from(error: BrushConfigValueError) -> Self {
+        Self::Config(error)
+    }
+}
+
+/// Per-dab color variation applied by the engine runtime on top of the
+/// stroke's foreground color.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ColorDynamics {
+    /// Hue offset drawn per dab, up to this many degrees either way.
+    pub hue_jitter_deg: f32,
+    pub saturation_jitter: f32,
+    pub value_jitter: f32,
+    pub mix_source: ColorMixSource,
+    /// Hue offset drawn once per stroke, up to this many degrees either way.
+    pub stroke_hue_shift_deg: f32,
+}
+
+impl Default for ColorDynamics {
+    fn default() -> Self {
+        Self {
+            hue_jitter_deg: 0.0,
+            saturation_jitter: 0.0,
+            value_jitter: 0.0,
+            mix_source: ColorMixSource::Off,
+            stroke_hue_shift_deg: 0.0,
+        }
+    }
+}
+
+impl ColorDynamics {
+    pub fn config_items(&self) -> Vec<BrushConfigItem> {
+        vec![
+            angle_item("color_hue_jitter_deg", "Hue Jitter", self.hue_jitter_deg),
+            scalar_item(
+                "color_saturation_jitter",
+                "Saturation Jitter",
+                (0.0, 1.0),
+                self.saturation_jitter,
+            ),
+            scalar_item(
+                "color_value_jitter",
+                "Value Jitter",
+                (0.0, 1.0),
+                self.value_jitter,
+            ),
+            BrushConfigItem {
+                key: "color_mix_source",
+                label: "FG/BG Mix",
+                default_hidden: true,
+                kind: BrushConfigKind::Enum {
+                    options: ColorMixSource::OPTIONS,
+                },
+                default_value: BrushConfigValue::Enum(self.mix_source.index()),
+            },
+            angle_item(
+                "color_stroke_hue_shift_deg",
+                "Stroke Hue Shift",
+                self.stroke_hue_shift_deg,
+            ),
+        ]
+    }
+
+    /// Reads the `color_` keys; the brush's own keys may sit in the same map.
+    pub fn from_config_values(
+        values: &BrushConfigValues,
+    ) -> Result<Self, ColorDynamicsConfigError> {
+        let hue_jitter_deg = values.angle_deg("color_hue_jitter_deg")?;
+        let saturation_jitter = values.scalar_f32("color_saturation_jitter")?;
+        let value_jitter = values.scalar_f32("color_value_jitter")?;
+        let mix_source = values.enum_index("color_mix_source")?;
+        let stroke_hue_shift_deg = values.angle_deg("color_stroke_hue_shift_deg")?;
+        Ok(Self {
+            hue_jitter_deg: hue_jitter_deg.clamp(0.0, 180.0),
+            saturation_jitter: saturation_jitter.clamp(0.0, 1.0),
+            value_jitter: value_jitter.clamp(0.0, 1.0),
+            mix_source: ColorMixSource::from_index(mix_source),
+            stroke_hue_shift_deg: stroke_hue_shift_deg.clamp(0.0, 180.0),
+        })
+    }
+
+    pub fn is_identity(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+fn scalar_item(
+    key: &'static str,
+    label: &'static str,
+    (min, max): (f32, f32),
+    value: f32,
+) -> BrushConfigItem {
+    BrushConfigItem {
+        key,
+        label,
+        default_hidden: true,
+        kind: BrushConfigKind::ScalarF32 { min, max },
+        default_value: BrushConfigValue::ScalarF32(value),
+    }
+}
+
+fn angle_item(key: &'static str, label: &'static str, value: f32) -> BrushConfigItem {
+    BrushConfigItem {
+        key,
+        label,
+        default_hidden: true,
+        kind: BrushConfigKind::AngleDeg {
+            min: 0.0,
+            max: 180.0,
+        },
+        default_value: BrushConfigValue::AngleDeg(value),
+    }
+}
+
+/// Applies [`ColorDynamics`] dab by dab; the random sequence restarts from the
+/// stroke id so replaying a trace repeats the colors.
+#[derive(Debug, Clone)]
+pub struct ColorDynamicsSampler {
+    dynamics: ColorDynamics,
+    stroke: Option<StrokeId>,
+    rng_state: u64,
+    stroke_hue_offset_deg: f32,
+}
+
+impl ColorDynamicsSampler {
+    pub fn new(dynamics: ColorDynamics) -> Self {
+        Self {
+            dynamics,
+            stroke: None,
+            rng_state: 0,
+            stroke_hue_offset_deg: 0.0,
+        }
+    }
+
+    pub fn dynamics(&self) -> &ColorDynamics {
+        &self.dynamics
+    }
+
+    pub fn dab_rgb(
+        &mut self,
+        foreground: [f32; 3],
+        background: [f32; 3],
+        brush_input: &BrushInput,
+    ) -> [f32; 3] {
+        if self.dynamics.is_identity() {
+            return foreground;
+        }
+        if self.stroke != Some(brush_input.stroke) {
+            self.stroke = Some(brush_input.stroke);
+            self.rng_state = brush_input.stroke.0 ^ COLOR_SEED_SALT;
+            self.stroke_hue_offset_deg =
+                (unit_random(&mut self.rng_state) * 2.0 - 1.0) * self.dynamics.stroke_hue_shift_deg;
+        }
+
+        let weight = self.dynamics.mix_source.background_weight(brush_input);
+        let mixed = [0, 1, 2].map(|channel| {
+            foreground[channel] + (background[channel] - foreground[channel]) * weight
+        });
+
+        let [hue, saturation, value] =
+            dab_randoms(&mut self.rng_state).map(|random| random * 2.0 - 1.0);
+        let [h, s, v] = rgb_to_hsv(mixed);
+        hsv_to_rgb([
+            h + (self.stroke_hue_offset_deg + hue * self.dynamics.hue_jitter_deg) / 360.0,
+            (s + saturation * self.dynamics.saturation_jitter).clamp(0.0, 1.0),
+            (v + value * self.dynamics.value_jitter).clamp(0.0, 1.0),
+        ])
+    }
+}
+
+/// Hue is returned in turns, `[0, 1)`.
+fn rgb_to_hsv([r, g, b]: [f32; 3]) -> [f32; 3] {
+    let max = r.max(g).max(b);
+    let min = r.min(g).min(b);
+    let chroma = max - min;
+    let hue = if chroma <= f32::EPSILON {
+        0.0
+    } else if max == r {
+        ((g - b) / chroma).rem_euclid(6.0) / 6.0
+    } else if max == g {
+        ((b - r) / chroma + 2.0) / 6.0
+    } else {
+        ((r - g) / chroma + 4.0) / 6.0
+    };
+    let saturation = if max <= f32::EPSILON {
+        0.0
+    } else {
+        chroma / max
+    };
+    [hue, saturation, max]
+}
+
+/// Hue is taken in turns and wraps.
+fn hsv_to_rgb([h, s, v]: [f32; 3]) -> [f32; 3] {
+    let sector = h.rem_euclid(1.0) * 6.0;
+    let chroma = v * s;
+    let x = chroma * (1.0 - ((sector % 2.0) - 1.0).abs());
+    let m = v - chroma;
+    let (r, g, b) = match sector as u32 {
+        0 => (chroma, x, 0.0),
+        1 => (x, chroma, 0.0),
+        2 => (0.0, chroma, x),
+        3 => (0.0, x, chroma),
+        4 => (x, 0.0, chroma),
+        _ => (chroma, 0.0, x),
+    };
+    [r + m, g + m, b + m]
+}
+
+#[cfg(test)]
+mod tests {
+    use glaphica_core::{
+        BrushInput, BrushInputFlags, CanvasVec2, MappedCursor, RadianVec2, StrokeId,
+    };
+
+    use super::{ColorDynamics, ColorDynamicsSampler, ColorMixSource, hsv_to_rgb, rgb_to_hsv};
+
+    fn build_input(stroke: u64, pressure: f32) -> BrushInput {
+        BrushInput {
+            stroke: StrokeId(stroke),
+            cursor: MappedCursor {
+                cursor: CanvasVec2::new(10.0, 10.0),
+                tilt: RadianVec2::new(0.0, 0.0),
+                pressure,
+                twist: 0.0,
+            },
+            flags: BrushInputFlags::empty(),
+            path_s: 0.0,
+            delta_s: 1.0,
+            dt_s: 0.0,
+            vel: CanvasVec2::new(0.0, 0.0),
+            speed: 0.0,
+            tangent: CanvasVec2::new(1.0, 0.0),
+            acc: CanvasVec2::new(0.0, 0.0),
+            accel: 0.0,
+            curvature: 0.0,
+            confidence: 1.0,
+        }
+    }
+
+    fn assert_rgb_near(actual: [f32; 3], expected: [f32; 3]) {
+        for (actual, expected) in actual.into_iter().zip(expected) {
+            assert!((actual - expected).abs() < 1e-4, "{actual} != {expected}");
+        }
+    }
+
+    #[test]
+    fn hsv_round_trips_primary_and_mixed_colors() {
+        for rgb in [
+            [1.0, 0.0, 0.0],
+            [0.2, 0.6, 0.4],
+            [0.9, 0.9, 0.1],
+            [0.3, 0.3, 0.3],
+        ] {
+            assert_rgb_near(hsv_to_rgb(rgb_to_hsv(rgb)), rgb);
+        }
+    }
+
+    #[test]
+    fn pressure_mix_blends_toward_background_on_light_pressure() {
+        let mut sampler = ColorDynamicsSampler::new(ColorDynamics {
+            mix_source: ColorMixSource::Pressure,
+            ..ColorDynamics::default()
+        });
+        let foreground = [1.0, 0.0, 0.0];
+        let background = [0.0, 0.0, 1.0];
+        assert_rgb_near(
+            sampler.dab_rgb(foreground, background, &build_input(1, 1.0)),
+            foreground,
+        );
+        assert_rgb_near(
+            sampler.dab_rgb(foreground, background, &build_input(1, 0.0)),
+            background,
+        );
+        assert_rgb_near(
+            sampler.dab_rgb(foreground, background, &build_input(1, 0.5)),
+            [0.5, 0.0, 0.5],
+        );
+    }
+
+    #[test]
+    fn jitter_repeats_per_stroke_and_differs_between_strokes() {
+        let dynamics = ColorDynamics {
+            hue_jitter_deg: 30.0,
+            stroke_hue_shift_deg: 90.0,
+            ..ColorDynamics::default()
+        };
+        let foreground = [0.8, 0.2, 0.2];
+        let sample_stroke = |stroke| {
+            let mut sampler = ColorDynamicsSampler::new(dynamics);
+            (0..4)
+                .map(|_| sampler.dab_rgb(foreground, foreground, &build_input(stroke, 1.0)))
+                .collect::<Vec<_>>()
+        };
+        let first = sample_stroke(3);
+        assert_eq!(first, sample_stroke(3));
+        assert_ne!(first, sample_stroke(4));
+        assert!(first.iter().any(|rgb| *rgb != foreground));
+        for rgb in first {
+            // Hue only moves, so saturation and value stay put.
+            let [_, s, v] = rgb_to_hsv(rgb);
+            assert!((s - 0.75).abs() < 1e-4);
+            assert!((v - 0.8).abs() < 1e-4);
+        }
+    }
+}
@@ -1,3 +1,6 @@
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub struct UnitIntervalPoint {
     pub x: f32,
@@ -12,14 +15,53 @@ impl UnitIntervalPoint {
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum BrushConfigKind {
-    ScalarF32 { min: f32, max: f32 },
+    ScalarF32 {
+        min: f32,
+        max: f32,
+    },
     UnitIntervalCurve,
+    Bool,
+    Integer {
+        min: i32,
+        max: i32,
+    },
+    /// One of a fixed list of choices; the value is the option index.
+    Enum {
+        options: &'static [&'static str],
+    },
+    AngleDeg {
+        min: f32,
+        max: f32,
+    },
+    ColorRgb,
+    /// Name of a texture the app resolves, such as a tip image path.
+    /// An empty name means the brush's built-in texture.
+    TextureRef,
 }
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum BrushConfigValue {
     ScalarF32(f32),
     UnitIntervalCurve(Vec<UnitIntervalPoint>),
+    Bool(bool),
+    Integer(i32),
+    Enum(usize),
+    AngleDeg(f32),
+    ColorRgb([f32; 3]),
+    TextureRef(String),
+}
+
+impl BrushConfigValue {
+    /// Numeric view of single-number values, for shaders and lane sources.
+    pub fn as_f32(&self) -> Option<f32> {
+        match self {
+            Self::ScalarF32(value) | Self::AngleDeg(value) => Some(*value),
+            Self::Bool(value) => Some(if *value { 1.0 } else { 0.0 }),
+            Self::Integer(value) => Some(*value as f32),
+            Self::Enum(index) => Some(*index as f32),
+            Self::UnitIntervalCurve(_) | Self::ColorRgb(_) | Self::TextureRef(_) => None,
+        }
+    }
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -31,25 +73,318 @@ pub struct BrushConfigItem {
     pub default_value: BrushConfigValue,
 }
 
-pub fn eval_unit_interval_curve_polynomial(points: &[UnitIntervalPoint], x: f32) -> Option<f32> {
+impl BrushConfigKind {
+    /// Whether `value` is the variant this kind stores. Ranges are not
+    /// checked; brushes clamp or reject out of range values themselves.
+    pub fn matches(&self, value: &BrushConfigValue) -> bool {
+        matches!(
+            (self, value),
+            (Self::ScalarF32 { .. }, BrushConfigValue::ScalarF32(_))
+                | (
+                    Self::UnitIntervalCurve,
+                    BrushConfigValue::UnitIntervalCurve(_)
+                )
+                | (Self::Bool, BrushConfigValue::Bool(_))
+                | (Self::Integer { .. }, BrushConfigValue::Integer(_))
+                | (Self::Enum { .. }, BrushConfigValue::Enum(_))
+                | (Self::AngleDeg { .. }, BrushConfigValue::AngleDeg(_))
+                | (Self::ColorRgb, BrushConfigValue::ColorRgb(_))
+                | (Self::TextureRef, BrushConfigValue::TextureRef(_))
+        )
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BrushConfigValueError {
+    Missing { key: &'static str },
+    TypeMismatch { key: &'static str },
+}
+
+impl Display for BrushConfigValueError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Missing { key } => write!(f, "config value {key} is missing"),
+            Self::TypeMismatch { key } => write!(f, "config value {key} has the wrong type"),
+        }
+    }
+}
+
+impl Error for BrushConfigValueError {}
+
+/// Config values keyed by [`BrushConfigItem::key`].
+///
+/// Brushes read their values by key, so items can be added, removed or
+/// reordered without breaking callers that build values from another list.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct BrushConfigValues {
+    entries: Vec<(&'static str, BrushConfigValue)>,
+}
+
+impl BrushConfigValues {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Pairs `values` with `items` in order.
+    pub fn from_items(items: &[BrushConfigItem], values: &[BrushConfigValue]) -> Self {
+        Self {
+            entries: items
+                .iter()
+                .zip(values)
+                .map(|(item, value)| (item.key, value.clone()))
+                .collect(),
+        }
+    }
+
+    pub fn defaults(items: &[BrushConfigItem]) -> Self {
+        Self {
+            entries: items
+                .iter()
+                .map(|item| (item.key, item.default_value.clone()))
+                .collect(),
+        }
+    }
+
+    /// Sets `key`, replacing any earlier value.
+    pub fn set(&mut self, key: &'static str, value: BrushConfigValue) {
+        match self
+            .entries
+            .iter_mut()
+            .find(|(existing, _)| *existing == key)
+        {
+            Some((_, slot)) => *slot = value,
+            None => self.entries.push((key, value)),
+        }
+    }
+
+    pub fn with(mut self, key: &'static str, value: BrushConfigValue) -> Self {
+        self.set(key, value);
+        self
+    }
+
+    pub fn get(&self, key: &str) -> Option<&BrushConfigValue> {
+        self.entries
+            .iter()
+            .find(|(existing, _)| *existing == key)
+            .map(|(_, value)| value)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&'static str, &BrushConfigValue)> {
+        self.entries.iter().map(|(key, value)| (*key, value))
+    }
+
+    pub fn value(&self, key: &'static str) -> Result<&BrushConfigValue, BrushConfigValueError> {
+        self.get(key).ok_or(BrushConfigValueError::Missing { key })
+    }
+
+    pub fn scalar_f32(&self, key: &'static str) -> Result<f32, BrushConfigValueError> {
+        match self.value(key)? {
+            BrushConfigValue::ScalarF32(value) => Ok(*value),
+            _ => Err(BrushConfigValueError::TypeMismatch { key }),
+        }
+    }
+
+    pub fn curve(&self, key: &'static str) -> Result<&[UnitIntervalPoint], BrushConfigValueError> {
+        match self.value(key)? {
+            BrushConfigValue::UnitIntervalCurve(points) => Ok(points),
+            _ => Err(BrushConfigValueError::TypeMismatch { key }),
+        }
+    }
+
+    pub fn bool(&self, key: &'static str) -> Result<bool, BrushConfigValueError> {
+        match self.value(key)? {
+            BrushConfigValue::Bool(value) => Ok(*value),
+            _ => Err(BrushConfigValueError::TypeMismatch { key }),
+        }
+    }
+
+    pub fn integer(&self, key: &'static str) -> Result<i32, BrushConfigValueError> {
+        match self.value(key)? {
+            BrushConfigValue::Integer(value) => Ok(*value),
+            _ => Err(BrushConfigValueError::TypeMismatch { key }),
+        }
+    }
+
+    pub fn enum_index(&self, key: &'static str) -> Result<usize, BrushConfigValueError> {
+        match self.value(key)? {
+            BrushConfigValue::Enum(index) => Ok(*index),
+            _ => Err(BrushConfigValueError::TypeMismatch { key }),
+        }
+    }
+
+    pub fn angle_deg(&self, key: &'static str) -> Result<f32, BrushConfigValueError> {
+        match self.value(key)? {
+            BrushConfigValue::AngleDeg(value) => Ok(*value),
+            _ => Err(BrushConfigValueError::TypeMismatch { key }),
+        }
+    }
+
+    pub fn color_rgb(&self, key: &'static str) -> Result<[f32; 3], BrushConfigValueError> {
+        match self.value(key)? {
+            BrushConfigValue::ColorRgb(rgb) => Ok(*rgb),
+            _ => Err(BrushConfigValueError::TypeMismatch { key }),
+        }
+    }
+
+    pub fn texture_ref(&self, key: &'static str) -> Result<&str, BrushConfigValueError> {
+        match self.value(key)? {
+            BrushConfigValue::TextureRef(name) => Ok(name),
+            _ => Err(BrushConfigValueError::TypeMismatch { key }),
+        }
+    }
+}
+
+/// Evaluates a unit interval curve as a monotone piecewise cubic.
+///
+/// Tangents follow the Fritsch–Carlson conditions with PCHIP's weighted
+/// harmonic mean, so the curve passes through every point, never overshoots
+/// between them and stays flat wherever neighbouring points are level. Each
+/// segment only looks at its neighbours, so no per-curve state is needed.
+/// Points must have strictly increasing `x`; outside them the curve holds
+/// the end values.
+pub fn eval_unit_interval_curve(points: &[UnitIntervalPoint], x: f32) -> Option<f32> {
     if points.len() < 2 {
         return None;
     }
+    if points
+        .windows(2)
+        .any(|pair| pair[1].x - pair[0].x <= f32::EPSILON)
+    {
+        return None;
+    }
+    let last = points.len() - 1;
     let x = x.clamp(0.0, 1.0);
-    let mut y = 0.0f32;
-    for (i, point_i) in points.iter().enumerate() {
-        let mut basis = 1.0f32;
-        for (j, point_j) in points.iter().enumerate() {
-            if i == j {
-                continue;
-            }
-            let denominator = point_i.x - point_j.x;
-            if denominator.abs() <= f32::EPSILON {
-                return None;
-            }
-            basis *= (x - point_j.x) / denominator;
-        }
-        y += point_i.y * basis;
+    if x <= points[0].x {
+        return Some(points[0].y.clamp(0.0, 1.0));
+    }
+    if x >= points[last].x {
+        return Some(points[last].y.clamp(0.0, 1.0));
     }
+
+    let segment = points
+        .windows(2)
+        .position(|pair| x < pair[1].x)
+        .unwrap_or(last - 1);
+    let p0 = points[segment];
+    let p1 = points[segment + 1];
+    let h = p1.x - p0.x;
+    let m0 = curve_tangent(points, segment);
+    let m1 = curve_tangent(points, segment + 1);
+
+    let t = (x - p0.x) / h;
+    let t2 = t * t;
+    let t3 = t2 * t;
+    let y = (2.0 * t3 - 3.0 * t2 + 1.0) * p0.y
+        + (t3 - 2.0 * t2 + t) * h * m0
+        + (-2.0 * t3 + 3.0 * t2) * p1.y
+        + (t3 - t2) * h * m1;
     Some(y.clamp(0.0, 1.0))
 }
+
+fn curve_secant(points: &[UnitIntervalPoint], segment: usize) -> (f32, f32) {
+    let h = points[segment + 1].x - points[segment].x;
+    (h, (points[segment + 1].y - points[segment].y) / h)
+}
+
+fn curve_tangent(points: &[UnitIntervalPoint], index: usize) -> f32 {
+    let last = points.len() - 1;
+    if last == 1 {
+        return curve_secant(points, 0).1;
+    }
+    if index == 0 {
+        return curve_end_tangent(curve_secant(points, 0), curve_secant(points, 1));
+    }
+    if index == last {
+        return curve_end_tangent(
+            curve_secant(points, last - 1),
+            curve_secant(points, last - 2),
+        );
+    }
+    let (h0, d0) = curve_secant(points, index - 1);
+    let (h1, d1) = curve_secant(points, index);
+    if d0 * d1 <= 0.0 {
+        // Local extremum or flat run: a level tangent keeps the curve from
+        // swinging past either point.
+        return 0.0;
+    }
+    let w0 = 2.0 * h1 + h0;
+    let w1 = h1 + 2.0 * h0;
+    (w0 + w1) / (w0 / d0 + w1 / d1)
+}
+
+/// One-sided three-point tangent, limited so the end segment stays monotone.
+fn curve_end_tangent((h0, d0): (f32, f32), (h1, d1): (f32, f32)) -> f32 {
+    let tangent = ((2.0 * h0 + h1) * d0 - h0 * d1) / (h0 + h1);
+    if tangent * d0 <= 0.0 {
+        0.0
+    } else if d0 * d1 <= 0.0 && tangent.abs() > 3.0 * d0.abs() {
+        3.0 * d0
+    } else {
+        tangent
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{UnitIntervalPoint, eval_unit_interval_curve};
+
+    fn sample(points: &[UnitIntervalPoint], x: f32) -> f32 {
+        let y = eval_unit_interval_curve(points, x);
+        assert!(y.is_some());
+        y.unwrap_or(f32::NAN)
+    }
+
+    #[test]
+    fn two_point_curve_is_a_line() {
+        let points = [
+            UnitIntervalPoint::new(0.0, 0.2),
+            UnitIntervalPoint::new(1.0, 0.6),
+        ];
+        for x in [0.0, 0.25, 0.5, 1.0] {
+            assert!((sample(&points, x) - (0.2 + 0.4 * x)).abs() < 1e-6);
+        }
+    }
+
+    #[test]
+    fn many_point_curve_passes_through_points_without_overshoot() {
+        // A Lagrange polynomial through these swings far outside [0, 1].
+        let points = [
+            UnitIntervalPoint::new(0.0, 0.0),
+            UnitIntervalPoint::new(0.2, 0.0),
+            UnitIntervalPoint::new(0.4, 0.1),
+            UnitIntervalPoint::new(0.5, 0.9),
+            UnitIntervalPoint::new(0.7, 1.0),
+            UnitIntervalPoint::new(1.0, 1.0),
+        ];
+        for point in points {
+            assert!((sample(&points, point.x) - point.y).abs() < 1e-6);
+        }
+        let mut previous = 0.0;
+        for step in 0..=200 {
+            let y = sample(&points, step as f32 / 200.0);
+            assert!(y >= previous - 1e-6, "curve dips at step {step}");
+            previous = y;
+        }
+        // Level runs stay level instead of rippling.
+        assert_eq!(sample(&points, 0.1), 0.0);
+        assert_eq!(sample(&points, 0.85), 1.0);
+    }
+
+    #[test]
+    fn curve_rejects_repeated_x_and_holds_end_values() {
+        let repeated = [
+            UnitIntervalPoint::new(0.0, 0.0),
+            UnitIntervalPoint::new(0.5, 0.5),
+            UnitIntervalPoint::new(0.5, 0.7),
+            UnitIntervalPoint::new(1.0, 1.0),
+        ];
+        assert_eq!(eval_unit_interval_curve(&repeated, 0.5), None);
+
+        let inset = [
+            UnitIntervalPoint::new(0.2, 0.3),
+            UnitIntervalPoint::new(0.8, 0.9),
+        ];
+        assert_eq!(eval_unit_interval_curve(&inset, 0.0), Some(0.3));
+        assert_eq!(eval_unit_interval_curve(&inset, 1.0), Some(0.9));
+    }
+}
@@ -2,6 +2,11 @@
 pub enum BrushDrawKind {
     PixelRect,
     Round,
+    Stamp,
+    Smudge,
+    Spray,
+    /// Layout declared by a runtime-loaded brush manifest.
+    Custom,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -0,0 +1,684 @@
+use std::collections::VecDeque;
+use std::error::Error;
+use std::f32::consts::TAU;
+use std::fmt::{Display, Formatter};
+
+use glaphica_core::{BackendKind, BrushInput, CanvasVec2, StrokeId, TileKey};
+use thread_protocol::GpuCmdFrameMergeTag;
+
+use crate::BrushPipelineError;
+use crate::brush_spec::BrushSpec;
+use crate::config::{
+    BrushConfigItem, BrushConfigKind, BrushConfigValue, BrushConfigValueError, BrushConfigValues,
+};
+use crate::draw_layout::BrushDrawInputLayout;
+use crate::engine_runtime::EngineBrushPipeline;
+use crate::gpu_pipeline_spec::BrushGpuPipelineSpec;
+use crate::resampler_distance::BrushResamplerDistancePolicy;
+
+const MAX_DAB_COUNT: i32 = 16;
+const SEED_OPTIONS: &[&str] = &["Per Stroke", "Random"];
+
+/// Where the jitter random sequence starts for each stroke.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DabDynamicsSeed {
+    /// Seeded from the stroke id, so replaying a trace repeats the jitter.
+    PerStroke,
+    /// Seeded from the stroke id and where the stroke starts, so repeated
+    /// strokes differ while replaying a trace still repeats the jitter.
+    Random,
+}
+
+impl DabDynamicsSeed {
+    fn from_index(index: usize) -> Self {
+        if index == 1 {
+            Self::Random
+        } else {
+            Self::PerStroke
+        }
+    }
+
+    fn index(self) -> usize {
+        match self {
+            Self::PerStroke => 0,
+            Self::Random => 1,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DabDynamicsConfigError {
+    Config(BrushConfigValueError),
+    DabCountOutOfRange,
+}
+
+impl Display for DabDynamicsConfigError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Config(error) => write!(f, "dab dynamics {error}"),
+            Self::DabCountOutOfRange => write!(f, "dab count per step must be in [1, 16]"),
+        }
+    }
+}
+
+impl Error for DabDynamicsConfigError {}
+
+impl From<BrushConfigValueError> for DabDynamicsConfigError {
+    fn from(error: BrushConfigValueError) -> Self {
+        Self::Config(error)
+    }
+}
+
+/// Per-dab randomisation shared by every brush that opts in through
+/// [`DabDynamicsTarget`]. Jitter only shrinks size, opacity and flow, so a
+/// brush's `max_affected_radius_px` stays a valid bound.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DabDynamics {
+    pub size_jitter: f32,
+    pub angle_jitter_deg: f32,
+    pub opacity_jitter: f32,
+    pub flow_jitter: f32,
+    /// Scatter distance across the stroke, in dab diameters.
+    pub scatter: f32,
+    pub dab_count: u32,
+    pub seed: DabDynamicsSeed,
+}
+
+impl Default for DabDynamics {
+    fn default() -> Self {
+        Self {
+            size_jitter: 0.0,
+            angle_jitter_deg: 0.0,
+            opacity_jitter: 0.0,
+            flow_jitter: 0.0,
+            scatter: 0.0,
+            dab_count: 1,
+            seed: DabDynamicsSeed::PerStroke,
+        }
+    }
+}
+
+impl DabDynamics {
+    pub fn config_items(&self) -> Vec<BrushConfigItem> {
+        vec![
+            scalar_item(
+                "dyn_size_jitter",
+                "Size Jitter",
+                (0.0, 1.0),
+                self.size_jitter,
+            ),
+            hidden_item(
+                "dyn_angle_jitter_deg",
+                "Angle Jitter",
+                BrushConfigKind::AngleDeg {
+                    min: 0.0,
+                    max: 180.0,
+                },
+                BrushConfigValue::AngleDeg(self.angle_jitter_deg),
+            ),
+            scalar_item(
+                "dyn_opacity_jitter",
+                "Opacity Jitter",
+                (0.0, 1.0),
+                self.opacity_jitter,
+            ),
+            scalar_item(
+                "dyn_flow_jitter",
+                "Flow Jitter",
+                (0.0, 1.0),
+                self.flow_jitter,
+            ),
+            scalar_item("dyn_scatter", "Scatter", (0.0, 4.0), self.scatter),
+            hidden_item(
+                "dyn_dab_count",
+                "Dabs per Step",
+                BrushConfigKind::Integer {
+                    min: 1,
+                    max: MAX_DAB_COUNT,
+                },
+                BrushConfigValue::Integer(self.dab_count as i32),
+            ),
+            hidden_item(
+                "dyn_seed",
+                "Jitter Seed",
+                BrushConfigKind::Enum {
+                    options: SEED_OPTIONS,
+                },
+                BrushConfigValue::Enum(self.seed.index()),
+            ),
+        ]
+    }
+
+    /// Reads the `dyn_` keys; the brush's own keys may sit in the same map.
+    pub fn from_config_values(values: &BrushConfigValues) -> Result<Self, DabDynamicsConfigError> {
+        let size_jitter = values.scalar_f32("dyn_size_jitter")?;
+        let angle_jitter_deg = values.angle_deg("dyn_angle_jitter_deg")?;
+        let opacity_jitter = values.scalar_f32("dyn_opacity_jitter")?;
+        let flow_jitter = values.scalar_f32("dyn_flow_jitter")?;
+        let scatter = values.scalar_f32("dyn_scatter")?;
+        let dab_count = values.integer("dyn_dab_count")?;
+        let seed = values.enum_index("dyn_seed")?;
+        if !(1..=MAX_DAB_COUNT).contains(&dab_count) {
+            return Err(DabDynamicsConfigError::DabCountOutOfRange);
+        }
+        Ok(Self {
+            size_jitter: size_jitter.clamp(0.0, 1.0),
+            angle_jitter_deg: angle_jitter_deg.clamp(0.0, 180.0),
+            opacity_jitter: opacity_jitter.clamp(0.0, 1.0),
+            flow_jitter: flow_jitter.clamp(0.0, 1.0),
+            scatter: scatter.clamp(0.0, 4.0),
+            dab_count: dab_count as u32,
+            seed: DabDynamicsSeed::from_index(seed),
+        })
+    }
+
+    fn sample(&self, rng: &mut u64, normal: CanvasVec2, dab_size_px: f32) -> DabVariation {
+        let [size, angle, opacity, flow, scatter] = dab_randoms(rng);
+        let angle = angle * 2.0 - 1.0;
+        let scatter = (scatter * 2.0 - 1.0) * self.scatter * dab_size_px;
+        DabVariation {
+            size_scale: 1.0 - self.size_jitter * size,
+            angle_rad: (angle * self.angle_jitter_deg).to_radians(),
+            opacity_scale: 1.0 - self.opacity_jitter * opacity,
+            flow_scale: 1.0 - self.flow_jitter * flow,
+            offset: CanvasVec2::new(normal.x * scatter, normal.y * scatter),
+        }
+    }
+}
+
+fn scalar_item(
+    key: &'static str,
+    label: &'static str,
+    (min, max): (f32, f32),
+    value: f32,
+) -> BrushConfigItem {
+    hidden_item(
+        key,
+        label,
+        BrushConfigKind::ScalarF32 { min, max },
+        BrushConfigValue::ScalarF32(value),
+    )
+}
+
+fn hidden_item(
+    key: &'static str,
+    label: &'static str,
+    kind: BrushConfigKind,
+    default_value: BrushConfigValue,
+) -> BrushConfigItem {
+    BrushConfigItem {
+        key,
+        label,
+        default_hidden: true,
+        kind,
+        default_value,
+    }
+}
+
+/// SplitMix64 step mapped to `[0, 1)`.
+pub(crate) fn unit_random(state: &mut u64) -> f32 {
+    *state = state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+    let mut z = *state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^= z >> 31;
+    (z >> 40) as f32 / (1u64 << 24) as f32
+}
+
+/// One [`unit_random`] per varied setting of a dab, in a fixed order.
+///
+/// Every value is drawn even when its setting is off, so turning one setting
+/// on or off never shifts the sequence another setting sees.
+pub(crate) fn dab_randoms<const N: usize>(state: &mut u64) -> [f32; N] {
+    std::array::from_fn(|_| unit_random(state))
+}
+
+/// One dab's deviation from the brush's nominal output.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DabVariation {
+    pub size_scale: f32,
+    pub angle_rad: f32,
+    pub opacity_scale: f32,
+    pub flow_scale: f32,
+    /// Already applied to the dab's cursor; kept for brushes that need it.
+    pub offset: CanvasVec2,
+}
+
+impl DabVariation {
+    pub const IDENTITY: Self = Self {
+        size_scale: 1.0,
+        angle_rad: 0.0,
+        opacity_scale: 1.0,
+        flow_scale: 1.0,
+        offset: CanvasVec2::new(0.0, 0.0),
+    };
+}
+
+/// Brushes that know which draw input lanes hold size, angle and opacity.
+pub trait DabDynamicsTarget: BrushSpec + BrushResamplerDistancePolicy {
+    /// The brush's own items; dynamics items are appended after them.
+    fn base_config_items(&self) -> Vec<BrushConfigItem>;
+
+    /// Nominal dab diameter, the unit scatter is measured in.
+    fn dab_size_px(&self) -> f32;
+
+    /// Applies a variation to an input this brush just encoded.
+    fn apply_dab_variation(&self, input: &mut [f32], variation: &DabVariation);
+}
+
+/// Wraps a brush with [`DabDynamics`]; registers like the brush it wraps.
+#[derive(Debug, Clone)]
+pub struct WithDabDynamics<B> {
+    brush: B,
+    dynamics: DabDynamics,
+    stroke: Option<StrokeId>,
+    rng_state: u64,
+    pending: VecDeque<DabVariation>,
+    current: DabVariation,
+    expanded: Vec<BrushInput>,
+}
+
+impl<B: DabDynamicsTarget> WithDabDynamics<B> {
+    pub fn new(brush: B, dynamics: DabDynamics) -> Self {
+        Self {
+            brush,
+            dynamics,
+            stroke: None,
+            rng_state: 0,
+            pending: VecDeque::new(),
+            current: DabVariation::IDENTITY,
+            expanded: Vec::new(),
+        }
+    }
+
+    pub fn brush(&self) -> &B {
+        &self.brush
+    }
+
+    pub fn dynamics(&self) -> &DabDynamics {
+        &self.dynamics
+    }
+
+    pub fn config_items(&self) -> Vec<BrushConfigItem> {
+        let mut items = self.brush.base_config_items();
+        items.extend(self.dynamics.config_items());
+        items
+    }
+
+    fn reseed(&mut self, first_input: &BrushInput) {
+        let stroke = first_input.stroke;
+        self.stroke = Some(stroke);
+        self.rng_state = match self.dynamics.seed {
+            DabDynamicsSeed::PerStroke => stroke.0,
+            DabDynamicsSeed::Random => {
+                let start = first_input.cursor.cursor;
+                let start_bits =
+                    (u64::from(start.x.to_bits()) << 32) | u64::from(start.y.to_bits());
+                stroke.0 ^ start_bits
+            }
+        };
+    }
+
+    fn vary(&self, mut input: Vec<f32>) -> Vec<f32> {
+        self.brush.apply_dab_variation(&mut input, &self.current);
+        input
+    }
+
+    /// Stamps `dab_count` varied copies of each dab the wrapped brush
+    /// produced into `dabs`, queueing their variations for `begin_dab`.
+    fn push_varied_dabs(&mut self, dabs: &mut Vec<BrushInput>) {
+        let dab_size_px = self.brush.dab_size_px();
+        for dab in &self.expanded {
+            let tangent = dab.tangent;
+            let length = (tangent.x * tangent.x + tangent.y * tangent.y).sqrt();
+            let normal = if length > f32::EPSILON {
+                CanvasVec2::new(-tangent.y / length, tangent.x / length)
+            } else {
+                // No direction yet: scatter along a random axis instead.
+                let angle = unit_random(&mut self.rng_state) * TAU;
+                CanvasVec2::new(angle.cos(), angle.sin())
+            };
+            for _ in 0..self.dynamics.dab_count.max(1) {
+                let variation = self
+                    .dynamics
+                    .sample(&mut self.rng_state, normal, dab_size_px);
+                let mut varied = *dab;
+                varied.cursor.cursor = CanvasVec2::new(
+                    varied.cursor.cursor.x + variation.offset.x,
+                    varied.cursor.cursor.y + variation.offset.y,
+                );
+                dabs.push(varied);
+                self.pending.push_back(variation);
+            }
+        }
+    }
+}
+
+impl<B: DabDynamicsTarget> EngineBrushPipeline for WithDabDynamics<B> {
+    fn expand_dabs(&mut self, brush_input: &BrushInput, dabs: &mut Vec<BrushInput>) {
+        if self.stroke != Some(brush_input.stroke) {
+            self.reseed(brush_input);
+        }
+        self.pending.clear();
+        self.expanded.clear();
+        self.brush.expand_dabs(brush_input, &mut self.expanded);
+        self.push_varied_dabs(dabs);
+    }
+
+    fn finish_dabs(&mut self, dabs: &mut Vec<BrushInput>) {
+        self.pending.clear();
+        self.expanded.clear();
+        self.brush.finish_dabs(&mut self.expanded);
+        self.push_varied_dabs(dabs);
+    }
+
+    fn begin_symmetry_copy(&mut self, copy_index: usize) {
+        self.brush.begin_symmetry_copy(copy_index);
+    }
+
+    fn begin_dab(&mut self, brush_input: &BrushInput) {
+        self.current = self.pending.pop_front().unwrap_or(DabVariation::IDENTITY);
+        self.brush.begin_dab(brush_input);
+    }
+
+    fn encode_draw_input(
+        &mut self,
+        brush_input: &BrushInput,
+        tile_key: TileKey,
+        tile_canvas_origin: CanvasVec2,
+    ) -> Result<Vec<f32>, BrushPipelineError> {
+        let input = self
+            .brush
+            .encode_draw_input(brush_input, tile_key, tile_canvas_origin)?;
+        Ok(self.vary(input))
+    }
+
+    fn uses_stroke_buffer(&self) -> bool {
+        self.brush.uses_stroke_buffer()
+    }
+
+    fn encode_stroke_buffer_dab_input(
+        &mut self,
+        brush_input: &BrushInput,
+        tile_key: TileKey,
+        tile_canvas_origin: CanvasVec2,
+    ) -> Result<Vec<f32>, BrushPipelineError> {
+        let input =
+            self.brush
+                .encode_stroke_buffer_dab_input(brush_input, tile_key, tile_canvas_origin)?;
+        Ok(self.vary(input))
+    }
+
+    fn encode_stroke_buffer_composite_input(
+        &mut self,
+        brush_input: &BrushInput,
+        tile_key: TileKey,
+        tile_canvas_origin: CanvasVec2,
+    ) -> Result<Vec<f32>, BrushPipelineError> {
+        let input = self.brush.encode_stroke_buffer_composite_input(
+            brush_input,
+            tile_key,
+            tile_canvas_origin,
+        )?;
+        Ok(self.vary(input))
+    }
+
+    fn stroke_buffer_write_opacity(
+        &mut self,
+        brush_input: &BrushInput,
+    ) -> Result<f32, BrushPipelineError> {
+        self.brush.stroke_buffer_write_opacity(brush_input)
+    }
+
+    fn stroke_buffer_copy_frame_merge_tag(&self) -> GpuCmdFrameMergeTag {
+        self.brush.stroke_buffer_copy_frame_merge_tag()
+    }
+
+    fn stroke_buffer_write_frame_merge_tag(&self) -> GpuCmdFrameMergeTag {
+        self.brush.stroke_buffer_write_frame_merge_tag()
+    }
+
+    fn restore_origin_before_each_dab(&self) -> bool {
+        self.brush.restore_origin_before_each_dab()
+    }
+
+    fn stroke_buffer_accumulates_color(&self) -> bool {
+        self.brush.stroke_buffer_accumulates_color()
+    }
+
+    fn samples_composite(&self) -> bool {
+        self.brush.samples_composite()
+    }
+}
+
+impl<B: DabDynamicsTarget> BrushSpec for WithDabDynamics<B> {
+    fn max_affected_radius_px(&self) -> u32 {
+        self.brush.max_affected_radius_px()
+    }
+
+    fn draw_input_layout(&self) -> BrushDrawInputLayout {
+        self.brush.draw_input_layout()
+    }
+
+    fn gpu_pipeline_spec(&self) -> BrushGpuPipelineSpec {
+        self.brush.gpu_pipeline_spec()
+    }
+
+    fn cache_backend_kind(&self) -> Option<BackendKind> {
+        self.brush.cache_backend_kind()
+    }
+}
+
+impl<B: DabDynamicsTarget> BrushResamplerDistancePolicy for WithDabDynamics<B> {
+    fn brush_size(&self) -> u32 {
+        self.brush.brush_size()
+    }
+
+    fn max_distance_rate(&self) -> f32 {
+        self.brush.max_distance_rate()
+    }
+
+    fn min_distance_rate(&self) -> f32 {
+        self.brush.min_distance_rate()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use glaphica_core::{
+        BrushInput, BrushInputFlags, CanvasVec2, MappedCursor, RadianVec2, StrokeId, TileKey,
+    };
+
+    use crate::builtin_brushes::pixel_rect::PixelRectBrush;
+    use crate::builtin_brushes::stamp::StampBrush;
+    use crate::config::{BrushConfigValue, BrushConfigValues};
+    use crate::engine_runtime::EngineBrushPipeline;
+    use crate::tip_image::BrushTipImage;
+
+    use super::{DabDynamics, DabDynamicsConfigError, DabDynamicsSeed, WithDabDynamics};
+
+    fn build_input(stroke: u64) -> BrushInput {
+        BrushInput {
+            stroke: StrokeId(stroke),
+            cursor: MappedCursor {
+                cursor: CanvasVec2::new(30.0, 30.0),
+                tilt: RadianVec2::new(0.0, 0.0),
+                pressure: 1.0,
+                twist: 0.0,
+            },
+            flags: BrushInputFlags::empty(),
+            path_s: 0.0,
+            delta_s: 0.0,
+            dt_s: 0.0,
+            vel: CanvasVec2::new(0.0, 0.0),
+            speed: 0.0,
+            tangent: CanvasVec2::new(1.0, 0.0),
+            acc: CanvasVec2::new(0.0, 0.0),
+            accel: 0.0,
+            curvature: 0.0,
+            confidence: 1.0,
+        }
+    }
+
+    fn stamp(dynamics: DabDynamics) -> WithDabDynamics<StampBrush> {
+        WithDabDynamics::new(
+            StampBrush::new(Arc::new(BrushTipImage::soft_disc(8)), 20.0, 25.0),
+            dynamics,
+        )
+    }
+
+    fn encode_all(brush: &mut WithDabDynamics<StampBrush>, input: &BrushInput) -> Vec<Vec<f32>> {
+        let mut dabs = Vec::new();
+        brush.expand_dabs(input, &mut dabs);
+        dabs.iter()
+            .map(|dab| {
+                brush.begin_dab(dab);
+                brush
+                    .encode_draw_input(dab, TileKey::from_parts(0, 0, 0), CanvasVec2::new(0.0, 0.0))
+                    .unwrap_or_default()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn default_dynamics_leave_the_brush_output_untouched() {
+        let mut wrapped = stamp(DabDynamics::default());
+        let mut plain = StampBrush::new(Arc::new(BrushTipImage::soft_disc(8)), 20.0, 25.0);
+        let input = build_input(1);
+        let expected = plain.encode_draw_input(
+            &input,
+            TileKey::from_parts(0, 0, 0),
+            CanvasVec2::new(0.0, 0.0),
+        );
+        assert_eq!(
+            encode_all(&mut wrapped, &input),
+            vec![expected.unwrap_or_default()]
+        );
+    }
+
+    fn at(x: f32, y: f32) -> BrushInput {
+        let mut input = build_input(1);
+        input.cursor.cursor = CanvasVec2::new(x, y);
+        input.tangent = CanvasVec2::new(0.0, 0.0);
+        input
+    }
+
+    fn dab_cursors(dabs: &[BrushInput]) -> Vec<CanvasVec2> {
+        dabs.iter().map(|dab| dab.cursor.cursor).collect()
+    }
+
+    #[test]
+    fn wrapped_brush_still_expands_its_own_dabs() {
+        let staircase = [at(0.5, 0.5), at(1.5, 0.5), at(1.5, 1.5), at(4.5, 1.5)];
+        let mut plain = PixelRectBrush::new(1).with_pixel_perfect(true);
+        let mut wrapped = WithDabDynamics::new(plain.clone(), DabDynamics::default());
+        let (mut expected, mut actual) = (Vec::new(), Vec::new());
+        for input in &staircase {
+            plain.expand_dabs(input, &mut expected);
+            wrapped.expand_dabs(input, &mut actual);
+        }
+
+        // Pixel-perfect holds corners back and fills the gap to x = 4.
+        assert_ne!(dab_cursors(&expected), dab_cursors(&staircase));
+        assert_eq!(dab_cursors(&actual), dab_cursors(&expected));
+    }
+
+    #[test]
+    fn wrapped_brush_still_flushes_held_dabs_at_stroke_end() {
+        let staircase = [
+            at(0.5, 0.5),
+            at(1.5, 0.5),
+            at(1.5, 1.5),
+            at(2.5, 1.5),
+            at(2.5, 2.5),
+        ];
+        let mut wrapped = WithDabDynamics::new(
+            PixelRectBrush::new(1).with_pixel_perfect(true),
+            DabDynamics::default(),
+        );
+        let mut dabs = Vec::new();
+        for input in &staircase {
+            wrapped.expand_dabs(input, &mut dabs);
+        }
+        wrapped.finish_dabs(&mut dabs);
+
+        assert_eq!(
+            dab_cursors(&dabs),
+            vec![
+                CanvasVec2::new(0.5, 0.5),
+                CanvasVec2::new(1.5, 1.5),
+                CanvasVec2::new(2.5, 2.5),
+            ]
+        );
+    }
+
+    #[test]
+    fn per_stroke_seed_repeats_scattered_dabs_across_the_tangent() {
+        let dynamics = DabDynamics {
+            size_jitter: 0.5,
+            scatter: 1.0,
+            dab_count: 3,
+            ..DabDynamics::default()
+        };
+        let input = build_input(9);
+        let first = encode_all(&mut stamp(dynamics), &input);
+        let second = encode_all(&mut stamp(dynamics), &input);
+        assert_eq!(first.len(), 3);
+        assert_eq!(first, second);
+        for dab in &first {
+            // Tangent is +x, so scatter only moves dabs along y, within one diameter.
+            assert_eq!(dab[0], 30.0);
+            assert!((dab[1] - 30.0).abs() <= 20.0);
+            assert!(dab[2] <= 10.0 && dab[2] >= 5.0);
+        }
+        assert_ne!(first[0], first[1]);
+    }
+
+    #[test]
+    fn random_seed_follows_the_stroke_start_so_replays_repeat() {
+        let dynamics = DabDynamics {
+            size_jitter: 0.5,
+            seed: DabDynamicsSeed::Random,
+            ..DabDynamics::default()
+        };
+        // Lane 2 is the jittered size; the cursor lanes move with the input.
+        let size = |dynamics, input: &BrushInput| encode_all(&mut stamp(dynamics), input)[0][2];
+        let input = build_input(9);
+        let mut moved = input;
+        moved.cursor.cursor = CanvasVec2::new(31.0, 30.0);
+        assert_eq!(size(dynamics, &input), size(dynamics, &input));
+        assert_ne!(size(dynamics, &input), size(dynamics, &moved));
+
+        let per_stroke = DabDynamics {
+            seed: DabDynamicsSeed::PerStroke,
+            ..dynamics
+        };
+        assert_eq!(size(per_stroke, &input), size(per_stroke, &moved));
+    }
+
+    #[test]
+    fn from_config_values_reads_dynamics_keys_beside_brush_keys() {
+        let brush = stamp(DabDynamics::default());
+        let values = BrushConfigValues::defaults(&brush.config_items())
+            .with("dyn_size_jitter", BrushConfigValue::ScalarF32(0.2))
+            .with("dyn_dab_count", BrushConfigValue::Integer(2))
+            .with("dyn_seed", BrushConfigValue::Enum(1));
+        let dynamics = DabDynamics::from_config_values(&values);
+        assert!(dynamics.is_ok());
+        let dynamics = match dynamics {
+            Ok(dynamics) => dynamics,
+            Err(_) => return,
+        };
+        assert_eq!(dynamics.size_jitter, 0.2);
+        assert_eq!(dynamics.dab_count, 2);
+        assert_eq!(dynamics.seed, DabDynamicsSeed::Random);
+
+        let values = values.with("dyn_dab_count", BrushConfigValue::Integer(0));
+        assert_eq!(
+            DabDynamics::from_config_values(&values),
+            Err(DabDynamicsConfigError::DabCountOutOfRange)
+        );
+    }
+}
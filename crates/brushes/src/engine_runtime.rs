@@ -2,7 +2,7 @@ use std::collections::HashMap;
 use std::error::Error;
 use std::fmt::{Display, Formatter};
 
-use glaphica_core::{BackendId, BrushId, BrushInput, CanvasVec2, NodeId, TileKey};
+use glaphica_core::{BackendId, BrushId, BrushInput, BrushInputFlags, CanvasVec2, NodeId, TileKey};
 use images::Image;
 use thread_protocol::{
     ClearOp, CompositeOp, CopyOp, DrawBlendMode, DrawFrameMergePolicy, DrawOp, GpuCmdFrameMergeTag,
@@ -10,6 +10,7 @@ use thread_protocol::{
 };
 
 use crate::brush_registry::BrushRegistry;
+use crate::color_dynamics::{ColorDynamics, ColorDynamicsSampler};
 use crate::{BrushPipelineError, BrushRegistryError};
 
 pub trait TileSlotAllocator {
@@ -26,6 +27,14 @@ pub trait TileSlotAllocator {
     fn replace(&mut self, _old: TileKey, _new: TileKey) {}
 
     fn release(&mut self, _tile: TileKey) {}
+
+    /// Frees a tile allocated during the stroke being cancelled; its contents
+    /// are never needed again.
+    fn discard(&mut self, _tile: TileKey) {}
+
+    /// Ends the stroke without retiring the tiles it replaced, which become
+    /// current again.
+    fn cancel_stroke(&mut self) {}
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
@@ -99,6 +108,37 @@ pub trait EngineBrushPipeline: Send {
     fn restore_origin_before_each_dab(&self) -> bool {
         false
     }
+
+    /// Stroke buffers that accumulate color premultiplied by thickness keep
+    /// each dab's own color; their writes then carry no tint.
+    fn stroke_buffer_accumulates_color(&self) -> bool {
+        false
+    }
+
+    /// Expands one resampled input into the dabs actually stamped for it.
+    /// Each dab is dispatched like its own input, so brushes can scatter or
+    /// repeat dabs without the runtime knowing why.
+    fn expand_dabs(&mut self, brush_input: &BrushInput, dabs: &mut Vec<BrushInput>) {
+        dabs.push(*brush_input);
+    }
+
+    /// Called instead of `expand_dabs` for the stroke-end input, so brushes
+    /// that hold dabs back while looking at later inputs can emit them.
+    fn finish_dabs(&mut self, _dabs: &mut Vec<BrushInput>) {}
+
+    /// Called before the inputs of each symmetry copy are dispatched, so
+    /// brushes that look back over earlier dabs keep one history per copy.
+    fn begin_symmetry_copy(&mut self, _copy_index: usize) {}
+
+    /// Called once per dab before any of its tiles are encoded, so per-dab
+    /// state such as random seeds is shared by every touched tile.
+    fn begin_dab(&mut self, _brush_input: &BrushInput) {}
+
+    /// Brushes that read canvas color get the document composite bound as
+    /// their ref image, so the shader can sample it at binding 0.
+    fn samples_composite(&self) -> bool {
+        false
+    }
 }
 
 #[derive(Debug)]
@@ -155,6 +195,23 @@ struct EngineBrushRegistration {
     max_affected_radius_px: u32,
     stroke_buffer_backend: Option<BackendId>,
     pipeline: Box<dyn EngineBrushPipeline>,
+    color: ColorDynamicsSampler,
+}
+
+impl EngineBrushRegistration {
+    /// Erasing ignores color, so it leaves the color sequence untouched.
+    fn dab_rgb(
+        &mut self,
+        foreground: [f32; 3],
+        background: [f32; 3],
+        erase: bool,
+        brush_input: &BrushInput,
+    ) -> [f32; 3] {
+        if erase {
+            return foreground;
+        }
+        self.color.dab_rgb(foreground, background, brush_input)
+    }
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -170,6 +227,7 @@ pub struct BrushEngineRuntime {
     stroke_tiles: HashMap<StrokeTileKey, TileKey>,
     stroke_restore_tiles: HashMap<StrokeTileKey, TileKey>,
     stroke_buffer_tiles: HashMap<StrokeTileKey, TileKey>,
+    background_rgb: [f32; 3],
 }
 
 impl BrushEngineRuntime {
@@ -180,6 +238,7 @@ impl BrushEngineRuntime {
             stroke_tiles: HashMap::new(),
             stroke_restore_tiles: HashMap::new(),
             stroke_buffer_tiles: HashMap::new(),
+            background_rgb: [1.0, 1.0, 1.0],
         }
     }
 
@@ -216,6 +275,32 @@ impl BrushEngineRuntime {
         allocator.end_stroke();
     }
 
+    /// Abandons the stroke: the snapshot and stroke-buffer tiles it allocated
+    /// are discarded, and the origin tiles it replaced stay as they were.
+    /// Callers point the image back at those origin tiles.
+    pub fn cancel_stroke<A>(&mut self, allocator: &mut A)
+    where
+        A: TileSlotAllocator,
+    {
+        for (&stroke_key, &restore_tile) in &self.stroke_restore_tiles {
+            let origin_tile = self
+                .stroke_tiles
+                .get(&stroke_key)
+                .copied()
+                .unwrap_or(TileKey::EMPTY);
+            if origin_tile == TileKey::EMPTY && restore_tile != TileKey::EMPTY {
+                allocator.discard(restore_tile);
+            }
+        }
+        for &tile_key in self.stroke_buffer_tiles.values() {
+            allocator.discard(tile_key);
+        }
+        self.stroke_tiles.clear();
+        self.stroke_restore_tiles.clear();
+        self.stroke_buffer_tiles.clear();
+        allocator.cancel_stroke();
+    }
+
     pub fn register_pipeline<P>(
         &mut self,
         brush_id: BrushId,
@@ -254,6 +339,7 @@ impl BrushEngineRuntime {
                 max_affected_radius_px,
                 stroke_buffer_backend,
                 pipeline: Box::new(pipeline),
+                color: ColorDynamicsSampler::new(ColorDynamics::default()),
             },
         )
     }
@@ -265,6 +351,40 @@ impl BrushEngineRuntime {
         self.pipelines.ensure_can_register(brush_id)
     }
 
+    pub fn samples_composite(&self, brush_id: BrushId) -> Result<bool, BrushRegistryError> {
+        Ok(self.pipelines.get(brush_id)?.pipeline.samples_composite())
+    }
+
+    pub fn begin_symmetry_copy(
+        &mut self,
+        brush_id: BrushId,
+        copy_index: usize,
+    ) -> Result<(), BrushRegistryError> {
+        self.pipelines
+            .get_mut(brush_id)?
+            .pipeline
+            .begin_symmetry_copy(copy_index);
+        Ok(())
+    }
+
+    /// Background color that color dynamics mix toward.
+    pub fn set_background_rgb(&mut self, rgb: [f32; 3]) {
+        self.background_rgb = rgb;
+    }
+
+    pub fn set_color_dynamics(
+        &mut self,
+        brush_id: BrushId,
+        dynamics: ColorDynamics,
+    ) -> Result<(), BrushRegistryError> {
+        self.pipelines.get_mut(brush_id)?.color = ColorDynamicsSampler::new(dynamics);
+        Ok(())
+    }
+
+    pub fn color_dynamics(&self, brush_id: BrushId) -> Result<ColorDynamics, BrushRegistryError> {
+        Ok(*self.pipelines.get(brush_id)?.color.dynamics())
+    }
+
     pub fn update_pipeline<P>(
         &mut self,
         brush_id: BrushId,
@@ -274,9 +394,18 @@ impl BrushEngineRuntime {
     where
         P: EngineBrushPipeline + 'static,
     {
+        self.update_boxed_pipeline(brush_id, max_affected_radius_px, Box::new(pipeline))
+    }
+
+    pub fn update_boxed_pipeline(
+        &mut self,
+        brush_id: BrushId,
+        max_affected_radius_px: u32,
+        pipeline: Box<dyn EngineBrushPipeline>,
+    ) -> Result<(), BrushRegistryError> {
         let registration = self.pipelines.get_mut(brush_id)?;
         registration.max_affected_radius_px = max_affected_radius_px;
-        registration.pipeline = Box::new(pipeline);
+        registration.pipeline = pipeline;
         Ok(())
     }
 
@@ -314,6 +443,8 @@ impl BrushEngineRuntime {
         ref_tile_key: Option<TileKey>,
     ) -> Result<DrawOp, EngineBrushDispatchError> {
         let registration = self.pipelines.get_mut(brush_id)?;
+        registration.pipeline.begin_dab(brush_input);
+        let rgb = registration.dab_rgb(rgb, self.background_rgb, erase, brush_input);
         let encoded_input = registration
             .pipeline
             .encode_draw_input(brush_input, tile_key, CanvasVec2::new(0.0, 0.0))
@@ -449,6 +580,21 @@ impl BrushEngineRuntime {
         )
     }
 
+    fn collect_dabs(
+        &mut self,
+        brush_id: BrushId,
+        brush_input: &BrushInput,
+        dabs: &mut Vec<BrushInput>,
+    ) -> Result<(), EngineBrushDispatchError> {
+        let pipeline = &mut self.pipelines.get_mut(brush_id)?.pipeline;
+        if brush_input.flags.contains(BrushInputFlags::STROKE_END) {
+            pipeline.finish_dabs(dabs);
+        } else {
+            pipeline.expand_dabs(brush_input, dabs);
+        }
+        Ok(())
+    }
+
     fn dispatch_draw_ops_for_image<F>(
         &mut self,
         brush_id: BrushId,
@@ -463,51 +609,59 @@ impl BrushEngineRuntime {
     where
         F: FnMut(DrawOp),
     {
-        let max_affected_radius_px = self.pipelines.get_mut(brush_id)?.max_affected_radius_px;
-        self.scratch_affected_tiles.clear();
-        let scratch_affected_tiles = &mut self.scratch_affected_tiles;
-        image.for_each_affected_tile_key(
-            brush_input.cursor.cursor,
-            max_affected_radius_px,
-            |tile_index, tile_key| {
-                let ref_tile_key = ref_image.and_then(|image| image.tile_key(tile_index));
-                scratch_affected_tiles.push(AffectedTile {
-                    tile_index,
-                    tile_key,
-                    ref_tile_key,
-                });
-            },
-        );
-
-        let registration = self.pipelines.get_mut(brush_id)?;
-        for affected_tile in self.scratch_affected_tiles.iter().copied() {
-            let tile_canvas_origin = image
-                .tile_canvas_origin(affected_tile.tile_index)
-                .unwrap_or(CanvasVec2::new(0.0, 0.0));
-            let encoded_input = registration
-                .pipeline
-                .encode_draw_input(brush_input, affected_tile.tile_key, tile_canvas_origin)
-                .map_err(|source| EngineBrushDispatchError::Pipeline { brush_id, source })?;
-            emit(DrawOp {
-                node_id,
-                tile_index: affected_tile.tile_index,
-                tile_key: affected_tile.tile_key,
-                blend_mode: if erase {
-                    DrawBlendMode::Replace
-                } else {
-                    DrawBlendMode::Alpha
+        let mut dabs = Vec::with_capacity(1);
+        self.collect_dabs(brush_id, brush_input, &mut dabs)?;
+        for brush_input in &dabs {
+            let max_affected_radius_px = self.pipelines.get_mut(brush_id)?.max_affected_radius_px;
+            self.scratch_affected_tiles.clear();
+            let scratch_affected_tiles = &mut self.scratch_affected_tiles;
+            image.for_each_affected_tile_key(
+                brush_input.cursor.cursor,
+                max_affected_radius_px,
+                |tile_index, tile_key| {
+                    let ref_tile_key = ref_image
+                        .and_then(|image| image.tile_key(tile_index))
+                        .filter(|tile_key| *tile_key != TileKey::EMPTY);
+                    scratch_affected_tiles.push(AffectedTile {
+                        tile_index,
+                        tile_key,
+                        ref_tile_key,
+                    });
                 },
-                frame_merge: DrawFrameMergePolicy::None,
-                origin_tile: TileKey::EMPTY,
-                ref_image: affected_tile
-                    .ref_tile_key
-                    .map(|tile_key| RefImage { tile_key }),
-                input: encoded_input,
-                rgb,
-                erase,
-                brush_id,
-                stroke_id: brush_input.stroke,
-            });
+            );
+
+            let registration = self.pipelines.get_mut(brush_id)?;
+            registration.pipeline.begin_dab(brush_input);
+            let dab_rgb = registration.dab_rgb(rgb, self.background_rgb, erase, brush_input);
+            for affected_tile in self.scratch_affected_tiles.iter().copied() {
+                let tile_canvas_origin = image
+                    .tile_canvas_origin(affected_tile.tile_index)
+                    .unwrap_or(CanvasVec2::new(0.0, 0.0));
+                let encoded_input = registration
+                    .pipeline
+                    .encode_draw_input(brush_input, affected_tile.tile_key, tile_canvas_origin)
+                    .map_err(|source| EngineBrushDispatchError::Pipeline { brush_id, source })?;
+                emit(DrawOp {
+                    node_id,
+                    tile_index: affected_tile.tile_index,
+                    tile_key: affected_tile.tile_key,
+                    blend_mode: if erase {
+                        DrawBlendMode::Replace
+                    } else {
+                        DrawBlendMode::Alpha
+                    },
+                    frame_merge: DrawFrameMergePolicy::None,
+                    origin_tile: TileKey::EMPTY,
+                    ref_image: affected_tile
+                        .ref_tile_key
+                        .map(|tile_key| RefImage { tile_key }),
+                    input: encoded_input,
+                    rgb: dab_rgb,
+                    erase,
+                    brush_id,
+                    stroke_id: brush_input.stroke,
+                });
+            }
         }
         Ok(())
     }
@@ -527,252 +681,276 @@ impl BrushEngineRuntime {
     where
         A: TileSlotAllocator,
     {
-        let max_affected_radius_px = self.pipelines.get_mut(brush_id)?.max_affected_radius_px;
-        self.scratch_affected_tiles.clear();
-        let scratch_affected_tiles = &mut self.scratch_affected_tiles;
-        image.for_each_affected_tile_key(
-            brush_input.cursor.cursor,
-            max_affected_radius_px,
-            |tile_index, tile_key| {
-                let ref_tile_key = ref_image.and_then(|image| image.tile_key(tile_index));
-                scratch_affected_tiles.push(AffectedTile {
-                    tile_index,
-                    tile_key,
-                    ref_tile_key,
-                });
-            },
-        );
+        let mut dabs = Vec::with_capacity(1);
+        self.collect_dabs(brush_id, brush_input, &mut dabs)?;
+        for brush_input in &dabs {
+            let max_affected_radius_px = self.pipelines.get_mut(brush_id)?.max_affected_radius_px;
+            self.scratch_affected_tiles.clear();
+            let scratch_affected_tiles = &mut self.scratch_affected_tiles;
+            image.for_each_affected_tile_key(
+                brush_input.cursor.cursor,
+                max_affected_radius_px,
+                |tile_index, tile_key| {
+                    let ref_tile_key = ref_image
+                        .and_then(|image| image.tile_key(tile_index))
+                        .filter(|tile_key| *tile_key != TileKey::EMPTY);
+                    scratch_affected_tiles.push(AffectedTile {
+                        tile_index,
+                        tile_key,
+                        ref_tile_key,
+                    });
+                },
+            );
 
-        let affected_tiles: Vec<AffectedTile> = self.scratch_affected_tiles.clone();
-
-        let mut prepared_tiles: Vec<(
-            usize,
-            TileKey,
-            TileKey,
-            Option<TileKey>,
-            Option<CopyOp>,
-            Option<ClearOp>,
-            Option<ClearOp>,
-            Option<TileKey>,
-            TileKey,
-            Option<StrokeTileKeyUpdate>,
-        )> = Vec::new();
-        let stroke_buffer_backend = self.pipelines.get(brush_id)?.stroke_buffer_backend;
-        let uses_stroke_buffer = stroke_buffer_backend.is_some();
-        let (copy_frame_merge_tag, write_frame_merge_tag, restore_origin_before_each_dab) =
-            if uses_stroke_buffer {
-                let registration = self.pipelines.get_mut(brush_id)?;
-                (
-                    registration.pipeline.stroke_buffer_copy_frame_merge_tag(),
-                    registration.pipeline.stroke_buffer_write_frame_merge_tag(),
-                    registration.pipeline.restore_origin_before_each_dab(),
-                )
+            let affected_tiles: Vec<AffectedTile> = self.scratch_affected_tiles.clone();
+
+            let mut prepared_tiles: Vec<(
+                usize,
+                TileKey,
+                TileKey,
+                Option<TileKey>,
+                Option<CopyOp>,
+                Option<ClearOp>,
+                Option<ClearOp>,
+                Option<TileKey>,
+                TileKey,
+                Option<StrokeTileKeyUpdate>,
+            )> = Vec::new();
+            let stroke_buffer_backend = self.pipelines.get(brush_id)?.stroke_buffer_backend;
+            let uses_stroke_buffer = stroke_buffer_backend.is_some();
+            let (copy_frame_merge_tag, write_frame_merge_tag, restore_origin_before_each_dab) =
+                if uses_stroke_buffer {
+                    let registration = self.pipelines.get_mut(brush_id)?;
+                    (
+                        registration.pipeline.stroke_buffer_copy_frame_merge_tag(),
+                        registration.pipeline.stroke_buffer_write_frame_merge_tag(),
+                        registration.pipeline.restore_origin_before_each_dab(),
+                    )
+                } else {
+                    let registration = self.pipelines.get_mut(brush_id)?;
+                    (
+                        GpuCmdFrameMergeTag::None,
+                        GpuCmdFrameMergeTag::None,
+                        registration.pipeline.restore_origin_before_each_dab(),
+                    )
+                };
+            let registration = self.pipelines.get_mut(brush_id)?;
+            registration.pipeline.begin_dab(brush_input);
+            let dab_rgb = registration.dab_rgb(rgb, self.background_rgb, erase, brush_input);
+            let write_rgb = if erase || registration.pipeline.stroke_buffer_accumulates_color() {
+                None
             } else {
-                let registration = self.pipelines.get_mut(brush_id)?;
-                (
-                    GpuCmdFrameMergeTag::None,
-                    GpuCmdFrameMergeTag::None,
-                    registration.pipeline.restore_origin_before_each_dab(),
-                )
+                Some(dab_rgb)
             };
 
-        for affected_tile in affected_tiles {
-            let stroke_key = StrokeTileKey {
-                node_id,
-                tile_index: affected_tile.tile_index,
-            };
+            for affected_tile in affected_tiles {
+                let stroke_key = StrokeTileKey {
+                    node_id,
+                    tile_index: affected_tile.tile_index,
+                };
 
-            let (
-                final_tile_key,
-                origin_tile,
-                copy_op,
-                origin_init_clear_op,
-                erase_origin_tile_key,
-                tile_key_update,
-            ) = self.prepare_tile_for_stroke(
-                stroke_key,
-                affected_tile.tile_key,
-                affected_tile.tile_index,
-                node_id,
-                image,
-                allocator,
-                restore_origin_before_each_dab,
-            );
+                let (
+                    final_tile_key,
+                    origin_tile,
+                    copy_op,
+                    origin_init_clear_op,
+                    erase_origin_tile_key,
+                    tile_key_update,
+                ) = self.prepare_tile_for_stroke(
+                    stroke_key,
+                    affected_tile.tile_key,
+                    affected_tile.tile_index,
+                    node_id,
+                    image,
+                    allocator,
+                    restore_origin_before_each_dab,
+                );
 
-            let (buffer_tile_key, clear_op) = if let Some(buffer_backend) = stroke_buffer_backend {
                 let (buffer_tile_key, clear_op) =
-                    self.prepare_stroke_buffer_tile(stroke_key, buffer_backend, allocator);
-                (Some(buffer_tile_key), clear_op)
-            } else {
-                (None, None)
-            };
+                    if let Some(buffer_backend) = stroke_buffer_backend {
+                        let (buffer_tile_key, clear_op) =
+                            self.prepare_stroke_buffer_tile(stroke_key, buffer_backend, allocator);
+                        (Some(buffer_tile_key), clear_op)
+                    } else {
+                        (None, None)
+                    };
+
+                prepared_tiles.push((
+                    affected_tile.tile_index,
+                    final_tile_key,
+                    origin_tile,
+                    affected_tile.ref_tile_key,
+                    copy_op,
+                    origin_init_clear_op,
+                    clear_op,
+                    buffer_tile_key,
+                    erase_origin_tile_key,
+                    tile_key_update,
+                ));
+            }
 
-            prepared_tiles.push((
-                affected_tile.tile_index,
+            for (
+                tile_index,
                 final_tile_key,
                 origin_tile,
-                affected_tile.ref_tile_key,
+                ref_tile_key,
                 copy_op,
                 origin_init_clear_op,
                 clear_op,
                 buffer_tile_key,
                 erase_origin_tile_key,
                 tile_key_update,
-            ));
-        }
+            ) in prepared_tiles
+            {
+                let tile_canvas_origin = image
+                    .tile_canvas_origin(tile_index)
+                    .unwrap_or(CanvasVec2::new(0.0, 0.0));
+                if uses_stroke_buffer {
+                    let Some(buffer_tile_key) = buffer_tile_key else {
+                        continue;
+                    };
+                    if let Some(clear_op) = origin_init_clear_op {
+                        output.push(StrokeDrawOutput {
+                            clear_op: Some(clear_op),
+                            draw_op: None,
+                            copy_op: None,
+                            write_op: None,
+                            composite_op: None,
+                            tile_key_update: None,
+                        });
+                    }
+                    if buffer_tile_key == TileKey::EMPTY {
+                        return Err(EngineBrushDispatchError::StrokeBufferUnavailable {
+                            brush_id,
+                            node_id,
+                            tile_index,
+                        });
+                    }
+                    let encoded_dab_input = self
+                        .pipelines
+                        .get_mut(brush_id)?
+                        .pipeline
+                        .encode_stroke_buffer_dab_input(
+                            brush_input,
+                            buffer_tile_key,
+                            tile_canvas_origin,
+                        )
+                        .map_err(|source| EngineBrushDispatchError::Pipeline {
+                            brush_id,
+                            source,
+                        })?;
+
+                    let copy_op = copy_op.map(|copy_op| CopyOp {
+                        src_tile_key: copy_op.src_tile_key,
+                        dst_tile_key: copy_op.dst_tile_key,
+                        frame_merge: copy_frame_merge_tag,
+                    });
 
-        for (
-            tile_index,
-            final_tile_key,
-            origin_tile,
-            ref_tile_key,
-            copy_op,
-            origin_init_clear_op,
-            clear_op,
-            buffer_tile_key,
-            erase_origin_tile_key,
-            tile_key_update,
-        ) in prepared_tiles
-        {
-            let tile_canvas_origin = image
-                .tile_canvas_origin(tile_index)
-                .unwrap_or(CanvasVec2::new(0.0, 0.0));
-            if uses_stroke_buffer {
-                let Some(buffer_tile_key) = buffer_tile_key else {
-                    continue;
-                };
-                if let Some(clear_op) = origin_init_clear_op {
                     output.push(StrokeDrawOutput {
-                        clear_op: Some(clear_op),
-                        draw_op: None,
-                        copy_op: None,
+                        clear_op,
+                        draw_op: Some(DrawOp {
+                            node_id,
+                            tile_index,
+                            tile_key: buffer_tile_key,
+                            blend_mode: DrawBlendMode::Additive,
+                            frame_merge: DrawFrameMergePolicy::None,
+                            origin_tile: TileKey::EMPTY,
+                            ref_image: None,
+                            input: encoded_dab_input,
+                            rgb: dab_rgb,
+                            erase: false,
+                            brush_id,
+                            stroke_id: brush_input.stroke,
+                        }),
+                        copy_op,
                         write_op: None,
                         composite_op: None,
-                        tile_key_update: None,
+                        tile_key_update,
                     });
-                }
-                if buffer_tile_key == TileKey::EMPTY {
-                    return Err(EngineBrushDispatchError::StrokeBufferUnavailable {
-                        brush_id,
-                        node_id,
-                        tile_index,
-                    });
-                }
-                let encoded_dab_input = self
-                    .pipelines
-                    .get_mut(brush_id)?
-                    .pipeline
-                    .encode_stroke_buffer_dab_input(
-                        brush_input,
-                        buffer_tile_key,
-                        tile_canvas_origin,
-                    )
-                    .map_err(|source| EngineBrushDispatchError::Pipeline { brush_id, source })?;
-
-                let copy_op = copy_op.map(|copy_op| CopyOp {
-                    src_tile_key: copy_op.src_tile_key,
-                    dst_tile_key: copy_op.dst_tile_key,
-                    frame_merge: copy_frame_merge_tag,
-                });
-
-                output.push(StrokeDrawOutput {
-                    clear_op,
-                    draw_op: Some(DrawOp {
-                        node_id,
-                        tile_index,
-                        tile_key: buffer_tile_key,
-                        blend_mode: DrawBlendMode::Additive,
-                        frame_merge: DrawFrameMergePolicy::None,
-                        origin_tile: TileKey::EMPTY,
-                        ref_image: None,
-                        input: encoded_dab_input,
-                        rgb,
-                        erase: false,
-                        brush_id,
-                        stroke_id: brush_input.stroke,
-                    }),
-                    copy_op,
-                    write_op: None,
-                    composite_op: None,
-                    tile_key_update,
-                });
-
-                let write_opacity = self
-                    .pipelines
-                    .get_mut(brush_id)?
-                    .pipeline
-                    .stroke_buffer_write_opacity(brush_input)
-                    .map_err(|source| EngineBrushDispatchError::Pipeline { brush_id, source })?;
-                let write_dst_tile_key = copy_op
-                    .map(|copy_op| copy_op.dst_tile_key)
-                    .unwrap_or(final_tile_key);
-                output.push(StrokeDrawOutput {
-                    clear_op: None,
-                    draw_op: None,
-                    copy_op: None,
-                    write_op: Some(WriteOp {
-                        src_tile_key: buffer_tile_key,
-                        dst_tile_key: write_dst_tile_key,
-                        blend_mode: if erase {
-                            WriteBlendMode::Erase
-                        } else {
-                            WriteBlendMode::Normal
-                        },
-                        opacity: write_opacity,
-                        rgb: if erase { None } else { Some(rgb) },
-                        origin_tile_key: if erase {
-                            Some(erase_origin_tile_key)
-                        } else {
-                            None
-                        },
-                        frame_merge: write_frame_merge_tag,
-                    }),
-                    composite_op: None,
-                    tile_key_update: None,
-                });
-            } else {
-                let encoded_input = self
-                    .pipelines
-                    .get_mut(brush_id)?
-                    .pipeline
-                    .encode_draw_input(brush_input, final_tile_key, tile_canvas_origin)
-                    .map_err(|source| EngineBrushDispatchError::Pipeline { brush_id, source })?;
 
-                if let Some(clear_op) = origin_init_clear_op {
+                    let write_opacity = self
+                        .pipelines
+                        .get_mut(brush_id)?
+                        .pipeline
+                        .stroke_buffer_write_opacity(brush_input)
+                        .map_err(|source| EngineBrushDispatchError::Pipeline {
+                            brush_id,
+                            source,
+                        })?;
+                    let write_dst_tile_key = copy_op
+                        .map(|copy_op| copy_op.dst_tile_key)
+                        .unwrap_or(final_tile_key);
                     output.push(StrokeDrawOutput {
-                        clear_op: Some(clear_op),
+                        clear_op: None,
                         draw_op: None,
                         copy_op: None,
-                        write_op: None,
+                        write_op: Some(WriteOp {
+                            src_tile_key: buffer_tile_key,
+                            dst_tile_key: write_dst_tile_key,
+                            blend_mode: if erase {
+                                WriteBlendMode::Erase
+                            } else {
+                                WriteBlendMode::Normal
+                            },
+                            opacity: write_opacity,
+                            rgb: write_rgb,
+                            origin_tile_key: if erase {
+                                Some(erase_origin_tile_key)
+                            } else {
+                                None
+                            },
+                            frame_merge: write_frame_merge_tag,
+                        }),
                         composite_op: None,
                         tile_key_update: None,
                     });
+                } else {
+                    let encoded_input = self
+                        .pipelines
+                        .get_mut(brush_id)?
+                        .pipeline
+                        .encode_draw_input(brush_input, final_tile_key, tile_canvas_origin)
+                        .map_err(|source| EngineBrushDispatchError::Pipeline {
+                            brush_id,
+                            source,
+                        })?;
+
+                    if let Some(clear_op) = origin_init_clear_op {
+                        output.push(StrokeDrawOutput {
+                            clear_op: Some(clear_op),
+                            draw_op: None,
+                            copy_op: None,
+                            write_op: None,
+                            composite_op: None,
+                            tile_key_update: None,
+                        });
+                    }
+                    output.push(StrokeDrawOutput {
+                        clear_op: None,
+                        draw_op: Some(DrawOp {
+                            node_id,
+                            tile_index,
+                            tile_key: final_tile_key,
+                            blend_mode: if erase {
+                                DrawBlendMode::Replace
+                            } else {
+                                DrawBlendMode::Alpha
+                            },
+                            frame_merge: DrawFrameMergePolicy::None,
+                            origin_tile,
+                            ref_image: ref_tile_key.map(|tile_key| RefImage { tile_key }),
+                            input: encoded_input,
+                            rgb: dab_rgb,
+                            erase,
+                            brush_id,
+                            stroke_id: brush_input.stroke,
+                        }),
+                        copy_op,
+                        write_op: None,
+                        composite_op: None,
+                        tile_key_update,
+                    });
                 }
-                output.push(StrokeDrawOutput {
-                    clear_op: None,
-                    draw_op: Some(DrawOp {
-                        node_id,
-                        tile_index,
-                        tile_key: final_tile_key,
-                        blend_mode: if erase {
-                            DrawBlendMode::Replace
-                        } else {
-                            DrawBlendMode::Alpha
-                        },
-                        frame_merge: DrawFrameMergePolicy::None,
-                        origin_tile,
-                        ref_image: ref_tile_key.map(|tile_key| RefImage { tile_key }),
-                        input: encoded_input,
-                        rgb,
-                        erase,
-                        brush_id,
-                        stroke_id: brush_input.stroke,
-                    }),
-                    copy_op,
-                    write_op: None,
-                    composite_op: None,
-                    tile_key_update,
-                });
             }
         }
         Ok(())
@@ -934,6 +1112,7 @@ mod tests {
     use super::{
         BrushEngineRuntime, EngineBrushDispatchError, EngineBrushPipeline, TileSlotAllocator,
     };
+    use crate::color_dynamics::{ColorDynamics, ColorMixSource};
 
     struct TestEnginePipeline;
 
@@ -973,6 +1152,8 @@ mod tests {
         parity_requests: Vec<bool>,
         replacements: Vec<(TileKey, TileKey)>,
         releases: Vec<TileKey>,
+        discards: Vec<TileKey>,
+        cancelled: bool,
     }
 
     impl TileSlotAllocator for TestAllocator {
@@ -1000,6 +1181,14 @@ mod tests {
         fn release(&mut self, tile: TileKey) {
             self.releases.push(tile);
         }
+
+        fn discard(&mut self, tile: TileKey) {
+            self.discards.push(tile);
+        }
+
+        fn cancel_stroke(&mut self) {
+            self.cancelled = true;
+        }
     }
 
     fn build_test_brush_input(center: CanvasVec2) -> BrushInput {
@@ -1073,6 +1262,59 @@ mod tests {
         assert_eq!(draw_ops[1].ref_image, None);
     }
 
+    #[test]
+    fn color_dynamics_give_each_dab_its_own_rgb() {
+        let layout = ImageLayout::new(IMAGE_TILE_SIZE, IMAGE_TILE_SIZE);
+        let image_result = Image::new(layout, glaphica_core::BackendId::new(1));
+        assert!(image_result.is_ok());
+        let mut image = match image_result {
+            Ok(image) => image,
+            Err(_) => return,
+        };
+        assert!(
+            image
+                .set_tile_key(0, TileKey::from_parts(1, 1, 100))
+                .is_ok()
+        );
+
+        let mut runtime = BrushEngineRuntime::new(4);
+        assert!(
+            runtime
+                .register_pipeline(BrushId(2), 0, TestEnginePipeline)
+                .is_ok()
+        );
+        assert!(
+            runtime
+                .set_color_dynamics(
+                    BrushId(2),
+                    ColorDynamics {
+                        mix_source: ColorMixSource::Pressure,
+                        ..ColorDynamics::default()
+                    },
+                )
+                .is_ok()
+        );
+        runtime.set_background_rgb([0.0, 0.0, 1.0]);
+
+        let mut draw_ops = Vec::new();
+        for pressure in [1.0, 0.0] {
+            let mut brush_input = build_test_brush_input(CanvasVec2::new(10.0, 10.0));
+            brush_input.cursor.pressure = pressure;
+            let build_result = runtime.build_draw_ops_for_image(
+                BrushId(2),
+                &brush_input,
+                [1.0, 0.0, 0.0],
+                false,
+                NodeId(1),
+                &image,
+                &mut draw_ops,
+            );
+            assert!(build_result.is_ok());
+        }
+        let colors: Vec<_> = draw_ops.iter().map(|draw_op| draw_op.rgb).collect();
+        assert_eq!(colors, vec![[1.0, 0.0, 0.0], [0.0, 0.0, 1.0]]);
+    }
+
     #[test]
     fn build_draw_ops_for_image_resolves_ref_image_tile_key_by_same_tile_index() {
         let layout = ImageLayout::new(IMAGE_TILE_SIZE * 2, IMAGE_TILE_SIZE);
@@ -1163,6 +1405,8 @@ mod tests {
             parity_requests: Vec::new(),
             replacements: Vec::new(),
             releases: Vec::new(),
+            discards: Vec::new(),
+            cancelled: false,
         };
         runtime.begin_stroke(&mut allocator);
         let brush_input = build_test_brush_input(CanvasVec2::new(10.0, 10.0));
@@ -1220,6 +1464,8 @@ mod tests {
             parity_requests: Vec::new(),
             replacements: Vec::new(),
             releases: Vec::new(),
+            discards: Vec::new(),
+            cancelled: false,
         };
         runtime.begin_stroke(&mut first_allocator);
         let brush_input = build_test_brush_input(CanvasVec2::new(10.0, 10.0));
@@ -1312,4 +1558,57 @@ mod tests {
             })
         ));
     }
+
+    #[test]
+    fn cancel_stroke_discards_stroke_buffer_tiles_and_keeps_origin() {
+        let layout = ImageLayout::new(IMAGE_TILE_SIZE, IMAGE_TILE_SIZE);
+        let mut image = match Image::new(layout, glaphica_core::BackendId::new(1)) {
+            Ok(image) => image,
+            Err(_) => return,
+        };
+        let existing_key = TileKey::from_parts(1, 0, 0x0000_0007);
+        let stroke_tile = TileKey::from_parts(1, 0, 0x8000_0001);
+        let buffer_tile = TileKey::from_parts(2, 0, 3);
+        assert!(image.set_tile_key(0, existing_key).is_ok());
+
+        let mut runtime = BrushEngineRuntime::new(4);
+        assert!(
+            runtime
+                .register_pipeline_with_stroke_buffer_backend(
+                    BrushId(3),
+                    0,
+                    Some(glaphica_core::BackendId::new(2)),
+                    TestStrokeBufferPipeline,
+                )
+                .is_ok()
+        );
+        let mut allocator = TestAllocator {
+            regular_alloc: Some(buffer_tile),
+            odd_alloc: Some(stroke_tile),
+            ..TestAllocator::default()
+        };
+        runtime.begin_stroke(&mut allocator);
+        let brush_input = build_test_brush_input(CanvasVec2::new(10.0, 10.0));
+        let mut output = Vec::new();
+        let result = runtime.build_stroke_draw_outputs_for_image(
+            BrushId(3),
+            &brush_input,
+            [1.0, 0.0, 0.0],
+            false,
+            NodeId(9),
+            &mut image,
+            None,
+            &mut allocator,
+            &mut output,
+        );
+        assert!(result.is_ok());
+        assert_eq!(image.tile_key(0), Some(stroke_tile));
+
+        runtime.cancel_stroke(&mut allocator);
+
+        assert!(allocator.cancelled);
+        assert_eq!(allocator.discards, vec![buffer_tile]);
+        assert!(allocator.releases.is_empty());
+        assert_eq!(allocator.replacements, vec![(existing_key, stroke_tile)]);
+    }
 }
@@ -0,0 +1,1085 @@
+use std::borrow::Cow;
+use std::collections::HashSet;
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::SystemTime;
+
+use glaphica_core::{BrushInput, CanvasVec2, TileKey};
+use serde::Deserialize;
+
+use crate::BrushPipelineError;
+use crate::brush_spec::BrushSpec;
+use crate::config::{
+    BrushConfigItem, BrushConfigKind, BrushConfigValue, BrushConfigValueError, BrushConfigValues,
+    UnitIntervalPoint, eval_unit_interval_curve,
+};
+use crate::draw_layout::{BrushDrawInputLayout, BrushDrawInputShape, BrushDrawKind};
+use crate::engine_runtime::EngineBrushPipeline;
+use crate::gpu_pipeline_spec::BrushGpuPipelineSpec;
+use crate::resampler_distance::BrushResamplerDistancePolicy;
+
+const MANIFEST_VERSION: u32 = 1;
+
+#[derive(Debug)]
+pub enum FileBrushError {
+    Io {
+        path: PathBuf,
+        error: std::io::Error,
+    },
+    Manifest {
+        path: PathBuf,
+        error: serde_json::Error,
+    },
+    UnsupportedVersion(u32),
+    InvalidManifest(String),
+    ShaderParse(String),
+    ShaderValidation(String),
+    MissingEntryPoint {
+        name: String,
+        stage: &'static str,
+    },
+    Config(BrushConfigValueError),
+}
+
+impl Display for FileBrushError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Io { path, error } => write!(f, "{}: {error}", path.display()),
+            Self::Manifest { path, error } => {
+                write!(f, "invalid brush manifest {}: {error}", path.display())
+            }
+            Self::UnsupportedVersion(version) => {
+                write!(f, "unsupported brush manifest version: {version}")
+            }
+            Self::InvalidManifest(reason) => write!(f, "invalid brush manifest: {reason}"),
+            Self::ShaderParse(message) => write!(f, "brush shader parse error:\n{message}"),
+            Self::ShaderValidation(message) => {
+                write!(f, "brush shader validation error:\n{message}")
+            }
+            Self::MissingEntryPoint { name, stage } => {
+                write!(f, "brush shader has no {stage} entry point named {name}")
+            }
+            Self::Config(error) => write!(f, "brush {error}"),
+        }
+    }
+}
+
+impl Error for FileBrushError {}
+
+impl From<BrushConfigValueError> for FileBrushError {
+    fn from(error: BrushConfigValueError) -> Self {
+        Self::Config(error)
+    }
+}
+
+/// A `BrushInput` value that can feed a draw input lane.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BrushInputField {
+    CanvasX,
+    CanvasY,
+    /// Cursor position relative to the tile being drawn.
+    LocalX,
+    LocalY,
+    Pressure,
+    TiltX,
+    TiltY,
+    Twist,
+    PathS,
+    DeltaS,
+    DtS,
+    VelX,
+    VelY,
+    Speed,
+    TangentX,
+    TangentY,
+    AccX,
+    AccY,
+    Accel,
+    Curvature,
+    Confidence,
+}
+
+impl BrushInputField {
+    const NAMES: [(&'static str, Self); 21] = [
+        ("x", Self::CanvasX),
+        ("y", Self::CanvasY),
+        ("local_x", Self::LocalX),
+        ("local_y", Self::LocalY),
+        ("pressure", Self::Pressure),
+        ("tilt_x", Self::TiltX),
+        ("tilt_y", Self::TiltY),
+        ("twist", Self::Twist),
+        ("path_s", Self::PathS),
+        ("delta_s", Self::DeltaS),
+        ("dt_s", Self::DtS),
+        ("vel_x", Self::VelX),
+        ("vel_y", Self::VelY),
+        ("speed", Self::Speed),
+        ("tangent_x", Self::TangentX),
+        ("tangent_y", Self::TangentY),
+        ("acc_x", Self::AccX),
+        ("acc_y", Self::AccY),
+        ("accel", Self::Accel),
+        ("curvature", Self::Curvature),
+        ("confidence", Self::Confidence),
+    ];
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::NAMES
+            .iter()
+            .find(|(candidate, _)| *candidate == name)
+            .map(|(_, field)| *field)
+    }
+
+    pub fn read(self, input: &BrushInput, tile_canvas_origin: CanvasVec2) -> f32 {
+        let cursor = &input.cursor;
+        match self {
+            Self::CanvasX => cursor.cursor.x,
+            Self::CanvasY => cursor.cursor.y,
+            Self::LocalX => cursor.cursor.x - tile_canvas_origin.x,
+            Self::LocalY => cursor.cursor.y - tile_canvas_origin.y,
+            Self::Pressure => cursor.pressure,
+            Self::TiltX => cursor.tilt.x,
+            Self::TiltY => cursor.tilt.y,
+            Self::Twist => cursor.twist,
+            Self::PathS => input.path_s,
+            Self::DeltaS => input.delta_s,
+            Self::DtS => input.dt_s,
+            Self::VelX => input.vel.x,
+            Self::VelY => input.vel.y,
+            Self::Speed => input.speed,
+            Self::TangentX => input.tangent.x,
+            Self::TangentY => input.tangent.y,
+            Self::AccX => input.acc.x,
+            Self::AccY => input.acc.y,
+            Self::Accel => input.accel,
+            Self::Curvature => input.curvature,
+            Self::Confidence => input.confidence,
+        }
+    }
+}
+
+/// Where one `f32` lane of the draw payload comes from.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FileBrushSource {
+    Input(BrushInputField),
+    /// Single-number config item (scalar, bool, integer, enum index or
+    /// angle in degrees), by item index.
+    Config(usize),
+    /// Curve config item evaluated at an input field.
+    Curve {
+        config: usize,
+        input: BrushInputField,
+    },
+    Constant(f32),
+}
+
+#[derive(Debug, Deserialize)]
+struct Manifest {
+    version: u32,
+    label: String,
+    shader: PathBuf,
+    #[serde(default = "default_vertex_entry")]
+    vertex_entry: String,
+    #[serde(default = "default_fragment_entry")]
+    fragment_entry: String,
+    /// Scalar config key holding the dab radius in pixels.
+    radius: String,
+    #[serde(default = "default_max_distance_rate")]
+    max_distance_rate: f32,
+    #[serde(default = "default_min_distance_rate")]
+    min_distance_rate: f32,
+    #[serde(default)]
+    config: Vec<ManifestConfigItem>,
+    inputs: Vec<ManifestInputSlot>,
+}
+
+fn default_vertex_entry() -> String {
+    "vs_main".to_string()
+}
+
+fn default_fragment_entry() -> String {
+    "fs_main".to_string()
+}
+
+fn default_max_distance_rate() -> f32 {
+    0.5
+}
+
+fn default_min_distance_rate() -> f32 {
+    0.25
+}
+
+fn default_angle_min() -> f32 {
+    -180.0
+}
+
+fn default_angle_max() -> f32 {
+    180.0
+}
+
+fn default_curve() -> Vec<[f32; 2]> {
+    vec![[0.0, 0.0], [1.0, 1.0]]
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+enum ManifestConfigItem {
+    Scalar {
+        key: String,
+        label: Option<String>,
+        #[serde(default)]
+        hidden: bool,
+        min: f32,
+        max: f32,
+        default: f32,
+    },
+    Curve {
+        key: String,
+        label: Option<String>,
+        #[serde(default)]
+        hidden: bool,
+        #[serde(default = "default_curve")]
+        default: Vec<[f32; 2]>,
+    },
+    Bool {
+        key: String,
+        label: Option<String>,
+        #[serde(default)]
+        hidden: bool,
+        #[serde(default)]
+        default: bool,
+    },
+    Integer {
+        key: String,
+        label: Option<String>,
+        #[serde(default)]
+        hidden: bool,
+        min: i32,
+        max: i32,
+        default: i32,
+    },
+    Enum {
+        key: String,
+        label: Option<String>,
+        #[serde(default)]
+        hidden: bool,
+        options: Vec<String>,
+        /// Option index.
+        #[serde(default)]
+        default: usize,
+    },
+    Angle {
+        key: String,
+        label: Option<String>,
+        #[serde(default)]
+        hidden: bool,
+        #[serde(default = "default_angle_min")]
+        min: f32,
+        #[serde(default = "default_angle_max")]
+        max: f32,
+        #[serde(default)]
+        default: f32,
+    },
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum ManifestShape {
+    F32,
+    Vec2,
+    Vec3,
+    Vec4,
+}
+
+impl ManifestShape {
+    fn draw_input_shape(&self) -> BrushDrawInputShape {
+        match self {
+            Self::F32 => BrushDrawInputShape::F32,
+            Self::Vec2 => BrushDrawInputShape::Vec2F32,
+            Self::Vec3 => BrushDrawInputShape::Vec3F32,
+            Self::Vec4 => BrushDrawInputShape::Vec4F32,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct ManifestInputSlot {
+    shape: ManifestShape,
+    /// One source per lane: a number, `input.<field>`, `config.<key>` or
+    /// `curve.<key>(<field>)`.
+    sources: Vec<ManifestSource>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum ManifestSource {
+    Constant(f32),
+    Expression(String),
+}
+
+/// Parsed brush manifest plus its current shader source.
+///
+/// The manifest is JSON next to the WGSL file it names; see
+/// `assets/brushes/soft_square` for an example.
+#[derive(Debug)]
+pub struct FileBrushDefinition {
+    shader_path: PathBuf,
+    label: &'static str,
+    vertex_entry: &'static str,
+    fragment_entry: &'static str,
+    wgsl_source: String,
+    layout: BrushDrawInputLayout,
+    config_items: Vec<BrushConfigItem>,
+    lanes: Vec<FileBrushSource>,
+    radius_config: usize,
+    max_distance_rate: f32,
+    min_distance_rate: f32,
+}
+
+impl FileBrushDefinition {
+    /// Reads the manifest and its shader, validating both.
+    pub fn load(manifest_path: &Path) -> Result<Self, FileBrushError> {
+        let manifest_json = read_to_string(manifest_path)?;
+        let manifest: Manifest =
+            serde_json::from_str(&manifest_json).map_err(|error| FileBrushError::Manifest {
+                path: manifest_path.to_path_buf(),
+                error,
+            })?;
+        let shader_path = manifest_path
+            .parent()
+            .unwrap_or(Path::new(""))
+            .join(&manifest.shader);
+        let wgsl_source = read_to_string(&shader_path)?;
+        Self::from_manifest(manifest, shader_path, wgsl_source)
+    }
+
+    /// Builds a definition from in-memory manifest JSON and shader source.
+    pub fn from_sources(
+        manifest_json: &str,
+        shader_path: PathBuf,
+        wgsl_source: String,
+    ) -> Result<Self, FileBrushError> {
+        let manifest: Manifest =
+            serde_json::from_str(manifest_json).map_err(|error| FileBrushError::Manifest {
+                path: shader_path.clone(),
+                error,
+            })?;
+        Self::from_manifest(manifest, shader_path, wgsl_source)
+    }
+
+    fn from_manifest(
+        manifest: Manifest,
+        shader_path: PathBuf,
+        wgsl_source: String,
+    ) -> Result<Self, FileBrushError> {
+        if manifest.version > MANIFEST_VERSION {
+            return Err(FileBrushError::UnsupportedVersion(manifest.version));
+        }
+        let config_items = manifest
+            .config
+            .iter()
+            .map(config_item_from_manifest)
+            .collect::<Result<Vec<_>, _>>()?;
+        let mut seen_keys = HashSet::new();
+        if let Some(item) = config_items.iter().find(|item| !seen_keys.insert(item.key)) {
+            return Err(FileBrushError::InvalidManifest(format!(
+                "duplicate config key {}",
+                item.key
+            )));
+        }
+
+        let radius_config = scalar_config_index(&config_items, &manifest.radius)?;
+
+        let mut shapes = Vec::with_capacity(manifest.inputs.len());
+        let mut lanes = Vec::new();
+        for (slot_index, slot) in manifest.inputs.iter().enumerate() {
+            let shape = slot.shape.draw_input_shape();
+            if slot.sources.len() != shape.lane_count() {
+                return Err(FileBrushError::InvalidManifest(format!(
+                    "input {slot_index} needs {} sources, found {}",
+                    shape.lane_count(),
+                    slot.sources.len()
+                )));
+            }
+            shapes.push(shape);
+            for source in &slot.sources {
+                lanes.push(parse_source(source, &config_items)?);
+            }
+        }
+        if shapes.is_empty() {
+            return Err(FileBrushError::InvalidManifest(
+                "at least one input is required".to_string(),
+            ));
+        }
+
+        validate_wgsl(
+            &wgsl_source,
+            &manifest.vertex_entry,
+            &manifest.fragment_entry,
+        )?;
+
+        Ok(Self {
+            shader_path,
+            label: intern_str(&manifest.label),
+            vertex_entry: intern_str(&manifest.vertex_entry),
+            fragment_entry: intern_str(&manifest.fragment_entry),
+            wgsl_source,
+            layout: BrushDrawInputLayout::new(BrushDrawKind::Custom, intern_shapes(&shapes)),
+            config_items,
+            lanes,
+            radius_config,
+            max_distance_rate: manifest.max_distance_rate,
+            min_distance_rate: manifest.min_distance_rate,
+        })
+    }
+
+    pub fn label(&self) -> &'static str {
+        self.label
+    }
+
+    pub fn shader_path(&self) -> &Path {
+        &self.shader_path
+    }
+
+    pub fn config_items(&self) -> &[BrushConfigItem] {
+        &self.config_items
+    }
+
+    pub fn draw_input_layout(&self) -> BrushDrawInputLayout {
+        self.layout
+    }
+
+    /// Re-reads the shader file and validates it against this definition's
+    /// entry points. The definition itself is left unchanged.
+    pub fn read_shader(&self) -> Result<String, FileBrushError> {
+        let wgsl_source = read_to_string(&self.shader_path)?;
+        validate_wgsl(&wgsl_source, self.vertex_entry, self.fragment_entry)?;
+        Ok(wgsl_source)
+    }
+}
+
+fn read_to_string(path: &Path) -> Result<String, FileBrushError> {
+    std::fs::read_to_string(path).map_err(|error| FileBrushError::Io {
+        path: path.to_path_buf(),
+        error,
+    })
+}
+
+fn config_item_from_manifest(item: &ManifestConfigItem) -> Result<BrushConfigItem, FileBrushError> {
+    let (key, label, hidden, kind, default_value) = match item {
+        ManifestConfigItem::Scalar {
+            key,
+            label,
+            hidden,
+            min,
+            max,
+            default,
+        } => {
+            if !(min.is_finite() && max.is_finite() && min <= max) {
+                return Err(FileBrushError::InvalidManifest(format!(
+                    "config {key} has an invalid range"
+                )));
+            }
+            (
+                key,
+                label,
+                *hidden,
+                BrushConfigKind::ScalarF32 {
+                    min: *min,
+                    max: *max,
+                },
+                BrushConfigValue::ScalarF32(default.clamp(*min, *max)),
+            )
+        }
+        ManifestConfigItem::Curve {
+            key,
+            label,
+            hidden,
+            default,
+        } => {
+            if default.len() < 2 {
+                return Err(FileBrushError::InvalidManifest(format!(
+                    "config {key} curve needs at least two points"
+                )));
+            }
+            (
+                key,
+                label,
+                *hidden,
+                BrushConfigKind::UnitIntervalCurve,
+                BrushConfigValue::UnitIntervalCurve(
+                    default
+                        .iter()
+                        .map(|[x, y]| UnitIntervalPoint::new(*x, *y))
+                        .collect(),
+                ),
+            )
+        }
+        ManifestConfigItem::Bool {
+            key,
+            label,
+            hidden,
+            default,
+        } => (
+            key,
+            label,
+            *hidden,
+            BrushConfigKind::Bool,
+            BrushConfigValue::Bool(*default),
+        ),
+        ManifestConfigItem::Integer {
+            key,
+            label,
+            hidden,
+            min,
+            max,
+            default,
+        } => {
+            if min > max {
+                return Err(FileBrushError::InvalidManifest(format!(
+                    "config {key} has an invalid range"
+                )));
+            }
+            (
+                key,
+                label,
+                *hidden,
+                BrushConfigKind::Integer {
+                    min: *min,
+                    max: *max,
+                },
+                BrushConfigValue::Integer((*default).clamp(*min, *max)),
+            )
+        }
+        ManifestConfigItem::Enum {
+            key,
+            label,
+            hidden,
+            options,
+            default,
+        } => {
+            if options.is_empty() {
+                return Err(FileBrushError::InvalidManifest(format!(
+                    "config {key} needs at least one option"
+                )));
+            }
+            (
+                key,
+                label,
+                *hidden,
+                BrushConfigKind::Enum {
+                    options: intern_options(options),
+                },
+                BrushConfigValue::Enum((*default).min(options.len() - 1)),
+            )
+        }
+        ManifestConfigItem::Angle {
+            key,
+            label,
+            hidden,
+            min,
+            max,
+            default,
+        } => {
+            if !(min.is_finite() && max.is_finite() && min <= max) {
+                return Err(FileBrushError::InvalidManifest(format!(
+                    "config {key} has an invalid range"
+                )));
+            }
+            (
+                key,
+                label,
+                *hidden,
+                BrushConfigKind::AngleDeg {
+                    min: *min,
+                    max: *max,
+                },
+                BrushConfigValue::AngleDeg(default.clamp(*min, *max)),
+            )
+        }
+    };
+    Ok(BrushConfigItem {
+        key: intern_str(key),
+        label: intern_str(label.as_deref().unwrap_or(key)),
+        default_hidden: hidden,
+        kind,
+        default_value,
+    })
+}
+
+fn scalar_config_index(items: &[BrushConfigItem], key: &str) -> Result<usize, FileBrushError> {
+    items
+        .iter()
+        .position(|item| item.key == key && matches!(item.kind, BrushConfigKind::ScalarF32 { .. }))
+        .ok_or_else(|| FileBrushError::InvalidManifest(format!("no scalar config named {key}")))
+}
+
+/// Index of an item that reads as one number, for `config.<key>` lanes.
+fn number_config_index(items: &[BrushConfigItem], key: &str) -> Result<usize, FileBrushError> {
+    items
+        .iter()
+        .position(|item| item.key == key && item.default_value.as_f32().is_some())
+        .ok_or_else(|| FileBrushError::InvalidManifest(format!("no numeric config named {key}")))
+}
+
+fn parse_source(
+    source: &ManifestSource,
+    items: &[BrushConfigItem],
+) -> Result<FileBrushSource, FileBrushError> {
+    let expression = match source {
+        ManifestSource::Constant(value) => return Ok(FileBrushSource::Constant(*value)),
+        ManifestSource::Expression(expression) => expression.trim(),
+    };
+    let input_field = |name: &str| {
+        BrushInputField::from_name(name)
+            .ok_or_else(|| FileBrushError::InvalidManifest(format!("unknown input field {name}")))
+    };
+    if let Some(name) = expression.strip_prefix("input.") {
+        return Ok(FileBrushSource::Input(input_field(name)?));
+    }
+    if let Some(key) = expression.strip_prefix("config.") {
+        return Ok(FileBrushSource::Config(number_config_index(items, key)?));
+    }
+    if let Some(call) = expression.strip_prefix("curve.")
+        && let Some((key, argument)) = call.strip_suffix(')').and_then(|call| call.split_once('('))
+    {
+        let config = items
+            .iter()
+            .position(|item| item.key == key && item.kind == BrushConfigKind::UnitIntervalCurve)
+            .ok_or_else(|| {
+                FileBrushError::InvalidManifest(format!("no curve config named {key}"))
+            })?;
+        return Ok(FileBrushSource::Curve {
+            config,
+            input: input_field(argument.trim())?,
+        });
+    }
+    Err(FileBrushError::InvalidManifest(format!(
+        "unrecognized source {expression}"
+    )))
+}
+
+/// Parses and validates WGSL and checks that both entry points exist, so a
+/// broken shader is rejected before it reaches the GPU.
+pub fn validate_wgsl(
+    source: &str,
+    vertex_entry: &str,
+    fragment_entry: &str,
+) -> Result<(), FileBrushError> {
+    let module = naga::front::wgsl::parse_str(source)
+        .map_err(|error| FileBrushError::ShaderParse(error.emit_to_string(source)))?;
+    naga::valid::Validator::new(
+        naga::valid::ValidationFlags::all(),
+        naga::valid::Capabilities::default(),
+    )
+    .validate(&module)
+    .map_err(|error| FileBrushError::ShaderValidation(error.emit_to_string(source)))?;
+    for (name, stage, stage_label) in [
+        (vertex_entry, naga::ShaderStage::Vertex, "vertex"),
+        (fragment_entry, naga::ShaderStage::Fragment, "fragment"),
+    ] {
+        if !module
+            .entry_points
+            .iter()
+            .any(|entry| entry.name == name && entry.stage == stage)
+        {
+            return Err(FileBrushError::MissingEntryPoint {
+                name: name.to_string(),
+                stage: stage_label,
+            });
+        }
+    }
+    Ok(())
+}
+
+// Registry types hold `'static` data, so runtime definitions are leaked once
+// per distinct value; reloading the same brush reuses earlier allocations.
+fn intern_str(value: &str) -> &'static str {
+    static INTERNED: OnceLock<Mutex<HashSet<&'static str>>> = OnceLock::new();
+    let mut interned = INTERNED
+        .get_or_init(Default::default)
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner());
+    if let Some(existing) = interned.get(value) {
+        return existing;
+    }
+    let leaked: &'static str = Box::leak(value.to_string().into_boxed_str());
+    interned.insert(leaked);
+    leaked
+}
+
+fn intern_options(options: &[String]) -> &'static [&'static str] {
+    static INTERNED: OnceLock<Mutex<Vec<&'static [&'static str]>>> = OnceLock::new();
+    let options: Vec<&'static str> = options.iter().map(|option| intern_str(option)).collect();
+    let mut interned = INTERNED
+        .get_or_init(Default::default)
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner());
+    if let Some(existing) = interned.iter().find(|existing| **existing == options) {
+        return existing;
+    }
+    let leaked: &'static [&'static str] = Box::leak(options.into_boxed_slice());
+    interned.push(leaked);
+    leaked
+}
+
+fn intern_shapes(shapes: &[BrushDrawInputShape]) -> &'static [BrushDrawInputShape] {
+    static INTERNED: OnceLock<Mutex<Vec<&'static [BrushDrawInputShape]>>> = OnceLock::new();
+    let mut interned = INTERNED
+        .get_or_init(Default::default)
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner());
+    if let Some(existing) = interned.iter().find(|existing| **existing == shapes) {
+        return existing;
+    }
+    let leaked: &'static [BrushDrawInputShape] = Box::leak(shapes.to_vec().into_boxed_slice());
+    interned.push(leaked);
+    leaked
+}
+
+/// Brush instance built from a [`FileBrushDefinition`] and config values.
+#[derive(Debug, Clone)]
+pub struct FileBrush {
+    definition: Arc<FileBrushDefinition>,
+    values: Vec<BrushConfigValue>,
+    radius_px: f32,
+}
+
+impl FileBrush {
+    pub fn new(definition: Arc<FileBrushDefinition>) -> Self {
+        let values = definition
+            .config_items
+            .iter()
+            .map(|item| item.default_value.clone())
+            .collect();
+        Self::with_values(definition, values)
+    }
+
+    /// Looks up every manifest item by key; extra keys are ignored.
+    pub fn from_config_values(
+        definition: Arc<FileBrushDefinition>,
+        values: &BrushConfigValues,
+    ) -> Result<Self, FileBrushError> {
+        let mut ordered = Vec::with_capacity(definition.config_items.len());
+        for item in &definition.config_items {
+            let value = values.value(item.key)?;
+            if !item.kind.matches(value) {
+                return Err(BrushConfigValueError::TypeMismatch { key: item.key }.into());
+            }
+            ordered.push(value.clone());
+        }
+        Ok(Self::with_values(definition, ordered))
+    }
+
+    fn with_values(definition: Arc<FileBrushDefinition>, values: Vec<BrushConfigValue>) -> Self {
+        let radius_px = match values.get(definition.radius_config) {
+            Some(BrushConfigValue::ScalarF32(radius_px)) => radius_px.max(0.0),
+            _ => 0.0,
+        };
+        Self {
+            definition,
+            values,
+            radius_px,
+        }
+    }
+
+    pub fn definition(&self) -> &Arc<FileBrushDefinition> {
+        &self.definition
+    }
+
+    pub fn config_items(&self) -> Vec<BrushConfigItem> {
+        self.definition.config_items.clone()
+    }
+
+    fn lane_value(&self, source: FileBrushSource, input: &BrushInput, origin: CanvasVec2) -> f32 {
+        match source {
+            FileBrushSource::Input(field) => field.read(input, origin),
+            FileBrushSource::Config(index) => self
+                .values
+                .get(index)
+                .and_then(BrushConfigValue::as_f32)
+                .unwrap_or(0.0),
+            FileBrushSource::Curve {
+                config,
+                input: field,
+            } => {
+                let x = field.read(input, origin);
+                match self.values.get(config) {
+                    Some(BrushConfigValue::UnitIntervalCurve(points)) => {
+                        eval_unit_interval_curve(points, x).unwrap_or(x)
+                    }
+                    _ => x,
+                }
+            }
+            FileBrushSource::Constant(value) => value,
+        }
+    }
+}
+
+impl BrushResamplerDistancePolicy for FileBrush {
+    fn brush_size(&self) -> u32 {
+        self.radius_px.ceil() as u32
+    }
+
+    fn max_distance_rate(&self) -> f32 {
+        self.definition.max_distance_rate
+    }
+
+    fn min_distance_rate(&self) -> f32 {
+        self.definition.min_distance_rate
+    }
+}
+
+impl EngineBrushPipeline for FileBrush {
+    fn encode_draw_input(
+        &mut self,
+        brush_input: &BrushInput,
+        _tile_key: TileKey,
+        tile_canvas_origin: CanvasVec2,
+    ) -> Result<Vec<f32>, BrushPipelineError> {
+        Ok(self
+            .definition
+            .lanes
+            .iter()
+            .map(|source| self.lane_value(*source, brush_input, tile_canvas_origin))
+            .collect())
+    }
+}
+
+impl BrushSpec for FileBrush {
+    fn max_affected_radius_px(&self) -> u32 {
+        self.radius_px.ceil() as u32
+    }
+
+    fn draw_input_layout(&self) -> BrushDrawInputLayout {
+        self.definition.layout
+    }
+
+    fn gpu_pipeline_spec(&self) -> BrushGpuPipelineSpec {
+        BrushGpuPipelineSpec {
+            label: self.definition.label,
+            wgsl_source: Cow::Owned(self.definition.wgsl_source.clone()),
+            vertex_entry: self.definition.vertex_entry,
+            fragment_entry: self.definition.fragment_entry,
+            uses_brush_cache_backend: false,
+            cache_backend_format: None,
+            tip_image: None,
+        }
+    }
+}
+
+/// Polls a file's modification time to detect saves.
+#[derive(Debug)]
+pub struct ShaderFileWatcher {
+    path: PathBuf,
+    last_modified: Option<SystemTime>,
+}
+
+impl ShaderFileWatcher {
+    pub fn new(path: PathBuf) -> Self {
+        let last_modified = modified_time(&path);
+        Self {
+            path,
+            last_modified,
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Returns true once per observed change to the file.
+    pub fn poll_changed(&mut self) -> bool {
+        let modified = modified_time(&self.path);
+        if modified.is_none() || modified == self.last_modified {
+            return false;
+        }
+        self.last_modified = modified;
+        true
+    }
+}
+
+fn modified_time(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path)
+        .and_then(|metadata| metadata.modified())
+        .ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+    use std::sync::Arc;
+
+    use glaphica_core::{
+        BrushInput, BrushInputFlags, CanvasVec2, MappedCursor, RadianVec2, StrokeId, TileKey,
+    };
+
+    use crate::brush_spec::BrushSpec;
+    use crate::config::{
+        BrushConfigKind, BrushConfigValue, BrushConfigValueError, BrushConfigValues,
+    };
+    use crate::draw_layout::BrushDrawInputShape;
+    use crate::engine_runtime::EngineBrushPipeline;
+
+    use super::{FileBrush, FileBrushDefinition, FileBrushError, validate_wgsl};
+
+    const EXAMPLE_MANIFEST: &str = include_str!("../../../assets/brushes/soft_square/brush.json");
+    const EXAMPLE_WGSL: &str = include_str!("../../../assets/brushes/soft_square/brush.wgsl");
+
+    fn parse_manifest(manifest_json: &str) -> Result<FileBrushDefinition, FileBrushError> {
+        FileBrushDefinition::from_sources(
+            manifest_json,
+            PathBuf::from("brush.wgsl"),
+            EXAMPLE_WGSL.to_string(),
+        )
+    }
+
+    fn build_input(center: CanvasVec2, pressure: f32) -> BrushInput {
+        BrushInput {
+            stroke: StrokeId(1),
+            cursor: MappedCursor {
+                cursor: center,
+                tilt: RadianVec2::new(0.0, 0.0),
+                pressure,
+                twist: 0.0,
+            },
+            flags: BrushInputFlags::empty(),
+            path_s: 0.0,
+            delta_s: 0.0,
+            dt_s: 0.0,
+            vel: CanvasVec2::new(0.0, 0.0),
+            speed: 0.0,
+            tangent: CanvasVec2::new(0.0, 0.0),
+            acc: CanvasVec2::new(0.0, 0.0),
+            accel: 0.0,
+            curvature: 0.0,
+            confidence: 1.0,
+        }
+    }
+
+    #[test]
+    fn example_manifest_maps_inputs_to_draw_lanes() {
+        let definition = parse_manifest(EXAMPLE_MANIFEST);
+        assert!(definition.is_ok());
+        let definition = match definition {
+            Ok(definition) => Arc::new(definition),
+            Err(_) => return,
+        };
+        assert_eq!(
+            definition.draw_input_layout().shape(),
+            &[
+                BrushDrawInputShape::Vec2F32,
+                BrushDrawInputShape::F32,
+                BrushDrawInputShape::F32
+            ]
+        );
+
+        let values = BrushConfigValues::defaults(definition.config_items())
+            .with("radius", BrushConfigValue::ScalarF32(10.0));
+        let brush = FileBrush::from_config_values(definition, &values);
+        assert!(brush.is_ok());
+        let mut brush = match brush {
+            Ok(brush) => brush,
+            Err(_) => return,
+        };
+
+        let encoded = brush.encode_draw_input(
+            &build_input(CanvasVec2::new(70.0, 20.0), 0.5),
+            TileKey::from_parts(0, 0, 0),
+            CanvasVec2::new(64.0, 0.0),
+        );
+        assert!(encoded.is_ok());
+        let encoded = match encoded {
+            Ok(encoded) => encoded,
+            Err(_) => return,
+        };
+        assert_eq!(encoded, vec![6.0, 20.0, 10.0, 0.5]);
+        assert_eq!(brush.max_affected_radius_px(), 10);
+    }
+
+    #[test]
+    fn config_values_must_match_manifest_items() {
+        let definition = parse_manifest(EXAMPLE_MANIFEST);
+        assert!(definition.is_ok());
+        let definition = match definition {
+            Ok(definition) => Arc::new(definition),
+            Err(_) => return,
+        };
+        let swapped = BrushConfigValues::defaults(definition.config_items())
+            .with("radius", BrushConfigValue::UnitIntervalCurve(Vec::new()));
+        assert!(matches!(
+            FileBrush::from_config_values(definition.clone(), &swapped),
+            Err(FileBrushError::Config(
+                BrushConfigValueError::TypeMismatch { key: "radius" }
+            ))
+        ));
+        assert!(matches!(
+            FileBrush::from_config_values(definition, &BrushConfigValues::new()),
+            Err(FileBrushError::Config(BrushConfigValueError::Missing {
+                key: "radius"
+            }))
+        ));
+    }
+
+    #[test]
+    fn manifest_rejects_unknown_sources_and_lane_count_mismatch() {
+        let unknown = EXAMPLE_MANIFEST.replace("input.local_x", "input.altitude");
+        assert!(matches!(
+            parse_manifest(&unknown),
+            Err(FileBrushError::InvalidManifest(_))
+        ));
+        let short = EXAMPLE_MANIFEST.replace(
+            "[\"input.local_x\", \"input.local_y\"]",
+            "[\"input.local_x\"]",
+        );
+        assert!(matches!(
+            parse_manifest(&short),
+            Err(FileBrushError::InvalidManifest(_))
+        ));
+    }
+
+    #[test]
+    fn manifest_choice_items_feed_config_lanes_as_numbers() {
+        let manifest = EXAMPLE_MANIFEST
+            .replace(
+                "\"config\": [",
+                "\"config\": [\n    { \"kind\": \"enum\", \"key\": \"shape\", \"options\": [\"Square\", \"Disc\"], \"default\": 1 },",
+            )
+            .replace("\"curve.pressure_opacity(pressure)\"", "\"config.shape\"");
+        let definition = parse_manifest(&manifest);
+        assert!(definition.is_ok());
+        let definition = match definition {
+            Ok(definition) => Arc::new(definition),
+            Err(_) => return,
+        };
+        assert_eq!(
+            definition.config_items()[0].kind,
+            BrushConfigKind::Enum {
+                options: &["Square", "Disc"]
+            }
+        );
+        let mut brush = FileBrush::new(definition);
+        let encoded = brush.encode_draw_input(
+            &build_input(CanvasVec2::new(4.0, 4.0), 1.0),
+            TileKey::from_parts(0, 0, 0),
+            CanvasVec2::new(0.0, 0.0),
+        );
+        assert_eq!(encoded.ok(), Some(vec![4.0, 4.0, 8.0, 1.0]));
+    }
+
+    #[test]
+    fn shader_validation_rejects_broken_wgsl_and_missing_entry_points() {
+        assert!(validate_wgsl(EXAMPLE_WGSL, "vs_main", "fs_main").is_ok());
+        assert!(matches!(
+            validate_wgsl("fn broken( {", "vs_main", "fs_main"),
+            Err(FileBrushError::ShaderParse(_))
+        ));
+        assert!(matches!(
+            validate_wgsl(EXAMPLE_WGSL, "vs_main", "fs_other"),
+            Err(FileBrushError::MissingEntryPoint { .. })
+        ));
+    }
+}
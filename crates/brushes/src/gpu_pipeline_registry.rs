@@ -1,3 +1,5 @@
+use std::borrow::Cow;
+
 use glaphica_core::BrushId;
 
 use crate::{BrushGpuPipelineSpec, BrushRegistry, BrushRegistryError};
@@ -28,11 +30,21 @@ impl BrushGpuPipelineRegistry {
         self.specs.ensure_can_register(brush_id)
     }
 
+    /// Swaps the shader of an already registered brush.
+    pub fn replace_wgsl_source(
+        &mut self,
+        brush_id: BrushId,
+        wgsl_source: Cow<'static, str>,
+    ) -> Result<(), BrushRegistryError> {
+        self.specs.get_mut(brush_id)?.wgsl_source = wgsl_source;
+        Ok(())
+    }
+
     pub fn pipeline_spec(
         &self,
         brush_id: BrushId,
     ) -> Result<BrushGpuPipelineSpec, BrushRegistryError> {
-        self.specs.get(brush_id).copied()
+        self.specs.get(brush_id).cloned()
     }
 }
 
@@ -49,13 +61,18 @@ mod tests {
         let mut registry = BrushGpuPipelineRegistry::new(4);
         let spec = BrushGpuPipelineSpec {
             label: "test",
-            wgsl_source: "@vertex fn vs_main(@builtin(vertex_index) idx:u32)->@builtin(position) vec4<f32>{ let p=array<vec2<f32>,3>(vec2<f32>(-1.0,-1.0),vec2<f32>(3.0,-1.0),vec2<f32>(-1.0,3.0)); let xy=p[idx]; return vec4<f32>(xy,0.0,1.0);} @fragment fn fs_main()->@location(0) vec4<f32>{ return vec4<f32>(1.0); }",
+            wgsl_source: "@vertex fn vs_main(@builtin(vertex_index) idx:u32)->@builtin(position) vec4<f32>{ let p=array<vec2<f32>,3>(vec2<f32>(-1.0,-1.0),vec2<f32>(3.0,-1.0),vec2<f32>(-1.0,3.0)); let xy=p[idx]; return vec4<f32>(xy,0.0,1.0);} @fragment fn fs_main()->@location(0) vec4<f32>{ return vec4<f32>(1.0); }".into(),
             vertex_entry: "vs_main",
             fragment_entry: "fs_main",
             uses_brush_cache_backend: false,
             cache_backend_format: None,
+            tip_image: None,
         };
-        assert!(registry.register_pipeline_spec(BrushId(2), spec).is_ok());
+        assert!(
+            registry
+                .register_pipeline_spec(BrushId(2), spec.clone())
+                .is_ok()
+        );
         assert_eq!(registry.pipeline_spec(BrushId(2)), Ok(spec));
     }
 }
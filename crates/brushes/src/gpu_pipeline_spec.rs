@@ -1,11 +1,20 @@
+use std::borrow::Cow;
+use std::sync::Arc;
+
 use glaphica_core::TextureFormat;
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+use crate::tip_image::BrushTipImage;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct BrushGpuPipelineSpec {
     pub label: &'static str,
-    pub wgsl_source: &'static str,
+    /// Borrowed for built-in brushes, owned for shaders loaded at runtime.
+    pub wgsl_source: Cow<'static, str>,
     pub vertex_entry: &'static str,
     pub fragment_entry: &'static str,
     pub uses_brush_cache_backend: bool,
     pub cache_backend_format: Option<TextureFormat>,
+    /// Grayscale tip the executor uploads into the brush cache backend as a
+    /// [`BrushTipSheet`](crate::BrushTipSheet) before the brush first draws.
+    pub tip_image: Option<Arc<BrushTipImage>>,
 }
@@ -4,26 +4,42 @@ mod brush_registry;
 
 pub mod brush_spec;
 pub mod builtin_brushes;
+pub mod color_dynamics;
 pub mod config;
 pub mod draw_layout;
+pub mod dynamics;
 pub mod engine_runtime;
+pub mod file_brush;
 pub mod gpu_pipeline_registry;
 pub mod gpu_pipeline_spec;
 pub mod layout_registry;
 pub mod resampler_distance;
+pub mod tip_image;
 
 pub use brush_registry::{BrushRegistry, BrushRegistryError};
 pub use brush_spec::{BrushSpec, BrushSpecRegisterError};
+pub use color_dynamics::{
+    ColorDynamics, ColorDynamicsConfigError, ColorDynamicsSampler, ColorMixSource,
+};
 pub use config::{
-    BrushConfigItem, BrushConfigKind, BrushConfigValue, UnitIntervalPoint,
-    eval_unit_interval_curve_polynomial,
+    BrushConfigItem, BrushConfigKind, BrushConfigValue, BrushConfigValueError, BrushConfigValues,
+    UnitIntervalPoint, eval_unit_interval_curve,
 };
 pub use draw_layout::{BrushDrawInputLayout, BrushDrawInputShape, BrushDrawKind};
+pub use dynamics::{
+    DabDynamics, DabDynamicsConfigError, DabDynamicsSeed, DabDynamicsTarget, DabVariation,
+    WithDabDynamics,
+};
 pub use engine_runtime::{
     BrushEngineRuntime, EngineBrushDispatchError, EngineBrushPipeline, StrokeDrawOutput,
     StrokeTileKey, TileSlotAllocator,
 };
+pub use file_brush::{
+    BrushInputField, FileBrush, FileBrushDefinition, FileBrushError, FileBrushSource,
+    ShaderFileWatcher, validate_wgsl,
+};
 pub use gpu_pipeline_registry::BrushGpuPipelineRegistry;
 pub use gpu_pipeline_spec::BrushGpuPipelineSpec;
 pub use layout_registry::BrushLayoutRegistry;
 pub use resampler_distance::{BrushResamplerDistance, BrushResamplerDistancePolicy};
+pub use tip_image::{BrushTipError, BrushTipImage, BrushTipSheet};
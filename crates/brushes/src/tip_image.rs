@@ -0,0 +1,328 @@
+use std::borrow::Cow;
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+use std::path::Path;
+
+use glaphica_core::{ATLAS_TILE_SIZE, GUTTER_SIZE, IMAGE_TILE_SIZE};
+
+/// Longest edge of the level a [`BrushTipSheet`] starts from. Stamps are at
+/// most 512 px, so finer levels would only ever be minified.
+const MAX_SHEET_TIP_EDGE_PX: u32 = 512;
+
+#[derive(Debug)]
+pub enum BrushTipError {
+    Io(std::io::Error),
+    PngDecode(png::DecodingError),
+    UnsupportedColorType(png::ColorType),
+    InvalidSize { width: u32, height: u32 },
+}
+
+impl Display for BrushTipError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Io(error) => write!(f, "brush tip io error: {error}"),
+            Self::PngDecode(error) => write!(f, "brush tip png decode error: {error}"),
+            Self::UnsupportedColorType(color_type) => {
+                write!(f, "brush tip png color type {color_type:?} is unsupported")
+            }
+            Self::InvalidSize { width, height } => {
+                write!(f, "brush tip size {width}x{height} is invalid")
+            }
+        }
+    }
+}
+
+impl Error for BrushTipError {}
+
+impl From<std::io::Error> for BrushTipError {
+    fn from(value: std::io::Error) -> Self {
+        Self::Io(value)
+    }
+}
+
+impl From<png::DecodingError> for BrushTipError {
+    fn from(value: png::DecodingError) -> Self {
+        Self::PngDecode(value)
+    }
+}
+
+/// Single-channel stamp coverage, row-major, one byte per texel.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BrushTipImage {
+    width: u32,
+    height: u32,
+    coverage: Vec<u8>,
+}
+
+impl BrushTipImage {
+    pub fn new(width: u32, height: u32, coverage: Vec<u8>) -> Result<Self, BrushTipError> {
+        if width == 0 || height == 0 || coverage.len() != width as usize * height as usize {
+            return Err(BrushTipError::InvalidSize { width, height });
+        }
+        Ok(Self {
+            width,
+            height,
+            coverage,
+        })
+    }
+
+    /// Decodes a PNG tip. Images with alpha use alpha as coverage; opaque
+    /// images are read as ink on paper, so black paints and white is empty.
+    pub fn read_png(path: &Path) -> Result<Self, BrushTipError> {
+        let file = std::fs::File::open(path)?;
+        let mut decoder = png::Decoder::new(std::io::BufReader::new(file));
+        decoder.set_transformations(png::Transformations::EXPAND | png::Transformations::STRIP_16);
+        let mut reader = decoder.read_info()?;
+        let mut buffer = vec![0; reader.output_buffer_size()];
+        let info = reader.next_frame(&mut buffer)?;
+        let bytes = &buffer[..info.buffer_size()];
+        let coverage = match info.color_type {
+            png::ColorType::Grayscale => bytes.iter().map(|luma| 255 - luma).collect(),
+            png::ColorType::GrayscaleAlpha => bytes.chunks_exact(2).map(|px| px[1]).collect(),
+            png::ColorType::Rgb => bytes
+                .chunks_exact(3)
+                .map(|px| 255 - luma_u8(px[0], px[1], px[2]))
+                .collect(),
+            png::ColorType::Rgba => bytes.chunks_exact(4).map(|px| px[3]).collect(),
+            color_type => return Err(BrushTipError::UnsupportedColorType(color_type)),
+        };
+        Self::new(info.width, info.height, coverage)
+    }
+
+    /// Round tip with a smooth falloff, used when no tip file is given.
+    pub fn soft_disc(edge_px: u32) -> Self {
+        let edge_px = edge_px.max(1);
+        let half = edge_px as f32 * 0.5;
+        let mut coverage = Vec::with_capacity(edge_px as usize * edge_px as usize);
+        for y in 0..edge_px {
+            for x in 0..edge_px {
+                let dx = (x as f32 + 0.5 - half) / half;
+                let dy = (y as f32 + 0.5 - half) / half;
+                let t = (1.0 - (dx * dx + dy * dy).sqrt()).clamp(0.0, 1.0);
+                let smooth = t * t * (3.0 - 2.0 * t);
+                coverage.push((smooth * 255.0).round() as u8);
+            }
+        }
+        Self {
+            width: edge_px,
+            height: edge_px,
+            coverage,
+        }
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    pub fn coverage(&self) -> &[u8] {
+        &self.coverage
+    }
+
+    /// Width over height.
+    pub fn aspect(&self) -> f32 {
+        self.width as f32 / self.height as f32
+    }
+
+    /// Box-filtered half-size level for the mip chain, or `None` at 1x1.
+    pub fn downsample(&self) -> Option<Self> {
+        if self.width == 1 && self.height == 1 {
+            return None;
+        }
+        let width = (self.width / 2).max(1);
+        let height = (self.height / 2).max(1);
+        let mut coverage = Vec::with_capacity(width as usize * height as usize);
+        for y in 0..height {
+            for x in 0..width {
+                let mut sum = 0u32;
+                let mut count = 0u32;
+                for sy in (y * 2)..(y * 2 + 2).min(self.height) {
+                    for sx in (x * 2)..(x * 2 + 2).min(self.width) {
+                        sum += u32::from(self.coverage[(sy * self.width + sx) as usize]);
+                        count += 1;
+                    }
+                }
+                coverage.push(((sum + count / 2) / count) as u8);
+            }
+        }
+        Some(Self {
+            width,
+            height,
+            coverage,
+        })
+    }
+
+    /// Size of the level a [`BrushTipSheet`] starts from.
+    pub fn sheet_base_size(&self) -> (u32, u32) {
+        let (mut width, mut height) = (self.width, self.height);
+        while width.max(height) > MAX_SHEET_TIP_EDGE_PX {
+            width = (width / 2).max(1);
+            height = (height / 2).max(1);
+        }
+        (width, height)
+    }
+
+    /// Packs the tip's mip chain, from [`Self::sheet_base_size`] down to
+    /// 1x1, into one sheet for the brush cache backend.
+    pub fn mip_sheet(&self) -> BrushTipSheet {
+        let mut base = Cow::Borrowed(self);
+        while base.width.max(base.height) > MAX_SHEET_TIP_EDGE_PX {
+            match base.downsample() {
+                Some(next) => base = Cow::Owned(next),
+                None => break,
+            }
+        }
+        let mut levels = vec![base.into_owned()];
+        while let Some(next) = levels.last().and_then(Self::downsample) {
+            levels.push(next);
+        }
+
+        let base = &levels[0];
+        let smaller = &levels[1..];
+        let width = base.width + smaller.first().map_or(0, |level| level.width);
+        let height = base
+            .height
+            .max(smaller.iter().map(|level| level.height).sum());
+        let mut coverage = vec![0; width as usize * height as usize];
+        let mut origin = (0, 0);
+        for (index, level) in levels.iter().enumerate() {
+            match index {
+                0 => {}
+                1 => origin = (base.width, 0),
+                _ => origin.1 += levels[index - 1].height,
+            }
+            for (row, texels) in level
+                .coverage
+                .chunks_exact(level.width as usize)
+                .enumerate()
+            {
+                let start = (origin.1 as usize + row) * width as usize + origin.0 as usize;
+                coverage[start..start + texels.len()].copy_from_slice(texels);
+            }
+        }
+        BrushTipSheet {
+            width,
+            height,
+            coverage,
+        }
+    }
+}
+
+/// A tip's mip chain packed into one sheet and cut into brush cache backend
+/// slots.
+///
+/// Level 0 sits at the origin, level 1 to its right, and every further level
+/// directly below the one before. The sheet is cut into `IMAGE_TILE_SIZE`
+/// tiles stored row by row in slots 0, 1, 2, ... of the brush's own cache
+/// backend; `stamp.wgsl` walks the same layout.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BrushTipSheet {
+    width: u32,
+    height: u32,
+    coverage: Vec<u8>,
+}
+
+impl BrushTipSheet {
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    pub fn tiles_x(&self) -> u32 {
+        self.width.div_ceil(IMAGE_TILE_SIZE)
+    }
+
+    pub fn slot_count(&self) -> u32 {
+        self.tiles_x() * self.height.div_ceil(IMAGE_TILE_SIZE)
+    }
+
+    /// `ATLAS_TILE_SIZE` squared texels for slot `index`, the content inset
+    /// by the gutter. Gutter texels and texels past the sheet stay empty.
+    pub fn slot_texels(&self, index: u32) -> Vec<u8> {
+        let tiles_x = self.tiles_x().max(1);
+        let x0 = (index % tiles_x) * IMAGE_TILE_SIZE;
+        let y0 = (index / tiles_x) * IMAGE_TILE_SIZE;
+        let width = IMAGE_TILE_SIZE.min(self.width.saturating_sub(x0)) as usize;
+        let height = IMAGE_TILE_SIZE.min(self.height.saturating_sub(y0));
+        let mut texels = vec![0; (ATLAS_TILE_SIZE * ATLAS_TILE_SIZE) as usize];
+        for row in 0..height {
+            let src = ((y0 + row) * self.width + x0) as usize;
+            let dst = ((row + GUTTER_SIZE) * ATLAS_TILE_SIZE + GUTTER_SIZE) as usize;
+            texels[dst..dst + width].copy_from_slice(&self.coverage[src..src + width]);
+        }
+        texels
+    }
+}
+
+fn luma_u8(r: u8, g: u8, b: u8) -> u8 {
+    ((u32::from(r) * 299 + u32::from(g) * 587 + u32::from(b) * 114 + 500) / 1000) as u8
+}
+
+#[cfg(test)]
+mod tests {
+    use glaphica_core::{ATLAS_TILE_SIZE, IMAGE_TILE_SIZE};
+
+    use super::BrushTipImage;
+
+    #[test]
+    fn downsample_box_filters_down_to_one_texel() {
+        let tip = BrushTipImage::new(2, 2, vec![0, 255, 255, 255]);
+        assert!(tip.is_ok());
+        let tip = match tip {
+            Ok(tip) => tip,
+            Err(_) => return,
+        };
+        let level = tip.downsample();
+        assert_eq!(
+            level.as_ref().map(|level| level.coverage()),
+            Some(&[191][..])
+        );
+        assert_eq!(level.and_then(|level| level.downsample()), None);
+    }
+
+    #[test]
+    fn mip_sheet_puts_smaller_levels_right_of_the_base_level() {
+        let tip = BrushTipImage::new(4, 2, (1..=8).collect());
+        assert!(tip.is_ok());
+        let tip = match tip {
+            Ok(tip) => tip,
+            Err(_) => return,
+        };
+        let sheet = tip.mip_sheet();
+        // Levels 4x2, 2x1 and 1x1: the two small ones stack at x = 4.
+        assert_eq!((sheet.width(), sheet.height()), (6, 2));
+        assert_eq!(&sheet.coverage[..6], &[1, 2, 3, 4, 4, 6]);
+        assert_eq!(&sheet.coverage[6..], &[5, 6, 7, 8, 5, 0]);
+
+        let slot = sheet.slot_texels(0);
+        let row = ATLAS_TILE_SIZE as usize;
+        assert_eq!(&slot[row..row + 8], &[0, 1, 2, 3, 4, 4, 6, 0]);
+        assert_eq!(slot[0], 0);
+    }
+
+    #[test]
+    fn mip_sheet_starts_from_a_level_a_stamp_can_use() {
+        let tip = BrushTipImage::soft_disc(1024);
+        assert_eq!(tip.sheet_base_size(), (512, 512));
+        let sheet = tip.mip_sheet();
+        assert_eq!((sheet.width(), sheet.height()), (768, 512));
+        assert_eq!(sheet.tiles_x(), 768u32.div_ceil(IMAGE_TILE_SIZE));
+        assert_eq!(
+            sheet.slot_count(),
+            sheet.tiles_x() * 512u32.div_ceil(IMAGE_TILE_SIZE)
+        );
+    }
+
+    #[test]
+    fn soft_disc_is_opaque_at_center_and_empty_at_corners() {
+        let tip = BrushTipImage::soft_disc(16);
+        assert!(tip.coverage()[8 * 16 + 8] > 200);
+        assert_eq!(tip.coverage()[0], 0);
+    }
+}
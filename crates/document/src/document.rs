@@ -24,8 +24,15 @@ pub struct Document {
     pub(crate) active_node: Option<NodeId>,
 }
 
+#[derive(Debug, Clone, PartialEq)]
 pub struct Metadata {
     name: String,
+    author: String,
+    created_at_ms: u64,
+    modified_at_ms: u64,
+    dpi: f32,
+    editing_time_ms: u64,
+    stroke_count: u64,
 }
 
 pub struct CanvasResizeResult {
@@ -33,13 +40,95 @@ pub struct CanvasResizeResult {
 }
 
 impl Metadata {
+    pub const DEFAULT_DPI: f32 = 72.0;
+
     pub fn new(name: String) -> Self {
-        Self { name }
+        Self {
+            name,
+            author: String::new(),
+            created_at_ms: 0,
+            modified_at_ms: 0,
+            dpi: Self::DEFAULT_DPI,
+            editing_time_ms: 0,
+            stroke_count: 0,
+        }
     }
 
     pub fn name(&self) -> &str {
         &self.name
     }
+
+    pub fn author(&self) -> &str {
+        &self.author
+    }
+
+    pub fn set_author(&mut self, author: String) {
+        self.author = author;
+    }
+
+    /// Unix time in milliseconds; zero when the document predates tracking.
+    pub fn created_at_ms(&self) -> u64 {
+        self.created_at_ms
+    }
+
+    pub fn modified_at_ms(&self) -> u64 {
+        self.modified_at_ms
+    }
+
+    /// Stamps a save: sets the modified time, and the created time if unset.
+    pub fn mark_saved(&mut self, now_ms: u64) {
+        if self.created_at_ms == 0 {
+            self.created_at_ms = now_ms;
+        }
+        self.modified_at_ms = now_ms;
+    }
+
+    pub fn dpi(&self) -> f32 {
+        self.dpi
+    }
+
+    pub fn set_dpi(&mut self, dpi: f32) {
+        if dpi.is_finite() && dpi > 0.0 {
+            self.dpi = dpi;
+        }
+    }
+
+    /// Canvas size in inches at the document DPI.
+    pub fn physical_size_inches(&self, layout: ImageLayout) -> (f32, f32) {
+        (
+            layout.size_x() as f32 / self.dpi,
+            layout.size_y() as f32 / self.dpi,
+        )
+    }
+
+    pub fn editing_time_ms(&self) -> u64 {
+        self.editing_time_ms
+    }
+
+    pub fn add_editing_time_ms(&mut self, elapsed_ms: u64) {
+        self.editing_time_ms = self.editing_time_ms.saturating_add(elapsed_ms);
+    }
+
+    pub fn stroke_count(&self) -> u64 {
+        self.stroke_count
+    }
+
+    pub fn record_stroke(&mut self) {
+        self.stroke_count = self.stroke_count.saturating_add(1);
+    }
+
+    pub(crate) fn restore_history(
+        &mut self,
+        created_at_ms: u64,
+        modified_at_ms: u64,
+        editing_time_ms: u64,
+        stroke_count: u64,
+    ) {
+        self.created_at_ms = created_at_ms;
+        self.modified_at_ms = modified_at_ms;
+        self.editing_time_ms = editing_time_ms;
+        self.stroke_count = stroke_count;
+    }
 }
 
 #[derive(Debug)]
@@ -112,7 +201,7 @@ impl Document {
         Ok(Self {
             layer_tree,
             layout,
-            metadata: Metadata { name },
+            metadata: Metadata::new(name),
             leaf_backend,
             render_cache_backend,
             next_node_id: NodeId(root_id.0 + 1),
@@ -146,7 +235,7 @@ impl Document {
         Ok(Self {
             layer_tree,
             layout,
-            metadata: Metadata { name },
+            metadata: Metadata::new(name),
             leaf_backend,
             render_cache_backend,
             next_node_id: NodeId(initial_id.0 + 1),
@@ -198,6 +287,10 @@ impl Document {
         &self.metadata
     }
 
+    pub fn metadata_mut(&mut self) -> &mut Metadata {
+        &mut self.metadata
+    }
+
     pub fn layer_tree(&self) -> &UiLayerTree {
         &self.layer_tree
     }
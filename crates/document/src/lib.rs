@@ -18,6 +18,7 @@ pub use shared_tree::{
 };
 pub use storage::{
     DocumentStorageError, DocumentStorageManifest, RasterLayerAssetMetadata,
-    RasterLayerExportRequest, StoredBranchBlendMode, StoredLayerNode, StoredLeafBlendMode,
+    RasterLayerExportRequest, StoredBranchBlendMode, StoredDocumentMetadata, StoredLayerNode,
+    StoredLeafBlendMode,
 };
 pub use view::View;
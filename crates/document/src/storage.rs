@@ -17,6 +17,10 @@ fn default_visible() -> bool {
     true
 }
 
+fn default_dpi() -> f32 {
+    Metadata::DEFAULT_DPI
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum DocumentStorageError {
     UnsupportedVersion {
@@ -50,6 +54,50 @@ pub struct DocumentStorageManifest {
     pub next_node_id: u64,
     pub next_layer_label_index: u64,
     pub next_group_label_index: u64,
+    #[serde(default)]
+    pub metadata: StoredDocumentMetadata,
+}
+
+/// Descriptive fields that older files may lack; all have defaults.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StoredDocumentMetadata {
+    #[serde(default)]
+    pub author: String,
+    #[serde(default)]
+    pub created_at_ms: u64,
+    #[serde(default)]
+    pub modified_at_ms: u64,
+    #[serde(default = "default_dpi")]
+    pub dpi: f32,
+    #[serde(default)]
+    pub editing_time_ms: u64,
+    #[serde(default)]
+    pub stroke_count: u64,
+}
+
+impl Default for StoredDocumentMetadata {
+    fn default() -> Self {
+        Self {
+            author: String::new(),
+            created_at_ms: 0,
+            modified_at_ms: 0,
+            dpi: default_dpi(),
+            editing_time_ms: 0,
+            stroke_count: 0,
+        }
+    }
+}
+
+impl StoredDocumentMetadata {
+    /// Canvas size in inches at the stored DPI.
+    pub fn physical_size_inches(&self, canvas_width: u32, canvas_height: u32) -> (f32, f32) {
+        let dpi = if self.dpi > 0.0 {
+            self.dpi
+        } else {
+            default_dpi()
+        };
+        (canvas_width as f32 / dpi, canvas_height as f32 / dpi)
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
@@ -125,6 +173,14 @@ impl Document {
             next_node_id: self.next_node_id.0,
             next_layer_label_index: self.next_layer_label_index,
             next_group_label_index: self.next_group_label_index,
+            metadata: StoredDocumentMetadata {
+                author: self.metadata.author().to_string(),
+                created_at_ms: self.metadata.created_at_ms(),
+                modified_at_ms: self.metadata.modified_at_ms(),
+                dpi: self.metadata.dpi(),
+                editing_time_ms: self.metadata.editing_time_ms(),
+                stroke_count: self.metadata.stroke_count(),
+            },
         }
     }
 
@@ -148,11 +204,21 @@ impl Document {
 
         let layout = ImageLayout::new(manifest.canvas_width, manifest.canvas_height);
         let root = import_layer_node(&manifest.root, layout, leaf_backend)?;
+        let stored = manifest.metadata;
+        let mut metadata = Metadata::new(manifest.name);
+        metadata.set_author(stored.author);
+        metadata.set_dpi(stored.dpi);
+        metadata.restore_history(
+            stored.created_at_ms,
+            stored.modified_at_ms,
+            stored.editing_time_ms,
+            stored.stroke_count,
+        );
 
         Ok(Document {
             layer_tree: UiLayerTree::new(root),
             layout,
-            metadata: Metadata::new(manifest.name),
+            metadata,
             leaf_backend,
             render_cache_backend,
             next_node_id: NodeId(manifest.next_node_id),
@@ -368,6 +434,11 @@ mod tests {
             BackendId::new(2),
         )
         .unwrap();
+        document.metadata_mut().set_author("Ada".to_string());
+        document.metadata_mut().set_dpi(300.0);
+        document.metadata_mut().mark_saved(1_700_000_000_000);
+        document.metadata_mut().add_editing_time_ms(90_000);
+        document.metadata_mut().record_stroke();
         document.create_group_above_active().unwrap();
         document
             .create_layer_above_active(NewLayerKind::SolidColor {
@@ -384,6 +455,7 @@ mod tests {
         .unwrap();
 
         assert_eq!(restored.metadata().name(), "storage");
+        assert_eq!(restored.metadata(), document.metadata());
         assert_eq!(restored.layout().size_x(), 128);
         assert_eq!(restored.layout().size_y(), 64);
         assert_eq!(restored.storage_manifest(), manifest);
@@ -422,4 +494,25 @@ mod tests {
             raster_assets
         );
     }
+
+    #[test]
+    fn manifest_without_metadata_uses_defaults() {
+        let document = Document::new(
+            "legacy".to_string(),
+            ImageLayout::new(300, 150),
+            BackendId::new(1),
+            BackendId::new(2),
+        )
+        .unwrap();
+        let mut json = serde_json::to_value(document.storage_manifest()).unwrap();
+        json.as_object_mut().unwrap().remove("metadata");
+
+        let manifest: super::DocumentStorageManifest = serde_json::from_value(json).unwrap();
+
+        assert_eq!(manifest.metadata, super::StoredDocumentMetadata::default());
+        assert_eq!(
+            manifest.metadata.physical_size_inches(300, 150),
+            (300.0 / 72.0, 150.0 / 72.0)
+        );
+    }
 }
@@ -1,10 +1,17 @@
-use crate::brush_ui::state::{BrushKind, PIXEL_RECT_BRUSH_ID, ROUND_BRUSH_ID};
+use crate::brush_ui::state::{
+    BrushKind, FILE_BRUSH_ID, PIXEL_RECT_BRUSH_ID, ROUND_BRUSH_ID, SMUDGE_BRUSH_ID, SPRAY_BRUSH_ID,
+    STAMP_BRUSH_ID,
+};
 use glaphica_core::BrushId;
 
 pub fn brush_id_to_kind(brush_id: BrushId) -> Option<BrushKind> {
     match brush_id {
         ROUND_BRUSH_ID => Some(BrushKind::Round),
         PIXEL_RECT_BRUSH_ID => Some(BrushKind::PixelRect),
+        STAMP_BRUSH_ID => Some(BrushKind::Stamp),
+        SMUDGE_BRUSH_ID => Some(BrushKind::Smudge),
+        SPRAY_BRUSH_ID => Some(BrushKind::Spray),
+        FILE_BRUSH_ID => Some(BrushKind::File),
         _ => None,
     }
 }
@@ -1,22 +1,43 @@
-use brushes::{BrushConfigItem, BrushConfigValue};
+use app::brush_presets::{BrushPreset, ResolvedPresetValues};
+use brushes::{BrushConfigItem, BrushConfigValue, BrushConfigValues};
 use glaphica_core::BrushId;
 
 pub const ROUND_BRUSH_ID: BrushId = BrushId(0);
 pub const PIXEL_RECT_BRUSH_ID: BrushId = BrushId(1);
+pub const FILE_BRUSH_ID: BrushId = BrushId(2);
+pub const STAMP_BRUSH_ID: BrushId = BrushId(3);
+pub const SMUDGE_BRUSH_ID: BrushId = BrushId(4);
+pub const SPRAY_BRUSH_ID: BrushId = BrushId(5);
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum BrushKind {
     Round,
     PixelRect,
+    Stamp,
+    Smudge,
+    Spray,
+    /// Brush loaded from a manifest with `--brush-file`.
+    File,
 }
 
 impl BrushKind {
-    pub const ALL: [Self; 2] = [Self::Round, Self::PixelRect];
+    pub const ALL: [Self; 6] = [
+        Self::Round,
+        Self::PixelRect,
+        Self::Stamp,
+        Self::Smudge,
+        Self::Spray,
+        Self::File,
+    ];
 
     pub const fn brush_id(self) -> BrushId {
         match self {
             Self::Round => ROUND_BRUSH_ID,
             Self::PixelRect => PIXEL_RECT_BRUSH_ID,
+            Self::Stamp => STAMP_BRUSH_ID,
+            Self::Smudge => SMUDGE_BRUSH_ID,
+            Self::Spray => SPRAY_BRUSH_ID,
+            Self::File => FILE_BRUSH_ID,
         }
     }
 
@@ -24,8 +45,16 @@ impl BrushKind {
         match self {
             Self::Round => "Round",
             Self::PixelRect => "PixelRect",
+            Self::Stamp => "Stamp",
+            Self::Smudge => "Smudge",
+            Self::Spray => "Spray",
+            Self::File => "File",
         }
     }
+
+    pub fn from_label(label: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|kind| kind.label() == label)
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -66,4 +95,41 @@ impl BrushUiState {
         }
         self.dirty = true;
     }
+
+    /// Current values keyed by item, as brushes read them.
+    pub fn config_values(&self) -> BrushConfigValues {
+        BrushConfigValues::from_items(&self.items, &self.values)
+    }
+
+    pub fn capture_preset(&self, name: String, color_rgb: [f32; 3]) -> BrushPreset {
+        BrushPreset::capture(
+            name,
+            self.kind.label(),
+            color_rgb,
+            self.eraser,
+            &self.items,
+            &self.values,
+        )
+    }
+
+    /// Loads preset values by key; items the preset changed from their
+    /// defaults are made visible so the change shows in the panel.
+    pub fn apply_preset(&mut self, preset: &BrushPreset) -> ResolvedPresetValues {
+        let resolved = preset.resolve_values(&self.items);
+        for ((item, value), visible) in self
+            .items
+            .iter()
+            .zip(&resolved.values)
+            .zip(self.visible.iter_mut())
+        {
+            if *value != item.default_value {
+                *visible = true;
+            }
+        }
+        self.values = resolved.values.clone();
+        self.color_rgb = preset.color_rgb;
+        self.eraser = preset.eraser;
+        self.dirty = true;
+        resolved
+    }
 }
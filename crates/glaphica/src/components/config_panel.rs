@@ -1,9 +1,13 @@
 use crate::brush_ui::state::{BrushKind, BrushUiState};
 use crate::theme::Theme;
 use brushes::{
-    BrushConfigKind, BrushConfigValue, UnitIntervalPoint, eval_unit_interval_curve_polynomial,
+    BrushConfigKind, BrushConfigValue, BrushConfigValues, UnitIntervalPoint,
+    eval_unit_interval_curve,
 };
 use egui::{Color32, Frame, Rect, Sense, Shape, SidePanel, Stroke, vec2};
+use stroke_input::{
+    SmoothingConfig, SmoothingKind, StrokeTaper, SyntheticPressure, VelocityPressureConfig,
+};
 
 pub const RIGHT_PANEL_COMPACT_WIDTH: f32 = 160.0;
 const RIGHT_PANEL_DRAG_MIN_WIDTH: f32 = 28.0;
@@ -13,9 +17,103 @@ pub struct ConfigPanel<'a> {
     collapsed: bool,
     width: f32,
     max_width: f32,
-    active_color_rgb: &'a mut [f32; 3],
+    colors: &'a mut BrushColors,
     brush_states: &'a mut [BrushUiState],
     selected_brush_index: usize,
+    presets: &'a mut BrushPresetPicker,
+    stabilizer: Option<&'a mut StabilizerSettings>,
+    stroke_ends: Option<&'a mut StrokeEndSettings>,
+    calibration: Option<&'a mut CalibrationSettings>,
+}
+
+/// Global stroke stabilizer: which smoothing strategy and how strongly.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct StabilizerSettings {
+    pub kind: SmoothingKind,
+    pub strength: f32,
+}
+
+impl StabilizerSettings {
+    pub fn smoothing_config(&self) -> SmoothingConfig {
+        SmoothingConfig::from_strength(self.kind, self.strength)
+    }
+}
+
+impl Default for StabilizerSettings {
+    /// Matches the smoothing the engine starts with.
+    fn default() -> Self {
+        Self {
+            kind: SmoothingKind::ExponentialMovingAverage,
+            strength: 0.7,
+        }
+    }
+}
+
+/// Freehand taper lengths in canvas px, and whether mouse strokes take their
+/// pressure from speed.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct StrokeEndSettings {
+    pub taper_in: f32,
+    pub taper_out: f32,
+    pub mouse_pressure_from_speed: bool,
+}
+
+impl StrokeEndSettings {
+    pub fn taper(&self) -> StrokeTaper {
+        StrokeTaper::new(self.taper_in, self.taper_out)
+    }
+
+    pub fn synthetic_pressure(&self) -> SyntheticPressure {
+        if self.mouse_pressure_from_speed {
+            SyntheticPressure::Velocity(VelocityPressureConfig::default())
+        } else {
+            SyntheticPressure::Off
+        }
+    }
+}
+
+/// Pressure calibration controls; the curves themselves live in the user
+/// preferences.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct CalibrationSettings {
+    /// The next freehand stroke is recorded to fit its device's curve.
+    pub recording: bool,
+    pub pen_tilt_deadzone_deg: f32,
+    /// Outcome of the last recording, shown under the buttons.
+    pub status: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CalibrationAction {
+    StartRecording,
+    CancelRecording,
+    Reset,
+    PenTiltDeadzone(f32),
+}
+
+/// Foreground paints by default; brush color dynamics mix toward the background.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BrushColors {
+    pub foreground_rgb: [f32; 3],
+    pub background_rgb: [f32; 3],
+}
+
+impl Default for BrushColors {
+    fn default() -> Self {
+        Self {
+            foreground_rgb: [1.0, 0.0, 0.0],
+            background_rgb: [1.0, 1.0, 1.0],
+        }
+    }
+}
+
+/// Preset names known to the library plus the picker's edit state.
+#[derive(Debug, Default)]
+pub struct BrushPresetPicker {
+    /// `(brush label, preset name)` pairs for every preset in the library.
+    pub entries: Vec<(String, String)>,
+    pub selected: Option<String>,
+    pub name_input: String,
 }
 
 impl<'a> ConfigPanel<'a> {
@@ -23,20 +121,40 @@ impl<'a> ConfigPanel<'a> {
         collapsed: bool,
         width: f32,
         max_width: f32,
-        active_color_rgb: &'a mut [f32; 3],
+        colors: &'a mut BrushColors,
         brush_states: &'a mut [BrushUiState],
         selected_brush_index: usize,
+        presets: &'a mut BrushPresetPicker,
     ) -> Self {
         Self {
             collapsed,
             width,
             max_width,
-            active_color_rgb,
+            colors,
             brush_states,
             selected_brush_index,
+            presets,
+            stabilizer: None,
+            stroke_ends: None,
+            calibration: None,
         }
     }
 
+    pub fn with_stabilizer(mut self, stabilizer: &'a mut StabilizerSettings) -> Self {
+        self.stabilizer = Some(stabilizer);
+        self
+    }
+
+    pub fn with_calibration(mut self, calibration: &'a mut CalibrationSettings) -> Self {
+        self.calibration = Some(calibration);
+        self
+    }
+
+    pub fn with_stroke_ends(mut self, stroke_ends: &'a mut StrokeEndSettings) -> Self {
+        self.stroke_ends = Some(stroke_ends);
+        self
+    }
+
     pub fn render(&mut self, ctx: &egui::Context, theme: &Theme) -> ConfigPanelOutput {
         let mut output = ConfigPanelOutput::default();
         let panel_fill = translucent_panel_fill(theme);
@@ -137,16 +255,36 @@ impl<'a> ConfigPanel<'a> {
                 {
                     let previous = &mut self.brush_states[previous_index];
                     previous.dirty = false;
-                    output.pending_brush_update = Some((previous.kind, previous.values.clone()));
+                    output.pending_brush_update = Some((previous.kind, previous.config_values()));
                 }
 
-                render_color_section(ui, self.active_color_rgb, compact, theme);
+                render_color_section(ui, self.colors, compact, theme);
+                if let Some(stabilizer) = self.stabilizer.as_deref_mut() {
+                    output.stabilizer_changed =
+                        render_stabilizer_section(ui, stabilizer, compact, theme);
+                }
+                if let Some(stroke_ends) = self.stroke_ends.as_deref_mut() {
+                    output.stroke_ends_changed =
+                        render_stroke_ends_section(ui, stroke_ends, compact, theme);
+                }
+                if let Some(calibration) = self.calibration.as_deref_mut() {
+                    output.calibration_action =
+                        render_calibration_section(ui, calibration, compact, theme);
+                }
 
                 if let Some(brush_state) = self.brush_states.get_mut(self.selected_brush_index) {
                     ui.separator();
                     egui::ScrollArea::vertical()
                         .auto_shrink([false, true])
                         .show(ui, |ui| {
+                            render_preset_section(
+                                ui,
+                                brush_state.kind.label(),
+                                self.presets,
+                                theme,
+                                &mut output,
+                            );
+                            ui.add_space(4.0);
                             render_brush_params(ui, brush_state, theme, &mut output);
                             ui.add_space(8.0);
                             render_config_actions(ui, brush_state, &mut output);
@@ -160,18 +298,17 @@ impl<'a> ConfigPanel<'a> {
     }
 }
 
-fn render_color_section(
-    ui: &mut egui::Ui,
-    active_color_rgb: &mut [f32; 3],
-    compact: bool,
-    theme: &Theme,
-) {
+fn render_color_section(ui: &mut egui::Ui, colors: &mut BrushColors, compact: bool, theme: &Theme) {
     ui.group(|ui| {
         if compact {
             ui.horizontal(|ui| {
-                ui.menu_button(color_swatch_text(*active_color_rgb), |ui| {
-                    render_color_picker(ui, active_color_rgb);
+                ui.menu_button(color_swatch_text(colors.foreground_rgb), |ui| {
+                    render_color_picker(ui, &mut colors.foreground_rgb);
+                });
+                ui.menu_button(color_swatch_text(colors.background_rgb), |ui| {
+                    render_color_picker(ui, &mut colors.background_rgb);
                 });
+                render_color_swap_button(ui, colors);
             });
         } else {
             ui.horizontal(|ui| {
@@ -181,14 +318,166 @@ fn render_color_section(
                         .color(theme.text_color)
                         .strong(),
                 );
+                render_color_swap_button(ui, colors);
             });
             ui.add_space(4.0);
             ui.scope(|ui| {
                 ui.spacing_mut().slider_width = ui.available_width().max(48.0);
-                render_color_picker(ui, active_color_rgb);
+                render_color_picker(ui, &mut colors.foreground_rgb);
+            });
+            ui.add_space(4.0);
+            ui.horizontal(|ui| {
+                ui.label(
+                    egui::RichText::new("Background")
+                        .size(12.0)
+                        .color(theme.text_color),
+                );
+                ui.menu_button(color_swatch_text(colors.background_rgb), |ui| {
+                    render_color_picker(ui, &mut colors.background_rgb);
+                });
+            });
+        }
+    });
+}
+
+fn render_stabilizer_section(
+    ui: &mut egui::Ui,
+    stabilizer: &mut StabilizerSettings,
+    compact: bool,
+    theme: &Theme,
+) -> bool {
+    let mut changed = false;
+    ui.group(|ui| {
+        if !compact {
+            ui.label(
+                egui::RichText::new("Stabilizer")
+                    .size(12.0)
+                    .color(theme.text_color)
+                    .strong(),
+            );
+        }
+        egui::ComboBox::from_id_salt("stabilizer-kind")
+            .selected_text(stabilizer.kind.label())
+            .show_ui(ui, |ui| {
+                for kind in SmoothingKind::ALL {
+                    changed |= ui
+                        .selectable_value(&mut stabilizer.kind, kind, kind.label())
+                        .changed();
+                }
             });
+        if stabilizer.kind != SmoothingKind::None {
+            changed |= ui
+                .add(egui::Slider::new(&mut stabilizer.strength, 0.0..=1.0).text("Strength"))
+                .changed();
+        }
+    });
+    changed
+}
+
+fn render_stroke_ends_section(
+    ui: &mut egui::Ui,
+    stroke_ends: &mut StrokeEndSettings,
+    compact: bool,
+    theme: &Theme,
+) -> bool {
+    let mut changed = false;
+    ui.group(|ui| {
+        if !compact {
+            ui.label(
+                egui::RichText::new("Stroke ends")
+                    .size(12.0)
+                    .color(theme.text_color)
+                    .strong(),
+            );
+        }
+        ui.horizontal(|ui| {
+            changed |= ui
+                .add(
+                    egui::DragValue::new(&mut stroke_ends.taper_in)
+                        .range(0.0..=500.0)
+                        .speed(1.0)
+                        .prefix("In "),
+                )
+                .changed();
+            changed |= ui
+                .add(
+                    egui::DragValue::new(&mut stroke_ends.taper_out)
+                        .range(0.0..=500.0)
+                        .speed(1.0)
+                        .prefix("Out "),
+                )
+                .changed();
+        });
+        changed |= ui
+            .checkbox(
+                &mut stroke_ends.mouse_pressure_from_speed,
+                "Mouse pressure from speed",
+            )
+            .changed();
+    });
+    changed
+}
+
+fn render_calibration_section(
+    ui: &mut egui::Ui,
+    calibration: &mut CalibrationSettings,
+    compact: bool,
+    theme: &Theme,
+) -> Option<CalibrationAction> {
+    let mut action = None;
+    ui.group(|ui| {
+        if !compact {
+            ui.label(
+                egui::RichText::new("Pressure calibration")
+                    .size(12.0)
+                    .color(theme.text_color)
+                    .strong(),
+            );
+        }
+        ui.horizontal(|ui| {
+            if calibration.recording {
+                if ui.button("Cancel").clicked() {
+                    action = Some(CalibrationAction::CancelRecording);
+                }
+                ui.label("Draw a stroke");
+            } else {
+                if ui.button("Record stroke").clicked() {
+                    action = Some(CalibrationAction::StartRecording);
+                }
+                if ui.button("Reset").clicked() {
+                    action = Some(CalibrationAction::Reset);
+                }
+            }
+        });
+        if ui
+            .add(
+                egui::DragValue::new(&mut calibration.pen_tilt_deadzone_deg)
+                    .range(0.0..=45.0)
+                    .speed(0.5)
+                    .prefix("Tilt dead zone ")
+                    .suffix("°"),
+            )
+            .changed()
+        {
+            action = Some(CalibrationAction::PenTiltDeadzone(
+                calibration.pen_tilt_deadzone_deg.to_radians(),
+            ));
+        }
+        if let Some(status) = &calibration.status {
+            ui.label(
+                egui::RichText::new(status)
+                    .size(11.0)
+                    .color(theme.text_color),
+            );
         }
     });
+    action
+}
+
+fn render_color_swap_button(ui: &mut egui::Ui, colors: &mut BrushColors) {
+    if ui.small_button("Swap").clicked() {
+        std::mem::swap(&mut colors.foreground_rgb, &mut colors.background_rgb);
+    }
 }
 
 fn render_color_picker(ui: &mut egui::Ui, color_rgb: &mut [f32; 3]) {
@@ -227,10 +516,101 @@ fn translucent_panel_fill(theme: &Theme) -> Color32 {
 #[derive(Default)]
 pub struct ConfigPanelOutput {
     pub toggle_collapse: bool,
-    pub pending_brush_update: Option<(BrushKind, Vec<BrushConfigValue>)>,
+    pub stabilizer_changed: bool,
+    pub stroke_ends_changed: bool,
+    pub calibration_action: Option<CalibrationAction>,
+    pub pending_brush_update: Option<(BrushKind, BrushConfigValues)>,
     pub brush_selection_changed: bool,
     pub new_selected_index: Option<usize>,
     pub panel_rect: Option<Rect>,
+    pub preset_selected: Option<String>,
+    pub preset_save_requested: Option<String>,
+    pub preset_delete_requested: Option<String>,
+    pub preset_import_clicked: bool,
+    pub preset_export_clicked: bool,
+}
+
+fn render_preset_section(
+    ui: &mut egui::Ui,
+    brush_label: &str,
+    presets: &mut BrushPresetPicker,
+    theme: &Theme,
+    output: &mut ConfigPanelOutput,
+) {
+    ui.group(|ui| {
+        ui.label(
+            egui::RichText::new("Presets")
+                .size(12.0)
+                .color(theme.text_color)
+                .strong(),
+        );
+        ui.add_space(4.0);
+
+        let names = presets
+            .entries
+            .iter()
+            .filter(|(brush, _)| brush == brush_label)
+            .map(|(_, name)| name.as_str())
+            .collect::<Vec<_>>();
+        if presets
+            .selected
+            .as_deref()
+            .is_some_and(|selected| !names.contains(&selected))
+        {
+            presets.selected = None;
+        }
+        egui::ComboBox::from_id_salt("brush-preset-picker")
+            .width(ui.available_width())
+            .selected_text(presets.selected.as_deref().unwrap_or("Choose preset"))
+            .show_ui(ui, |ui| {
+                for name in &names {
+                    if ui
+                        .selectable_label(presets.selected.as_deref() == Some(*name), *name)
+                        .clicked()
+                    {
+                        output.preset_selected = Some(name.to_string());
+                    }
+                }
+            });
+        if let Some(name) = &output.preset_selected {
+            presets.selected = Some(name.clone());
+            presets.name_input = name.clone();
+        }
+
+        ui.horizontal(|ui| {
+            ui.add(
+                egui::TextEdit::singleline(&mut presets.name_input)
+                    .hint_text("Preset name")
+                    .desired_width((ui.available_width() - 48.0).max(48.0)),
+            );
+            let name = presets.name_input.trim();
+            if ui
+                .add_enabled(!name.is_empty(), egui::Button::new("Save"))
+                .clicked()
+            {
+                output.preset_save_requested = Some(name.to_string());
+            }
+        });
+
+        ui.horizontal(|ui| {
+            let has_selection = presets.selected.is_some();
+            if ui
+                .add_enabled(has_selection, egui::Button::new("Delete"))
+                .clicked()
+            {
+                output.preset_delete_requested = presets.selected.take();
+            }
+            if ui.button("Import").clicked() {
+                output.preset_import_clicked = true;
+            }
+            if ui
+                .add_enabled(has_selection, egui::Button::new("Export"))
+                .clicked()
+            {
+                output.preset_export_clicked = true;
+            }
+        });
+    });
 }
 
 fn render_brush_params(
@@ -257,18 +637,45 @@ fn render_brush_params(
 
             let item = &brush_state.items[index];
             let item_key = item.key;
+            let item_label = item.label;
             let item_kind = item.kind.clone();
+            let dirty = &mut brush_state.dirty;
 
             match (&item_kind, &mut brush_state.values[index]) {
                 (BrushConfigKind::ScalarF32 { min, max }, BrushConfigValue::ScalarF32(current)) => {
-                    render_scalar_config(ui, item_key, current, *min, *max, &mut brush_state.dirty);
+                    render_scalar_config(ui, item_key, current, *min, *max, "", dirty);
+                }
+                (BrushConfigKind::Integer { min, max }, BrushConfigValue::Integer(current)) => {
+                    render_scalar_config(ui, item_key, current, *min, *max, "", dirty);
+                }
+                (BrushConfigKind::AngleDeg { min, max }, BrushConfigValue::AngleDeg(current)) => {
+                    render_scalar_config(ui, item_key, current, *min, *max, "°", dirty);
+                }
+                (BrushConfigKind::Bool, BrushConfigValue::Bool(current)) => {
+                    if ui.checkbox(current, item_label).changed() {
+                        *dirty = true;
+                    }
+                }
+                (BrushConfigKind::Enum { options }, BrushConfigValue::Enum(current)) => {
+                    render_enum_config(ui, item_key, item_label, options, current, dirty);
+                }
+                (BrushConfigKind::ColorRgb, BrushConfigValue::ColorRgb(rgb)) => {
+                    ui.horizontal(|ui| {
+                        if ui.color_edit_button_rgb(rgb).changed() {
+                            *dirty = true;
+                        }
+                        ui.label(item_label);
+                    });
+                }
+                (BrushConfigKind::TextureRef, BrushConfigValue::TextureRef(name)) => {
+                    render_texture_config(ui, item_key, item_label, name, dirty);
                 }
                 (
                     BrushConfigKind::UnitIntervalCurve,
                     BrushConfigValue::UnitIntervalCurve(points),
                 ) => {
                     ui.add_space(4.0);
-                    render_curve_config(ui, item_key, points, &mut brush_state.dirty, theme);
+                    render_curve_config(ui, item_key, points, dirty, theme);
                 }
                 _ => {
                     ui.colored_label(theme.error_color, "Config type mismatch");
@@ -313,17 +720,18 @@ fn render_config_actions(
     });
 }
 
-fn render_scalar_config(
+fn render_scalar_config<T: egui::emath::Numeric>(
     ui: &mut egui::Ui,
     key: &'static str,
-    value: &mut f32,
-    min: f32,
-    max: f32,
+    value: &mut T,
+    min: T,
+    max: T,
+    suffix: &str,
     dirty: &mut bool,
 ) {
     const VALUE_FIELD_WIDTH: f32 = 72.0;
     let available_width = ui.available_width();
-    let drag_speed = (max - min) * 0.01;
+    let drag_speed = (max.to_f64() - min.to_f64()) * 0.01;
     let minimum_slider_width = ui.spacing().interact_size.x;
     let slider_width = (available_width - VALUE_FIELD_WIDTH - ui.spacing().item_spacing.x).max(0.0);
     let is_compact =
@@ -338,7 +746,8 @@ fn render_scalar_config(
                     .add(
                         egui::DragValue::new(value)
                             .speed(drag_speed)
-                            .range(min..=max),
+                            .range(min..=max)
+                            .suffix(suffix),
                     )
                     .changed()
                 {
@@ -359,7 +768,8 @@ fn render_scalar_config(
                         [VALUE_FIELD_WIDTH, 0.0],
                         egui::DragValue::new(value)
                             .speed(drag_speed)
-                            .range(min..=max),
+                            .range(min..=max)
+                            .suffix(suffix),
                     )
                     .changed();
 
@@ -371,6 +781,48 @@ fn render_scalar_config(
     });
 }
 
+fn render_enum_config(
+    ui: &mut egui::Ui,
+    key: &'static str,
+    label: &'static str,
+    options: &[&'static str],
+    current: &mut usize,
+    dirty: &mut bool,
+) {
+    let selected_text = options.get(*current).copied().unwrap_or("");
+    egui::ComboBox::new(key, label)
+        .selected_text(selected_text)
+        .show_ui(ui, |ui| {
+            for (index, option) in options.iter().enumerate() {
+                if ui.selectable_value(current, index, *option).changed() {
+                    *dirty = true;
+                }
+            }
+        });
+}
+
+/// Texture names are committed when the field loses focus, so the app does
+/// not try to load every partial path while typing.
+fn render_texture_config(
+    ui: &mut egui::Ui,
+    key: &'static str,
+    label: &'static str,
+    name: &mut String,
+    dirty: &mut bool,
+) {
+    ui.push_id(key, |ui| {
+        ui.label(label);
+        let response = ui.add(
+            egui::TextEdit::singleline(name)
+                .hint_text("Default")
+                .desired_width(ui.available_width()),
+        );
+        if response.lost_focus() {
+            *dirty = true;
+        }
+    });
+}
+
 fn render_curve_config(
     ui: &mut egui::Ui,
     key: &'static str,
@@ -463,12 +915,14 @@ fn paint_curve_editor(
         );
     }
 
-    let mut curve = Vec::with_capacity(65);
-    for step in 0..=64 {
-        let x = step as f32 / 64.0;
-        let y = eval_unit_interval_curve_polynomial(points, x).unwrap_or(0.0);
-        curve.push(curve_pos(rect, x, y));
-    }
+    // Sample each segment on its own so the line bends exactly at the points.
+    let mut xs: Vec<f32> = (0..=64).map(|step| step as f32 / 64.0).collect();
+    xs.extend(points.iter().map(|point| point.x));
+    xs.sort_by(f32::total_cmp);
+    let curve = xs
+        .into_iter()
+        .map(|x| curve_pos(rect, x, eval_unit_interval_curve(points, x).unwrap_or(0.0)))
+        .collect::<Vec<_>>();
     painter.add(Shape::line(curve, Stroke::new(2.0, theme.curve_line)));
 
     for point in points {
@@ -0,0 +1,235 @@
+use crate::theme::Theme;
+use app::image_export::{
+    ExportBackground, ExportBitDepth, ExportFormat, ExportOptions, ExportRect,
+};
+use app::layer_batch_export::{
+    DEFAULT_NAME_TEMPLATE, LayerBatchBounds, LayerBatchExportOptions, LayerBatchScope,
+};
+use egui::{DragValue, RichText};
+use glaphica_core::NodeId;
+
+#[derive(Clone)]
+pub struct ExportOptionsForm {
+    pub format: ExportFormat,
+    pub quality: u8,
+    pub bit_depth: ExportBitDepth,
+    pub scale: f32,
+    pub crop_enabled: bool,
+    pub crop: ExportRect,
+    pub fill_background: bool,
+    pub background_rgb: [f32; 3],
+    pub selected_node_only: bool,
+}
+
+impl Default for ExportOptionsForm {
+    fn default() -> Self {
+        Self {
+            format: ExportFormat::Png,
+            quality: 90,
+            bit_depth: ExportBitDepth::Eight,
+            scale: 1.0,
+            crop_enabled: false,
+            crop: ExportRect {
+                x: 0,
+                y: 0,
+                width: 0,
+                height: 0,
+            },
+            fill_background: false,
+            background_rgb: [1.0, 1.0, 1.0],
+            selected_node_only: false,
+        }
+    }
+}
+
+impl ExportOptionsForm {
+    pub fn to_options(&self, selected_node: Option<NodeId>) -> ExportOptions {
+        ExportOptions {
+            format: self.format,
+            quality: self.quality,
+            bit_depth: self.bit_depth,
+            scale: self.scale,
+            crop: self.crop_enabled.then_some(self.crop),
+            background: if self.fill_background {
+                ExportBackground::Fill(self.background_rgb)
+            } else {
+                ExportBackground::Transparent
+            },
+            node_id: if self.selected_node_only {
+                selected_node
+            } else {
+                None
+            },
+        }
+    }
+
+    /// Renders the option widgets and returns true when the format changed.
+    /// Batch exports pick their own bounds and sources, so `batch` hides the
+    /// crop and source rows.
+    pub fn render(
+        &mut self,
+        ui: &mut egui::Ui,
+        theme: &Theme,
+        document_size: (u32, u32),
+        batch: bool,
+    ) -> bool {
+        let previous_format = self.format;
+        egui::Grid::new("export-options-grid")
+            .num_columns(2)
+            .spacing([12.0, 6.0])
+            .show(ui, |ui| {
+                ui.label(RichText::new("Format").color(theme.text_color));
+                egui::ComboBox::from_id_salt("export-format")
+                    .selected_text(self.format.label())
+                    .show_ui(ui, |ui| {
+                        for format in ExportFormat::ALL {
+                            ui.selectable_value(&mut self.format, format, format.label());
+                        }
+                    });
+                ui.end_row();
+
+                if self.format.uses_quality() {
+                    ui.label(RichText::new("Quality").color(theme.text_color));
+                    ui.add(egui::Slider::new(&mut self.quality, 1..=100));
+                    ui.end_row();
+                }
+
+                if self.format.supports_sixteen_bit() {
+                    ui.label(RichText::new("Depth").color(theme.text_color));
+                    ui.horizontal(|ui| {
+                        for depth in [ExportBitDepth::Eight, ExportBitDepth::Sixteen] {
+                            ui.selectable_value(&mut self.bit_depth, depth, depth.label());
+                        }
+                    });
+                    ui.end_row();
+                }
+
+                ui.label(RichText::new("Scale").color(theme.text_color));
+                ui.add(
+                    DragValue::new(&mut self.scale)
+                        .speed(0.01)
+                        .range(0.01..=8.0)
+                        .suffix("x"),
+                );
+                ui.end_row();
+
+                ui.label(RichText::new("Background").color(theme.text_color));
+                ui.horizontal(|ui| {
+                    ui.checkbox(&mut self.fill_background, "Fill");
+                    if self.fill_background {
+                        ui.color_edit_button_rgb(&mut self.background_rgb);
+                    } else if !self.format.supports_alpha() {
+                        ui.label(RichText::new("white").color(theme.text_color));
+                    }
+                });
+                ui.end_row();
+
+                if batch {
+                    return;
+                }
+
+                ui.label(RichText::new("Crop").color(theme.text_color));
+                ui.horizontal(|ui| {
+                    if ui.checkbox(&mut self.crop_enabled, "").changed()
+                        && self.crop_enabled
+                        && (self.crop.width == 0 || self.crop.height == 0)
+                    {
+                        self.crop = ExportRect {
+                            x: 0,
+                            y: 0,
+                            width: document_size.0,
+                            height: document_size.1,
+                        };
+                    }
+                    if self.crop_enabled {
+                        ui.add(DragValue::new(&mut self.crop.x).prefix("x "));
+                        ui.add(DragValue::new(&mut self.crop.y).prefix("y "));
+                        ui.add(
+                            DragValue::new(&mut self.crop.width)
+                                .range(1..=u32::MAX)
+                                .prefix("w "),
+                        );
+                        ui.add(
+                            DragValue::new(&mut self.crop.height)
+                                .range(1..=u32::MAX)
+                                .prefix("h "),
+                        );
+                    }
+                });
+                ui.end_row();
+
+                ui.label(RichText::new("Source").color(theme.text_color));
+                ui.checkbox(&mut self.selected_node_only, "Selected layer only");
+                ui.end_row();
+            });
+        self.format != previous_format
+    }
+}
+
+#[derive(Clone)]
+pub struct LayerBatchForm {
+    pub scope: LayerBatchScope,
+    pub bounds: LayerBatchBounds,
+    pub include_hidden: bool,
+    pub name_template: String,
+}
+
+impl Default for LayerBatchForm {
+    fn default() -> Self {
+        Self {
+            scope: LayerBatchScope::Leaves,
+            bounds: LayerBatchBounds::Trimmed,
+            include_hidden: false,
+            name_template: DEFAULT_NAME_TEMPLATE.to_string(),
+        }
+    }
+}
+
+impl LayerBatchForm {
+    pub fn to_options(&self, image_form: &ExportOptionsForm) -> LayerBatchExportOptions {
+        let mut options = LayerBatchExportOptions::new(image_form.to_options(None));
+        options.scope = self.scope;
+        options.bounds = self.bounds;
+        options.include_hidden = self.include_hidden;
+        options.name_template = self.name_template.clone();
+        options
+    }
+
+    pub fn render(&mut self, ui: &mut egui::Ui, theme: &Theme) {
+        egui::Grid::new("layer-batch-grid")
+            .num_columns(2)
+            .spacing([12.0, 6.0])
+            .show(ui, |ui| {
+                ui.label(RichText::new("Layers").color(theme.text_color));
+                ui.horizontal(|ui| {
+                    ui.selectable_value(&mut self.scope, LayerBatchScope::Leaves, "Each layer");
+                    ui.selectable_value(
+                        &mut self.scope,
+                        LayerBatchScope::TopLevelGroups,
+                        "Top-level groups",
+                    );
+                });
+                ui.end_row();
+
+                ui.label(RichText::new("Bounds").color(theme.text_color));
+                ui.horizontal(|ui| {
+                    ui.selectable_value(&mut self.bounds, LayerBatchBounds::Trimmed, "Trimmed");
+                    ui.selectable_value(
+                        &mut self.bounds,
+                        LayerBatchBounds::FullCanvas,
+                        "Full canvas",
+                    );
+                });
+                ui.end_row();
+
+                ui.label(RichText::new("Names").color(theme.text_color))
+                    .on_hover_text("{label}, {index} or zero-padded {index:3}");
+                ui.add(egui::TextEdit::singleline(&mut self.name_template).desired_width(200.0));
+                ui.end_row();
+
+                ui.label(RichText::new("Hidden").color(theme.text_color));
+                ui.checkbox(&mut self.include_hidden, "Include hidden layers");
+                ui.end_row();
+            });
+    }
+}
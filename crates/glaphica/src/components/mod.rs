@@ -1,11 +1,16 @@
 mod config_panel;
+mod export_options;
 mod layer_tree;
 mod sidebar;
 mod status_bar;
 mod top_bar;
 
-pub use config_panel::ConfigPanel;
+pub use config_panel::{
+    BrushColors, BrushPresetPicker, CalibrationAction, CalibrationSettings, ConfigPanel,
+    StabilizerSettings, StrokeEndSettings,
+};
+pub use export_options::{ExportOptionsForm, LayerBatchForm};
 pub use layer_tree::{LayerTree, LayerTreeMove};
 pub use sidebar::Sidebar;
 pub use status_bar::StatusBar;
-pub use top_bar::TopBar;
+pub use top_bar::{StrokeTool, TopBar};
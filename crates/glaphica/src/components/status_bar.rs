@@ -1,44 +1,114 @@
 use crate::theme::Theme;
 use app::AppStats;
-use egui::{Align, Frame, Layout, RichText, TopBottomPanel};
+use egui::{Align, Button, Frame, Layout, RichText, TopBottomPanel};
 
 pub struct StatusBar;
 
 impl StatusBar {
-    pub fn render(ctx: &egui::Context, theme: &Theme, stats: Option<&AppStats>) {
+    pub fn render(
+        ctx: &egui::Context,
+        theme: &Theme,
+        stats: Option<&AppStats>,
+        document_status: Option<(&str, bool)>,
+    ) -> StatusBarOutput {
+        let mut output = StatusBarOutput::default();
         TopBottomPanel::bottom("overlay-bottom-bar")
             .exact_height(30.0)
             .frame(Frame::default().fill(theme.bg_color))
             .show(ctx, |ui| {
-                ui.with_layout(Layout::right_to_left(Align::Center), |ui| {
-                    let undo_text = stats
-                        .map(|stats| format!("Undo {}", stats.undo_stroke_count))
-                        .unwrap_or_else(|| "Undo -".to_owned());
-                    ui.label(
-                        RichText::new(undo_text)
-                            .color(theme.text_color)
-                            .monospace()
-                            .size(11.0),
-                    );
+                ui.horizontal_centered(|ui| {
+                    if ui
+                        .add(
+                            Button::new(RichText::new("Export").size(11.0))
+                                .fill(theme.input_bg_color),
+                        )
+                        .clicked()
+                    {
+                        output.export_clicked = true;
+                    }
+                    if let Some((text, is_error)) = document_status {
+                        let color = if is_error {
+                            theme.error_color
+                        } else {
+                            theme.text_color
+                        };
+                        ui.label(RichText::new(text).color(color).size(11.0));
+                    }
 
-                    if let Some(stats) = stats {
-                        for backend in stats.backend_tiles.iter().rev() {
+                    ui.with_layout(Layout::right_to_left(Align::Center), |ui| {
+                        let undo_text = stats
+                            .map(|stats| format!("Undo {}", stats.undo_stroke_count))
+                            .unwrap_or_else(|| "Undo -".to_owned());
+                        ui.label(
+                            RichText::new(undo_text)
+                                .color(theme.text_color)
+                                .monospace()
+                                .size(11.0),
+                        );
+
+                        if let Some(stats) = stats {
+                            ui.add_space(10.0);
+                            ui.label(
+                                RichText::new(format!("Q {}", stats.gpu_command_queue_depth))
+                                    .color(theme.text_color)
+                                    .monospace()
+                                    .size(11.0),
+                            );
                             ui.add_space(10.0);
                             ui.label(
                                 RichText::new(format!(
-                                    "B{} A:{} C:{} F:{}",
-                                    backend.backend_id.raw(),
-                                    backend.active,
-                                    backend.cached,
-                                    backend.free
+                                    "Eng {:.1}ms",
+                                    stats.engine_frame.total().as_secs_f64() * 1000.0
                                 ))
                                 .color(theme.text_color)
                                 .monospace()
                                 .size(11.0),
                             );
+                            let input = &stats.input;
+                            let input_color = if input.dropped_samples > 0 || input.behind {
+                                theme.error_color
+                            } else {
+                                theme.text_color
+                            };
+                            let mut input_text = format!(
+                                "In {} drop {}",
+                                input.pushed_samples, input.dropped_samples
+                            );
+                            if input.behind {
+                                input_text.push_str(&format!(" batch {}", input.batch_size));
+                            }
+                            ui.add_space(10.0);
+                            ui.label(
+                                RichText::new(input_text)
+                                    .color(input_color)
+                                    .monospace()
+                                    .size(11.0),
+                            );
+
+                            for backend in stats.backend_tiles.iter().rev() {
+                                ui.add_space(10.0);
+                                ui.label(
+                                    RichText::new(format!(
+                                        "B{} A:{} C:{} F:{}",
+                                        backend.backend_id.raw(),
+                                        backend.active,
+                                        backend.cached,
+                                        backend.free
+                                    ))
+                                    .color(theme.text_color)
+                                    .monospace()
+                                    .size(11.0),
+                                );
+                            }
                         }
-                    }
+                    });
                 });
             });
+        output
     }
 }
+
+#[derive(Default)]
+pub struct StatusBarOutput {
+    pub export_clicked: bool,
+}
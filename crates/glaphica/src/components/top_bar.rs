@@ -1,5 +1,34 @@
 use crate::theme::Theme;
-use egui::{Button, Frame, RichText, TopBottomPanel};
+use egui::{Button, ComboBox, DragValue, Frame, RichText, TopBottomPanel};
+use stroke_input::{MAX_RADIAL_WAYS, ShapeTaper, SymmetryMode};
+
+const SYMMETRY_OPTIONS: [(&str, SymmetryMode); 5] = [
+    ("Off", SymmetryMode::Off),
+    ("Vertical", SymmetryMode::Vertical),
+    ("Horizontal", SymmetryMode::Horizontal),
+    ("Both", SymmetryMode::Both),
+    ("Radial", SymmetryMode::Radial { ways: 6 }),
+];
+
+const STROKE_TOOL_OPTIONS: [(&str, StrokeTool); 5] = [
+    ("Freehand", StrokeTool::Freehand),
+    ("Line", StrokeTool::Line),
+    ("Rectangle", StrokeTool::Rectangle),
+    ("Ellipse", StrokeTool::Ellipse),
+    ("Polyline", StrokeTool::Polyline),
+];
+
+/// What a left-button gesture on the canvas strokes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum StrokeTool {
+    #[default]
+    Freehand,
+    Line,
+    Rectangle,
+    Ellipse,
+    /// Click to place vertices, right-click to stroke them.
+    Polyline,
+}
 
 pub struct TopBar;
 
@@ -13,6 +42,9 @@ impl TopBar {
         ctx: &egui::Context,
         theme: &Theme,
         canvas_crop_mode_active: bool,
+        symmetry_mode: &mut SymmetryMode,
+        stroke_tool: &mut StrokeTool,
+        shape_taper: &mut ShapeTaper,
     ) -> TopBarOutput {
         let mut output = TopBarOutput::default();
         TopBottomPanel::top("overlay-top-bar")
@@ -32,7 +64,11 @@ impl TopBar {
                     {
                         output.toggle_canvas_crop_mode = true;
                     }
-                    ui.add_space((ui.available_width() - 272.0).max(0.0));
+                    ui.add_space(12.0);
+                    output.symmetry_changed = render_symmetry_mode(ui, symmetry_mode);
+                    ui.add_space(12.0);
+                    render_stroke_tool(ui, stroke_tool, shape_taper);
+                    ui.add_space((ui.available_width() - 376.0).max(0.0));
                     if ui
                         .add(Button::new("Save").fill(theme.input_bg_color))
                         .clicked()
@@ -51,16 +87,86 @@ impl TopBar {
                     {
                         output.export_clicked = true;
                     }
+                    if ui
+                        .add(Button::new("Export Layers").fill(theme.input_bg_color))
+                        .clicked()
+                    {
+                        output.export_layers_clicked = true;
+                    }
                 });
             });
         output
     }
 }
 
+fn render_symmetry_mode(ui: &mut egui::Ui, symmetry_mode: &mut SymmetryMode) -> bool {
+    let selected_label = SYMMETRY_OPTIONS
+        .iter()
+        .find(|(_, mode)| std::mem::discriminant(mode) == std::mem::discriminant(symmetry_mode))
+        .map(|(label, _)| *label)
+        .unwrap_or("Off");
+    let mut changed = false;
+    ComboBox::from_id_salt("symmetry-mode")
+        .selected_text(format!("Symmetry: {selected_label}"))
+        .show_ui(ui, |ui| {
+            for (label, mode) in SYMMETRY_OPTIONS {
+                if ui
+                    .selectable_label(label == selected_label, label)
+                    .clicked()
+                    && label != selected_label
+                {
+                    *symmetry_mode = mode;
+                    changed = true;
+                }
+            }
+        });
+    if let SymmetryMode::Radial { ways } = symmetry_mode {
+        changed |= ui
+            .add(
+                DragValue::new(ways)
+                    .range(2..=MAX_RADIAL_WAYS)
+                    .suffix(" ways"),
+            )
+            .changed();
+    }
+    changed
+}
+
+fn render_stroke_tool(ui: &mut egui::Ui, stroke_tool: &mut StrokeTool, taper: &mut ShapeTaper) {
+    let selected_label = STROKE_TOOL_OPTIONS
+        .iter()
+        .find(|(_, tool)| tool == stroke_tool)
+        .map(|(label, _)| *label)
+        .unwrap_or("Freehand");
+    ComboBox::from_id_salt("stroke-tool")
+        .selected_text(format!("Tool: {selected_label}"))
+        .show_ui(ui, |ui| {
+            for (label, tool) in STROKE_TOOL_OPTIONS {
+                ui.selectable_value(stroke_tool, tool, label);
+            }
+        });
+    if *stroke_tool != StrokeTool::Freehand {
+        ui.add(
+            DragValue::new(&mut taper.start)
+                .range(0.0..=0.5)
+                .speed(0.01)
+                .prefix("Taper in "),
+        );
+        ui.add(
+            DragValue::new(&mut taper.end)
+                .range(0.0..=0.5)
+                .speed(0.01)
+                .prefix("Taper out "),
+        );
+    }
+}
+
 #[derive(Default)]
 pub struct TopBarOutput {
     pub toggle_canvas_crop_mode: bool,
+    pub symmetry_changed: bool,
     pub save_clicked: bool,
     pub load_clicked: bool,
     pub export_clicked: bool,
+    pub export_layers_clicked: bool,
 }
@@ -1,14 +1,33 @@
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::time::{Duration, Instant};
 
-use app::{AppThreadIntegration, trace::TraceRecorder};
-use brushes::builtin_brushes::{pixel_rect::PixelRectBrush, round::RoundBrush};
+use app::{
+    AppThreadIntegration, EngineThreadMode,
+    brush_presets::{BrushPreset, BrushPresetLibrary},
+    image_export::ExportOptions,
+    layer_batch_export::LayerBatchExportOptions,
+    preferences::UserPreferences,
+    recovery::RecoveryJournal,
+    trace::TraceRecorder,
+};
+use brushes::builtin_brushes::{
+    pixel_rect::PixelRectBrush, round::RoundBrush, smudge::SmudgeBrush, spray::SprayBrush,
+    stamp::StampBrush,
+};
+use brushes::{
+    BrushConfigItem, BrushConfigValues, BrushTipImage, ColorDynamics, DabDynamics, FileBrush,
+    FileBrushDefinition, WithDabDynamics,
+};
 use egui::Pos2;
-use glaphica_core::{EpochId, NodeId};
+use glaphica_core::{CanvasVec2, EpochId, InputDeviceKind, NodeId};
 use gpu_runtime::{GpuContext, GpuContextInitDescriptor, surface_runtime::SurfaceRuntime};
 use images::layout::ImageLayout;
+use stroke_input::{
+    DeviceCalibrations, PressureCurve, SHAPE_SNAP_ANGLE, SmoothingConfig, StrokeShape, StrokeTaper,
+    Symmetry, SyntheticPressure, TouchGestureRecognizer, snap_angle, square_corner,
+};
 use winit::{
     application::ApplicationHandler,
     event::WindowEvent,
@@ -16,9 +35,16 @@ use winit::{
     window::{Window, WindowId},
 };
 
-use crate::brush_ui::state::{BrushKind, BrushUiState, PIXEL_RECT_BRUSH_ID, ROUND_BRUSH_ID};
+use crate::brush_ui::state::{
+    BrushKind, BrushUiState, FILE_BRUSH_ID, PIXEL_RECT_BRUSH_ID, ROUND_BRUSH_ID, SMUDGE_BRUSH_ID,
+    SPRAY_BRUSH_ID, STAMP_BRUSH_ID,
+};
+use crate::components::{CalibrationAction, StrokeTool};
 use crate::input::{MouseInputResult, handle_window_event};
-use crate::overlay::{EguiOverlay, ExitConfirmAction, PathDialogAction, UiCommand};
+use crate::overlay::{
+    EguiOverlay, ExitConfirmAction, PathDialogAction, RecoveryPromptAction, SymmetryGuide,
+    UiCommand,
+};
 use crate::run_config::RunConfig;
 
 #[derive(Debug, Default)]
@@ -53,6 +79,8 @@ pub enum AppActionError {
     DocumentSave(PathBuf, String),
     DocumentLoad(PathBuf, String),
     DocumentExport(PathBuf, String),
+    Recovery(PathBuf, String),
+    BrushPreset(String),
 }
 
 impl std::fmt::Display for AppActionError {
@@ -82,6 +110,10 @@ impl std::fmt::Display for AppActionError {
             AppActionError::DocumentExport(path, e) => {
                 write!(f, "document export failed ({}): {}", path.display(), e)
             }
+            AppActionError::Recovery(path, e) => {
+                write!(f, "recovery failed ({}): {}", path.display(), e)
+            }
+            AppActionError::BrushPreset(e) => write!(f, "brush preset failed: {}", e),
         }
     }
 }
@@ -111,7 +143,19 @@ pub struct DesktopApp {
     pub(crate) shift_pressed: bool,
     pub(crate) active_brush_kind: BrushKind,
     pub(crate) brush_states: Vec<BrushUiState>,
+    pub(crate) brush_presets: Option<BrushPresetLibrary>,
+    pub(crate) stamp_tip: Option<Arc<BrushTipImage>>,
+    pub(crate) file_brush: Option<Arc<FileBrushDefinition>>,
     pub(crate) canvas_crop: CanvasCropState,
+    pub(crate) symmetry_center_drag: bool,
+    pub(crate) shape_draft: Option<ShapeDraft>,
+    pub(crate) touch_gestures: TouchGestureRecognizer,
+    pub(crate) preferences: Option<UserPreferences>,
+    /// Pressures of the stroke being recorded for calibration, with the
+    /// device that reported them.
+    pub(crate) calibration_samples: Option<Vec<(InputDeviceKind, f32)>>,
+    pub(crate) recovery_dir: Option<PathBuf>,
+    pub(crate) last_autosave_at: Option<Instant>,
 }
 
 #[derive(Default)]
@@ -120,6 +164,36 @@ pub struct CanvasCropState {
     pub(crate) preview_size: Option<(u32, u32)>,
 }
 
+/// Shape being dragged or clicked out, in document space, before it is
+/// stroked.
+pub struct ShapeDraft {
+    pub(crate) tool: StrokeTool,
+    pub(crate) points: Vec<CanvasVec2>,
+    pub(crate) cursor: CanvasVec2,
+}
+
+impl ShapeDraft {
+    /// The drafted shape; `with_cursor` lets a polyline rubber-band to the
+    /// cursor.
+    fn shape(&self, with_cursor: bool) -> Option<StrokeShape> {
+        let start = *self.points.first()?;
+        let end = self.cursor;
+        match self.tool {
+            StrokeTool::Freehand => None,
+            StrokeTool::Line => Some(StrokeShape::Line { start, end }),
+            StrokeTool::Rectangle => Some(StrokeShape::Rectangle { start, end }),
+            StrokeTool::Ellipse => Some(StrokeShape::Ellipse { start, end }),
+            StrokeTool::Polyline => {
+                let mut points = self.points.clone();
+                if with_cursor {
+                    points.push(end);
+                }
+                Some(StrokeShape::Polyline { points })
+            }
+        }
+    }
+}
+
 impl DesktopApp {
     pub fn new(run_config: RunConfig, sigint_flag: Arc<AtomicBool>) -> Self {
         Self {
@@ -145,7 +219,17 @@ impl DesktopApp {
             shift_pressed: false,
             active_brush_kind: BrushKind::Round,
             brush_states: Vec::new(),
+            brush_presets: None,
+            stamp_tip: None,
+            file_brush: None,
             canvas_crop: CanvasCropState::default(),
+            symmetry_center_drag: false,
+            shape_draft: None,
+            touch_gestures: TouchGestureRecognizer::new(),
+            preferences: None,
+            calibration_samples: None,
+            recovery_dir: None,
+            last_autosave_at: None,
         }
     }
 
@@ -232,6 +316,264 @@ impl DesktopApp {
         }
     }
 
+    pub fn symmetry_center_hit(&self, screen_position: (f32, f32)) -> bool {
+        let Some(handle_center) = self
+            .overlay
+            .as_ref()
+            .and_then(|overlay| overlay.symmetry_center_handle())
+        else {
+            return false;
+        };
+        handle_center.distance(Pos2::new(screen_position.0, screen_position.1)) <= 12.0
+    }
+
+    /// Drags the symmetry center to the cursor; returns whether it moved.
+    pub fn update_symmetry_center_drag(&mut self, screen_position: (f32, f32)) -> bool {
+        if !self.symmetry_center_drag {
+            return false;
+        }
+        let (Some(integration), Some(overlay)) = (self.integration.as_mut(), self.overlay.as_mut())
+        else {
+            return false;
+        };
+        let (doc_x, doc_y) =
+            integration.map_screen_to_document(screen_position.0, screen_position.1);
+        overlay.move_symmetry_center(CanvasVec2::new(doc_x, doc_y));
+        true
+    }
+
+    /// Opens a freehand stroke on the active paint node, first applying brush
+    /// edits still pending in the overlay so the stroke uses them.
+    pub(crate) fn begin_input_stroke(&mut self) {
+        let mut effect = ApplyActionsEffect::default();
+        if let Some(overlay) = &mut self.overlay {
+            overlay.flush_selected_brush_if_dirty();
+            let overlay_actions = overlay.take_pending_actions();
+            let report = self.apply_overlay_actions(overlay_actions);
+            for error in report.errors {
+                eprintln!("overlay action errors: {}", error);
+            }
+            effect.merge(report.effect);
+        }
+        if effect.advance_epoch {
+            self.advance_epoch();
+        }
+        self.render_frame();
+        if let Some(integration) = &mut self.integration
+            && let Some(node_id) = integration.active_paint_node()
+        {
+            integration.begin_stroke(node_id);
+        }
+        if let Some(window) = &self.window {
+            window.request_redraw();
+        }
+    }
+
+    /// Closes the freehand stroke, if one is open. Returns whether one was.
+    pub(crate) fn end_input_stroke(&mut self) -> bool {
+        let stroke_was_active = std::mem::take(&mut self.stroke_active);
+        if let Some(integration) = &mut self.integration {
+            integration.end_stroke();
+        }
+        if stroke_was_active {
+            if let Some(overlay) = &mut self.overlay {
+                overlay.mark_document_dirty();
+            }
+            self.finish_calibration_stroke();
+        }
+        stroke_was_active
+    }
+
+    /// Abandons the freehand stroke, if one is open, restoring what it drew
+    /// over. A calibration recording in progress is discarded with it.
+    pub(crate) fn cancel_input_stroke(&mut self) -> bool {
+        if !std::mem::take(&mut self.stroke_active) {
+            return false;
+        }
+        if let Some(integration) = &mut self.integration {
+            integration.cancel_stroke();
+        }
+        if self.calibration_samples.take().is_some()
+            && let Some(overlay) = &mut self.overlay
+        {
+            overlay.calibration.recording = false;
+            overlay.calibration.status = Some("Stroke cancelled".to_string());
+        }
+        if let Some(window) = &self.window {
+            window.request_redraw();
+        }
+        true
+    }
+
+    pub(crate) fn record_calibration_sample(&mut self, device: InputDeviceKind, pressure: f32) {
+        if let Some(samples) = &mut self.calibration_samples {
+            samples.push((device, pressure));
+        }
+    }
+
+    /// Fits the recorded device's pressure curve from the stroke that just
+    /// ended, then applies and saves it.
+    fn finish_calibration_stroke(&mut self) {
+        let Some(samples) = self.calibration_samples.take() else {
+            return;
+        };
+        let status = match samples.last().map(|(device, _)| *device) {
+            None => "Nothing recorded".to_string(),
+            Some(device) => {
+                let pressures = samples
+                    .iter()
+                    .filter(|(sample_device, _)| *sample_device == device)
+                    .map(|(_, pressure)| *pressure)
+                    .collect::<Vec<_>>();
+                match PressureCurve::fit(&pressures) {
+                    Some(curve) => {
+                        self.update_device_calibrations(|calibrations| {
+                            calibrations.get_mut(device).pressure = curve;
+                        });
+                        format!("Calibrated {}", device_label(device))
+                    }
+                    None => "Stroke too short or too even; try again".to_string(),
+                }
+            }
+        };
+        if let Some(overlay) = &mut self.overlay {
+            overlay.calibration.recording = false;
+            overlay.calibration.status = Some(status);
+        }
+    }
+
+    fn update_device_calibrations(&mut self, update: impl FnOnce(&mut DeviceCalibrations)) {
+        let path = self.preferences_path();
+        let Some(preferences) = &mut self.preferences else {
+            return;
+        };
+        let mut calibrations = preferences.device_calibrations();
+        update(&mut calibrations);
+        preferences.set_device_calibrations(calibrations);
+        if let Some(integration) = &mut self.integration {
+            integration.set_device_calibration(calibrations);
+        }
+        if let Err(error) = preferences.save(&path) {
+            eprintln!("Preferences save failed ({}): {}", path.display(), error);
+        }
+    }
+
+    fn preferences_path(&self) -> PathBuf {
+        self.run_config
+            .preferences_path
+            .clone()
+            .unwrap_or_else(UserPreferences::default_path)
+    }
+
+    pub fn stroke_tool(&self) -> StrokeTool {
+        self.overlay
+            .as_ref()
+            .map_or(StrokeTool::Freehand, |overlay| overlay.stroke_tool)
+    }
+
+    /// Starts a shape at the cursor, or places the next polyline vertex.
+    /// Returns whether a shape tool took the press.
+    pub fn press_shape_tool(&mut self, screen_position: (f32, f32)) -> bool {
+        let tool = self.stroke_tool();
+        if tool == StrokeTool::Freehand
+            || self
+                .integration
+                .as_ref()
+                .and_then(|integration| integration.active_paint_node())
+                .is_none()
+        {
+            return false;
+        }
+        let continues_polyline = tool == StrokeTool::Polyline
+            && self
+                .shape_draft
+                .as_ref()
+                .is_some_and(|draft| draft.tool == StrokeTool::Polyline);
+        if continues_polyline {
+            self.update_shape_draft(screen_position);
+            if let Some(draft) = self.shape_draft.as_mut() {
+                draft.points.push(draft.cursor);
+            }
+            return true;
+        }
+        let Some(integration) = self.integration.as_ref() else {
+            return false;
+        };
+        let (doc_x, doc_y) =
+            integration.map_screen_to_document(screen_position.0, screen_position.1);
+        let point = CanvasVec2::new(doc_x, doc_y);
+        self.shape_draft = Some(ShapeDraft {
+            tool,
+            points: vec![point],
+            cursor: point,
+        });
+        true
+    }
+
+    /// Moves the free end of the drafted shape to the cursor. Shift snaps
+    /// lines to 15 degree steps and keeps rectangles and ellipses square.
+    pub fn update_shape_draft(&mut self, screen_position: (f32, f32)) -> bool {
+        let (Some(draft), Some(integration)) = (self.shape_draft.as_mut(), &self.integration)
+        else {
+            return false;
+        };
+        let Some(&anchor) = draft.points.last() else {
+            return false;
+        };
+        let (doc_x, doc_y) =
+            integration.map_screen_to_document(screen_position.0, screen_position.1);
+        let point = CanvasVec2::new(doc_x, doc_y);
+        draft.cursor = match (self.shift_pressed, draft.tool) {
+            (true, StrokeTool::Line | StrokeTool::Polyline) => {
+                snap_angle(anchor, point, SHAPE_SNAP_ANGLE)
+            }
+            (true, StrokeTool::Rectangle | StrokeTool::Ellipse) => square_corner(anchor, point),
+            _ => point,
+        };
+        true
+    }
+
+    /// Ends a line, rectangle or ellipse drag by stroking it.
+    pub fn finish_shape_drag(&mut self) -> bool {
+        if self
+            .shape_draft
+            .as_ref()
+            .is_none_or(|draft| draft.tool == StrokeTool::Polyline)
+        {
+            return false;
+        }
+        self.commit_shape_draft()
+    }
+
+    /// Strokes the drafted shape with the active brush as one undo step.
+    pub fn commit_shape_draft(&mut self) -> bool {
+        let Some(draft) = self.shape_draft.take() else {
+            return false;
+        };
+        let Some(shape) = draft.shape(false) else {
+            return false;
+        };
+        if matches!(&shape, StrokeShape::Polyline { points } if points.len() < 2) {
+            return false;
+        }
+        let Some(integration) = self.integration.as_mut() else {
+            return false;
+        };
+        let Some(node_id) = integration.active_paint_node() else {
+            return false;
+        };
+        let taper = self
+            .overlay
+            .as_ref()
+            .map(|overlay| overlay.shape_taper)
+            .unwrap_or_default();
+        integration.draw_shape_stroke(node_id, shape, taper);
+        if let Some(overlay) = self.overlay.as_mut() {
+            overlay.mark_document_dirty();
+        }
+        true
+    }
+
     pub fn cancel_canvas_crop_interaction(&mut self) {
         self.canvas_crop.active_drag = false;
         self.canvas_crop.preview_size = None;
@@ -247,6 +589,7 @@ impl DesktopApp {
             integration.process_main_render();
             if let Some(overlay) = &mut self.overlay {
                 overlay.set_app_stats(integration.stats());
+                overlay.set_document_size(integration.document_size());
                 overlay.sync_layer_tree(
                     integration.layer_tree_items(),
                     integration.active_document_node(),
@@ -268,6 +611,28 @@ impl DesktopApp {
                     clear_canvas_crop = true;
                     overlay.set_canvas_crop_overlay(None, None, false);
                 }
+                let symmetry_guide = overlay.symmetry.is_active().then(|| {
+                    symmetry_guide_screen(
+                        integration,
+                        overlay.symmetry,
+                        integration.document_size(),
+                        self.symmetry_center_drag,
+                    )
+                });
+                overlay.set_symmetry_guide(symmetry_guide);
+                if self
+                    .shape_draft
+                    .as_ref()
+                    .is_some_and(|draft| draft.tool != overlay.stroke_tool)
+                {
+                    self.shape_draft = None;
+                }
+                let shape_preview = self
+                    .shape_draft
+                    .as_ref()
+                    .and_then(|draft| draft.shape(true))
+                    .map(|shape| shape_outline_screen(integration, &shape));
+                overlay.set_shape_preview(shape_preview);
                 let Some(window) = self.window.as_deref() else {
                     integration.present_to_screen();
                     return;
@@ -283,6 +648,8 @@ impl DesktopApp {
                     integration.set_active_brush(self.active_brush_kind.brush_id());
                 }
                 integration.set_active_brush_color_rgb(overlay.selected_brush_color_rgb());
+                integration
+                    .set_active_brush_background_rgb(overlay.selected_brush_background_rgb());
                 integration.set_active_brush_erase(overlay.selected_brush_erase());
                 overlay_actions = overlay.take_pending_actions();
             } else {
@@ -326,6 +693,13 @@ impl DesktopApp {
             UiCommand::BrushUpdated(brush_kind, values) => {
                 self.apply_brush_action(brush_kind, &values)
             }
+            UiCommand::BrushPresetSelected(name) => self.apply_brush_preset_select(&name),
+            UiCommand::BrushPresetSaved(preset) => self.apply_brush_preset_save(preset),
+            UiCommand::BrushPresetDeleted(name) => self.apply_brush_preset_delete(&name),
+            UiCommand::BrushPresetImportRequested(path) => self.apply_brush_preset_import(path),
+            UiCommand::BrushPresetExportRequested(name, path) => {
+                self.apply_brush_preset_export(&name, path)
+            }
             UiCommand::LayerSelected(node_id) => self.apply_layer_select(node_id),
             UiCommand::LayerCreated(kind) => self.apply_layer_create(kind),
             UiCommand::GroupCreated => self.apply_group_create(),
@@ -339,10 +713,22 @@ impl DesktopApp {
             UiCommand::LayerBlendModeChanged(node_id, blend_mode) => {
                 self.apply_layer_blend_mode(node_id, blend_mode)
             }
+            UiCommand::SymmetryChanged(symmetry) => self.apply_symmetry(symmetry),
+            UiCommand::SmoothingChanged(smoothing) => self.apply_smoothing(smoothing),
+            UiCommand::PressureRampChanged(taper, synthetic_pressure) => {
+                self.apply_pressure_ramp(taper, synthetic_pressure)
+            }
+            UiCommand::CalibrationRequested(action) => self.apply_calibration(action),
             UiCommand::DocumentSaveRequested(path) => self.apply_document_save(path),
             UiCommand::DocumentLoadRequested(path) => self.apply_document_load(path),
-            UiCommand::DocumentExportRequested(path) => self.apply_document_export(path),
+            UiCommand::DocumentExportRequested(path, options) => {
+                self.apply_document_export(path, options)
+            }
+            UiCommand::LayerBatchExportRequested(path, options) => {
+                self.apply_layer_batch_export(path, options)
+            }
             UiCommand::ExitConfirmed(action) => self.apply_exit_confirm(action),
+            UiCommand::RecoveryPromptAnswered(action) => self.apply_recovery_prompt(action),
             UiCommand::PathDialogCancelled => self.apply_path_dialog_cancel(),
         }
     }
@@ -350,10 +736,40 @@ impl DesktopApp {
     fn apply_brush_action(
         &mut self,
         brush_kind: BrushKind,
-        values: &[brushes::BrushConfigValue],
+        values: &BrushConfigValues,
     ) -> Result<ApplyActionsEffect, AppActionError> {
         match brush_kind {
-            BrushKind::Round => match RoundBrush::from_config_values(values) {
+            BrushKind::Round => {
+                let color_dynamics = read_color_dynamics(values, "round brush")?;
+                let dynamics = DabDynamics::from_config_values(values).map_err(|error| {
+                    AppActionError::BrushBuild(format!("round brush: {}", error))
+                })?;
+                match RoundBrush::from_config_values(values) {
+                    Ok(updated_brush) => {
+                        let Some(integration) = self.integration.as_mut() else {
+                            return Ok(ApplyActionsEffect::default());
+                        };
+                        integration
+                            .update_brush(
+                                brush_kind.brush_id(),
+                                WithDabDynamics::new(updated_brush, dynamics),
+                            )
+                            .map_err(|e| AppActionError::BrushUpdate(format!("{:?}", e)))?;
+                        integration
+                            .set_brush_color_dynamics(brush_kind.brush_id(), color_dynamics)
+                            .map_err(|e| AppActionError::BrushUpdate(format!("{:?}", e)))?;
+                        Ok(ApplyActionsEffect {
+                            advance_epoch: true,
+                            request_redraw: true,
+                        })
+                    }
+                    Err(error) => Err(AppActionError::BrushBuild(format!(
+                        "round brush: {}",
+                        error
+                    ))),
+                }
+            }
+            BrushKind::PixelRect => match PixelRectBrush::from_config_values(values) {
                 Ok(updated_brush) => {
                     let Some(integration) = self.integration.as_mut() else {
                         return Ok(ApplyActionsEffect::default());
@@ -367,11 +783,38 @@ impl DesktopApp {
                     })
                 }
                 Err(error) => Err(AppActionError::BrushBuild(format!(
-                    "round brush: {}",
+                    "pixel rect brush: {}",
                     error
                 ))),
             },
-            BrushKind::PixelRect => match PixelRectBrush::from_config_values(values) {
+            BrushKind::Stamp => {
+                let Some(tip) = self.resolve_stamp_tip(values)? else {
+                    return Ok(ApplyActionsEffect::default());
+                };
+                let color_dynamics = read_color_dynamics(values, "stamp brush")?;
+                let dynamics = DabDynamics::from_config_values(values).map_err(|error| {
+                    AppActionError::BrushBuild(format!("stamp brush: {}", error))
+                })?;
+                let updated_brush =
+                    StampBrush::from_config_values(tip, values).map_err(|error| {
+                        AppActionError::BrushBuild(format!("stamp brush: {}", error))
+                    })?;
+                let updated_brush = WithDabDynamics::new(updated_brush, dynamics);
+                let Some(integration) = self.integration.as_mut() else {
+                    return Ok(ApplyActionsEffect::default());
+                };
+                integration
+                    .update_brush(brush_kind.brush_id(), updated_brush)
+                    .map_err(|e| AppActionError::BrushUpdate(format!("{:?}", e)))?;
+                integration
+                    .set_brush_color_dynamics(brush_kind.brush_id(), color_dynamics)
+                    .map_err(|e| AppActionError::BrushUpdate(format!("{:?}", e)))?;
+                Ok(ApplyActionsEffect {
+                    advance_epoch: true,
+                    request_redraw: true,
+                })
+            }
+            BrushKind::Smudge => match SmudgeBrush::from_config_values(values) {
                 Ok(updated_brush) => {
                     let Some(integration) = self.integration.as_mut() else {
                         return Ok(ApplyActionsEffect::default());
@@ -385,13 +828,68 @@ impl DesktopApp {
                     })
                 }
                 Err(error) => Err(AppActionError::BrushBuild(format!(
-                    "pixel rect brush: {}",
+                    "smudge brush: {}",
                     error
                 ))),
             },
+            BrushKind::Spray => {
+                let color_dynamics = read_color_dynamics(values, "spray brush")?;
+                let updated_brush = SprayBrush::from_config_values(values).map_err(|error| {
+                    AppActionError::BrushBuild(format!("spray brush: {}", error))
+                })?;
+                let Some(integration) = self.integration.as_mut() else {
+                    return Ok(ApplyActionsEffect::default());
+                };
+                integration
+                    .update_brush(brush_kind.brush_id(), updated_brush)
+                    .map_err(|e| AppActionError::BrushUpdate(format!("{:?}", e)))?;
+                integration
+                    .set_brush_color_dynamics(brush_kind.brush_id(), color_dynamics)
+                    .map_err(|e| AppActionError::BrushUpdate(format!("{:?}", e)))?;
+                Ok(ApplyActionsEffect {
+                    advance_epoch: true,
+                    request_redraw: true,
+                })
+            }
+            BrushKind::File => {
+                let Some(definition) = self.file_brush.clone() else {
+                    return Ok(ApplyActionsEffect::default());
+                };
+                let updated_brush =
+                    FileBrush::from_config_values(definition, values).map_err(|error| {
+                        AppActionError::BrushBuild(format!("file brush: {}", error))
+                    })?;
+                let Some(integration) = self.integration.as_mut() else {
+                    return Ok(ApplyActionsEffect::default());
+                };
+                integration
+                    .update_brush(brush_kind.brush_id(), updated_brush)
+                    .map_err(|e| AppActionError::BrushUpdate(format!("{:?}", e)))?;
+                Ok(ApplyActionsEffect {
+                    advance_epoch: true,
+                    request_redraw: true,
+                })
+            }
         }
     }
 
+    /// Tip image for the stamp's `tip_texture` value: the startup tip when it
+    /// is empty, otherwise the PNG at that path.
+    fn resolve_stamp_tip(
+        &self,
+        values: &BrushConfigValues,
+    ) -> Result<Option<Arc<BrushTipImage>>, AppActionError> {
+        let texture = values
+            .texture_ref("tip_texture")
+            .map_err(|error| AppActionError::BrushBuild(format!("stamp brush: {}", error)))?;
+        if texture.trim().is_empty() {
+            return Ok(self.stamp_tip.clone());
+        }
+        BrushTipImage::read_png(Path::new(texture.trim()))
+            .map(|tip| Some(Arc::new(tip)))
+            .map_err(|error| AppActionError::BrushBuild(format!("stamp tip {texture}: {error}")))
+    }
+
     fn apply_layer_select(
         &mut self,
         node_id: NodeId,
@@ -513,6 +1011,79 @@ impl DesktopApp {
             .map_err(|e| AppActionError::LayerOpacity(node_id, format!("{:?}", e)))
     }
 
+    fn apply_smoothing(
+        &mut self,
+        smoothing: SmoothingConfig,
+    ) -> Result<ApplyActionsEffect, AppActionError> {
+        let Some(integration) = self.integration.as_mut() else {
+            return Ok(ApplyActionsEffect::default());
+        };
+        integration.set_smoothing(smoothing);
+        Ok(ApplyActionsEffect::default())
+    }
+
+    fn apply_pressure_ramp(
+        &mut self,
+        taper: StrokeTaper,
+        synthetic_pressure: SyntheticPressure,
+    ) -> Result<ApplyActionsEffect, AppActionError> {
+        let Some(integration) = self.integration.as_mut() else {
+            return Ok(ApplyActionsEffect::default());
+        };
+        integration.set_pressure_ramp(taper, synthetic_pressure);
+        Ok(ApplyActionsEffect::default())
+    }
+
+    fn apply_calibration(
+        &mut self,
+        action: CalibrationAction,
+    ) -> Result<ApplyActionsEffect, AppActionError> {
+        match action {
+            CalibrationAction::StartRecording => {
+                self.calibration_samples = Some(Vec::new());
+                if let Some(overlay) = &mut self.overlay {
+                    overlay.calibration.recording = true;
+                    overlay.calibration.status = None;
+                }
+            }
+            CalibrationAction::CancelRecording => {
+                self.calibration_samples = None;
+                if let Some(overlay) = &mut self.overlay {
+                    overlay.calibration.recording = false;
+                }
+            }
+            CalibrationAction::Reset => {
+                self.update_device_calibrations(|calibrations| {
+                    *calibrations = DeviceCalibrations::default();
+                });
+                if let Some(overlay) = &mut self.overlay {
+                    overlay.calibration.pen_tilt_deadzone_deg = 0.0;
+                    overlay.calibration.status = Some("Calibration reset".to_string());
+                }
+            }
+            CalibrationAction::PenTiltDeadzone(radians) => {
+                self.update_device_calibrations(|calibrations| {
+                    calibrations.pen.tilt_deadzone = radians;
+                });
+            }
+        }
+        Ok(ApplyActionsEffect {
+            advance_epoch: false,
+            request_redraw: true,
+        })
+    }
+
+    fn apply_symmetry(&mut self, symmetry: Symmetry) -> Result<ApplyActionsEffect, AppActionError> {
+        let Some(integration) = self.integration.as_mut() else {
+            return Ok(ApplyActionsEffect::default());
+        };
+        integration.set_symmetry(symmetry);
+        Ok(ApplyActionsEffect {
+            advance_epoch: false,
+            request_redraw: true,
+        })
+    }
+
     fn apply_layer_blend_mode(
         &mut self,
         node_id: NodeId,
@@ -597,12 +1168,13 @@ impl DesktopApp {
     fn apply_document_export(
         &mut self,
         path: std::path::PathBuf,
+        options: ExportOptions,
     ) -> Result<ApplyActionsEffect, AppActionError> {
         let Some(integration) = self.integration.as_mut() else {
             return Ok(ApplyActionsEffect::default());
         };
         integration
-            .export_document_jpeg(&path)
+            .export_document_image(&path, &options)
             .inspect(|_| {
                 if let Some(overlay) = self.overlay.as_mut() {
                     overlay.set_document_status(format!("Exported {}", path.display()), false);
@@ -620,6 +1192,40 @@ impl DesktopApp {
             .map_err(|error| AppActionError::DocumentExport(path, format!("{:?}", error)))
     }
 
+    fn apply_layer_batch_export(
+        &mut self,
+        path: std::path::PathBuf,
+        options: LayerBatchExportOptions,
+    ) -> Result<ApplyActionsEffect, AppActionError> {
+        let Some(integration) = self.integration.as_mut() else {
+            return Ok(ApplyActionsEffect::default());
+        };
+        integration
+            .export_layer_batch(&path, &options)
+            .inspect(|report| {
+                if let Some(overlay) = self.overlay.as_mut() {
+                    overlay.set_document_status(
+                        format!(
+                            "Exported {} layers to {}",
+                            report.written.len(),
+                            path.display()
+                        ),
+                        false,
+                    );
+                }
+            })
+            .inspect_err(|error| {
+                if let Some(overlay) = self.overlay.as_mut() {
+                    overlay.set_document_status(format!("Layer export failed: {}", error), true);
+                }
+            })
+            .map(|_| ApplyActionsEffect {
+                advance_epoch: false,
+                request_redraw: true,
+            })
+            .map_err(|error| AppActionError::DocumentExport(path, format!("{:?}", error)))
+    }
+
     fn apply_exit_confirm(
         &mut self,
         action: ExitConfirmAction,
@@ -644,6 +1250,238 @@ impl DesktopApp {
         })
     }
 
+    fn apply_brush_preset_select(
+        &mut self,
+        name: &str,
+    ) -> Result<ApplyActionsEffect, AppActionError> {
+        let (Some(library), Some(overlay)) = (self.brush_presets.as_ref(), self.overlay.as_mut())
+        else {
+            return Ok(ApplyActionsEffect::default());
+        };
+        let Some(preset) = library.get(name) else {
+            return Err(AppActionError::BrushPreset(format!(
+                "unknown preset {name}"
+            )));
+        };
+        let Some(resolved) = overlay.apply_brush_preset(preset) else {
+            return Err(AppActionError::BrushPreset(format!(
+                "preset {name} uses unknown brush {}",
+                preset.brush
+            )));
+        };
+        let mut status = format!("Loaded preset {name}");
+        if !resolved.defaulted_keys.is_empty() {
+            status.push_str(&format!(
+                " (defaults for {})",
+                resolved.defaulted_keys.join(", ")
+            ));
+        }
+        if !resolved.ignored_keys.is_empty() {
+            status.push_str(&format!(" (ignored {})", resolved.ignored_keys.join(", ")));
+        }
+        overlay.set_document_status(status, false);
+        Ok(ApplyActionsEffect {
+            advance_epoch: false,
+            request_redraw: true,
+        })
+    }
+
+    fn apply_brush_preset_save(
+        &mut self,
+        preset: BrushPreset,
+    ) -> Result<ApplyActionsEffect, AppActionError> {
+        let Some(library) = self.brush_presets.as_mut() else {
+            return Ok(ApplyActionsEffect::default());
+        };
+        let name = preset.name.clone();
+        let result = library
+            .save(preset)
+            .map(|()| format!("Saved preset {name}"));
+        self.report_brush_preset_result(result)
+    }
+
+    fn apply_brush_preset_delete(
+        &mut self,
+        name: &str,
+    ) -> Result<ApplyActionsEffect, AppActionError> {
+        let Some(library) = self.brush_presets.as_mut() else {
+            return Ok(ApplyActionsEffect::default());
+        };
+        let result = library
+            .remove(name)
+            .map(|()| format!("Deleted preset {name}"));
+        self.report_brush_preset_result(result)
+    }
+
+    fn apply_brush_preset_import(
+        &mut self,
+        path: PathBuf,
+    ) -> Result<ApplyActionsEffect, AppActionError> {
+        let Some(library) = self.brush_presets.as_mut() else {
+            return Ok(ApplyActionsEffect::default());
+        };
+        let result = library.import(&path).map(|preset| preset.name.clone());
+        match result {
+            Ok(name) => {
+                self.sync_brush_preset_entries();
+                self.apply_brush_preset_select(&name)
+            }
+            Err(error) => self.report_brush_preset_result(Err(error)),
+        }
+    }
+
+    fn apply_brush_preset_export(
+        &mut self,
+        name: &str,
+        path: PathBuf,
+    ) -> Result<ApplyActionsEffect, AppActionError> {
+        let Some(library) = self.brush_presets.as_ref() else {
+            return Ok(ApplyActionsEffect::default());
+        };
+        let result = library
+            .export(name, &path)
+            .map(|()| format!("Exported preset {name} to {}", path.display()));
+        self.report_brush_preset_result(result)
+    }
+
+    /// Refreshes the picker and shows `result` in the status bar; `Ok`
+    /// carries the success message.
+    fn report_brush_preset_result(
+        &mut self,
+        result: Result<String, app::brush_presets::BrushPresetError>,
+    ) -> Result<ApplyActionsEffect, AppActionError> {
+        self.sync_brush_preset_entries();
+        let Some(overlay) = self.overlay.as_mut() else {
+            return Ok(ApplyActionsEffect::default());
+        };
+        match result {
+            Ok(status) => {
+                overlay.set_document_status(status, false);
+                Ok(ApplyActionsEffect {
+                    advance_epoch: false,
+                    request_redraw: true,
+                })
+            }
+            Err(error) => {
+                overlay.set_document_status(format!("Brush preset failed: {}", error), true);
+                Err(AppActionError::BrushPreset(error.to_string()))
+            }
+        }
+    }
+
+    fn sync_brush_preset_entries(&mut self) {
+        let (Some(library), Some(overlay)) = (self.brush_presets.as_ref(), self.overlay.as_mut())
+        else {
+            return;
+        };
+        overlay.set_brush_preset_entries(
+            library
+                .presets()
+                .iter()
+                .map(|preset| (preset.brush.clone(), preset.name.clone()))
+                .collect(),
+        );
+    }
+
+    fn apply_recovery_prompt(
+        &mut self,
+        action: RecoveryPromptAction,
+    ) -> Result<ApplyActionsEffect, AppActionError> {
+        let Some(recovery_dir) = self.recovery_dir.clone() else {
+            return Ok(ApplyActionsEffect::default());
+        };
+        let mut result = Ok(());
+        if let RecoveryPromptAction::Restore = action
+            && let Some(integration) = self.integration.as_mut()
+        {
+            result = integration
+                .restore_recovery_session(&recovery_dir)
+                .inspect(|entry_count| {
+                    if let Some(overlay) = self.overlay.as_mut() {
+                        overlay.set_document_status(
+                            format!("Recovered {} journaled edits", entry_count),
+                            false,
+                        );
+                        overlay.mark_document_dirty();
+                    }
+                })
+                .inspect_err(|error| {
+                    if let Some(overlay) = self.overlay.as_mut() {
+                        overlay.set_document_status(format!("Recovery failed: {}", error), true);
+                    }
+                })
+                .map(|_| ())
+                .map_err(|error| AppActionError::Recovery(recovery_dir, format!("{:?}", error)));
+        }
+        self.begin_recovery_session();
+        result.map(|()| ApplyActionsEffect {
+            advance_epoch: true,
+            request_redraw: true,
+        })
+    }
+
+    fn begin_recovery_session(&mut self) {
+        let (Some(integration), Some(recovery_dir)) =
+            (self.integration.as_mut(), self.recovery_dir.as_deref())
+        else {
+            return;
+        };
+        match integration.begin_recovery_session(recovery_dir) {
+            Ok(()) => self.last_autosave_at = Some(Instant::now()),
+            Err(error) => eprintln!("Recovery session start failed: {}", error),
+        }
+    }
+
+    fn autosave_recovery_if_due(&mut self) {
+        let interval = Duration::from_secs(
+            self.run_config
+                .autosave_interval_s
+                .unwrap_or(app::config::recovery::AUTOSAVE_INTERVAL_S),
+        );
+        let Some(last_autosave_at) = self.last_autosave_at else {
+            return;
+        };
+        if self.stroke_active || last_autosave_at.elapsed() < interval {
+            return;
+        }
+        let Some(integration) = self.integration.as_mut() else {
+            return;
+        };
+        if let Err(error) = integration.autosave_recovery_checkpoint() {
+            eprintln!("Recovery autosave failed: {}", error);
+        }
+        self.last_autosave_at = Some(Instant::now());
+    }
+
+    fn reload_file_brushes_if_changed(&mut self) {
+        let Some(integration) = self.integration.as_mut() else {
+            return;
+        };
+        for reload in integration.poll_file_brush_reloads() {
+            let (message, is_error) = match &reload.result {
+                Ok(()) => (format!("Reloaded brush shader {}", reload.label), false),
+                Err(error) => {
+                    eprintln!("Brush shader reload failed ({}): {}", reload.label, error);
+                    (
+                        format!(
+                            "Brush shader {} kept previous version: {}",
+                            reload.label, error
+                        ),
+                        true,
+                    )
+                }
+            };
+            if let Some(overlay) = self.overlay.as_mut() {
+                overlay.set_document_status(message, is_error);
+            }
+            if reload.result.is_ok()
+                && let Some(window) = &self.window
+            {
+                window.request_redraw();
+            }
+        }
+    }
+
     fn apply_path_dialog_cancel(&mut self) -> Result<ApplyActionsEffect, AppActionError> {
         self.shutdown_after_save = false;
         Ok(ApplyActionsEffect {
@@ -656,9 +1494,10 @@ impl DesktopApp {
         if self.output_finalized {
             return;
         }
-        if self.integration.is_none() {
+        let Some(integration) = &mut self.integration else {
             return;
-        }
+        };
+        integration.flush_engine_output();
         self.render_frame();
 
         if let Some(screenshot_path) = &self.run_config.screenshot_path {
@@ -670,6 +1509,37 @@ impl DesktopApp {
             }
         }
 
+        if let Some(export_path) = &self.run_config.export_path
+            && let Some(integration) = &mut self.integration
+        {
+            match self.run_config.export_options() {
+                Some(options) => {
+                    if let Err(error) = integration.export_document_image(export_path, &options) {
+                        eprintln!("Export failed: {}", error);
+                    }
+                }
+                None => eprintln!(
+                    "Export failed: unknown format for {}",
+                    export_path.display()
+                ),
+            }
+        }
+
+        if let Some(output_dir) = &self.run_config.export_layers_dir
+            && let Some(integration) = &mut self.integration
+        {
+            let options = self.run_config.layer_batch_export_options();
+            match integration.export_layer_batch(output_dir, &options) {
+                Ok(report) => eprintln!(
+                    "Exported {} layer images to {} ({} empty skipped)",
+                    report.written.len(),
+                    output_dir.display(),
+                    report.skipped_empty.len()
+                ),
+                Err(error) => eprintln!("Layer export failed: {}", error),
+            }
+        }
+
         if self.run_config.record_input_path.is_some()
             || self.run_config.record_output_path.is_some()
         {
@@ -701,6 +1571,11 @@ impl DesktopApp {
     pub fn perform_shutdown(&mut self, event_loop: &ActiveEventLoop) {
         self.shutdown_requested = true;
         self.finalize_outputs();
+        if let Some(integration) = self.integration.as_mut()
+            && let Err(error) = integration.end_recovery_session()
+        {
+            eprintln!("Recovery session cleanup failed: {}", error);
+        }
         event_loop.exit();
     }
 }
@@ -719,6 +1594,63 @@ fn crop_outline_screen_points(
     corners.map(|(x, y)| Pos2::new(x, y))
 }
 
+/// Guide lines run from the symmetry center past the canvas edge.
+fn symmetry_guide_screen(
+    integration: &AppThreadIntegration,
+    symmetry: Symmetry,
+    document_size: (u32, u32),
+    dragging: bool,
+) -> SymmetryGuide {
+    let (width, height) = document_size;
+    let reach = (width as f32).hypot(height as f32);
+    let center = symmetry.center;
+    let to_screen = |x: f32, y: f32| {
+        let (x, y) = integration.map_document_to_screen(x, y);
+        Pos2::new(x, y)
+    };
+    let center_screen = to_screen(center.x, center.y);
+    let lines = symmetry
+        .guide_rays()
+        .into_iter()
+        .map(|ray| {
+            [
+                center_screen,
+                to_screen(center.x + ray.x * reach, center.y + ray.y * reach),
+            ]
+        })
+        .collect();
+    SymmetryGuide {
+        lines,
+        center: center_screen,
+        dragging,
+    }
+}
+
+fn shape_outline_screen(integration: &AppThreadIntegration, shape: &StrokeShape) -> Vec<Pos2> {
+    shape
+        .outline()
+        .into_iter()
+        .map(|point| {
+            let (x, y) = integration.map_document_to_screen(point.x, point.y);
+            Pos2::new(x, y)
+        })
+        .collect()
+}
+
+/// Color dynamics items trail every other item of brushes that paint color.
+fn with_color_dynamics_items(mut items: Vec<BrushConfigItem>) -> Vec<BrushConfigItem> {
+    items.extend(ColorDynamics::default().config_items());
+    items
+}
+
+fn read_color_dynamics(
+    values: &BrushConfigValues,
+    label: &str,
+) -> Result<ColorDynamics, AppActionError> {
+    ColorDynamics::from_config_values(values)
+        .map_err(|error| AppActionError::BrushBuild(format!("{label}: {error}")))
+}
+
 fn crop_extent_to_size(value: f32) -> u32 {
     if !value.is_finite() {
         return 1;
@@ -795,7 +1727,7 @@ impl ApplicationHandler for DesktopApp {
             };
 
             let round_brush = match RoundBrush::with_default_curves(3.0, 0.8) {
-                Ok(brush) => brush,
+                Ok(brush) => WithDabDynamics::new(brush, DabDynamics::default()),
                 Err(error) => {
                     eprintln!("failed to build default brush: {}", error);
                     event_loop.exit();
@@ -803,9 +1735,39 @@ impl ApplicationHandler for DesktopApp {
                 }
             };
             let pixel_rect_brush = PixelRectBrush::new(8);
+            let stamp_tip = match &self.run_config.stamp_tip_path {
+                Some(tip_path) => match BrushTipImage::read_png(tip_path) {
+                    Ok(tip) => tip,
+                    Err(error) => {
+                        eprintln!("Stamp tip load failed ({}): {}", tip_path.display(), error);
+                        BrushTipImage::soft_disc(128)
+                    }
+                },
+                None => BrushTipImage::soft_disc(128),
+            };
+            let stamp_tip = Arc::new(stamp_tip);
+            let stamp_brush = WithDabDynamics::new(
+                StampBrush::new(stamp_tip.clone(), 24.0, 25.0),
+                DabDynamics::default(),
+            );
+            self.stamp_tip = Some(stamp_tip);
+            let smudge_brush = SmudgeBrush::new(16.0);
+            let spray_brush = SprayBrush::new(24.0);
             self.brush_states = vec![
-                BrushUiState::new(BrushKind::Round, round_brush.config_items()),
+                BrushUiState::new(
+                    BrushKind::Round,
+                    with_color_dynamics_items(round_brush.config_items()),
+                ),
                 BrushUiState::new(BrushKind::PixelRect, pixel_rect_brush.config_items()),
+                BrushUiState::new(
+                    BrushKind::Stamp,
+                    with_color_dynamics_items(stamp_brush.config_items()),
+                ),
+                BrushUiState::new(BrushKind::Smudge, smudge_brush.config_items()),
+                BrushUiState::new(
+                    BrushKind::Spray,
+                    with_color_dynamics_items(spray_brush.config_items()),
+                ),
             ];
             if let Err(error) = integration.register_brush(ROUND_BRUSH_ID, round_brush) {
                 eprintln!("failed to register round brush: {:?}", error);
@@ -817,8 +1779,56 @@ impl ApplicationHandler for DesktopApp {
                 event_loop.exit();
                 return;
             }
+            if let Err(error) = integration.register_brush(STAMP_BRUSH_ID, stamp_brush) {
+                eprintln!("failed to register stamp brush: {:?}", error);
+                event_loop.exit();
+                return;
+            }
+            if let Err(error) = integration.register_brush(SMUDGE_BRUSH_ID, smudge_brush) {
+                eprintln!("failed to register smudge brush: {:?}", error);
+                event_loop.exit();
+                return;
+            }
+            if let Err(error) = integration.register_brush(SPRAY_BRUSH_ID, spray_brush) {
+                eprintln!("failed to register spray brush: {:?}", error);
+                event_loop.exit();
+                return;
+            }
+            if let Some(manifest_path) = &self.run_config.brush_file_path {
+                match FileBrushDefinition::load(manifest_path) {
+                    Ok(definition) => {
+                        let definition = Arc::new(definition);
+                        let file_brush = FileBrush::new(definition.clone());
+                        let items = file_brush.config_items();
+                        match integration.register_file_brush(FILE_BRUSH_ID, file_brush) {
+                            Ok(()) => {
+                                self.brush_states
+                                    .push(BrushUiState::new(BrushKind::File, items));
+                                self.file_brush = Some(definition);
+                            }
+                            Err(error) => {
+                                eprintln!("failed to register file brush: {:?}", error)
+                            }
+                        }
+                    }
+                    Err(error) => eprintln!(
+                        "Brush file load failed ({}): {}",
+                        manifest_path.display(),
+                        error
+                    ),
+                }
+            }
             integration.set_active_brush(self.active_brush_kind.brush_id());
 
+            if let Some(author) = &self.run_config.document_author {
+                integration
+                    .document_metadata_mut()
+                    .set_author(author.clone());
+            }
+            if let Some(dpi) = self.run_config.document_dpi {
+                integration.document_metadata_mut().set_dpi(dpi);
+            }
+
             if self.run_config.record_input_path.is_some()
                 || self.run_config.record_output_path.is_some()
             {
@@ -830,6 +1840,13 @@ impl ApplicationHandler for DesktopApp {
         }
 
         if let Some(integration) = &mut self.integration {
+            integration.set_engine_thread_mode(if self.run_config.inline_engine {
+                EngineThreadMode::Inline
+            } else {
+                EngineThreadMode::Dedicated
+            });
+            let engine_window = window.clone();
+            integration.set_engine_waker(move || engine_window.request_redraw());
             let gpu_context = integration.main_state().gpu_context().clone();
             let adapter = &gpu_context.adapter;
             let device = &gpu_context.device;
@@ -875,6 +1892,42 @@ impl ApplicationHandler for DesktopApp {
             ));
         }
 
+        if self.brush_presets.is_none() {
+            let preset_dir = self
+                .run_config
+                .brush_preset_dir
+                .clone()
+                .unwrap_or_else(BrushPresetLibrary::default_dir);
+            match BrushPresetLibrary::open(&preset_dir) {
+                Ok(library) => {
+                    self.brush_presets = Some(library);
+                    self.sync_brush_preset_entries();
+                }
+                Err(error) => eprintln!(
+                    "Brush preset library load failed ({}): {}",
+                    preset_dir.display(),
+                    error
+                ),
+            }
+        }
+
+        if self.preferences.is_none() {
+            let path = self.preferences_path();
+            let preferences = UserPreferences::load(&path).unwrap_or_else(|error| {
+                eprintln!("Preferences load failed ({}): {}", path.display(), error);
+                UserPreferences::default()
+            });
+            let calibrations = preferences.device_calibrations();
+            if let Some(integration) = &mut self.integration {
+                integration.set_device_calibration(calibrations);
+            }
+            if let Some(overlay) = &mut self.overlay {
+                overlay.calibration.pen_tilt_deadzone_deg =
+                    calibrations.pen.tilt_deadzone.to_degrees();
+            }
+            self.preferences = Some(preferences);
+        }
+
         if let Some(replay_input_path) = &self.run_config.replay_input_path {
             match TraceRecorder::load_input_file(replay_input_path) {
                 Ok(input_file) => {
@@ -890,6 +1943,20 @@ impl ApplicationHandler for DesktopApp {
             }
         }
 
+        if !self.is_replay_mode() && self.recovery_dir.is_none() {
+            let recovery_dir = self
+                .run_config
+                .recovery_dir
+                .clone()
+                .unwrap_or_else(RecoveryJournal::default_dir);
+            let unclean_session = RecoveryJournal::has_unclean_session(&recovery_dir);
+            self.recovery_dir = Some(recovery_dir);
+            match self.overlay.as_mut() {
+                Some(overlay) if unclean_session => overlay.recovery_prompt_open = true,
+                _ => self.begin_recovery_session(),
+            }
+        }
+
         if let Some(window) = &self.window {
             window.request_redraw();
         }
@@ -1000,6 +2067,15 @@ impl ApplicationHandler for DesktopApp {
                                 window.request_redraw();
                             }
                         }
+                        Key::Named(NamedKey::Escape) if self.shape_draft.is_some() => {
+                            self.shape_draft = None;
+                            if let Some(window) = &self.window {
+                                window.request_redraw();
+                            }
+                        }
+                        Key::Named(NamedKey::Escape) if self.stroke_active => {
+                            self.cancel_input_stroke();
+                        }
                         Key::Named(NamedKey::Escape) => self.request_shutdown(event_loop),
                         _ => {}
                     }
@@ -1008,30 +2084,7 @@ impl ApplicationHandler for DesktopApp {
             _ => {
                 let (result, needs_redraw) = handle_window_event(self, &event, ui_event_consumed);
                 match result {
-                    MouseInputResult::StrokeBegan => {
-                        let mut effect = ApplyActionsEffect::default();
-                        if let Some(overlay) = &mut self.overlay {
-                            overlay.flush_selected_brush_if_dirty();
-                            let overlay_actions = overlay.take_pending_actions();
-                            let report = self.apply_overlay_actions(overlay_actions);
-                            for error in report.errors {
-                                eprintln!("overlay action errors: {}", error);
-                            }
-                            effect.merge(report.effect);
-                        }
-                        if effect.advance_epoch {
-                            self.advance_epoch();
-                        }
-                        self.render_frame();
-                        if let Some(integration) = &mut self.integration {
-                            if let Some(node_id) = integration.active_paint_node() {
-                                integration.begin_stroke(node_id);
-                            }
-                        }
-                        if let Some(window) = &self.window {
-                            window.request_redraw();
-                        }
-                    }
+                    MouseInputResult::StrokeBegan => self.begin_input_stroke(),
                     MouseInputResult::StrokeEnded => {
                         if let Some(window) = &self.window {
                             window.request_redraw();
@@ -1104,6 +2157,10 @@ impl ApplicationHandler for DesktopApp {
                 self.request_shutdown(event_loop);
             }
         }
+        if !replay_mode {
+            self.autosave_recovery_if_due();
+            self.reload_file_brushes_if_changed();
+        }
     }
 }
 
@@ -1124,3 +2181,11 @@ pub fn run_app(run_config: RunConfig) {
         .run_app(&mut app)
         .expect("failed to run app: event loop terminated unexpectedly");
 }
+
+fn device_label(device: InputDeviceKind) -> &'static str {
+    match device {
+        InputDeviceKind::Pen => "pen",
+        InputDeviceKind::Cursor => "mouse",
+        InputDeviceKind::Finger(_) => "touch",
+    }
+}
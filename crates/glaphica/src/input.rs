@@ -1,8 +1,9 @@
 use std::time::{SystemTime, UNIX_EPOCH};
 
-use glaphica_core::{CanvasVec2, InputDeviceKind, MappedCursor, RadianVec2};
+use glaphica_core::{CanvasVec2, InputDeviceKind, MappedCursor, RadianVec2, ScreenVec2};
+use stroke_input::{Gesture, TouchPhase};
 use thread_protocol::InputRingSample;
-use winit::event::{ElementState, MouseButton, MouseScrollDelta, WindowEvent};
+use winit::event::{ElementState, MouseButton, MouseScrollDelta, Touch, WindowEvent};
 
 use crate::desktop_app::DesktopApp;
 
@@ -50,6 +51,12 @@ pub fn handle_window_event(
             if app.update_canvas_crop_preview(current_position) {
                 return (MouseInputResult::None, true);
             }
+            if app.update_symmetry_center_drag(current_position) {
+                return (MouseInputResult::None, true);
+            }
+            if app.update_shape_draft(current_position) {
+                return (MouseInputResult::None, true);
+            }
             if ui_event_consumed {
                 return (MouseInputResult::None, false);
             }
@@ -63,6 +70,14 @@ pub fn handle_window_event(
             let needs_redraw = handle_mouse_wheel(app, delta);
             (MouseInputResult::None, needs_redraw)
         }
+        WindowEvent::Touch(touch) => {
+            if app.is_replay_mode()
+                || (ui_event_consumed && touch.phase == winit::event::TouchPhase::Started)
+            {
+                return (MouseInputResult::None, false);
+            }
+            handle_touch(app, touch)
+        }
         WindowEvent::ModifiersChanged(modifiers) => {
             app.ctrl_pressed = modifiers.state().control_key();
             app.shift_pressed = modifiers.state().shift_key();
@@ -82,14 +97,15 @@ fn handle_mouse_input_ui_consumed(
             let applied = app.commit_canvas_crop();
             (MouseInputResult::CanvasCropCommitted, applied)
         }
+        (MouseButton::Left, ElementState::Released) if app.symmetry_center_drag => {
+            app.symmetry_center_drag = false;
+            (MouseInputResult::None, true)
+        }
+        (MouseButton::Left, ElementState::Released) if app.finish_shape_drag() => {
+            (MouseInputResult::StrokeEnded, true)
+        }
         (MouseButton::Left, ElementState::Released) if app.stroke_active => {
-            app.stroke_active = false;
-            if let Some(integration) = &mut app.integration {
-                integration.end_stroke();
-            }
-            if let Some(overlay) = &mut app.overlay {
-                overlay.mark_document_dirty();
-            }
+            app.end_input_stroke();
             (MouseInputResult::StrokeEnded, true)
         }
         (MouseButton::Middle, ElementState::Released) => {
@@ -121,6 +137,17 @@ fn handle_mouse_input(
                     }
                     return (MouseInputResult::None, false);
                 }
+                if let Some(cursor_position) = app.cursor_position
+                    && app.symmetry_center_hit(cursor_position)
+                {
+                    app.symmetry_center_drag = true;
+                    return (MouseInputResult::None, true);
+                }
+                if let Some(cursor_position) = app.cursor_position
+                    && app.press_shape_tool(cursor_position)
+                {
+                    return (MouseInputResult::None, true);
+                }
                 app.stroke_active = false;
                 if let Some(integration) = &mut app.integration {
                     if integration.active_paint_node().is_some() {
@@ -135,16 +162,14 @@ fn handle_mouse_input(
                     let applied = app.commit_canvas_crop();
                     return (MouseInputResult::CanvasCropCommitted, applied);
                 }
-                let stroke_was_active = app.stroke_active;
-                app.stroke_active = false;
-                if let Some(integration) = &mut app.integration {
-                    integration.end_stroke();
+                if app.symmetry_center_drag {
+                    app.symmetry_center_drag = false;
+                    return (MouseInputResult::None, true);
                 }
-                if stroke_was_active {
-                    if let Some(overlay) = &mut app.overlay {
-                        overlay.mark_document_dirty();
-                    }
+                if app.finish_shape_drag() {
+                    return (MouseInputResult::StrokeEnded, true);
                 }
+                app.end_input_stroke();
                 (MouseInputResult::None, false)
             }
         },
@@ -160,6 +185,12 @@ fn handle_mouse_input(
                 (MouseInputResult::PanEnded, true)
             }
         },
+        MouseButton::Right if *state == ElementState::Pressed && app.shape_draft.is_some() => {
+            if app.commit_shape_draft() {
+                return (MouseInputResult::StrokeEnded, true);
+            }
+            (MouseInputResult::None, true)
+        }
         _ => (MouseInputResult::None, false),
     }
 }
@@ -195,12 +226,106 @@ fn handle_cursor_moved(app: &mut DesktopApp, current_position: (f32, f32)) -> bo
                 },
             };
             integration.push_input_sample(sample);
+            app.record_calibration_sample(sample.device, sample.cursor.pressure);
         }
     }
 
     needs_redraw
 }
 
+fn handle_touch(app: &mut DesktopApp, touch: &Touch) -> (MouseInputResult, bool) {
+    let phase = match touch.phase {
+        winit::event::TouchPhase::Started => TouchPhase::Started,
+        winit::event::TouchPhase::Moved => TouchPhase::Moved,
+        winit::event::TouchPhase::Ended => TouchPhase::Ended,
+        winit::event::TouchPhase::Cancelled => TouchPhase::Cancelled,
+    };
+    let position = ScreenVec2::new(touch.location.x as f32, touch.location.y as f32);
+    let pressure = touch
+        .force
+        .map(|force| force.normalized() as f32)
+        .unwrap_or(1.0);
+    let gestures = app
+        .touch_gestures
+        .touch(touch.id, phase, position, current_time_ns());
+
+    let mut result = MouseInputResult::None;
+    let mut needs_redraw = false;
+    for gesture in gestures {
+        match gesture {
+            Gesture::PaintBegan { finger, position } => {
+                app.stroke_active = app
+                    .integration
+                    .as_ref()
+                    .is_some_and(|integration| integration.active_paint_node().is_some());
+                if app.stroke_active {
+                    app.begin_input_stroke();
+                    push_touch_sample(app, finger, position, pressure);
+                }
+            }
+            Gesture::PaintMoved { finger, position } => {
+                if app.stroke_active {
+                    push_touch_sample(app, finger, position, pressure);
+                }
+            }
+            Gesture::PaintEnded => {
+                if app.stroke_active && app.end_input_stroke() {
+                    result = MouseInputResult::StrokeEnded;
+                }
+            }
+            Gesture::PaintCancelled => {
+                if app.cancel_input_stroke() {
+                    result = MouseInputResult::StrokeEnded;
+                }
+            }
+            Gesture::Transform {
+                pan,
+                zoom,
+                rotation,
+                center,
+            } => {
+                if let Some(integration) = &mut app.integration {
+                    integration.pan_view(pan.x, pan.y);
+                    integration.zoom_view(zoom, center.x, center.y);
+                    integration.rotate_view(rotation, center.x, center.y);
+                    needs_redraw = true;
+                }
+            }
+            Gesture::TwoFingerTap => {
+                if let Some(integration) = &mut app.integration
+                    && integration.undo_stroke()
+                {
+                    if let Some(overlay) = &mut app.overlay {
+                        overlay.mark_document_dirty();
+                    }
+                    needs_redraw = true;
+                }
+            }
+        }
+    }
+    (result, needs_redraw)
+}
+
+fn push_touch_sample(app: &mut DesktopApp, finger: u64, position: ScreenVec2, pressure: f32) {
+    let Some(integration) = &mut app.integration else {
+        return;
+    };
+    let (doc_x, doc_y) = integration.map_screen_to_document(position.x, position.y);
+    let device = InputDeviceKind::Finger(finger as u32);
+    integration.push_input_sample(InputRingSample {
+        epoch: app.epoch,
+        time_ns: current_time_ns(),
+        device,
+        cursor: MappedCursor {
+            cursor: CanvasVec2::new(doc_x, doc_y),
+            tilt: RadianVec2::new(0.0, 0.0),
+            pressure,
+            twist: 0.0,
+        },
+    });
+    app.record_calibration_sample(device, pressure);
+}
+
 fn handle_mouse_wheel(app: &mut DesktopApp, delta: &MouseScrollDelta) -> bool {
     let scroll = scroll_delta_lines(delta);
     if scroll.abs() <= f32::EPSILON {
@@ -7,10 +7,54 @@ mod overlay;
 mod run_config;
 mod theme;
 
+use std::path::Path;
+
+use app::image_export::{ExportFormat, ExportOptions, write_export_image};
 use desktop_app::run_app;
 use run_config::RunConfig;
 
 fn main() {
     let run_config = RunConfig::from_args(std::env::args().skip(1).collect());
+    if let Some(info_path) = &run_config.document_info_path {
+        print_document_info(info_path, run_config.thumbnail_out_path.as_deref());
+        return;
+    }
     run_app(run_config);
 }
+
+/// Prints saved document metadata without opening a window or decoding layers.
+fn print_document_info(path: &Path, thumbnail_out: Option<&Path>) {
+    let preview = match app::read_document_preview(path) {
+        Ok(preview) => preview,
+        Err(error) => {
+            eprintln!("Document info read failed: {}", error);
+            return;
+        }
+    };
+    let metadata = &preview.metadata;
+    let (width_in, height_in) =
+        metadata.physical_size_inches(preview.canvas_width, preview.canvas_height);
+    println!("name: {}", preview.name);
+    println!("author: {}", metadata.author);
+    println!(
+        "canvas: {}x{} px ({:.2}x{:.2} in at {} dpi)",
+        preview.canvas_width, preview.canvas_height, width_in, height_in, metadata.dpi
+    );
+    println!("created_at_ms: {}", metadata.created_at_ms);
+    println!("modified_at_ms: {}", metadata.modified_at_ms);
+    println!("editing_time_s: {}", metadata.editing_time_ms / 1000);
+    println!("strokes: {}", metadata.stroke_count);
+    match (&preview.thumbnail, thumbnail_out) {
+        (Some(thumbnail), Some(out)) => {
+            let options = ExportOptions::new(ExportFormat::Png);
+            match write_export_image(out, thumbnail, &options) {
+                Ok(()) => eprintln!("Thumbnail written: {}", out.display()),
+                Err(error) => eprintln!("Thumbnail write failed: {}", error),
+            }
+        }
+        (Some(thumbnail), None) => {
+            println!("thumbnail: {}x{}", thumbnail.width(), thumbnail.height());
+        }
+        (None, _) => println!("thumbnail: none"),
+    }
+}
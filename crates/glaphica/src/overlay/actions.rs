@@ -1,10 +1,15 @@
 use std::path::PathBuf;
 
-use brushes::BrushConfigValue;
+use app::brush_presets::BrushPreset;
+use app::image_export::ExportOptions;
+use app::layer_batch_export::LayerBatchExportOptions;
+use brushes::BrushConfigValues;
 use document::{LayerMoveTarget, NewLayerKind, UiBlendMode};
 use glaphica_core::NodeId;
+use stroke_input::{SmoothingConfig, StrokeTaper, Symmetry, SyntheticPressure};
 
 use crate::brush_ui::state::BrushKind;
+use crate::components::CalibrationAction;
 
 #[derive(Clone, Copy)]
 pub enum ExitConfirmAction {
@@ -13,15 +18,29 @@ pub enum ExitConfirmAction {
     Cancel,
 }
 
+#[derive(Clone, Copy)]
+pub enum RecoveryPromptAction {
+    Restore,
+    Discard,
+}
+
 #[derive(Clone, Copy)]
 pub enum PathDialogAction {
     Save,
     Load,
     Export,
+    ExportLayers,
+    ImportBrushPreset,
+    ExportBrushPreset,
 }
 
 pub enum UiCommand {
-    BrushUpdated(BrushKind, Vec<BrushConfigValue>),
+    BrushUpdated(BrushKind, BrushConfigValues),
+    BrushPresetSelected(String),
+    BrushPresetSaved(BrushPreset),
+    BrushPresetDeleted(String),
+    BrushPresetImportRequested(PathBuf),
+    BrushPresetExportRequested(String, PathBuf),
     LayerSelected(NodeId),
     LayerCreated(NewLayerKind),
     GroupCreated,
@@ -29,9 +48,15 @@ pub enum UiCommand {
     LayerVisibilityChanged(NodeId, bool),
     LayerOpacityChanged(NodeId, f32),
     LayerBlendModeChanged(NodeId, UiBlendMode),
+    SymmetryChanged(Symmetry),
+    SmoothingChanged(SmoothingConfig),
+    PressureRampChanged(StrokeTaper, SyntheticPressure),
+    CalibrationRequested(CalibrationAction),
     DocumentSaveRequested(PathBuf),
     DocumentLoadRequested(PathBuf),
-    DocumentExportRequested(PathBuf),
+    DocumentExportRequested(PathBuf, ExportOptions),
+    LayerBatchExportRequested(PathBuf, LayerBatchExportOptions),
     ExitConfirmed(ExitConfirmAction),
+    RecoveryPromptAnswered(RecoveryPromptAction),
     PathDialogCancelled,
 }
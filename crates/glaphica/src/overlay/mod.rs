@@ -2,5 +2,5 @@ pub mod actions;
 pub mod state;
 pub mod texture_cache;
 
-pub use actions::{ExitConfirmAction, PathDialogAction, UiCommand};
-pub use state::EguiOverlay;
+pub use actions::{ExitConfirmAction, PathDialogAction, RecoveryPromptAction, UiCommand};
+pub use state::{EguiOverlay, SymmetryGuide};
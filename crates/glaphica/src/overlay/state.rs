@@ -1,17 +1,24 @@
 use std::collections::HashMap;
 use std::path::{Path, PathBuf};
 
+use app::brush_presets::{BrushPreset, ResolvedPresetValues};
 use app::{AppStats, LayerPreviewBitmap};
 use document::UiLayerTreeItem;
 use egui::{Color32, Pos2, Rect, Stroke, StrokeKind, Vec2};
 use egui_winit::EventResponse;
-use glaphica_core::NodeId;
+use glaphica_core::{CanvasVec2, NodeId};
+use stroke_input::{ShapeTaper, Symmetry};
 use winit::{event::WindowEvent, event_loop::ActiveEventLoop, window::Window};
 
 use crate::brush_ui::state::{BrushKind, BrushUiState};
-use crate::components::{ConfigPanel, Sidebar, StatusBar, TopBar};
+use crate::components::{
+    BrushColors, BrushPresetPicker, CalibrationSettings, ConfigPanel, ExportOptionsForm,
+    LayerBatchForm, Sidebar, StabilizerSettings, StatusBar, StrokeEndSettings, StrokeTool, TopBar,
+};
 use crate::egui_renderer::EguiRenderer;
-use crate::overlay::actions::{ExitConfirmAction, PathDialogAction, UiCommand};
+use crate::overlay::actions::{
+    ExitConfirmAction, PathDialogAction, RecoveryPromptAction, UiCommand,
+};
 use crate::overlay::texture_cache::LayerTextureCache;
 use crate::theme::Theme;
 
@@ -22,29 +29,51 @@ pub struct EguiOverlay {
     pub theme: Theme,
     pub left_panel_collapsed: bool,
     pub right_panel_collapsed: bool,
-    pub active_color_rgb: [f32; 3],
+    pub brush_colors: BrushColors,
+    pub stabilizer: StabilizerSettings,
+    pub stroke_ends: StrokeEndSettings,
+    pub calibration: CalibrationSettings,
     pub left_panel_width: f32,
     pub right_panel_width: f32,
     pub brush_states: Vec<BrushUiState>,
     pub selected_brush_index: usize,
+    pub brush_presets: BrushPresetPicker,
     pub layer_tree_items: Vec<UiLayerTreeItem>,
     pub selected_node: Option<NodeId>,
     pub texture_cache: LayerTextureCache,
     pub document_path: String,
     pub path_dialog_action: Option<PathDialogAction>,
+    pub export_form: ExportOptionsForm,
+    pub layer_batch_form: LayerBatchForm,
+    pub document_size: (u32, u32),
     pub document_status_text: Option<String>,
     pub document_status_is_error: bool,
     pub document_dirty: bool,
     pub exit_confirm_open: bool,
+    pub recovery_prompt_open: bool,
     pub config_panel_rect: Option<Rect>,
     pub app_stats: Option<AppStats>,
     pub canvas_crop_mode_active: bool,
     pub canvas_crop_outline: Option<[Pos2; 4]>,
     pub canvas_crop_handle_center: Option<Pos2>,
     pub canvas_crop_dragging: bool,
+    pub symmetry: Symmetry,
+    pub symmetry_guide: Option<SymmetryGuide>,
+    pub stroke_tool: StrokeTool,
+    pub shape_taper: ShapeTaper,
+    /// Rubber-band outline of the shape being drawn, in screen space.
+    pub shape_preview: Option<Vec<Pos2>>,
     pending_actions: Vec<UiCommand>,
 }
 
+/// Symmetry guide lines and center handle, in screen space.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SymmetryGuide {
+    pub lines: Vec<[Pos2; 2]>,
+    pub center: Pos2,
+    pub dragging: bool,
+}
+
 impl EguiOverlay {
     pub fn new(
         event_loop: &ActiveEventLoop,
@@ -77,26 +106,39 @@ impl EguiOverlay {
             theme: Theme::dark(),
             left_panel_collapsed: false,
             right_panel_collapsed: false,
-            active_color_rgb: [1.0, 0.0, 0.0],
+            brush_colors: BrushColors::default(),
+            stabilizer: StabilizerSettings::default(),
+            stroke_ends: StrokeEndSettings::default(),
+            calibration: CalibrationSettings::default(),
             left_panel_width: 280.0,
             right_panel_width: 240.0,
             brush_states,
             selected_brush_index,
+            brush_presets: BrushPresetPicker::default(),
             layer_tree_items: Vec::new(),
             selected_node: None,
             texture_cache: LayerTextureCache::new(),
             document_path,
             path_dialog_action: None,
+            export_form: ExportOptionsForm::default(),
+            layer_batch_form: LayerBatchForm::default(),
+            document_size: (0, 0),
             document_status_text: None,
             document_status_is_error: false,
             document_dirty: false,
             exit_confirm_open: false,
+            recovery_prompt_open: false,
             config_panel_rect: None,
             app_stats: None,
             canvas_crop_mode_active: false,
             canvas_crop_outline: None,
             canvas_crop_handle_center: None,
             canvas_crop_dragging: false,
+            symmetry: Symmetry::OFF,
+            symmetry_guide: None,
+            stroke_tool: StrokeTool::Freehand,
+            shape_taper: ShapeTaper::NONE,
+            shape_preview: None,
             pending_actions: Vec::new(),
         }
     }
@@ -105,6 +147,35 @@ impl EguiOverlay {
         self.app_stats = Some(stats);
     }
 
+    pub fn set_document_size(&mut self, document_size: (u32, u32)) {
+        self.document_size = document_size;
+        // The center follows the canvas until symmetry is turned on, then
+        // stays wherever it is dragged.
+        if !self.symmetry.is_active() {
+            self.symmetry.center =
+                CanvasVec2::new(document_size.0 as f32 * 0.5, document_size.1 as f32 * 0.5);
+        }
+    }
+
+    pub fn set_symmetry_guide(&mut self, guide: Option<SymmetryGuide>) {
+        self.symmetry_guide = guide;
+    }
+
+    pub fn set_shape_preview(&mut self, preview: Option<Vec<Pos2>>) {
+        self.shape_preview = preview;
+    }
+
+    pub fn symmetry_center_handle(&self) -> Option<Pos2> {
+        self.symmetry_guide.as_ref().map(|guide| guide.center)
+    }
+
+    /// Moves the symmetry center and queues the change for the engine.
+    pub fn move_symmetry_center(&mut self, center: CanvasVec2) {
+        self.symmetry.center = center;
+        self.pending_actions
+            .push(UiCommand::SymmetryChanged(self.symmetry));
+    }
+
     pub fn on_window_event(&mut self, window: &Window, event: &WindowEvent) -> EventResponse {
         self.state.on_window_event(window, event)
     }
@@ -117,7 +188,11 @@ impl EguiOverlay {
     }
 
     pub fn selected_brush_color_rgb(&self) -> [f32; 3] {
-        self.active_color_rgb
+        self.brush_colors.foreground_rgb
+    }
+
+    pub fn selected_brush_background_rgb(&self) -> [f32; 3] {
+        self.brush_colors.background_rgb
     }
 
     pub fn selected_brush_erase(&self) -> bool {
@@ -192,10 +267,38 @@ impl EguiOverlay {
         brush_state.dirty = false;
         self.pending_actions.push(UiCommand::BrushUpdated(
             brush_state.kind,
-            brush_state.values.clone(),
+            brush_state.config_values(),
         ));
     }
 
+    /// Replaces the picker entries with `(brush label, preset name)` pairs.
+    pub fn set_brush_preset_entries(&mut self, entries: Vec<(String, String)>) {
+        self.brush_presets.entries = entries;
+    }
+
+    /// Switches to the preset's brush, loads its values and color, and
+    /// queues the brush update. Returns `None` for an unknown brush.
+    pub fn apply_brush_preset(&mut self, preset: &BrushPreset) -> Option<ResolvedPresetValues> {
+        let kind = BrushKind::from_label(&preset.brush)?;
+        let index = self
+            .brush_states
+            .iter()
+            .position(|state| state.kind == kind)?;
+        let resolved = self.brush_states[index].apply_preset(preset);
+        self.selected_brush_index = index;
+        self.brush_colors.foreground_rgb = preset.color_rgb;
+        self.brush_presets.selected = Some(preset.name.clone());
+        self.brush_presets.name_input = preset.name.clone();
+        self.queue_brush_update_if_dirty(index);
+        Some(resolved)
+    }
+
+    fn capture_selected_brush_preset(&self, name: String) -> Option<BrushPreset> {
+        self.brush_states
+            .get(self.selected_brush_index)
+            .map(|state| state.capture_preset(name, self.brush_colors.foreground_rgb))
+    }
+
     pub fn open_path_dialog(&mut self, action: PathDialogAction) {
         self.document_path = self.suggested_path_for_action(action);
         self.path_dialog_action = Some(action);
@@ -210,9 +313,19 @@ impl EguiOverlay {
             .unwrap_or("document");
         let extension = match action {
             PathDialogAction::Save | PathDialogAction::Load => "glaphica",
-            PathDialogAction::Export => "jpeg",
+            PathDialogAction::Export | PathDialogAction::ExportLayers => {
+                self.export_form.format.extension()
+            }
+            PathDialogAction::ImportBrushPreset | PathDialogAction::ExportBrushPreset => "json",
+        };
+        let file_name = match action {
+            PathDialogAction::ExportLayers => format!("{file_stem}-layers"),
+            PathDialogAction::ImportBrushPreset | PathDialogAction::ExportBrushPreset => {
+                let preset = self.brush_presets.selected.as_deref().unwrap_or("preset");
+                format!("{preset}.{extension}")
+            }
+            _ => format!("{}.{}", file_stem, extension),
         };
-        let file_name = format!("{}.{}", file_stem, extension);
         match current.parent() {
             Some(parent) if !parent.as_os_str().is_empty() => parent.join(file_name),
             _ => PathBuf::from(file_name),
@@ -245,18 +358,30 @@ impl EguiOverlay {
         let right_panel_collapsed = &mut self.right_panel_collapsed;
         let left_panel_width = &mut self.left_panel_width;
         let right_panel_width = &mut self.right_panel_width;
-        let active_color_rgb = &mut self.active_color_rgb;
+        let brush_colors = &mut self.brush_colors;
+        let stabilizer = &mut self.stabilizer;
+        let stroke_ends = &mut self.stroke_ends;
+        let calibration = &mut self.calibration;
         let brush_states = &mut self.brush_states;
+        let brush_presets = &mut self.brush_presets;
         let selected_brush_index = &mut self.selected_brush_index;
         let pending_actions = &mut self.pending_actions;
         let layer_tree_items = &self.layer_tree_items;
         let selected_node = &mut self.selected_node;
         let exit_confirm_open = &mut self.exit_confirm_open;
+        let recovery_prompt_open = &mut self.recovery_prompt_open;
         let mut config_panel_rect = self.config_panel_rect;
         let app_stats = self.app_stats.clone();
         let _document_dirty = self.document_dirty;
         let document_path = &mut self.document_path;
         let path_dialog_action = &mut self.path_dialog_action;
+        let export_form = &mut self.export_form;
+        let layer_batch_form = &mut self.layer_batch_form;
+        let document_size = self.document_size;
+        let document_status = self
+            .document_status_text
+            .as_deref()
+            .map(|text| (text, self.document_status_is_error));
 
         let panel_max_width = (target_width as f32 - 96.0)
             .max(0.0)
@@ -265,14 +390,26 @@ impl EguiOverlay {
         let mut requested_path_dialog: Option<PathDialogAction> = None;
         let mut confirm_path_dialog_flag = false;
         let mut cancel_path_dialog_flag = false;
+        let mut export_format_changed = false;
+        let mut save_preset_name: Option<String> = None;
 
         let full_output = self.ctx.run(raw_input, |ctx| {
             // Top bar
             let mut top_bar = TopBar::new();
-            let top_bar_output = top_bar.render(ctx, &theme, self.canvas_crop_mode_active);
+            let top_bar_output = top_bar.render(
+                ctx,
+                &theme,
+                self.canvas_crop_mode_active,
+                &mut self.symmetry.mode,
+                &mut self.stroke_tool,
+                &mut self.shape_taper,
+            );
             if top_bar_output.toggle_canvas_crop_mode {
                 self.canvas_crop_mode_active = !self.canvas_crop_mode_active;
             }
+            if top_bar_output.symmetry_changed {
+                pending_actions.push(UiCommand::SymmetryChanged(self.symmetry));
+            }
             if top_bar_output.save_clicked {
                 requested_path_dialog = Some(PathDialogAction::Save);
             }
@@ -282,9 +419,16 @@ impl EguiOverlay {
             if top_bar_output.export_clicked {
                 requested_path_dialog = Some(PathDialogAction::Export);
             }
+            if top_bar_output.export_layers_clicked {
+                requested_path_dialog = Some(PathDialogAction::ExportLayers);
+            }
 
             // Status bar
-            StatusBar::render(ctx, &theme, app_stats.as_ref());
+            let status_bar_output =
+                StatusBar::render(ctx, &theme, app_stats.as_ref(), document_status);
+            if status_bar_output.export_clicked {
+                requested_path_dialog = Some(PathDialogAction::Export);
+            }
 
             // Sidebar (left panel)
             let sidebar = Sidebar::new(
@@ -330,11 +474,27 @@ impl EguiOverlay {
                 *right_panel_collapsed,
                 *right_panel_width,
                 panel_max_width,
-                active_color_rgb,
+                brush_colors,
                 brush_states,
                 *selected_brush_index,
-            );
+                brush_presets,
+            )
+            .with_stabilizer(stabilizer)
+            .with_stroke_ends(stroke_ends)
+            .with_calibration(calibration);
             let config_output = config_panel.render(ctx, &theme);
+            if config_output.stabilizer_changed {
+                pending_actions.push(UiCommand::SmoothingChanged(stabilizer.smoothing_config()));
+            }
+            if let Some(action) = config_output.calibration_action {
+                pending_actions.push(UiCommand::CalibrationRequested(action));
+            }
+            if config_output.stroke_ends_changed {
+                pending_actions.push(UiCommand::PressureRampChanged(
+                    stroke_ends.taper(),
+                    stroke_ends.synthetic_pressure(),
+                ));
+            }
 
             if config_output.toggle_collapse {
                 *right_panel_collapsed = !*right_panel_collapsed;
@@ -347,6 +507,19 @@ impl EguiOverlay {
                     *selected_brush_index = new_index;
                 }
             }
+            if let Some(name) = config_output.preset_selected {
+                pending_actions.push(UiCommand::BrushPresetSelected(name));
+            }
+            save_preset_name = config_output.preset_save_requested;
+            if let Some(name) = config_output.preset_delete_requested {
+                pending_actions.push(UiCommand::BrushPresetDeleted(name));
+            }
+            if config_output.preset_import_clicked {
+                requested_path_dialog = Some(PathDialogAction::ImportBrushPreset);
+            }
+            if config_output.preset_export_clicked {
+                requested_path_dialog = Some(PathDialogAction::ExportBrushPreset);
+            }
             config_panel_rect = config_output.panel_rect;
             if let Some(rect) = config_output.panel_rect {
                 *right_panel_width = rect.width();
@@ -382,13 +555,49 @@ impl EguiOverlay {
                     });
             }
 
+            // Crash recovery prompt
+            if *recovery_prompt_open {
+                egui::Window::new("Recover Unsaved Work")
+                    .anchor(egui::Align2::CENTER_CENTER, [0.0, 0.0])
+                    .collapsible(false)
+                    .resizable(false)
+                    .show(ctx, |ui| {
+                        ui.label("The previous session did not exit cleanly.");
+                        ui.label("Restore its unsaved work?");
+                        ui.add_space(8.0);
+                        ui.horizontal(|ui| {
+                            if ui.button("Restore").clicked() {
+                                *recovery_prompt_open = false;
+                                pending_actions.push(UiCommand::RecoveryPromptAnswered(
+                                    RecoveryPromptAction::Restore,
+                                ));
+                            }
+                            if ui.button("Discard").clicked() {
+                                *recovery_prompt_open = false;
+                                pending_actions.push(UiCommand::RecoveryPromptAnswered(
+                                    RecoveryPromptAction::Discard,
+                                ));
+                            }
+                        });
+                    });
+            }
+
             // Path dialog
             if let Some(action) = *path_dialog_action {
                 let (title, confirm_label, hint) = match action {
                     PathDialogAction::Save => ("Save Document", "Save", "Enter bundle output path"),
                     PathDialogAction::Load => ("Load Document", "Load", "Enter bundle input path"),
                     PathDialogAction::Export => {
-                        ("Export JPEG", "Export", "Enter .jpg or .jpeg output path")
+                        ("Export Image", "Export", "Enter image output path")
+                    }
+                    PathDialogAction::ExportLayers => {
+                        ("Export Layers", "Export", "Enter output folder")
+                    }
+                    PathDialogAction::ImportBrushPreset => {
+                        ("Import Brush Preset", "Import", "Enter preset file path")
+                    }
+                    PathDialogAction::ExportBrushPreset => {
+                        ("Export Brush Preset", "Export", "Enter preset output path")
                     }
                 };
                 egui::Window::new(title)
@@ -400,6 +609,23 @@ impl EguiOverlay {
                         ui.add_space(8.0);
                         ui.add(egui::TextEdit::singleline(document_path).desired_width(360.0));
                         ui.add_space(8.0);
+                        match action {
+                            PathDialogAction::Export => {
+                                export_format_changed =
+                                    export_form.render(ui, &theme, document_size, false);
+                                ui.add_space(8.0);
+                            }
+                            PathDialogAction::ExportLayers => {
+                                layer_batch_form.render(ui, &theme);
+                                ui.add_space(8.0);
+                                export_form.render(ui, &theme, document_size, true);
+                                ui.add_space(8.0);
+                            }
+                            PathDialogAction::Save
+                            | PathDialogAction::Load
+                            | PathDialogAction::ImportBrushPreset
+                            | PathDialogAction::ExportBrushPreset => {}
+                        }
                         ui.horizontal(|ui| {
                             if ui.button(confirm_label).clicked() {
                                 confirm_path_dialog_flag = true;
@@ -412,10 +638,20 @@ impl EguiOverlay {
             }
         });
 
+        if let Some(preset) =
+            save_preset_name.and_then(|name| self.capture_selected_brush_preset(name))
+        {
+            self.pending_actions
+                .push(UiCommand::BrushPresetSaved(preset));
+        }
+
         // Handle path dialog actions after ctx.run()
         if let Some(action) = requested_path_dialog {
             self.open_path_dialog(action);
         }
+        if export_format_changed {
+            self.document_path = self.suggested_path_for_action(PathDialogAction::Export);
+        }
         if confirm_path_dialog_flag {
             self.confirm_path_dialog();
         }
@@ -431,6 +667,8 @@ impl EguiOverlay {
         if self.canvas_crop_mode_active {
             self.paint_canvas_crop_overlay();
         }
+        self.paint_symmetry_guide();
+        self.paint_shape_preview();
 
         // Auto-flush brush update when pointer leaves config panel
         let pointer_pos = self.ctx.input(|input| input.pointer.latest_pos());
@@ -495,6 +733,36 @@ impl EguiOverlay {
         );
     }
 
+    fn paint_symmetry_guide(&self) {
+        let Some(guide) = &self.symmetry_guide else {
+            return;
+        };
+        let layer = egui::LayerId::new(egui::Order::Background, egui::Id::new("symmetry-guide"));
+        let painter = self.ctx.layer_painter(layer);
+        let line_color = self.theme.accent_color.linear_multiply(0.6);
+        for line in &guide.lines {
+            painter.line_segment(*line, Stroke::new(1.5, line_color));
+        }
+        let handle_color = if guide.dragging {
+            Color32::from_rgb(124, 196, 255)
+        } else {
+            self.theme.accent_color
+        };
+        painter.circle_filled(guide.center, 6.0, handle_color);
+        painter.circle_stroke(guide.center, 9.0, Stroke::new(2.0, line_color));
+    }
+
+    fn paint_shape_preview(&self) {
+        let Some(points) = &self.shape_preview else {
+            return;
+        };
+        let layer = egui::LayerId::new(egui::Order::Foreground, egui::Id::new("shape-preview"));
+        let painter = self.ctx.layer_painter(layer);
+        let outer_color = Color32::from_black_alpha(140);
+        painter.line(points.clone(), Stroke::new(3.0, outer_color));
+        painter.line(points.clone(), Stroke::new(1.5, self.theme.accent_color));
+    }
+
     fn confirm_path_dialog(&mut self) {
         let path = self.document_path.trim();
         if path.is_empty() {
@@ -508,9 +776,32 @@ impl EguiOverlay {
             Some(PathDialogAction::Load) => self
                 .pending_actions
                 .push(UiCommand::DocumentLoadRequested(PathBuf::from(path))),
-            Some(PathDialogAction::Export) => self
+            Some(PathDialogAction::Export) => {
+                self.pending_actions
+                    .push(UiCommand::DocumentExportRequested(
+                        PathBuf::from(path),
+                        self.export_form.to_options(self.selected_node),
+                    ))
+            }
+            Some(PathDialogAction::ExportLayers) => {
+                self.pending_actions
+                    .push(UiCommand::LayerBatchExportRequested(
+                        PathBuf::from(path),
+                        self.layer_batch_form.to_options(&self.export_form),
+                    ))
+            }
+            Some(PathDialogAction::ImportBrushPreset) => self
                 .pending_actions
-                .push(UiCommand::DocumentExportRequested(PathBuf::from(path))),
+                .push(UiCommand::BrushPresetImportRequested(PathBuf::from(path))),
+            Some(PathDialogAction::ExportBrushPreset) => {
+                if let Some(name) = self.brush_presets.selected.clone() {
+                    self.pending_actions
+                        .push(UiCommand::BrushPresetExportRequested(
+                            name,
+                            PathBuf::from(path),
+                        ));
+                }
+            }
             None => {}
         }
     }
@@ -1,5 +1,11 @@
 use std::path::{Path, PathBuf};
 
+use app::image_export::{
+    ExportBackground, ExportBitDepth, ExportFormat, ExportOptions, ExportRect,
+};
+use app::layer_batch_export::{LayerBatchBounds, LayerBatchExportOptions, LayerBatchScope};
+use glaphica_core::NodeId;
+
 #[derive(Debug, Default)]
 pub struct RunConfig {
     pub replay_input_path: Option<PathBuf>,
@@ -7,7 +13,31 @@ pub struct RunConfig {
     pub record_output_path: Option<PathBuf>,
     pub screenshot_path: Option<PathBuf>,
     pub document_bundle_path: Option<PathBuf>,
+    pub document_author: Option<String>,
+    pub document_dpi: Option<f32>,
+    pub document_info_path: Option<PathBuf>,
+    pub thumbnail_out_path: Option<PathBuf>,
     pub exit_after_ms: Option<u64>,
+    pub recovery_dir: Option<PathBuf>,
+    pub brush_preset_dir: Option<PathBuf>,
+    pub preferences_path: Option<PathBuf>,
+    pub brush_file_path: Option<PathBuf>,
+    pub stamp_tip_path: Option<PathBuf>,
+    pub autosave_interval_s: Option<u64>,
+    pub inline_engine: bool,
+    pub export_path: Option<PathBuf>,
+    pub export_format: Option<ExportFormat>,
+    pub export_quality: Option<u8>,
+    pub export_bit_depth: Option<ExportBitDepth>,
+    pub export_scale: Option<f32>,
+    pub export_crop: Option<ExportRect>,
+    pub export_background: Option<[f32; 3]>,
+    pub export_node: Option<u64>,
+    pub export_layers_dir: Option<PathBuf>,
+    pub export_layers_scope: Option<LayerBatchScope>,
+    pub export_layers_bounds: Option<LayerBatchBounds>,
+    pub export_layers_include_hidden: bool,
+    pub export_layers_template: Option<String>,
 }
 
 impl RunConfig {
@@ -46,6 +76,26 @@ impl RunConfig {
                     }
                     index += 2;
                 }
+                "--document-author" => {
+                    config.document_author = args.get(index + 1).cloned();
+                    index += 2;
+                }
+                "--document-dpi" => {
+                    config.document_dpi = args.get(index + 1).and_then(|value| value.parse().ok());
+                    index += 2;
+                }
+                "--document-info" => {
+                    if let Some(path) = args.get(index + 1) {
+                        config.document_info_path = Some(Path::new(path).to_path_buf());
+                    }
+                    index += 2;
+                }
+                "--thumbnail-out" => {
+                    if let Some(path) = args.get(index + 1) {
+                        config.thumbnail_out_path = Some(Path::new(path).to_path_buf());
+                    }
+                    index += 2;
+                }
                 "--exit-after-ms" => {
                     if let Some(value) = args.get(index + 1) {
                         if let Ok(ms) = value.parse::<u64>() {
@@ -54,6 +104,129 @@ impl RunConfig {
                     }
                     index += 2;
                 }
+                "--recovery-dir" => {
+                    if let Some(path) = args.get(index + 1) {
+                        config.recovery_dir = Some(Path::new(path).to_path_buf());
+                    }
+                    index += 2;
+                }
+                "--brush-preset-dir" => {
+                    if let Some(path) = args.get(index + 1) {
+                        config.brush_preset_dir = Some(Path::new(path).to_path_buf());
+                    }
+                    index += 2;
+                }
+                "--preferences" => {
+                    if let Some(path) = args.get(index + 1) {
+                        config.preferences_path = Some(Path::new(path).to_path_buf());
+                    }
+                    index += 2;
+                }
+                "--brush-file" => {
+                    if let Some(path) = args.get(index + 1) {
+                        config.brush_file_path = Some(Path::new(path).to_path_buf());
+                    }
+                    index += 2;
+                }
+                "--stamp-tip" => {
+                    if let Some(path) = args.get(index + 1) {
+                        config.stamp_tip_path = Some(Path::new(path).to_path_buf());
+                    }
+                    index += 2;
+                }
+                "--autosave-interval-s" => {
+                    if let Some(value) = args.get(index + 1)
+                        && let Ok(seconds) = value.parse::<u64>()
+                    {
+                        config.autosave_interval_s = Some(seconds);
+                    }
+                    index += 2;
+                }
+                "--inline-engine" => {
+                    config.inline_engine = true;
+                    index += 1;
+                }
+                "--export" => {
+                    if let Some(path) = args.get(index + 1) {
+                        config.export_path = Some(Path::new(path).to_path_buf());
+                    }
+                    index += 2;
+                }
+                "--export-format" => {
+                    config.export_format = args
+                        .get(index + 1)
+                        .and_then(|value| ExportFormat::from_name(value));
+                    index += 2;
+                }
+                "--export-quality" => {
+                    config.export_quality =
+                        args.get(index + 1).and_then(|value| value.parse().ok());
+                    index += 2;
+                }
+                "--export-bit-depth" => {
+                    config.export_bit_depth = args
+                        .get(index + 1)
+                        .and_then(|value| value.parse().ok())
+                        .and_then(ExportBitDepth::from_bits);
+                    index += 2;
+                }
+                "--export-scale" => {
+                    config.export_scale = args.get(index + 1).and_then(|value| value.parse().ok());
+                    index += 2;
+                }
+                "--export-crop" => {
+                    config.export_crop = args
+                        .get(index + 1)
+                        .and_then(|value| parse_u32_list::<4>(value))
+                        .map(|[x, y, width, height]| ExportRect {
+                            x,
+                            y,
+                            width,
+                            height,
+                        });
+                    index += 2;
+                }
+                "--export-background" => {
+                    config.export_background =
+                        args.get(index + 1).and_then(|value| parse_rgb(value));
+                    index += 2;
+                }
+                "--export-node" => {
+                    config.export_node = args.get(index + 1).and_then(|value| value.parse().ok());
+                    index += 2;
+                }
+                "--export-layers" => {
+                    if let Some(path) = args.get(index + 1) {
+                        config.export_layers_dir = Some(Path::new(path).to_path_buf());
+                    }
+                    index += 2;
+                }
+                "--export-layers-scope" => {
+                    config.export_layers_scope =
+                        args.get(index + 1).and_then(|value| match value.as_str() {
+                            "leaves" => Some(LayerBatchScope::Leaves),
+                            "groups" => Some(LayerBatchScope::TopLevelGroups),
+                            _ => None,
+                        });
+                    index += 2;
+                }
+                "--export-layers-bounds" => {
+                    config.export_layers_bounds =
+                        args.get(index + 1).and_then(|value| match value.as_str() {
+                            "trimmed" => Some(LayerBatchBounds::Trimmed),
+                            "full" => Some(LayerBatchBounds::FullCanvas),
+                            _ => None,
+                        });
+                    index += 2;
+                }
+                "--export-layers-hidden" => {
+                    config.export_layers_include_hidden = true;
+                    index += 1;
+                }
+                "--export-layers-template" => {
+                    config.export_layers_template = args.get(index + 1).cloned();
+                    index += 2;
+                }
                 _ => {
                     index += 1;
                 }
@@ -61,4 +234,80 @@ impl RunConfig {
         }
         config
     }
+
+    /// Export options for `--export`, with the format taken from the path
+    /// extension unless `--export-format` is given.
+    pub fn export_options(&self) -> Option<ExportOptions> {
+        let format = self
+            .export_format
+            .or_else(|| ExportFormat::from_path(self.export_path.as_deref()?))?;
+        let mut options = self.image_export_options(format);
+        options.crop = self.export_crop;
+        options.node_id = self.export_node.map(NodeId);
+        Some(options)
+    }
+
+    /// Batch options for `--export-layers`; the format defaults to PNG.
+    pub fn layer_batch_export_options(&self) -> LayerBatchExportOptions {
+        let format = self.export_format.unwrap_or(ExportFormat::Png);
+        let mut options = LayerBatchExportOptions::new(self.image_export_options(format));
+        if let Some(scope) = self.export_layers_scope {
+            options.scope = scope;
+        }
+        if let Some(bounds) = self.export_layers_bounds {
+            options.bounds = bounds;
+        }
+        options.include_hidden = self.export_layers_include_hidden;
+        if let Some(template) = &self.export_layers_template {
+            options.name_template = template.clone();
+        }
+        options
+    }
+
+    fn image_export_options(&self, format: ExportFormat) -> ExportOptions {
+        let mut options = ExportOptions::new(format);
+        if let Some(quality) = self.export_quality {
+            options.quality = quality;
+        }
+        if let Some(bit_depth) = self.export_bit_depth {
+            options.bit_depth = bit_depth;
+        }
+        if let Some(scale) = self.export_scale {
+            options.scale = scale;
+        }
+        if let Some(rgb) = self.export_background {
+            options.background = ExportBackground::Fill(rgb);
+        }
+        options
+    }
+}
+
+fn parse_u32_list<const N: usize>(value: &str) -> Option<[u32; N]> {
+    let mut output = [0; N];
+    let mut parts = value.split(',');
+    for slot in &mut output {
+        *slot = parts.next()?.trim().parse().ok()?;
+    }
+    parts.next().is_none().then_some(output)
+}
+
+/// Parses `r,g,b` in `0..=1` or `#rrggbb`.
+fn parse_rgb(value: &str) -> Option<[f32; 3]> {
+    if let Some(hex) = value.strip_prefix('#') {
+        if hex.len() != 6 {
+            return None;
+        }
+        let channel = |range: std::ops::Range<usize>| {
+            u8::from_str_radix(hex.get(range)?, 16)
+                .ok()
+                .map(|value| f32::from(value) / 255.0)
+        };
+        return Some([channel(0..2)?, channel(2..4)?, channel(4..6)?]);
+    }
+    let mut output = [0.0; 3];
+    let mut parts = value.split(',');
+    for slot in &mut output {
+        *slot = parts.next()?.trim().parse().ok()?;
+    }
+    parts.next().is_none().then_some(output)
 }
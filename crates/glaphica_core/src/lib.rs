@@ -75,6 +75,9 @@ bitflags::bitflags! {
         const ACCEL = 1 << 7;
         const CURVATURE = 1 << 8;
         const CONFIDENCE = 1 << 9;
+        /// Sent once when the stroke ends: repeats the last input so brushes
+        /// can flush dabs they held back, without stamping it again.
+        const STROKE_END = 1 << 10;
     }
 }
 
@@ -109,7 +109,12 @@ impl AtlasStorageRuntime {
         self.validate_backend_id(backend_id)?;
         let edge_size = layout.tiles_per_edge() * ATLAS_TILE_SIZE;
         let usage = if config.usage.is_empty() {
+            // Drop what the format cannot do, e.g. storage binding for `R8Unorm`.
             default_usage_for_kind(kind)
+                & config
+                    .format
+                    .guaranteed_format_features(device.features())
+                    .allowed_usages
         } else {
             config.usage
         };
@@ -62,7 +62,9 @@ fn fs_normal(input: VertexOutput) -> @location(0) vec4<f32> {
         let tint = vec3<f32>(params.tint_r, params.tint_g, params.tint_b);
         return vec4<f32>(tint * alpha, alpha);
     }
-    return vec4<f32>(color.rgb * alpha, alpha);
+    // Untinted stroke buffers carry color premultiplied by thickness.
+    let unpremul_rgb = clamp(color.rgb / max(thickness, 1e-6), vec3<f32>(0.0), vec3<f32>(1.0));
+    return vec4<f32>(unpremul_rgb * alpha, alpha);
 }
 
 @fragment
@@ -7,7 +7,7 @@ use std::sync::OnceLock;
 use std::sync::atomic::{AtomicU64, Ordering};
 
 use brushes::{BrushDrawInputLayout, BrushGpuPipelineSpec, BrushPipelineError};
-use glaphica_core::{ATLAS_TILE_SIZE, BrushId, TextureFormat};
+use glaphica_core::{ATLAS_TILE_SIZE, BrushId, TextureFormat, TileKey};
 use thread_protocol::{DrawBlendMode, DrawOp};
 
 use crate::atlas_runtime::{AtlasBackendResource, AtlasStorageRuntime};
@@ -36,6 +36,11 @@ pub enum WgpuBrushExecutorError {
         brush_id: BrushId,
         label: &'static str,
     },
+    PipelineValidationFailed {
+        brush_id: BrushId,
+        label: &'static str,
+        message: String,
+    },
     UnsupportedAtlasSampleType {
         brush_id: BrushId,
         backend_role: &'static str,
@@ -97,6 +102,15 @@ impl Display for WgpuBrushExecutorError {
                 "wgpu pipeline creation panicked for brush {} (label: {label})",
                 brush_id.0
             ),
+            Self::PipelineValidationFailed {
+                brush_id,
+                label,
+                message,
+            } => write!(
+                f,
+                "wgpu pipeline validation failed for brush {} (label: {label}): {message}",
+                brush_id.0
+            ),
             Self::UnsupportedAtlasSampleType {
                 brush_id,
                 backend_role,
@@ -137,6 +151,13 @@ struct AtlasBindGroupLayoutKey {
     cache_sample_type: wgpu::TextureSampleType,
 }
 
+/// Target format and atlas layout a brush's pipelines were built against.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct BrushPipelineKey {
+    target_format: wgpu::TextureFormat,
+    atlas_layout_key: AtlasBindGroupLayoutKey,
+}
+
 #[derive(Debug)]
 struct CachedAtlasBindGroupLayout {
     key: AtlasBindGroupLayoutKey,
@@ -148,7 +169,7 @@ struct StrokeAtlasBindGroupKey {
     source_backend_id: u8,
     cache_backend_id: Option<u8>,
     has_ref_image: bool,
-    has_cache_tile: bool,
+    binds_cache_backend: bool,
     layout_key: AtlasBindGroupLayoutKey,
 }
 
@@ -203,7 +224,7 @@ struct BrushShaderParams {
     tint_r: f32,
     tint_g: f32,
     tint_b: f32,
-    _pad1: f32,
+    has_ref_image: u32,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -236,6 +257,8 @@ struct BrushContext {
     alpha_pipeline: Option<wgpu::RenderPipeline>,
     additive_pipeline: Option<wgpu::RenderPipeline>,
     replace_pipeline: Option<wgpu::RenderPipeline>,
+    pipeline_key: Option<BrushPipelineKey>,
+    tip_uploaded: bool,
     draw_ring: Option<BrushDrawRing>,
     stroke_cached_bind_groups: Vec<CachedStrokeAtlasBindGroup>,
     cached_stroke_id: Option<StrokeId>,
@@ -404,7 +427,7 @@ impl WgpuBrushExecutor {
             layout,
             brush_id,
             key.has_ref_image,
-            key.has_cache_tile,
+            key.binds_cache_backend,
         )?;
         let brush_context = self
             .brushes
@@ -436,6 +459,8 @@ impl WgpuBrushExecutor {
             alpha_pipeline: None,
             additive_pipeline: None,
             replace_pipeline: None,
+            pipeline_key: None,
+            tip_uploaded: false,
             draw_ring: None,
             stroke_cached_bind_groups: Vec::new(),
             cached_stroke_id: None,
@@ -443,6 +468,101 @@ impl WgpuBrushExecutor {
         Ok(())
     }
 
+    /// Replaces a configured brush's shader.
+    ///
+    /// All three blend pipelines are built from the new source inside a
+    /// validation error scope, against the layouts the brush last drew with
+    /// (or `default_target_format` if it has not drawn yet). The brush keeps
+    /// its current shader and pipelines unless every pipeline builds.
+    pub async fn reload_brush_shader(
+        &mut self,
+        context: &WgpuBrushContext<'_>,
+        brush_id: BrushId,
+        wgsl_source: std::borrow::Cow<'static, str>,
+        default_target_format: wgpu::TextureFormat,
+    ) -> Result<(), WgpuBrushExecutorError> {
+        let brush_index = Self::brush_index(brush_id)?;
+        let brush_context = self
+            .brushes
+            .get(brush_index)
+            .and_then(|entry| entry.as_ref())
+            .ok_or(WgpuBrushExecutorError::BrushNotConfigured { brush_id })?;
+        let mut spec = brush_context.spec.clone();
+        spec.wgsl_source = wgsl_source;
+        let pipeline_key = match brush_context.pipeline_key {
+            Some(pipeline_key) => pipeline_key,
+            None => {
+                let cache_format = match brush_context.cache_backend_id {
+                    Some(backend_id) => {
+                        context
+                            .atlas_storage
+                            .backend_resource(backend_id)
+                            .ok_or(WgpuBrushExecutorError::MissingCacheBackend {
+                                brush_id,
+                                backend_id,
+                            })?
+                            .format
+                    }
+                    None => wgpu::TextureFormat::Rgba8Unorm,
+                };
+                BrushPipelineKey {
+                    target_format: default_target_format,
+                    atlas_layout_key: Self::atlas_layout_key(
+                        brush_id,
+                        default_target_format,
+                        cache_format,
+                        context.gpu_context.device.features(),
+                    )?,
+                }
+            }
+        };
+
+        let device = &context.gpu_context.device;
+        let draw_bind_group_layout = self.ensure_draw_bind_group_layout(device);
+        let atlas_bind_group_layout =
+            self.ensure_atlas_bind_group_layout(device, pipeline_key.atlas_layout_key);
+        let bind_group_layouts = [&draw_bind_group_layout, &atlas_bind_group_layout];
+
+        let error_scope = device.push_error_scope(wgpu::ErrorFilter::Validation);
+        let pipelines = [
+            DrawBlendMode::Alpha,
+            DrawBlendMode::Additive,
+            DrawBlendMode::Replace,
+        ]
+        .map(|blend_mode| {
+            Self::create_render_pipeline(
+                device,
+                &spec,
+                pipeline_key.target_format,
+                &bind_group_layouts,
+                brush_id,
+                blend_mode,
+            )
+        });
+        if let Some(error) = error_scope.pop().await {
+            return Err(WgpuBrushExecutorError::PipelineValidationFailed {
+                brush_id,
+                label: spec.label,
+                message: error.to_string(),
+            });
+        }
+        let [alpha_pipeline, additive_pipeline, replace_pipeline] = pipelines;
+        let (alpha_pipeline, additive_pipeline, replace_pipeline) =
+            (alpha_pipeline?, additive_pipeline?, replace_pipeline?);
+
+        let brush_context = self
+            .brushes
+            .get_mut(brush_index)
+            .and_then(|entry| entry.as_mut())
+            .ok_or(WgpuBrushExecutorError::BrushNotConfigured { brush_id })?;
+        brush_context.spec = spec;
+        brush_context.alpha_pipeline = Some(alpha_pipeline);
+        brush_context.additive_pipeline = Some(additive_pipeline);
+        brush_context.replace_pipeline = Some(replace_pipeline);
+        brush_context.pipeline_key = Some(pipeline_key);
+        Ok(())
+    }
+
     pub fn clear_transient_draw_resources(&mut self) {
         self.transient_draw_resources.clear();
         for brush in &mut self.brushes {
@@ -558,6 +678,86 @@ impl WgpuBrushExecutor {
         sampler
     }
 
+    /// Writes the brush's tip sheet into its cache backend before its first
+    /// draw. The brush owns that backend outright, so the sheet takes the
+    /// even-parity slots from 0 in order and keeps them until the brush is
+    /// reconfigured.
+    fn ensure_tip_uploaded(
+        &mut self,
+        context: &WgpuBrushContext<'_>,
+        brush_id: BrushId,
+        brush_index: usize,
+    ) -> Result<(), WgpuBrushExecutorError> {
+        let brush_context = self
+            .brushes
+            .get_mut(brush_index)
+            .and_then(|entry| entry.as_mut())
+            .ok_or(WgpuBrushExecutorError::BrushNotConfigured { brush_id })?;
+        if brush_context.tip_uploaded {
+            return Ok(());
+        }
+        let (Some(tip_image), Some(cache_backend_id)) = (
+            brush_context.spec.tip_image.as_ref(),
+            brush_context.cache_backend_id,
+        ) else {
+            return Err(WgpuBrushExecutorError::InternalInvariantViolation {
+                brush_id,
+                context: "tip brushes need a tip and a cache backend",
+            });
+        };
+        let cache_backend = context
+            .atlas_storage
+            .backend_resource(cache_backend_id)
+            .ok_or(WgpuBrushExecutorError::MissingCacheBackend {
+                brush_id,
+                backend_id: cache_backend_id,
+            })?;
+        let tiles_per_edge = cache_backend.texture2d_array.width() / ATLAS_TILE_SIZE;
+        let even_slots = tiles_per_edge * tiles_per_edge * cache_backend.layers.div_ceil(2);
+
+        let sheet = tip_image.mip_sheet();
+        if sheet.slot_count() > even_slots {
+            return Err(WgpuBrushExecutorError::InternalInvariantViolation {
+                brush_id,
+                context: "tip sheet does not fit the brush cache backend",
+            });
+        }
+        for slot in 0..sheet.slot_count() {
+            let resolved = context
+                .atlas_storage
+                .resolve(TileKey::from_parts(cache_backend_id, 0, slot))
+                .ok_or(WgpuBrushExecutorError::MissingCacheBackend {
+                    brush_id,
+                    backend_id: cache_backend_id,
+                })?;
+            context.gpu_context.queue.write_texture(
+                wgpu::TexelCopyTextureInfo {
+                    texture: resolved.texture2d_array,
+                    mip_level: 0,
+                    origin: wgpu::Origin3d {
+                        x: resolved.address.texel_offset.0,
+                        y: resolved.address.texel_offset.1,
+                        z: resolved.address.layer,
+                    },
+                    aspect: wgpu::TextureAspect::All,
+                },
+                &sheet.slot_texels(slot),
+                wgpu::TexelCopyBufferLayout {
+                    offset: 0,
+                    bytes_per_row: Some(ATLAS_TILE_SIZE),
+                    rows_per_image: Some(ATLAS_TILE_SIZE),
+                },
+                wgpu::Extent3d {
+                    width: ATLAS_TILE_SIZE,
+                    height: ATLAS_TILE_SIZE,
+                    depth_or_array_layers: 1,
+                },
+            );
+        }
+        brush_context.tip_uploaded = true;
+        Ok(())
+    }
+
     fn ensure_dummy_cache_texture<'a>(
         &'a mut self,
         device: &wgpu::Device,
@@ -626,22 +826,43 @@ impl WgpuBrushExecutor {
         }
     }
 
+    fn atlas_layout_key(
+        brush_id: BrushId,
+        source_format: wgpu::TextureFormat,
+        cache_format: wgpu::TextureFormat,
+        device_features: wgpu::Features,
+    ) -> Result<AtlasBindGroupLayoutKey, WgpuBrushExecutorError> {
+        Ok(AtlasBindGroupLayoutKey {
+            source_sample_type: Self::texture_sample_type_for_atlas(
+                brush_id,
+                "source",
+                source_format,
+                device_features,
+            )?,
+            cache_sample_type: Self::texture_sample_type_for_atlas(
+                brush_id,
+                "cache",
+                cache_format,
+                device_features,
+            )?,
+        })
+    }
+
     fn create_render_pipeline(
         device: &wgpu::Device,
         spec: &BrushGpuPipelineSpec,
         target_format: wgpu::TextureFormat,
-        draw_bind_group_layout: &wgpu::BindGroupLayout,
-        atlas_bind_group_layout: &wgpu::BindGroupLayout,
+        bind_group_layouts: &[&wgpu::BindGroupLayout],
         brush_id: BrushId,
         blend_mode: DrawBlendMode,
     ) -> Result<wgpu::RenderPipeline, WgpuBrushExecutorError> {
         let shader_module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
             label: Some(spec.label),
-            source: wgpu::ShaderSource::Wgsl(spec.wgsl_source.into()),
+            source: wgpu::ShaderSource::Wgsl(spec.wgsl_source.clone()),
         });
         let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
             label: Some(spec.label),
-            bind_group_layouts: &[draw_bind_group_layout, atlas_bind_group_layout],
+            bind_group_layouts,
             immediate_size: 0,
         });
         let create_pipeline = || {
@@ -704,7 +925,7 @@ impl WgpuBrushExecutor {
         atlas_bind_group_layout: &wgpu::BindGroupLayout,
         brush_id: BrushId,
         has_ref_image: bool,
-        has_cache_tile: bool,
+        binds_cache_backend: bool,
     ) -> Result<wgpu::BindGroup, WgpuBrushExecutorError> {
         let source_view = if has_ref_image {
             let source_backend = atlas_storage.backend_resource(source_backend_id).ok_or(
@@ -720,7 +941,7 @@ impl WgpuBrushExecutor {
             self.ensure_dummy_cache_texture(device).clone()
         };
 
-        let cache_view = match (cache_backend_id, has_cache_tile) {
+        let cache_view = match (cache_backend_id, binds_cache_backend) {
             (_, false) => self.ensure_dummy_cache_texture(device).clone(),
             (Some(cache_backend_id), true) => {
                 let cache_backend = atlas_storage.backend_resource(cache_backend_id).ok_or(
@@ -772,6 +993,7 @@ impl WgpuBrushExecutor {
             needs_alpha_pipeline,
             needs_additive_pipeline,
             needs_replace_pipeline,
+            has_tip,
             spec,
         ) = {
             let brush_context = self
@@ -784,12 +1006,19 @@ impl WgpuBrushExecutor {
                 .ok_or(WgpuBrushExecutorError::BrushNotConfigured {
                     brush_id: draw_op.brush_id,
                 })?;
+            let needs_alpha_pipeline = brush_context.alpha_pipeline.is_none();
+            let needs_additive_pipeline = brush_context.additive_pipeline.is_none();
+            let needs_replace_pipeline = brush_context.replace_pipeline.is_none();
+            // Only clone the spec (and its shader source) when a pipeline must be built.
+            let spec = (needs_alpha_pipeline || needs_additive_pipeline || needs_replace_pipeline)
+                .then(|| brush_context.spec.clone());
             (
                 brush_context.cache_backend_id,
-                brush_context.alpha_pipeline.is_none(),
-                brush_context.additive_pipeline.is_none(),
-                brush_context.replace_pipeline.is_none(),
-                brush_context.spec,
+                needs_alpha_pipeline,
+                needs_additive_pipeline,
+                needs_replace_pipeline,
+                brush_context.spec.tip_image.is_some(),
+                spec,
             )
         };
 
@@ -853,25 +1082,18 @@ impl WgpuBrushExecutor {
             .map(|b| b.format)
             .unwrap_or(wgpu::TextureFormat::Rgba8Unorm);
 
-        let atlas_layout_key = AtlasBindGroupLayoutKey {
-            source_sample_type: Self::texture_sample_type_for_atlas(
-                draw_op.brush_id,
-                "source",
-                source_resolved.format,
-                device_features,
-            )?,
-            cache_sample_type: Self::texture_sample_type_for_atlas(
-                draw_op.brush_id,
-                "cache",
-                cache_format,
-                device_features,
-            )?,
-        };
+        let atlas_layout_key = Self::atlas_layout_key(
+            draw_op.brush_id,
+            source_resolved.format,
+            cache_format,
+            device_features,
+        )?;
 
         let draw_bind_group_layout =
             self.ensure_draw_bind_group_layout(&context.gpu_context.device);
         let atlas_bind_group_layout =
             self.ensure_atlas_bind_group_layout(&context.gpu_context.device, atlas_layout_key);
+        let bind_group_layouts = [&draw_bind_group_layout, &atlas_bind_group_layout];
         let cache_resolved = if draw_op.origin_tile == glaphica_core::TileKey::EMPTY {
             None
         } else {
@@ -890,6 +1112,12 @@ impl WgpuBrushExecutor {
                 .ok_or(WgpuBrushExecutorError::BrushNotConfigured {
                     brush_id: draw_op.brush_id,
                 })?;
+            if spec.is_some() {
+                brush_context.pipeline_key = Some(BrushPipelineKey {
+                    target_format: resolved.format,
+                    atlas_layout_key,
+                });
+            }
             if brush_context.cached_stroke_id != Some(draw_op.stroke_id) {
                 brush_context.cached_stroke_id = Some(draw_op.stroke_id);
                 brush_context.stroke_cached_bind_groups.clear();
@@ -899,7 +1127,8 @@ impl WgpuBrushExecutor {
             source_backend_id,
             cache_backend_id,
             has_ref_image: draw_op.ref_image.is_some(),
-            has_cache_tile: cache_resolved.is_some(),
+            // A tip brush samples its cache backend on every draw.
+            binds_cache_backend: cache_resolved.is_some() || has_tip,
             layout_key: atlas_layout_key,
         };
         let atlas_bind_group = self.get_or_create_stroke_atlas_bind_group(
@@ -911,13 +1140,12 @@ impl WgpuBrushExecutor {
             &atlas_bind_group_layout,
         )?;
 
-        if needs_alpha_pipeline {
+        if needs_alpha_pipeline && let Some(spec) = &spec {
             let pipeline = Self::create_render_pipeline(
                 &context.gpu_context.device,
-                &spec,
+                spec,
                 resolved.format,
-                &draw_bind_group_layout,
-                &atlas_bind_group_layout,
+                &bind_group_layouts,
                 draw_op.brush_id,
                 DrawBlendMode::Alpha,
             )?;
@@ -935,13 +1163,12 @@ impl WgpuBrushExecutor {
             brush_context.alpha_pipeline = Some(pipeline);
         }
 
-        if needs_additive_pipeline {
+        if needs_additive_pipeline && let Some(spec) = &spec {
             let pipeline = Self::create_render_pipeline(
                 &context.gpu_context.device,
-                &spec,
+                spec,
                 resolved.format,
-                &draw_bind_group_layout,
-                &atlas_bind_group_layout,
+                &bind_group_layouts,
                 draw_op.brush_id,
                 DrawBlendMode::Additive,
             )?;
@@ -959,13 +1186,12 @@ impl WgpuBrushExecutor {
             brush_context.additive_pipeline = Some(pipeline);
         }
 
-        if needs_replace_pipeline {
+        if needs_replace_pipeline && let Some(spec) = &spec {
             let pipeline = Self::create_render_pipeline(
                 &context.gpu_context.device,
-                &spec,
+                spec,
                 resolved.format,
-                &draw_bind_group_layout,
-                &atlas_bind_group_layout,
+                &bind_group_layouts,
                 draw_op.brush_id,
                 DrawBlendMode::Replace,
             )?;
@@ -1023,6 +1249,10 @@ impl WgpuBrushExecutor {
             }
         };
 
+        if has_tip {
+            self.ensure_tip_uploaded(context, draw_op.brush_id, brush_index)?;
+        }
+
         let input_bytes = encode_input_bytes(&draw_op.input);
         let params = BrushShaderParams {
             input_len: draw_op.input.len() as u32,
@@ -1048,7 +1278,7 @@ impl WgpuBrushExecutor {
             tint_r: draw_op.rgb[0],
             tint_g: draw_op.rgb[1],
             tint_b: draw_op.rgb[2],
-            _pad1: 0.0,
+            has_ref_image: if draw_op.ref_image.is_some() { 1 } else { 0 },
         };
         let params_bytes = encode_shader_params_bytes(params);
         let limits = context.gpu_context.device.limits();
@@ -1438,7 +1668,7 @@ fn encode_shader_params_bytes(params: BrushShaderParams) -> [u8; 72] {
     bytes[56..60].copy_from_slice(&params.tint_r.to_ne_bytes());
     bytes[60..64].copy_from_slice(&params.tint_g.to_ne_bytes());
     bytes[64..68].copy_from_slice(&params.tint_b.to_ne_bytes());
-    bytes[68..72].copy_from_slice(&params._pad1.to_ne_bytes());
+    bytes[68..72].copy_from_slice(&params.has_ref_image.to_ne_bytes());
     bytes
 }
 
@@ -1472,7 +1702,7 @@ mod tests {
             tint_r: 0.25,
             tint_g: 0.5,
             tint_b: 0.75,
-            _pad1: 0.0,
+            has_ref_image: 0,
         };
         let encoded = encode_shader_params_bytes(params);
         assert_eq!(encoded.len(), 72);
@@ -3,4 +3,4 @@ pub mod layout;
 mod stored_image;
 
 pub use image::{Image, ImageCreateError, ImageTileAccessError, NonEmptyTileBounds};
-pub use stored_image::{StoredImage, StoredImageError};
+pub use stored_image::{StoredImage, StoredImageError, StoredPixelFormat};
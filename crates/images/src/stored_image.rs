@@ -1,3 +1,4 @@
+use std::borrow::Cow;
 use std::error::Error;
 use std::fmt::{Display, Formatter};
 
@@ -6,7 +7,7 @@ use serde::{Deserialize, Serialize};
 
 use crate::layout::ImageLayout;
 
-const RGBA_BYTES_PER_PIXEL: usize = 4;
+const RGBA_CHANNELS: usize = 4;
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum StoredImageError {
@@ -21,7 +22,7 @@ impl Display for StoredImageError {
             Self::InvalidPixelCount { expected, actual } => {
                 write!(
                     f,
-                    "stored image pixel count mismatch: expected {expected} bytes, got {actual}"
+                    "stored image pixel count mismatch: expected {expected} samples, got {actual}"
                 )
             }
             Self::TooLarge => write!(f, "stored image dimensions are too large"),
@@ -32,11 +33,27 @@ impl Display for StoredImageError {
 
 impl Error for StoredImageError {}
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum StoredPixelFormat {
+    Rgba8,
+    /// Straight `u16` channels, used for readbacks from high-precision atlases.
+    Rgba16,
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct StoredImage {
     width: u32,
     height: u32,
-    pixels_rgba8: Vec<u8>,
+    #[serde(flatten)]
+    pixels: StoredPixels,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+enum StoredPixels {
+    #[serde(rename = "pixels_rgba8")]
+    Rgba8(Vec<u8>),
+    #[serde(rename = "pixels_rgba16")]
+    Rgba16(Vec<u16>),
 }
 
 impl StoredImage {
@@ -45,17 +62,24 @@ impl StoredImage {
         height: u32,
         pixels_rgba8: Vec<u8>,
     ) -> Result<Self, StoredImageError> {
-        let expected = expected_rgba8_len(width, height)?;
-        if pixels_rgba8.len() != expected {
-            return Err(StoredImageError::InvalidPixelCount {
-                expected,
-                actual: pixels_rgba8.len(),
-            });
-        }
+        validate_sample_count(width, height, pixels_rgba8.len())?;
+        Ok(Self {
+            width,
+            height,
+            pixels: StoredPixels::Rgba8(pixels_rgba8),
+        })
+    }
+
+    pub fn new_rgba16(
+        width: u32,
+        height: u32,
+        pixels_rgba16: Vec<u16>,
+    ) -> Result<Self, StoredImageError> {
+        validate_sample_count(width, height, pixels_rgba16.len())?;
         Ok(Self {
             width,
             height,
-            pixels_rgba8,
+            pixels: StoredPixels::Rgba16(pixels_rgba16),
         })
     }
 
@@ -71,8 +95,46 @@ impl StoredImage {
         ImageLayout::new(self.width, self.height)
     }
 
-    pub fn pixels_rgba8(&self) -> &[u8] {
-        &self.pixels_rgba8
+    pub fn format(&self) -> StoredPixelFormat {
+        match self.pixels {
+            StoredPixels::Rgba8(_) => StoredPixelFormat::Rgba8,
+            StoredPixels::Rgba16(_) => StoredPixelFormat::Rgba16,
+        }
+    }
+
+    /// Pixels as RGBA8, rounding 16-bit channels down to 8 bits.
+    pub fn pixels_rgba8(&self) -> Cow<'_, [u8]> {
+        match &self.pixels {
+            StoredPixels::Rgba8(pixels) => Cow::Borrowed(pixels),
+            StoredPixels::Rgba16(pixels) => {
+                Cow::Owned(pixels.iter().map(|value| u16_to_u8(*value)).collect())
+            }
+        }
+    }
+
+    /// Pixels as RGBA16, widening 8-bit channels losslessly.
+    pub fn pixels_rgba16(&self) -> Cow<'_, [u16]> {
+        match &self.pixels {
+            StoredPixels::Rgba8(pixels) => {
+                Cow::Owned(pixels.iter().map(|value| u8_to_u16(*value)).collect())
+            }
+            StoredPixels::Rgba16(pixels) => Cow::Borrowed(pixels),
+        }
+    }
+
+    pub fn to_format(&self, format: StoredPixelFormat) -> Self {
+        if self.format() == format {
+            return self.clone();
+        }
+        let pixels = match format {
+            StoredPixelFormat::Rgba8 => StoredPixels::Rgba8(self.pixels_rgba8().into_owned()),
+            StoredPixelFormat::Rgba16 => StoredPixels::Rgba16(self.pixels_rgba16().into_owned()),
+        };
+        Self {
+            width: self.width,
+            height: self.height,
+            pixels,
+        }
     }
 
     pub fn collect_non_empty_tile_indices(&self, output: &mut Vec<usize>) {
@@ -89,15 +151,34 @@ impl StoredImage {
         &self,
         tile_index: usize,
         output: &mut Vec<u8>,
+    ) -> Result<(), StoredImageError> {
+        self.copy_tile_samples(tile_index, output, |value| value, u16_to_u8)
+    }
+
+    /// Copies one tile as RGBA16, widening 8-bit channels losslessly.
+    pub fn copy_tile_rgba16(
+        &self,
+        tile_index: usize,
+        output: &mut Vec<u16>,
+    ) -> Result<(), StoredImageError> {
+        self.copy_tile_samples(tile_index, output, u8_to_u16, |value| value)
+    }
+
+    fn copy_tile_samples<T: Copy + Default>(
+        &self,
+        tile_index: usize,
+        output: &mut Vec<T>,
+        from_u8: fn(u8) -> T,
+        from_u16: fn(u16) -> T,
     ) -> Result<(), StoredImageError> {
         let layout = self.layout();
         let tile_origin = layout
             .tile_canvas_origin(tile_index)
             .ok_or(StoredImageError::TileOutOfBounds)?;
         let tile_width = IMAGE_TILE_SIZE as usize;
-        let tile_len = tile_width * tile_width * RGBA_BYTES_PER_PIXEL;
+        let tile_len = tile_width * tile_width * RGBA_CHANNELS;
         output.clear();
-        output.resize(tile_len, 0);
+        output.resize(tile_len, T::default());
 
         let image_width = self.width as usize;
         let image_height = self.height as usize;
@@ -114,11 +195,23 @@ impl StoredImage {
                 break;
             }
 
-            let src_start = (src_y * image_width + origin_x) * RGBA_BYTES_PER_PIXEL;
-            let src_end = src_start + copy_width * RGBA_BYTES_PER_PIXEL;
-            let dst_start = row * tile_width * RGBA_BYTES_PER_PIXEL;
-            let dst_end = dst_start + copy_width * RGBA_BYTES_PER_PIXEL;
-            output[dst_start..dst_end].copy_from_slice(&self.pixels_rgba8[src_start..src_end]);
+            let src_start = (src_y * image_width + origin_x) * RGBA_CHANNELS;
+            let src_end = src_start + copy_width * RGBA_CHANNELS;
+            let dst_start = row * tile_width * RGBA_CHANNELS;
+            let dst_end = dst_start + copy_width * RGBA_CHANNELS;
+            let dst = &mut output[dst_start..dst_end];
+            match &self.pixels {
+                StoredPixels::Rgba8(pixels) => {
+                    for (dst, src) in dst.iter_mut().zip(&pixels[src_start..src_end]) {
+                        *dst = from_u8(*src);
+                    }
+                }
+                StoredPixels::Rgba16(pixels) => {
+                    for (dst, src) in dst.iter_mut().zip(&pixels[src_start..src_end]) {
+                        *dst = from_u16(*src);
+                    }
+                }
+            }
         }
 
         Ok(())
@@ -139,12 +232,17 @@ impl StoredImage {
         let max_y = (origin_y + tile_size).min(image_height);
 
         for y in origin_y..max_y {
-            let row_start = (y * image_width + origin_x) * RGBA_BYTES_PER_PIXEL;
-            let row_end = (y * image_width + max_x) * RGBA_BYTES_PER_PIXEL;
-            if self.pixels_rgba8[row_start..row_end]
-                .iter()
-                .any(|channel| *channel != 0)
-            {
+            let row_start = (y * image_width + origin_x) * RGBA_CHANNELS;
+            let row_end = (y * image_width + max_x) * RGBA_CHANNELS;
+            let non_zero = match &self.pixels {
+                StoredPixels::Rgba8(pixels) => pixels[row_start..row_end]
+                    .iter()
+                    .any(|channel| *channel != 0),
+                StoredPixels::Rgba16(pixels) => pixels[row_start..row_end]
+                    .iter()
+                    .any(|channel| *channel != 0),
+            };
+            if non_zero {
                 return true;
             }
         }
@@ -153,21 +251,37 @@ impl StoredImage {
     }
 }
 
-fn expected_rgba8_len(width: u32, height: u32) -> Result<usize, StoredImageError> {
+fn validate_sample_count(width: u32, height: u32, actual: usize) -> Result<(), StoredImageError> {
+    let expected = expected_rgba_sample_len(width, height)?;
+    if actual != expected {
+        return Err(StoredImageError::InvalidPixelCount { expected, actual });
+    }
+    Ok(())
+}
+
+fn expected_rgba_sample_len(width: u32, height: u32) -> Result<usize, StoredImageError> {
     let pixels = u64::from(width)
         .checked_mul(u64::from(height))
         .ok_or(StoredImageError::TooLarge)?;
-    let bytes = pixels
-        .checked_mul(RGBA_BYTES_PER_PIXEL as u64)
+    let samples = pixels
+        .checked_mul(RGBA_CHANNELS as u64)
         .ok_or(StoredImageError::TooLarge)?;
-    usize::try_from(bytes).map_err(|_| StoredImageError::TooLarge)
+    usize::try_from(samples).map_err(|_| StoredImageError::TooLarge)
+}
+
+fn u8_to_u16(value: u8) -> u16 {
+    u16::from(value) * 257
+}
+
+fn u16_to_u8(value: u16) -> u8 {
+    ((u32::from(value) * 255 + 32767) / 65535) as u8
 }
 
 #[cfg(test)]
 mod tests {
     use glaphica_core::IMAGE_TILE_SIZE;
 
-    use super::{StoredImage, StoredImageError};
+    use super::{StoredImage, StoredImageError, StoredPixelFormat};
 
     #[test]
     fn rejects_invalid_rgba8_len() {
@@ -211,4 +325,25 @@ mod tests {
         assert_eq!(&tile[..4], &[9, 8, 7, 6]);
         assert!(tile[4..].iter().all(|value| *value == 0));
     }
+
+    #[test]
+    fn rgba16_round_trips_through_rgba8_conversion() {
+        let image = StoredImage::new_rgba16(1, 1, vec![0, 32768, 65535, 257]).unwrap();
+        assert_eq!(image.format(), StoredPixelFormat::Rgba16);
+        assert_eq!(&*image.pixels_rgba8(), &[0, 128, 255, 1]);
+
+        let widened = image
+            .to_format(StoredPixelFormat::Rgba8)
+            .to_format(StoredPixelFormat::Rgba16);
+        assert_eq!(&*widened.pixels_rgba16(), &[0, 32896, 65535, 257]);
+
+        let mut tile = Vec::new();
+        image.copy_tile_rgba8(0, &mut tile).unwrap();
+        assert_eq!(&tile[..4], &[0, 128, 255, 1]);
+
+        let mut tile = Vec::new();
+        image.copy_tile_rgba16(0, &mut tile).unwrap();
+        assert_eq!(&tile[..4], &[0, 32768, 65535, 257]);
+        assert!(tile[4..].iter().all(|value| *value == 0));
+    }
 }
@@ -0,0 +1,208 @@
+use glaphica_core::{InputDeviceKind, MappedCursor, RadianVec2};
+
+pub const PRESSURE_CURVE_POINTS: usize = 5;
+/// Quantiles of a recorded stroke's pressures the fitted curve passes
+/// through; the ends skip outliers from touch-down and lift-off.
+const FIT_QUANTILES: [f32; PRESSURE_CURVE_POINTS] = [0.02, 0.25, 0.5, 0.75, 0.98];
+/// Samples lighter than this are the pen hovering or lifting, not pressing.
+const FIT_MIN_PRESSURE: f32 = 0.001;
+/// Narrowest pressure range a recorded stroke must cover to be fitted.
+const FIT_MIN_RANGE: f32 = 0.05;
+const FIT_MIN_SAMPLES: usize = 16;
+/// Smallest input step between neighbouring curve points.
+const MIN_POINT_GAP: f32 = 1e-3;
+
+/// Maps the pressure a device reports onto the pressure brushes see,
+/// piecewise linear through `points` (`[input, output]`, inputs ascending)
+/// and flat beyond the first and last point.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PressureCurve {
+    pub points: [[f32; 2]; PRESSURE_CURVE_POINTS],
+}
+
+impl PressureCurve {
+    pub const IDENTITY: Self = Self {
+        points: [
+            [0.0, 0.0],
+            [0.25, 0.25],
+            [0.5, 0.5],
+            [0.75, 0.75],
+            [1.0, 1.0],
+        ],
+    };
+
+    pub fn eval(&self, pressure: f32) -> f32 {
+        let points = &self.points;
+        let first = points[0];
+        let last = points[PRESSURE_CURVE_POINTS - 1];
+        if pressure <= first[0] {
+            return first[1];
+        }
+        if pressure >= last[0] {
+            return last[1];
+        }
+        for pair in points.windows(2) {
+            let ([x0, y0], [x1, y1]) = (pair[0], pair[1]);
+            if pressure <= x1 {
+                let span = x1 - x0;
+                if span <= 0.0 {
+                    return y1;
+                }
+                return y0 + (y1 - y0) * (pressure - x0) / span;
+            }
+        }
+        last[1]
+    }
+
+    /// Fits a curve that spreads the pressures of a recorded stroke evenly
+    /// over the full range, so a natural light-to-firm stroke on this device
+    /// reaches both ends. `None` when the stroke is too short or too flat.
+    pub fn fit(pressures: &[f32]) -> Option<Self> {
+        let mut sorted = pressures
+            .iter()
+            .copied()
+            .filter(|pressure| pressure.is_finite() && *pressure >= FIT_MIN_PRESSURE)
+            .map(|pressure| pressure.min(1.0))
+            .collect::<Vec<_>>();
+        if sorted.len() < FIT_MIN_SAMPLES {
+            return None;
+        }
+        sorted.sort_by(f32::total_cmp);
+        let quantile = |q: f32| sorted[((sorted.len() - 1) as f32 * q).round() as usize];
+        if quantile(FIT_QUANTILES[PRESSURE_CURVE_POINTS - 1]) - quantile(FIT_QUANTILES[0])
+            < FIT_MIN_RANGE
+        {
+            return None;
+        }
+
+        let mut points = [[0.0; 2]; PRESSURE_CURVE_POINTS];
+        let mut previous_input = f32::NEG_INFINITY;
+        for (index, point) in points.iter_mut().enumerate() {
+            let input = quantile(FIT_QUANTILES[index]).max(previous_input + MIN_POINT_GAP);
+            let output = index as f32 / (PRESSURE_CURVE_POINTS - 1) as f32;
+            *point = [input, output];
+            previous_input = input;
+        }
+        Some(Self { points })
+    }
+}
+
+impl Default for PressureCurve {
+    fn default() -> Self {
+        Self::IDENTITY
+    }
+}
+
+/// Global input correction for one kind of device, applied before any brush
+/// dynamics.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct DeviceCalibration {
+    pub pressure: PressureCurve,
+    /// Tilt magnitude in radians read as upright; larger tilts are reduced
+    /// by the same amount so there is no jump at the edge.
+    pub tilt_deadzone: f32,
+}
+
+impl DeviceCalibration {
+    pub fn apply(&self, mut cursor: MappedCursor) -> MappedCursor {
+        cursor.pressure = self.pressure.eval(cursor.pressure).clamp(0.0, 1.0);
+        if self.tilt_deadzone > 0.0 {
+            let magnitude = cursor.tilt.x.hypot(cursor.tilt.y);
+            cursor.tilt = if magnitude <= self.tilt_deadzone {
+                RadianVec2::new(0.0, 0.0)
+            } else {
+                let scale = (magnitude - self.tilt_deadzone) / magnitude;
+                RadianVec2::new(cursor.tilt.x * scale, cursor.tilt.y * scale)
+            };
+        }
+        cursor
+    }
+}
+
+/// Calibration per [`InputDeviceKind`]; every finger shares one.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct DeviceCalibrations {
+    pub pen: DeviceCalibration,
+    pub cursor: DeviceCalibration,
+    pub finger: DeviceCalibration,
+}
+
+impl DeviceCalibrations {
+    pub fn get(&self, device: InputDeviceKind) -> &DeviceCalibration {
+        match device {
+            InputDeviceKind::Pen => &self.pen,
+            InputDeviceKind::Cursor => &self.cursor,
+            InputDeviceKind::Finger(_) => &self.finger,
+        }
+    }
+
+    pub fn get_mut(&mut self, device: InputDeviceKind) -> &mut DeviceCalibration {
+        match device {
+            InputDeviceKind::Pen => &mut self.pen,
+            InputDeviceKind::Cursor => &mut self.cursor,
+            InputDeviceKind::Finger(_) => &mut self.finger,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use glaphica_core::{CanvasVec2, MappedCursor, RadianVec2};
+
+    use super::{DeviceCalibration, PressureCurve};
+
+    #[test]
+    fn curve_interpolates_between_points_and_holds_past_the_ends() {
+        let curve = PressureCurve {
+            points: [[0.1, 0.0], [0.2, 0.5], [0.3, 0.7], [0.4, 0.9], [0.6, 1.0]],
+        };
+        assert_eq!(curve.eval(0.0), 0.0);
+        assert!((curve.eval(0.15) - 0.25).abs() < 1e-6);
+        assert!((curve.eval(0.5) - 0.95).abs() < 1e-6);
+        assert_eq!(curve.eval(1.0), 1.0);
+        assert_eq!(PressureCurve::IDENTITY.eval(0.37), 0.37);
+    }
+
+    #[test]
+    fn fit_spreads_a_narrow_device_range_over_the_full_range() {
+        // A stiff pen that never reports above 0.4.
+        let pressures = (0..200)
+            .map(|index| 0.1 + 0.3 * index as f32 / 199.0)
+            .chain([0.0; 20])
+            .collect::<Vec<_>>();
+        let curve = PressureCurve::fit(&pressures).unwrap();
+
+        assert!(curve.eval(0.1) < 0.05);
+        assert!((curve.eval(0.25) - 0.5).abs() < 0.02);
+        assert!(curve.eval(0.4) > 0.95);
+        assert!(
+            curve
+                .points
+                .windows(2)
+                .all(|pair| pair[1][0] > pair[0][0] && pair[1][1] > pair[0][1])
+        );
+    }
+
+    #[test]
+    fn fit_rejects_short_or_flat_strokes() {
+        assert_eq!(PressureCurve::fit(&[0.5; 8]), None);
+        assert_eq!(PressureCurve::fit(&[0.5; 100]), None);
+    }
+
+    #[test]
+    fn tilt_deadzone_zeroes_small_tilts_and_shifts_larger_ones() {
+        let calibration = DeviceCalibration {
+            pressure: PressureCurve::IDENTITY,
+            tilt_deadzone: 0.1,
+        };
+        let cursor = |tilt_x: f32| MappedCursor {
+            cursor: CanvasVec2::new(0.0, 0.0),
+            tilt: RadianVec2::new(tilt_x, 0.0),
+            pressure: 0.5,
+            twist: 0.0,
+        };
+        assert_eq!(calibration.apply(cursor(0.05)).tilt.x, 0.0);
+        assert!((calibration.apply(cursor(0.5)).tilt.x - 0.4).abs() < 1e-6);
+        assert_eq!(calibration.apply(cursor(0.5)).pressure, 0.5);
+    }
+}
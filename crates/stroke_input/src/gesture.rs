@@ -0,0 +1,442 @@
+use std::f32::consts::{PI, TAU};
+
+use glaphica_core::ScreenVec2;
+
+/// Screen px a lone finger may drift before it starts painting.
+const PAINT_SLOP_PX: f32 = 8.0;
+/// How long a lone finger waits for a second one before it starts painting.
+const PAINT_DELAY_NS: u64 = 80_000_000;
+/// Screen px either finger of a two-finger tap may drift.
+const TAP_SLOP_PX: f32 = 12.0;
+/// Longest a two-finger tap may be held.
+const TAP_MAX_NS: u64 = 250_000_000;
+/// Finger spans shorter than this give no usable zoom or angle.
+const MIN_SPAN_PX: f32 = 1.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TouchPhase {
+    Started,
+    Moved,
+    Ended,
+    Cancelled,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Gesture {
+    /// A lone finger starts painting where it touched down.
+    PaintBegan {
+        finger: u64,
+        position: ScreenVec2,
+    },
+    PaintMoved {
+        finger: u64,
+        position: ScreenVec2,
+    },
+    PaintEnded,
+    /// The system took the painting touch away before it lifted.
+    PaintCancelled,
+    /// Two fingers moved: pan by `pan`, then zoom by `zoom` and rotate by
+    /// `rotation` radians about `center`, all in screen space.
+    Transform {
+        pan: ScreenVec2,
+        zoom: f32,
+        rotation: f32,
+        center: ScreenVec2,
+    },
+    TwoFingerTap,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Finger {
+    id: u64,
+    start: ScreenVec2,
+    position: ScreenVec2,
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+enum State {
+    #[default]
+    Idle,
+    /// One finger down; it paints once it moves or waits long enough, or
+    /// joins a two-finger gesture if another lands first.
+    Pending {
+        finger: Finger,
+        started_ns: u64,
+    },
+    Painting {
+        finger: u64,
+    },
+    TwoFinger {
+        fingers: [Finger; 2],
+        started_ns: u64,
+        moved: bool,
+    },
+    /// The gesture is over; waiting for the remaining fingers to lift.
+    Lifting,
+}
+
+/// Classifies raw touch points into painting and two-finger view gestures.
+/// Fingers beyond the first two, and fingers landing while one paints, are
+/// ignored until every finger has lifted.
+#[derive(Debug, Default)]
+pub struct TouchGestureRecognizer {
+    state: State,
+    down: Vec<u64>,
+}
+
+impl TouchGestureRecognizer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn is_painting(&self) -> bool {
+        matches!(self.state, State::Painting { .. })
+    }
+
+    pub fn touch(
+        &mut self,
+        id: u64,
+        phase: TouchPhase,
+        position: ScreenVec2,
+        time_ns: u64,
+    ) -> Vec<Gesture> {
+        match phase {
+            TouchPhase::Started if !self.down.contains(&id) => self.down.push(id),
+            TouchPhase::Ended | TouchPhase::Cancelled => self.down.retain(|down| *down != id),
+            _ => {}
+        }
+
+        let mut gestures = Vec::new();
+        self.state = match (self.state, phase) {
+            (State::Idle, TouchPhase::Started) => State::Pending {
+                finger: Finger {
+                    id,
+                    start: position,
+                    position,
+                },
+                started_ns: time_ns,
+            },
+            (State::Pending { finger, started_ns }, TouchPhase::Started) if finger.id != id => {
+                State::TwoFinger {
+                    fingers: [
+                        finger,
+                        Finger {
+                            id,
+                            start: position,
+                            position,
+                        },
+                    ],
+                    started_ns,
+                    moved: false,
+                }
+            }
+            (State::Pending { finger, started_ns }, TouchPhase::Moved) if finger.id == id => {
+                if distance(finger.start, position) > PAINT_SLOP_PX
+                    || time_ns.saturating_sub(started_ns) >= PAINT_DELAY_NS
+                {
+                    gestures.push(Gesture::PaintBegan {
+                        finger: id,
+                        position: finger.start,
+                    });
+                    gestures.push(Gesture::PaintMoved {
+                        finger: id,
+                        position,
+                    });
+                    State::Painting { finger: id }
+                } else {
+                    State::Pending {
+                        finger: Finger { position, ..finger },
+                        started_ns,
+                    }
+                }
+            }
+            (State::Pending { finger, .. }, TouchPhase::Ended) if finger.id == id => {
+                // A quick tap still leaves a dab.
+                gestures.push(Gesture::PaintBegan {
+                    finger: id,
+                    position: finger.start,
+                });
+                if distance(finger.start, position) > 0.0 {
+                    gestures.push(Gesture::PaintMoved {
+                        finger: id,
+                        position,
+                    });
+                }
+                gestures.push(Gesture::PaintEnded);
+                self.settled()
+            }
+            (State::Pending { finger, .. }, TouchPhase::Cancelled) if finger.id == id => {
+                self.settled()
+            }
+            (State::Painting { finger }, TouchPhase::Moved) if finger == id => {
+                gestures.push(Gesture::PaintMoved {
+                    finger: id,
+                    position,
+                });
+                self.state
+            }
+            (State::Painting { finger }, TouchPhase::Ended) if finger == id => {
+                gestures.push(Gesture::PaintEnded);
+                self.settled()
+            }
+            (State::Painting { finger }, TouchPhase::Cancelled) if finger == id => {
+                gestures.push(Gesture::PaintCancelled);
+                self.settled()
+            }
+            (
+                State::TwoFinger {
+                    mut fingers,
+                    started_ns,
+                    mut moved,
+                },
+                TouchPhase::Moved,
+            ) => {
+                let before = fingers;
+                for finger in &mut fingers {
+                    if finger.id == id {
+                        finger.position = position;
+                        moved |= distance(finger.start, position) > TAP_SLOP_PX;
+                    }
+                }
+                gestures.extend(transform(before, fingers));
+                State::TwoFinger {
+                    fingers,
+                    started_ns,
+                    moved,
+                }
+            }
+            (
+                State::TwoFinger {
+                    fingers,
+                    started_ns,
+                    moved,
+                },
+                TouchPhase::Ended,
+            ) if fingers.iter().any(|finger| finger.id == id) => {
+                if !moved && time_ns.saturating_sub(started_ns) <= TAP_MAX_NS {
+                    gestures.push(Gesture::TwoFingerTap);
+                }
+                self.settled()
+            }
+            (State::TwoFinger { fingers, .. }, TouchPhase::Cancelled)
+                if fingers.iter().any(|finger| finger.id == id) =>
+            {
+                self.settled()
+            }
+            (State::Lifting, TouchPhase::Ended | TouchPhase::Cancelled) => self.settled(),
+            (state, _) => state,
+        };
+        gestures
+    }
+
+    /// Drops whatever gesture is in progress, as when the window loses focus.
+    pub fn reset(&mut self) {
+        self.state = State::Idle;
+        self.down.clear();
+    }
+
+    fn settled(&self) -> State {
+        if self.down.is_empty() {
+            State::Idle
+        } else {
+            State::Lifting
+        }
+    }
+}
+
+fn distance(a: ScreenVec2, b: ScreenVec2) -> f32 {
+    (b.x - a.x).hypot(b.y - a.y)
+}
+
+fn transform(before: [Finger; 2], after: [Finger; 2]) -> Option<Gesture> {
+    let center = |fingers: [Finger; 2]| {
+        ScreenVec2::new(
+            (fingers[0].position.x + fingers[1].position.x) * 0.5,
+            (fingers[0].position.y + fingers[1].position.y) * 0.5,
+        )
+    };
+    let span = |fingers: [Finger; 2]| {
+        ScreenVec2::new(
+            fingers[1].position.x - fingers[0].position.x,
+            fingers[1].position.y - fingers[0].position.y,
+        )
+    };
+    let (center_before, center_after) = (center(before), center(after));
+    let (span_before, span_after) = (span(before), span(after));
+    let length_before = span_before.x.hypot(span_before.y);
+    let length_after = span_after.x.hypot(span_after.y);
+
+    let (zoom, rotation) = if length_before >= MIN_SPAN_PX && length_after >= MIN_SPAN_PX {
+        let mut rotation = span_after.y.atan2(span_after.x) - span_before.y.atan2(span_before.x);
+        if rotation > PI {
+            rotation -= TAU;
+        } else if rotation <= -PI {
+            rotation += TAU;
+        }
+        (length_after / length_before, rotation)
+    } else {
+        (1.0, 0.0)
+    };
+    let pan = ScreenVec2::new(
+        center_after.x - center_before.x,
+        center_after.y - center_before.y,
+    );
+    if pan.x == 0.0 && pan.y == 0.0 && zoom == 1.0 && rotation == 0.0 {
+        return None;
+    }
+    Some(Gesture::Transform {
+        pan,
+        zoom,
+        rotation,
+        center: center_after,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use glaphica_core::ScreenVec2;
+
+    use super::{Gesture, TouchGestureRecognizer, TouchPhase};
+
+    const MS: u64 = 1_000_000;
+
+    fn at(x: f32, y: f32) -> ScreenVec2 {
+        ScreenVec2::new(x, y)
+    }
+
+    #[test]
+    fn one_finger_paints_once_it_leaves_the_slop() {
+        let mut touch = TouchGestureRecognizer::new();
+        assert!(
+            touch
+                .touch(1, TouchPhase::Started, at(10.0, 10.0), 0)
+                .is_empty()
+        );
+        assert!(
+            touch
+                .touch(1, TouchPhase::Moved, at(12.0, 10.0), 5 * MS)
+                .is_empty()
+        );
+        assert_eq!(
+            touch.touch(1, TouchPhase::Moved, at(30.0, 10.0), 10 * MS),
+            vec![
+                Gesture::PaintBegan {
+                    finger: 1,
+                    position: at(10.0, 10.0),
+                },
+                Gesture::PaintMoved {
+                    finger: 1,
+                    position: at(30.0, 10.0),
+                },
+            ]
+        );
+        assert!(touch.is_painting());
+        // A second finger landing mid-stroke is a resting palm, not a gesture.
+        assert!(
+            touch
+                .touch(2, TouchPhase::Started, at(200.0, 200.0), 20 * MS)
+                .is_empty()
+        );
+        assert_eq!(
+            touch.touch(1, TouchPhase::Ended, at(30.0, 10.0), 30 * MS),
+            vec![Gesture::PaintEnded]
+        );
+        assert!(
+            touch
+                .touch(2, TouchPhase::Ended, at(200.0, 200.0), 40 * MS)
+                .is_empty()
+        );
+        assert!(!touch.is_painting());
+    }
+
+    #[test]
+    fn two_fingers_pan_pinch_and_rotate_about_their_center() {
+        let mut touch = TouchGestureRecognizer::new();
+        touch.touch(1, TouchPhase::Started, at(0.0, 0.0), 0);
+        touch.touch(2, TouchPhase::Started, at(100.0, 0.0), 10 * MS);
+
+        let gestures = touch.touch(2, TouchPhase::Moved, at(0.0, 200.0), 20 * MS);
+        let [
+            Gesture::Transform {
+                pan,
+                zoom,
+                rotation,
+                center,
+            },
+        ] = gestures.as_slice()
+        else {
+            panic!("expected a transform, got {gestures:?}");
+        };
+        assert_eq!(*pan, at(-50.0, 100.0));
+        assert_eq!(*zoom, 2.0);
+        assert!((rotation - std::f32::consts::FRAC_PI_2).abs() < 1e-6);
+        assert_eq!(*center, at(0.0, 100.0));
+
+        assert!(
+            touch
+                .touch(1, TouchPhase::Ended, at(0.0, 0.0), 30 * MS)
+                .is_empty()
+        );
+        // The remaining finger does not start painting.
+        assert!(
+            touch
+                .touch(2, TouchPhase::Moved, at(50.0, 50.0), 40 * MS)
+                .is_empty()
+        );
+        assert!(
+            touch
+                .touch(2, TouchPhase::Ended, at(50.0, 50.0), 50 * MS)
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn quick_still_two_finger_touch_is_a_tap() {
+        let mut touch = TouchGestureRecognizer::new();
+        touch.touch(1, TouchPhase::Started, at(0.0, 0.0), 0);
+        touch.touch(2, TouchPhase::Started, at(80.0, 0.0), 20 * MS);
+        touch.touch(2, TouchPhase::Moved, at(82.0, 1.0), 60 * MS);
+        assert_eq!(
+            touch.touch(1, TouchPhase::Ended, at(0.0, 0.0), 120 * MS),
+            vec![Gesture::TwoFingerTap]
+        );
+        assert!(
+            touch
+                .touch(2, TouchPhase::Ended, at(82.0, 1.0), 130 * MS)
+                .is_empty()
+        );
+
+        touch.touch(1, TouchPhase::Started, at(0.0, 0.0), 1000 * MS);
+        touch.touch(2, TouchPhase::Started, at(80.0, 0.0), 1010 * MS);
+        assert!(
+            touch
+                .touch(1, TouchPhase::Ended, at(0.0, 0.0), 1500 * MS)
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn lone_tap_leaves_a_dab_and_cancel_discards_the_stroke() {
+        let mut touch = TouchGestureRecognizer::new();
+        touch.touch(1, TouchPhase::Started, at(5.0, 5.0), 0);
+        assert_eq!(
+            touch.touch(1, TouchPhase::Ended, at(5.0, 5.0), 30 * MS),
+            vec![
+                Gesture::PaintBegan {
+                    finger: 1,
+                    position: at(5.0, 5.0),
+                },
+                Gesture::PaintEnded,
+            ]
+        );
+
+        touch.touch(3, TouchPhase::Started, at(5.0, 5.0), 100 * MS);
+        touch.touch(3, TouchPhase::Moved, at(6.0, 5.0), 200 * MS);
+        assert!(touch.is_painting());
+        assert_eq!(
+            touch.touch(3, TouchPhase::Cancelled, at(6.0, 5.0), 210 * MS),
+            vec![Gesture::PaintCancelled]
+        );
+        assert!(!touch.is_painting());
+    }
+}
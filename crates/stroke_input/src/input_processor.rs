@@ -1,14 +1,26 @@
-use glaphica_core::{BrushInput, BrushInputFlags, CanvasVec2, MappedCursor, StrokeId};
+use glaphica_core::{
+    BrushInput, BrushInputFlags, CanvasVec2, InputDeviceKind, MappedCursor, StrokeId,
+};
 
-use crate::resampler::{DistanceResampler, ResampleResult, ResamplerConfig};
-use crate::smoother::{
-    ExponentialMovingAverage, ExponentialMovingAverageConfig, SmoothingStrategy,
+use crate::calibration::DeviceCalibrations;
+use crate::pressure_ramp::{
+    StrokeTaper, SyntheticPressure, TaperBuffer, VelocityPressure, reports_pressure,
 };
 
+use crate::resampler::{ResampleResult, ResamplerConfig, ResamplerKind, ResamplingStrategy};
+use crate::smoother::{SmoothingConfig, SmoothingStrategy};
+
+/// Spacing of the catch-up samples a smoother emits at stroke end.
+const CATCH_UP_INTERVAL_NS: u64 = 8_333_333;
+
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub struct InputProcessingConfig {
-    pub smoothing: ExponentialMovingAverageConfig,
+    pub smoothing: SmoothingConfig,
     pub resampling: ResamplerConfig,
+    pub resampler: ResamplerKind,
+    pub taper: StrokeTaper,
+    pub synthetic_pressure: SyntheticPressure,
+    pub calibration: DeviceCalibrations,
     pub velocity_window_size: usize,
     pub curvature_window_size: usize,
 }
@@ -16,8 +28,12 @@ pub struct InputProcessingConfig {
 impl Default for InputProcessingConfig {
     fn default() -> Self {
         Self {
-            smoothing: ExponentialMovingAverageConfig::default(),
+            smoothing: SmoothingConfig::default(),
             resampling: ResamplerConfig::default(),
+            resampler: ResamplerKind::default(),
+            taper: StrokeTaper::NONE,
+            synthetic_pressure: SyntheticPressure::Off,
+            calibration: DeviceCalibrations::default(),
             velocity_window_size: crate::VELOCITY_WINDOW_SIZE,
             curvature_window_size: crate::CURVATURE_WINDOW_SIZE,
         }
@@ -35,9 +51,12 @@ struct ProcessedSample {
 
 pub struct StrokeInputProcessor {
     config: InputProcessingConfig,
-    smoother: ExponentialMovingAverage,
-    resampler: DistanceResampler,
+    smoother: Box<dyn SmoothingStrategy + Send>,
+    resampler: Box<dyn ResamplingStrategy + Send>,
+    velocity_pressure: VelocityPressure,
+    taper_buffer: TaperBuffer,
     stroke_id: Option<StrokeId>,
+    last_input_time_ns: Option<u64>,
     /// History of processed samples for derivative calculations
     history: Vec<ProcessedSample>,
     total_path_s: f32,
@@ -47,10 +66,13 @@ pub struct StrokeInputProcessor {
 impl StrokeInputProcessor {
     pub fn new(config: InputProcessingConfig) -> Self {
         Self {
-            smoother: ExponentialMovingAverage::new(config.smoothing),
-            resampler: DistanceResampler::new(config.resampling),
+            smoother: config.smoothing.build(),
+            resampler: config.resampler.build(config.resampling),
+            velocity_pressure: VelocityPressure::default(),
+            taper_buffer: TaperBuffer::default(),
             config,
             stroke_id: None,
+            last_input_time_ns: None,
             history: Vec::with_capacity(crate::config::HISTORY_CAPACITY),
             total_path_s: 0.0,
             last_output_time_ns: None,
@@ -59,8 +81,11 @@ impl StrokeInputProcessor {
 
     pub fn begin_stroke(&mut self, stroke_id: StrokeId) {
         self.stroke_id = Some(stroke_id);
+        self.last_input_time_ns = None;
         self.smoother.reset();
         self.resampler.reset();
+        self.velocity_pressure.reset();
+        self.taper_buffer.clear();
         self.history.clear();
         self.total_path_s = 0.0;
         self.last_output_time_ns = None;
@@ -68,8 +93,11 @@ impl StrokeInputProcessor {
 
     pub fn end_stroke(&mut self) {
         self.stroke_id = None;
+        self.last_input_time_ns = None;
         self.smoother.reset();
         self.resampler.reset();
+        self.velocity_pressure.reset();
+        self.taper_buffer.clear();
         self.history.clear();
         self.total_path_s = 0.0;
         self.last_output_time_ns = None;
@@ -78,11 +106,67 @@ impl StrokeInputProcessor {
     pub fn process_input(
         &mut self,
         stroke_id: StrokeId,
+        device: InputDeviceKind,
         cursor: MappedCursor,
         timestamp_ns: u64,
     ) -> Vec<BrushInput> {
-        let smoothed = self.smoother.smooth(cursor);
+        self.last_input_time_ns = Some(timestamp_ns);
+        let cursor = self.config.calibration.get(device).apply(cursor);
+        let cursor = match self.config.synthetic_pressure {
+            SyntheticPressure::Velocity(config) if !reports_pressure(device) => {
+                self.velocity_pressure.apply(config, cursor, timestamp_ns)
+            }
+            _ => cursor,
+        };
+        let smoothed = self.smoother.smooth(cursor, timestamp_ns);
+        self.resample_and_convert(stroke_id, smoothed, timestamp_ns)
+    }
+
+    /// Inputs that let a lagging smoother reach the last raw sample, flush
+    /// what the resampler held back and taper the stroke's end; called once
+    /// before the stroke ends.
+    pub fn finish_stroke(&mut self) -> Vec<BrushInput> {
+        let (Some(stroke_id), Some(mut timestamp_ns)) = (self.stroke_id, self.last_input_time_ns)
+        else {
+            return Vec::new();
+        };
+        let mut catch_up = Vec::new();
+        self.smoother.catch_up(&mut catch_up);
+        let mut result = Vec::new();
+        for cursor in catch_up {
+            timestamp_ns += CATCH_UP_INTERVAL_NS;
+            result.extend(self.resample_and_convert(stroke_id, cursor, timestamp_ns));
+        }
+        self.last_input_time_ns = Some(timestamp_ns);
+        for (cursor, ts) in self.resampler.finish() {
+            if let Some(input) = self.convert_to_brush_input(stroke_id, cursor, ts) {
+                self.taper_buffer
+                    .push(self.config.taper, input, &mut result);
+            }
+        }
+        self.taper_buffer.finish(self.config.taper, &mut result);
+        result
+    }
+
+    pub fn smoothing_config(&self) -> SmoothingConfig {
+        self.config.smoothing
+    }
 
+    /// Swaps the smoothing strategy, dropping whatever the old one held.
+    pub fn set_smoothing_config(&mut self, config: SmoothingConfig) {
+        if self.config.smoothing == config {
+            return;
+        }
+        self.config.smoothing = config;
+        self.smoother = config.build();
+    }
+
+    fn resample_and_convert(
+        &mut self,
+        stroke_id: StrokeId,
+        smoothed: MappedCursor,
+        timestamp_ns: u64,
+    ) -> Vec<BrushInput> {
         let resampled = match self.resampler.add_sample(smoothed, timestamp_ns) {
             ResampleResult::Rejected => {
                 return Vec::new();
@@ -93,19 +177,64 @@ impl StrokeInputProcessor {
             ResampleResult::Interpolated(points) => points,
         };
 
-        let result: Vec<BrushInput> = resampled
-            .into_iter()
-            .filter_map(|(cursor, ts)| self.convert_to_brush_input(stroke_id, cursor, ts))
-            .collect();
-
+        let mut result = Vec::with_capacity(resampled.len());
+        for (cursor, ts) in resampled {
+            if let Some(input) = self.convert_to_brush_input(stroke_id, cursor, ts) {
+                self.taper_buffer
+                    .push(self.config.taper, input, &mut result);
+            }
+        }
         result
     }
 
+    pub fn taper(&self) -> StrokeTaper {
+        self.config.taper
+    }
+
+    /// Takes effect from the next input; inputs already held for the end
+    /// taper are released under the new lengths.
+    pub fn set_taper(&mut self, taper: StrokeTaper) {
+        self.config.taper = taper;
+    }
+
+    pub fn synthetic_pressure(&self) -> SyntheticPressure {
+        self.config.synthetic_pressure
+    }
+
+    pub fn set_synthetic_pressure(&mut self, synthetic_pressure: SyntheticPressure) {
+        self.config.synthetic_pressure = synthetic_pressure;
+    }
+
+    pub fn calibration(&self) -> DeviceCalibrations {
+        self.config.calibration
+    }
+
+    pub fn set_calibration(&mut self, calibration: DeviceCalibrations) {
+        self.config.calibration = calibration;
+    }
+
+    pub fn resampling_config(&self) -> ResamplerConfig {
+        self.config.resampling
+    }
+
     pub fn set_resampling_config(&mut self, config: ResamplerConfig) {
         self.config.resampling = config;
         self.resampler.set_config(config);
     }
 
+    pub fn resampler_kind(&self) -> ResamplerKind {
+        self.config.resampler
+    }
+
+    /// Swaps the resampling strategy, dropping whatever the old one held.
+    pub fn set_resampler_kind(&mut self, kind: ResamplerKind) {
+        if self.config.resampler == kind {
+            return;
+        }
+        self.config.resampler = kind;
+        self.resampler = kind.build(self.config.resampling);
+    }
+
     fn convert_to_brush_input(
         &mut self,
         stroke_id: StrokeId,
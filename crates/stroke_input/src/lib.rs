@@ -1,9 +1,25 @@
+pub mod calibration;
 pub mod config;
+pub mod gesture;
 pub mod input_processor;
+pub mod pressure_ramp;
 pub mod resampler;
+pub mod shape;
 pub mod smoother;
+pub mod spline_resampler;
+pub mod symmetry;
 
+pub use calibration::{
+    DeviceCalibration, DeviceCalibrations, PRESSURE_CURVE_POINTS, PressureCurve,
+};
 pub use config::{CURVATURE_WINDOW_SIZE, HISTORY_CAPACITY, VELOCITY_WINDOW_SIZE};
+pub use gesture::{Gesture, TouchGestureRecognizer, TouchPhase};
 pub use input_processor::{InputProcessingConfig, StrokeInputProcessor};
-pub use resampler::ResamplerConfig;
-pub use smoother::{ExponentialMovingAverageConfig, SmoothingStrategy};
+pub use pressure_ramp::{StrokeTaper, SyntheticPressure, VelocityPressureConfig, reports_pressure};
+pub use resampler::{ResamplerConfig, ResamplerKind, ResamplingStrategy};
+pub use shape::{SHAPE_SNAP_ANGLE, ShapeTaper, StrokeShape, snap_angle, square_corner};
+pub use smoother::{
+    ExponentialMovingAverageConfig, LazyNibConfig, MovingWindowConfig, OneEuroConfig,
+    SmoothingConfig, SmoothingKind, SmoothingStrategy,
+};
+pub use symmetry::{MAX_RADIAL_WAYS, Symmetry, SymmetryMode};
@@ -0,0 +1,235 @@
+use std::collections::VecDeque;
+
+use glaphica_core::{BrushInput, CanvasVec2, InputDeviceKind, MappedCursor};
+
+/// Share of each new speed reading folded into the running speed.
+const SPEED_SMOOTHING: f32 = 0.3;
+
+/// Canvas px over which a freehand stroke's pressure ramps up from zero at
+/// its start and back down to zero at its end. Zero leaves that end blunt.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct StrokeTaper {
+    pub start_length: f32,
+    pub end_length: f32,
+}
+
+impl StrokeTaper {
+    pub const NONE: Self = Self {
+        start_length: 0.0,
+        end_length: 0.0,
+    };
+
+    pub const fn new(start_length: f32, end_length: f32) -> Self {
+        Self {
+            start_length,
+            end_length,
+        }
+    }
+
+    /// Pressure scale for an input `path_s` px into the stroke with
+    /// `remaining` px of stroke still after it.
+    pub fn scale(&self, path_s: f32, remaining: f32) -> f32 {
+        let ramp = |distance: f32, length: f32| {
+            if length > 0.0 {
+                (distance / length).clamp(0.0, 1.0)
+            } else {
+                1.0
+            }
+        };
+        ramp(path_s, self.start_length).min(ramp(remaining, self.end_length))
+    }
+}
+
+/// Pressure made up from speed: slow movement presses fully and pressure
+/// falls linearly to `min_pressure` at `full_speed` canvas px/s.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct VelocityPressureConfig {
+    pub min_pressure: f32,
+    pub full_speed: f32,
+}
+
+impl Default for VelocityPressureConfig {
+    fn default() -> Self {
+        Self {
+            min_pressure: 0.2,
+            full_speed: 3000.0,
+        }
+    }
+}
+
+/// Pressure for devices that report none, such as a mouse.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum SyntheticPressure {
+    /// Keep the constant pressure the device sends.
+    #[default]
+    Off,
+    Velocity(VelocityPressureConfig),
+}
+
+/// Whether samples from `device` carry real pressure.
+pub fn reports_pressure(device: InputDeviceKind) -> bool {
+    matches!(device, InputDeviceKind::Pen)
+}
+
+/// Running speed of the raw samples a synthetic pressure is derived from.
+#[derive(Debug, Default)]
+pub(crate) struct VelocityPressure {
+    last: Option<(CanvasVec2, u64)>,
+    speed: f32,
+}
+
+impl VelocityPressure {
+    pub(crate) fn apply(
+        &mut self,
+        config: VelocityPressureConfig,
+        mut cursor: MappedCursor,
+        timestamp_ns: u64,
+    ) -> MappedCursor {
+        if let Some((last, last_ns)) = self.last
+            && timestamp_ns > last_ns
+        {
+            let distance = (cursor.cursor.x - last.x).hypot(cursor.cursor.y - last.y);
+            let dt_s = (timestamp_ns - last_ns) as f32 / 1_000_000_000.0;
+            self.speed += (distance / dt_s - self.speed) * SPEED_SMOOTHING;
+        }
+        self.last = Some((cursor.cursor, timestamp_ns));
+
+        let fraction = if config.full_speed > 0.0 {
+            (self.speed / config.full_speed).clamp(0.0, 1.0)
+        } else {
+            0.0
+        };
+        cursor.pressure = 1.0 - (1.0 - config.min_pressure.clamp(0.0, 1.0)) * fraction;
+        cursor
+    }
+
+    pub(crate) fn reset(&mut self) {
+        self.last = None;
+        self.speed = 0.0;
+    }
+}
+
+/// Inputs held back until the stroke has run `end_length` past them, since
+/// the end taper can only be applied once it is known where the stroke stops.
+#[derive(Debug, Default)]
+pub(crate) struct TaperBuffer {
+    held: VecDeque<BrushInput>,
+}
+
+impl TaperBuffer {
+    pub(crate) fn push(
+        &mut self,
+        taper: StrokeTaper,
+        input: BrushInput,
+        output: &mut Vec<BrushInput>,
+    ) {
+        self.held.push_back(input);
+        while let Some(front) = self.held.front()
+            && input.path_s - front.path_s >= taper.end_length
+        {
+            let mut front = *front;
+            self.held.pop_front();
+            front.cursor.pressure *= taper.scale(front.path_s, f32::INFINITY);
+            output.push(front);
+        }
+    }
+
+    /// Releases everything still held, tapered toward the last input.
+    pub(crate) fn finish(&mut self, taper: StrokeTaper, output: &mut Vec<BrushInput>) {
+        let Some(end_s) = self.held.back().map(|input| input.path_s) else {
+            return;
+        };
+        output.extend(self.held.drain(..).map(|mut input| {
+            input.cursor.pressure *= taper.scale(input.path_s, end_s - input.path_s);
+            input
+        }));
+    }
+
+    pub(crate) fn clear(&mut self) {
+        self.held.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use glaphica_core::{
+        BrushInput, BrushInputFlags, CanvasVec2, MappedCursor, RadianVec2, StrokeId,
+    };
+
+    use super::{StrokeTaper, TaperBuffer, VelocityPressure, VelocityPressureConfig};
+
+    fn cursor(x: f32) -> MappedCursor {
+        MappedCursor {
+            cursor: CanvasVec2::new(x, 0.0),
+            tilt: RadianVec2::new(0.0, 0.0),
+            pressure: 1.0,
+            twist: 0.0,
+        }
+    }
+
+    fn input(path_s: f32) -> BrushInput {
+        BrushInput {
+            stroke: StrokeId(1),
+            cursor: cursor(path_s),
+            flags: BrushInputFlags::PATH_S,
+            path_s,
+            delta_s: 0.0,
+            dt_s: 0.0,
+            vel: CanvasVec2::new(0.0, 0.0),
+            speed: 0.0,
+            tangent: CanvasVec2::new(1.0, 0.0),
+            acc: CanvasVec2::new(0.0, 0.0),
+            accel: 0.0,
+            curvature: 0.0,
+            confidence: 1.0,
+        }
+    }
+
+    #[test]
+    fn taper_ramps_both_ends_and_takes_the_thinner_on_short_strokes() {
+        let taper = StrokeTaper::new(10.0, 20.0);
+        assert_eq!(taper.scale(0.0, 100.0), 0.0);
+        assert_eq!(taper.scale(5.0, 100.0), 0.5);
+        assert_eq!(taper.scale(50.0, 50.0), 1.0);
+        assert_eq!(taper.scale(50.0, 5.0), 0.25);
+        assert_eq!(taper.scale(8.0, 4.0), 0.2);
+        assert_eq!(StrokeTaper::NONE.scale(0.0, 0.0), 1.0);
+    }
+
+    #[test]
+    fn buffer_holds_the_end_taper_length_and_tapers_it_on_finish() {
+        let taper = StrokeTaper::new(0.0, 4.0);
+        let mut buffer = TaperBuffer::default();
+        let mut output = Vec::new();
+        for step in 0..=10 {
+            buffer.push(taper, input(step as f32), &mut output);
+        }
+        assert_eq!(output.len(), 7);
+        assert!(output.iter().all(|input| input.cursor.pressure == 1.0));
+
+        buffer.finish(taper, &mut output);
+        let tail = output[7..]
+            .iter()
+            .map(|input| input.cursor.pressure)
+            .collect::<Vec<_>>();
+        assert_eq!(tail, vec![0.75, 0.5, 0.25, 0.0]);
+    }
+
+    #[test]
+    fn velocity_pressure_thins_fast_movement() {
+        let config = VelocityPressureConfig {
+            min_pressure: 0.2,
+            full_speed: 1000.0,
+        };
+        let mut pressure = VelocityPressure::default();
+        assert_eq!(pressure.apply(config, cursor(0.0), 0).pressure, 1.0);
+
+        let mut last = 1.0;
+        for step in 1..=40u64 {
+            let sample = pressure.apply(config, cursor(step as f32 * 20.0), step * 10_000_000);
+            assert!(sample.pressure <= last);
+            last = sample.pressure;
+        }
+        assert!((last - 0.2).abs() < 0.01, "pressure {last}");
+    }
+}
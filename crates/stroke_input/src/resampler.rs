@@ -1,5 +1,7 @@
 use glaphica_core::{CanvasVec2, MappedCursor, RadianVec2};
 
+use crate::spline_resampler::CatmullRomResampler;
+
 /// Configuration for the resampler
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub struct ResamplerConfig {
@@ -20,6 +22,37 @@ impl Default for ResamplerConfig {
     }
 }
 
+/// How raw samples are filled in between.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ResamplerKind {
+    /// Straight lines between raw samples.
+    #[default]
+    Linear,
+    /// Centripetal Catmull-Rom spline, one sample behind the pen.
+    CatmullRom,
+}
+
+impl ResamplerKind {
+    pub fn build(self, config: ResamplerConfig) -> Box<dyn ResamplingStrategy + Send> {
+        match self {
+            Self::Linear => Box::new(DistanceResampler::new(config)),
+            Self::CatmullRom => Box::new(CatmullRomResampler::new(config)),
+        }
+    }
+}
+
+pub trait ResamplingStrategy {
+    fn add_sample(&mut self, cursor: MappedCursor, timestamp_ns: u64) -> ResampleResult;
+
+    /// Samples still held back for look-ahead, flushed when the stroke ends.
+    fn finish(&mut self) -> Vec<(MappedCursor, u64)> {
+        Vec::new()
+    }
+
+    fn set_config(&mut self, config: ResamplerConfig);
+    fn reset(&mut self);
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub enum ResampleResult {
     Accepted(MappedCursor),
@@ -109,6 +142,20 @@ impl DistanceResampler {
     }
 }
 
+impl ResamplingStrategy for DistanceResampler {
+    fn add_sample(&mut self, cursor: MappedCursor, timestamp_ns: u64) -> ResampleResult {
+        DistanceResampler::add_sample(self, cursor, timestamp_ns)
+    }
+
+    fn set_config(&mut self, config: ResamplerConfig) {
+        DistanceResampler::set_config(self, config);
+    }
+
+    fn reset(&mut self) {
+        DistanceResampler::reset(self);
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use glaphica_core::{CanvasVec2, MappedCursor, RadianVec2};
@@ -0,0 +1,372 @@
+use std::f32::consts::{PI, TAU};
+
+use glaphica_core::{BrushInput, BrushInputFlags, CanvasVec2, MappedCursor, RadianVec2, StrokeId};
+
+/// Angle step a shift-constrained line snaps to.
+pub const SHAPE_SNAP_ANGLE: f32 = PI / 12.0;
+
+/// Speed synthetic shape strokes pretend to be drawn at, in canvas px/s.
+const SHAPE_STROKE_SPEED: f32 = 1000.0;
+
+/// Canvas length of one straight piece of a flattened ellipse.
+const ELLIPSE_SEGMENT_LENGTH: f32 = 4.0;
+const ELLIPSE_MIN_SEGMENTS: usize = 16;
+const ELLIPSE_MAX_SEGMENTS: usize = 512;
+
+/// Geometric path a shape tool strokes with the active brush.
+#[derive(Debug, Clone, PartialEq)]
+pub enum StrokeShape {
+    Line {
+        start: CanvasVec2,
+        end: CanvasVec2,
+    },
+    /// Axis-aligned rectangle between two opposite corners.
+    Rectangle {
+        start: CanvasVec2,
+        end: CanvasVec2,
+    },
+    /// Ellipse inscribed in the box between two opposite corners.
+    Ellipse {
+        start: CanvasVec2,
+        end: CanvasVec2,
+    },
+    Polyline {
+        points: Vec<CanvasVec2>,
+    },
+}
+
+/// Pressure ramps at the ends of a shape stroke, as fractions of its length.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ShapeTaper {
+    pub start: f32,
+    pub end: f32,
+}
+
+impl ShapeTaper {
+    pub const NONE: Self = Self {
+        start: 0.0,
+        end: 0.0,
+    };
+
+    pub const fn new(start: f32, end: f32) -> Self {
+        Self { start, end }
+    }
+
+    /// Pressure at arc length `s` of a path `length` long: rises from zero
+    /// over the start taper, falls back to zero over the end taper.
+    pub fn pressure_at(&self, s: f32, length: f32) -> f32 {
+        if length <= 0.0 {
+            return 1.0;
+        }
+        let ramp = |fraction: f32, distance: f32| {
+            let span = fraction.clamp(0.0, 1.0) * length;
+            if span > 0.0 {
+                (distance / span).clamp(0.0, 1.0)
+            } else {
+                1.0
+            }
+        };
+        ramp(self.start, s).min(ramp(self.end, length - s))
+    }
+}
+
+impl Default for ShapeTaper {
+    fn default() -> Self {
+        Self::NONE
+    }
+}
+
+/// Turns `end` about `start` onto the nearest multiple of `step` radians,
+/// keeping the line's length.
+pub fn snap_angle(start: CanvasVec2, end: CanvasVec2, step: f32) -> CanvasVec2 {
+    let dx = end.x - start.x;
+    let dy = end.y - start.y;
+    let length = (dx * dx + dy * dy).sqrt();
+    if length <= f32::EPSILON || step <= 0.0 {
+        return end;
+    }
+    let angle = (dy.atan2(dx) / step).round() * step;
+    CanvasVec2::new(
+        start.x + angle.cos() * length,
+        start.y + angle.sin() * length,
+    )
+}
+
+/// Moves `end` so the box from `start` is square, growing the shorter side.
+pub fn square_corner(start: CanvasVec2, end: CanvasVec2) -> CanvasVec2 {
+    let dx = end.x - start.x;
+    let dy = end.y - start.y;
+    let side = dx.abs().max(dy.abs());
+    CanvasVec2::new(start.x + side.copysign(dx), start.y + side.copysign(dy))
+}
+
+impl StrokeShape {
+    /// Vertices of the path in drawing order. Closed shapes repeat their
+    /// first vertex at the end; ellipses are flattened into short segments.
+    pub fn outline(&self) -> Vec<CanvasVec2> {
+        match self {
+            Self::Line { start, end } => vec![*start, *end],
+            Self::Rectangle { start, end } => vec![
+                *start,
+                CanvasVec2::new(end.x, start.y),
+                *end,
+                CanvasVec2::new(start.x, end.y),
+                *start,
+            ],
+            Self::Ellipse { start, end } => {
+                let center_x = (start.x + end.x) * 0.5;
+                let center_y = (start.y + end.y) * 0.5;
+                let radius_x = (end.x - start.x).abs() * 0.5;
+                let radius_y = (end.y - start.y).abs() * 0.5;
+                // Ramanujan's approximation of the perimeter.
+                let h = ((radius_x - radius_y) / (radius_x + radius_y).max(f32::EPSILON)).powi(2);
+                let perimeter =
+                    PI * (radius_x + radius_y) * (1.0 + 3.0 * h / (10.0 + (4.0 - 3.0 * h).sqrt()));
+                let segments = ((perimeter / ELLIPSE_SEGMENT_LENGTH).ceil() as usize)
+                    .clamp(ELLIPSE_MIN_SEGMENTS, ELLIPSE_MAX_SEGMENTS);
+                (0..=segments)
+                    .map(|index| {
+                        let angle = (index % segments) as f32 * TAU / segments as f32;
+                        CanvasVec2::new(
+                            center_x + radius_x * angle.cos(),
+                            center_y + radius_y * angle.sin(),
+                        )
+                    })
+                    .collect()
+            }
+            Self::Polyline { points } => points.clone(),
+        }
+    }
+
+    /// Stroke inputs walking the outline at most `spacing` apart, hitting
+    /// every vertex. They carry what the input processor would have derived
+    /// for a steady hand: arc length, a constant speed along each segment
+    /// and tapered pressure.
+    pub fn brush_inputs(
+        &self,
+        stroke: StrokeId,
+        spacing: f32,
+        taper: ShapeTaper,
+    ) -> Vec<BrushInput> {
+        let mut outline = self.outline();
+        outline.dedup();
+        let Some(&first) = outline.first() else {
+            return Vec::new();
+        };
+        let spacing = spacing.max(0.5);
+        let length = outline
+            .windows(2)
+            .map(|pair| distance(pair[0], pair[1]))
+            .sum::<f32>();
+
+        let mut inputs = Vec::new();
+        let mut path_s = 0.0;
+        let mut last = first;
+        let mut tangent = match outline.get(1) {
+            Some(&next) => direction(first, next),
+            None => CanvasVec2::new(1.0, 0.0),
+        };
+        inputs.push(shape_input(
+            stroke,
+            first,
+            0.0,
+            0.0,
+            tangent,
+            taper.pressure_at(0.0, length),
+        ));
+        for pair in outline.windows(2) {
+            let (from, to) = (pair[0], pair[1]);
+            let segment_length = distance(from, to);
+            tangent = direction(from, to);
+            let steps = (segment_length / spacing).ceil().max(1.0) as usize;
+            for step in 1..=steps {
+                let t = step as f32 / steps as f32;
+                let point =
+                    CanvasVec2::new(from.x + (to.x - from.x) * t, from.y + (to.y - from.y) * t);
+                let delta_s = distance(last, point);
+                path_s += delta_s;
+                last = point;
+                inputs.push(shape_input(
+                    stroke,
+                    point,
+                    path_s,
+                    delta_s,
+                    tangent,
+                    taper.pressure_at(path_s, length),
+                ));
+            }
+        }
+        inputs
+    }
+}
+
+fn distance(a: CanvasVec2, b: CanvasVec2) -> f32 {
+    let dx = b.x - a.x;
+    let dy = b.y - a.y;
+    (dx * dx + dy * dy).sqrt()
+}
+
+fn direction(from: CanvasVec2, to: CanvasVec2) -> CanvasVec2 {
+    let length = distance(from, to);
+    if length <= f32::EPSILON {
+        return CanvasVec2::new(1.0, 0.0);
+    }
+    CanvasVec2::new((to.x - from.x) / length, (to.y - from.y) / length)
+}
+
+fn shape_input(
+    stroke: StrokeId,
+    point: CanvasVec2,
+    path_s: f32,
+    delta_s: f32,
+    tangent: CanvasVec2,
+    pressure: f32,
+) -> BrushInput {
+    let mut flags = BrushInputFlags::PATH_S
+        | BrushInputFlags::DELTA_S
+        | BrushInputFlags::DT_S
+        | BrushInputFlags::CONFIDENCE;
+    let (vel, speed) = if delta_s > 0.0 {
+        flags |= BrushInputFlags::VEL | BrushInputFlags::SPEED | BrushInputFlags::TANGENT;
+        (
+            CanvasVec2::new(
+                tangent.x * SHAPE_STROKE_SPEED,
+                tangent.y * SHAPE_STROKE_SPEED,
+            ),
+            SHAPE_STROKE_SPEED,
+        )
+    } else {
+        (CanvasVec2::new(0.0, 0.0), 0.0)
+    };
+    BrushInput {
+        stroke,
+        cursor: MappedCursor {
+            cursor: point,
+            tilt: RadianVec2::new(0.0, 0.0),
+            pressure,
+            twist: 0.0,
+        },
+        flags,
+        path_s,
+        delta_s,
+        dt_s: delta_s / SHAPE_STROKE_SPEED,
+        vel,
+        speed,
+        tangent,
+        acc: CanvasVec2::new(0.0, 0.0),
+        accel: 0.0,
+        curvature: 0.0,
+        confidence: 1.0,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use glaphica_core::{BrushInputFlags, CanvasVec2, StrokeId};
+
+    use super::{SHAPE_SNAP_ANGLE, ShapeTaper, StrokeShape, snap_angle, square_corner};
+
+    fn assert_close(actual: f32, expected: f32) {
+        assert!(
+            (actual - expected).abs() < 0.001,
+            "expected {expected}, got {actual}"
+        );
+    }
+
+    #[test]
+    fn line_inputs_are_evenly_spaced_and_reach_the_end() {
+        let shape = StrokeShape::Line {
+            start: CanvasVec2::new(0.0, 0.0),
+            end: CanvasVec2::new(25.0, 0.0),
+        };
+        let inputs = shape.brush_inputs(StrokeId(7), 10.0, ShapeTaper::NONE);
+        let xs = inputs
+            .iter()
+            .map(|input| input.cursor.cursor.x)
+            .collect::<Vec<_>>();
+        assert_eq!(xs.len(), 4);
+        assert_close(xs[1], 25.0 / 3.0);
+        assert_close(xs[3], 25.0);
+        assert_close(inputs[3].path_s, 25.0);
+        assert_eq!(inputs[0].stroke, StrokeId(7));
+        assert!(!inputs[0].flags.contains(BrushInputFlags::VEL));
+        assert!(inputs[1].flags.contains(BrushInputFlags::TANGENT));
+        assert_close(inputs[1].tangent.x, 1.0);
+        assert!(inputs.iter().all(|input| input.cursor.pressure == 1.0));
+    }
+
+    #[test]
+    fn rectangle_inputs_hit_every_corner() {
+        let shape = StrokeShape::Rectangle {
+            start: CanvasVec2::new(10.0, 10.0),
+            end: CanvasVec2::new(30.0, 20.0),
+        };
+        let inputs = shape.brush_inputs(StrokeId(1), 4.0, ShapeTaper::NONE);
+        for corner in [(30.0, 10.0), (30.0, 20.0), (10.0, 20.0)] {
+            assert!(
+                inputs
+                    .iter()
+                    .any(|input| { (input.cursor.cursor.x, input.cursor.cursor.y) == corner })
+            );
+        }
+        let last = inputs[inputs.len() - 1];
+        assert_eq!((last.cursor.cursor.x, last.cursor.cursor.y), (10.0, 10.0));
+        assert_close(last.path_s, 60.0);
+    }
+
+    #[test]
+    fn ellipse_outline_stays_on_the_ellipse() {
+        let shape = StrokeShape::Ellipse {
+            start: CanvasVec2::new(0.0, 0.0),
+            end: CanvasVec2::new(200.0, 100.0),
+        };
+        let outline = shape.outline();
+        assert!(outline.len() > 16);
+        assert_eq!(outline.first(), outline.last());
+        for point in outline {
+            let x = (point.x - 100.0) / 100.0;
+            let y = (point.y - 50.0) / 50.0;
+            assert_close(x * x + y * y, 1.0);
+        }
+    }
+
+    #[test]
+    fn taper_ramps_pressure_at_both_ends() {
+        let taper = ShapeTaper::new(0.25, 0.5);
+        assert_close(taper.pressure_at(0.0, 100.0), 0.0);
+        assert_close(taper.pressure_at(12.5, 100.0), 0.5);
+        assert_close(taper.pressure_at(40.0, 100.0), 1.0);
+        assert_close(taper.pressure_at(75.0, 100.0), 0.5);
+        assert_close(taper.pressure_at(100.0, 100.0), 0.0);
+        assert_close(ShapeTaper::NONE.pressure_at(0.0, 100.0), 1.0);
+    }
+
+    #[test]
+    fn snapping_keeps_length_and_rounds_the_angle() {
+        let start = CanvasVec2::new(10.0, 10.0);
+        let snapped = snap_angle(start, CanvasVec2::new(20.0, 11.0), SHAPE_SNAP_ANGLE);
+        assert_close(snapped.y, 10.0);
+        assert_close(snapped.x, 10.0 + 101.0_f32.sqrt());
+        let diagonal = snap_angle(start, CanvasVec2::new(20.0, 19.0), SHAPE_SNAP_ANGLE);
+        assert_close(diagonal.x - 10.0, diagonal.y - 10.0);
+
+        let square = square_corner(start, CanvasVec2::new(4.0, 30.0));
+        assert_eq!((square.x, square.y), (-10.0, 30.0));
+    }
+
+    #[test]
+    fn polyline_with_one_point_stamps_a_single_dot() {
+        let shape = StrokeShape::Polyline {
+            points: vec![CanvasVec2::new(5.0, 5.0)],
+        };
+        let inputs = shape.brush_inputs(StrokeId(2), 4.0, ShapeTaper::new(0.5, 0.5));
+        assert_eq!(inputs.len(), 1);
+        assert_eq!(inputs[0].cursor.pressure, 1.0);
+        let empty = StrokeShape::Polyline { points: Vec::new() };
+        assert!(
+            empty
+                .brush_inputs(StrokeId(2), 4.0, ShapeTaper::NONE)
+                .is_empty()
+        );
+    }
+}
@@ -1,5 +1,15 @@
+use std::collections::VecDeque;
+use std::f32::consts::TAU;
+
 use glaphica_core::{CanvasVec2, MappedCursor, RadianVec2};
 
+/// Largest dead zone radius the strength setting maps to, in canvas px.
+const LAZY_NIB_MAX_RADIUS: f32 = 48.0;
+/// Largest window the strength setting maps to, in samples.
+const MOVING_WINDOW_MAX_SIZE: usize = 16;
+/// Sample interval assumed when two samples share a timestamp.
+const ONE_EURO_FALLBACK_DT_S: f32 = 1.0 / 120.0;
+
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub struct ExponentialMovingAverageConfig {
     pub position_alpha: f32,
@@ -19,8 +29,133 @@ impl Default for ExponentialMovingAverageConfig {
     }
 }
 
+/// Dead zone the cursor drags the nib through like a pulled string.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LazyNibConfig {
+    pub radius: f32,
+}
+
+/// Weighted average over the latest samples, newest weighted most.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MovingWindowConfig {
+    pub window_size: usize,
+}
+
+/// Parameters of the 1€ filter: the cutoff rises from `min_cutoff_hz` by
+/// `beta` per canvas px/s of speed.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct OneEuroConfig {
+    pub min_cutoff_hz: f32,
+    pub beta: f32,
+    pub derivative_cutoff_hz: f32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SmoothingKind {
+    None,
+    #[default]
+    ExponentialMovingAverage,
+    LazyNib,
+    MovingWindow,
+    OneEuro,
+}
+
+impl SmoothingKind {
+    pub const ALL: [Self; 5] = [
+        Self::None,
+        Self::ExponentialMovingAverage,
+        Self::LazyNib,
+        Self::MovingWindow,
+        Self::OneEuro,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::None => "None",
+            Self::ExponentialMovingAverage => "Exponential",
+            Self::LazyNib => "Lazy nib",
+            Self::MovingWindow => "Moving window",
+            Self::OneEuro => "1€ filter",
+        }
+    }
+}
+
+/// Smoothing strategy and its parameters.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SmoothingConfig {
+    None,
+    ExponentialMovingAverage(ExponentialMovingAverageConfig),
+    LazyNib(LazyNibConfig),
+    MovingWindow(MovingWindowConfig),
+    OneEuro(OneEuroConfig),
+}
+
+impl Default for SmoothingConfig {
+    fn default() -> Self {
+        Self::ExponentialMovingAverage(ExponentialMovingAverageConfig::default())
+    }
+}
+
+impl SmoothingConfig {
+    /// Maps one 0..1 strength setting onto the parameters of `kind`; zero
+    /// leaves the input untouched.
+    pub fn from_strength(kind: SmoothingKind, strength: f32) -> Self {
+        let strength = strength.clamp(0.0, 1.0);
+        match kind {
+            SmoothingKind::None => Self::None,
+            SmoothingKind::ExponentialMovingAverage => {
+                let alpha = (1.0 - strength).max(0.05);
+                Self::ExponentialMovingAverage(ExponentialMovingAverageConfig {
+                    position_alpha: alpha,
+                    pressure_alpha: alpha,
+                    tilt_alpha: alpha,
+                    twist_alpha: alpha,
+                })
+            }
+            SmoothingKind::LazyNib => Self::LazyNib(LazyNibConfig {
+                radius: strength * LAZY_NIB_MAX_RADIUS,
+            }),
+            SmoothingKind::MovingWindow => Self::MovingWindow(MovingWindowConfig {
+                window_size: 1 + (strength * (MOVING_WINDOW_MAX_SIZE - 1) as f32).round() as usize,
+            }),
+            SmoothingKind::OneEuro => Self::OneEuro(OneEuroConfig {
+                min_cutoff_hz: 8.0 + (0.3 - 8.0) * strength,
+                beta: 0.02,
+                derivative_cutoff_hz: 1.0,
+            }),
+        }
+    }
+
+    pub fn kind(&self) -> SmoothingKind {
+        match self {
+            Self::None => SmoothingKind::None,
+            Self::ExponentialMovingAverage(_) => SmoothingKind::ExponentialMovingAverage,
+            Self::LazyNib(_) => SmoothingKind::LazyNib,
+            Self::MovingWindow(_) => SmoothingKind::MovingWindow,
+            Self::OneEuro(_) => SmoothingKind::OneEuro,
+        }
+    }
+
+    pub fn build(&self) -> Box<dyn SmoothingStrategy + Send> {
+        match *self {
+            Self::None => Box::new(NoSmoothing),
+            Self::ExponentialMovingAverage(config) => {
+                Box::new(ExponentialMovingAverage::new(config))
+            }
+            Self::LazyNib(config) => Box::new(LazyNib::new(config)),
+            Self::MovingWindow(config) => Box::new(MovingWindow::new(config)),
+            Self::OneEuro(config) => Box::new(OneEuroFilter::new(config)),
+        }
+    }
+}
+
 pub trait SmoothingStrategy {
-    fn smooth(&mut self, sample: MappedCursor) -> MappedCursor;
+    fn smooth(&mut self, sample: MappedCursor, timestamp_ns: u64) -> MappedCursor;
+
+    /// Pushes samples that carry a lagging output the rest of the way to the
+    /// last raw sample once the stroke ends.
+    fn catch_up(&mut self, _samples: &mut Vec<MappedCursor>) {}
+
     fn reset(&mut self);
 }
 
@@ -51,7 +186,7 @@ impl ExponentialMovingAverage {
 }
 
 impl SmoothingStrategy for ExponentialMovingAverage {
-    fn smooth(&mut self, sample: MappedCursor) -> MappedCursor {
+    fn smooth(&mut self, sample: MappedCursor, _timestamp_ns: u64) -> MappedCursor {
         let smoothed = match self.last_smoothed {
             None => sample,
             Some(last) => MappedCursor {
@@ -77,9 +212,293 @@ impl SmoothingStrategy for ExponentialMovingAverage {
 pub struct NoSmoothing;
 
 impl SmoothingStrategy for NoSmoothing {
-    fn smooth(&mut self, sample: MappedCursor) -> MappedCursor {
+    fn smooth(&mut self, sample: MappedCursor, _timestamp_ns: u64) -> MappedCursor {
         sample
     }
 
     fn reset(&mut self) {}
 }
+
+/// Pulled-string stabilizer: the nib stays put while the cursor moves inside
+/// the dead zone and is dragged along once the cursor pulls the string taut.
+pub struct LazyNib {
+    config: LazyNibConfig,
+    nib: Option<CanvasVec2>,
+}
+
+impl LazyNib {
+    pub fn new(config: LazyNibConfig) -> Self {
+        Self { config, nib: None }
+    }
+}
+
+impl SmoothingStrategy for LazyNib {
+    fn smooth(&mut self, sample: MappedCursor, _timestamp_ns: u64) -> MappedCursor {
+        let target = sample.cursor;
+        let nib = match self.nib {
+            None => target,
+            Some(nib) => {
+                let dx = target.x - nib.x;
+                let dy = target.y - nib.y;
+                let distance = (dx * dx + dy * dy).sqrt();
+                let radius = self.config.radius.max(0.0);
+                if distance > radius {
+                    let pull = (distance - radius) / distance;
+                    CanvasVec2::new(nib.x + dx * pull, nib.y + dy * pull)
+                } else {
+                    nib
+                }
+            }
+        };
+        self.nib = Some(nib);
+        MappedCursor {
+            cursor: nib,
+            ..sample
+        }
+    }
+
+    fn reset(&mut self) {
+        self.nib = None;
+    }
+}
+
+/// Linearly weighted average over a sliding window of samples. At stroke end
+/// the window refills with the last sample so the line reaches the pen.
+pub struct MovingWindow {
+    config: MovingWindowConfig,
+    window: VecDeque<MappedCursor>,
+}
+
+impl MovingWindow {
+    pub fn new(config: MovingWindowConfig) -> Self {
+        Self {
+            config,
+            window: VecDeque::with_capacity(config.window_size.max(1)),
+        }
+    }
+
+    fn push(&mut self, sample: MappedCursor) -> MappedCursor {
+        self.window.push_back(sample);
+        while self.window.len() > self.config.window_size.max(1) {
+            self.window.pop_front();
+        }
+        self.average()
+    }
+
+    fn average(&self) -> MappedCursor {
+        let mut total_weight = 0.0;
+        let mut cursor = CanvasVec2::new(0.0, 0.0);
+        let mut tilt = RadianVec2::new(0.0, 0.0);
+        let mut pressure = 0.0;
+        let mut twist = 0.0;
+        for (index, sample) in self.window.iter().enumerate() {
+            let weight = (index + 1) as f32;
+            total_weight += weight;
+            cursor = CanvasVec2::new(
+                cursor.x + sample.cursor.x * weight,
+                cursor.y + sample.cursor.y * weight,
+            );
+            tilt = RadianVec2::new(
+                tilt.x + sample.tilt.x * weight,
+                tilt.y + sample.tilt.y * weight,
+            );
+            pressure += sample.pressure * weight;
+            twist += sample.twist * weight;
+        }
+        MappedCursor {
+            cursor: CanvasVec2::new(cursor.x / total_weight, cursor.y / total_weight),
+            tilt: RadianVec2::new(tilt.x / total_weight, tilt.y / total_weight),
+            pressure: pressure / total_weight,
+            twist: twist / total_weight,
+        }
+    }
+}
+
+impl SmoothingStrategy for MovingWindow {
+    fn smooth(&mut self, sample: MappedCursor, _timestamp_ns: u64) -> MappedCursor {
+        self.push(sample)
+    }
+
+    fn catch_up(&mut self, samples: &mut Vec<MappedCursor>) {
+        let Some(&last) = self.window.back() else {
+            return;
+        };
+        for _ in 1..self.window.len() {
+            samples.push(self.push(last));
+        }
+    }
+
+    fn reset(&mut self) {
+        self.window.clear();
+    }
+}
+
+/// The 1€ filter (Casiez et al.): a low-pass on position whose cutoff rises
+/// with speed, steadying slow strokes without lagging fast ones. Pressure,
+/// tilt and twist pass through.
+pub struct OneEuroFilter {
+    config: OneEuroConfig,
+    last: Option<OneEuroState>,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct OneEuroState {
+    position: CanvasVec2,
+    derivative: CanvasVec2,
+    timestamp_ns: u64,
+}
+
+impl OneEuroFilter {
+    pub fn new(config: OneEuroConfig) -> Self {
+        Self { config, last: None }
+    }
+
+    fn alpha(dt_s: f32, cutoff_hz: f32) -> f32 {
+        let tau = 1.0 / (TAU * cutoff_hz.max(f32::EPSILON));
+        1.0 / (1.0 + tau / dt_s)
+    }
+}
+
+impl SmoothingStrategy for OneEuroFilter {
+    fn smooth(&mut self, sample: MappedCursor, timestamp_ns: u64) -> MappedCursor {
+        let Some(last) = self.last else {
+            self.last = Some(OneEuroState {
+                position: sample.cursor,
+                derivative: CanvasVec2::new(0.0, 0.0),
+                timestamp_ns,
+            });
+            return sample;
+        };
+        let dt_s = if timestamp_ns > last.timestamp_ns {
+            (timestamp_ns - last.timestamp_ns) as f32 / 1_000_000_000.0
+        } else {
+            ONE_EURO_FALLBACK_DT_S
+        };
+        let raw_derivative = CanvasVec2::new(
+            (sample.cursor.x - last.position.x) / dt_s,
+            (sample.cursor.y - last.position.y) / dt_s,
+        );
+        let derivative_alpha = Self::alpha(dt_s, self.config.derivative_cutoff_hz);
+        let derivative =
+            ExponentialMovingAverage::lerp_vec2(last.derivative, raw_derivative, derivative_alpha);
+        let speed = (derivative.x * derivative.x + derivative.y * derivative.y).sqrt();
+        let cutoff_hz = self.config.min_cutoff_hz + self.config.beta * speed;
+        let position = ExponentialMovingAverage::lerp_vec2(
+            last.position,
+            sample.cursor,
+            Self::alpha(dt_s, cutoff_hz),
+        );
+        self.last = Some(OneEuroState {
+            position,
+            derivative,
+            timestamp_ns,
+        });
+        MappedCursor {
+            cursor: position,
+            ..sample
+        }
+    }
+
+    fn reset(&mut self) {
+        self.last = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use glaphica_core::{CanvasVec2, MappedCursor, RadianVec2};
+
+    use super::{
+        LazyNib, LazyNibConfig, MovingWindow, MovingWindowConfig, OneEuroConfig, OneEuroFilter,
+        SmoothingConfig, SmoothingKind, SmoothingStrategy,
+    };
+
+    fn sample(x: f32, y: f32) -> MappedCursor {
+        MappedCursor {
+            cursor: CanvasVec2::new(x, y),
+            tilt: RadianVec2::new(0.0, 0.0),
+            pressure: 1.0,
+            twist: 0.0,
+        }
+    }
+
+    fn assert_close(actual: f32, expected: f32) {
+        assert!(
+            (actual - expected).abs() < 0.001,
+            "expected {expected}, got {actual}"
+        );
+    }
+
+    #[test]
+    fn lazy_nib_holds_inside_the_dead_zone_and_trails_by_the_radius() {
+        let mut nib = LazyNib::new(LazyNibConfig { radius: 10.0 });
+        assert_eq!(
+            nib.smooth(sample(0.0, 0.0), 0).cursor,
+            CanvasVec2::new(0.0, 0.0)
+        );
+        assert_eq!(
+            nib.smooth(sample(6.0, 8.0), 1).cursor,
+            CanvasVec2::new(0.0, 0.0)
+        );
+        let pulled = nib.smooth(sample(30.0, 0.0), 2).cursor;
+        assert_close(pulled.x, 20.0);
+        assert_close(pulled.y, 0.0);
+    }
+
+    #[test]
+    fn moving_window_weights_recent_samples_and_catches_up() {
+        let mut window = MovingWindow::new(MovingWindowConfig { window_size: 3 });
+        window.smooth(sample(0.0, 0.0), 0);
+        window.smooth(sample(3.0, 0.0), 1);
+        let smoothed = window.smooth(sample(6.0, 0.0), 2);
+        // Weights 1, 2, 3 over x = 0, 3, 6.
+        assert_close(smoothed.cursor.x, 4.0);
+
+        let mut tail = Vec::new();
+        window.catch_up(&mut tail);
+        assert_eq!(tail.len(), 2);
+        assert_close(tail[1].cursor.x, 6.0);
+        assert!(tail[0].cursor.x > 4.0 && tail[0].cursor.x < 6.0);
+    }
+
+    #[test]
+    fn one_euro_steadies_jitter_but_follows_fast_motion() {
+        let config = OneEuroConfig {
+            min_cutoff_hz: 1.0,
+            beta: 0.05,
+            derivative_cutoff_hz: 1.0,
+        };
+        let step_ns = 8_000_000;
+        let mut slow = OneEuroFilter::new(config);
+        let mut jitter = 0.0_f32;
+        for index in 0..20 {
+            let y = if index % 2 == 0 { 1.0 } else { -1.0 };
+            jitter = slow.smooth(sample(0.0, y), index * step_ns).cursor.y;
+        }
+        assert!(jitter.abs() < 0.2);
+
+        let mut fast = OneEuroFilter::new(config);
+        let mut followed = 0.0;
+        for index in 0..20 {
+            followed = fast
+                .smooth(sample(index as f32 * 20.0, 0.0), index * step_ns)
+                .cursor
+                .x;
+        }
+        assert!(380.0 - followed < 40.0, "lagged to {followed}");
+    }
+
+    #[test]
+    fn zero_strength_leaves_samples_untouched() {
+        for kind in SmoothingKind::ALL {
+            let config = SmoothingConfig::from_strength(kind, 0.0);
+            assert_eq!(config.kind(), kind);
+            if kind == SmoothingKind::OneEuro {
+                continue;
+            }
+            let mut smoother = config.build();
+            smoother.smooth(sample(0.0, 0.0), 0);
+            assert_eq!(smoother.smooth(sample(5.0, 5.0), 1), sample(5.0, 5.0));
+        }
+    }
+}
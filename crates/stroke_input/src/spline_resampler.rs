@@ -0,0 +1,331 @@
+use glaphica_core::{CanvasVec2, MappedCursor, RadianVec2};
+
+use crate::resampler::{ResampleResult, ResamplerConfig, ResamplingStrategy};
+
+/// Closest spacing the spline emits samples at, in canvas px.
+const MIN_SPACING: f32 = 0.5;
+/// Raw samples closer than this to the previous one carry no direction and
+/// would collapse a knot interval.
+const MIN_KNOT_DISTANCE: f32 = 0.01;
+/// Arc length subdivisions per output spacing when measuring a segment.
+const ARC_STEPS_PER_SPACING: f32 = 4.0;
+const MIN_ARC_STEPS: usize = 8;
+const MAX_ARC_STEPS: usize = 256;
+
+/// Position, pressure, tilt and twist, interpolated as one point.
+type Channels = [f32; 6];
+
+#[derive(Debug, Clone, Copy)]
+struct Knot {
+    channels: Channels,
+    timestamp_ns: u64,
+}
+
+impl Knot {
+    fn new(cursor: MappedCursor, timestamp_ns: u64) -> Self {
+        Self {
+            channels: [
+                cursor.cursor.x,
+                cursor.cursor.y,
+                cursor.pressure,
+                cursor.tilt.x,
+                cursor.tilt.y,
+                cursor.twist,
+            ],
+            timestamp_ns,
+        }
+    }
+
+    /// Phantom end knot mirroring `next` through `self`.
+    fn reflect(&self, next: &Knot) -> Knot {
+        Knot {
+            channels: std::array::from_fn(|index| {
+                2.0 * self.channels[index] - next.channels[index]
+            }),
+            timestamp_ns: self.timestamp_ns,
+        }
+    }
+}
+
+fn to_cursor(channels: &Channels) -> MappedCursor {
+    MappedCursor {
+        cursor: CanvasVec2::new(channels[0], channels[1]),
+        pressure: channels[2].clamp(0.0, 1.0),
+        tilt: RadianVec2::new(channels[3], channels[4]),
+        twist: channels[5],
+    }
+}
+
+fn position_distance(a: &Channels, b: &Channels) -> f32 {
+    (b[0] - a[0]).hypot(b[1] - a[1])
+}
+
+/// One centripetal Catmull-Rom span from `knots[1]` to `knots[2]`, with the
+/// outer knots shaping its tangents.
+struct Span {
+    knots: [Channels; 4],
+    params: [f32; 4],
+}
+
+impl Span {
+    fn new(knots: [&Knot; 4]) -> Self {
+        let knots = knots.map(|knot| knot.channels);
+        let mut params = [0.0; 4];
+        for index in 1..4 {
+            params[index] =
+                params[index - 1] + position_distance(&knots[index - 1], &knots[index]).sqrt();
+        }
+        Self { knots, params }
+    }
+
+    /// Barry-Goldman evaluation at spline parameter `u`.
+    fn eval(&self, u: f32) -> Channels {
+        let [p0, p1, p2, p3] = &self.knots;
+        let [t0, t1, t2, t3] = self.params;
+        let blend = |a: &Channels, b: &Channels, ta: f32, tb: f32| -> Channels {
+            let span = tb - ta;
+            std::array::from_fn(|index| ((tb - u) * a[index] + (u - ta) * b[index]) / span)
+        };
+        let a1 = blend(p0, p1, t0, t1);
+        let a2 = blend(p1, p2, t1, t2);
+        let a3 = blend(p2, p3, t2, t3);
+        let b1 = blend(&a1, &a2, t0, t2);
+        let b2 = blend(&a2, &a3, t1, t3);
+        blend(&b1, &b2, t1, t2)
+    }
+}
+
+/// Resamples along a centripetal Catmull-Rom spline through the raw samples,
+/// emitting points `min_distance` apart by arc length. Each span needs the
+/// sample after it, so output trails the pen by one sample until `finish`.
+pub struct CatmullRomResampler {
+    config: ResamplerConfig,
+    knots: Vec<Knot>,
+    /// Arc length covered since the last emitted sample.
+    carry: f32,
+}
+
+impl CatmullRomResampler {
+    pub fn new(config: ResamplerConfig) -> Self {
+        Self {
+            config,
+            knots: Vec::with_capacity(4),
+            carry: 0.0,
+        }
+    }
+
+    fn spacing(&self) -> f32 {
+        self.config.min_distance.max(MIN_SPACING)
+    }
+
+    fn emit_span(&mut self, knots: [&Knot; 4], output: &mut Vec<(MappedCursor, u64)>) {
+        let spacing = self.spacing();
+        let span = Span::new(knots);
+        let [_, t1, t2, _] = span.params;
+        let start_ns = knots[1].timestamp_ns;
+        let duration_ns = knots[2].timestamp_ns.saturating_sub(start_ns) as f32;
+        let chord = position_distance(&knots[1].channels, &knots[2].channels);
+        let steps = ((chord / spacing * ARC_STEPS_PER_SPACING).ceil() as usize)
+            .clamp(MIN_ARC_STEPS, MAX_ARC_STEPS);
+
+        let mut previous_u = t1;
+        let mut previous = span.eval(t1);
+        for step in 1..=steps {
+            let u = t1 + (t2 - t1) * step as f32 / steps as f32;
+            let current = span.eval(u);
+            let mut length = position_distance(&previous, &current);
+            while self.carry + length >= spacing && length > 0.0 {
+                let fraction = (spacing - self.carry) / length;
+                let sample_u = previous_u + (u - previous_u) * fraction;
+                let sample = span.eval(sample_u);
+                let elapsed = (sample_u - t1) / (t2 - t1) * duration_ns;
+                output.push((to_cursor(&sample), start_ns + elapsed as u64));
+                self.carry = 0.0;
+                length = position_distance(&sample, &current);
+                previous_u = sample_u;
+            }
+            self.carry += length;
+            previous_u = u;
+            previous = current;
+        }
+    }
+}
+
+impl ResamplingStrategy for CatmullRomResampler {
+    fn add_sample(&mut self, cursor: MappedCursor, timestamp_ns: u64) -> ResampleResult {
+        let knot = Knot::new(cursor, timestamp_ns);
+        let Some(last) = self.knots.last() else {
+            self.knots.push(knot);
+            return ResampleResult::Accepted(cursor);
+        };
+        let distance = position_distance(&last.channels, &knot.channels);
+        let delta_time_s = timestamp_ns.saturating_sub(last.timestamp_ns) as f32 / 1_000_000_000.0;
+        if distance < MIN_KNOT_DISTANCE
+            || (distance < self.config.min_distance && delta_time_s < self.config.min_time_s)
+        {
+            return ResampleResult::Rejected;
+        }
+
+        self.knots.push(knot);
+        if self.knots.len() > 4 {
+            self.knots.remove(0);
+        }
+        let mut output = Vec::new();
+        let knots = self.knots.clone();
+        match knots.as_slice() {
+            [first, second, third] => {
+                let phantom = first.reflect(second);
+                self.emit_span([&phantom, first, second, third], &mut output);
+            }
+            [first, second, third, fourth] => {
+                self.emit_span([first, second, third, fourth], &mut output);
+            }
+            _ => {}
+        }
+        ResampleResult::Interpolated(output)
+    }
+
+    fn finish(&mut self) -> Vec<(MappedCursor, u64)> {
+        let mut output = Vec::new();
+        let knots = std::mem::take(&mut self.knots);
+        let (before, last) = match knots.as_slice() {
+            [.., before, last] => (before, last),
+            _ => return output,
+        };
+        let phantom_end = last.reflect(before);
+        match knots.as_slice() {
+            [first, second] => {
+                let phantom_start = first.reflect(second);
+                self.emit_span([&phantom_start, first, second, &phantom_end], &mut output);
+            }
+            [.., previous, before, last] => {
+                self.emit_span([previous, before, last, &phantom_end], &mut output);
+            }
+            _ => {}
+        }
+        if self.carry > MIN_KNOT_DISTANCE {
+            output.push((to_cursor(&last.channels), last.timestamp_ns));
+        }
+        self.carry = 0.0;
+        output
+    }
+
+    fn set_config(&mut self, config: ResamplerConfig) {
+        self.config = config;
+    }
+
+    fn reset(&mut self) {
+        self.knots.clear();
+        self.carry = 0.0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use glaphica_core::{CanvasVec2, MappedCursor, RadianVec2};
+
+    use super::CatmullRomResampler;
+    use crate::resampler::{ResampleResult, ResamplerConfig, ResamplingStrategy};
+
+    fn cursor(x: f32, y: f32, pressure: f32) -> MappedCursor {
+        MappedCursor {
+            cursor: CanvasVec2::new(x, y),
+            tilt: RadianVec2::new(0.0, 0.0),
+            pressure,
+            twist: 0.0,
+        }
+    }
+
+    fn resampler(spacing: f32) -> CatmullRomResampler {
+        CatmullRomResampler::new(ResamplerConfig {
+            min_distance: spacing,
+            max_distance: spacing * 2.0,
+            min_time_s: 0.0,
+            max_time_s: 1.0,
+        })
+    }
+
+    fn run(resampler: &mut CatmullRomResampler, samples: &[MappedCursor]) -> Vec<MappedCursor> {
+        let mut output = Vec::new();
+        for (index, sample) in samples.iter().enumerate() {
+            match resampler.add_sample(*sample, index as u64 * 10_000_000) {
+                ResampleResult::Accepted(cursor) => output.push(cursor),
+                ResampleResult::Interpolated(points) => {
+                    output.extend(points.into_iter().map(|(cursor, _)| cursor))
+                }
+                ResampleResult::Rejected => {}
+            }
+        }
+        output.extend(resampler.finish().into_iter().map(|(cursor, _)| cursor));
+        output
+    }
+
+    #[test]
+    fn sparse_circle_samples_resample_onto_the_curve_evenly() {
+        let raw = (0..=8)
+            .map(|index| {
+                let angle = index as f32 * std::f32::consts::FRAC_PI_4;
+                cursor(50.0 * angle.cos(), 50.0 * angle.sin(), 1.0)
+            })
+            .collect::<Vec<_>>();
+        let output = run(&mut resampler(2.0), &raw);
+
+        // Chord midpoints of the raw octagon sit 3.8 px inside the circle. The
+        // first and last spans bend toward reflected phantom knots instead.
+        let interior = output.iter().filter(|point| {
+            point.cursor.y.atan2(point.cursor.x).abs() >= std::f32::consts::FRAC_PI_4
+        });
+        for point in interior {
+            let radius = point.cursor.x.hypot(point.cursor.y);
+            assert!((radius - 50.0).abs() < 0.5, "radius {radius}");
+        }
+        for pair in output.windows(2).take(output.len() - 2) {
+            let step =
+                (pair[1].cursor.x - pair[0].cursor.x).hypot(pair[1].cursor.y - pair[0].cursor.y);
+            assert!((step - 2.0).abs() < 0.1, "step {step}");
+        }
+        let last = output[output.len() - 1];
+        assert!((last.cursor.x - 50.0).abs() < 0.001);
+        assert!(last.cursor.y.abs() < 0.001);
+    }
+
+    #[test]
+    fn output_trails_by_one_sample_until_finished() {
+        let mut resampler = resampler(1.0);
+        assert_eq!(
+            resampler.add_sample(cursor(0.0, 0.0, 1.0), 0),
+            ResampleResult::Accepted(cursor(0.0, 0.0, 1.0))
+        );
+        assert_eq!(
+            resampler.add_sample(cursor(10.0, 0.0, 1.0), 10),
+            ResampleResult::Interpolated(Vec::new())
+        );
+        let ResampleResult::Interpolated(points) = resampler.add_sample(cursor(20.0, 0.0, 1.0), 20)
+        else {
+            panic!("expected interpolated points");
+        };
+        assert!(points.iter().all(|(point, _)| point.cursor.x <= 10.0));
+        let tail = resampler.finish();
+        let (end, _) = tail[tail.len() - 1];
+        assert!((end.cursor.x - 20.0).abs() < 0.01);
+        assert!(resampler.finish().is_empty());
+    }
+
+    #[test]
+    fn pressure_is_interpolated_smoothly_and_clamped() {
+        let raw = [
+            cursor(0.0, 0.0, 0.0),
+            cursor(10.0, 0.0, 0.6),
+            cursor(20.0, 0.0, 1.0),
+            cursor(30.0, 0.0, 1.0),
+        ];
+        let output = run(&mut resampler(1.0), &raw);
+        assert!(output.len() > 25);
+        assert!(
+            output
+                .windows(2)
+                .all(|pair| pair[1].pressure >= pair[0].pressure - 0.001)
+        );
+        assert!(output.iter().all(|point| point.pressure <= 1.0));
+    }
+}
@@ -0,0 +1,269 @@
+use std::f32::consts::{PI, TAU};
+
+use glaphica_core::{BrushInput, CanvasVec2, RadianVec2};
+
+/// Most copies radial symmetry makes of each input.
+pub const MAX_RADIAL_WAYS: u32 = 32;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SymmetryMode {
+    #[default]
+    Off,
+    /// Mirrors across the vertical axis through the center.
+    Vertical,
+    /// Mirrors across the horizontal axis through the center.
+    Horizontal,
+    /// Mirrors across both axes, four copies in total.
+    Both,
+    /// Rotates `ways` evenly spaced copies about the center.
+    Radial { ways: u32 },
+}
+
+/// Replicates each stroke input into mirrored or rotated copies.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Symmetry {
+    pub mode: SymmetryMode,
+    pub center: CanvasVec2,
+}
+
+/// Linear part of one copy's transform about the symmetry center.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct CopyTransform {
+    matrix: [f32; 4],
+    mirrored: bool,
+}
+
+impl CopyTransform {
+    const IDENTITY: Self = Self::scale(1.0, 1.0);
+
+    const fn scale(x: f32, y: f32) -> Self {
+        Self {
+            matrix: [x, 0.0, 0.0, y],
+            mirrored: x * y < 0.0,
+        }
+    }
+
+    fn rotation(angle: f32) -> Self {
+        let (sin, cos) = angle.sin_cos();
+        Self {
+            matrix: [cos, -sin, sin, cos],
+            mirrored: false,
+        }
+    }
+
+    fn apply(&self, x: f32, y: f32) -> (f32, f32) {
+        let [a, b, c, d] = self.matrix;
+        (a * x + b * y, c * x + d * y)
+    }
+
+    fn apply_vec(&self, v: CanvasVec2) -> CanvasVec2 {
+        let (x, y) = self.apply(v.x, v.y);
+        CanvasVec2::new(x, y)
+    }
+
+    fn apply_angle(&self, angle: f32) -> f32 {
+        let (sin, cos) = angle.sin_cos();
+        let (x, y) = self.apply(cos, sin);
+        y.atan2(x)
+    }
+}
+
+impl Symmetry {
+    pub const OFF: Self = Self {
+        mode: SymmetryMode::Off,
+        center: CanvasVec2::new(0.0, 0.0),
+    };
+
+    pub const fn new(mode: SymmetryMode, center: CanvasVec2) -> Self {
+        Self { mode, center }
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.copy_count() > 1
+    }
+
+    /// Number of copies stamped per input, the original included.
+    pub fn copy_count(&self) -> usize {
+        match self.mode {
+            SymmetryMode::Off => 1,
+            SymmetryMode::Vertical | SymmetryMode::Horizontal => 2,
+            SymmetryMode::Both => 4,
+            SymmetryMode::Radial { ways } => ways.clamp(1, MAX_RADIAL_WAYS) as usize,
+        }
+    }
+
+    /// Pushes every copy of `input`, the untouched original first. Positions
+    /// move about the center; direction, velocity, acceleration, tilt and
+    /// twist turn with them, and mirrored copies flip curvature.
+    pub fn replicate(&self, input: &BrushInput, copies: &mut Vec<BrushInput>) {
+        copies.push(*input);
+        for index in 1..self.copy_count() {
+            copies.push(self.transform(input, self.copy_transform(index)));
+        }
+    }
+
+    /// Unit directions of the guide lines drawn from the center.
+    pub fn guide_rays(&self) -> Vec<CanvasVec2> {
+        match self.mode {
+            SymmetryMode::Off => Vec::new(),
+            SymmetryMode::Vertical => {
+                vec![CanvasVec2::new(0.0, -1.0), CanvasVec2::new(0.0, 1.0)]
+            }
+            SymmetryMode::Horizontal => {
+                vec![CanvasVec2::new(-1.0, 0.0), CanvasVec2::new(1.0, 0.0)]
+            }
+            SymmetryMode::Both => vec![
+                CanvasVec2::new(0.0, -1.0),
+                CanvasVec2::new(1.0, 0.0),
+                CanvasVec2::new(0.0, 1.0),
+                CanvasVec2::new(-1.0, 0.0),
+            ],
+            SymmetryMode::Radial { .. } => {
+                let ways = self.copy_count();
+                (0..ways)
+                    .map(|index| {
+                        let angle = index as f32 * TAU / ways as f32 - PI * 0.5;
+                        CanvasVec2::new(angle.cos(), angle.sin())
+                    })
+                    .collect()
+            }
+        }
+    }
+
+    fn copy_transform(&self, index: usize) -> CopyTransform {
+        match self.mode {
+            SymmetryMode::Off => CopyTransform::IDENTITY,
+            SymmetryMode::Vertical => CopyTransform::scale(-1.0, 1.0),
+            SymmetryMode::Horizontal => CopyTransform::scale(1.0, -1.0),
+            SymmetryMode::Both => match index {
+                1 => CopyTransform::scale(-1.0, 1.0),
+                2 => CopyTransform::scale(1.0, -1.0),
+                _ => CopyTransform::scale(-1.0, -1.0),
+            },
+            SymmetryMode::Radial { .. } => {
+                CopyTransform::rotation(index as f32 * TAU / self.copy_count() as f32)
+            }
+        }
+    }
+
+    fn transform(&self, input: &BrushInput, transform: CopyTransform) -> BrushInput {
+        let mut copy = *input;
+        let (x, y) = transform.apply(
+            input.cursor.cursor.x - self.center.x,
+            input.cursor.cursor.y - self.center.y,
+        );
+        copy.cursor.cursor = CanvasVec2::new(self.center.x + x, self.center.y + y);
+        let (tilt_x, tilt_y) = transform.apply(input.cursor.tilt.x, input.cursor.tilt.y);
+        copy.cursor.tilt = RadianVec2::new(tilt_x, tilt_y);
+        copy.cursor.twist = transform.apply_angle(input.cursor.twist);
+        copy.vel = transform.apply_vec(input.vel);
+        copy.tangent = transform.apply_vec(input.tangent);
+        copy.acc = transform.apply_vec(input.acc);
+        if transform.mirrored {
+            copy.curvature = -input.curvature;
+        }
+        copy
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use glaphica_core::{
+        BrushInput, BrushInputFlags, CanvasVec2, MappedCursor, RadianVec2, StrokeId,
+    };
+
+    use super::{Symmetry, SymmetryMode};
+
+    fn build_input() -> BrushInput {
+        BrushInput {
+            stroke: StrokeId(3),
+            cursor: MappedCursor {
+                cursor: CanvasVec2::new(130.0, 80.0),
+                tilt: RadianVec2::new(0.2, 0.1),
+                pressure: 0.6,
+                twist: 0.5,
+            },
+            flags: BrushInputFlags::empty(),
+            path_s: 12.0,
+            delta_s: 2.0,
+            dt_s: 0.01,
+            vel: CanvasVec2::new(200.0, 50.0),
+            speed: 206.0,
+            tangent: CanvasVec2::new(0.97, 0.24),
+            acc: CanvasVec2::new(10.0, -5.0),
+            accel: 11.2,
+            curvature: 0.02,
+            confidence: 1.0,
+        }
+    }
+
+    fn assert_close(actual: f32, expected: f32) {
+        assert!(
+            (actual - expected).abs() < 0.0001,
+            "expected {expected}, got {actual}"
+        );
+    }
+
+    #[test]
+    fn vertical_mirror_flips_x_about_the_axis() {
+        let symmetry = Symmetry::new(SymmetryMode::Vertical, CanvasVec2::new(100.0, 0.0));
+        let mut copies = Vec::new();
+        symmetry.replicate(&build_input(), &mut copies);
+        assert_eq!(copies.len(), 2);
+        assert_eq!(copies[0], build_input());
+        let mirrored = copies[1];
+        assert_close(mirrored.cursor.cursor.x, 70.0);
+        assert_close(mirrored.cursor.cursor.y, 80.0);
+        assert_close(mirrored.vel.x, -200.0);
+        assert_close(mirrored.tangent.y, 0.24);
+        assert_close(mirrored.cursor.tilt.x, -0.2);
+        assert_close(mirrored.cursor.twist, std::f32::consts::PI - 0.5);
+        assert_close(mirrored.curvature, -0.02);
+        assert_eq!(mirrored.cursor.pressure, 0.6);
+        assert_eq!(mirrored.path_s, 12.0);
+    }
+
+    #[test]
+    fn both_axes_make_four_copies() {
+        let symmetry = Symmetry::new(SymmetryMode::Both, CanvasVec2::new(100.0, 100.0));
+        let mut copies = Vec::new();
+        symmetry.replicate(&build_input(), &mut copies);
+        let positions = copies
+            .iter()
+            .map(|copy| (copy.cursor.cursor.x, copy.cursor.cursor.y))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            positions,
+            vec![(130.0, 80.0), (70.0, 80.0), (130.0, 120.0), (70.0, 120.0)]
+        );
+        assert_close(copies[3].curvature, 0.02);
+    }
+
+    #[test]
+    fn radial_rotates_copies_about_the_center() {
+        let symmetry = Symmetry::new(
+            SymmetryMode::Radial { ways: 4 },
+            CanvasVec2::new(100.0, 100.0),
+        );
+        let mut copies = Vec::new();
+        symmetry.replicate(&build_input(), &mut copies);
+        assert_eq!(copies.len(), 4);
+        assert_close(copies[1].cursor.cursor.x, 120.0);
+        assert_close(copies[1].cursor.cursor.y, 130.0);
+        assert_close(copies[1].vel.x, -50.0);
+        assert_close(copies[1].vel.y, 200.0);
+        assert_close(copies[1].cursor.twist, 0.5 + std::f32::consts::FRAC_PI_2);
+        assert_close(copies[1].curvature, 0.02);
+        assert_close(copies[2].cursor.cursor.x, 70.0);
+        assert_close(copies[2].cursor.cursor.y, 120.0);
+    }
+
+    #[test]
+    fn off_keeps_only_the_original() {
+        let mut copies = Vec::new();
+        Symmetry::OFF.replicate(&build_input(), &mut copies);
+        assert_eq!(copies, vec![build_input()]);
+        assert!(Symmetry::OFF.guide_rays().is_empty());
+        assert!(!Symmetry::OFF.is_active());
+    }
+}
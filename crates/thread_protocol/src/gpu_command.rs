@@ -62,7 +62,7 @@ pub struct DrawOp {
     pub ref_image: Option<RefImage>,
     /// Brush-defined draw payload.
     pub input: Vec<f32>,
-    /// App-owned brush RGB tint in [0, 1].
+    /// Brush RGB tint in [0, 1] for this dab, after color dynamics.
     pub rgb: [f32; 3],
     /// Whether this direct draw should erase instead of painting.
     pub erase: bool,
@@ -93,7 +93,8 @@ pub struct WriteOp {
     pub blend_mode: WriteBlendMode,
     /// Global write opacity multiplier in [0, 1].
     pub opacity: f32,
-    /// Optional app-owned RGB tint in [0, 1]. When absent, source rgb is preserved.
+    /// Optional app-owned RGB tint in [0, 1]. When absent, source rgb is preserved;
+    /// normal writes read it as premultiplied by the source thickness.
     pub rgb: Option<[f32; 3]>,
     /// Optional origin snapshot tile used by erase writes.
     pub origin_tile_key: Option<TileKey>,
@@ -113,6 +113,11 @@ impl MainInputRingProducer {
     pub fn pushed_samples(&self) -> u64 {
         self.shared.pushed.load(Ordering::Relaxed)
     }
+
+    /// Samples waiting in the ring for the engine to drain.
+    pub fn pending_samples(&self) -> usize {
+        self.shared.queue.len()
+    }
 }
 
 pub struct EngineInputRingConsumer {
@@ -123,6 +128,11 @@ pub struct EngineInputRingConsumer {
     _not_clone: PhantomData<*const ()>,
 }
 
+// SAFETY: the raw-pointer marker is only there to keep the consumer unique;
+// handing that one consumer to the engine thread is the intended use, and the
+// ring behind the Arc is shared through atomics and a channel.
+unsafe impl Send for EngineInputRingConsumer {}
+
 /// Drain up to `max_items` samples into `output`.
 ///
 /// NOTE:
@@ -192,6 +202,11 @@ impl EngineInputRingConsumer {
     pub fn pushed_samples(&self) -> u64 {
         self.shared.pushed.load(Ordering::Relaxed)
     }
+
+    /// Samples waiting in the ring for the engine to drain.
+    pub fn pending_samples(&self) -> usize {
+        self.shared.queue.len()
+    }
 }
 
 pub struct MainInputControlQueue<Control>
@@ -322,11 +337,15 @@ where
 
 #[cfg(test)]
 mod tests {
-    use super::MailboxMergePolicy;
-    use glaphica_core::PresentFrameId;
+    use std::time::Duration;
+
+    use super::{MailboxMergePolicy, create_thread_channels};
+    use glaphica_core::{
+        CanvasVec2, EpochId, InputDeviceKind, MappedCursor, PresentFrameId, RadianVec2,
+    };
     use thread_protocol::{
         CompleteWaterline, ExecutedBatchWaterline, GpuFeedbackFrame, GpuFeedbackMergeState,
-        MergeItem, SubmitWaterline,
+        InputControlEvent, InputControlOp, InputRingSample, MergeItem, SubmitWaterline,
     };
 
     #[derive(Debug, Clone, PartialEq, Eq)]
@@ -403,4 +422,90 @@ mod tests {
         assert_eq!(via_policy, via_protocol);
         assert_eq!(via_policy.receipts[0].revision, 2);
     }
+
+    #[derive(Debug, Clone, PartialEq)]
+    struct TestControl(u32);
+
+    impl InputControlOp for TestControl {
+        type Target = u32;
+
+        fn apply(&self, target: &mut Self::Target) {
+            *target = self.0;
+        }
+
+        fn undo(&self, _target: &mut Self::Target) {}
+    }
+
+    #[test]
+    fn engine_channels_move_to_another_thread() {
+        let (mut main, engine) =
+            create_thread_channels::<TestControl, TestReceipt, TestError>(8, 8, 8, 8);
+        main.input_control_queue
+            .blocking_push(InputControlEvent::Control(TestControl(7)));
+        for index in 0..3u64 {
+            main.input_ring_producer.push(InputRingSample {
+                epoch: EpochId(0),
+                time_ns: index,
+                device: InputDeviceKind::Cursor,
+                cursor: MappedCursor {
+                    cursor: CanvasVec2::new(index as f32, 0.0),
+                    tilt: RadianVec2::new(0.0, 0.0),
+                    pressure: 1.0,
+                    twist: 0.0,
+                },
+            });
+        }
+
+        let received = std::thread::spawn(move || {
+            let mut engine = engine;
+            let mut controls = Vec::new();
+            while let Ok(InputControlEvent::Control(control)) = engine.input_control_queue.pop() {
+                controls.push(control);
+            }
+            let mut samples = Vec::new();
+            engine.input_ring_consumer.drain_batch_with_wait(
+                &mut samples,
+                16,
+                Duration::from_millis(100),
+            );
+            (controls, samples.len())
+        })
+        .join()
+        .unwrap();
+
+        assert_eq!(received, (vec![TestControl(7)], 3));
+    }
+
+    fn sample(time_ns: u64) -> InputRingSample {
+        InputRingSample {
+            epoch: EpochId(0),
+            time_ns,
+            device: InputDeviceKind::Cursor,
+            cursor: MappedCursor {
+                cursor: CanvasVec2::new(time_ns as f32, 0.0),
+                tilt: RadianVec2::new(0.0, 0.0),
+                pressure: 1.0,
+                twist: 0.0,
+            },
+        }
+    }
+
+    #[test]
+    fn input_ring_counts_pushed_dropped_and_pending_samples() {
+        let (main, engine) =
+            create_thread_channels::<TestControl, TestReceipt, TestError>(4, 8, 8, 8);
+        for time_ns in 0..6 {
+            main.input_ring_producer.push(sample(time_ns));
+        }
+        assert_eq!(main.input_ring_producer.pushed_samples(), 6);
+        assert_eq!(main.input_ring_producer.dropped_samples(), 2);
+        assert_eq!(engine.input_ring_consumer.pending_samples(), 4);
+
+        let mut samples = Vec::new();
+        engine
+            .input_ring_consumer
+            .drain_batch_with_wait(&mut samples, 3, Duration::ZERO);
+        assert_eq!(samples.first().map(|sample| sample.time_ns), Some(2));
+        assert_eq!(main.input_ring_producer.pending_samples(), 1);
+    }
 }